@@ -9,7 +9,10 @@ use crate::tool_use::{PendingToolUseStatus, ToolUse, ToolUseStatus};
 use crate::ui::{AddedContext, AgentNotification, AgentNotificationEvent, ContextPill};
 use crate::{AssistantPanel, OpenActiveThreadAsMarkdown};
 use anyhow::Context as _;
-use assistant_settings::{AssistantSettings, NotifyWhenAgentWaiting};
+use assistant_settings::{
+    AssistantSettings, NotifyWhenAgentWaiting, ToolPermissionDecision, ToolPermissionRuleContent,
+    exact_tool_permission_pattern,
+};
 use collections::{HashMap, HashSet};
 use editor::scroll::Autoscroll;
 use editor::{Editor, MultiBuffer};
@@ -63,6 +66,7 @@ pub struct ActiveThread {
     _subscriptions: Vec<Subscription>,
     notification_subscriptions: HashMap<WindowHandle<AgentNotification>, Vec<Subscription>>,
     open_feedback_editors: HashMap<MessageId, Entity<Editor>>,
+    show_compacted_messages: bool,
 }
 
 const MAX_UNCOLLAPSED_LINES_IN_CODE_BLOCK: usize = 5;
@@ -703,6 +707,7 @@ impl ActiveThread {
             _subscriptions: subscriptions,
             notification_subscriptions: HashMap::default(),
             open_feedback_editors: HashMap::default(),
+            show_compacted_messages: false,
         };
 
         for message in thread.read(cx).messages().cloned().collect::<Vec<_>>() {
@@ -923,6 +928,11 @@ impl ActiveThread {
                 }
             }
             ThreadEvent::CheckpointChanged => cx.notify(),
+            ThreadEvent::Compacted => {
+                self.list_state.reset(self.messages.len());
+                self.save_thread(cx);
+                cx.notify();
+            }
         }
     }
 
@@ -1169,6 +1179,37 @@ impl ActiveThread {
         self.confirm_editing_message(&menu::Confirm, window, cx);
     }
 
+    fn fork_thread(&mut self, message_id: MessageId, window: &mut Window, cx: &mut Context<Self>) {
+        let thread = self.thread.clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                if let Some(panel) = workspace.panel::<AssistantPanel>(cx) {
+                    panel.update(cx, |panel, cx| {
+                        panel
+                            .fork_thread(&thread, message_id, window, cx)
+                            .detach_and_log_err(cx);
+                    });
+                }
+            })
+            .ok();
+    }
+
+    fn render_fork_button(
+        &self,
+        ix: usize,
+        message_id: MessageId,
+        cx: &mut Context<Self>,
+    ) -> IconButton {
+        IconButton::new(("fork-thread", ix), IconName::GitBranch)
+            .shape(ui::IconButtonShape::Square)
+            .icon_size(IconSize::XSmall)
+            .icon_color(Color::Ignored)
+            .tooltip(Tooltip::text("Fork Thread From Here"))
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.fork_thread(message_id, window, cx);
+            }))
+    }
+
     fn handle_feedback_click(
         &mut self,
         message_id: MessageId,
@@ -1276,6 +1317,20 @@ impl ActiveThread {
             return Empty.into_any();
         };
 
+        let compaction_message_id = self
+            .thread
+            .read(cx)
+            .compaction()
+            .map(|compaction| compaction.message_id);
+        let is_compaction_boundary = compaction_message_id == Some(message_id);
+        if !self.show_compacted_messages && self.thread.read(cx).is_message_compacted(message_id) {
+            return if is_compaction_boundary {
+                self.render_compaction_marker(cx).into_any_element()
+            } else {
+                Empty.into_any()
+            };
+        }
+
         let context_store = self.context_store.clone();
         let workspace = self.workspace.clone();
         let thread = self.thread.read(cx);
@@ -1436,6 +1491,7 @@ impl ActiveThread {
                                     );
                                 })),
                         )
+                        .child(self.render_fork_button(ix, message_id, cx))
                         .child(open_as_markdown),
                 )
                 .into_any_element(),
@@ -1481,6 +1537,7 @@ impl ActiveThread {
                                     );
                                 })),
                         )
+                        .child(self.render_fork_button(ix, message_id, cx))
                         .child(open_as_markdown),
                 )
                 .into_any_element(),
@@ -1639,6 +1696,14 @@ impl ActiveThread {
                                                 )
                                             },
                                         )
+                                        .when(
+                                            edit_message_editor.is_none() && !is_generating,
+                                            |this| {
+                                                this.child(
+                                                    self.render_fork_button(ix, message_id, cx),
+                                                )
+                                            },
+                                        )
                                         .when(
                                             edit_message_editor.is_none() && allow_editing_message,
                                             |this| {
@@ -1762,6 +1827,9 @@ impl ActiveThread {
                 parent.child(self.render_rules_item(cx))
             })
             .child(styled_message)
+            .when(is_compaction_boundary, |parent| {
+                parent.child(self.render_compaction_marker(cx))
+            })
             .when(!needs_confirmation && generating_label.is_some(), |this| {
                 this.child(
                     h_flex()
@@ -2628,6 +2696,34 @@ impl ActiveThread {
                                         .gap_0p5()
                                         .child({
                                             let tool_id = tool_use.id.clone();
+                                            let tool_name: Arc<str> = tool_use.name.clone().into();
+                                            let rules = if tool_use.permission_subject_parts.is_empty() {
+                                                vec![ToolPermissionRuleContent {
+                                                    tool: tool_name,
+                                                    pattern: None,
+                                                    decision: ToolPermissionDecision::Allow,
+                                                }]
+                                            } else {
+                                                tool_use
+                                                    .permission_subject_parts
+                                                    .iter()
+                                                    .map(|part| ToolPermissionRuleContent {
+                                                        tool: tool_name.clone(),
+                                                        pattern: Some(exact_tool_permission_pattern(part)),
+                                                        decision: ToolPermissionDecision::Allow,
+                                                    })
+                                                    .collect::<Vec<_>>()
+                                            };
+                                            let tooltip_title = match &tool_use.permission_subject {
+                                                Some(subject) => format!(
+                                                    "Never ask for permission to use {} on {subject}",
+                                                    tool_use.name
+                                                ),
+                                                None => format!(
+                                                    "Never ask for permission to use {}",
+                                                    tool_use.name
+                                                ),
+                                            };
                                             Button::new(
                                                 "always-allow-tool-action",
                                                 "Always Allow",
@@ -2639,9 +2735,9 @@ impl ActiveThread {
                                             .icon_color(Color::Success)
                                             .tooltip(move |window, cx|  {
                                                 Tooltip::with_meta(
-                                                    "Never ask for permission",
+                                                    tooltip_title.clone(),
                                                     None,
-                                                    "Restore the original behavior in your Agent Panel settings",
+                                                    "Edit the tool permission rules in your Agent Panel settings",
                                                     window,
                                                     cx,
                                                 )
@@ -2649,11 +2745,14 @@ impl ActiveThread {
                                             .on_click(cx.listener(
                                                 move |this, event, window, cx| {
                                                     if let Some(fs) = fs.clone() {
+                                                        let rules = rules.clone();
                                                         update_settings_file::<AssistantSettings>(
                                                             fs.clone(),
                                                             cx,
-                                                            |settings, _| {
-                                                                settings.set_always_allow_tool_actions(true);
+                                                            move |settings, _| {
+                                                                for rule in rules {
+                                                                    settings.add_tool_permission_rule(rule);
+                                                                }
                                                             },
                                                         );
                                                     }
@@ -2714,6 +2813,56 @@ impl ActiveThread {
         })
     }
 
+    fn render_compaction_marker(&self, cx: &Context<Self>) -> impl IntoElement {
+        let compacted_message_count = self
+            .messages
+            .iter()
+            .filter(|message_id| self.thread.read(cx).is_message_compacted(**message_id))
+            .count();
+
+        h_flex()
+            .id("compaction-marker")
+            .w_full()
+            .py_2()
+            .px_4()
+            .gap_2()
+            .child(ui::Divider::horizontal())
+            .child(
+                h_flex()
+                    .flex_none()
+                    .gap_1()
+                    .child(
+                        Icon::new(IconName::ListTree)
+                            .size(IconSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Label::new(format!(
+                            "{compacted_message_count} earlier messages summarized to save context"
+                        ))
+                        .size(LabelSize::XSmall)
+                        .color(Color::Muted),
+                    )
+                    .child(
+                        Button::new(
+                            "toggle-compacted-messages",
+                            if self.show_compacted_messages {
+                                "Hide Original"
+                            } else {
+                                "Show Original"
+                            },
+                        )
+                        .label_size(LabelSize::XSmall)
+                        .on_click(cx.listener(|this, _, _window, cx| {
+                            this.show_compacted_messages = !this.show_compacted_messages;
+                            this.list_state.reset(this.messages.len());
+                            cx.notify();
+                        })),
+                    ),
+            )
+            .child(ui::Divider::horizontal())
+    }
+
     fn render_rules_item(&self, cx: &Context<Self>) -> AnyElement {
         let project_context = self.thread.read(cx).project_context();
         let project_context = project_context.borrow();
@@ -3006,6 +3155,13 @@ pub(crate) fn open_context(
         AssistantContext::FetchedUrl(fetched_url_context) => {
             cx.open_url(&fetched_url_context.url);
         }
+        AssistantContext::Resource(resource_context) => {
+            if resource_context.uri.starts_with("http://")
+                || resource_context.uri.starts_with("https://")
+            {
+                cx.open_url(&resource_context.uri);
+            }
+        }
         AssistantContext::Thread(thread_context) => {
             let thread_id = thread_context.thread.read(cx).id().clone();
             workspace.update(cx, |workspace, cx| {
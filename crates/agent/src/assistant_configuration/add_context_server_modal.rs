@@ -86,6 +86,8 @@ impl AddContextServerModal {
                                 args,
                                 env: None,
                             }),
+                            remote: None,
+                            auto_approve_sampling: false,
                             settings: Some(json!({})),
                         },
                     );
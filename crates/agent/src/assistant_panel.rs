@@ -12,6 +12,8 @@ use assistant_slash_command::SlashCommandWorkingSet;
 use assistant_tool::ToolWorkingSet;
 
 use client::zed_urls;
+use collections::HashSet;
+use context_server::sampling::SamplingRequestId;
 use editor::{Editor, EditorEvent, MultiBuffer};
 use fs::Fs;
 use gpui::{
@@ -33,6 +35,7 @@ use ui::{
 use util::ResultExt as _;
 use workspace::Workspace;
 use workspace::dock::{DockPosition, Panel, PanelEvent};
+use workspace::notifications::NotificationId;
 use zed_actions::agent::OpenConfiguration;
 use zed_actions::assistant::{OpenPromptLibrary, ToggleFocus};
 
@@ -40,9 +43,10 @@ use crate::active_thread::ActiveThread;
 use crate::assistant_configuration::{AssistantConfiguration, AssistantConfigurationEvent};
 use crate::history_store::{HistoryEntry, HistoryStore};
 use crate::message_editor::MessageEditor;
-use crate::thread::{Thread, ThreadError, ThreadId, TokenUsageRatio};
+use crate::thread::{MessageId, Thread, ThreadError, ThreadId, TokenUsageRatio};
 use crate::thread_history::{PastContext, PastThread, ThreadHistory};
 use crate::thread_store::ThreadStore;
+use crate::ui::SamplingRequestNotification;
 use crate::{
     AgentDiff, InlineAssistant, NewTextThread, NewThread, OpenActiveThreadAsMarkdown,
     OpenAgentDiff, OpenHistory, ThreadEvent, ToggleContextPicker,
@@ -171,6 +175,8 @@ pub struct AssistantPanel {
     thread_store: Entity<ThreadStore>,
     thread: Entity<ActiveThread>,
     _thread_subscription: Subscription,
+    _context_server_manager_subscription: Subscription,
+    shown_sampling_requests: HashSet<SamplingRequestId>,
     message_editor: Entity<MessageEditor>,
     context_store: Entity<assistant_context_editor::ContextStore>,
     context_editor: Option<Entity<ContextEditor>>,
@@ -265,6 +271,14 @@ impl AssistantPanel {
                 cx.notify();
             }
         });
+        let context_server_manager = thread_store.read(cx).context_server_manager();
+        context_server_manager.update(cx, |manager, _| manager.enable_sampling_prompts());
+        let context_server_manager_subscription =
+            cx.subscribe(&context_server_manager, |this, _, event, cx| {
+                if let context_server::manager::Event::SamplingRequestsChanged = event {
+                    this.show_sampling_requests(cx);
+                }
+            });
         let thread = cx.new(|cx| {
             ActiveThread::new(
                 thread.clone(),
@@ -286,6 +300,8 @@ impl AssistantPanel {
             thread_store: thread_store.clone(),
             thread,
             _thread_subscription: thread_subscription,
+            _context_server_manager_subscription: context_server_manager_subscription,
+            shown_sampling_requests: HashSet::default(),
             message_editor,
             context_store,
             context_editor: None,
@@ -519,41 +535,70 @@ impl AssistantPanel {
         cx.spawn_in(window, async move |this, cx| {
             let thread = open_thread_task.await?;
             this.update_in(cx, |this, window, cx| {
-                let thread_view = ActiveView::thread(thread.clone(), window, cx);
-                this.set_active_view(thread_view, window, cx);
-                let message_editor_context_store = cx.new(|_cx| {
-                    crate::context_store::ContextStore::new(
-                        this.project.downgrade(),
-                        Some(this.thread_store.downgrade()),
-                    )
-                });
-                this.thread = cx.new(|cx| {
-                    ActiveThread::new(
-                        thread.clone(),
-                        this.thread_store.clone(),
-                        this.language_registry.clone(),
-                        message_editor_context_store.clone(),
-                        this.workspace.clone(),
-                        window,
-                        cx,
-                    )
-                });
-                this.message_editor = cx.new(|cx| {
-                    MessageEditor::new(
-                        this.fs.clone(),
-                        this.workspace.clone(),
-                        message_editor_context_store,
-                        this.thread_store.downgrade(),
-                        thread,
-                        window,
-                        cx,
-                    )
-                });
-                this.message_editor.focus_handle(cx).focus(window);
+                this.set_active_thread(thread, window, cx);
+            })
+        })
+    }
+
+    /// Forks the given thread at `message_id` and makes the fork the active thread.
+    pub(crate) fn fork_thread(
+        &mut self,
+        thread: &Entity<Thread>,
+        message_id: MessageId,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let fork_thread_task = self
+            .thread_store
+            .update(cx, |this, cx| this.fork_thread(thread, message_id, cx));
+
+        cx.spawn_in(window, async move |this, cx| {
+            let thread = fork_thread_task.await?;
+            this.update_in(cx, |this, window, cx| {
+                this.set_active_thread(thread, window, cx);
             })
         })
     }
 
+    fn set_active_thread(
+        &mut self,
+        thread: Entity<Thread>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let thread_view = ActiveView::thread(thread.clone(), window, cx);
+        self.set_active_view(thread_view, window, cx);
+        let message_editor_context_store = cx.new(|_cx| {
+            crate::context_store::ContextStore::new(
+                self.project.downgrade(),
+                Some(self.thread_store.downgrade()),
+            )
+        });
+        self.thread = cx.new(|cx| {
+            ActiveThread::new(
+                thread.clone(),
+                self.thread_store.clone(),
+                self.language_registry.clone(),
+                message_editor_context_store.clone(),
+                self.workspace.clone(),
+                window,
+                cx,
+            )
+        });
+        self.message_editor = cx.new(|cx| {
+            MessageEditor::new(
+                self.fs.clone(),
+                self.workspace.clone(),
+                message_editor_context_store,
+                self.thread_store.downgrade(),
+                thread,
+                window,
+                cx,
+            )
+        });
+        self.message_editor.focus_handle(cx).focus(window);
+    }
+
     pub fn go_back(&mut self, _: &workspace::GoBack, window: &mut Window, cx: &mut Context<Self>) {
         match self.active_view {
             ActiveView::Configuration | ActiveView::History => {
@@ -1569,6 +1614,58 @@ impl AssistantPanel {
             .into_any()
     }
 
+    fn show_sampling_requests(&mut self, cx: &mut Context<Self>) {
+        let context_server_manager = self.thread_store.read(cx).context_server_manager();
+        let pending_requests = context_server_manager
+            .read(cx)
+            .pending_sampling_requests()
+            .iter()
+            .map(|request| (request.id, request.server_id.clone(), request.prompt_text()))
+            .collect::<Vec<_>>();
+        let shown_requests = std::mem::replace(
+            &mut self.shown_sampling_requests,
+            pending_requests.iter().map(|(id, _, _)| *id).collect(),
+        );
+        let answered_requests = shown_requests
+            .iter()
+            .filter(|id| !self.shown_sampling_requests.contains(id))
+            .copied()
+            .collect::<Vec<_>>();
+
+        let fs = self.fs.clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                for request_id in answered_requests {
+                    workspace.dismiss_notification(
+                        &NotificationId::composite::<SamplingRequestNotification>(request_id.0),
+                        cx,
+                    );
+                }
+                for (request_id, server_id, prompt) in pending_requests {
+                    if shown_requests.contains(&request_id) {
+                        continue;
+                    }
+                    workspace.show_notification(
+                        NotificationId::composite::<SamplingRequestNotification>(request_id.0),
+                        cx,
+                        |cx| {
+                            cx.new(|cx| {
+                                SamplingRequestNotification::new(
+                                    request_id,
+                                    server_id,
+                                    prompt,
+                                    context_server_manager.clone(),
+                                    fs.clone(),
+                                    cx,
+                                )
+                            })
+                        },
+                    );
+                }
+            })
+            .ok();
+    }
+
     fn key_context(&self) -> KeyContext {
         let mut key_context = KeyContext::new_with_defaults();
         key_context.add("AgentPanel");
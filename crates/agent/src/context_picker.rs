@@ -1,6 +1,7 @@
 mod completion_provider;
 mod fetch_context_picker;
 mod file_context_picker;
+mod resource_context_picker;
 mod symbol_context_picker;
 mod thread_context_picker;
 
@@ -9,6 +10,8 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::{Result, anyhow};
+use context_server::manager::ContextServerManager;
+use context_server::protocol::ServerCapability;
 use editor::display_map::{Crease, FoldId};
 use editor::{Anchor, AnchorRangeExt as _, Editor, ExcerptId, FoldPlaceholder, ToOffset};
 use file_context_picker::render_file_context_entry;
@@ -29,6 +32,7 @@ use crate::AssistantPanel;
 pub use crate::context_picker::completion_provider::ContextPickerCompletionProvider;
 use crate::context_picker::fetch_context_picker::FetchContextPicker;
 use crate::context_picker::file_context_picker::FileContextPicker;
+use crate::context_picker::resource_context_picker::ResourceContextPicker;
 use crate::context_picker::thread_context_picker::ThreadContextPicker;
 use crate::context_store::ContextStore;
 use crate::thread::ThreadId;
@@ -46,6 +50,7 @@ enum ContextPickerMode {
     Symbol,
     Fetch,
     Thread,
+    Resource,
 }
 
 impl TryFrom<&str> for ContextPickerMode {
@@ -69,6 +74,7 @@ impl ContextPickerMode {
             Self::Symbol => "symbol",
             Self::Fetch => "fetch",
             Self::Thread => "thread",
+            Self::Resource => "resource",
         }
     }
 
@@ -78,6 +84,7 @@ impl ContextPickerMode {
             Self::Symbol => "Symbols",
             Self::Fetch => "Fetch",
             Self::Thread => "Threads",
+            Self::Resource => "Resources",
         }
     }
 
@@ -87,6 +94,7 @@ impl ContextPickerMode {
             Self::Symbol => IconName::Code,
             Self::Fetch => IconName::Globe,
             Self::Thread => IconName::MessageBubbles,
+            Self::Resource => IconName::DatabaseZap,
         }
     }
 }
@@ -98,6 +106,7 @@ enum ContextPickerState {
     Symbol(Entity<SymbolContextPicker>),
     Fetch(Entity<FetchContextPicker>),
     Thread(Entity<ThreadContextPicker>),
+    Resource(Entity<ResourceContextPicker>),
 }
 
 pub(super) struct ContextPicker {
@@ -164,7 +173,10 @@ impl ContextPicker {
                 .enumerate()
                 .map(|(ix, entry)| self.recent_menu_item(context_picker.clone(), ix, entry));
 
-            let modes = supported_context_picker_modes(&self.thread_store);
+            let mut modes = supported_context_picker_modes(&self.thread_store);
+            if self.allow_resources(cx) {
+                modes.push(ContextPickerMode::Resource);
+            }
 
             let menu = menu
                 .when(has_recent, |menu| {
@@ -212,6 +224,24 @@ impl ContextPicker {
         self.thread_store.is_some()
     }
 
+    /// Whether any running context server offers resources that can be added as context.
+    fn allow_resources(&self, cx: &App) -> bool {
+        self.context_server_manager(cx)
+            .map_or(false, |context_server_manager| {
+                context_server_manager
+                    .read(cx)
+                    .running_servers()
+                    .iter()
+                    .filter_map(|server| server.client())
+                    .any(|client| client.capable(ServerCapability::Resources))
+            })
+    }
+
+    fn context_server_manager(&self, cx: &App) -> Option<Entity<ContextServerManager>> {
+        let thread_store = self.thread_store.as_ref()?.upgrade()?;
+        Some(thread_store.read(cx).context_server_manager())
+    }
+
     fn select_mode(
         &mut self,
         mode: ContextPickerMode,
@@ -271,6 +301,20 @@ impl ContextPicker {
                     }));
                 }
             }
+            ContextPickerMode::Resource => {
+                if let Some(context_server_manager) = self.context_server_manager(cx) {
+                    self.mode = ContextPickerState::Resource(cx.new(|cx| {
+                        ResourceContextPicker::new(
+                            context_server_manager,
+                            context_picker.clone(),
+                            self.context_store.clone(),
+                            self.confirm_behavior,
+                            window,
+                            cx,
+                        )
+                    }));
+                }
+            }
         }
 
         cx.notify();
@@ -399,6 +443,7 @@ impl ContextPicker {
             ContextPickerState::Symbol(entity) => entity.update(cx, |_, cx| cx.notify()),
             ContextPickerState::Fetch(entity) => entity.update(cx, |_, cx| cx.notify()),
             ContextPickerState::Thread(entity) => entity.update(cx, |_, cx| cx.notify()),
+            ContextPickerState::Resource(entity) => entity.update(cx, |_, cx| cx.notify()),
         }
     }
 }
@@ -413,6 +458,7 @@ impl Focusable for ContextPicker {
             ContextPickerState::Symbol(symbol_picker) => symbol_picker.focus_handle(cx),
             ContextPickerState::Fetch(fetch_picker) => fetch_picker.focus_handle(cx),
             ContextPickerState::Thread(thread_picker) => thread_picker.focus_handle(cx),
+            ContextPickerState::Resource(resource_picker) => resource_picker.focus_handle(cx),
         }
     }
 }
@@ -428,6 +474,9 @@ impl Render for ContextPicker {
                 ContextPickerState::Symbol(symbol_picker) => parent.child(symbol_picker.clone()),
                 ContextPickerState::Fetch(fetch_picker) => parent.child(fetch_picker.clone()),
                 ContextPickerState::Thread(thread_picker) => parent.child(thread_picker.clone()),
+                ContextPickerState::Resource(resource_picker) => {
+                    parent.child(resource_picker.clone())
+                }
             })
     }
 }
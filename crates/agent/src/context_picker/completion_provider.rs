@@ -87,6 +87,9 @@ fn search(
                 Task::ready(Vec::new())
             }
         }
+        // Resources are only offered through the context picker menu, since
+        // templates need their arguments entered before they can be read.
+        Some(ContextPickerMode::Resource) => Task::ready(Vec::new()),
         Some(ContextPickerMode::Fetch) => {
             if !query.is_empty() {
                 Task::ready(vec![Match::Fetch(query.into())])
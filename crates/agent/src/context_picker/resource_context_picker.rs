@@ -0,0 +1,728 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use anyhow::{Context as _, Result};
+use context_server::manager::ContextServerManager;
+use context_server::protocol::ServerCapability;
+use fuzzy::StringMatchCandidate;
+use gpui::{App, DismissEvent, Entity, FocusHandle, Focusable, Task, WeakEntity};
+use picker::{Picker, PickerDelegate};
+use ui::{ListItem, prelude::*};
+use util::ResultExt as _;
+use workspace::notifications::NotifyResultExt;
+
+use crate::context_picker::{ConfirmBehavior, ContextPicker};
+use crate::context_store::{self, ContextStore};
+
+pub struct ResourceContextPicker {
+    picker: Entity<Picker<ResourceContextPickerDelegate>>,
+}
+
+impl ResourceContextPicker {
+    pub fn new(
+        context_server_manager: Entity<ContextServerManager>,
+        context_picker: WeakEntity<ContextPicker>,
+        context_store: WeakEntity<ContextStore>,
+        confirm_behavior: ConfirmBehavior,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let load_entries = load_resource_entries(&context_server_manager, cx);
+        let delegate = ResourceContextPickerDelegate::new(
+            context_server_manager,
+            context_picker,
+            context_store,
+            confirm_behavior,
+        );
+        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+
+        let picker_handle = picker.downgrade();
+        cx.spawn_in(window, async move |_, cx| {
+            let entries = load_entries.await;
+            picker_handle.update_in(cx, |picker, window, cx| {
+                picker.delegate.entries = entries;
+                picker.refresh(window, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+
+        ResourceContextPicker { picker }
+    }
+}
+
+impl Focusable for ResourceContextPicker {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for ResourceContextPicker {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        self.picker.clone()
+    }
+}
+
+/// A resource, or resource template, offered by a running context server.
+#[derive(Debug, Clone)]
+pub struct ResourceEntry {
+    pub server_id: Arc<str>,
+    pub name: SharedString,
+    /// The resource's URI, or its URI template.
+    pub uri: SharedString,
+    pub is_template: bool,
+}
+
+/// The values entered so far for the variables of a URI template.
+struct TemplateArguments {
+    entry: ResourceEntry,
+    variables: Vec<String>,
+    values: Vec<String>,
+}
+
+impl TemplateArguments {
+    fn current_variable(&self) -> &str {
+        &self.variables[self.values.len()]
+    }
+}
+
+pub struct ResourceContextPickerDelegate {
+    context_server_manager: Entity<ContextServerManager>,
+    context_picker: WeakEntity<ContextPicker>,
+    context_store: WeakEntity<context_store::ContextStore>,
+    confirm_behavior: ConfirmBehavior,
+    entries: Vec<ResourceEntry>,
+    matches: Vec<ResourceEntry>,
+    template_arguments: Option<TemplateArguments>,
+    query: String,
+    selected_index: usize,
+}
+
+impl ResourceContextPickerDelegate {
+    pub fn new(
+        context_server_manager: Entity<ContextServerManager>,
+        context_picker: WeakEntity<ContextPicker>,
+        context_store: WeakEntity<context_store::ContextStore>,
+        confirm_behavior: ConfirmBehavior,
+    ) -> Self {
+        ResourceContextPickerDelegate {
+            context_server_manager,
+            context_picker,
+            context_store,
+            confirm_behavior,
+            entries: Vec::new(),
+            matches: Vec::new(),
+            template_arguments: None,
+            query: String::new(),
+            selected_index: 0,
+        }
+    }
+
+    fn add_resource(
+        &self,
+        server_id: Arc<str>,
+        uri: SharedString,
+        name: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) {
+        let read_task =
+            context_store::read_resource(&self.context_server_manager, &server_id, &uri, cx);
+        cx.spawn_in(window, async move |this, cx| {
+            let text = read_task.await.notify_async_err(cx)?;
+            this.update_in(cx, |this, window, cx| {
+                let context_server_manager = this.delegate.context_server_manager.clone();
+                this.delegate
+                    .context_store
+                    .update(cx, |context_store, cx| {
+                        context_store.add_resource(
+                            server_id,
+                            uri,
+                            name,
+                            text,
+                            &context_server_manager,
+                            cx,
+                        )
+                    })
+                    .ok();
+
+                match this.delegate.confirm_behavior {
+                    ConfirmBehavior::KeepOpen => {}
+                    ConfirmBehavior::Close => this.delegate.dismissed(window, cx),
+                }
+            })
+            .log_err()
+        })
+        .detach();
+    }
+
+    fn start_template_arguments(
+        &mut self,
+        entry: ResourceEntry,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) {
+        let Some(variables) = uri_template_variables(&entry.uri).log_err() else {
+            return;
+        };
+        if variables.is_empty() {
+            self.add_resource(entry.server_id, entry.uri.clone(), entry.name, window, cx);
+            return;
+        }
+
+        self.template_arguments = Some(TemplateArguments {
+            entry,
+            variables,
+            values: Vec::new(),
+        });
+        self.reset_query(window, cx);
+    }
+
+    fn reset_query(&self, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        cx.defer_in(window, |picker, window, cx| {
+            picker.refresh_placeholder(window, cx);
+            picker.set_query("", window, cx);
+        });
+    }
+}
+
+impl PickerDelegate for ResourceContextPickerDelegate {
+    type ListItem = ListItem;
+
+    fn match_count(&self) -> usize {
+        if self.template_arguments.is_some() {
+            1
+        } else {
+            self.matches.len()
+        }
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        match &self.template_arguments {
+            Some(arguments) => {
+                format!("Enter a value for {}…", arguments.current_variable()).into()
+            }
+            None => "Search resources…".into(),
+        }
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        Some("No resources found".into())
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        self.query = query.clone();
+        if self.template_arguments.is_some() {
+            self.selected_index = 0;
+            return Task::ready(());
+        }
+
+        let search_task = search_resources(
+            query,
+            Arc::new(AtomicBool::default()),
+            self.entries.clone(),
+            cx,
+        );
+        cx.spawn_in(window, async move |this, cx| {
+            let matches = search_task.await;
+            this.update(cx, |this, cx| {
+                this.delegate.matches = matches;
+                this.delegate.selected_index = 0;
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
+    fn confirm(&mut self, _secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        if let Some(arguments) = self.template_arguments.as_mut() {
+            arguments.values.push(self.query.clone());
+            if arguments.values.len() < arguments.variables.len() {
+                self.reset_query(window, cx);
+                return;
+            }
+
+            let Some(arguments) = self.template_arguments.take() else {
+                return;
+            };
+            let Some(uri) = expand_uri_template(
+                &arguments.entry.uri,
+                &arguments
+                    .variables
+                    .into_iter()
+                    .zip(arguments.values)
+                    .collect::<Vec<_>>(),
+            )
+            .log_err() else {
+                return;
+            };
+            self.add_resource(
+                arguments.entry.server_id,
+                uri.into(),
+                arguments.entry.name,
+                window,
+                cx,
+            );
+            self.reset_query(window, cx);
+            return;
+        }
+
+        let Some(entry) = self.matches.get(self.selected_index).cloned() else {
+            return;
+        };
+
+        if entry.is_template {
+            self.start_template_arguments(entry, window, cx);
+        } else {
+            self.add_resource(entry.server_id, entry.uri, entry.name, window, cx);
+        }
+    }
+
+    fn dismissed(&mut self, _window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.context_picker
+            .update(cx, |_, cx| {
+                cx.emit(DismissEvent);
+            })
+            .ok();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        if let Some(arguments) = &self.template_arguments {
+            return Some(
+                ListItem::new(ix).inset(true).toggle_state(selected).child(
+                    h_flex()
+                        .gap_1p5()
+                        .child(
+                            Icon::new(IconName::DatabaseZap)
+                                .size(IconSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                        .child(Label::new(arguments.entry.name.clone()))
+                        .child(
+                            Label::new(format!(
+                                "{} = {}",
+                                arguments.current_variable(),
+                                self.query
+                            ))
+                            .color(Color::Muted)
+                            .truncate(),
+                        ),
+                ),
+            );
+        }
+
+        let entry = &self.matches[ix];
+        Some(ListItem::new(ix).inset(true).toggle_state(selected).child(
+            render_resource_context_entry(entry, self.context_store.clone(), cx),
+        ))
+    }
+}
+
+pub fn render_resource_context_entry(
+    entry: &ResourceEntry,
+    context_store: WeakEntity<ContextStore>,
+    cx: &mut App,
+) -> Div {
+    let added = !entry.is_template
+        && context_store.upgrade().map_or(false, |ctx_store| {
+            ctx_store
+                .read(cx)
+                .includes_resource(&entry.server_id, &entry.uri)
+                .is_some()
+        });
+
+    h_flex()
+        .gap_1p5()
+        .w_full()
+        .justify_between()
+        .child(
+            h_flex()
+                .gap_1p5()
+                .max_w_72()
+                .child(
+                    Icon::new(IconName::DatabaseZap)
+                        .size(IconSize::XSmall)
+                        .color(Color::Muted),
+                )
+                .child(Label::new(entry.name.clone()).truncate())
+                .child(
+                    Label::new(entry.server_id.to_string())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+        )
+        .when(added, |el| {
+            el.child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Icon::new(IconName::Check)
+                            .size(IconSize::Small)
+                            .color(Color::Success),
+                    )
+                    .child(Label::new("Added").size(LabelSize::Small)),
+            )
+        })
+}
+
+/// Lists the resources and resource templates of every running context server.
+fn load_resource_entries(
+    context_server_manager: &Entity<ContextServerManager>,
+    cx: &App,
+) -> Task<Vec<ResourceEntry>> {
+    let clients = context_server_manager
+        .read(cx)
+        .running_servers()
+        .into_iter()
+        .filter_map(|server| Some((server.id(), server.client()?)))
+        .filter(|(_, client)| client.capable(ServerCapability::Resources))
+        .collect::<Vec<_>>();
+
+    cx.spawn(async move |_| {
+        let mut entries = Vec::new();
+        for (server_id, client) in clients {
+            if let Some(response) = client.list_resources().await.log_err() {
+                entries.extend(
+                    response
+                        .resources
+                        .into_iter()
+                        .map(|resource| ResourceEntry {
+                            server_id: server_id.clone(),
+                            name: resource.name.into(),
+                            uri: resource.uri.to_string().into(),
+                            is_template: false,
+                        }),
+                );
+            }
+            // Not every server that offers resources implements templates.
+            if let Ok(response) = client.list_resource_templates().await {
+                entries.extend(
+                    response
+                        .resource_templates
+                        .into_iter()
+                        // Templates that can't be expanded aren't offered.
+                        .filter(|template| {
+                            uri_template_variables(&template.uri_template)
+                                .log_err()
+                                .is_some()
+                        })
+                        .map(|template| ResourceEntry {
+                            server_id: server_id.clone(),
+                            name: template.name.into(),
+                            uri: template.uri_template.into(),
+                            is_template: true,
+                        }),
+                );
+            }
+        }
+        entries
+    })
+}
+
+pub(crate) fn search_resources(
+    query: String,
+    cancellation_flag: Arc<AtomicBool>,
+    entries: Vec<ResourceEntry>,
+    cx: &App,
+) -> Task<Vec<ResourceEntry>> {
+    let executor = cx.background_executor().clone();
+    cx.background_spawn(async move {
+        if query.is_empty() {
+            return entries;
+        }
+
+        let candidates = entries
+            .iter()
+            .enumerate()
+            .map(|(id, entry)| {
+                StringMatchCandidate::new(id, &format!("{} {}", entry.name, entry.uri))
+            })
+            .collect::<Vec<_>>();
+        let matches = fuzzy::match_strings(
+            &candidates,
+            &query,
+            false,
+            100,
+            &cancellation_flag,
+            executor,
+        )
+        .await;
+
+        matches
+            .into_iter()
+            .map(|mat| entries[mat.candidate_id].clone())
+            .collect()
+    })
+}
+
+/// How an RFC 6570 operator expands the variables of an expression.
+struct UriTemplateOperator {
+    first: &'static str,
+    separator: &'static str,
+    named: bool,
+    if_empty: &'static str,
+    allow_reserved: bool,
+}
+
+impl UriTemplateOperator {
+    fn new(operator: Option<char>) -> Option<Self> {
+        let (first, separator, named, if_empty, allow_reserved) = match operator {
+            None => ("", ",", false, "", false),
+            Some('+') => ("", ",", false, "", true),
+            Some('#') => ("#", ",", false, "", true),
+            Some('.') => (".", ".", false, "", false),
+            Some('/') => ("/", "/", false, "", false),
+            Some(';') => (";", ";", true, "", false),
+            Some('?') => ("?", "&", true, "=", false),
+            Some('&') => ("&", "&", true, "=", false),
+            Some(_) => return None,
+        };
+        Some(Self {
+            first,
+            separator,
+            named,
+            if_empty,
+            allow_reserved,
+        })
+    }
+}
+
+/// A variable of a URI template expression, with the number of characters
+/// of its value to keep if it has a prefix modifier.
+struct UriTemplateVariable<'a> {
+    name: &'a str,
+    max_len: Option<usize>,
+}
+
+enum UriTemplatePart<'a> {
+    Literal(&'a str),
+    Expression {
+        operator: UriTemplateOperator,
+        variables: Vec<UriTemplateVariable<'a>>,
+    },
+}
+
+/// Parses an RFC 6570 URI template, failing for templates that are malformed
+/// or use operators reserved for future extensions.
+fn parse_uri_template(template: &str) -> Result<Vec<UriTemplatePart>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .with_context(|| format!("unterminated expression in URI template {template:?}"))?;
+        if start > 0 {
+            parts.push(UriTemplatePart::Literal(&rest[..start]));
+        }
+
+        let expression = &rest[start + 1..start + end];
+        let operator = expression
+            .chars()
+            .next()
+            .filter(|c| !c.is_ascii_alphanumeric() && *c != '_' && *c != '%');
+        let variables = &expression[operator.map_or(0, char::len_utf8)..];
+        let operator = UriTemplateOperator::new(operator).with_context(|| {
+            format!("unsupported operator in URI template expression {{{expression}}}")
+        })?;
+        let variables = variables
+            .split(',')
+            .map(|variable| {
+                let variable = variable.strip_suffix('*').unwrap_or(variable);
+                let (name, max_len) = match variable.split_once(':') {
+                    Some((name, max_len)) => (name, Some(max_len.parse::<usize>()?)),
+                    None => (variable, None),
+                };
+                anyhow::ensure!(
+                    !name.is_empty()
+                        && name
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '%')),
+                    "invalid variable name {name:?}"
+                );
+                Ok(UriTemplateVariable { name, max_len })
+            })
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("invalid URI template expression {{{expression}}}"))?;
+        parts.push(UriTemplatePart::Expression {
+            operator,
+            variables,
+        });
+
+        rest = &rest[start + end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(UriTemplatePart::Literal(rest));
+    }
+    Ok(parts)
+}
+
+/// Returns the names of the variables in an RFC 6570 URI template, in order.
+fn uri_template_variables(template: &str) -> Result<Vec<String>> {
+    let mut variables = Vec::<String>::new();
+    for part in parse_uri_template(template)? {
+        if let UriTemplatePart::Expression {
+            variables: expression_variables,
+            ..
+        } = part
+        {
+            for variable in expression_variables {
+                if !variables.iter().any(|name| name == variable.name) {
+                    variables.push(variable.name.to_string());
+                }
+            }
+        }
+    }
+    Ok(variables)
+}
+
+/// Expands an RFC 6570 URI template with the given string values. Variables
+/// without a value are left out.
+fn expand_uri_template(template: &str, values: &[(String, String)]) -> Result<String> {
+    let mut expanded = String::new();
+    for part in parse_uri_template(template)? {
+        let (operator, variables) = match part {
+            UriTemplatePart::Literal(literal) => {
+                expanded.push_str(literal);
+                continue;
+            }
+            UriTemplatePart::Expression {
+                operator,
+                variables,
+            } => (operator, variables),
+        };
+
+        let expansions = variables
+            .iter()
+            .filter_map(|variable| {
+                let (_, value) = values.iter().find(|(name, _)| name == variable.name)?;
+                let value = match variable.max_len {
+                    Some(max_len) => match value.char_indices().nth(max_len) {
+                        Some((ix, _)) => &value[..ix],
+                        None => value,
+                    },
+                    None => value,
+                };
+                let value = percent_encode(value, operator.allow_reserved);
+                Some(if !operator.named {
+                    value
+                } else if value.is_empty() {
+                    format!("{}{}", variable.name, operator.if_empty)
+                } else {
+                    format!("{}={value}", variable.name)
+                })
+            })
+            .collect::<Vec<_>>();
+        if !expansions.is_empty() {
+            expanded.push_str(operator.first);
+            expanded.push_str(&expansions.join(operator.separator));
+        }
+    }
+    Ok(expanded)
+}
+
+/// Percent-encodes the characters of a value that aren't unreserved, or, if
+/// `allow_reserved` is set, reserved or part of a percent-encoded triplet.
+fn percent_encode(value: &str, allow_reserved: bool) -> String {
+    const RESERVED: &[u8] = b":/?#[]@!$&'()*+,;=";
+
+    let bytes = value.as_bytes();
+    let mut encoded = String::with_capacity(value.len());
+    for (ix, &byte) in bytes.iter().enumerate() {
+        let is_unreserved =
+            byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~');
+        let is_allowed_reserved = allow_reserved
+            && (RESERVED.contains(&byte)
+                || (byte == b'%'
+                    && bytes.get(ix + 1..ix + 3).is_some_and(|digits| {
+                        digits.iter().all(|digit| digit.is_ascii_hexdigit())
+                    })));
+        if is_unreserved || is_allowed_reserved {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_uri_template() {
+        let template = "db://{schema}/tables/{table}{#fragment}?path={+path}";
+        assert_eq!(
+            uri_template_variables(template).unwrap(),
+            vec!["schema", "table", "fragment", "path"]
+        );
+
+        let values = [
+            ("schema", "public"),
+            ("table", "user accounts"),
+            ("fragment", "columns"),
+            ("path", "a/b"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        assert_eq!(
+            expand_uri_template(template, &values).unwrap(),
+            "db://public/tables/user%20accounts#columns?path=a/b"
+        );
+    }
+
+    #[test]
+    fn test_expand_uri_template_operators() {
+        let values = [
+            ("var", "value"),
+            ("path", "/foo/bar"),
+            ("x", "1024"),
+            ("y", "768"),
+            ("empty", ""),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let expand = |template| expand_uri_template(template, &values).unwrap();
+
+        assert_eq!(expand("{var:3}"), "val");
+        assert_eq!(expand("{x,y}"), "1024,768");
+        assert_eq!(expand("{path}"), "%2Ffoo%2Fbar");
+        assert_eq!(expand("{+path}/here"), "/foo/bar/here");
+        assert_eq!(expand("{#path}"), "#/foo/bar");
+        assert_eq!(expand("X{.var,x}"), "X.value.1024");
+        assert_eq!(expand("{/var,x}/here"), "/value/1024/here");
+        assert_eq!(expand("{;x,y,empty}"), ";x=1024;y=768;empty");
+        assert_eq!(expand("{?x,y,empty}"), "?x=1024&y=768&empty=");
+        assert_eq!(expand("?fixed=yes{&x}"), "?fixed=yes&x=1024");
+        assert_eq!(expand("{?undefined}"), "");
+
+        assert_eq!(
+            uri_template_variables("{/var,x}{?y,var}").unwrap(),
+            vec!["var", "x", "y"]
+        );
+        assert!(uri_template_variables("{=var}").is_err());
+        assert!(uri_template_variables("{var").is_err());
+        assert!(uri_template_variables("{var:abc}").is_err());
+    }
+}
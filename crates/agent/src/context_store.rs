@@ -4,9 +4,12 @@ use std::sync::Arc;
 
 use anyhow::{Context as _, Result, anyhow};
 use collections::{BTreeMap, HashMap, HashSet};
+use context_server::manager::ContextServerManager;
+use context_server::types::ResourceContentsType;
 use futures::future::join_all;
 use futures::{self, Future, FutureExt, future};
-use gpui::{App, AppContext as _, Context, Entity, SharedString, Task, WeakEntity};
+use gpui::{App, AppContext as _, Context, Entity, SharedString, Subscription, Task, WeakEntity};
+use http_client::Url;
 use language::{Buffer, File};
 use project::{Project, ProjectItem, ProjectPath, Worktree};
 use rope::Rope;
@@ -16,7 +19,7 @@ use util::{ResultExt as _, maybe};
 use crate::ThreadStore;
 use crate::context::{
     AssistantContext, ContextBuffer, ContextId, ContextSymbol, ContextSymbolId, DirectoryContext,
-    FetchedUrlContext, FileContext, SymbolContext, ThreadContext,
+    FetchedUrlContext, FileContext, ResourceContext, SymbolContext, ThreadContext,
 };
 use crate::context_strip::SuggestedContext;
 use crate::thread::{Thread, ThreadId};
@@ -35,6 +38,9 @@ pub struct ContextStore {
     threads: HashMap<ThreadId, ContextId>,
     thread_summary_tasks: Vec<Task<()>>,
     fetched_urls: HashMap<String, ContextId>,
+    resources: HashMap<(Arc<str>, SharedString), ContextId>,
+    context_server_manager: Option<WeakEntity<ContextServerManager>>,
+    context_server_subscription: Option<Subscription>,
 }
 
 impl ContextStore {
@@ -55,6 +61,9 @@ impl ContextStore {
             threads: HashMap::default(),
             thread_summary_tasks: Vec::new(),
             fetched_urls: HashMap::default(),
+            resources: HashMap::default(),
+            context_server_manager: None,
+            context_server_subscription: None,
         }
     }
 
@@ -66,12 +75,22 @@ impl ContextStore {
         self.context().iter().find(|context| context.id() == id)
     }
 
-    pub fn clear(&mut self) {
+    pub fn clear(&mut self, cx: &App) {
         self.context.clear();
         self.files.clear();
         self.directories.clear();
         self.threads.clear();
         self.fetched_urls.clear();
+        let resources = std::mem::take(&mut self.resources);
+        if let Some(context_server_manager) = self
+            .context_server_manager
+            .as_ref()
+            .and_then(|manager| manager.upgrade())
+        {
+            for (server_id, uri) in resources.keys() {
+                set_resource_subscription(&context_server_manager, server_id, uri, false, cx);
+            }
+        }
     }
 
     pub fn add_file_from_path(
@@ -416,6 +435,82 @@ impl ContextStore {
         cx.notify();
     }
 
+    /// Adds a resource read from a context server, keeping it up to date when
+    /// the server reports that it changed.
+    pub fn add_resource(
+        &mut self,
+        server_id: Arc<str>,
+        uri: SharedString,
+        name: SharedString,
+        text: impl Into<SharedString>,
+        context_server_manager: &Entity<ContextServerManager>,
+        cx: &mut Context<ContextStore>,
+    ) {
+        if self.includes_resource(&server_id, &uri).is_some() {
+            return;
+        }
+
+        if self.context_server_subscription.is_none() {
+            self.context_server_manager = Some(context_server_manager.downgrade());
+            self.context_server_subscription = Some(cx.subscribe(
+                context_server_manager,
+                |this, context_server_manager, event, cx| {
+                    if let context_server::manager::Event::ResourceUpdated { server_id, uri } =
+                        event
+                    {
+                        this.refresh_resource(
+                            server_id.clone(),
+                            uri.clone().into(),
+                            context_server_manager,
+                            cx,
+                        );
+                    }
+                },
+            ));
+        }
+        set_resource_subscription(context_server_manager, &server_id, &uri, true, cx);
+
+        let id = self.next_context_id.post_inc();
+        self.resources.insert((server_id.clone(), uri.clone()), id);
+        self.context
+            .push(AssistantContext::Resource(ResourceContext {
+                id,
+                server_id,
+                uri,
+                name,
+                text: text.into(),
+            }));
+        cx.notify();
+    }
+
+    fn refresh_resource(
+        &mut self,
+        server_id: Arc<str>,
+        uri: SharedString,
+        context_server_manager: Entity<ContextServerManager>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(id) = self.includes_resource(&server_id, &uri) else {
+            return;
+        };
+        let read_task = read_resource(&context_server_manager, &server_id, &uri, cx);
+        cx.spawn(async move |this, cx| {
+            let text = read_task.await?;
+            this.update(cx, |this, cx| {
+                let Some(AssistantContext::Resource(resource)) = this.context_for_id(id) else {
+                    return;
+                };
+                let resource = ResourceContext {
+                    text: text.into(),
+                    ..resource.clone()
+                };
+                this.replace_context(AssistantContext::Resource(resource));
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
     pub fn accept_suggested_context(
         &mut self,
         suggested: &SuggestedContext,
@@ -471,6 +566,22 @@ impl ContextStore {
             AssistantContext::Thread(_) => {
                 self.threads.retain(|_, context_id| *context_id != id);
             }
+            AssistantContext::Resource(resource) => {
+                self.resources.retain(|_, context_id| *context_id != id);
+                if let Some(context_server_manager) = self
+                    .context_server_manager
+                    .as_ref()
+                    .and_then(|manager| manager.upgrade())
+                {
+                    set_resource_subscription(
+                        &context_server_manager,
+                        &resource.server_id,
+                        &resource.uri,
+                        false,
+                        cx,
+                    );
+                }
+            }
         }
 
         cx.notify();
@@ -571,6 +682,12 @@ impl ContextStore {
         self.fetched_urls.get(url).copied()
     }
 
+    pub fn includes_resource(&self, server_id: &Arc<str>, uri: &SharedString) -> Option<ContextId> {
+        self.resources
+            .get(&(server_id.clone(), uri.clone()))
+            .copied()
+    }
+
     /// Replaces the context that matches the ID of the new context, if any match.
     fn replace_context(&mut self, new_context: AssistantContext) {
         let id = new_context.id();
@@ -593,7 +710,8 @@ impl ContextStore {
                 AssistantContext::Directory(_)
                 | AssistantContext::Symbol(_)
                 | AssistantContext::FetchedUrl(_)
-                | AssistantContext::Thread(_) => None,
+                | AssistantContext::Thread(_)
+                | AssistantContext::Resource(_) => None,
             })
             .collect()
     }
@@ -603,6 +721,75 @@ impl ContextStore {
     }
 }
 
+/// Reads the text contents of a resource from a running context server.
+pub fn read_resource(
+    context_server_manager: &Entity<ContextServerManager>,
+    server_id: &str,
+    uri: &str,
+    cx: &App,
+) -> Task<Result<String>> {
+    let Some(client) = context_server_manager
+        .read(cx)
+        .get_server(server_id)
+        .and_then(|server| server.client())
+    else {
+        return Task::ready(Err(anyhow!("context server {server_id} is not running")));
+    };
+    let uri = match Url::parse(uri) {
+        Ok(uri) => uri,
+        Err(error) => return Task::ready(Err(anyhow!("invalid resource URI {uri:?}: {error}"))),
+    };
+
+    cx.spawn(async move |_| {
+        let response = client.read_resource(uri).await?;
+        let contents = response
+            .contents
+            .into_iter()
+            .map(|contents| match contents {
+                ResourceContentsType::Text(contents) => contents.text,
+                ResourceContentsType::Blob(contents) => format!(
+                    "[{} content from {}]",
+                    contents
+                        .mime_type
+                        .as_deref()
+                        .unwrap_or("application/octet-stream"),
+                    contents.uri
+                ),
+            })
+            .collect::<Vec<_>>();
+        Ok(contents.join("\n"))
+    })
+}
+
+fn set_resource_subscription(
+    context_server_manager: &Entity<ContextServerManager>,
+    server_id: &str,
+    uri: &str,
+    subscribed: bool,
+    cx: &App,
+) {
+    let Some(client) = context_server_manager
+        .read(cx)
+        .get_server(server_id)
+        .and_then(|server| server.client())
+        .filter(|client| client.supports_resource_subscriptions())
+    else {
+        return;
+    };
+    let Some(uri) = Url::parse(uri).log_err() else {
+        return;
+    };
+
+    cx.spawn(async move |_| {
+        if subscribed {
+            client.subscribe_resource(uri).await
+        } else {
+            client.unsubscribe_resource(uri).await
+        }
+    })
+    .detach_and_log_err(cx);
+}
+
 pub enum FileInclusion {
     Direct(ContextId),
     InDirectory(ProjectPath),
@@ -779,6 +966,8 @@ pub fn refresh_context_store_text(
                 // and doing the caching properly could be tricky (unless it's already handled by
                 // the HttpClient?).
                 AssistantContext::FetchedUrl(_) => {}
+                // Resources are refreshed when their context server reports an update.
+                AssistantContext::Resource(_) => {}
             }
 
             None
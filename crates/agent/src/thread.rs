@@ -5,10 +5,10 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use anyhow::{Context as _, Result, anyhow};
-use assistant_settings::AssistantSettings;
+use assistant_settings::{AssistantSettings, ToolPermissionDecision};
 use assistant_tool::{ActionLog, Tool, ToolWorkingSet};
 use chrono::{DateTime, Utc};
-use collections::{BTreeMap, HashMap};
+use collections::{BTreeMap, HashMap, HashSet};
 use feature_flags::{self, FeatureFlagAppExt};
 use futures::future::Shared;
 use futures::{FutureExt, StreamExt as _};
@@ -27,7 +27,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
 use thiserror::Error;
-use util::{ResultExt as _, TryFutureExt as _, post_inc};
+use util::{ResultExt as _, TryFutureExt as _, post_inc, truncate_lines_to_byte_limit};
 use uuid::Uuid;
 
 use crate::context::{AssistantContext, ContextId, format_context_as_string};
@@ -76,6 +76,15 @@ impl MessageId {
     }
 }
 
+/// The thread, and the message within it, that a thread was forked from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadParent {
+    pub thread_id: ThreadId,
+    pub message_id: MessageId,
+    /// The summary of the parent thread at the time it was forked.
+    pub summary: SharedString,
+}
+
 /// A message in a [`Thread`].
 #[derive(Debug, Clone)]
 pub struct Message {
@@ -221,6 +230,24 @@ pub enum DetailedSummaryState {
     },
 }
 
+/// The older part of a thread that is sent to the model as a summary instead
+/// of verbatim. The original messages are kept in the thread.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThreadCompaction {
+    /// The last message covered by the summary.
+    pub message_id: MessageId,
+    pub summary: SharedString,
+}
+
+/// The number of most recent user turns that are never compacted.
+const COMPACTION_RECENT_TURNS: usize = 2;
+/// The number of most recent messages with tool results whose results are
+/// never truncated by compaction.
+const COMPACTION_RECENT_TOOL_RESULTS: usize = 4;
+/// The size that older tool results in the turns kept by compaction are
+/// truncated to.
+const COMPACTED_TOOL_RESULT_BYTES: usize = 2048;
+
 #[derive(Default)]
 pub struct TotalTokenUsage {
     pub total: usize,
@@ -263,6 +290,18 @@ pub struct Thread {
     feedback: Option<ThreadFeedback>,
     message_feedback: HashMap<MessageId, ThreadFeedback>,
     last_auto_capture_at: Option<Instant>,
+    parent: Option<ThreadParent>,
+    request_token_usage: TokenUsage,
+    compaction: Option<ThreadCompaction>,
+    /// The last message whose tool results are truncated in requests.
+    truncated_tool_results: Option<MessageId>,
+    pending_compaction: Option<Task<()>>,
+    /// The model to send the thread to again once the pending compaction is
+    /// done, after the last request exceeded its context window.
+    retry_after_compaction: Option<Arc<dyn LanguageModel>>,
+    /// Whether the last request was sent again after compacting the thread, in
+    /// which case exceeding the context window again is reported as an error.
+    retried_after_compaction: bool,
 }
 
 impl Thread {
@@ -304,6 +343,125 @@ impl Thread {
             feedback: None,
             message_feedback: HashMap::default(),
             last_auto_capture_at: None,
+            parent: None,
+            request_token_usage: TokenUsage::default(),
+            compaction: None,
+            truncated_tool_results: None,
+            pending_compaction: None,
+            retry_after_compaction: None,
+            retried_after_compaction: false,
+        }
+    }
+
+    /// Creates a new thread containing the messages of `parent` up to and
+    /// including `message_id`, along with their tool uses, context, and checkpoints.
+    ///
+    /// The results of the tools used by `message_id` are kept too, and tool uses
+    /// that haven't got a result yet are left out.
+    pub fn fork(parent: &Entity<Thread>, message_id: MessageId, cx: &mut Context<Self>) -> Self {
+        let parent = parent.read(cx);
+        let mut end = parent
+            .messages
+            .iter()
+            .position(|message| message.id == message_id)
+            .map_or(parent.messages.len(), |ix| ix + 1);
+        // Tool results are attached to the user message after the one that used
+        // the tools, which has to be sent along with it.
+        if parent.messages.get(end).is_some_and(|message| {
+            message.role == Role::User && parent.tool_use.message_has_tool_results(message.id)
+        }) {
+            end += 1;
+        }
+        let messages = parent.messages[..end].to_vec();
+        let message_ids = messages
+            .iter()
+            .map(|message| message.id)
+            .collect::<HashSet<_>>();
+
+        let context_by_message = parent
+            .context_by_message
+            .iter()
+            .filter(|(message_id, _)| message_ids.contains(message_id))
+            .map(|(message_id, context_ids)| (*message_id, context_ids.clone()))
+            .collect::<HashMap<_, _>>();
+        let context = parent
+            .context
+            .iter()
+            .filter(|(context_id, _)| {
+                context_by_message
+                    .values()
+                    .any(|context_ids| context_ids.contains(context_id))
+            })
+            .map(|(context_id, context)| (*context_id, context.clone()))
+            .collect();
+        let checkpoints_by_message = parent
+            .checkpoints_by_message
+            .iter()
+            .filter(|(message_id, _)| message_ids.contains(message_id))
+            .map(|(message_id, checkpoint)| (*message_id, checkpoint.clone()))
+            .collect();
+        let message_feedback = parent
+            .message_feedback
+            .iter()
+            .filter(|(message_id, _)| message_ids.contains(message_id))
+            .map(|(message_id, feedback)| (*message_id, *feedback))
+            .collect();
+
+        let thread_parent = ThreadParent {
+            thread_id: parent.id.clone(),
+            message_id,
+            summary: parent.summary_or_default(),
+        };
+        let summary = parent.summary.clone();
+        let next_message_id = parent.next_message_id;
+        let project_context = parent.project_context.clone();
+        let project = parent.project.clone();
+        let prompt_builder = parent.prompt_builder.clone();
+        let tools = parent.tools.clone();
+        let tool_use = parent.tool_use.fork(&message_ids);
+        let initial_project_snapshot = parent.initial_project_snapshot.clone();
+        let compaction = parent
+            .compaction
+            .clone()
+            .filter(|compaction| message_ids.contains(&compaction.message_id));
+        let truncated_tool_results = parent
+            .truncated_tool_results
+            .filter(|message_id| message_ids.contains(message_id));
+
+        Self {
+            id: ThreadId::new(),
+            updated_at: Utc::now(),
+            summary,
+            pending_summary: Task::ready(None),
+            detailed_summary_state: DetailedSummaryState::NotGenerated,
+            messages,
+            next_message_id,
+            context,
+            context_by_message,
+            project_context,
+            checkpoints_by_message,
+            completion_count: 0,
+            pending_completions: Vec::new(),
+            project: project.clone(),
+            prompt_builder,
+            tools,
+            last_restore_checkpoint: None,
+            pending_checkpoint: None,
+            tool_use,
+            action_log: cx.new(|_| ActionLog::new(project)),
+            initial_project_snapshot,
+            // The parent's usage includes requests for messages that aren't in the fork.
+            cumulative_token_usage: TokenUsage::default(),
+            feedback: None,
+            message_feedback,
+            last_auto_capture_at: None,
+            parent: Some(thread_parent),
+            request_token_usage: TokenUsage::default(),
+            compaction,
+            truncated_tool_results,
+            pending_compaction: None,
+            retry_after_compaction: None,
+            retried_after_compaction: false,
         }
     }
 
@@ -370,6 +528,13 @@ impl Thread {
             feedback: None,
             message_feedback: HashMap::default(),
             last_auto_capture_at: None,
+            parent: serialized.parent,
+            request_token_usage: TokenUsage::default(),
+            compaction: serialized.compaction,
+            truncated_tool_results: serialized.truncated_tool_results,
+            pending_compaction: None,
+            retry_after_compaction: None,
+            retried_after_compaction: false,
         }
     }
 
@@ -377,6 +542,33 @@ impl Thread {
         &self.id
     }
 
+    /// Returns the thread and message this thread was forked from, if any.
+    pub fn parent(&self) -> Option<&ThreadParent> {
+        self.parent.as_ref()
+    }
+
+    /// Returns the summary that replaces older messages in requests, if any.
+    pub fn compaction(&self) -> Option<&ThreadCompaction> {
+        self.compaction.as_ref()
+    }
+
+    pub fn is_compacting(&self) -> bool {
+        self.pending_compaction.is_some()
+    }
+
+    /// Whether the given message is covered by the compaction summary.
+    pub fn is_message_compacted(&self, id: MessageId) -> bool {
+        self.compaction
+            .as_ref()
+            .map_or(false, |compaction| id <= compaction.message_id)
+    }
+
+    /// Whether the tool results of the given message are truncated in requests.
+    fn are_tool_results_truncated(&self, id: MessageId) -> bool {
+        self.truncated_tool_results
+            .map_or(false, |message_id| id <= message_id)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.messages.is_empty()
     }
@@ -443,7 +635,9 @@ impl Thread {
     }
 
     pub fn is_generating(&self) -> bool {
-        !self.pending_completions.is_empty() || !self.all_tools_finished()
+        !self.pending_completions.is_empty()
+            || self.retry_after_compaction.is_some()
+            || !self.all_tools_finished()
     }
 
     pub fn tools(&self) -> &Arc<ToolWorkingSet> {
@@ -582,6 +776,13 @@ impl Thread {
             self.context_by_message.remove(&deleted_message.id);
             self.checkpoints_by_message.remove(&deleted_message.id);
         }
+        if self.is_message_compacted(message_id) {
+            self.compaction = None;
+            self.pending_compaction = None;
+        }
+        if self.are_tool_results_truncated(message_id) {
+            self.truncated_tool_results = None;
+        }
         cx.notify();
     }
 
@@ -664,7 +865,9 @@ impl Thread {
                                 cx,
                             );
                         }
-                        AssistantContext::FetchedUrl(_) | AssistantContext::Thread(_) => {}
+                        AssistantContext::FetchedUrl(_)
+                        | AssistantContext::Thread(_)
+                        | AssistantContext::Resource(_) => {}
                     }
                 }
             });
@@ -817,6 +1020,9 @@ impl Thread {
                 initial_project_snapshot,
                 cumulative_token_usage: this.cumulative_token_usage.clone(),
                 detailed_summary_state: this.detailed_summary_state.clone(),
+                parent: this.parent.clone(),
+                compaction: this.compaction.clone(),
+                truncated_tool_results: this.truncated_tool_results,
             })
         })
     }
@@ -862,12 +1068,24 @@ impl Thread {
         &self,
         request_kind: RequestKind,
         cx: &App,
+    ) -> LanguageModelRequest {
+        self.completion_request_through(request_kind, None, cx)
+    }
+
+    /// Builds a request containing the messages up to and including `last_message_id`,
+    /// or all messages if it is `None`.
+    fn completion_request_through(
+        &self,
+        request_kind: RequestKind,
+        last_message_id: Option<MessageId>,
+        cx: &App,
     ) -> LanguageModelRequest {
         let mut request = LanguageModelRequest {
             messages: vec![],
             tools: Vec::new(),
             stop: Vec::new(),
             temperature: None,
+            max_tokens: None,
         };
 
         if let Some(project_context) = self.project_context.borrow().as_ref() {
@@ -887,7 +1105,26 @@ impl Thread {
             log::error!("project_context not set.")
         }
 
+        if let Some(compaction) = &self.compaction {
+            request.messages.push(LanguageModelRequestMessage {
+                role: Role::User,
+                content: vec![MessageContent::Text(format!(
+                    "The earlier part of this conversation was replaced by the following summary \
+                    to save space:\n\n{}",
+                    compaction.summary
+                ))],
+                cache: false,
+            });
+        }
+
         for message in &self.messages {
+            if last_message_id.map_or(false, |last_message_id| message.id > last_message_id) {
+                break;
+            }
+            if self.is_message_compacted(message.id) {
+                continue;
+            }
+
             let mut request_message = LanguageModelRequestMessage {
                 role: message.role,
                 content: Vec::new(),
@@ -898,6 +1135,9 @@ impl Thread {
                 RequestKind::Chat => {
                     self.tool_use
                         .attach_tool_results(message.id, &mut request_message);
+                    if self.are_tool_results_truncated(message.id) {
+                        truncate_tool_results(&mut request_message);
+                    }
                 }
                 RequestKind::Summarize => {
                     // We don't care about tool use during summarization.
@@ -1021,6 +1261,7 @@ impl Thread {
                                 thread.cumulative_token_usage =
                                     thread.cumulative_token_usage.clone() + token_usage.clone()
                                         - current_token_usage.clone();
+                                thread.request_token_usage = token_usage.clone();
                                 current_token_usage = token_usage;
                             }
                             LanguageModelCompletionEvent::Text(chunk) => {
@@ -1113,6 +1354,8 @@ impl Thread {
             thread
                 .update(cx, |thread, cx| {
                     thread.finalize_pending_checkpoint(cx);
+                    let retry = matches!(&result, Err(error) if is_context_window_exceeded(error))
+                        && thread.compact_and_retry(model.clone(), cx);
                     match result.as_ref() {
                         Ok(stop_reason) => match stop_reason {
                             StopReason::ToolUse => {
@@ -1122,6 +1365,11 @@ impl Thread {
                             StopReason::EndTurn => {}
                             StopReason::MaxTokens => {}
                         },
+                        Err(_) if retry => {
+                            thread
+                                .pending_completions
+                                .retain(|completion| completion.id != pending_completion_id);
+                        }
                         Err(error) => {
                             if error.is::<PaymentRequiredError>() {
                                 cx.emit(ThreadEvent::ShowError(ThreadError::PaymentRequired));
@@ -1144,7 +1392,13 @@ impl Thread {
                             thread.cancel_last_completion(cx);
                         }
                     }
-                    cx.emit(ThreadEvent::Stopped(result.map_err(Arc::new)));
+                    if result.is_ok() {
+                        thread.retried_after_compaction = false;
+                        thread.compact_if_needed(&model, cx);
+                    }
+                    if !retry {
+                        cx.emit(ThreadEvent::Stopped(result.map_err(Arc::new)));
+                    }
 
                     thread.auto_capture_telemetry(cx);
 
@@ -1240,6 +1494,42 @@ impl Thread {
             _ => {}
         }
 
+        let summary = self.stream_detailed_summary(last_message_id, cx)?;
+
+        let task = cx.spawn(async move |thread, cx| {
+            let Some(new_detailed_summary) = summary.await.log_err() else {
+                thread
+                    .update(cx, |this, _cx| {
+                        this.detailed_summary_state = DetailedSummaryState::NotGenerated;
+                    })
+                    .log_err();
+
+                return;
+            };
+
+            thread
+                .update(cx, |this, _cx| {
+                    this.detailed_summary_state = DetailedSummaryState::Generated {
+                        text: new_detailed_summary.into(),
+                        message_id: last_message_id,
+                    };
+                })
+                .log_err();
+        });
+
+        self.detailed_summary_state = DetailedSummaryState::Generating {
+            message_id: last_message_id,
+        };
+
+        Some(task)
+    }
+
+    /// Generates a detailed summary of the conversation up to and including `last_message_id`.
+    fn stream_detailed_summary(
+        &self,
+        last_message_id: MessageId,
+        cx: &App,
+    ) -> Option<Task<Result<String>>> {
         let ConfiguredModel { model, provider } =
             LanguageModelRegistry::read_global(cx).thread_summary_model()?;
 
@@ -1247,7 +1537,8 @@ impl Thread {
             return None;
         }
 
-        let mut request = self.to_completion_request(RequestKind::Summarize, cx);
+        let mut request =
+            self.completion_request_through(RequestKind::Summarize, Some(last_message_id), cx);
 
         request.messages.push(LanguageModelRequestMessage {
             role: Role::User,
@@ -1263,41 +1554,137 @@ impl Thread {
             cache: false,
         });
 
-        let task = cx.spawn(async move |thread, cx| {
-            let stream = model.stream_completion_text(request, &cx);
-            let Some(mut messages) = stream.await.log_err() else {
-                thread
-                    .update(cx, |this, _cx| {
-                        this.detailed_summary_state = DetailedSummaryState::NotGenerated;
-                    })
-                    .log_err();
-
-                return;
-            };
-
-            let mut new_detailed_summary = String::new();
-
+        Some(cx.spawn(async move |cx| {
+            let mut messages = model.stream_completion_text(request, &cx).await?;
+            let mut summary = String::new();
             while let Some(chunk) = messages.stream.next().await {
                 if let Some(chunk) = chunk.log_err() {
-                    new_detailed_summary.push_str(&chunk);
+                    summary.push_str(&chunk);
                 }
             }
+            Ok(summary)
+        }))
+    }
 
-            thread
-                .update(cx, |this, _cx| {
-                    this.detailed_summary_state = DetailedSummaryState::Generated {
-                        text: new_detailed_summary.into(),
-                        message_id: last_message_id,
-                    };
-                })
-                .log_err();
-        });
+    /// Returns the last message that can be compacted, keeping the most recent
+    /// user turns (and their tool uses) verbatim.
+    fn compaction_boundary(&self) -> Option<MessageId> {
+        let turn_starts = self
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| {
+                message.role == Role::User && !self.tool_use.message_has_tool_results(message.id)
+            })
+            .map(|(ix, _)| ix)
+            .collect::<Vec<_>>();
+        let first_recent_turn_ix =
+            *turn_starts.get(turn_starts.len().checked_sub(COMPACTION_RECENT_TURNS)?)?;
+        let boundary = self.messages.get(first_recent_turn_ix.checked_sub(1)?)?.id;
+        if self.is_message_compacted(boundary) {
+            return None;
+        }
+        Some(boundary)
+    }
 
-        self.detailed_summary_state = DetailedSummaryState::Generating {
-            message_id: last_message_id,
+    /// Returns the last message whose tool results can be truncated, keeping
+    /// the most recent tool results intact.
+    fn tool_results_boundary(&self) -> Option<MessageId> {
+        let boundary = self
+            .messages
+            .iter()
+            .rev()
+            .filter(|message| self.tool_use.message_has_tool_results(message.id))
+            .nth(COMPACTION_RECENT_TOOL_RESULTS)?
+            .id;
+        if self.are_tool_results_truncated(boundary) {
+            return None;
+        }
+        Some(boundary)
+    }
+
+    /// Replaces older messages with a summary if the last request used more
+    /// than the configured share of the model's context window.
+    fn compact_if_needed(&mut self, model: &Arc<dyn LanguageModel>, cx: &mut Context<Self>) {
+        let settings = AssistantSettings::get_global(cx);
+        if !settings.auto_compact || self.pending_compaction.is_some() {
+            return;
+        }
+
+        let used_tokens = self.request_token_usage.total_tokens() as f32;
+        if used_tokens < model.max_token_count() as f32 * settings.auto_compact_threshold {
+            return;
+        }
+
+        self.compact(cx);
+    }
+
+    /// Compacts the thread after a request exceeded the model's context window,
+    /// sending it to the model again once that's done. Returns `false` if the
+    /// thread can't be compacted, or already was for this request.
+    fn compact_and_retry(&mut self, model: Arc<dyn LanguageModel>, cx: &mut Context<Self>) -> bool {
+        if !AssistantSettings::get_global(cx).auto_compact
+            || self.retried_after_compaction
+            || self.pending_compaction.is_some()
+        {
+            return false;
+        }
+
+        self.retry_after_compaction = Some(model);
+        if self.compact(cx) {
+            true
+        } else {
+            self.retry_after_compaction = None;
+            false
+        }
+    }
+
+    /// Replaces the messages before the most recent turns with a generated summary
+    /// in future requests, and truncates the older tool results in the turns
+    /// that are kept. Returns `false` if there was nothing to compact.
+    pub fn compact(&mut self, cx: &mut Context<Self>) -> bool {
+        let tool_results_boundary = self.tool_results_boundary();
+        if tool_results_boundary.is_some() {
+            self.truncated_tool_results = tool_results_boundary;
+        }
+
+        let summary = self
+            .compaction_boundary()
+            .and_then(|boundary| Some((boundary, self.stream_detailed_summary(boundary, cx)?)));
+        let Some((boundary, summary)) = summary else {
+            if tool_results_boundary.is_none() {
+                return false;
+            }
+            self.finish_compaction(cx);
+            return true;
         };
 
-        Some(task)
+        self.pending_compaction = Some(cx.spawn(async move |thread, cx| {
+            let summary = summary.await.log_err();
+            thread
+                .update(cx, |thread, cx| {
+                    thread.pending_compaction = None;
+                    if let Some(summary) = summary.filter(|summary| !summary.is_empty()) {
+                        thread.compaction = Some(ThreadCompaction {
+                            message_id: boundary,
+                            summary: summary.into(),
+                        });
+                    }
+                    thread.finish_compaction(cx);
+                })
+                .ok();
+        }));
+        cx.notify();
+        true
+    }
+
+    fn finish_compaction(&mut self, cx: &mut Context<Self>) {
+        cx.emit(ThreadEvent::Compacted);
+        if let Some(model) = self.retry_after_compaction.take() {
+            self.retried_after_compaction = true;
+            self.send_to_model(model, RequestKind::Chat, cx);
+        }
+        cx.notify();
     }
 
     pub fn is_generating_detailed_summary(&self) -> bool {
@@ -1321,26 +1708,38 @@ impl Thread {
 
         for tool_use in pending_tool_uses.iter() {
             if let Some(tool) = self.tools.tool(&tool_use.name, cx) {
-                if tool.needs_confirmation(&tool_use.input, cx)
-                    && !AssistantSettings::get_global(cx).always_allow_tool_actions
-                {
-                    self.tool_use.confirm_tool_use(
-                        tool_use.id.clone(),
-                        tool_use.ui_text.clone(),
-                        tool_use.input.clone(),
-                        messages.clone(),
-                        tool,
-                    );
-                    cx.emit(ThreadEvent::ToolConfirmationNeeded);
-                } else {
-                    self.run_tool(
-                        tool_use.id.clone(),
-                        tool_use.ui_text.clone(),
-                        tool_use.input.clone(),
-                        &messages,
-                        tool,
-                        cx,
-                    );
+                match self.tool_use.tool_permission(&tool, &tool_use.input, cx) {
+                    ToolPermissionDecision::Allow => {
+                        self.run_tool(
+                            tool_use.id.clone(),
+                            tool_use.ui_text.clone(),
+                            tool_use.input.clone(),
+                            &messages,
+                            tool,
+                            cx,
+                        );
+                    }
+                    ToolPermissionDecision::Ask => {
+                        self.tool_use.confirm_tool_use(
+                            tool_use.id.clone(),
+                            tool_use.ui_text.clone(),
+                            tool_use.input.clone(),
+                            messages.clone(),
+                            tool,
+                        );
+                        cx.emit(ThreadEvent::ToolConfirmationNeeded);
+                    }
+                    ToolPermissionDecision::Deny => {
+                        let pending_tool_use = self.tool_use.insert_tool_output(
+                            tool_use.id.clone(),
+                            tool_use.name.clone(),
+                            Err(anyhow!(
+                                "Permission to run tool action denied by the user's tool permission rules"
+                            )),
+                            cx,
+                        );
+                        self.tool_finished(tool_use.id.clone(), pending_tool_use, false, cx);
+                    }
                 }
             }
         }
@@ -1443,7 +1842,9 @@ impl Thread {
     ///
     /// Returns whether a completion was canceled.
     pub fn cancel_last_completion(&mut self, cx: &mut Context<Self>) -> bool {
-        let canceled = if self.pending_completions.pop().is_some() {
+        let canceled = if self.retry_after_compaction.take().is_some() {
+            true
+        } else if self.pending_completions.pop().is_some() {
             true
         } else {
             let mut canceled = false;
@@ -1877,6 +2278,42 @@ impl Thread {
     }
 }
 
+/// Whether a completion failed because the request didn't fit in the model's
+/// context window. Providers only report this in their error messages.
+fn is_context_window_exceeded(error: &anyhow::Error) -> bool {
+    const MESSAGES: &[&str] = &[
+        "prompt is too long",
+        "input is too long",
+        "context_length_exceeded",
+        "maximum context length",
+        "exceeds the context window",
+        "exceeds the maximum number of tokens",
+    ];
+    error.chain().any(|error| {
+        let error = error.to_string().to_lowercase();
+        MESSAGES.iter().any(|message| error.contains(message))
+    })
+}
+
+/// Truncates the tool results attached to a request message to their first
+/// [`COMPACTED_TOOL_RESULT_BYTES`].
+fn truncate_tool_results(request_message: &mut LanguageModelRequestMessage) {
+    for content in &mut request_message.content {
+        if let MessageContent::ToolResult(tool_result) = content {
+            if tool_result.content.len() > COMPACTED_TOOL_RESULT_BYTES {
+                let truncated =
+                    truncate_lines_to_byte_limit(&tool_result.content, COMPACTED_TOOL_RESULT_BYTES);
+                tool_result.content = format!(
+                    "Tool result truncated to save space. The first {} bytes:\n\n{}",
+                    truncated.len(),
+                    truncated
+                )
+                .into();
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Error)]
 pub enum ThreadError {
     #[error("Payment required")]
@@ -1913,6 +2350,8 @@ pub enum ThreadEvent {
     },
     CheckpointChanged,
     ToolConfirmationNeeded,
+    /// Older messages were replaced by a summary.
+    Compacted,
 }
 
 impl EventEmitter<ThreadEvent> for Thread {}
@@ -2256,6 +2695,306 @@ fn main() {{
         );
     }
 
+    #[gpui::test]
+    async fn test_fork_thread(cx: &mut TestAppContext) {
+        init_test_settings(cx);
+
+        let project = create_test_project(
+            cx,
+            json!({
+                "file1.rs": "fn function1() {}\n",
+                "file2.rs": "fn function2() {}\n",
+            }),
+        )
+        .await;
+
+        let (_, _thread_store, thread, context_store) =
+            setup_test_environment(cx, project.clone()).await;
+
+        add_file_to_context(&project, &context_store, "test/file1.rs", cx)
+            .await
+            .unwrap();
+        add_file_to_context(&project, &context_store, "test/file2.rs", cx)
+            .await
+            .unwrap();
+        let contexts = context_store.update(cx, |store, _| store.context().clone());
+
+        let message1_id = thread.update(cx, |thread, cx| {
+            thread.insert_user_message("Message 1", vec![contexts[0].clone()], None, cx)
+        });
+        let message2_id = thread.update(cx, |thread, cx| {
+            thread.insert_message(
+                Role::Assistant,
+                vec![MessageSegment::Text("Reply 1".into())],
+                cx,
+            )
+        });
+        thread.update(cx, |thread, cx| {
+            thread.insert_user_message("Message 2", vec![contexts[1].clone()], None, cx)
+        });
+
+        let forked_thread = cx.new(|cx| Thread::fork(&thread, message2_id, cx));
+        forked_thread.read_with(cx, |forked_thread, cx| {
+            let parent = forked_thread.parent().unwrap();
+            assert_eq!(&parent.thread_id, thread.read(cx).id());
+            assert_eq!(parent.message_id, message2_id);
+            assert_ne!(forked_thread.id(), thread.read(cx).id());
+
+            assert_eq!(
+                forked_thread
+                    .messages()
+                    .map(|message| message.id)
+                    .collect::<Vec<_>>(),
+                vec![message1_id, message2_id]
+            );
+            assert_eq!(forked_thread.context_for_message(message1_id).count(), 1);
+            assert_eq!(forked_thread.context.len(), 1);
+        });
+
+        // Continuing the fork leaves the original thread untouched.
+        forked_thread.update(cx, |thread, cx| {
+            thread.insert_user_message("Alternative message 2", vec![], None, cx)
+        });
+        assert_eq!(
+            thread.read_with(cx, |thread, _| thread.messages().count()),
+            3
+        );
+        assert_eq!(
+            forked_thread.read_with(cx, |thread, _| thread.messages().count()),
+            3
+        );
+        assert_eq!(
+            forked_thread.read_with(cx, |thread, _| thread
+                .messages()
+                .last()
+                .unwrap()
+                .to_string()),
+            "Alternative message 2"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_fork_thread_with_tool_uses(cx: &mut TestAppContext) {
+        init_test_settings(cx);
+        cx.update(LanguageModelRegistry::test);
+
+        let project = create_test_project(cx, json!({})).await;
+        let (_, _thread_store, thread, _context_store) =
+            setup_test_environment(cx, project.clone()).await;
+
+        let tool_use = |id: &str| language_model::LanguageModelToolUse {
+            id: id.into(),
+            name: "list_directory".into(),
+            input: json!({}),
+        };
+        let (tool_call_id, tool_results_id, pending_call_id) = thread.update(cx, |thread, cx| {
+            thread.insert_user_message("List the files", vec![], None, cx);
+            let tool_call_id = thread.insert_message(
+                Role::Assistant,
+                vec![MessageSegment::Text("Listing".into())],
+                cx,
+            );
+            thread
+                .tool_use
+                .request_tool_use(tool_call_id, tool_use("tool-1"), cx);
+            thread.tool_use.insert_tool_output(
+                "tool-1".into(),
+                "list_directory".into(),
+                Ok("file.rs".into()),
+                cx,
+            );
+            let tool_results_id = thread.insert_message(Role::User, vec![], cx);
+            let pending_call_id = thread.insert_message(
+                Role::Assistant,
+                vec![MessageSegment::Text("Listing again".into())],
+                cx,
+            );
+            thread
+                .tool_use
+                .request_tool_use(pending_call_id, tool_use("tool-2"), cx);
+            thread.cumulative_token_usage = TokenUsage {
+                input_tokens: 100,
+                output_tokens: 10,
+                ..Default::default()
+            };
+            (tool_call_id, tool_results_id, pending_call_id)
+        });
+
+        // Forking at a message that used tools keeps the message with their results.
+        let forked_thread = cx.new(|cx| Thread::fork(&thread, tool_call_id, cx));
+        forked_thread.read_with(cx, |forked_thread, _| {
+            assert_eq!(
+                forked_thread
+                    .messages()
+                    .map(|message| message.id)
+                    .collect::<Vec<_>>(),
+                vec![MessageId(0), tool_call_id, tool_results_id]
+            );
+            assert!(forked_thread.message_has_tool_results(tool_results_id));
+            assert_eq!(forked_thread.cumulative_token_usage().total_tokens(), 0);
+        });
+
+        // Tool uses that haven't got a result yet are left out.
+        let forked_thread = cx.new(|cx| Thread::fork(&thread, pending_call_id, cx));
+        forked_thread.read_with(cx, |forked_thread, cx| {
+            assert_eq!(forked_thread.messages().count(), 4);
+            assert_eq!(
+                forked_thread.tool_uses_for_message(tool_call_id, cx).len(),
+                1
+            );
+            assert!(
+                forked_thread
+                    .tool_uses_for_message(pending_call_id, cx)
+                    .is_empty()
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_compacted_request(cx: &mut TestAppContext) {
+        init_test_settings(cx);
+
+        let project = create_test_project(cx, json!({"code.rs": "fn main() {}"})).await;
+        let (_, _thread_store, thread, _context_store) =
+            setup_test_environment(cx, project.clone()).await;
+
+        let compacted_message_id = thread.update(cx, |thread, cx| {
+            thread.insert_user_message("Message 1", vec![], None, cx);
+            let reply_id = thread.insert_message(
+                Role::Assistant,
+                vec![MessageSegment::Text("Reply 1".into())],
+                cx,
+            );
+            thread.insert_user_message("Message 2", vec![], None, cx);
+            thread.insert_message(
+                Role::Assistant,
+                vec![MessageSegment::Text("Reply 2".into())],
+                cx,
+            );
+            thread.insert_user_message("Message 3", vec![], None, cx);
+            reply_id
+        });
+
+        // Only the turns before the last two are compacted.
+        let boundary = thread.read_with(cx, |thread, _| thread.compaction_boundary());
+        assert_eq!(boundary, Some(compacted_message_id));
+
+        thread.update(cx, |thread, _| {
+            thread.compaction = Some(ThreadCompaction {
+                message_id: compacted_message_id,
+                summary: "Summary of message 1".into(),
+            });
+        });
+
+        let request = thread.read_with(cx, |thread, cx| {
+            thread.to_completion_request(RequestKind::Chat, cx)
+        });
+        let contents = request
+            .messages
+            .iter()
+            .skip(1)
+            .map(|message| message.string_contents())
+            .collect::<Vec<_>>();
+        assert_eq!(contents.len(), 4);
+        assert!(contents[0].ends_with("Summary of message 1"));
+        assert_eq!(&contents[1..], &["Message 2", "Reply 2", "Message 3"]);
+
+        // The original messages are kept in the thread.
+        assert_eq!(
+            thread.read_with(cx, |thread, _| thread.messages().count()),
+            5
+        );
+        assert_eq!(
+            thread.read_with(cx, |thread, _| thread.compaction_boundary()),
+            None
+        );
+    }
+
+    #[gpui::test]
+    async fn test_compaction_truncates_old_tool_results(cx: &mut TestAppContext) {
+        init_test_settings(cx);
+        cx.update(LanguageModelRegistry::test);
+
+        let project = create_test_project(cx, json!({})).await;
+        let (_, _thread_store, thread, _context_store) =
+            setup_test_environment(cx, project.clone()).await;
+
+        let long_output = "line\n".repeat(COMPACTED_TOOL_RESULT_BYTES);
+        let tool_results_ids = thread.update(cx, |thread, cx| {
+            thread.insert_user_message("Read the files", vec![], None, cx);
+            (0..6)
+                .map(|ix| {
+                    let tool_call_id = thread.insert_message(
+                        Role::Assistant,
+                        vec![MessageSegment::Text("Reading".into())],
+                        cx,
+                    );
+                    let id = format!("tool-{ix}");
+                    thread.tool_use.request_tool_use(
+                        tool_call_id,
+                        language_model::LanguageModelToolUse {
+                            id: id.clone().into(),
+                            name: "read_file".into(),
+                            input: json!({}),
+                        },
+                        cx,
+                    );
+                    thread.tool_use.insert_tool_output(
+                        id.into(),
+                        "read_file".into(),
+                        Ok(long_output.clone()),
+                        cx,
+                    );
+                    thread.insert_message(Role::User, vec![], cx)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // The single turn can't be summarized, but its older tool results are truncated.
+        let compacted = thread.update(cx, |thread, cx| thread.compact(cx));
+        assert!(compacted);
+
+        let request = thread.read_with(cx, |thread, cx| {
+            thread.to_completion_request(RequestKind::Chat, cx)
+        });
+        let tool_results = request
+            .messages
+            .iter()
+            .flat_map(|message| &message.content)
+            .filter_map(|content| match content {
+                MessageContent::ToolResult(tool_result) => Some(tool_result.content.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(tool_results.len(), 6);
+        for tool_result in &tool_results[..2] {
+            assert!(tool_result.starts_with("Tool result truncated"));
+            assert!(tool_result.len() < COMPACTED_TOOL_RESULT_BYTES + 100);
+        }
+        for tool_result in &tool_results[2..] {
+            assert_eq!(tool_result.as_ref(), long_output);
+        }
+
+        // Nothing is left to compact until more tools are used.
+        assert_eq!(
+            thread.read_with(cx, |thread, _| thread.truncated_tool_results),
+            Some(tool_results_ids[1])
+        );
+        assert!(!thread.update(cx, |thread, cx| thread.compact(cx)));
+    }
+
+    #[test]
+    fn test_is_context_window_exceeded() {
+        assert!(is_context_window_exceeded(&anyhow!(
+            "prompt is too long: 212345 tokens > 200000 maximum"
+        )));
+        assert!(is_context_window_exceeded(
+            &anyhow!("This model's maximum context length is 128000 tokens")
+                .context("Failed to connect to API")
+        ));
+        assert!(!is_context_window_exceeded(&anyhow!("Overloaded")));
+    }
+
     fn init_test_settings(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings_store = SettingsStore::test(cx);
@@ -431,6 +431,26 @@ impl RenderOnce for PastThread {
             .end_slot(
                 h_flex()
                     .gap_1p5()
+                    .when_some(self.thread.parent.clone(), |this, parent| {
+                        this.child(
+                            IconButton::new("open-parent-thread", IconName::GitBranch)
+                                .shape(IconButtonShape::Square)
+                                .icon_size(IconSize::XSmall)
+                                .icon_color(Color::Muted)
+                                .tooltip(Tooltip::text(format!("Forked from {}", parent.summary)))
+                                .on_click({
+                                    let assistant_panel = self.assistant_panel.clone();
+                                    move |_event, window, cx| {
+                                        assistant_panel
+                                            .update(cx, |this, cx| {
+                                                this.open_thread(&parent.thread_id, window, cx)
+                                                    .detach_and_log_err(cx);
+                                            })
+                                            .ok();
+                                    }
+                                }),
+                        )
+                    })
                     .child(
                         Label::new(thread_timestamp)
                             .color(Color::Muted)
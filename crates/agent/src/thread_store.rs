@@ -27,7 +27,10 @@ use serde::{Deserialize, Serialize};
 use settings::{Settings as _, SettingsStore};
 use util::ResultExt as _;
 
-use crate::thread::{DetailedSummaryState, MessageId, ProjectSnapshot, Thread, ThreadId};
+use crate::thread::{
+    DetailedSummaryState, MessageId, ProjectSnapshot, Thread, ThreadCompaction, ThreadId,
+    ThreadParent,
+};
 
 const RULES_FILE_NAMES: [&'static str; 6] = [
     ".rules",
@@ -309,6 +312,25 @@ impl ThreadStore {
         })
     }
 
+    /// Forks `thread` at the given message into a new, saved thread.
+    pub fn fork_thread(
+        &mut self,
+        thread: &Entity<Thread>,
+        message_id: MessageId,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Entity<Thread>>> {
+        if thread.read(cx).message(message_id).is_none() {
+            return Task::ready(Err(anyhow!("no message found with ID: {message_id:?}")));
+        }
+
+        let forked_thread = cx.new(|cx| Thread::fork(thread, message_id, cx));
+        let save_task = self.save_thread(&forked_thread, cx);
+        cx.foreground_executor().spawn(async move {
+            save_task.await?;
+            Ok(forked_thread)
+        })
+    }
+
     pub fn save_thread(&self, thread: &Entity<Thread>, cx: &mut Context<Self>) -> Task<Result<()>> {
         let (metadata, serialized_thread) =
             thread.update(cx, |thread, cx| (thread.id().clone(), thread.serialize(cx)));
@@ -468,6 +490,8 @@ impl ThreadStore {
                     self.load_default_profile(cx);
                 }
             }
+            context_server::manager::Event::SamplingRequestsChanged
+            | context_server::manager::Event::ResourceUpdated { .. } => {}
         }
     }
 }
@@ -477,6 +501,8 @@ pub struct SerializedThreadMetadata {
     pub id: ThreadId,
     pub summary: SharedString,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub parent: Option<ThreadParent>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -491,6 +517,12 @@ pub struct SerializedThread {
     pub cumulative_token_usage: TokenUsage,
     #[serde(default)]
     pub detailed_summary_state: DetailedSummaryState,
+    #[serde(default)]
+    pub parent: Option<ThreadParent>,
+    #[serde(default)]
+    pub compaction: Option<ThreadCompaction>,
+    #[serde(default)]
+    pub truncated_tool_results: Option<MessageId>,
 }
 
 impl SerializedThread {
@@ -577,6 +609,9 @@ impl LegacySerializedThread {
             initial_project_snapshot: self.initial_project_snapshot,
             cumulative_token_usage: TokenUsage::default(),
             detailed_summary_state: DetailedSummaryState::default(),
+            parent: None,
+            compaction: None,
+            truncated_tool_results: None,
         }
     }
 }
@@ -692,6 +727,7 @@ impl ThreadsDatabase {
                     id: key,
                     summary: value.summary,
                     updated_at: value.updated_at,
+                    parent: value.parent,
                 });
             }
 
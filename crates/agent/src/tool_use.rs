@@ -1,8 +1,9 @@
 use std::sync::Arc;
 
 use anyhow::Result;
+use assistant_settings::{AssistantSettings, ToolPermissionDecision};
 use assistant_tool::{Tool, ToolWorkingSet};
-use collections::HashMap;
+use collections::{HashMap, HashSet};
 use futures::FutureExt as _;
 use futures::future::Shared;
 use gpui::{App, SharedString, Task};
@@ -10,6 +11,7 @@ use language_model::{
     LanguageModelRegistry, LanguageModelRequestMessage, LanguageModelToolResult,
     LanguageModelToolUse, LanguageModelToolUseId, MessageContent, Role,
 };
+use settings::Settings as _;
 use ui::IconName;
 use util::truncate_lines_to_byte_limit;
 
@@ -25,6 +27,8 @@ pub struct ToolUse {
     pub input: serde_json::Value,
     pub icon: ui::IconName,
     pub needs_confirmation: bool,
+    pub permission_subject: Option<String>,
+    pub permission_subject_parts: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -143,6 +147,59 @@ impl ToolUseState {
         this
     }
 
+    /// Returns a copy of the tool uses and results belonging to the given messages.
+    ///
+    /// Only the tool uses whose results are attached to one of the messages are
+    /// kept, since models reject tool uses that aren't followed by their result.
+    pub fn fork(&self, message_ids: &HashSet<MessageId>) -> Self {
+        let tool_uses_by_user_message = self
+            .tool_uses_by_user_message
+            .iter()
+            .filter(|(message_id, _)| message_ids.contains(message_id))
+            .map(|(message_id, tool_use_ids)| {
+                let tool_use_ids = tool_use_ids
+                    .iter()
+                    .filter(|tool_use_id| self.tool_results.contains_key(tool_use_id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (*message_id, tool_use_ids)
+            })
+            .filter(|(_, tool_use_ids)| !tool_use_ids.is_empty())
+            .collect::<HashMap<_, _>>();
+        let answered_tool_use_ids = tool_uses_by_user_message
+            .values()
+            .flatten()
+            .collect::<HashSet<_>>();
+        let tool_uses_by_assistant_message = self
+            .tool_uses_by_assistant_message
+            .iter()
+            .filter(|(message_id, _)| message_ids.contains(message_id))
+            .map(|(message_id, tool_uses)| {
+                let tool_uses = tool_uses
+                    .iter()
+                    .filter(|tool_use| answered_tool_use_ids.contains(&tool_use.id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (*message_id, tool_uses)
+            })
+            .filter(|(_, tool_uses)| !tool_uses.is_empty())
+            .collect();
+        let tool_results = self
+            .tool_results
+            .iter()
+            .filter(|(tool_use_id, _)| answered_tool_use_ids.contains(tool_use_id))
+            .map(|(tool_use_id, result)| (tool_use_id.clone(), result.clone()))
+            .collect();
+
+        Self {
+            tools: self.tools.clone(),
+            tool_uses_by_assistant_message,
+            tool_uses_by_user_message,
+            tool_results,
+            pending_tool_uses_by_id: HashMap::default(),
+        }
+    }
+
     pub fn cancel_pending(&mut self) -> Vec<PendingToolUse> {
         let mut pending_tools = Vec::new();
         for (tool_use_id, tool_use) in self.pending_tool_uses_by_id.drain() {
@@ -199,12 +256,17 @@ impl ToolUseState {
                 }
             })();
 
-            let (icon, needs_confirmation) = if let Some(tool) = self.tools.tool(&tool_use.name, cx)
-            {
-                (tool.icon(), tool.needs_confirmation(&tool_use.input, cx))
-            } else {
-                (IconName::Cog, false)
-            };
+            let (icon, needs_confirmation, permission_subject, permission_subject_parts) =
+                if let Some(tool) = self.tools.tool(&tool_use.name, cx) {
+                    (
+                        tool.icon(),
+                        tool.needs_confirmation(&tool_use.input, cx),
+                        tool.permission_subject(&tool_use.input),
+                        tool.permission_subject_parts(&tool_use.input),
+                    )
+                } else {
+                    (IconName::Cog, false, None, Vec::new())
+                };
 
             tool_uses.push(ToolUse {
                 id: tool_use.id.clone(),
@@ -214,12 +276,51 @@ impl ToolUseState {
                 status,
                 icon,
                 needs_confirmation,
+                permission_subject,
+                permission_subject_parts,
             })
         }
 
         tool_uses
     }
 
+    /// Decides whether a tool use can run without asking, based on the user's
+    /// tool permission rules and, when no rule matches, on whether the tool
+    /// itself needs confirmation.
+    pub fn tool_permission(
+        &self,
+        tool: &Arc<dyn Tool>,
+        input: &serde_json::Value,
+        cx: &App,
+    ) -> ToolPermissionDecision {
+        let settings = AssistantSettings::get_global(cx);
+        let subject_parts = tool.permission_subject_parts(input);
+        if let Some(decision) = settings
+            .tool_permissions
+            .decision_for_parts(&tool.name(), &subject_parts)
+        {
+            return decision;
+        }
+
+        let nested_tool_uses = tool.nested_tool_uses(input);
+        if !nested_tool_uses.is_empty() {
+            return nested_tool_uses
+                .into_iter()
+                .map(|(name, input)| match self.tools.tool(&name, cx) {
+                    Some(tool) => self.tool_permission(&tool, &input, cx),
+                    None => ToolPermissionDecision::Allow,
+                })
+                .max()
+                .unwrap_or(ToolPermissionDecision::Allow);
+        }
+
+        if tool.needs_confirmation(input, cx) && !settings.always_allow_tool_actions {
+            ToolPermissionDecision::Ask
+        } else {
+            ToolPermissionDecision::Allow
+        }
+    }
+
     pub fn tool_ui_label(
         &self,
         tool_name: &str,
@@ -1,7 +1,9 @@
 mod agent_notification;
 mod context_pill;
+mod sampling_request_notification;
 mod user_spending;
 
 pub use agent_notification::*;
 pub use context_pill::*;
+pub use sampling_request_notification::*;
 // pub use user_spending::*;
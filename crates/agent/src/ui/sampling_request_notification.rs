@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use context_server::ContextServerSettings;
+use context_server::manager::ContextServerManager;
+use context_server::sampling::SamplingRequestId;
+use fs::Fs;
+use gpui::{DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, FontWeight, Subscription};
+use settings::update_settings_file;
+use ui::prelude::*;
+use workspace::notifications::Notification;
+
+/// Asks the user whether a context server may use their language model.
+///
+/// The request is denied if the notification goes away without an answer.
+pub struct SamplingRequestNotification {
+    request_id: SamplingRequestId,
+    server_id: Arc<str>,
+    prompt: SharedString,
+    context_server_manager: Entity<ContextServerManager>,
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    _release_subscription: Subscription,
+}
+
+impl SamplingRequestNotification {
+    pub fn new(
+        request_id: SamplingRequestId,
+        server_id: Arc<str>,
+        prompt: impl Into<SharedString>,
+        context_server_manager: Entity<ContextServerManager>,
+        fs: Arc<dyn Fs>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let release_subscription = cx.on_release(|this, cx| {
+            this.context_server_manager.update(cx, |manager, cx| {
+                manager.respond_to_sampling_request(this.request_id, false, cx);
+            });
+        });
+
+        Self {
+            request_id,
+            server_id,
+            prompt: prompt.into(),
+            context_server_manager,
+            fs,
+            focus_handle: cx.focus_handle(),
+            _release_subscription: release_subscription,
+        }
+    }
+
+    fn respond(&mut self, approved: bool, cx: &mut Context<Self>) {
+        self.context_server_manager.update(cx, |manager, cx| {
+            manager.respond_to_sampling_request(self.request_id, approved, cx);
+        });
+        cx.emit(DismissEvent);
+    }
+
+    fn always_allow(&mut self, cx: &mut Context<Self>) {
+        let server_id = self.server_id.clone();
+        update_settings_file::<ContextServerSettings>(self.fs.clone(), cx, move |settings, _| {
+            settings
+                .context_servers
+                .entry(server_id)
+                .or_default()
+                .auto_approve_sampling = true;
+        });
+        self.respond(true, cx);
+    }
+}
+
+impl EventEmitter<DismissEvent> for SamplingRequestNotification {}
+
+impl Focusable for SamplingRequestNotification {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Notification for SamplingRequestNotification {}
+
+impl Render for SamplingRequestNotification {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .occlude()
+            .p_3()
+            .gap_2()
+            .elevation_3(cx)
+            .child(
+                h_flex()
+                    .gap_4()
+                    .justify_between()
+                    .items_start()
+                    .child(
+                        h_flex()
+                            .gap_1p5()
+                            .items_center()
+                            .child(Icon::new(IconName::Context).color(Color::Muted))
+                            .child(
+                                Label::new(format!(
+                                    "{} wants to use the language model",
+                                    self.server_id
+                                ))
+                                .weight(FontWeight::MEDIUM),
+                            ),
+                    )
+                    .child(
+                        IconButton::new("close", IconName::Close)
+                            .on_click(cx.listener(|this, _, _, cx| this.respond(false, cx))),
+                    ),
+            )
+            .child(
+                div()
+                    .id("sampling-prompt")
+                    .max_w_96()
+                    .max_h_32()
+                    .overflow_y_scroll()
+                    .child(
+                        Label::new(self.prompt.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .child(
+                h_flex()
+                    .justify_end()
+                    .gap_1()
+                    .child(
+                        Button::new("deny-sampling", "Deny")
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _, _, cx| this.respond(false, cx))),
+                    )
+                    .child(
+                        Button::new("always-allow-sampling", "Always Allow")
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _, _, cx| this.always_allow(cx))),
+                    )
+                    .child(
+                        Button::new("allow-sampling", "Allow")
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _, _, cx| this.respond(true, cx))),
+                    ),
+            )
+    }
+}
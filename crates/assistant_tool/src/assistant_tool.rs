@@ -50,6 +50,25 @@ pub trait Tool: 'static + Send + Sync {
     /// before having permission to run.
     fn needs_confirmation(&self, input: &serde_json::Value, cx: &App) -> bool;
 
+    /// Returns the part of the input that tool permission rules are matched
+    /// against, such as a command, a path, or a domain.
+    fn permission_subject(&self, _input: &serde_json::Value) -> Option<String> {
+        None
+    }
+
+    /// Returns the parts of the input that are each matched against the tool
+    /// permission rules, such as the commands chained in a shell command or
+    /// both paths of a copy. The strictest decision among them applies.
+    fn permission_subject_parts(&self, input: &serde_json::Value) -> Vec<String> {
+        self.permission_subject(input).into_iter().collect()
+    }
+
+    /// Returns the names and inputs of the tools this tool runs on the
+    /// model's behalf, such as the invocations of a batch.
+    fn nested_tool_uses(&self, _input: &serde_json::Value) -> Vec<(String, serde_json::Value)> {
+        Vec::new()
+    }
+
     /// Returns the JSON schema that describes the tool's input.
     fn input_schema(&self, _: LanguageModelToolSchemaFormat) -> serde_json::Value {
         serde_json::Value::Object(serde_json::Map::default())
@@ -164,6 +164,18 @@ impl Tool for BatchTool {
             .unwrap_or(false)
     }
 
+    fn nested_tool_uses(&self, input: &serde_json::Value) -> Vec<(String, serde_json::Value)> {
+        serde_json::from_value::<BatchToolInput>(input.clone())
+            .map(|input| {
+                input
+                    .invocations
+                    .into_iter()
+                    .map(|invocation| (invocation.name, invocation.input))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     fn description(&self) -> String {
         include_str!("./batch_tool/description.md").into()
     }
@@ -47,6 +47,18 @@ impl Tool for CopyPathTool {
         true
     }
 
+    fn permission_subject(&self, input: &serde_json::Value) -> Option<String> {
+        serde_json::from_value::<CopyPathToolInput>(input.clone())
+            .ok()
+            .map(|input| format!("{} to {}", input.source_path, input.destination_path))
+    }
+
+    fn permission_subject_parts(&self, input: &serde_json::Value) -> Vec<String> {
+        serde_json::from_value::<CopyPathToolInput>(input.clone())
+            .map(|input| vec![input.source_path, input.destination_path])
+            .unwrap_or_default()
+    }
+
     fn description(&self) -> String {
         include_str!("./copy_path_tool/description.md").into()
     }
@@ -44,6 +44,12 @@ impl Tool for CreateFileTool {
         false
     }
 
+    fn permission_subject(&self, input: &serde_json::Value) -> Option<String> {
+        serde_json::from_value::<CreateFileToolInput>(input.clone())
+            .ok()
+            .map(|input| input.path)
+    }
+
     fn description(&self) -> String {
         include_str!("./create_file_tool/description.md").into()
     }
@@ -37,6 +37,12 @@ impl Tool for DeletePathTool {
         true
     }
 
+    fn permission_subject(&self, input: &serde_json::Value) -> Option<String> {
+        serde_json::from_value::<DeletePathToolInput>(input.clone())
+            .ok()
+            .map(|input| input.path)
+    }
+
     fn description(&self) -> String {
         include_str!("./delete_path_tool/description.md").into()
     }
@@ -8,7 +8,7 @@ use assistant_tool::{ActionLog, Tool};
 use futures::AsyncReadExt as _;
 use gpui::{App, AppContext as _, Entity, Task};
 use html_to_markdown::{TagHandler, convert_html_to_markdown, markdown};
-use http_client::{AsyncBody, HttpClientWithUrl};
+use http_client::{AsyncBody, HttpClientWithUrl, Url};
 use language_model::{LanguageModelRequestMessage, LanguageModelToolSchemaFormat};
 use project::Project;
 use schemars::JsonSchema;
@@ -120,6 +120,16 @@ impl Tool for FetchTool {
         true
     }
 
+    fn permission_subject(&self, input: &serde_json::Value) -> Option<String> {
+        let input = serde_json::from_value::<FetchToolInput>(input.clone()).ok()?;
+        let url = if input.url.starts_with("https://") || input.url.starts_with("http://") {
+            Url::parse(&input.url)
+        } else {
+            Url::parse(&format!("https://{}", input.url))
+        };
+        Some(url.ok()?.host_str()?.to_string())
+    }
+
     fn description(&self) -> String {
         include_str!("./fetch_tool/description.md").to_string()
     }
@@ -143,6 +143,12 @@ impl Tool for FindReplaceFileTool {
         false
     }
 
+    fn permission_subject(&self, input: &serde_json::Value) -> Option<String> {
+        serde_json::from_value::<FindReplaceFileToolInput>(input.clone())
+            .ok()
+            .map(|input| input.path.to_string_lossy().into_owned())
+    }
+
     fn description(&self) -> String {
         include_str!("find_replace_tool/description.md").to_string()
     }
@@ -46,6 +46,18 @@ impl Tool for MovePathTool {
         true
     }
 
+    fn permission_subject(&self, input: &serde_json::Value) -> Option<String> {
+        serde_json::from_value::<MovePathToolInput>(input.clone())
+            .ok()
+            .map(|input| format!("{} to {}", input.source_path, input.destination_path))
+    }
+
+    fn permission_subject_parts(&self, input: &serde_json::Value) -> Vec<String> {
+        serde_json::from_value::<MovePathToolInput>(input.clone())
+            .map(|input| vec![input.source_path, input.destination_path])
+            .unwrap_or_default()
+    }
+
     fn description(&self) -> String {
         include_str!("./move_path_tool/description.md").into()
     }
@@ -130,3 +142,25 @@ impl Tool for MovePathTool {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_permission_subject_parts() {
+        let input = json!({
+            "source_path": "src/lib.rs",
+            "destination_path": "secrets/lib.rs",
+        });
+        assert_eq!(
+            MovePathTool.permission_subject_parts(&input),
+            ["src/lib.rs", "secrets/lib.rs"]
+        );
+        assert_eq!(
+            MovePathTool.permission_subject(&input).as_deref(),
+            Some("src/lib.rs to secrets/lib.rs")
+        );
+    }
+}
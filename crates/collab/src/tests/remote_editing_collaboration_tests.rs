@@ -88,6 +88,7 @@ async fn test_sharing_an_ssh_remote_project(
                 languages,
                 debug_adapters: Arc::new(DapRegistry::fake()),
                 extension_host_proxy: Arc::new(ExtensionHostProxy::new()),
+                port_source: None,
             },
             cx,
         )
@@ -256,6 +257,7 @@ async fn test_ssh_collaboration_git_branches(
                 languages,
                 debug_adapters: Arc::new(DapRegistry::fake()),
                 extension_host_proxy: Arc::new(ExtensionHostProxy::new()),
+                port_source: None,
             },
             cx,
         )
@@ -462,6 +464,7 @@ async fn test_ssh_collaboration_formatting_with_prettier(
                 languages,
                 debug_adapters: Arc::new(DapRegistry::fake()),
                 extension_host_proxy: Arc::new(ExtensionHostProxy::new()),
+                port_source: None,
             },
             cx,
         )
@@ -1,7 +1,9 @@
 use anyhow::{Context, Result, anyhow};
 use collections::HashMap;
-use futures::{FutureExt, StreamExt, channel::oneshot, select};
+use context_server_settings::{RemoteServerConfig, RemoteTransport};
+use futures::{Future, FutureExt, StreamExt, channel::oneshot, future::LocalBoxFuture, select};
 use gpui::{AppContext as _, AsyncApp, BackgroundExecutor, Task};
+use http_client::HttpClient;
 use parking_lot::Mutex;
 use postage::barrier;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
@@ -16,9 +18,11 @@ use std::{
     },
     time::{Duration, Instant},
 };
-use util::TryFutureExt;
+use util::{ResultExt as _, TryFutureExt};
 
-use crate::transport::{StdioTransport, Transport};
+use crate::transport::{
+    HttpTransportOptions, SseTransport, StdioTransport, StreamableHttpTransport, Transport,
+};
 
 const JSON_RPC_VERSION: &str = "2.0";
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
@@ -32,6 +36,8 @@ pub const INTERNAL_ERROR: i32 = -32603;
 
 type ResponseHandler = Box<dyn Send + FnOnce(Result<String, Error>)>;
 type NotificationHandler = Box<dyn Send + FnMut(Value, AsyncApp)>;
+type RequestHandler =
+    Box<dyn Send + FnMut(Value, AsyncApp) -> LocalBoxFuture<'static, Result<Value>>>;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -46,6 +52,7 @@ pub struct Client {
     outbound_tx: channel::Sender<String>,
     name: Arc<str>,
     notification_handlers: Arc<Mutex<HashMap<&'static str, NotificationHandler>>>,
+    request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
     response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
     #[allow(clippy::type_complexity)]
     #[allow(dead_code)]
@@ -106,6 +113,31 @@ enum CspResult<T> {
     Error(Option<Error>),
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct AnyRequest<'a> {
+    jsonrpc: &'a str,
+    id: RequestId,
+    method: String,
+    #[serde(default)]
+    params: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct OutgoingResponse {
+    jsonrpc: &'static str,
+    id: RequestId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<OutgoingError>,
+}
+
+#[derive(Serialize)]
+struct OutgoingError {
+    code: i32,
+    message: String,
+}
+
 #[derive(Serialize, Deserialize)]
 struct Notification<'a, T> {
     jsonrpc: &'static str,
@@ -158,23 +190,72 @@ impl Client {
             .unwrap_or_else(String::new);
 
         let transport = Arc::new(StdioTransport::new(binary, &cx)?);
+        Self::with_transport(server_id, server_name.into(), transport, cx)
+    }
 
+    /// Creates a new Client instance that talks to a remote context server over HTTP.
+    ///
+    /// The transport is chosen based on the remote configuration: either Streamable HTTP
+    /// or the legacy HTTP+SSE transport.
+    pub fn remote(
+        server_id: ContextServerId,
+        config: &RemoteServerConfig,
+        http_client: Arc<dyn HttpClient>,
+        cx: AsyncApp,
+    ) -> Result<Self> {
+        log::info!(
+            "connecting to context server (url={:?}, transport={:?})",
+            config.url,
+            config.transport
+        );
+
+        let options = HttpTransportOptions::from_settings(config)?;
+        let server_name = options
+            .url
+            .host_str()
+            .map(ToString::to_string)
+            .unwrap_or_else(|| config.url.clone());
+        let transport: Arc<dyn Transport> = match config.transport {
+            RemoteTransport::StreamableHttp => {
+                Arc::new(StreamableHttpTransport::new(http_client, options, &cx))
+            }
+            RemoteTransport::Sse => Arc::new(SseTransport::new(http_client, options, &cx)),
+        };
+        Self::with_transport(server_id, server_name.into(), transport, cx)
+    }
+
+    pub(crate) fn with_transport(
+        server_id: ContextServerId,
+        server_name: Arc<str>,
+        transport: Arc<dyn Transport>,
+        cx: AsyncApp,
+    ) -> Result<Self> {
         let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
         let (output_done_tx, output_done_rx) = barrier::channel();
 
         let notification_handlers =
             Arc::new(Mutex::new(HashMap::<_, NotificationHandler>::default()));
+        let request_handlers = Arc::new(Mutex::new(HashMap::<_, RequestHandler>::default()));
         let response_handlers =
             Arc::new(Mutex::new(Some(HashMap::<_, ResponseHandler>::default())));
 
         let stdout_input_task = cx.spawn({
             let notification_handlers = notification_handlers.clone();
+            let request_handlers = request_handlers.clone();
             let response_handlers = response_handlers.clone();
+            let outbound_tx = outbound_tx.clone();
             let transport = transport.clone();
             async move |cx| {
-                Self::handle_input(transport, notification_handlers, response_handlers, cx)
-                    .log_err()
-                    .await
+                Self::handle_input(
+                    transport,
+                    notification_handlers,
+                    request_handlers,
+                    response_handlers,
+                    outbound_tx,
+                    cx,
+                )
+                .log_err()
+                .await
             }
         });
         let stderr_input_task = cx.spawn({
@@ -200,8 +281,9 @@ impl Client {
         Ok(Self {
             server_id,
             notification_handlers,
+            request_handlers,
             response_handlers,
-            name: server_name.into(),
+            name: server_name,
             next_id: Default::default(),
             outbound_tx,
             executor: cx.background_executor().clone(),
@@ -214,19 +296,64 @@ impl Client {
     /// Handles input from the server's stdout.
     ///
     /// This function continuously reads lines from the provided stdout stream,
-    /// parses them as JSON-RPC responses or notifications, and dispatches them
-    /// to the appropriate handlers. It processes both responses (which are matched
-    /// to pending requests) and notifications (which trigger registered handlers).
+    /// parses them as JSON-RPC requests, responses or notifications, and dispatches them
+    /// to the appropriate handlers. Requests from the server are answered by the
+    /// registered request handlers, responses are matched to pending requests, and
+    /// notifications trigger registered handlers.
     async fn handle_input(
         transport: Arc<dyn Transport>,
         notification_handlers: Arc<Mutex<HashMap<&'static str, NotificationHandler>>>,
+        request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
         response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
+        outbound_tx: channel::Sender<String>,
         cx: &mut AsyncApp,
     ) -> anyhow::Result<()> {
         let mut receiver = transport.receive();
 
         while let Some(message) = receiver.next().await {
-            if let Ok(response) = serde_json::from_str::<AnyResponse>(&message) {
+            if let Ok(request) = serde_json::from_str::<AnyRequest>(&message) {
+                let response = request_handlers
+                    .lock()
+                    .get_mut(request.method.as_str())
+                    .map(|handler| handler(request.params.unwrap_or(Value::Null), cx.clone()));
+                let id = request.id;
+                let method = request.method;
+                let outbound_tx = outbound_tx.clone();
+                cx.spawn(async move |_| {
+                    let response = match response {
+                        Some(response) => match response.await {
+                            Ok(result) => OutgoingResponse {
+                                jsonrpc: JSON_RPC_VERSION,
+                                id,
+                                result: Some(result),
+                                error: None,
+                            },
+                            Err(error) => OutgoingResponse {
+                                jsonrpc: JSON_RPC_VERSION,
+                                id,
+                                result: None,
+                                error: Some(OutgoingError {
+                                    code: INTERNAL_ERROR,
+                                    message: error.to_string(),
+                                }),
+                            },
+                        },
+                        None => OutgoingResponse {
+                            jsonrpc: JSON_RPC_VERSION,
+                            id,
+                            result: None,
+                            error: Some(OutgoingError {
+                                code: METHOD_NOT_FOUND,
+                                message: format!("unhandled method {method}"),
+                            }),
+                        },
+                    };
+                    if let Some(response) = serde_json::to_string(&response).log_err() {
+                        outbound_tx.send(response).await.log_err();
+                    }
+                })
+                .detach();
+            } else if let Ok(response) = serde_json::from_str::<AnyResponse>(&message) {
                 if let Some(handlers) = response_handlers.lock().as_mut() {
                     if let Some(handler) = handlers.remove(&response.id) {
                         handler(Ok(message.to_string()));
@@ -367,6 +494,24 @@ impl Client {
             .insert(method, Box::new(f));
     }
 
+    /// Registers a handler for requests sent by the context server.
+    ///
+    /// The value returned by the handler is sent back as the result of the request.
+    pub fn on_request<F, Fut>(&self, method: &'static str, mut f: F)
+    where
+        F: 'static + Send + FnMut(Value, AsyncApp) -> Fut,
+        Fut: 'static + Future<Output = Result<Value>>,
+    {
+        self.request_handlers.lock().insert(
+            method,
+            Box::new(move |params, cx| f(params, cx).boxed_local()),
+        );
+    }
+
+    pub fn handles_request(&self, method: &str) -> bool {
+        self.request_handlers.lock().contains_key(method)
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
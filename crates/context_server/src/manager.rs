@@ -11,27 +11,34 @@
 //! - `ContextServerManager`: Manages multiple context servers
 //! - `GlobalContextServerManager`: Provides global access to the ContextServerManager
 //!
+//! The manager also answers requests made by the servers themselves: `roots/list` is
+//! answered with the project's worktree roots, and `sampling/createMessage` is routed
+//! to the model the server prefers, or the user's default one, once the user approves it.
+//!
 //! The module also includes initialization logic to set up the context server system
 //! and react to changes in settings.
 
 use std::path::Path;
 use std::sync::Arc;
 
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
 use collections::HashMap;
 use command_palette_hooks::CommandPaletteFilter;
-use gpui::{AsyncApp, Context, Entity, EventEmitter, Subscription, Task, WeakEntity};
+use futures::channel::oneshot;
+use gpui::{App, AsyncApp, Context, Entity, EventEmitter, Subscription, Task, WeakEntity};
 use log;
 use parking_lot::RwLock;
 use project::Project;
 use settings::{Settings, SettingsStore};
-use util::ResultExt as _;
+use url::Url;
+use util::{ResultExt as _, post_inc};
 
 use crate::{ContextServerSettings, ServerConfig};
 
 use crate::{
     CONTEXT_SERVERS_NAMESPACE, ContextServerFactoryRegistry,
     client::{self, Client},
+    sampling::{self, PendingSamplingRequest, SamplingRequestId},
     types,
 };
 
@@ -62,20 +69,38 @@ impl ContextServer {
         self.client.read().clone()
     }
 
-    pub async fn start(self: Arc<Self>, cx: &AsyncApp) -> Result<()> {
+    pub async fn start(
+        self: Arc<Self>,
+        manager: WeakEntity<ContextServerManager>,
+        cx: &AsyncApp,
+    ) -> Result<()> {
         log::info!("starting context server {}", self.id);
-        let Some(command) = &self.config.command else {
-            bail!("no command specified for server {}", self.id);
+        let server_id = client::ContextServerId(self.id.clone());
+        let client = if let Some(remote) = &self.config.remote {
+            let http_client = cx.update(|cx| cx.http_client())?;
+            Client::remote(server_id, remote, http_client, cx.clone())?
+        } else if let Some(command) = &self.config.command {
+            Client::new(
+                server_id,
+                client::ModelContextServerBinary {
+                    executable: Path::new(&command.path).to_path_buf(),
+                    args: command.args.clone(),
+                    env: command.env.clone(),
+                },
+                cx.clone(),
+            )?
+        } else {
+            bail!("no command or remote specified for server {}", self.id);
         };
-        let client = Client::new(
-            client::ContextServerId(self.id.clone()),
-            client::ModelContextServerBinary {
-                executable: Path::new(&command.path).to_path_buf(),
-                args: command.args.clone(),
-                env: command.env.clone(),
-            },
-            cx.clone(),
-        )?;
+        self.initialize(client, manager).await
+    }
+
+    async fn initialize(
+        self: Arc<Self>,
+        client: Client,
+        manager: WeakEntity<ContextServerManager>,
+    ) -> Result<()> {
+        Self::register_request_handlers(&client, self.id.clone(), manager);
 
         let protocol = crate::protocol::ModelContextProtocol::new(client);
         let client_info = types::Implementation {
@@ -94,6 +119,57 @@ impl ContextServer {
         Ok(())
     }
 
+    fn register_request_handlers(
+        client: &Client,
+        server_id: Arc<str>,
+        manager: WeakEntity<ContextServerManager>,
+    ) {
+        client.on_request(types::RequestType::ListRoots.as_str(), {
+            let manager = manager.clone();
+            move |_, cx| {
+                let manager = manager.clone();
+                async move {
+                    let roots = manager.read_with(&cx, |manager, cx| manager.roots(cx))?;
+                    Ok(serde_json::to_value(types::ListRootsResponse {
+                        roots,
+                        meta: None,
+                    })?)
+                }
+            }
+        });
+
+        client.on_notification(types::NotificationType::ResourcesUpdated.as_str(), {
+            let manager = manager.clone();
+            let server_id = server_id.clone();
+            move |params, mut cx| {
+                let Some(uri) = params.get("uri").and_then(|uri| uri.as_str()) else {
+                    return;
+                };
+                let event = Event::ResourceUpdated {
+                    server_id: server_id.clone(),
+                    uri: uri.to_string(),
+                };
+                manager.update(&mut cx, |_, cx| cx.emit(event)).ok();
+            }
+        });
+
+        client.on_request(types::RequestType::CreateMessage.as_str(), {
+            move |params, mut cx| {
+                let manager = manager.clone();
+                let server_id = server_id.clone();
+                async move {
+                    let request: types::CreateMessageRequest = serde_json::from_value(params)?;
+                    let result = manager
+                        .update(&mut cx, |manager, cx| {
+                            manager.create_message(server_id, request, cx)
+                        })?
+                        .await?;
+                    Ok(serde_json::to_value(result)?)
+                }
+            }
+        });
+    }
+
     pub fn stop(&self) -> Result<()> {
         let mut client = self.client.write();
         if let Some(protocol) = client.take() {
@@ -109,12 +185,17 @@ pub struct ContextServerManager {
     registry: Entity<ContextServerFactoryRegistry>,
     update_servers_task: Option<Task<Result<()>>>,
     needs_server_update: bool,
+    pending_sampling_requests: Vec<PendingSamplingRequest>,
+    next_sampling_request_id: usize,
+    prompts_for_sampling: bool,
     _subscriptions: Vec<Subscription>,
 }
 
 pub enum Event {
     ServerStarted { server_id: Arc<str> },
     ServerStopped { server_id: Arc<str> },
+    SamplingRequestsChanged,
+    ResourceUpdated { server_id: Arc<str>, uri: String },
 }
 
 impl EventEmitter<Event> for ContextServerManager {}
@@ -133,10 +214,19 @@ impl ContextServerManager {
                 cx.observe_global::<SettingsStore>(|this, cx| {
                     this.available_context_servers_changed(cx);
                 }),
+                cx.subscribe(&project, |this, _, event, _| match event {
+                    project::Event::WorktreeAdded(_) | project::Event::WorktreeRemoved(_) => {
+                        this.roots_changed();
+                    }
+                    _ => {}
+                }),
             ],
             project,
             registry,
             needs_server_update: false,
+            pending_sampling_requests: Vec::new(),
+            next_sampling_request_id: 0,
+            prompts_for_sampling: false,
             servers: HashMap::default(),
             update_servers_task: None,
         };
@@ -188,7 +278,7 @@ impl ContextServerManager {
     ) -> Task<anyhow::Result<()>> {
         cx.spawn(async move |this, cx| {
             let id = server.id.clone();
-            server.start(&cx).await?;
+            server.start(this.clone(), &cx).await?;
             this.update(cx, |_, cx| cx.emit(Event::ServerStarted { server_id: id }))?;
             Ok(())
         })
@@ -217,7 +307,7 @@ impl ContextServerManager {
                 server.stop()?;
                 let config = server.config();
                 let new_server = Arc::new(ContextServer::new(id.clone(), config));
-                new_server.clone().start(&cx).await?;
+                new_server.clone().start(this.clone(), &cx).await?;
                 this.update(cx, |this, cx| {
                     this.servers.insert(id.clone(), new_server);
                     cx.emit(Event::ServerStopped {
@@ -244,19 +334,117 @@ impl ContextServerManager {
             .collect()
     }
 
+    /// Returns the worktree roots of the project, as reported to servers via `roots/list`.
+    pub fn roots(&self, cx: &App) -> Vec<types::Root> {
+        self.project
+            .read(cx)
+            .visible_worktrees(cx)
+            .filter_map(|worktree| {
+                let worktree = worktree.read(cx);
+                Some(types::Root {
+                    uri: Url::from_file_path(worktree.abs_path()).ok()?,
+                    name: Some(worktree.root_name().to_string()),
+                })
+            })
+            .collect()
+    }
+
+    fn roots_changed(&self) {
+        for server in self.running_servers() {
+            if let Some(client) = server.client() {
+                client.notify_roots_list_changed().log_err();
+            }
+        }
+    }
+
+    /// Lets sampling requests wait for the user's approval. Until this is called,
+    /// requests from servers that aren't auto-approved are rejected right away,
+    /// since nothing would show them to the user.
+    pub fn enable_sampling_prompts(&mut self) {
+        self.prompts_for_sampling = true;
+    }
+
+    pub fn pending_sampling_requests(&self) -> &[PendingSamplingRequest] {
+        &self.pending_sampling_requests
+    }
+
+    /// Approves or rejects a pending sampling request.
+    pub fn respond_to_sampling_request(
+        &mut self,
+        id: SamplingRequestId,
+        approved: bool,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(ix) = self
+            .pending_sampling_requests
+            .iter()
+            .position(|request| request.id == id)
+        {
+            let PendingSamplingRequest {
+                request,
+                response_tx,
+                ..
+            } = self.pending_sampling_requests.remove(ix);
+            response_tx.send(approved.then_some(request)).ok();
+            cx.emit(Event::SamplingRequestsChanged);
+        }
+    }
+
+    fn create_message(
+        &mut self,
+        server_id: Arc<str>,
+        request: types::CreateMessageRequest,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<types::CreateMessageResult>> {
+        let auto_approve = ContextServerSettings::get(self.settings_location(cx), cx)
+            .context_servers
+            .get(&server_id)
+            .map_or(false, |config| config.auto_approve_sampling);
+        if auto_approve {
+            return sampling::create_message(&request, cx);
+        }
+        if !self.prompts_for_sampling {
+            return Task::ready(Err(anyhow!(
+                "sampling requests from {server_id} must be approved, \
+                but there is no agent panel to approve them in"
+            )));
+        }
+
+        let (response_tx, response_rx) = oneshot::channel();
+        let id = SamplingRequestId(post_inc(&mut self.next_sampling_request_id));
+        self.pending_sampling_requests.push(PendingSamplingRequest {
+            id,
+            server_id: server_id.clone(),
+            request,
+            response_tx,
+        });
+        cx.emit(Event::SamplingRequestsChanged);
+
+        cx.spawn(async move |_, cx| {
+            let Some(request) = response_rx.await.ok().flatten() else {
+                bail!("the user rejected the sampling request");
+            };
+            cx.update(|cx| sampling::create_message(&request, cx))?
+                .await
+        })
+    }
+
+    fn settings_location<'a>(&self, cx: &'a App) -> Option<settings::SettingsLocation<'a>> {
+        self.project
+            .read(cx)
+            .visible_worktrees(cx)
+            .next()
+            .map(|worktree| settings::SettingsLocation {
+                worktree_id: worktree.read(cx).id(),
+                path: Path::new(""),
+            })
+    }
+
     async fn maintain_servers(this: WeakEntity<Self>, cx: &mut AsyncApp) -> Result<()> {
         let mut desired_servers = HashMap::default();
 
         let (registry, project) = this.update(cx, |this, cx| {
-            let location = this
-                .project
-                .read(cx)
-                .visible_worktrees(cx)
-                .next()
-                .map(|worktree| settings::SettingsLocation {
-                    worktree_id: worktree.read(cx).id(),
-                    path: Path::new(""),
-                });
+            let location = this.settings_location(cx);
             let settings = ContextServerSettings::get(location, cx);
             desired_servers = settings.context_servers.clone();
 
@@ -267,7 +455,7 @@ impl ContextServerManager {
             registry.read_with(cx, |registry, _| registry.context_server_factories())?
         {
             let config = desired_servers.entry(id).or_default();
-            if config.command.is_none() {
+            if config.command.is_none() && config.remote.is_none() {
                 if let Some(extension_command) = factory(project.clone(), &cx).await.log_err() {
                     config.command = Some(extension_command);
                 }
@@ -289,7 +477,12 @@ impl ContextServerManager {
 
             for (id, config) in desired_servers {
                 let existing_config = this.servers.get(&id).map(|server| server.config());
-                if existing_config.as_deref() != Some(&config) {
+                let needs_restart = existing_config.map_or(true, |existing_config| {
+                    existing_config.command != config.command
+                        || existing_config.remote != config.remote
+                        || existing_config.settings != config.settings
+                });
+                if needs_restart {
                     let config = Arc::new(config);
                     let server = Arc::new(ContextServer::new(id.clone(), config));
                     servers_to_start.insert(id.clone(), server.clone());
@@ -307,7 +500,7 @@ impl ContextServerManager {
         }
 
         for (id, server) in servers_to_start {
-            if server.start(&cx).await.log_err().is_some() {
+            if server.start(this.clone(), &cx).await.log_err().is_some() {
                 this.update(cx, |_, cx| cx.emit(Event::ServerStarted { server_id: id }))?;
             }
         }
@@ -315,3 +508,137 @@ impl ContextServerManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+
+    use async_trait::async_trait;
+    use fs::FakeFs;
+    use futures::Stream;
+    use gpui::TestAppContext;
+    use serde_json::{Value, json};
+    use smol::channel;
+    use util::path;
+
+    use super::*;
+    use crate::client::ContextServerId;
+    use crate::transport::Transport;
+
+    struct FakeTransport {
+        incoming: channel::Receiver<String>,
+        outgoing: channel::Sender<String>,
+    }
+
+    #[async_trait]
+    impl Transport for FakeTransport {
+        async fn send(&self, message: String) -> Result<()> {
+            self.outgoing.send(message).await?;
+            Ok(())
+        }
+
+        fn receive(&self) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+            Box::pin(self.incoming.clone())
+        }
+
+        fn receive_err(&self) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+            Box::pin(futures::stream::pending())
+        }
+    }
+
+    #[gpui::test]
+    async fn test_roots(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+            crate::init(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(path!("/project"), json!({ "main.rs": "" }))
+            .await;
+        fs.insert_tree(path!("/other"), json!({})).await;
+        let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+        let registry = cx.update(ContextServerFactoryRegistry::default_global);
+        let manager = cx.new(|cx| ContextServerManager::new(registry, project.clone(), cx));
+        cx.run_until_parked();
+
+        let (incoming_tx, incoming_rx) = channel::unbounded();
+        let (outgoing_tx, outgoing_rx) = channel::unbounded();
+        let transport = Arc::new(FakeTransport {
+            incoming: incoming_rx,
+            outgoing: outgoing_tx,
+        });
+        let client = Client::with_transport(
+            ContextServerId("test".into()),
+            "test".into(),
+            transport,
+            cx.to_async(),
+        )
+        .unwrap();
+        let server = Arc::new(ContextServer::new(
+            "test".into(),
+            Arc::new(ServerConfig::default()),
+        ));
+        let initialize = cx
+            .foreground_executor()
+            .spawn(server.clone().initialize(client, manager.downgrade()));
+
+        let next_message =
+            || async { serde_json::from_str::<Value>(&outgoing_rx.recv().await.unwrap()).unwrap() };
+
+        let request = next_message().await;
+        assert_eq!(request["method"], "initialize");
+        assert_eq!(
+            request["params"]["capabilities"]["roots"],
+            json!({ "listChanged": true })
+        );
+        incoming_tx
+            .send(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": {
+                        "protocolVersion": types::LATEST_PROTOCOL_VERSION,
+                        "capabilities": {},
+                        "serverInfo": { "name": "test", "version": "1.0.0" },
+                    },
+                })
+                .to_string(),
+            )
+            .await
+            .unwrap();
+        initialize.await.unwrap();
+        assert_eq!(next_message().await["method"], "notifications/initialized");
+        manager.update(cx, |manager, _| {
+            manager.servers.insert(server.id(), server.clone());
+        });
+
+        incoming_tx
+            .send(json!({ "jsonrpc": "2.0", "id": 7, "method": "roots/list" }).to_string())
+            .await
+            .unwrap();
+        let response = next_message().await;
+        assert_eq!(response["id"], 7);
+        assert_eq!(
+            response["result"]["roots"],
+            json!([{
+                "uri": Url::from_file_path(path!("/project")).unwrap(),
+                "name": "project",
+            }])
+        );
+
+        project
+            .update(cx, |project, cx| {
+                project.find_or_create_worktree(path!("/other"), true, cx)
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            next_message().await["method"],
+            "notifications/roots/list_changed"
+        );
+    }
+}
@@ -7,6 +7,7 @@
 
 use anyhow::Result;
 use collections::HashMap;
+use url::Url;
 
 use crate::client::Client;
 use crate::types;
@@ -34,8 +35,16 @@ impl ModelContextProtocol {
             protocol_version: types::ProtocolVersion(types::LATEST_PROTOCOL_VERSION.to_string()),
             capabilities: types::ClientCapabilities {
                 experimental: None,
-                sampling: None,
-                roots: None,
+                sampling: self
+                    .inner
+                    .handles_request(types::RequestType::CreateMessage.as_str())
+                    .then(|| serde_json::json!({})),
+                roots: self
+                    .inner
+                    .handles_request(types::RequestType::ListRoots.as_str())
+                    .then(|| types::RootsCapabilities {
+                        list_changed: Some(true),
+                    }),
             },
             meta: None,
             client_info,
@@ -121,21 +130,111 @@ impl InitializedContextServerProtocol {
         Ok(response.prompts)
     }
 
-    /// List the MCP resources.
+    /// List the MCP resources, following the server's pagination to the last page.
     pub async fn list_resources(&self) -> Result<types::ResourcesListResponse> {
         self.check_capability(ServerCapability::Resources)?;
 
-        let response: types::ResourcesListResponse = self
+        let mut resources = Vec::new();
+        let mut cursor = None;
+        loop {
+            let response: types::ResourcesListResponse = self
+                .inner
+                .request(
+                    types::RequestType::ResourcesList.as_str(),
+                    pagination_params(cursor.clone()),
+                )
+                .await?;
+            resources.extend(response.resources);
+            // Stop if a server keeps returning the same page.
+            cursor = response
+                .next_cursor
+                .filter(|next| Some(next) != cursor.as_ref());
+            if cursor.is_none() {
+                return Ok(types::ResourcesListResponse {
+                    resources,
+                    next_cursor: None,
+                    meta: response.meta,
+                });
+            }
+        }
+    }
+
+    /// List the MCP resource templates, following the server's pagination to the last page.
+    pub async fn list_resource_templates(&self) -> Result<types::ListResourceTemplatesResponse> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let mut resource_templates = Vec::new();
+        let mut cursor = None;
+        loop {
+            let response: types::ListResourceTemplatesResponse = self
+                .inner
+                .request(
+                    types::RequestType::ListResourceTemplates.as_str(),
+                    pagination_params(cursor.clone()),
+                )
+                .await?;
+            resource_templates.extend(response.resource_templates);
+            // Stop if a server keeps returning the same page.
+            cursor = response
+                .next_cursor
+                .filter(|next| Some(next) != cursor.as_ref());
+            if cursor.is_none() {
+                return Ok(types::ListResourceTemplatesResponse {
+                    resource_templates,
+                    next_cursor: None,
+                    meta: response.meta,
+                });
+            }
+        }
+    }
+
+    /// Reads the contents of the resource with the given URI.
+    pub async fn read_resource(&self, uri: Url) -> Result<types::ResourcesReadResponse> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let params = types::ResourcesReadParams { uri, meta: None };
+        let response: types::ResourcesReadResponse = self
             .inner
-            .request(
-                types::RequestType::ResourcesList.as_str(),
-                serde_json::json!({}),
-            )
+            .request(types::RequestType::ResourcesRead.as_str(), params)
             .await?;
 
         Ok(response)
     }
 
+    /// Whether the server sends `notifications/resources/updated` for subscribed resources.
+    pub fn supports_resource_subscriptions(&self) -> bool {
+        self.initialize
+            .capabilities
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false)
+    }
+
+    /// Subscribes to updates of the resource with the given URI.
+    pub async fn subscribe_resource(&self, uri: Url) -> Result<()> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let params = types::ResourcesSubscribeParams { uri, meta: None };
+        self.inner
+            .request::<serde_json::Value>(types::RequestType::ResourcesSubscribe.as_str(), params)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Unsubscribes from updates of the resource with the given URI.
+    pub async fn unsubscribe_resource(&self, uri: Url) -> Result<()> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let params = types::ResourcesUnsubscribeParams { uri, meta: None };
+        self.inner
+            .request::<serde_json::Value>(types::RequestType::ResourcesUnsubscribe.as_str(), params)
+            .await?;
+
+        Ok(())
+    }
+
     /// Executes a prompt with the given arguments and returns the result.
     pub async fn run_prompt<P: AsRef<str>>(
         &self,
@@ -224,6 +323,14 @@ impl InitializedContextServerProtocol {
 }
 
 impl InitializedContextServerProtocol {
+    /// Notifies the server that the roots it can operate on have changed.
+    pub fn notify_roots_list_changed(&self) -> Result<()> {
+        self.inner.notify(
+            types::NotificationType::RootsListChanged.as_str(),
+            serde_json::json!({}),
+        )
+    }
+
     pub async fn request<R: serde::de::DeserializeOwned>(
         &self,
         method: &str,
@@ -232,3 +339,11 @@ impl InitializedContextServerProtocol {
         self.inner.request(method, params).await
     }
 }
+
+/// The params of a paginated list request, asking for the page after `cursor`.
+fn pagination_params(cursor: Option<String>) -> serde_json::Value {
+    match cursor {
+        Some(cursor) => serde_json::json!({ "cursor": cursor }),
+        None => serde_json::json!({}),
+    }
+}
@@ -0,0 +1,188 @@
+//! Support for MCP sampling, which lets context servers request completions
+//! from the user's configured language model.
+
+use std::sync::Arc;
+
+use anyhow::{Result, anyhow};
+use futures::StreamExt as _;
+use futures::channel::oneshot;
+use gpui::{App, AsyncApp, Task};
+use language_model::{
+    ConfiguredModel, LanguageModelRegistry, LanguageModelRequest, LanguageModelRequestMessage,
+    MessageContent, Role,
+};
+
+use crate::types;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplingRequestId(pub usize);
+
+/// A sampling request from a context server that is waiting for the user's approval.
+pub struct PendingSamplingRequest {
+    pub id: SamplingRequestId,
+    pub server_id: Arc<str>,
+    pub request: types::CreateMessageRequest,
+    pub(crate) response_tx: oneshot::Sender<Option<types::CreateMessageRequest>>,
+}
+
+impl PendingSamplingRequest {
+    /// Returns the prompt that will be sent to the model, for display to the user.
+    pub fn prompt_text(&self) -> String {
+        let mut text = String::new();
+        if let Some(system_prompt) = &self.request.system_prompt {
+            text.push_str(system_prompt);
+            text.push_str("\n\n");
+        }
+        for message in &self.request.messages {
+            let role = match message.role {
+                types::Role::User => "User",
+                types::Role::Assistant => "Assistant",
+            };
+            text.push_str(&format!("{role}: {}\n", message_text(&message.content)));
+        }
+        text
+    }
+}
+
+fn message_text(content: &types::MessageContent) -> String {
+    match content {
+        types::MessageContent::Text { text, .. } => text.clone(),
+        types::MessageContent::Image { mime_type, .. } => format!("[{mime_type} image]"),
+        types::MessageContent::Resource { resource, .. } => format!("[resource {}]", resource.uri),
+    }
+}
+
+/// Converts a sampling request into a request for a [`language_model::LanguageModel`].
+pub fn to_language_model_request(request: &types::CreateMessageRequest) -> LanguageModelRequest {
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = &request.system_prompt {
+        messages.push(LanguageModelRequestMessage {
+            role: Role::System,
+            content: vec![MessageContent::Text(system_prompt.clone())],
+            cache: false,
+        });
+    }
+    messages.extend(
+        request
+            .messages
+            .iter()
+            .map(|message| LanguageModelRequestMessage {
+                role: match message.role {
+                    types::Role::User => Role::User,
+                    types::Role::Assistant => Role::Assistant,
+                },
+                content: vec![MessageContent::Text(message_text(&message.content))],
+                cache: false,
+            }),
+    );
+
+    LanguageModelRequest {
+        messages,
+        tools: Vec::new(),
+        stop: request.stop_sequences.clone().unwrap_or_default(),
+        temperature: request.temperature.map(|temperature| temperature as f32),
+        max_tokens: Some(request.max_tokens),
+    }
+}
+
+/// Picks the model named by the first of the server's hints that matches a model
+/// of an authenticated provider, falling back to the user's default model.
+///
+/// The cost, speed and intelligence priorities aren't taken into account, since
+/// models don't describe themselves in those terms.
+fn select_model(
+    preferences: Option<&types::ModelPreferences>,
+    cx: &App,
+) -> Option<ConfiguredModel> {
+    let registry = LanguageModelRegistry::read_global(cx);
+    let hints = preferences
+        .and_then(|preferences| preferences.hints.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|hint| hint.name.as_deref());
+    for hint in hints {
+        for provider in registry.providers() {
+            if !provider.is_authenticated(cx) {
+                continue;
+            }
+            let model = provider
+                .provided_models(cx)
+                .into_iter()
+                .find(|model| model.id().0.contains(hint) || model.name().0.contains(hint));
+            if let Some(model) = model {
+                return Some(ConfiguredModel { provider, model });
+            }
+        }
+    }
+    registry.default_model()
+}
+
+/// Runs a sampling request against the model the server prefers, or the user's
+/// default language model.
+pub fn create_message(
+    request: &types::CreateMessageRequest,
+    cx: &App,
+) -> Task<Result<types::CreateMessageResult>> {
+    let Some(model) = select_model(request.model_preferences.as_ref(), cx) else {
+        return Task::ready(Err(anyhow!("no language model configured")));
+    };
+    if !model.provider.is_authenticated(cx) {
+        return Task::ready(Err(anyhow!("language model provider is not authenticated")));
+    }
+
+    let request = to_language_model_request(request);
+    cx.spawn(async move |cx: &mut AsyncApp| {
+        let mut response = model.model.stream_completion_text(request, &cx).await?;
+        let mut text = String::new();
+        while let Some(chunk) = response.stream.next().await {
+            text.push_str(&chunk?);
+        }
+
+        Ok(types::CreateMessageResult {
+            role: types::Role::Assistant,
+            content: types::MessageContent::Text {
+                text,
+                annotations: None,
+            },
+            model: model.model.id().0.to_string(),
+            stop_reason: Some("endTurn".into()),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_language_model_request() {
+        let request: types::CreateMessageRequest = serde_json::from_value(serde_json::json!({
+            "messages": [
+                { "role": "user", "content": { "type": "text", "text": "What is 2+2?" } },
+                { "role": "assistant", "content": { "type": "text", "text": "4" } },
+            ],
+            "systemPrompt": "Be brief.",
+            "temperature": 0.5,
+            "maxTokens": 100,
+            "stopSequences": ["\n\n"],
+        }))
+        .unwrap();
+
+        let request = to_language_model_request(&request);
+        assert_eq!(
+            request
+                .messages
+                .iter()
+                .map(|message| (message.role, message.string_contents()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Role::System, "Be brief.".to_string()),
+                (Role::User, "What is 2+2?".to_string()),
+                (Role::Assistant, "4".to_string()),
+            ]
+        );
+        assert_eq!(request.stop, vec!["\n\n".to_string()]);
+        assert_eq!(request.temperature, Some(0.5));
+        assert_eq!(request.max_tokens, Some(100));
+    }
+}
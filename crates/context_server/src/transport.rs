@@ -1,12 +1,20 @@
+mod sse;
+mod sse_transport;
 mod stdio_transport;
+mod streamable_http_transport;
 
 use std::pin::Pin;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use async_trait::async_trait;
+use context_server_settings::RemoteServerConfig;
 use futures::Stream;
+use http_client::{Method, Request, Url};
 
+pub use sse_transport::*;
 pub use stdio_transport::*;
+pub use streamable_http_transport::*;
 
 #[async_trait]
 pub trait Transport: Send + Sync {
@@ -14,3 +22,44 @@ pub trait Transport: Send + Sync {
     fn receive(&self) -> Pin<Box<dyn Stream<Item = String> + Send>>;
     fn receive_err(&self) -> Pin<Box<dyn Stream<Item = String> + Send>>;
 }
+
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// The endpoint and headers used by the HTTP-based transports.
+#[derive(Debug, Clone)]
+pub struct HttpTransportOptions {
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpTransportOptions {
+    pub fn from_settings(config: &RemoteServerConfig) -> Result<Self> {
+        let url = Url::parse(&config.url)
+            .with_context(|| format!("invalid context server url {:?}", config.url))?;
+        let mut headers = config
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<Vec<_>>();
+        if let Some(token) = &config.bearer_token {
+            headers.push(("Authorization".into(), format!("Bearer {token}")));
+        }
+        Ok(Self { url, headers })
+    }
+
+    fn request(&self, method: Method, url: &Url) -> http_client::http::request::Builder {
+        self.headers.iter().fold(
+            Request::builder().method(method).uri(url.as_str()),
+            |builder, (name, value)| builder.header(name.as_str(), value.as_str()),
+        )
+    }
+}
+
+/// Returns how long to wait before the given (1-based) reconnection attempt.
+fn reconnect_delay(attempt: u32) -> Duration {
+    INITIAL_RECONNECT_DELAY
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_RECONNECT_DELAY)
+}
@@ -0,0 +1,114 @@
+//! A minimal parser for `text/event-stream` bodies, as used by the MCP HTTP transports.
+
+use futures::io::BufReader;
+use futures::{AsyncBufReadExt as _, AsyncRead, Stream, StreamExt as _, future};
+
+/// A single event dispatched by a server-sent event stream.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+    pub id: Option<String>,
+}
+
+#[derive(Default)]
+pub struct SseParser {
+    event: Option<String>,
+    data: Vec<String>,
+    id: Option<String>,
+}
+
+impl SseParser {
+    /// Feeds a single line of the stream, without its line terminator.
+    ///
+    /// Returns an event once a blank line terminates it.
+    pub fn push_line(&mut self, line: &str) -> Option<SseEvent> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" => self.id = Some(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        if self.data.is_empty() {
+            self.event = None;
+            self.id = None;
+            return None;
+        }
+        Some(SseEvent {
+            event: self.event.take(),
+            data: std::mem::take(&mut self.data).join("\n"),
+            id: self.id.take(),
+        })
+    }
+}
+
+/// Returns a stream of the events read from the given body.
+pub fn events<R>(body: R) -> impl Stream<Item = std::io::Result<SseEvent>> + Send
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let mut parser = SseParser::default();
+    BufReader::new(body).lines().filter_map(move |line| {
+        future::ready(match line {
+            Ok(line) => parser.push_line(&line).map(Ok),
+            Err(error) => Some(Err(error)),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let mut parser = SseParser::default();
+        let lines = [
+            ": keep-alive",
+            "event: endpoint",
+            "data: /messages?session=1",
+            "",
+            "id: 7",
+            "data: {\"a\":",
+            "data:1}\r",
+            "",
+            "",
+        ];
+        let events = lines
+            .iter()
+            .filter_map(|line| parser.push_line(line))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: Some("endpoint".into()),
+                    data: "/messages?session=1".into(),
+                    id: None,
+                },
+                SseEvent {
+                    event: None,
+                    data: "{\"a\":\n1}".into(),
+                    id: Some("7".into()),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use async_trait::async_trait;
+use futures::{AsyncReadExt as _, Stream, StreamExt as _};
+use gpui::{AsyncApp, BackgroundExecutor, Task};
+use http_client::{AsyncBody, HttpClient, Method, Url};
+use postage::{prelude::Stream as _, watch};
+use smol::channel;
+
+use crate::transport::{
+    HttpTransportOptions, MAX_RECONNECT_ATTEMPTS, Transport, reconnect_delay, sse,
+};
+
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+
+/// A transport for the legacy MCP HTTP+SSE protocol.
+///
+/// Server messages arrive on a long-lived event stream, whose first `endpoint`
+/// event announces the URL that client messages are POSTed to. The stream is
+/// reopened with exponential backoff when it drops, and the server announces
+/// a fresh endpoint on the new connection. Messages sent while reconnecting
+/// wait for that endpoint, as the previous one belonged to the dropped session.
+pub struct SseTransport {
+    state: Arc<State>,
+    endpoint_rx: watch::Receiver<Option<Url>>,
+    inbound_rx: channel::Receiver<String>,
+    stderr_rx: channel::Receiver<String>,
+    _listen_task: Task<()>,
+}
+
+struct State {
+    http_client: Arc<dyn HttpClient>,
+    options: HttpTransportOptions,
+    inbound_tx: channel::Sender<String>,
+    stderr_tx: channel::Sender<String>,
+    executor: BackgroundExecutor,
+}
+
+impl SseTransport {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        options: HttpTransportOptions,
+        cx: &AsyncApp,
+    ) -> Self {
+        let (inbound_tx, inbound_rx) = channel::unbounded::<String>();
+        let (stderr_tx, stderr_rx) = channel::unbounded::<String>();
+        let (endpoint_tx, endpoint_rx) = watch::channel();
+        let state = Arc::new(State {
+            http_client,
+            options,
+            inbound_tx,
+            stderr_tx,
+            executor: cx.background_executor().clone(),
+        });
+        let listen_task = state.executor.spawn(state.clone().listen(endpoint_tx));
+        Self {
+            state,
+            endpoint_rx,
+            inbound_rx,
+            stderr_rx,
+            _listen_task: listen_task,
+        }
+    }
+
+    async fn endpoint(&self) -> Result<Url> {
+        let mut endpoint_rx = self.endpoint_rx.clone();
+        loop {
+            if let Some(endpoint) = endpoint_rx.borrow().clone() {
+                return Ok(endpoint);
+            }
+            endpoint_rx
+                .recv()
+                .await
+                .context("context server event stream closed")?;
+        }
+    }
+}
+
+impl State {
+    async fn listen(self: Arc<Self>, mut endpoint_tx: watch::Sender<Option<Url>>) {
+        let mut last_event_id = None;
+        let mut attempt = 0;
+        loop {
+            match self.open_event_stream(last_event_id.as_deref()).await {
+                Ok(body) => {
+                    let mut events = Box::pin(sse::events(body));
+                    while let Some(event) = events.next().await {
+                        let event = match event {
+                            Ok(event) => event,
+                            Err(error) => {
+                                self.report_error(anyhow!(error));
+                                break;
+                            }
+                        };
+                        attempt = 0;
+                        if event.id.is_some() {
+                            last_event_id = event.id;
+                        }
+                        match event.event.as_deref() {
+                            Some("endpoint") => match self.options.url.join(&event.data) {
+                                Ok(endpoint) => *endpoint_tx.borrow_mut() = Some(endpoint),
+                                Err(error) => self.report_error(anyhow!(
+                                    "invalid context server endpoint {:?}: {error}",
+                                    event.data
+                                )),
+                            },
+                            None | Some("message") => {
+                                if self.inbound_tx.send(event.data).await.is_err() {
+                                    return;
+                                }
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                }
+                Err(error) => self.report_error(error),
+            }
+            *endpoint_tx.borrow_mut() = None;
+
+            attempt += 1;
+            if attempt > MAX_RECONNECT_ATTEMPTS {
+                self.report_error(anyhow!(
+                    "giving up on context server event stream after {MAX_RECONNECT_ATTEMPTS} attempts"
+                ));
+                return;
+            }
+            self.executor.timer(reconnect_delay(attempt)).await;
+        }
+    }
+
+    async fn open_event_stream(&self, last_event_id: Option<&str>) -> Result<AsyncBody> {
+        let mut request = self
+            .options
+            .request(Method::GET, &self.options.url)
+            .header("Accept", "text/event-stream");
+        if let Some(last_event_id) = last_event_id {
+            request = request.header(LAST_EVENT_ID_HEADER, last_event_id);
+        }
+        let response = self
+            .http_client
+            .send(request.body(AsyncBody::empty())?)
+            .await?;
+        if !response.status().is_success() {
+            bail!(
+                "failed to open context server event stream: {}",
+                response.status()
+            );
+        }
+        Ok(response.into_body())
+    }
+
+    fn report_error(&self, error: anyhow::Error) {
+        self.stderr_tx.try_send(format!("{error:#}")).ok();
+    }
+}
+
+#[async_trait]
+impl Transport for SseTransport {
+    async fn send(&self, message: String) -> Result<()> {
+        let endpoint = self.endpoint().await?;
+        let request = self
+            .state
+            .options
+            .request(Method::POST, &endpoint)
+            .header("Content-Type", "application/json")
+            .body(AsyncBody::from(message))?;
+        let mut response = self.state.http_client.send(request).await?;
+        if !response.status().is_success() {
+            let mut body = String::new();
+            response.body_mut().read_to_string(&mut body).await.ok();
+            bail!(
+                "context server responded with {}: {body}",
+                response.status()
+            );
+        }
+        Ok(())
+    }
+
+    fn receive(&self) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+        Box::pin(self.inbound_rx.clone())
+    }
+
+    fn receive_err(&self) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+        Box::pin(self.stderr_rx.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt as _;
+    use gpui::TestAppContext;
+    use http_client::{FakeHttpClient, Response};
+    use parking_lot::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// An event stream body that stays open after the given events, like a
+    /// live server connection.
+    fn open_event_stream(events: &'static str) -> AsyncBody {
+        AsyncBody::from_reader(
+            futures::stream::iter([Ok::<_, std::io::Error>(events.as_bytes())])
+                .chain(futures::stream::pending())
+                .into_async_read(),
+        )
+    }
+
+    #[gpui::test]
+    async fn test_sse_transport(cx: &mut TestAppContext) {
+        let posted = Arc::new(Mutex::new(Vec::new()));
+        let http_client = FakeHttpClient::create({
+            let posted = posted.clone();
+            move |mut request| {
+                let posted = posted.clone();
+                async move {
+                    if request.method() == Method::GET {
+                        return Ok(Response::builder()
+                            .header("Content-Type", "text/event-stream")
+                            .body(open_event_stream(concat!(
+                                "event: endpoint\n",
+                                "data: /messages?session_id=1\n\n",
+                                "event: message\n",
+                                "data: {\"jsonrpc\":\"2.0\",\"method\":\"ping\"}\n\n",
+                            )))
+                            .unwrap());
+                    }
+
+                    let mut body = String::new();
+                    request.body_mut().read_to_string(&mut body).await?;
+                    posted.lock().push((request.uri().to_string(), body));
+                    Ok(Response::builder()
+                        .status(202)
+                        .body(AsyncBody::empty())
+                        .unwrap())
+                }
+            }
+        });
+
+        let options = HttpTransportOptions {
+            url: Url::parse("http://localhost:8080/sse").unwrap(),
+            headers: Vec::new(),
+        };
+        let transport = SseTransport::new(http_client, options, &cx.to_async());
+        let mut inbound = transport.receive();
+
+        assert_eq!(
+            inbound.next().await.unwrap(),
+            r#"{"jsonrpc":"2.0","method":"ping"}"#
+        );
+        transport.send("{}".into()).await.unwrap();
+        assert_eq!(
+            posted.lock().as_slice(),
+            &[(
+                "http://localhost:8080/messages?session_id=1".to_string(),
+                "{}".to_string()
+            )]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_sse_transport_reconnect(cx: &mut TestAppContext) {
+        let posted = Arc::new(Mutex::new(Vec::new()));
+        let connections = Arc::new(AtomicUsize::new(0));
+        let http_client = FakeHttpClient::create({
+            let posted = posted.clone();
+            let connections = connections.clone();
+            move |mut request| {
+                let posted = posted.clone();
+                let connections = connections.clone();
+                async move {
+                    if request.method() == Method::GET {
+                        // The first connection drops right after announcing its endpoint.
+                        let body = match connections.fetch_add(1, Ordering::SeqCst) {
+                            0 => "event: endpoint\ndata: /messages?session_id=1\n\n".into(),
+                            _ => open_event_stream(
+                                "event: endpoint\ndata: /messages?session_id=2\n\n",
+                            ),
+                        };
+                        return Ok(Response::builder()
+                            .header("Content-Type", "text/event-stream")
+                            .body(body)
+                            .unwrap());
+                    }
+
+                    let mut body = String::new();
+                    request.body_mut().read_to_string(&mut body).await?;
+                    posted.lock().push((request.uri().to_string(), body));
+                    Ok(Response::builder()
+                        .status(202)
+                        .body(AsyncBody::empty())
+                        .unwrap())
+                }
+            }
+        });
+
+        let options = HttpTransportOptions {
+            url: Url::parse("http://localhost:8080/sse").unwrap(),
+            headers: Vec::new(),
+        };
+        let transport = Arc::new(SseTransport::new(http_client, options, &cx.to_async()));
+        cx.run_until_parked();
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        let send = cx.executor().spawn({
+            let transport = transport.clone();
+            async move { transport.send("{}".into()).await }
+        });
+        cx.run_until_parked();
+        assert!(posted.lock().is_empty());
+
+        cx.executor().advance_clock(reconnect_delay(1));
+        send.await.unwrap();
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            posted.lock().as_slice(),
+            &[(
+                "http://localhost:8080/messages?session_id=2".to_string(),
+                "{}".to_string()
+            )]
+        );
+    }
+}
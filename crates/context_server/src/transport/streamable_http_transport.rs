@@ -0,0 +1,378 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use futures::{AsyncReadExt as _, Stream, StreamExt as _};
+use gpui::{AsyncApp, BackgroundExecutor, Task};
+use http_client::{AsyncBody, HttpClient, Method, Response, StatusCode};
+use parking_lot::Mutex;
+use serde_json::Value;
+use smol::channel;
+
+use crate::transport::{
+    HttpTransportOptions, MAX_RECONNECT_ATTEMPTS, Transport, reconnect_delay, sse,
+};
+
+const SESSION_ID_HEADER: &str = "Mcp-Session-Id";
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+
+/// A transport for the MCP Streamable HTTP protocol.
+///
+/// Every client message is POSTed to a single endpoint, which answers with
+/// either a JSON body or an event stream. Once the server assigns a session,
+/// a GET event stream is kept open (and reopened on failure) to receive
+/// server-initiated messages.
+pub struct StreamableHttpTransport {
+    state: Arc<State>,
+    inbound_rx: channel::Receiver<String>,
+    stderr_rx: channel::Receiver<String>,
+    listen_task: Mutex<Option<Task<()>>>,
+}
+
+struct State {
+    http_client: Arc<dyn HttpClient>,
+    options: HttpTransportOptions,
+    session_id: Mutex<Option<String>>,
+    inbound_tx: channel::Sender<String>,
+    stderr_tx: channel::Sender<String>,
+    executor: BackgroundExecutor,
+}
+
+impl StreamableHttpTransport {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        options: HttpTransportOptions,
+        cx: &AsyncApp,
+    ) -> Self {
+        let (inbound_tx, inbound_rx) = channel::unbounded::<String>();
+        let (stderr_tx, stderr_rx) = channel::unbounded::<String>();
+        Self {
+            state: Arc::new(State {
+                http_client,
+                options,
+                session_id: Mutex::new(None),
+                inbound_tx,
+                stderr_tx,
+                executor: cx.background_executor().clone(),
+            }),
+            inbound_rx,
+            stderr_rx,
+            listen_task: Mutex::new(None),
+        }
+    }
+
+    pub fn session_id(&self) -> Option<String> {
+        self.state.session_id.lock().clone()
+    }
+
+    fn ensure_listening(&self) {
+        let mut listen_task = self.listen_task.lock();
+        if listen_task.is_none() {
+            let state = self.state.clone();
+            *listen_task = Some(self.state.executor.spawn(state.listen()));
+        }
+    }
+}
+
+impl State {
+    async fn post(self: &Arc<Self>, message: String) -> Result<bool> {
+        let session_id = self.session_id.lock().clone();
+        let mut request = self
+            .options
+            .request(Method::POST, &self.options.url)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json, text/event-stream");
+        if let Some(session_id) = &session_id {
+            request = request.header(SESSION_ID_HEADER, session_id.as_str());
+        }
+
+        let mut response = self
+            .http_client
+            .send(request.body(AsyncBody::from(message))?)
+            .await?;
+
+        let assigned_session = response
+            .headers()
+            .get(SESSION_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string);
+        let has_session = assigned_session.is_some() || session_id.is_some();
+        if let Some(assigned_session) = assigned_session {
+            *self.session_id.lock() = Some(assigned_session);
+        }
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND && session_id.is_some() {
+            self.session_id.lock().take();
+            bail!("context server session expired");
+        }
+        if !status.is_success() {
+            let mut body = String::new();
+            response.body_mut().read_to_string(&mut body).await.ok();
+            bail!("context server responded with {status}: {body}");
+        }
+        if status == StatusCode::ACCEPTED {
+            return Ok(has_session);
+        }
+
+        if is_event_stream(&response) {
+            let this = self.clone();
+            self.executor
+                .spawn(async move {
+                    this.forward_events(response.into_body(), &mut None).await;
+                })
+                .detach();
+        } else {
+            let mut body = String::new();
+            response.body_mut().read_to_string(&mut body).await?;
+            self.forward_json(&body)?;
+        }
+
+        Ok(has_session)
+    }
+
+    fn forward_json(&self, body: &str) -> Result<()> {
+        let body = body.trim();
+        if body.is_empty() {
+            return Ok(());
+        }
+        if body.starts_with('[') {
+            for message in serde_json::from_str::<Vec<Value>>(body)? {
+                self.inbound_tx.try_send(message.to_string())?;
+            }
+        } else {
+            self.inbound_tx.try_send(body.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Forwards every message on the event stream, returning whether any
+    /// message was received.
+    async fn forward_events(&self, body: AsyncBody, last_event_id: &mut Option<String>) -> bool {
+        let mut received = false;
+        let mut events = Box::pin(sse::events(body));
+        while let Some(event) = events.next().await {
+            match event {
+                Ok(event) => {
+                    if event.id.is_some() {
+                        *last_event_id = event.id;
+                    }
+                    if event
+                        .event
+                        .as_deref()
+                        .map_or(true, |kind| kind == "message")
+                    {
+                        received = true;
+                        if self.inbound_tx.send(event.data).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(error) => {
+                    self.report_error(anyhow!(error));
+                    break;
+                }
+            }
+        }
+        received
+    }
+
+    /// Keeps a GET event stream open for server-initiated messages,
+    /// resuming from the last seen event after a disconnect.
+    async fn listen(self: Arc<Self>) {
+        let mut last_event_id = None;
+        let mut attempt = 0;
+        loop {
+            match self.open_event_stream(last_event_id.as_deref()).await {
+                Ok(Some(body)) => {
+                    if self.forward_events(body, &mut last_event_id).await {
+                        attempt = 0;
+                    }
+                    if self.inbound_tx.is_closed() {
+                        return;
+                    }
+                }
+                Ok(None) => return,
+                Err(error) => self.report_error(error),
+            }
+
+            attempt += 1;
+            if attempt > MAX_RECONNECT_ATTEMPTS {
+                self.report_error(anyhow!(
+                    "giving up on context server event stream after {MAX_RECONNECT_ATTEMPTS} attempts"
+                ));
+                return;
+            }
+            self.executor.timer(reconnect_delay(attempt)).await;
+        }
+    }
+
+    /// Opens the GET event stream, or returns `None` if the server doesn't offer one.
+    async fn open_event_stream(&self, last_event_id: Option<&str>) -> Result<Option<AsyncBody>> {
+        let mut request = self
+            .options
+            .request(Method::GET, &self.options.url)
+            .header("Accept", "text/event-stream");
+        if let Some(session_id) = self.session_id.lock().clone() {
+            request = request.header(SESSION_ID_HEADER, session_id);
+        }
+        if let Some(last_event_id) = last_event_id {
+            request = request.header(LAST_EVENT_ID_HEADER, last_event_id);
+        }
+
+        let response = self
+            .http_client
+            .send(request.body(AsyncBody::empty())?)
+            .await?;
+        match response.status() {
+            StatusCode::METHOD_NOT_ALLOWED => Ok(None),
+            status if status.is_success() && is_event_stream(&response) => {
+                Ok(Some(response.into_body()))
+            }
+            status => bail!("failed to open context server event stream: {status}"),
+        }
+    }
+
+    fn report_error(&self, error: anyhow::Error) {
+        self.stderr_tx.try_send(format!("{error:#}")).ok();
+    }
+}
+
+fn is_event_stream(response: &Response<AsyncBody>) -> bool {
+    response
+        .headers()
+        .get("Content-Type")
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |content_type| {
+            content_type.starts_with("text/event-stream")
+        })
+}
+
+#[async_trait]
+impl Transport for StreamableHttpTransport {
+    async fn send(&self, message: String) -> Result<()> {
+        if self.state.post(message).await? {
+            self.ensure_listening();
+        }
+        Ok(())
+    }
+
+    fn receive(&self) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+        Box::pin(self.inbound_rx.clone())
+    }
+
+    fn receive_err(&self) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+        Box::pin(self.stderr_rx.clone())
+    }
+}
+
+impl Drop for StreamableHttpTransport {
+    fn drop(&mut self) {
+        let Some(session_id) = self.state.session_id.lock().take() else {
+            return;
+        };
+        let request = self
+            .state
+            .options
+            .request(Method::DELETE, &self.state.options.url)
+            .header(SESSION_ID_HEADER, session_id)
+            .body(AsyncBody::empty());
+        if let Ok(request) = request {
+            let http_client = self.state.http_client.clone();
+            self.state
+                .executor
+                .spawn(async move {
+                    http_client.send(request).await.ok();
+                })
+                .detach();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context_server_settings::RemoteServerConfig;
+    use gpui::TestAppContext;
+    use http_client::FakeHttpClient;
+
+    #[gpui::test]
+    async fn test_streamable_http_transport(cx: &mut TestAppContext) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let http_client = FakeHttpClient::create({
+            let requests = requests.clone();
+            move |request| {
+                let requests = requests.clone();
+                async move {
+                    let session_id = request
+                        .headers()
+                        .get(SESSION_ID_HEADER)
+                        .map(|value| value.to_str().unwrap().to_string());
+                    let authorization = request
+                        .headers()
+                        .get("Authorization")
+                        .map(|value| value.to_str().unwrap().to_string());
+                    requests.lock().push((
+                        request.method().clone(),
+                        session_id.clone(),
+                        authorization,
+                    ));
+
+                    let response = Response::builder().header(SESSION_ID_HEADER, "session-1");
+                    Ok(if request.method() != Method::POST {
+                        response.status(405).body(AsyncBody::empty()).unwrap()
+                    } else if session_id.is_none() {
+                        response
+                            .header("Content-Type", "application/json")
+                            .body(r#"{"jsonrpc":"2.0","id":0,"result":{}}"#.into())
+                            .unwrap()
+                    } else {
+                        response
+                            .header("Content-Type", "text/event-stream")
+                            .body("data: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n\n".into())
+                            .unwrap()
+                    })
+                }
+            }
+        });
+
+        let options = HttpTransportOptions::from_settings(&RemoteServerConfig {
+            url: "http://localhost:8080/mcp".into(),
+            transport: Default::default(),
+            headers: Default::default(),
+            bearer_token: Some("secret".into()),
+        })
+        .unwrap();
+        let transport = StreamableHttpTransport::new(http_client, options, &cx.to_async());
+        let mut inbound = transport.receive();
+
+        transport.send("{}".into()).await.unwrap();
+        assert_eq!(transport.session_id().as_deref(), Some("session-1"));
+        assert_eq!(
+            inbound.next().await.unwrap(),
+            r#"{"jsonrpc":"2.0","id":0,"result":{}}"#
+        );
+
+        transport.send("{}".into()).await.unwrap();
+        assert_eq!(
+            inbound.next().await.unwrap(),
+            r#"{"jsonrpc":"2.0","id":1,"result":{}}"#
+        );
+        cx.run_until_parked();
+
+        let bearer = Some("Bearer secret".to_string());
+        let requests = requests.lock();
+        assert_eq!(
+            requests
+                .iter()
+                .filter(|(method, _, _)| method == Method::POST)
+                .collect::<Vec<_>>(),
+            &[
+                &(Method::POST, None, bearer.clone()),
+                &(Method::POST, Some("session-1".into()), bearer.clone()),
+            ]
+        );
+        assert!(requests.contains(&(Method::GET, Some("session-1".into()), bearer)));
+    }
+}
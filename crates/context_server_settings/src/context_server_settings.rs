@@ -18,6 +18,16 @@ pub struct ServerConfig {
     ///
     /// This will override the command set by an extension.
     pub command: Option<ServerCommand>,
+    /// Connect to a context server over HTTP instead of spawning a local process.
+    ///
+    /// When set, `command` is ignored.
+    pub remote: Option<RemoteServerConfig>,
+    /// Whether to send sampling requests from this context server to the
+    /// language model without asking for confirmation first.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub auto_approve_sampling: bool,
     /// The settings for this context server.
     ///
     /// Consult the documentation for the context server to see what settings
@@ -40,6 +50,33 @@ pub struct ServerCommand {
     pub env: Option<HashMap<String, String>>,
 }
 
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, JsonSchema, Debug)]
+pub struct RemoteServerConfig {
+    /// The URL of the server's MCP endpoint.
+    pub url: String,
+    /// The HTTP transport spoken by the server.
+    ///
+    /// Default: streamable_http
+    #[serde(default)]
+    pub transport: RemoteTransport,
+    /// Additional headers to send with every request.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// A token to send in the `Authorization: Bearer` header.
+    pub bearer_token: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteTransport {
+    /// The Streamable HTTP transport, where every message is POSTed to a single endpoint.
+    #[default]
+    StreamableHttp,
+    /// The legacy HTTP+SSE transport, where server messages arrive on a long-lived
+    /// event stream and client messages are POSTed to an endpoint announced on it.
+    Sse,
+}
+
 #[derive(Deserialize, Serialize, Default, Clone, PartialEq, Eq, JsonSchema, Debug)]
 pub struct ContextServerSettings {
     /// Settings for context servers used in the Assistant.
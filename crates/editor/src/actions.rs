@@ -193,6 +193,33 @@ pub struct SpawnNearestTask {
     pub reveal: task::RevealStrategy,
 }
 
+/// Where a keyboard macro is replayed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayMacroAt {
+    /// Replay the macro once, starting from the current selections.
+    #[default]
+    Cursor,
+    /// Replay the macro separately at each selection.
+    EveryCursor,
+    /// Replay the macro separately at each buffer search match.
+    EverySearchMatch,
+}
+
+#[derive(PartialEq, Clone, Deserialize, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReplayMacro {
+    /// The name of a macro saved in the macros file.
+    /// Defaults to the most recently recorded macro.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// How many times to replay the macro in a row.
+    #[serde(default)]
+    pub times: Option<usize>,
+    #[serde(default)]
+    pub at: ReplayMacroAt,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Default)]
 pub enum UuidVersion {
     #[default]
@@ -219,6 +246,7 @@ impl_actions!(
         MoveToBeginningOfLine,
         MoveToEndOfLine,
         MoveUpByLines,
+        ReplayMacro,
         SelectDownByLines,
         SelectNext,
         SelectPrevious,
@@ -356,6 +384,7 @@ actions!(
         PageUp,
         Paste,
         PreviousEditPrediction,
+        RecordMacro,
         Redo,
         RedoSelection,
         Rename,
@@ -364,13 +393,21 @@ actions!(
         ReverseLines,
         RevertFile,
         ReloadFile,
+        ReplayMacroAtEveryCursor,
+        ReplayMacroAtEverySearchMatch,
+        ReplayNamedMacro,
         Rewrap,
+        SaveMacro,
         ScrollCursorBottom,
         ScrollCursorCenter,
         ScrollCursorCenterTopBottom,
         ScrollCursorTop,
         SelectAll,
         SelectAllMatches,
+        SelectColumnsDown,
+        SelectColumnsLeft,
+        SelectColumnsRight,
+        SelectColumnsUp,
         SelectToStartOfExcerpt,
         SelectToStartOfNextExcerpt,
         SelectToEndOfExcerpt,
@@ -402,6 +439,7 @@ actions!(
         SortLinesCaseSensitive,
         SplitSelectionIntoLines,
         StopLanguageServer,
+        StopRecordingMacro,
         SwitchSourceHeader,
         Tab,
         Backtab,
@@ -0,0 +1,114 @@
+use std::ops::Range;
+
+use collections::HashMap;
+use gpui::App;
+use language::{BufferSnapshot, language_settings::language_settings};
+use multi_buffer::{MultiBufferRow, MultiBufferSnapshot};
+use text::{Bias, Point};
+
+use crate::{DisplayPoint, DisplayRow, Editor, EditorSnapshot};
+
+/// The nesting depths of the brackets around the visible rows, kept until the
+/// buffer or its syntax tree changes.
+#[derive(Default)]
+pub struct BracketDepthsCache {
+    version: Option<(usize, usize)>,
+    range: Option<Range<usize>>,
+    brackets: Vec<(Range<usize>, usize)>,
+    block_depths: HashMap<MultiBufferRow, Option<usize>>,
+}
+
+impl BracketDepthsCache {
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn invalidate_if_stale(&mut self, snapshot: &MultiBufferSnapshot) {
+        let version = Some((
+            snapshot.edit_count(),
+            snapshot.non_text_state_update_count(),
+        ));
+        if self.version != version {
+            *self = Self {
+                version,
+                ..Self::default()
+            };
+        }
+    }
+}
+
+impl Editor {
+    /// Returns the brackets on the given display rows, in languages that have
+    /// `colorize_brackets` enabled, along with how deeply each one is nested.
+    pub(crate) fn bracket_depths(
+        &mut self,
+        rows: Range<DisplayRow>,
+        snapshot: &EditorSnapshot,
+        cx: &App,
+    ) -> Vec<(Range<usize>, usize)> {
+        let buffer = &snapshot.buffer_snapshot;
+        let range = buffer.point_to_offset(
+            snapshot.display_point_to_point(DisplayPoint::new(rows.start, 0), Bias::Left),
+        )
+            ..buffer.point_to_offset(
+                snapshot.display_point_to_point(DisplayPoint::new(rows.end, 0), Bias::Right),
+            );
+
+        let cache = &mut self.bracket_depths_cache;
+        cache.invalidate_if_stale(buffer);
+        if !cache.range.as_ref().is_some_and(|cached_range| {
+            cached_range.start <= range.start && cached_range.end >= range.end
+        }) {
+            // Cover the text around the requested range too, so that scrolling
+            // doesn't query the syntax tree again.
+            let margin = range.len();
+            let cached_range = buffer.clip_offset(range.start.saturating_sub(margin), Bias::Left)
+                ..buffer.clip_offset(range.end + margin, Bias::Right);
+            cache.brackets = buffer
+                .bracket_depths(cached_range.clone(), |buffer| {
+                    colorization_enabled(buffer, cx)
+                })
+                .collect();
+            cache.range = Some(cached_range);
+        }
+
+        let start = cache
+            .brackets
+            .partition_point(|(bracket, _)| bracket.end <= range.start);
+        let end = cache
+            .brackets
+            .partition_point(|(bracket, _)| bracket.start < range.end);
+        cache.brackets[start..end].to_vec()
+    }
+
+    /// Returns how deeply the bracket pair enclosing the indented block that
+    /// starts at the given row is nested, i.e. the depth of the last bracket
+    /// left open on the line above it.
+    pub(crate) fn block_bracket_depth(
+        &mut self,
+        row: MultiBufferRow,
+        snapshot: &MultiBufferSnapshot,
+    ) -> Option<usize> {
+        let cache = &mut self.bracket_depths_cache;
+        cache.invalidate_if_stale(snapshot);
+        *cache.block_depths.entry(row).or_insert_with(|| {
+            let opening_row = MultiBufferRow(row.0.checked_sub(1)?);
+            let line = Point::new(opening_row.0, 0)
+                ..Point::new(opening_row.0, snapshot.line_len(opening_row));
+            let mut open_depths = Vec::new();
+            for (bracket, depth) in snapshot.bracket_depths(line, |_| true) {
+                match snapshot.chars_at(bracket.start).next() {
+                    Some('(' | '[' | '{') => open_depths.push(depth),
+                    _ => {
+                        open_depths.pop();
+                    }
+                }
+            }
+            open_depths.pop()
+        })
+    }
+}
+
+fn colorization_enabled(buffer: &BufferSnapshot, cx: &App) -> bool {
+    language_settings(buffer.language().map(|l| l.name()), buffer.file(), cx).colorize_brackets
+}
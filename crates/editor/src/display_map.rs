@@ -67,6 +67,7 @@ use tab_map::{TabMap, TabSnapshot};
 use text::{BufferId, LineIndent};
 use ui::{SharedString, px};
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 use wrap_map::{WrapMap, WrapSnapshot};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -614,6 +615,7 @@ pub enum ChunkReplacement {
     Str(SharedString),
 }
 
+#[derive(Clone)]
 pub struct HighlightedChunk<'a> {
     pub text: &'a str,
     pub style: Option<HighlightStyle>,
@@ -1118,6 +1120,54 @@ impl DisplaySnapshot {
         self.block_snapshot.sticky_header_excerpt(row)
     }
 
+    /// Returns the outline items enclosing the top of the viewport whose first
+    /// lines have been scrolled out of view, outermost first.
+    ///
+    /// Every pinned scope hides one more row at the top of the viewport, so
+    /// each nested scope is looked up at the row below the previous ones.
+    /// `enclosing_items` returns the ranges of the outline items containing a
+    /// buffer offset, outermost first.
+    pub fn sticky_scopes(
+        &self,
+        scroll_top: f32,
+        max_depth: usize,
+        mut enclosing_items: impl FnMut(usize) -> Vec<Range<Anchor>>,
+    ) -> Vec<StickyScope> {
+        let top_row = scroll_top as u32;
+        let max_row = self.max_point().row();
+        let mut scopes = Vec::<StickyScope>::new();
+        while scopes.len() < max_depth {
+            let row = DisplayRow(top_row + scopes.len() as u32);
+            if row > max_row {
+                break;
+            }
+
+            let offset = DisplayPoint::new(row, 0).to_offset(self, Bias::Left);
+            let Some(item) = enclosing_items(offset).into_iter().nth(scopes.len()) else {
+                break;
+            };
+            let start_row = item.start.to_display_point(self).row();
+            if start_row >= row || scopes.last().is_some_and(|parent| start_row <= parent.row) {
+                break;
+            }
+
+            scopes.push(StickyScope {
+                start: item.start,
+                row: start_row,
+                end_row: item.end.to_display_point(self).row(),
+            });
+        }
+        scopes
+    }
+
+    /// Returns the ranges of the outline items containing the offset, outermost first.
+    pub fn enclosing_outline_items(&self, offset: usize) -> Vec<Range<Anchor>> {
+        self.buffer_snapshot
+            .symbols_containing(offset, None)
+            .map(|(_, items)| items.into_iter().map(|item| item.range).collect())
+            .unwrap_or_default()
+    }
+
     pub fn block_for_id(&self, id: BlockId) -> Option<Block> {
         self.block_snapshot.block_for_id(id)
     }
@@ -1173,6 +1223,59 @@ impl DisplaySnapshot {
         self.block_snapshot.line_len(BlockRow(row.0))
     }
 
+    /// The number of cells on screen before the given point, counting each column
+    /// past the end of its line as one cell.
+    pub fn screen_column(&self, point: DisplayPoint) -> u32 {
+        let line = self.line(point.row());
+        let column = point.column() as usize;
+        let width = line
+            .char_indices()
+            .take_while(|(ix, _)| *ix < column)
+            .map(|(_, ch)| char_width(ch))
+            .sum::<u32>();
+        width + column.saturating_sub(line.len()) as u32
+    }
+
+    /// The point in the given row that is displayed at the given number of cells
+    /// from the start of the row, along with how many cells past the end of the
+    /// line that is. The bias picks a side of characters that span the cell.
+    pub fn point_for_screen_column(
+        &self,
+        row: DisplayRow,
+        screen_column: u32,
+        bias: Bias,
+    ) -> (DisplayPoint, u32) {
+        let line = self.line(row);
+        let mut width = 0;
+        for (ix, ch) in line.char_indices() {
+            let ch_width = char_width(ch);
+            if width + ch_width > screen_column {
+                let column = if width == screen_column || bias == Bias::Left {
+                    ix
+                } else {
+                    ix + ch.len_utf8()
+                };
+                let point = self.clip_point(DisplayPoint::new(row, column as u32), bias);
+                return (point, 0);
+            }
+            width += ch_width;
+        }
+        let point = self.clip_point(DisplayPoint::new(row, line.len() as u32), bias);
+        (point, screen_column - width)
+    }
+
+    /// The number of cells on screen taken up by the given row.
+    pub fn line_width(&self, row: DisplayRow) -> u32 {
+        text_width(&self.line(row))
+    }
+
+    /// Whether the given row ends a line of the buffer, rather than being soft
+    /// wrapped onto the next row.
+    pub fn ends_buffer_line(&self, row: DisplayRow) -> bool {
+        let end = DisplayPoint::new(row, self.line_len(row)).to_point(self);
+        end.column == self.buffer_snapshot.line_len(MultiBufferRow(end.row))
+    }
+
     pub fn longest_row(&self) -> DisplayRow {
         DisplayRow(self.block_snapshot.longest_row())
     }
@@ -1318,6 +1421,18 @@ impl DisplaySnapshot {
     }
 }
 
+/// A scope whose first line is pinned to the top of the editor while
+/// scrolling through its body.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StickyScope {
+    /// The start of the scope's declaration.
+    pub start: Anchor,
+    /// The display row of the scope's first line.
+    pub row: DisplayRow,
+    /// The display row of the scope's last line.
+    pub end_row: DisplayRow,
+}
+
 #[derive(Copy, Clone, Default, Eq, Ord, PartialOrd, PartialEq)]
 pub struct DisplayPoint(BlockPoint);
 
@@ -1450,6 +1565,17 @@ impl ToDisplayPoint for Anchor {
     }
 }
 
+/// The number of cells on screen that a character takes up, where wide characters
+/// take two and combining characters none.
+fn char_width(ch: char) -> u32 {
+    ch.width().unwrap_or(0) as u32
+}
+
+/// The number of cells on screen that the given text takes up.
+pub fn text_width(text: &str) -> u32 {
+    text.chars().map(char_width).sum()
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -2190,6 +2316,71 @@ pub mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_sticky_scopes(cx: &mut gpui::TestAppContext) {
+        let text = r#"
+            mod a {
+                fn b() {
+                    one();
+                    two();
+                    three();
+                }
+            }
+        "#
+        .unindent();
+
+        let language = Arc::new(
+            Language::new(
+                LanguageConfig {
+                    name: "Rust".into(),
+                    ..Default::default()
+                },
+                Some(tree_sitter_rust::LANGUAGE.into()),
+            )
+            .with_outline_query(
+                r#"
+                (mod_item "mod" @context name: (_) @name) @item
+                (function_item "fn" @context name: (_) @name) @item
+                "#,
+            )
+            .unwrap(),
+        );
+
+        cx.update(|cx| init_test(cx, |_| {}));
+
+        let buffer = cx.new(|cx| Buffer::local(text, cx).with_language(language, cx));
+        cx.condition(&buffer, |buf, _| !buf.is_parsing()).await;
+        let buffer = cx.new(|cx| MultiBuffer::singleton(buffer, cx));
+
+        let map = cx.new(|cx| {
+            DisplayMap::new(
+                buffer,
+                font("Courier"),
+                px(16.0),
+                None,
+                1,
+                1,
+                FoldPlaceholder::test(),
+                cx,
+            )
+        });
+        let snapshot = map.update(cx, |map, cx| map.snapshot(cx));
+        let sticky_rows = |scroll_top: f32, max_depth: usize| {
+            snapshot
+                .sticky_scopes(scroll_top, max_depth, |offset| {
+                    snapshot.enclosing_outline_items(offset)
+                })
+                .into_iter()
+                .map(|scope| (scope.row.0, scope.end_row.0))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sticky_rows(0., 5), []);
+        assert_eq!(sticky_rows(1., 5), [(0, 6), (1, 5)]);
+        assert_eq!(sticky_rows(3., 5), [(0, 6), (1, 5)]);
+        assert_eq!(sticky_rows(3., 1), [(0, 6)]);
+    }
+
     #[gpui::test]
     async fn test_chunks_with_diagnostics_across_blocks(cx: &mut gpui::TestAppContext) {
         cx.background_executor
@@ -14,6 +14,7 @@
 //! If you're looking to improve Vim mode, you should check out Vim crate that wraps Editor and overrides its behavior.
 pub mod actions;
 mod blink_manager;
+mod bracket_colorization;
 mod clangd_ext;
 mod code_context_menus;
 pub mod display_map;
@@ -28,6 +29,8 @@ mod indent_guides;
 mod inlay_hint_cache;
 pub mod items;
 mod jsx_tag_auto_close;
+pub mod keyboard_macro;
+mod large_file_banner;
 mod linked_editing_ranges;
 mod lsp_ext;
 mod mouse_context_menu;
@@ -44,6 +47,7 @@ mod editor_tests;
 #[cfg(test)]
 mod inline_completion_tests;
 mod signature_help;
+mod sticky_scopes;
 #[cfg(any(test, feature = "test-support"))]
 pub mod test;
 
@@ -52,6 +56,7 @@ pub use actions::{AcceptEditPrediction, OpenExcerpts, OpenExcerptsSplit};
 use aho_corasick::AhoCorasick;
 use anyhow::{Context as _, Result, anyhow};
 use blink_manager::BlinkManager;
+use bracket_colorization::BracketDepthsCache;
 use buffer_diff::DiffHunkStatus;
 use client::{Collaborator, ParticipantIndex};
 use clock::ReplicaId;
@@ -126,6 +131,7 @@ use project::{
 };
 
 pub use git::blame::BlameRenderer;
+pub use large_file_banner::LargeFileBanner;
 pub use proposed_changes_editor::{
     ProposedChangeLocation, ProposedChangesEditor, ProposedChangesEditorToolbar,
 };
@@ -163,7 +169,7 @@ use rand::prelude::*;
 use rpc::{ErrorExt, proto::*};
 use scroll::{Autoscroll, OngoingScroll, ScrollAnchor, ScrollManager, ScrollbarAutoHide};
 use selections_collection::{
-    MutableSelectionsCollection, SelectionsCollection, resolve_selections,
+    ColumnarSelection, MutableSelectionsCollection, SelectionsCollection, resolve_selections,
 };
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsLocation, SettingsStore, update_settings_file};
@@ -182,6 +188,7 @@ use std::{
     rc::Rc,
     time::{Duration, Instant},
 };
+use sticky_scopes::StickyScopesCache;
 pub use sum_tree::Bias;
 use sum_tree::TreeMap;
 use text::{BufferId, FromAnchor, OffsetUtf16, Rope};
@@ -320,6 +327,7 @@ pub fn init(cx: &mut App) {
         },
     )
     .detach();
+    keyboard_macro::init(cx);
 
     cx.on_action(move |_: &workspace::NewFile, cx| {
         let app_state = workspace::AppState::global(cx);
@@ -692,7 +700,7 @@ pub struct Editor {
     /// When inline assist editors are linked, they all render cursors because
     /// typing enters text into each of them, even the ones that aren't focused.
     pub(crate) show_cursor_when_unfocused: bool,
-    columnar_selection_tail: Option<Anchor>,
+    columnar_selection_tail: Option<(Anchor, u32)>,
     add_selections_state: Option<AddSelectionsState>,
     select_next_state: Option<SelectNextState>,
     select_prev_state: Option<SelectNextState>,
@@ -737,6 +745,8 @@ pub struct Editor {
     gutter_highlights: TreeMap<TypeId, GutterHighlight>,
     scrollbar_marker_state: ScrollbarMarkerState,
     active_indent_guides_state: ActiveIndentGuidesState,
+    bracket_depths_cache: BracketDepthsCache,
+    sticky_scopes_cache: StickyScopesCache,
     nav_history: Option<ItemNavHistory>,
     context_menu: RefCell<Option<CodeContextMenu>>,
     context_menu_options: Option<ContextMenuOptions>,
@@ -1090,6 +1100,9 @@ pub struct ClipboardSelection {
     pub is_entire_line: bool,
     /// The indentation of the first line when this content was originally copied.
     pub first_line_indent: u32,
+    /// Whether this was one row of a columnar selection.
+    #[serde(default)]
+    pub is_columnar: bool,
 }
 
 // selections, scroll behavior, was newest selection reversed
@@ -1506,6 +1519,8 @@ impl Editor {
             gutter_highlights: TreeMap::default(),
             scrollbar_marker_state: ScrollbarMarkerState::default(),
             active_indent_guides_state: ActiveIndentGuidesState::default(),
+            bracket_depths_cache: BracketDepthsCache::default(),
+            sticky_scopes_cache: StickyScopesCache::default(),
             nav_history: None,
             context_menu: RefCell::new(None),
             context_menu_options: None,
@@ -2841,12 +2856,26 @@ impl Editor {
             });
         }
 
-        let tail = self.selections.newest::<Point>(cx).tail();
-        self.columnar_selection_tail = Some(display_map.buffer_snapshot.anchor_before(tail));
+        // Extending an existing columnar selection keeps its tail, which may be past
+        // the end of its line.
+        let (tail, tail_column) = match self.selections.columnar() {
+            Some(columnar) if !reset => (columnar.tail, columnar.tail_column),
+            _ => {
+                let tail = self.selections.newest::<Point>(cx).tail();
+                let tail_column = if reset {
+                    display_map.screen_column(DisplayPoint::new(position.row(), goal_column))
+                } else {
+                    display_map.screen_column(tail.to_display_point(&display_map))
+                };
+                (display_map.buffer_snapshot.anchor_before(tail), tail_column)
+            }
+        };
+        self.columnar_selection_tail = Some((tail, tail_column));
 
         if !reset {
             self.select_columns(
-                tail.to_display_point(&display_map),
+                tail,
+                tail_column,
                 position,
                 goal_column,
                 &display_map,
@@ -2866,9 +2895,16 @@ impl Editor {
     ) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
 
-        if let Some(tail) = self.columnar_selection_tail.as_ref() {
-            let tail = tail.to_display_point(&display_map);
-            self.select_columns(tail, position, goal_column, &display_map, window, cx);
+        if let Some((tail, tail_column)) = self.columnar_selection_tail {
+            self.select_columns(
+                tail,
+                tail_column,
+                position,
+                goal_column,
+                &display_map,
+                window,
+                cx,
+            );
         } else if let Some(mut pending) = self.selections.pending_anchor() {
             let buffer = self.buffer.read(cx).snapshot(cx);
             let head;
@@ -2967,43 +3003,21 @@ impl Editor {
 
     fn select_columns(
         &mut self,
-        tail: DisplayPoint,
+        tail: Anchor,
+        tail_column: u32,
         head: DisplayPoint,
         goal_column: u32,
         display_map: &DisplaySnapshot,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let start_row = cmp::min(tail.row(), head.row());
-        let end_row = cmp::max(tail.row(), head.row());
-        let start_column = cmp::min(tail.column(), goal_column);
-        let end_column = cmp::max(tail.column(), goal_column);
-        let reversed = start_column < tail.column();
-
-        let selection_ranges = (start_row.0..=end_row.0)
-            .map(DisplayRow)
-            .filter_map(|row| {
-                if start_column <= display_map.line_len(row) && !display_map.is_block_line(row) {
-                    let start = display_map
-                        .clip_point(DisplayPoint::new(row, start_column), Bias::Left)
-                        .to_point(display_map);
-                    let end = display_map
-                        .clip_point(DisplayPoint::new(row, end_column), Bias::Right)
-                        .to_point(display_map);
-                    if reversed {
-                        Some(end..start)
-                    } else {
-                        Some(start..end)
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
-
-        self.change_selections(None, window, cx, |s| {
-            s.select_ranges(selection_ranges);
-        });
+        let columnar = ColumnarSelection {
+            tail,
+            head: display_map.display_point_to_anchor(head, Bias::Left),
+            tail_column,
+            head_column: display_map.screen_column(DisplayPoint::new(head.row(), goal_column)),
+        };
+        self.change_selections(None, window, cx, |s| s.select_columnar(columnar));
         cx.notify();
     }
 
@@ -3149,6 +3163,14 @@ impl Editor {
 
         self.hide_mouse_cursor(&HideMouseCursorOrigin::TypingAction);
 
+        if let Some(columnar) = self.selections.columnar().cloned() {
+            let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+            let rows = columnar.text_rows(&display_map);
+            let lines = vec![&*text; rows.len()];
+            self.replace_columns(&rows, columnar.columns(), &lines, window, cx);
+            return;
+        }
+
         let selections = self.selections.all_adjusted(cx);
         let mut bracket_inserted = false;
         let mut edits = Vec::new();
@@ -8080,6 +8102,10 @@ impl Editor {
 
     pub fn backspace(&mut self, _: &Backspace, window: &mut Window, cx: &mut Context<Self>) {
         self.hide_mouse_cursor(&HideMouseCursorOrigin::TypingAction);
+        if let Some(columnar) = self.selections.columnar().cloned() {
+            self.delete_columns(columnar, true, window, cx);
+            return;
+        }
         self.transact(window, cx, |this, window, cx| {
             this.select_autoclose_pair(window, cx);
             let mut linked_ranges = HashMap::<_, Vec<_>>::default();
@@ -8176,6 +8202,10 @@ impl Editor {
 
     pub fn delete(&mut self, _: &Delete, window: &mut Window, cx: &mut Context<Self>) {
         self.hide_mouse_cursor(&HideMouseCursorOrigin::TypingAction);
+        if let Some(columnar) = self.selections.columnar().cloned() {
+            self.delete_columns(columnar, false, window, cx);
+            return;
+        }
         self.transact(window, cx, |this, window, cx| {
             this.change_selections(Some(Autoscroll::fit()), window, cx, |s| {
                 s.move_with(|map, selection| {
@@ -9939,6 +9969,14 @@ impl Editor {
     }
 
     pub fn cut_common(&mut self, window: &mut Window, cx: &mut Context<Self>) -> ClipboardItem {
+        if let Some(columnar) = self.selections.columnar().cloned() {
+            let item = self.columnar_clipboard_item(&columnar, cx);
+            let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+            let rows = columnar.text_rows(&display_map);
+            self.replace_columns(&rows, columnar.columns(), &vec![""; rows.len()], window, cx);
+            return item;
+        }
+
         let mut text = String::new();
         let buffer = self.buffer.read(cx).snapshot(cx);
         let mut selections = self.selections.all::<Point>(cx);
@@ -9973,6 +10011,7 @@ impl Editor {
                     first_line_indent: buffer
                         .indent_size_for_line(MultiBufferRow(selection.start.row))
                         .len,
+                    is_columnar: false,
                 });
             }
         }
@@ -10033,6 +10072,11 @@ impl Editor {
     }
 
     fn do_copy(&self, strip_leading_indents: bool, cx: &mut Context<Self>) {
+        if let Some(columnar) = self.selections.columnar() {
+            cx.write_to_clipboard(self.columnar_clipboard_item(columnar, cx));
+            return;
+        }
+
         let selections = self.selections.all::<Point>(cx);
         let buffer = self.buffer.read(cx).read(cx);
         let mut text = String::new();
@@ -10096,6 +10140,7 @@ impl Editor {
                         first_line_indent: buffer
                             .indent_size_for_line(MultiBufferRow(trimmed_range.start.row))
                             .len,
+                        is_columnar: false,
                     });
                 }
             }
@@ -10119,6 +10164,23 @@ impl Editor {
             return;
         }
 
+        if let Some(clipboard_selections) = clipboard_selections.as_ref().filter(|selections| {
+            !selections.is_empty() && selections.iter().all(|selection| selection.is_columnar)
+        }) {
+            let mut start_offset = 0;
+            let lines = clipboard_selections
+                .iter()
+                .map(|selection| {
+                    let end_offset = start_offset + selection.len;
+                    let line = &text[start_offset..end_offset];
+                    start_offset = end_offset + 1;
+                    line
+                })
+                .collect::<Vec<_>>();
+            self.paste_columns(&lines, window, cx);
+            return;
+        }
+
         let clipboard_text = Cow::Borrowed(text);
 
         self.transact(window, cx, |this, window, cx| {
@@ -11514,6 +11576,253 @@ impl Editor {
         });
     }
 
+    pub fn select_columns_up(
+        &mut self,
+        _: &SelectColumnsUp,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_columnar_selection(-1, 0, window, cx);
+    }
+
+    pub fn select_columns_down(
+        &mut self,
+        _: &SelectColumnsDown,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_columnar_selection(1, 0, window, cx);
+    }
+
+    pub fn select_columns_left(
+        &mut self,
+        _: &SelectColumnsLeft,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_columnar_selection(0, -1, window, cx);
+    }
+
+    pub fn select_columns_right(
+        &mut self,
+        _: &SelectColumnsRight,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_columnar_selection(0, 1, window, cx);
+    }
+
+    /// Moves the head of the columnar selection, first turning the newest selection
+    /// into one if there isn't one. Its column may move past the end of lines.
+    fn extend_columnar_selection(
+        &mut self,
+        row_delta: i32,
+        column_delta: i32,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.hide_mouse_cursor(&HideMouseCursorOrigin::MovementAction);
+
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let mut columnar = match self.selections.columnar() {
+            Some(columnar) => columnar.clone(),
+            None => {
+                let newest = self.selections.newest_display(cx);
+                ColumnarSelection {
+                    tail: display_map.display_point_to_anchor(newest.tail(), Bias::Left),
+                    head: display_map.display_point_to_anchor(newest.head(), Bias::Left),
+                    tail_column: display_map.screen_column(newest.tail()),
+                    head_column: display_map.screen_column(newest.head()),
+                }
+            }
+        };
+
+        let mut head_row = columnar.head.to_display_point(&display_map).row();
+        let max_row = display_map.max_point().row();
+        let mut row = head_row;
+        // Blocks can't be selected, so move past them to the next row of text.
+        while (row_delta < 0 && row.0 > 0) || (row_delta > 0 && row < max_row) {
+            row = DisplayRow(row.0.saturating_add_signed(row_delta));
+            if !display_map.is_block_line(row) {
+                head_row = row;
+                break;
+            }
+        }
+
+        columnar.head =
+            display_map.display_point_to_anchor(DisplayPoint::new(head_row, 0), Bias::Left);
+        columnar.head_column = columnar.head_column.saturating_add_signed(column_delta);
+        self.change_selections(Some(Autoscroll::fit()), window, cx, |s| {
+            s.select_columnar(columnar)
+        });
+    }
+
+    /// Replaces the given columns of each row with the corresponding line, padding
+    /// with spaces the rows that end before the columns start, and the lines that
+    /// are shorter than the others when there's text after them. Rows that are soft
+    /// wrapped before the columns start are left as they are. Lines beyond the last
+    /// row are added below the end of the buffer. Leaves a columnar selection with
+    /// its cursors after the inserted text.
+    fn replace_columns(
+        &mut self,
+        rows: &[DisplayRow],
+        columns: Range<u32>,
+        lines: &[&str],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.read_only(cx) || rows.is_empty() {
+            return;
+        }
+
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let buffer = &display_map.buffer_snapshot;
+        let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+        let padding = |len: u32| " ".repeat(len as usize);
+
+        let mut edits = Vec::new();
+        for (&row, line) in rows.iter().zip(lines) {
+            let (start, overshoot) =
+                display_map.point_for_screen_column(row, columns.start, Bias::Left);
+            if overshoot > 0 && !display_map.ends_buffer_line(row) {
+                continue;
+            }
+            let (end, _) = display_map.point_for_screen_column(row, columns.end, Bias::Right);
+            let start = start.to_point(&display_map);
+            let end = end.to_point(&display_map);
+            let mut text = String::new();
+            if !line.is_empty() {
+                text.push_str(&padding(overshoot));
+                text.push_str(line);
+                if columns.end < display_map.line_width(row) {
+                    text.push_str(&padding(width - text_width(line)));
+                }
+            }
+            if start != end || !text.is_empty() {
+                edits.push((start..end, text));
+            }
+        }
+
+        let mut appended_lines = String::new();
+        for line in lines.iter().skip(rows.len()) {
+            appended_lines.push('\n');
+            appended_lines.push_str(&padding(columns.start));
+            appended_lines.push_str(line);
+        }
+        let bottom = if appended_lines.is_empty() {
+            let last_row = rows[rows.len() - 1];
+            display_map.display_point_to_anchor(DisplayPoint::new(last_row, 0), Bias::Left)
+        } else {
+            let max_point = buffer.max_point();
+            match edits.last_mut() {
+                Some((range, text)) if range.end == max_point => text.push_str(&appended_lines),
+                _ => edits.push((max_point..max_point, appended_lines)),
+            }
+            Anchor::max()
+        };
+        let top = display_map.display_point_to_anchor(DisplayPoint::new(rows[0], 0), Bias::Left);
+        let column = columns.start + width;
+
+        self.transact(window, cx, |this, window, cx| {
+            this.buffer
+                .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+            this.change_selections(Some(Autoscroll::fit()), window, cx, |s| {
+                s.select_columnar(ColumnarSelection {
+                    tail: top,
+                    head: bottom,
+                    tail_column: column,
+                    head_column: column,
+                })
+            });
+        });
+    }
+
+    /// Deletes the columns of a columnar selection or, when it's empty, the column
+    /// before or after its cursors. Rows that end before those columns are left as
+    /// they are.
+    fn delete_columns(
+        &mut self,
+        columnar: ColumnarSelection,
+        backward: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mut columns = columnar.columns();
+        if columns.is_empty() {
+            if backward {
+                if columns.start == 0 {
+                    return;
+                }
+                columns.start -= 1;
+            } else {
+                columns.end += 1;
+            }
+        }
+
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let rows = columnar.text_rows(&display_map);
+        self.replace_columns(&rows, columns, &vec![""; rows.len()], window, cx);
+    }
+
+    /// Copies each row of a columnar selection as a line, marked so that pasting
+    /// them inserts them as a block again.
+    fn columnar_clipboard_item(
+        &self,
+        columnar: &ColumnarSelection,
+        cx: &mut Context<Self>,
+    ) -> ClipboardItem {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let buffer = &display_map.buffer_snapshot;
+        let mut text = String::new();
+        let mut clipboard_selections = Vec::new();
+        for (ix, range) in columnar.row_ranges(&display_map).into_iter().enumerate() {
+            if ix > 0 {
+                text.push('\n');
+            }
+            let mut len = 0;
+            for chunk in buffer.text_for_range(range.clone()) {
+                text.push_str(chunk);
+                len += chunk.len();
+            }
+            clipboard_selections.push(ClipboardSelection {
+                len,
+                is_entire_line: false,
+                first_line_indent: buffer
+                    .indent_size_for_line(MultiBufferRow(range.start.row))
+                    .len,
+                is_columnar: true,
+            });
+        }
+        ClipboardItem::new_string_with_json_metadata(text, clipboard_selections)
+    }
+
+    /// Pastes lines copied from a columnar selection as a block, each into the next
+    /// row at the same column. The block replaces the columnar selection if there is
+    /// one, or is inserted at the newest cursor otherwise.
+    fn paste_columns(&mut self, lines: &[&str], window: &mut Window, cx: &mut Context<Self>) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let (top_row, columns) = match self.selections.columnar() {
+            Some(columnar) => (columnar.rows(&display_map).start, columnar.columns()),
+            None => {
+                let head = self.selections.newest_display(cx).head();
+                let column = display_map.screen_column(head);
+                (head.row(), column..column)
+            }
+        };
+        // Rows that are soft wrapped before the column can't take text at it, so the
+        // block skips them.
+        let rows = (top_row.0..=display_map.max_point().row().0)
+            .map(DisplayRow)
+            .filter(|row| {
+                !display_map.is_block_line(*row)
+                    && (display_map.ends_buffer_line(*row)
+                        || display_map.line_width(*row) >= columns.start)
+            })
+            .take(lines.len())
+            .collect::<Vec<_>>();
+        self.replace_columns(&rows, columns, lines, window, cx);
+    }
+
     pub fn add_selection_above(
         &mut self,
         _: &AddSelectionAbove,
@@ -15562,6 +15871,8 @@ impl Editor {
                         diff_base_byte_range: hunk.diff_base_byte_range,
                         secondary_status: hunk.secondary_status,
                         range: Point::zero()..Point::zero(), // unused
+                        buffer_word_diffs: Vec::new(),       // unused
+                        base_word_diffs: Vec::new(),         // unused
                     })
                     .collect::<Vec<_>>(),
                 &buffer_snapshot,
@@ -15844,7 +16155,20 @@ impl Editor {
 
     pub fn soft_wrap_mode(&self, cx: &App) -> SoftWrap {
         let settings = self.buffer.read(cx).language_settings(cx);
-        let mode = self.soft_wrap_mode_override.unwrap_or(settings.soft_wrap);
+        let mode = self.soft_wrap_mode_override.unwrap_or_else(|| {
+            // Wrapping lays out every line of the file, so large files aren't
+            // wrapped unless asked to.
+            let is_large_file = self
+                .buffer
+                .read(cx)
+                .as_singleton()
+                .is_some_and(|buffer| buffer.read(cx).large_file_mode());
+            if is_large_file {
+                language_settings::SoftWrap::None
+            } else {
+                settings.soft_wrap
+            }
+        });
         match mode {
             language_settings::SoftWrap::PreferLine | language_settings::SoftWrap::None => {
                 SoftWrap::None
@@ -17304,6 +17628,21 @@ impl Editor {
                 cx.emit(EditorEvent::Reparsed(*buffer_id));
                 cx.notify();
             }
+            multi_buffer::Event::LargeFileModeChanged(buffer_id) => {
+                if let Some((project, buffer)) = self
+                    .project
+                    .as_ref()
+                    .zip(multibuffer.read(cx).buffer(*buffer_id))
+                {
+                    if !buffer.read(cx).large_file_mode()
+                        && self.buffer.read(cx).diff_for(*buffer_id).is_none()
+                    {
+                        get_uncommitted_diff_for_buffer(project, [buffer], self.buffer.clone(), cx)
+                            .detach();
+                    }
+                }
+                cx.notify();
+            }
             multi_buffer::Event::DirtyChanged => cx.emit(EditorEvent::DirtyChanged),
             multi_buffer::Event::Saved => cx.emit(EditorEvent::Saved),
             multi_buffer::Event::FileHandleChanged
@@ -17330,6 +17669,7 @@ impl Editor {
     }
 
     fn settings_changed(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.bracket_depths_cache.clear();
         self.tasks_update_task = Some(self.refresh_runnables(window, cx));
         self.update_edit_prediction_settings(cx);
         self.refresh_inline_completion(true, false, window, cx);
@@ -19741,6 +20081,7 @@ impl EntityInputHandler for Editor {
             cx.emit(EditorEvent::InputIgnored { text: text.into() });
             return;
         }
+        keyboard_macro::record_input(text, cx);
 
         self.transact(window, cx, |this, window, cx| {
             let new_selected_ranges = if let Some(range_utf16) = range_utf16 {
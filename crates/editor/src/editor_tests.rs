@@ -5769,6 +5769,167 @@ async fn test_add_selection_above_below(cx: &mut TestAppContext) {
     ));
 }
 
+#[gpui::test]
+async fn test_columnar_selection_in_virtual_space(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Typing into a columnar selection pads the lines that end before its columns.
+    cx.set_state(indoc! {"
+        abcdeˇf
+        ab
+        abcdefgh"});
+    cx.update_editor(|e, window, cx| {
+        e.select_columns_down(&SelectColumnsDown, window, cx);
+        e.select_columns_down(&SelectColumnsDown, window, cx);
+        e.handle_input("|", window, cx);
+    });
+    cx.assert_editor_state(indoc! {"
+        abcde|ˇf
+        ab   |ˇ
+        abcde|ˇfgh"});
+
+    cx.update_editor(|e, window, cx| e.backspace(&Backspace, window, cx));
+    cx.assert_editor_state(indoc! {"
+        abcdeˇf
+        ab   ˇ
+        abcdeˇfgh"});
+
+    // The selection's columns can extend past the end of a line.
+    cx.update_editor(|e, window, cx| {
+        e.select_columns_right(&SelectColumnsRight, window, cx);
+        e.select_columns_right(&SelectColumnsRight, window, cx);
+        assert_eq!(e.selections.columnar().map(|c| c.columns()), Some(5..7));
+        e.copy(&Copy, window, cx);
+    });
+    cx.assert_editor_state(indoc! {"
+        abcde«fˇ»
+        ab   ˇ
+        abcde«fgˇ»h"});
+
+    // Pasting the copied rows inserts them as a block, padding the shorter rows
+    // that have text after them and adding lines past the end of the buffer.
+    cx.set_state(indoc! {"
+        1ˇ2
+        1"});
+    cx.update_editor(|e, window, cx| {
+        e.paste(&Paste, window, cx);
+        assert_eq!(e.selections.columnar().map(|c| c.columns()), Some(3..3));
+    });
+    cx.assert_editor_state(indoc! {"
+        1f ˇ2
+        1ˇ
+         fgˇ"});
+
+    // Any other change to the selections ends the columnar selection.
+    cx.update_editor(|e, window, cx| {
+        e.move_left(&MoveLeft, window, cx);
+        assert!(e.selections.columnar().is_none());
+    });
+}
+
+#[gpui::test]
+async fn test_columnar_selection_with_wide_characters(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Columns are counted in cells on screen, so rows with multi-byte and wide
+    // characters line up with the rows around them.
+    cx.set_state(indoc! {"
+        éeˇ
+        中
+        é
+        abcdefgh"});
+    cx.update_editor(|e, window, cx| {
+        for _ in 0..3 {
+            e.select_columns_down(&SelectColumnsDown, window, cx);
+        }
+        for _ in 0..2 {
+            e.select_columns_right(&SelectColumnsRight, window, cx);
+        }
+        assert_eq!(e.selections.columnar().map(|c| c.columns()), Some(2..4));
+    });
+    cx.assert_editor_state(indoc! {"
+        éeˇ
+        中ˇ
+        éˇ
+        ab«cdˇ»efgh"});
+
+    cx.update_editor(|e, window, cx| e.handle_input("|", window, cx));
+    cx.assert_editor_state(indoc! {"
+        ée|ˇ
+        中|ˇ
+        é |ˇ
+        ab|ˇefgh"});
+}
+
+#[gpui::test]
+async fn test_columnar_selection_with_soft_wrap(cx: &mut TestAppContext) {
+    init_test(cx, |s| {
+        s.defaults.soft_wrap = Some(language_settings::SoftWrap::PreferredLineLength);
+        s.defaults.preferred_line_length = Some(16);
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+        aaaa bbbbbbbbbbbb
+        cc
+        ddddddddˇdd"});
+    assert_eq!(cx.display_text(), "aaaa \nbbbbbbbbbbbb\ncc\ndddddddddd");
+
+    // A row that's soft wrapped before the columns is left as it is, rather than
+    // being padded in the middle of its line, while a row that ends its line is.
+    cx.update_editor(|e, window, cx| {
+        for _ in 0..3 {
+            e.select_columns_up(&SelectColumnsUp, window, cx);
+        }
+        e.handle_input("|", window, cx);
+    });
+    assert_eq!(
+        cx.buffer_text(),
+        indoc! {"
+            aaaa bbbbbbbb|bbbb
+            cc      |
+            dddddddd|dd"}
+    );
+}
+
+#[gpui::test]
+async fn test_columnar_selection_with_alt_drag(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+        abcdef
+        x
+        abcdefˇ"});
+    let start = cx.pixel_position(indoc! {"
+        abˇcdef
+        x
+        abcdef"});
+    let end = cx.pixel_position(indoc! {"
+        abcdef
+        x
+        abcdˇef"});
+
+    // Dragging with alt held selects the rectangle between where the drag started
+    // and where it ends, replacing the other selections.
+    cx.simulate_mouse_down(start, MouseButton::Left, Modifiers::alt());
+    cx.simulate_mouse_move(end, MouseButton::Left, Modifiers::alt());
+    cx.simulate_mouse_up(end, MouseButton::Left, Modifiers::alt());
+    cx.update_editor(|e, _, _| {
+        assert_eq!(e.selections.columnar().map(|c| c.columns()), Some(2..4));
+    });
+    cx.assert_editor_state(indoc! {"
+        ab«cdˇ»ef
+        xˇ
+        ab«cdˇ»ef"});
+}
+
 #[gpui::test]
 async fn test_select_next(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -16342,6 +16503,123 @@ async fn test_indent_guide_with_expanded_diff_hunks(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_bracket_depths(cx: &mut TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.colorize_brackets = Some(true);
+    });
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(rust_lang()), cx));
+    cx.set_state(indoc! {"
+        fn main() {
+            let x = [1, (2)];
+            if true {
+                foo(|| {
+                    ˇ
+                });
+            }
+        }
+    "});
+    cx.run_until_parked();
+
+    let row_bracket_depths = |cx: &mut EditorTestContext| {
+        cx.update_editor(|editor, window, cx| {
+            let snapshot = editor.snapshot(window, cx);
+            editor
+                .bracket_depths(DisplayRow(1)..DisplayRow(2), &snapshot, cx)
+                .into_iter()
+                .map(|(_, depth)| depth)
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(row_bracket_depths(&mut cx), [1, 2, 2, 1]);
+
+    // Indented blocks take the depth of the last bracket left open above them.
+    cx.update_editor(|editor, window, cx| {
+        let snapshot = editor.snapshot(window, cx).buffer_snapshot;
+        let block_depths = (0..6)
+            .map(|row| editor.block_bracket_depth(MultiBufferRow(row), &snapshot))
+            .collect::<Vec<_>>();
+        assert_eq!(block_depths, [None, Some(0), None, Some(1), Some(3), None]);
+    });
+
+    // Cached depths are recomputed once the buffer changes.
+    cx.set_state(indoc! {"
+        fn main() {
+            let x = [1, ((2))];ˇ
+        }
+    "});
+    cx.run_until_parked();
+    assert_eq!(row_bracket_depths(&mut cx), [1, 2, 3, 3, 2, 1]);
+
+    // Brackets aren't colored in languages that don't have colorization enabled.
+    update_test_language_settings(&mut cx, |settings| {
+        settings.defaults.colorize_brackets = Some(false);
+    });
+    assert_eq!(row_bracket_depths(&mut cx), []);
+}
+
+#[gpui::test]
+async fn test_sticky_scopes(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                ..Default::default()
+            },
+            Some(tree_sitter_rust::LANGUAGE.into()),
+        )
+        .with_outline_query(
+            r#"
+            (mod_item "mod" @context name: (_) @name) @item
+            (function_item "fn" @context name: (_) @name) @item
+            "#,
+        )
+        .unwrap(),
+    );
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+    cx.set_state(indoc! {"
+        ˇmod a {
+            fn b() {
+                one();
+                two();
+                three();
+            }
+        }
+    "});
+    cx.run_until_parked();
+
+    let sticky_rows = |scroll_top: f32, cx: &mut EditorTestContext| {
+        cx.update_editor(|editor, window, cx| {
+            let snapshot = editor.snapshot(window, cx);
+            editor
+                .sticky_scopes(&snapshot.display_snapshot, scroll_top, 5)
+                .into_iter()
+                .map(|scope| (scope.row.0, scope.end_row.0))
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(sticky_rows(3., &mut cx), [(0, 6), (1, 5)]);
+    assert_eq!(sticky_rows(3., &mut cx), [(0, 6), (1, 5)]);
+    assert_eq!(sticky_rows(0., &mut cx), []);
+
+    // Cached scopes are looked up again once the buffer changes.
+    cx.set_state(indoc! {"
+        // Module a
+        ˇmod a {
+            fn b() {
+                one();
+                two();
+                three();
+            }
+        }
+    "});
+    cx.run_until_parked();
+    assert_eq!(sticky_rows(3., &mut cx), [(1, 7), (2, 6)]);
+}
+
 #[gpui::test]
 async fn test_adjacent_diff_hunks(executor: BackgroundExecutor, cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -24,6 +24,7 @@ use crate::{
     inlay_hint_settings,
     items::BufferSearchHighlights,
     mouse_context_menu::{self, MenuPosition},
+    scroll::Autoscroll,
     scroll::scroll_amount::ScrollAmount,
 };
 use buffer_diff::{DiffHunkStatus, DiffHunkStatusKind};
@@ -35,11 +36,11 @@ use git::{Oid, blame::BlameEntry, status::FileStatus};
 use gpui::{
     Action, Along, AnyElement, App, AvailableSpace, Axis as ScrollbarAxis, BorderStyle, Bounds,
     ClickEvent, ContentMask, Context, Corner, Corners, CursorStyle, DispatchPhase, Edges, Element,
-    ElementInputHandler, Entity, Focusable as _, FontId, GlobalElementId, Hitbox, Hsla,
-    InteractiveElement, IntoElement, Keystroke, Length, ModifiersChangedEvent, MouseButton,
+    ElementInputHandler, Entity, Focusable as _, FontId, GlobalElementId, HighlightStyle, Hitbox,
+    Hsla, InteractiveElement, IntoElement, Keystroke, Length, ModifiersChangedEvent, MouseButton,
     MouseDownEvent, MouseMoveEvent, MouseUpEvent, PaintQuad, ParentElement, Pixels, ScrollDelta,
     ScrollWheelEvent, ShapedLine, SharedString, Size, StatefulInteractiveElement, Style, Styled,
-    TextRun, TextStyleRefinement, WeakEntity, Window, anchored, deferred, div, fill,
+    StyledText, TextRun, TextStyleRefinement, WeakEntity, Window, anchored, deferred, div, fill,
     linear_color_stop, linear_gradient, outline, point, px, quad, relative, size, solid_background,
     transparent_black,
 };
@@ -64,7 +65,7 @@ use std::{
     cmp::{self, Ordering},
     fmt::{self, Write},
     iter, mem,
-    ops::{Deref, Range},
+    ops::{Deref, Range, RangeInclusive},
     rc::Rc,
     sync::Arc,
     time::Duration,
@@ -78,6 +79,12 @@ use util::{RangeExt, ResultExt, debug_panic};
 use workspace::{Workspace, item::Item, notifications::NotifyTaskExt};
 
 const INLINE_BLAME_PADDING_EM_WIDTHS: f32 = 7.;
+const MINIMAP_LINE_HEIGHT: Pixels = px(3.);
+const MINIMAP_COLUMN_WIDTH: Pixels = px(1.5);
+const MINIMAP_PADDING: Pixels = px(4.);
+const MINIMAP_MARKER_WIDTH: Pixels = px(2.);
+/// The minimap is hidden when it would leave fewer columns than this for the text.
+const MINIMAP_MIN_TEXT_COLUMNS: f32 = 40.;
 
 /// Determines what kinds of highlights should be applied to a lines background.
 #[derive(Clone, Copy, Default)]
@@ -95,6 +102,37 @@ struct SelectionLayout {
     range: Range<DisplayPoint>,
     active_rows: Range<DisplayRow>,
     user_name: Option<SharedString>,
+    virtual_columns: Option<VirtualColumns>,
+}
+
+/// The columns of a row of a columnar selection that extends past the end of the
+/// row's line, where there's no text to position it by.
+struct VirtualColumns {
+    start: VirtualColumn,
+    end: VirtualColumn,
+    head: VirtualColumn,
+}
+
+/// A column that may be past the end of its row's line, as the column of the text
+/// before it and the number of cells past the end of the line that it is.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct VirtualColumn {
+    column: u32,
+    overshoot: u32,
+}
+
+impl VirtualColumn {
+    fn new(map: &DisplaySnapshot, row: DisplayRow, screen_column: u32, bias: Bias) -> Self {
+        let (point, overshoot) = map.point_for_screen_column(row, screen_column, bias);
+        Self {
+            column: point.column(),
+            overshoot,
+        }
+    }
+
+    fn x(&self, line: &LineWithInvisibles, em_advance: Pixels) -> Pixels {
+        line.x_for_index(self.column as usize) + em_advance * self.overshoot as f32
+    }
 }
 
 impl SelectionLayout {
@@ -152,6 +190,7 @@ impl SelectionLayout {
             range,
             active_rows,
             user_name,
+            virtual_columns: None,
         }
     }
 }
@@ -318,6 +357,10 @@ impl EditorElement {
         });
         register_action(editor, window, Editor::select_line);
         register_action(editor, window, Editor::split_selection_into_lines);
+        register_action(editor, window, Editor::select_columns_up);
+        register_action(editor, window, Editor::select_columns_down);
+        register_action(editor, window, Editor::select_columns_left);
+        register_action(editor, window, Editor::select_columns_right);
         register_action(editor, window, Editor::add_selection_above);
         register_action(editor, window, Editor::add_selection_below);
         register_action(editor, window, |editor, action, window, cx| {
@@ -905,6 +948,31 @@ impl EditorElement {
             scroll_delta.x = scale_horizontal_mouse_autoscroll_delta(event.position.x - right);
         }
 
+        // Dragging with only alt held selects a rectangle, starting from where the
+        // drag began. Until the mouse leaves the clicked character, it's still an
+        // alt-click, which may be adding a cursor.
+        let modifiers = event.modifiers;
+        if modifiers.alt
+            && !modifiers.shift
+            && !modifiers.control
+            && !modifiers.platform
+            && editor.columnar_selection_tail.is_none()
+            && editor.selections.pending_anchor().is_some_and(|pending| {
+                pending.tail().to_display_point(&position_map.snapshot)
+                    != point_for_position.previous_valid
+            })
+        {
+            editor.select(
+                SelectPhase::BeginColumnar {
+                    position: point_for_position.previous_valid,
+                    reset: false,
+                    goal_column: point_for_position.exact_unclipped.column(),
+                },
+                window,
+                cx,
+            );
+        }
+
         editor.select(
             SelectPhase::Update {
                 position: point_for_position.previous_valid,
@@ -1085,11 +1153,18 @@ impl EditorElement {
             if editor.show_local_selections {
                 let mut layouts = Vec::new();
                 let newest = editor.selections.newest(cx);
+                let columnar = editor.selections.columnar().map(|columnar| {
+                    (
+                        columnar.rows(&snapshot.display_snapshot),
+                        columnar.columns(),
+                        columnar.reversed(),
+                    )
+                });
                 for selection in local_selections.iter().cloned() {
                     let is_empty = selection.start == selection.end;
                     let is_newest = selection == newest;
 
-                    let layout = SelectionLayout::new(
+                    let mut layout = SelectionLayout::new(
                         selection,
                         editor.selections.line_mode,
                         editor.cursor_shape,
@@ -1098,6 +1173,22 @@ impl EditorElement {
                         editor.leader_peer_id.is_none(),
                         None,
                     );
+                    if let Some((rows, columns, reversed)) = &columnar {
+                        let row = layout.head.row();
+                        let map = &snapshot.display_snapshot;
+                        let end = rows
+                            .contains(&row)
+                            .then(|| VirtualColumn::new(map, row, columns.end, Bias::Right))
+                            .filter(|end| end.overshoot > 0);
+                        if let Some(end) = end {
+                            let start = VirtualColumn::new(map, row, columns.start, Bias::Left);
+                            layout.virtual_columns = Some(VirtualColumns {
+                                start,
+                                end,
+                                head: if *reversed { start } else { end },
+                            });
+                        }
+                    }
                     if is_newest {
                         newest_selection_head = Some(layout.head);
                     }
@@ -1275,10 +1366,13 @@ impl EditorElement {
                         [cursor_position.row().minus(visible_display_row_range.start) as usize];
                     let cursor_column = cursor_position.column() as usize;
 
-                    let cursor_character_x = cursor_row_layout.x_for_index(cursor_column);
+                    let cursor_character_x = match &selection.virtual_columns {
+                        Some(columns) => columns.head.x(cursor_row_layout, em_advance),
+                        None => cursor_row_layout.x_for_index(cursor_column),
+                    };
                     let mut block_width =
                         cursor_row_layout.x_for_index(cursor_column + 1) - cursor_character_x;
-                    if block_width == Pixels::ZERO {
+                    if block_width == Pixels::ZERO || selection.virtual_columns.is_some() {
                         block_width = em_advance;
                     }
                     let block_text = if let CursorShape::Block = selection.cursor_shape {
@@ -1464,6 +1558,258 @@ impl EditorElement {
         ))
     }
 
+    fn minimap_width(
+        &self,
+        snapshot: &EditorSnapshot,
+        text_width: Pixels,
+        em_width: Pixels,
+        cx: &App,
+    ) -> Pixels {
+        let minimap_settings = EditorSettings::get_global(cx).minimap;
+        let editor = self.editor.read(cx);
+        if !minimap_settings.show
+            || !snapshot.mode.is_full()
+            || !editor.show_scrollbars
+            || !editor.is_singleton(cx)
+        {
+            return Pixels::ZERO;
+        }
+
+        let minimap_width = px(minimap_settings.max_width as f32).min(text_width / 5.);
+        if minimap_width < MINIMAP_PADDING * 4.
+            || text_width - minimap_width < em_width * MINIMAP_MIN_TEXT_COLUMNS
+        {
+            Pixels::ZERO
+        } else {
+            minimap_width
+        }
+    }
+
+    fn layout_minimap(
+        &self,
+        snapshot: &EditorSnapshot,
+        text_hitbox: &Hitbox,
+        minimap_width: Pixels,
+        scroll_top: f32,
+        max_scroll_top: f32,
+        height_in_lines: f32,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<MinimapLayout> {
+        if minimap_width <= Pixels::ZERO {
+            return None;
+        }
+
+        // If a drag took place after we started dragging the minimap thumb,
+        // cancel the minimap drag.
+        if cx.has_active_drag() {
+            self.editor.update(cx, |editor, cx| {
+                editor.scroll_manager.reset_minimap_dragging_state(cx)
+            });
+        }
+
+        let bounds = Bounds::from_corner_and_size(
+            Corner::TopRight,
+            text_hitbox.top_right() - point(self.style.scrollbar_width, Pixels::ZERO),
+            size(minimap_width, text_hitbox.size.height),
+        );
+        let hitbox = window.insert_hitbox(bounds, false);
+
+        // When the buffer doesn't fit into the minimap, the minimap scrolls
+        // proportionally to the editor, so that both reach the end together.
+        let row_count = snapshot.max_point().row().next_row().as_f32();
+        let visible_row_count = bounds.size.height / MINIMAP_LINE_HEIGHT;
+        let scroll_ratio = if row_count > visible_row_count && max_scroll_top > 0. {
+            ((row_count - visible_row_count) / max_scroll_top).min(1.)
+        } else {
+            0.
+        };
+        let minimap_scroll_top = scroll_top * scroll_ratio;
+
+        let start_row = DisplayRow(minimap_scroll_top as u32);
+        let end_row = DisplayRow(
+            ((minimap_scroll_top + visible_row_count).ceil() as u32)
+                .min(snapshot.max_point().row().next_row().0),
+        );
+        let row_top = |row: DisplayRow| {
+            bounds.origin.y + MINIMAP_LINE_HEIGHT * (row.as_f32() - minimap_scroll_top)
+        };
+
+        let text_origin_x = bounds.origin.x + MINIMAP_PADDING;
+        let max_column = ((minimap_width - MINIMAP_PADDING * 2.) / MINIMAP_COLUMN_WIDTH) as usize;
+        let default_color = self.style.text.color;
+        let mut text_quads = Vec::new();
+        let mut row = start_row;
+        let mut column = 0;
+        for chunk in snapshot.highlighted_chunks(start_row..end_row, true, &self.style) {
+            let mut color = chunk
+                .style
+                .and_then(|style| style.color)
+                .unwrap_or(default_color);
+            color.fade_out(0.4);
+            for (ix, line) in chunk.text.split('\n').enumerate() {
+                if ix > 0 {
+                    row.0 += 1;
+                    column = 0;
+                }
+                let top = row_top(row);
+                let mut word_start = None;
+                for ch in line.chars().chain(iter::once(' ')) {
+                    match (ch.is_whitespace(), word_start) {
+                        (false, None) => word_start = Some(column),
+                        (true, Some(start)) => {
+                            word_start = None;
+                            let end = column.min(max_column);
+                            if start < end {
+                                text_quads.push((
+                                    Bounds::new(
+                                        point(
+                                            text_origin_x + MINIMAP_COLUMN_WIDTH * start as f32,
+                                            top,
+                                        ),
+                                        size(
+                                            MINIMAP_COLUMN_WIDTH * (end - start) as f32,
+                                            MINIMAP_LINE_HEIGHT - px(1.),
+                                        ),
+                                    ),
+                                    color,
+                                ));
+                            }
+                        }
+                        _ => {}
+                    }
+                    column += 1;
+                }
+                // Account for the space chained after the line above.
+                column -= 1;
+            }
+        }
+
+        let minimap_settings = EditorSettings::get_global(cx).minimap;
+        let theme = cx.theme().clone();
+        let display_snapshot = &snapshot.display_snapshot;
+        let buffer_snapshot = &snapshot.buffer_snapshot;
+        let last_row = DisplayRow(end_row.0.saturating_sub(1)).max(start_row);
+        let buffer_range = DisplayPoint::new(start_row, 0).to_point(display_snapshot)
+            ..display_snapshot.display_point_to_point(
+                DisplayPoint::new(last_row, display_snapshot.line_len(last_row)),
+                Bias::Right,
+            );
+        let marker_bounds = |rows: RangeInclusive<DisplayRow>, left: Pixels, width: Pixels| {
+            let top = row_top(*rows.start());
+            Bounds::from_corners(
+                point(left, top),
+                point(left + width, row_top(*rows.end()) + MINIMAP_LINE_HEIGHT),
+            )
+        };
+
+        let mut highlight_quads = Vec::new();
+        if minimap_settings.search_results {
+            let search_range = buffer_snapshot.anchor_before(buffer_range.start)
+                ..buffer_snapshot.anchor_after(buffer_range.end);
+            let search_rows = self
+                .editor
+                .read(cx)
+                .background_highlight_row_ranges::<BufferSearchHighlights>(
+                    search_range,
+                    display_snapshot,
+                    50_000,
+                );
+            let color = theme.colors().search_match_background;
+            highlight_quads.extend(search_rows.into_iter().map(|rows| {
+                (
+                    marker_bounds(
+                        rows.start().row()..=rows.end().row(),
+                        bounds.origin.x,
+                        minimap_width,
+                    ),
+                    color,
+                )
+            }));
+        }
+
+        let mut marker_quads = Vec::new();
+        if minimap_settings.git_diff {
+            for hunk in buffer_snapshot.diff_hunks_in_range(buffer_range.clone()) {
+                let start_row = MultiBufferPoint::new(hunk.row_range.start.0, 0)
+                    .to_display_point(display_snapshot)
+                    .row();
+                let mut end_row = MultiBufferPoint::new(hunk.row_range.end.0, 0)
+                    .to_display_point(display_snapshot)
+                    .row();
+                if end_row != start_row {
+                    end_row.0 -= 1;
+                }
+                let color = match hunk.status().kind {
+                    DiffHunkStatusKind::Added => theme.colors().version_control_added,
+                    DiffHunkStatusKind::Modified => theme.colors().version_control_modified,
+                    DiffHunkStatusKind::Deleted => theme.colors().version_control_deleted,
+                };
+                marker_quads.push((
+                    marker_bounds(start_row..=end_row, bounds.origin.x, MINIMAP_MARKER_WIDTH),
+                    color,
+                ));
+            }
+        }
+
+        if minimap_settings.diagnostics != ScrollbarDiagnostics::None {
+            let diagnostics = buffer_snapshot
+                .diagnostics_in_range::<Point>(buffer_range)
+                .filter(|diagnostic| {
+                    should_show_diagnostic(
+                        minimap_settings.diagnostics,
+                        diagnostic.diagnostic.severity,
+                    )
+                })
+                // Paint the most severe diagnostics last.
+                .sorted_by_key(|diagnostic| std::cmp::Reverse(diagnostic.diagnostic.severity));
+            for diagnostic in diagnostics {
+                let start_row = diagnostic
+                    .range
+                    .start
+                    .to_display_point(display_snapshot)
+                    .row();
+                let end_row = diagnostic
+                    .range
+                    .end
+                    .to_display_point(display_snapshot)
+                    .row();
+                let color = match diagnostic.diagnostic.severity {
+                    DiagnosticSeverity::ERROR => theme.status().error,
+                    DiagnosticSeverity::WARNING => theme.status().warning,
+                    DiagnosticSeverity::INFORMATION => theme.status().info,
+                    _ => theme.status().hint,
+                };
+                marker_quads.push((
+                    marker_bounds(
+                        start_row..=end_row,
+                        bounds.right() - MINIMAP_MARKER_WIDTH,
+                        MINIMAP_MARKER_WIDTH,
+                    ),
+                    color,
+                ));
+            }
+        }
+
+        let thumb_bounds = Bounds::new(
+            point(
+                bounds.origin.x,
+                bounds.origin.y + MINIMAP_LINE_HEIGHT * (scroll_top - minimap_scroll_top),
+            ),
+            size(minimap_width, MINIMAP_LINE_HEIGHT * height_in_lines),
+        );
+
+        Some(MinimapLayout {
+            hitbox,
+            thumb_bounds,
+            scroll_ratio,
+            max_scroll_top,
+            text_quads,
+            highlight_quads,
+            marker_quads,
+        })
+    }
+
     fn prepaint_crease_toggles(
         &self,
         crease_toggles: &mut [Option<AnyElement>],
@@ -1914,6 +2260,24 @@ impl EditorElement {
                 .unwrap_or_default()
         });
 
+        let block_bracket_depths = self.editor.update(cx, |editor, _| {
+            indent_guides
+                .iter()
+                .map(|indent_guide| {
+                    let settings = indent_guide.settings;
+                    if settings.coloring == IndentGuideColoring::BracketAware
+                        || settings.background_coloring
+                            == IndentGuideBackgroundColoring::BracketAware
+                    {
+                        editor
+                            .block_bracket_depth(indent_guide.start_row, &snapshot.buffer_snapshot)
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+
         Some(
             indent_guides
                 .into_iter()
@@ -1937,6 +2301,7 @@ impl EditorElement {
                             length,
                             single_indent_width,
                             depth: indent_guide.depth,
+                            bracket_depth: block_bracket_depths[i].map(|depth| depth as u32),
                             active: active_indent_guide_indices.contains(&i),
                             settings: indent_guide.settings,
                         })
@@ -2531,6 +2896,7 @@ impl EditorElement {
     fn layout_lines(
         rows: Range<DisplayRow>,
         snapshot: &EditorSnapshot,
+        bracket_depths: Vec<(Range<usize>, usize)>,
         style: &EditorStyle,
         editor_width: Pixels,
         is_row_soft_wrapped: impl Copy + Fn(usize) -> bool,
@@ -2579,6 +2945,7 @@ impl EditorElement {
                 .collect()
         } else {
             let chunks = snapshot.highlighted_chunks(rows.clone(), true, style);
+            let chunks = colorize_brackets(chunks, rows.clone(), bracket_depths, snapshot, cx);
             LineWithInvisibles::from_chunks(
                 chunks,
                 &style,
@@ -3317,6 +3684,101 @@ impl EditorElement {
         header
     }
 
+    fn layout_sticky_scopes(
+        &self,
+        snapshot: &EditorSnapshot,
+        scroll_top: f32,
+        scroll_left: Pixels,
+        line_height: Pixels,
+        text_hitbox: &Hitbox,
+        content_offset: Pixels,
+        width: Pixels,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Vec<AnyElement> {
+        let sticky_scroll = EditorSettings::get_global(cx).sticky_scroll;
+        if !sticky_scroll.enabled
+            || !snapshot.mode.is_full()
+            || snapshot.buffer_snapshot.show_headers()
+            || width <= Pixels::ZERO
+        {
+            return Vec::new();
+        }
+
+        let scopes = self.editor.update(cx, |editor, _| {
+            editor.sticky_scopes(
+                &snapshot.display_snapshot,
+                scroll_top,
+                sticky_scroll.max_depth,
+            )
+        });
+        let scope_count = scopes.len();
+        let colors = cx.theme().colors();
+        let background = colors.editor_background;
+        let border_color = colors.border_variant;
+
+        // Lay out the innermost scope first, so that the outer scopes are on top
+        // of it when it is pushed up by the end of its body.
+        scopes
+            .into_iter()
+            .enumerate()
+            .rev()
+            .map(|(ix, scope)| {
+                let (text, highlights) = sticky_scope_line(snapshot, scope.row, &self.style);
+                let start = scope.start;
+                let mut element = div()
+                    .id(("sticky-scope", ix))
+                    .occlude()
+                    .relative()
+                    .overflow_hidden()
+                    .w(width)
+                    .h(line_height)
+                    .bg(background)
+                    .when(ix + 1 == scope_count, |div| {
+                        div.border_b_1().border_color(border_color)
+                    })
+                    .cursor_pointer()
+                    .child(
+                        div()
+                            .absolute()
+                            .left(content_offset - scroll_left)
+                            .whitespace_nowrap()
+                            .child(
+                                StyledText::new(text)
+                                    .with_default_highlights(&self.style.text, highlights),
+                            ),
+                    )
+                    .on_click(
+                        window.listener_for(&self.editor, move |editor, _, window, cx| {
+                            window.focus(&editor.focus_handle(cx));
+                            // Keep the enclosing scopes pinned above the declaration.
+                            editor.change_selections(
+                                Some(Autoscroll::top_relative(ix)),
+                                window,
+                                cx,
+                                |selections| selections.select_anchor_ranges([start..start]),
+                            );
+                        }),
+                    )
+                    .into_any_element();
+
+                // A scope stays pinned until the end of its body pushes it up.
+                let top = (line_height * ix as f32)
+                    .min(line_height * (scope.end_row.as_f32() - scroll_top));
+                element.prepaint_as_root(
+                    text_hitbox.origin + point(Pixels::ZERO, top),
+                    size(
+                        AvailableSpace::Definite(width),
+                        AvailableSpace::Definite(line_height),
+                    ),
+                    window,
+                    cx,
+                );
+                element
+            })
+            .collect()
+    }
+
     fn layout_cursor_popovers(
         &self,
         line_height: Pixels,
@@ -4375,6 +4837,12 @@ impl EditorElement {
 
         for indent_guide in indent_guides {
             let indent_accent_colors = cx.theme().accents().color_for_index(indent_guide.depth);
+            // Blocks that aren't opened by a bracket fall back to indent aware coloring.
+            let bracket_accent_colors = indent_guide
+                .bracket_depth
+                .map_or(indent_accent_colors, |depth| {
+                    cx.theme().bracket_colors().color_for_index(depth)
+                });
             let settings = indent_guide.settings;
 
             // TODO fixed for now, expose them through themes later
@@ -4397,6 +4865,13 @@ impl EditorElement {
                 (IndentGuideColoring::IndentAware, true) => {
                     Some(faded_color(indent_accent_colors, INDENT_AWARE_ACTIVE_ALPHA))
                 }
+                (IndentGuideColoring::BracketAware, false) => {
+                    Some(faded_color(bracket_accent_colors, INDENT_AWARE_ALPHA))
+                }
+                (IndentGuideColoring::BracketAware, true) => Some(faded_color(
+                    bracket_accent_colors,
+                    INDENT_AWARE_ACTIVE_ALPHA,
+                )),
             };
 
             let background_color = match (settings.background_coloring, indent_guide.active) {
@@ -4409,6 +4884,14 @@ impl EditorElement {
                     indent_accent_colors,
                     INDENT_AWARE_BACKGROUND_ACTIVE_ALPHA,
                 )),
+                (IndentGuideBackgroundColoring::BracketAware, false) => Some(faded_color(
+                    bracket_accent_colors,
+                    INDENT_AWARE_BACKGROUND_ALPHA,
+                )),
+                (IndentGuideBackgroundColoring::BracketAware, true) => Some(faded_color(
+                    bracket_accent_colors,
+                    INDENT_AWARE_BACKGROUND_ACTIVE_ALPHA,
+                )),
             };
 
             let requested_line_width = if indent_guide.active {
@@ -4837,14 +5320,25 @@ impl EditorElement {
 
             for (player_color, selections) in &layout.selections {
                 for selection in selections.iter() {
-                    self.paint_highlighted_range(
-                        selection.range.clone(),
-                        player_color.selection,
-                        corner_radius,
-                        corner_radius * 2.,
-                        layout,
-                        window,
-                    );
+                    if let Some(columns) = &selection.virtual_columns {
+                        self.paint_virtual_selection(
+                            selection.head.row(),
+                            columns,
+                            player_color.selection,
+                            corner_radius,
+                            layout,
+                            window,
+                        );
+                    } else {
+                        self.paint_highlighted_range(
+                            selection.range.clone(),
+                            player_color.selection,
+                            corner_radius,
+                            corner_radius * 2.,
+                            layout,
+                            window,
+                        );
+                    }
 
                     if selection.is_local && !selection.range.is_empty() {
                         invisible_display_ranges.push(selection.range.clone());
@@ -4930,6 +5424,131 @@ impl EditorElement {
         }
     }
 
+    fn paint_minimap(&mut self, layout: &mut EditorLayout, window: &mut Window, cx: &mut App) {
+        let Some(minimap) = &layout.minimap else {
+            return;
+        };
+
+        let hitbox = minimap.hitbox.clone();
+        let thumb_bounds = minimap.thumb_bounds;
+        let is_dragging = self
+            .editor
+            .read(cx)
+            .scroll_manager
+            .minimap_thumb_drag_offset()
+            .is_some();
+        let colors = cx.theme().colors();
+        let thumb_color = if is_dragging || thumb_bounds.contains(&window.mouse_position()) {
+            colors.scrollbar_thumb_hover_background
+        } else {
+            colors.scrollbar_thumb_background
+        };
+
+        window.paint_layer(hitbox.bounds, |window| {
+            window.with_content_mask(
+                Some(ContentMask {
+                    bounds: hitbox.bounds,
+                }),
+                |window| {
+                    window.paint_quad(fill(hitbox.bounds, colors.editor_background));
+                    for (bounds, color) in &minimap.highlight_quads {
+                        window.paint_quad(fill(*bounds, *color));
+                    }
+                    for (bounds, color) in &minimap.text_quads {
+                        window.paint_quad(fill(*bounds, *color));
+                    }
+                    window.paint_quad(fill(thumb_bounds, thumb_color));
+                    for (bounds, color) in &minimap.marker_quads {
+                        window.paint_quad(fill(*bounds, *color));
+                    }
+                },
+            );
+        });
+        window.set_cursor_style(CursorStyle::Arrow, Some(&hitbox));
+
+        let minimap_top = hitbox.origin.y;
+        let scroll_ratio = minimap.scroll_ratio;
+        let max_scroll_top = minimap.max_scroll_top;
+        // Inverts the thumb placement from `layout_minimap`, returning the
+        // scroll position at which the thumb's top edge is at `thumb_top`.
+        let scroll_top_for_thumb_top = move |thumb_top: Pixels| {
+            let thumb_top = (thumb_top - minimap_top) / MINIMAP_LINE_HEIGHT;
+            (thumb_top / (1. - scroll_ratio).max(f32::EPSILON)).clamp(0., max_scroll_top)
+        };
+
+        if is_dragging {
+            window.on_mouse_event({
+                let editor = self.editor.clone();
+                move |event: &MouseMoveEvent, phase, window, cx| {
+                    if phase == DispatchPhase::Capture {
+                        return;
+                    }
+
+                    editor.update(cx, |editor, cx| {
+                        let Some(drag_offset) = editor.scroll_manager.minimap_thumb_drag_offset()
+                        else {
+                            return;
+                        };
+                        if event.pressed_button != Some(MouseButton::Left) {
+                            editor.scroll_manager.reset_minimap_dragging_state(cx);
+                            return;
+                        }
+
+                        let scroll_x = editor.scroll_position(cx).x;
+                        let scroll_top = scroll_top_for_thumb_top(event.position.y - drag_offset);
+                        editor.set_scroll_position(point(scroll_x, scroll_top), window, cx);
+                        cx.stop_propagation();
+                    });
+                }
+            });
+
+            window.on_mouse_event({
+                let editor = self.editor.clone();
+                move |_: &MouseUpEvent, phase, _, cx| {
+                    if phase == DispatchPhase::Capture {
+                        return;
+                    }
+
+                    editor.update(cx, |editor, cx| {
+                        editor.scroll_manager.reset_minimap_dragging_state(cx);
+                        cx.stop_propagation();
+                    });
+                }
+            });
+        } else {
+            window.on_mouse_event({
+                let editor = self.editor.clone();
+                move |event: &MouseDownEvent, phase, window, cx| {
+                    if phase == DispatchPhase::Capture
+                        || event.button != MouseButton::Left
+                        || !hitbox.is_hovered(window)
+                    {
+                        return;
+                    }
+
+                    editor.update(cx, |editor, cx| {
+                        // Clicking outside of the thumb centers it on the pointer, after
+                        // which it can be dragged just like when grabbing it directly.
+                        let drag_offset = if thumb_bounds.contains(&event.position) {
+                            event.position.y - thumb_bounds.origin.y
+                        } else {
+                            let drag_offset = thumb_bounds.size.height / 2.;
+                            let scroll_x = editor.scroll_position(cx).x;
+                            let scroll_top =
+                                scroll_top_for_thumb_top(event.position.y - drag_offset);
+                            editor.set_scroll_position(point(scroll_x, scroll_top), window, cx);
+                            drag_offset
+                        };
+                        editor
+                            .scroll_manager
+                            .set_minimap_thumb_drag_offset(drag_offset, cx);
+                        cx.stop_propagation();
+                    });
+                }
+            });
+        }
+    }
+
     fn paint_scrollbars(&mut self, layout: &mut EditorLayout, window: &mut Window, cx: &mut App) {
         let Some(scrollbars_layout) = &layout.scrollbars_layout else {
             return;
@@ -5246,28 +5865,10 @@ impl EditorElement {
                                     .diagnostics_in_range::<Point>(Point::zero()..max_point)
                                     // Don't show diagnostics the user doesn't care about
                                     .filter(|diagnostic| {
-                                        match (
+                                        should_show_diagnostic(
                                             scrollbar_settings.diagnostics,
                                             diagnostic.diagnostic.severity,
-                                        ) {
-                                            (ScrollbarDiagnostics::All, _) => true,
-                                            (
-                                                ScrollbarDiagnostics::Error,
-                                                DiagnosticSeverity::ERROR,
-                                            ) => true,
-                                            (
-                                                ScrollbarDiagnostics::Warning,
-                                                DiagnosticSeverity::ERROR
-                                                | DiagnosticSeverity::WARNING,
-                                            ) => true,
-                                            (
-                                                ScrollbarDiagnostics::Information,
-                                                DiagnosticSeverity::ERROR
-                                                | DiagnosticSeverity::WARNING
-                                                | DiagnosticSeverity::INFORMATION,
-                                            ) => true,
-                                            (_, _) => false,
-                                        }
+                                        )
                                     })
                                     // We want to sort by severity, in order to paint the most severe diagnostics last.
                                     .sorted_by_key(|diagnostic| {
@@ -5374,6 +5975,40 @@ impl EditorElement {
         }
     }
 
+    fn paint_virtual_selection(
+        &self,
+        row: DisplayRow,
+        columns: &VirtualColumns,
+        color: Hsla,
+        corner_radius: Pixels,
+        layout: &EditorLayout,
+        window: &mut Window,
+    ) {
+        if columns.start == columns.end || !layout.visible_display_row_range.contains(&row) {
+            return;
+        }
+
+        let position_map = &layout.position_map;
+        let line_layout =
+            &position_map.line_layouts[row.minus(layout.visible_display_row_range.start) as usize];
+        let x_for_column = |column: VirtualColumn| {
+            layout.content_origin.x + column.x(line_layout, position_map.em_advance)
+                - position_map.scroll_pixel_position.x
+        };
+        let highlighted_range = HighlightedRange {
+            color,
+            line_height: position_map.line_height,
+            corner_radius,
+            start_y: layout.content_origin.y + row.as_f32() * position_map.line_height
+                - position_map.scroll_pixel_position.y,
+            lines: vec![HighlightedRangeLine {
+                start_x: x_for_column(columns.start),
+                end_x: x_for_column(columns.end),
+            }],
+        };
+        highlighted_range.paint(position_map.text_hitbox.bounds, window);
+    }
+
     fn paint_inline_diagnostics(
         &mut self,
         layout: &mut EditorLayout,
@@ -5707,6 +6342,68 @@ impl EditorElement {
 
         unstaged == unstaged_hollow
     }
+
+    /// Emphasizes the words that changed within the visible modified hunks that are expanded.
+    fn layout_word_diff_highlights(
+        snapshot: &EditorSnapshot,
+        anchor_range: Range<Anchor>,
+        start_row: DisplayRow,
+        row_infos: &[RowInfo],
+        cx: &App,
+    ) -> Vec<(Range<DisplayPoint>, Hsla)> {
+        let opacity = if cx.theme().appearance().is_light() {
+            0.32
+        } else {
+            0.24
+        };
+        let added_background = cx.theme().colors().version_control_added.opacity(opacity);
+        let deleted_background = cx.theme().colors().version_control_deleted.opacity(opacity);
+        let is_shown_as = |range: &Range<DisplayPoint>, kind: DiffHunkStatusKind| {
+            let row = range.start.row().max(start_row);
+            row_infos
+                .get((row - start_row).0 as usize)
+                .and_then(|row_info| row_info.diff_status)
+                .is_some_and(|status| status.kind == kind)
+        };
+
+        let buffer_snapshot = &snapshot.buffer_snapshot;
+        let mut highlights = Vec::new();
+        for hunk in buffer_snapshot.diff_hunks_in_range(anchor_range) {
+            if hunk.base_word_diffs.is_empty() && hunk.buffer_word_diffs.is_empty() {
+                continue;
+            }
+            let Some(diff) = buffer_snapshot.diff_for_buffer_id(hunk.buffer_id) else {
+                continue;
+            };
+
+            let base_text = diff.base_text();
+            let deleted_anchor = |diff_base_anchor| Anchor {
+                buffer_id: Some(hunk.buffer_id),
+                excerpt_id: hunk.excerpt_id,
+                text_anchor: hunk.buffer_range.start,
+                diff_base_anchor: Some(diff_base_anchor),
+            };
+            for base_range in &hunk.base_word_diffs {
+                let start = deleted_anchor(base_text.anchor_after(base_range.start));
+                let end = deleted_anchor(base_text.anchor_before(base_range.end));
+                let range = start.to_display_point(snapshot)..end.to_display_point(snapshot);
+                if is_shown_as(&range, DiffHunkStatusKind::Deleted) {
+                    highlights.push((range, deleted_background));
+                }
+            }
+
+            for buffer_range in &hunk.buffer_word_diffs {
+                let range =
+                    Anchor::range_in_buffer(hunk.excerpt_id, hunk.buffer_id, buffer_range.clone());
+                let range =
+                    range.start.to_display_point(snapshot)..range.end.to_display_point(snapshot);
+                if is_shown_as(&range, DiffHunkStatusKind::Added) {
+                    highlights.push((range, added_background));
+                }
+            }
+        }
+        highlights
+    }
 }
 
 fn header_jump_data(
@@ -6506,11 +7203,18 @@ impl Element for EditorElement {
                             window.request_measured_layout(
                                 Style::default(),
                                 move |_, _, window, cx| {
-                                    let editor_snapshot = editor_handle
-                                        .update(cx, |editor, cx| editor.snapshot(window, cx));
+                                    let rows = DisplayRow(0)..DisplayRow(1);
+                                    let (editor_snapshot, bracket_depths) =
+                                        editor_handle.update(cx, |editor, cx| {
+                                            let snapshot = editor.snapshot(window, cx);
+                                            let bracket_depths =
+                                                editor.bracket_depths(rows.clone(), &snapshot, cx);
+                                            (snapshot, bracket_depths)
+                                        });
                                     let line = Self::layout_lines(
-                                        DisplayRow(0)..DisplayRow(1),
+                                        rows,
                                         &editor_snapshot,
+                                        bracket_depths,
                                         &style,
                                         px(f32::MAX),
                                         |_| false, // Single lines never soft wrap
@@ -6617,8 +7321,13 @@ impl Element for EditorElement {
                         .unwrap_or_default();
                     let text_width = bounds.size.width - gutter_dimensions.width;
 
-                    let editor_width =
-                        text_width - gutter_dimensions.margin - em_width - style.scrollbar_width;
+                    let minimap_width = self.minimap_width(&snapshot, text_width, em_width, cx);
+
+                    let editor_width = text_width
+                        - gutter_dimensions.margin
+                        - em_width
+                        - style.scrollbar_width
+                        - minimap_width;
 
                     snapshot = self.editor.update(cx, |editor, cx| {
                         editor.last_bounds = Some(bounds);
@@ -6799,11 +7508,19 @@ impl Element for EditorElement {
                             .or_insert(background);
                     }
 
-                    let highlighted_ranges = self.editor.read(cx).background_highlights_in_range(
+                    let mut highlighted_ranges =
+                        self.editor.read(cx).background_highlights_in_range(
+                            start_anchor..end_anchor,
+                            &snapshot.display_snapshot,
+                            cx.theme().colors(),
+                        );
+                    highlighted_ranges.extend(Self::layout_word_diff_highlights(
+                        &snapshot,
                         start_anchor..end_anchor,
-                        &snapshot.display_snapshot,
-                        cx.theme().colors(),
-                    );
+                        start_row,
+                        &row_infos,
+                        cx,
+                    ));
                     let highlighted_gutter_ranges =
                         self.editor.read(cx).gutter_highlights_in_range(
                             start_anchor..end_anchor,
@@ -6950,9 +7667,13 @@ impl Element for EditorElement {
                         cx,
                     );
 
+                    let bracket_depths = self.editor.update(cx, |editor, cx| {
+                        editor.bracket_depths(start_row..end_row, &snapshot, cx)
+                    });
                     let mut line_layouts = Self::layout_lines(
                         start_row..end_row,
                         &snapshot,
+                        bracket_depths,
                         &self.style,
                         editor_width,
                         is_row_soft_wrapped,
@@ -7126,6 +7847,18 @@ impl Element for EditorElement {
                         scroll_position.y * line_height,
                     );
 
+                    let sticky_scopes = self.layout_sticky_scopes(
+                        &snapshot,
+                        scroll_position.y,
+                        scroll_pixel_position.x,
+                        line_height,
+                        &text_hitbox,
+                        content_offset.x,
+                        text_hitbox.size.width - style.scrollbar_width - minimap_width,
+                        window,
+                        cx,
+                    );
+
                     let indent_guides = self.layout_indent_guides(
                         content_origin,
                         text_hitbox.origin,
@@ -7297,6 +8030,17 @@ impl Element for EditorElement {
                         cx,
                     );
 
+                    let minimap = self.layout_minimap(
+                        &snapshot,
+                        &text_hitbox,
+                        minimap_width,
+                        scroll_position.y,
+                        max_scroll_top,
+                        height_in_lines,
+                        window,
+                        cx,
+                    );
+
                     let scrollbars_layout = self.layout_scrollbars(
                         &snapshot,
                         scrollbar_layout_information,
@@ -7562,6 +8306,7 @@ impl Element for EditorElement {
                         display_hunks,
                         content_origin,
                         scrollbars_layout,
+                        minimap,
                         active_rows,
                         highlighted_rows,
                         highlighted_ranges,
@@ -7587,6 +8332,7 @@ impl Element for EditorElement {
                         tab_invisible,
                         space_invisible,
                         sticky_buffer_header,
+                        sticky_scopes,
                         expand_toggles,
                     }
                 })
@@ -7654,6 +8400,11 @@ impl Element for EditorElement {
                         }
                     });
 
+                    for sticky_scope in &mut layout.sticky_scopes {
+                        sticky_scope.paint(window, cx);
+                    }
+
+                    self.paint_minimap(layout, window, cx);
                     self.paint_scrollbars(layout, window, cx);
                     self.paint_inline_completion_popover(layout, window, cx);
                     self.paint_mouse_context_menu(layout, window, cx);
@@ -7663,6 +8414,120 @@ impl Element for EditorElement {
     }
 }
 
+/// Splits the given brackets out of the chunks and colors them by how deeply
+/// they're nested.
+fn colorize_brackets<'a, I: Iterator<Item = HighlightedChunk<'a>>>(
+    chunks: I,
+    rows: Range<DisplayRow>,
+    bracket_depths: Vec<(Range<usize>, usize)>,
+    snapshot: &EditorSnapshot,
+    cx: &App,
+) -> impl Iterator<Item = HighlightedChunk<'a>> + use<'a, I> {
+    let bracket_colors = cx.theme().bracket_colors();
+    let mut brackets = bracket_depths
+        .into_iter()
+        .map(|(range, depth)| {
+            (
+                range.start.to_display_point(snapshot),
+                bracket_colors.color_for_index(depth as u32),
+            )
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .peekable();
+
+    let mut row = rows.start;
+    let mut column = 0;
+    chunks.flat_map(move |chunk| {
+        let mut pieces = SmallVec::<[HighlightedChunk<'a>; 1]>::new();
+        if brackets.peek().is_none() || chunk.replacement.is_some() || chunk.is_tab {
+            for line in chunk.text.split_inclusive('\n') {
+                if line.ends_with('\n') {
+                    row.0 += 1;
+                    column = 0;
+                } else {
+                    column += line.len() as u32;
+                }
+            }
+            pieces.push(chunk);
+            return pieces;
+        }
+
+        let mut text = chunk.text;
+        while !text.is_empty() {
+            // Brackets that were passed over, e.g. because they're folded, stay uncolored.
+            while brackets
+                .next_if(|(point, _)| *point < DisplayPoint::new(row, column))
+                .is_some()
+            {}
+
+            let line_len = text.find('\n').unwrap_or(text.len());
+            let bracket = brackets.peek().copied().filter(|(point, _)| {
+                point.row() == row
+                    && point.column() < column + line_len as u32
+                    && text.is_char_boundary((point.column() - column) as usize)
+            });
+            let split_ix = match bracket {
+                Some((point, _)) => (point.column() - column) as usize,
+                None if line_len < text.len() => line_len + 1,
+                None => text.len(),
+            };
+            if split_ix > 0 {
+                pieces.push(HighlightedChunk {
+                    text: &text[..split_ix],
+                    ..chunk.clone()
+                });
+                if text[..split_ix].ends_with('\n') {
+                    row.0 += 1;
+                    column = 0;
+                } else {
+                    column += split_ix as u32;
+                }
+                text = &text[split_ix..];
+            }
+
+            if let Some((_, color)) = bracket {
+                brackets.next();
+                if text.starts_with(['(', ')', '[', ']', '{', '}']) {
+                    let mut style = chunk.style.unwrap_or_default();
+                    style.highlight(HighlightStyle::color(color));
+                    pieces.push(HighlightedChunk {
+                        text: &text[..1],
+                        style: Some(style),
+                        ..chunk.clone()
+                    });
+                    column += 1;
+                    text = &text[1..];
+                }
+            }
+        }
+        pieces
+    })
+}
+
+/// The highlighted text of a display row, for rendering it outside of the
+/// regular line layout.
+fn sticky_scope_line(
+    snapshot: &EditorSnapshot,
+    row: DisplayRow,
+    style: &EditorStyle,
+) -> (SharedString, Vec<(Range<usize>, HighlightStyle)>) {
+    let mut text = String::new();
+    let mut highlights = Vec::new();
+    for chunk in snapshot.highlighted_chunks(row..row.next_row(), true, style) {
+        let chunk_text = chunk.text.split('\n').next().unwrap_or_default();
+        let start = text.len();
+        text.push_str(chunk_text);
+        if let Some(highlight) = chunk.style.filter(|_| !chunk_text.is_empty()) {
+            highlights.push((start..text.len(), highlight));
+        }
+        if chunk_text.len() < chunk.text.len() {
+            break;
+        }
+    }
+    (text.into(), highlights)
+}
+
 pub(super) fn gutter_bounds(
     editor_bounds: Bounds<Pixels>,
     gutter_dimensions: GutterDimensions,
@@ -7673,6 +8538,40 @@ pub(super) fn gutter_bounds(
     }
 }
 
+/// Whether a diagnostic of the given severity should be marked according to
+/// the scrollbar or minimap diagnostics setting.
+fn should_show_diagnostic(setting: ScrollbarDiagnostics, severity: DiagnosticSeverity) -> bool {
+    match (setting, severity) {
+        (ScrollbarDiagnostics::All, _) => true,
+        (ScrollbarDiagnostics::Error, DiagnosticSeverity::ERROR) => true,
+        (
+            ScrollbarDiagnostics::Warning,
+            DiagnosticSeverity::ERROR | DiagnosticSeverity::WARNING,
+        ) => true,
+        (
+            ScrollbarDiagnostics::Information,
+            DiagnosticSeverity::ERROR
+            | DiagnosticSeverity::WARNING
+            | DiagnosticSeverity::INFORMATION,
+        ) => true,
+        (_, _) => false,
+    }
+}
+
+/// A scaled-down overview of the buffer, painted between the text and the
+/// vertical scrollbar.
+struct MinimapLayout {
+    hitbox: Hitbox,
+    /// The part of the minimap that corresponds to the visible rows of the editor.
+    thumb_bounds: Bounds<Pixels>,
+    /// How many rows the minimap scrolls by for each row the editor scrolls by.
+    scroll_ratio: f32,
+    max_scroll_top: f32,
+    text_quads: Vec<(Bounds<Pixels>, Hsla)>,
+    highlight_quads: Vec<(Bounds<Pixels>, Hsla)>,
+    marker_quads: Vec<(Bounds<Pixels>, Hsla)>,
+}
+
 /// Holds information required for layouting the editor scrollbars.
 struct ScrollbarLayoutInformation {
     /// The bounds of the editor area (excluding the content offset).
@@ -7733,6 +8632,7 @@ pub struct EditorLayout {
     gutter_hitbox: Hitbox,
     content_origin: gpui::Point<Pixels>,
     scrollbars_layout: Option<EditorScrollbars>,
+    minimap: Option<MinimapLayout>,
     mode: EditorMode,
     wrap_guides: SmallVec<[(Pixels, bool); 2]>,
     indent_guides: Option<Vec<IndentGuideLayout>>,
@@ -7764,6 +8664,8 @@ pub struct EditorLayout {
     tab_invisible: ShapedLine,
     space_invisible: ShapedLine,
     sticky_buffer_header: Option<AnyElement>,
+    /// Pinned scope lines, innermost first so that outer ones are painted on top.
+    sticky_scopes: Vec<AnyElement>,
 }
 
 impl EditorLayout {
@@ -8167,6 +9069,7 @@ pub struct IndentGuideLayout {
     length: Pixels,
     single_indent_width: Pixels,
     depth: u32,
+    bracket_depth: Option<u32>,
     active: bool,
     settings: IndentGuideSettings,
 }
@@ -8524,11 +9427,13 @@ mod tests {
     use crate::{
         Editor, MultiBuffer,
         display_map::{BlockPlacement, BlockProperties},
+        editor_settings::MinimapContent,
         editor_tests::{init_test, update_test_language_settings},
     };
-    use gpui::{TestAppContext, VisualTestContext};
+    use gpui::{Modifiers, TestAppContext, VisualTestContext};
     use language::language_settings;
     use log::info;
+    use settings::SettingsStore;
     use std::num::NonZeroU32;
     use util::test::sample_text;
 
@@ -8750,6 +9655,222 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    fn test_minimap_layout(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+        cx.update(|cx| update_minimap_settings(cx, |minimap| minimap.show = Some(true)));
+
+        let window = cx.add_window(|window, cx| {
+            let buffer = MultiBuffer::build_simple(&sample_text(200, 20, 'a'), cx);
+            Editor::new(EditorMode::full(), buffer, None, window, cx)
+        });
+        let cx = &mut VisualTestContext::from_window(*window, cx);
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|_, cx| editor.read(cx).style().unwrap().clone());
+
+        let (_, state) = cx.draw(
+            point(px(500.), px(500.)),
+            size(px(800.), px(500.)),
+            |_, _| EditorElement::new(&editor, style.clone()),
+        );
+        let minimap = state.minimap.as_ref().unwrap();
+        assert_eq!(minimap.hitbox.size.width, px(120.));
+        assert_eq!(
+            minimap.hitbox.right(),
+            state.hitbox.right() - style.scrollbar_width
+        );
+        assert_eq!(minimap.thumb_bounds.origin.y, minimap.hitbox.origin.y);
+        assert!(!minimap.text_quads.is_empty());
+
+        // The minimap doesn't take space away from editors that are too narrow for it.
+        let (_, state) = cx.draw(
+            point(px(500.), px(500.)),
+            size(px(200.), px(500.)),
+            |_, _| EditorElement::new(&editor, style.clone()),
+        );
+        assert!(state.minimap.is_none());
+    }
+
+    #[gpui::test]
+    fn test_minimap_settings(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+
+        let window = cx.add_window(|window, cx| {
+            let buffer = MultiBuffer::build_simple(&sample_text(200, 20, 'a'), cx);
+            Editor::new(EditorMode::full(), buffer, None, window, cx)
+        });
+        let cx = &mut VisualTestContext::from_window(*window, cx);
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|_, cx| editor.read(cx).style().unwrap().clone());
+        let draw = |cx: &mut VisualTestContext| {
+            cx.draw(
+                point(px(500.), px(500.)),
+                size(px(800.), px(500.)),
+                |_, _| EditorElement::new(&editor, style.clone()),
+            )
+            .1
+        };
+
+        // The minimap is hidden by default.
+        assert!(draw(cx).minimap.is_none());
+
+        cx.update(|_, cx| {
+            update_minimap_settings(cx, |minimap| {
+                minimap.show = Some(true);
+                minimap.max_width = Some(60);
+            })
+        });
+        let state = draw(cx);
+        let minimap = state.minimap.as_ref().unwrap();
+        assert_eq!(minimap.hitbox.size.width, px(60.));
+        assert!(minimap.highlight_quads.is_empty());
+
+        // Search results are marked unless disabled.
+        editor.update(cx, |editor, cx| {
+            let buffer = editor.buffer().read(cx).snapshot(cx);
+            let range =
+                buffer.anchor_before(Point::new(3, 0))..buffer.anchor_after(Point::new(3, 4));
+            editor.highlight_background::<BufferSearchHighlights>(
+                &[range],
+                |colors| colors.search_match_background,
+                cx,
+            );
+        });
+        assert_eq!(draw(cx).minimap.unwrap().highlight_quads.len(), 1);
+        cx.update(|_, cx| {
+            update_minimap_settings(cx, |minimap| minimap.search_results = Some(false))
+        });
+        assert!(draw(cx).minimap.unwrap().highlight_quads.is_empty());
+
+        // The minimap is hidden along with the scrollbars.
+        editor.update(cx, |editor, cx| editor.set_show_scrollbars(false, cx));
+        assert!(draw(cx).minimap.is_none());
+        editor.update(cx, |editor, cx| editor.set_show_scrollbars(true, cx));
+        assert!(draw(cx).minimap.is_some());
+
+        cx.update(|_, cx| update_minimap_settings(cx, |minimap| minimap.show = Some(false)));
+        assert!(draw(cx).minimap.is_none());
+    }
+
+    #[gpui::test]
+    fn test_minimap_thumb_scrolling(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+        cx.update(|cx| update_minimap_settings(cx, |minimap| minimap.show = Some(true)));
+
+        let window = cx.add_window(|window, cx| {
+            let buffer = MultiBuffer::build_simple(&sample_text(1000, 20, 'a'), cx);
+            Editor::new(EditorMode::full(), buffer, None, window, cx)
+        });
+        let cx = &mut VisualTestContext::from_window(*window, cx);
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|_, cx| editor.read(cx).style().unwrap().clone());
+        let viewport_size = cx.update(|window, _| window.viewport_size());
+        // Draws the editor where the window does, so that simulated mouse
+        // events hit the same minimap.
+        let draw = |cx: &mut VisualTestContext| {
+            let minimap = cx
+                .draw(point(px(0.), px(0.)), viewport_size, |_, _| {
+                    EditorElement::new(&editor, style.clone())
+                })
+                .1
+                .minimap
+                .unwrap();
+            cx.run_until_parked();
+            minimap
+        };
+        let scroll_top = |cx: &mut VisualTestContext| {
+            editor.update(cx, |editor, cx| editor.scroll_position(cx).y)
+        };
+        let assert_close = |actual: Pixels, expected: Pixels| {
+            assert!(
+                (actual - expected).abs() < px(0.01),
+                "{actual:?} != {expected:?}"
+            );
+        };
+
+        // The buffer doesn't fit, so the minimap scrolls more slowly than the
+        // editor, and the thumb moves by the difference.
+        let minimap = draw(cx);
+        assert_eq!(minimap.thumb_bounds.origin.y, minimap.hitbox.origin.y);
+        assert!(minimap.scroll_ratio > 0. && minimap.scroll_ratio < 1.);
+        editor.update_in(cx, |editor, window, cx| {
+            editor.set_scroll_position(point(0., 100.), window, cx)
+        });
+        let minimap = draw(cx);
+        assert_close(
+            minimap.thumb_bounds.origin.y,
+            minimap.hitbox.origin.y + MINIMAP_LINE_HEIGHT * 100. * (1. - minimap.scroll_ratio),
+        );
+
+        // Clicking outside of the thumb centers it on the pointer.
+        let x = minimap.hitbox.center().x;
+        let click_y = minimap.thumb_bounds.bottom() + minimap.thumb_bounds.size.height;
+        assert!(click_y < minimap.hitbox.bottom());
+        cx.simulate_mouse_move(point(x, click_y), None, Modifiers::none());
+        cx.simulate_mouse_down(point(x, click_y), MouseButton::Left, Modifiers::none());
+        let minimap = draw(cx);
+        assert_close(minimap.thumb_bounds.center().y, click_y);
+
+        // Dragging keeps the thumb under the pointer.
+        cx.simulate_mouse_move(
+            point(x, click_y + px(30.)),
+            MouseButton::Left,
+            Modifiers::none(),
+        );
+        let minimap = draw(cx);
+        assert_close(minimap.thumb_bounds.center().y, click_y + px(30.));
+        cx.simulate_mouse_up(
+            point(x, click_y + px(30.)),
+            MouseButton::Left,
+            Modifiers::none(),
+        );
+        let scroll_top_after_drag = scroll_top(cx);
+        cx.simulate_mouse_move(point(x, click_y + px(60.)), None, Modifiers::none());
+        assert_eq!(scroll_top(cx), scroll_top_after_drag);
+
+        // Grabbing the thumb directly keeps the offset at which it was grabbed,
+        // without scrolling until the pointer moves.
+        let minimap = draw(cx);
+        let grab_y = minimap.thumb_bounds.origin.y + px(10.);
+        cx.simulate_mouse_move(point(x, grab_y), None, Modifiers::none());
+        cx.simulate_mouse_down(point(x, grab_y), MouseButton::Left, Modifiers::none());
+        assert_eq!(scroll_top(cx), scroll_top_after_drag);
+        cx.simulate_mouse_move(
+            point(x, grab_y - px(20.)),
+            MouseButton::Left,
+            Modifiers::none(),
+        );
+        let minimap = draw(cx);
+        assert_close(minimap.thumb_bounds.origin.y, grab_y - px(30.));
+        cx.simulate_mouse_up(
+            point(x, grab_y - px(20.)),
+            MouseButton::Left,
+            Modifiers::none(),
+        );
+
+        // The thumb can't be dragged past the start of the buffer.
+        let minimap = draw(cx);
+        let grab_y = minimap.thumb_bounds.origin.y + px(10.);
+        cx.simulate_mouse_move(point(x, grab_y), None, Modifiers::none());
+        cx.simulate_mouse_down(point(x, grab_y), MouseButton::Left, Modifiers::none());
+        cx.simulate_mouse_move(
+            point(x, minimap.hitbox.origin.y - px(100.)),
+            MouseButton::Left,
+            Modifiers::none(),
+        );
+        assert_eq!(scroll_top(cx), 0.);
+        let minimap = draw(cx);
+        assert_eq!(minimap.thumb_bounds.origin.y, minimap.hitbox.origin.y);
+    }
+
+    fn update_minimap_settings(cx: &mut App, f: impl FnOnce(&mut MinimapContent)) {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<EditorSettings>(cx, |settings| {
+                f(settings.minimap.get_or_insert_default());
+            });
+        });
+    }
+
     #[gpui::test]
     fn test_all_invisibles_drawing(cx: &mut TestAppContext) {
         const TAB_SIZE: u32 = 4;
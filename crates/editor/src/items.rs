@@ -1109,6 +1109,7 @@ impl SerializableItem for Editor {
                                         buffer.did_reload(
                                             buffer.version(),
                                             buffer.line_ending(),
+                                            buffer.encoding(),
                                             mtime,
                                             cx,
                                         );
@@ -0,0 +1,645 @@
+//! Keyboard macros for users who don't use vim mode.
+//!
+//! While a macro is being recorded, the actions dispatched by keystrokes and the
+//! text typed into editors are captured. Replaying a macro dispatches them again,
+//! one step per frame, so that each step observes the effects of the previous one.
+
+use crate::{
+    Editor,
+    actions::{
+        HandleInput, RecordMacro, ReplayMacro, ReplayMacroAt, ReplayMacroAtEveryCursor,
+        ReplayMacroAtEverySearchMatch, ReplayNamedMacro, SaveMacro, StopRecordingMacro,
+    },
+    items::BufferSearchHighlights,
+};
+use anyhow::{Context as _, Result, anyhow};
+use fs::Fs;
+use gpui::{Action, App, Entity, Focusable, Global, Keystroke, Task, WeakEntity, Window};
+use multi_buffer::Anchor;
+use serde::{Deserialize, Serialize};
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, VecDeque},
+    ops::Range,
+    sync::Arc,
+};
+use workspace::{Workspace, notifications::DetachAndPromptErr};
+
+/// Upper bound on the steps dispatched by a single replay, counting every
+/// repetition at every location, so that a macro replayed too many times can't
+/// lock up the editor.
+const MAX_REPLAYED_STEPS: usize = 10_000;
+
+#[derive(Default)]
+struct KeyboardMacros {
+    recording: Option<Vec<MacroStep>>,
+    last_macro: Option<Arc<[MacroStep]>>,
+    /// Set between stopping a recording and deciding whether it was stopped
+    /// from the command palette, in which case opening the palette is dropped
+    /// from the end of the macro.
+    trim_last_macro: bool,
+    replay: Option<MacroReplay>,
+}
+
+impl Global for KeyboardMacros {}
+
+enum MacroStep {
+    Action {
+        action: Box<dyn Action>,
+        keystroke: Option<Keystroke>,
+    },
+    Keystroke(Keystroke),
+    Input(Arc<str>),
+}
+
+impl Clone for MacroStep {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Action { action, keystroke } => Self::Action {
+                action: action.boxed_clone(),
+                keystroke: keystroke.clone(),
+            },
+            Self::Keystroke(keystroke) => Self::Keystroke(keystroke.clone()),
+            Self::Input(text) => Self::Input(text.clone()),
+        }
+    }
+}
+
+impl MacroStep {
+    fn replay(&self, window: &mut Window, cx: &mut App) {
+        match self {
+            Self::Action { action, .. } => window.dispatch_action(action.boxed_clone(), cx),
+            Self::Keystroke(keystroke) => {
+                window.dispatch_keystroke(keystroke.clone(), cx);
+            }
+            Self::Input(text) => {
+                window.dispatch_action(Box::new(HandleInput(text.to_string())), cx)
+            }
+        }
+    }
+
+    /// Actions with arguments can't be serialized, so they are saved as the
+    /// keystroke that triggered them instead.
+    fn to_saved(&self, cx: &App) -> SavedMacroStep {
+        match self {
+            Self::Action { action, keystroke } => {
+                let rebuilds_without_arguments = cx
+                    .build_action(action.name(), None)
+                    .is_ok_and(|rebuilt| rebuilt.partial_eq(action.as_ref()));
+                match keystroke {
+                    Some(keystroke) if !rebuilds_without_arguments => SavedMacroStep::Keystroke {
+                        keystroke: keystroke.unparse(),
+                    },
+                    _ => SavedMacroStep::Action {
+                        action: action.name().to_string(),
+                        args: None,
+                    },
+                }
+            }
+            Self::Keystroke(keystroke) => SavedMacroStep::Keystroke {
+                keystroke: keystroke.unparse(),
+            },
+            Self::Input(text) => SavedMacroStep::Text {
+                text: text.to_string(),
+            },
+        }
+    }
+
+    fn from_saved(step: &SavedMacroStep, cx: &App) -> Result<Self> {
+        Ok(match step {
+            SavedMacroStep::Action { action, args } => Self::Action {
+                action: cx.build_action(action, args.clone())?,
+                keystroke: None,
+            },
+            SavedMacroStep::Keystroke { keystroke } => {
+                Self::Keystroke(Keystroke::parse(keystroke)?)
+            }
+            SavedMacroStep::Text { text } => Self::Input(text.as_str().into()),
+        })
+    }
+}
+
+/// A step of a macro in the macros file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SavedMacroStep {
+    Action {
+        action: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        args: Option<serde_json::Value>,
+    },
+    Keystroke {
+        keystroke: String,
+    },
+    Text {
+        text: String,
+    },
+}
+
+type SavedMacros = BTreeMap<String, Vec<SavedMacroStep>>;
+
+struct MacroReplay {
+    steps: Arc<[MacroStep]>,
+    ix: usize,
+    locations: Option<ReplayLocations>,
+}
+
+/// The places a macro is replayed at, one after the other.
+struct ReplayLocations {
+    editor: WeakEntity<Editor>,
+    remaining: VecDeque<Range<Anchor>>,
+    /// The selections left behind by each finished run of the macro.
+    results: Vec<Range<Anchor>>,
+    started: bool,
+}
+
+pub(crate) fn init(cx: &mut App) {
+    cx.set_global(KeyboardMacros::default());
+
+    cx.observe_keystrokes(|event, _, cx| {
+        let Some(action) = event.action.as_ref() else {
+            return;
+        };
+        if action.as_any().is::<StopRecordingMacro>() {
+            cx.global_mut::<KeyboardMacros>().trim_last_macro = false;
+        }
+        if is_macro_action(action.as_ref()) {
+            return;
+        }
+        if let Some(recording) = recording(cx) {
+            recording.push(MacroStep::Action {
+                action: action.boxed_clone(),
+                keystroke: Some(event.keystroke.clone()),
+            });
+        }
+    })
+    .detach();
+
+    cx.observe_new(
+        |workspace: &mut Workspace, _: Option<&mut Window>, _cx: &mut gpui::Context<Workspace>| {
+            workspace.register_action(|_, _: &RecordMacro, _, cx| start_recording(cx));
+            workspace.register_action(|_, _: &StopRecordingMacro, _, cx| stop_recording(cx));
+            workspace.register_action(replay_macro);
+            workspace.register_action(|workspace, _: &ReplayMacroAtEveryCursor, window, cx| {
+                let action = ReplayMacro {
+                    at: ReplayMacroAt::EveryCursor,
+                    ..Default::default()
+                };
+                replay_macro(workspace, &action, window, cx);
+            });
+            workspace.register_action(
+                |workspace, _: &ReplayMacroAtEverySearchMatch, window, cx| {
+                    let action = ReplayMacro {
+                        at: ReplayMacroAt::EverySearchMatch,
+                        ..Default::default()
+                    };
+                    replay_macro(workspace, &action, window, cx);
+                },
+            );
+        },
+    )
+    .detach();
+}
+
+fn is_macro_action(action: &dyn Action) -> bool {
+    let action = action.as_any();
+    action.is::<RecordMacro>()
+        || action.is::<StopRecordingMacro>()
+        || action.is::<ReplayMacro>()
+        || action.is::<ReplayMacroAtEveryCursor>()
+        || action.is::<ReplayMacroAtEverySearchMatch>()
+        || action.is::<ReplayNamedMacro>()
+        || action.is::<SaveMacro>()
+}
+
+/// Returns the macro being recorded, unless steps are currently being
+/// dispatched by a replay.
+fn recording(cx: &mut App) -> Option<&mut Vec<MacroStep>> {
+    if !cx.has_global::<KeyboardMacros>() {
+        return None;
+    }
+    let macros = cx.global_mut::<KeyboardMacros>();
+    if macros.replay.is_some() {
+        return None;
+    }
+    macros.recording.as_mut()
+}
+
+/// Records text typed into an editor.
+pub(crate) fn record_input(text: &str, cx: &mut App) {
+    if text.is_empty() {
+        return;
+    }
+    let Some(recording) = recording(cx) else {
+        return;
+    };
+    if let Some(MacroStep::Input(last_text)) = recording.last_mut() {
+        *last_text = format!("{last_text}{text}").into();
+    } else {
+        recording.push(MacroStep::Input(text.into()));
+    }
+}
+
+fn start_recording(cx: &mut App) {
+    let macros = cx.global_mut::<KeyboardMacros>();
+    if macros.replay.is_none() {
+        macros.recording = Some(Vec::new());
+    }
+}
+
+fn stop_recording(cx: &mut App) {
+    let macros = cx.global_mut::<KeyboardMacros>();
+    let Some(steps) = macros.recording.take() else {
+        return;
+    };
+    macros.last_macro = Some(steps.into());
+    // When recording is stopped with a keybinding, the keystroke observer runs
+    // right after this handler and clears the flag before the deferred check.
+    macros.trim_last_macro = true;
+    cx.defer(|cx| {
+        let macros = cx.global_mut::<KeyboardMacros>();
+        if !std::mem::take(&mut macros.trim_last_macro) {
+            return;
+        }
+        if let Some(last_macro) = macros.last_macro.as_mut() {
+            let mut steps = last_macro.to_vec();
+            trim_command_palette_invocation(&mut steps);
+            *last_macro = steps.into();
+        }
+    });
+}
+
+/// Drops the trailing steps that opened the command palette and picked the
+/// action that stopped the recording.
+fn trim_command_palette_invocation(steps: &mut Vec<MacroStep>) {
+    let Some(ix) = steps.iter().rposition(|step| {
+        matches!(step, MacroStep::Action { action, .. } if action.name() == "command_palette::Toggle")
+    }) else {
+        return;
+    };
+    let only_palette_input = steps[ix + 1..].iter().all(|step| match step {
+        MacroStep::Action { action, .. } => action.name().starts_with("menu::"),
+        MacroStep::Keystroke(_) => false,
+        MacroStep::Input(_) => true,
+    });
+    if only_palette_input {
+        steps.truncate(ix);
+    }
+}
+
+fn replay_macro(
+    workspace: &mut Workspace,
+    action: &ReplayMacro,
+    window: &mut Window,
+    cx: &mut gpui::Context<Workspace>,
+) {
+    let times = action.times.unwrap_or(1).max(1);
+    let target = if action.at == ReplayMacroAt::Cursor {
+        None
+    } else {
+        let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+            return;
+        };
+        let locations = {
+            let editor = editor.read(cx);
+            if action.at == ReplayMacroAt::EveryCursor {
+                editor
+                    .selections
+                    .disjoint_anchor_ranges()
+                    .collect::<Vec<_>>()
+            } else {
+                editor
+                    .background_highlights
+                    .get(&TypeId::of::<BufferSearchHighlights>())
+                    .map_or_else(Vec::new, |(_, ranges)| ranges.to_vec())
+            }
+        };
+        if locations.is_empty() {
+            return;
+        }
+        Some((editor, locations))
+    };
+
+    let Some(name) = action.name.clone() else {
+        let Some(steps) = cx.global::<KeyboardMacros>().last_macro.clone() else {
+            return;
+        };
+        if let Err(error) = start_replay(&steps, times, target, window, cx) {
+            workspace.show_error(&error, cx);
+        }
+        return;
+    };
+    let fs = workspace.app_state().fs.clone();
+    cx.spawn_in(window, async move |_, cx| {
+        let saved_macros = load_saved_macros(fs.as_ref()).await?;
+        let saved_steps = saved_macros
+            .get(&name)
+            .with_context(|| format!("no macro named {name:?} in {:?}", paths::macros_file()))?;
+        cx.update(|window, cx| {
+            let steps = saved_steps
+                .iter()
+                .map(|step| MacroStep::from_saved(step, cx))
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!("invalid step in macro {name:?}"))?;
+            start_replay(&steps, times, target, window, cx)
+        })?
+    })
+    .detach_and_prompt_err("Failed to replay macro", window, cx, |_, _, _| None);
+}
+
+fn start_replay(
+    steps: &[MacroStep],
+    times: usize,
+    target: Option<(Entity<Editor>, Vec<Range<Anchor>>)>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Result<()> {
+    let macros = cx.global::<KeyboardMacros>();
+    if steps.is_empty() || macros.replay.is_some() || macros.recording.is_some() {
+        return Ok(());
+    }
+
+    let location_count = target.as_ref().map_or(1, |(_, locations)| locations.len());
+    let replayed_steps = steps
+        .len()
+        .saturating_mul(times)
+        .saturating_mul(location_count);
+    if replayed_steps > MAX_REPLAYED_STEPS {
+        return Err(anyhow!(
+            "replaying this macro would take {replayed_steps} steps, \
+            more than the limit of {MAX_REPLAYED_STEPS}"
+        ));
+    }
+
+    let steps: Arc<[MacroStep]> = steps
+        .iter()
+        .cycle()
+        .take(steps.len() * times)
+        .cloned()
+        .collect();
+    let locations = target.map(|(editor, locations)| ReplayLocations {
+        editor: editor.downgrade(),
+        remaining: locations.into(),
+        results: Vec::new(),
+        started: false,
+    });
+    // When replaying at several locations, the first step moves to the first one.
+    let ix = if locations.is_some() { steps.len() } else { 0 };
+    cx.global_mut::<KeyboardMacros>().replay = Some(MacroReplay {
+        steps,
+        ix,
+        locations,
+    });
+    window.defer(cx, next_step);
+    Ok(())
+}
+
+fn next_step(window: &mut Window, cx: &mut App) {
+    let macros = cx.global_mut::<KeyboardMacros>();
+    let Some(replay) = macros.replay.as_mut() else {
+        return;
+    };
+
+    if let Some(step) = replay.steps.get(replay.ix).cloned() {
+        replay.ix += 1;
+        step.replay(window, cx);
+        window.defer(cx, next_step);
+        return;
+    }
+
+    let Some(mut locations) = replay.locations.take() else {
+        macros.replay = None;
+        return;
+    };
+    let Some(editor) = locations.editor.upgrade() else {
+        macros.replay = None;
+        return;
+    };
+    replay.ix = 0;
+
+    let next_location = locations.remaining.pop_front();
+    let done = next_location.is_none();
+    window.focus(&editor.focus_handle(cx));
+    editor.update(cx, |editor, cx| {
+        if locations.started {
+            locations
+                .results
+                .extend(editor.selections.disjoint_anchor_ranges());
+        }
+        locations.started = true;
+        let selections = match next_location {
+            Some(location) => vec![location],
+            None => std::mem::take(&mut locations.results),
+        };
+        editor.change_selections(None, window, cx, |s| s.select_anchor_ranges(selections));
+    });
+
+    let macros = cx.global_mut::<KeyboardMacros>();
+    if done {
+        macros.replay = None;
+    } else if let Some(replay) = macros.replay.as_mut() {
+        replay.locations = Some(locations);
+        window.defer(cx, next_step);
+    }
+}
+
+async fn load_saved_macros(fs: &dyn Fs) -> Result<SavedMacros> {
+    let path = paths::macros_file();
+    if !fs.is_file(path).await {
+        return Ok(SavedMacros::default());
+    }
+    let content = fs.load(path).await?;
+    if content.trim().is_empty() {
+        return Ok(SavedMacros::default());
+    }
+    settings::parse_json_with_comments(&content)
+        .with_context(|| format!("failed to parse {path:?}"))
+}
+
+/// Returns the names of the macros saved in the macros file.
+pub fn saved_macro_names(cx: &App) -> Task<Result<Vec<String>>> {
+    let fs = <dyn Fs>::global(cx);
+    cx.background_spawn(async move {
+        let saved_macros = load_saved_macros(fs.as_ref()).await?;
+        Ok(saved_macros.into_keys().collect())
+    })
+}
+
+/// Saves the most recently recorded macro to the macros file, replacing any
+/// macro with the same name.
+pub fn save_last_macro(name: String, cx: &App) -> Task<Result<()>> {
+    let Some(last_macro) = cx
+        .try_global::<KeyboardMacros>()
+        .and_then(|macros| macros.last_macro.clone())
+    else {
+        return Task::ready(Err(anyhow!("no macro has been recorded yet")));
+    };
+    let steps = last_macro
+        .iter()
+        .map(|step| step.to_saved(cx))
+        .collect::<Vec<_>>();
+    let fs = <dyn Fs>::global(cx);
+    cx.background_spawn(async move {
+        let mut saved_macros = load_saved_macros(fs.as_ref()).await?;
+        saved_macros.insert(name, steps);
+        fs.create_dir(paths::config_dir()).await?;
+        fs.atomic_write(
+            paths::macros_file().clone(),
+            serde_json::to_string_pretty(&saved_macros)?,
+        )
+        .await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EditorMode, actions::MoveToEndOfLine, editor_tests::init_test};
+    use gpui::{KeyBinding, TestAppContext, VisualTestContext};
+    use multi_buffer::MultiBuffer;
+    use project::{FakeFs, Project};
+    use text::Point;
+
+    async fn init_macro_test(
+        text: &str,
+        cx: &mut TestAppContext,
+    ) -> (Entity<Editor>, &mut VisualTestContext) {
+        init_test(cx, |_| {});
+        cx.update(|cx| {
+            cx.bind_keys([KeyBinding::new(
+                "end",
+                MoveToEndOfLine {
+                    stop_at_soft_wraps: true,
+                },
+                Some("Editor"),
+            )])
+        });
+        let project = Project::test(FakeFs::new(cx.executor()), [], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+        let editor = cx.new_window_entity(|window, cx| {
+            let buffer = MultiBuffer::build_simple(text, cx);
+            Editor::new(EditorMode::full(), buffer, Some(project), window, cx)
+        });
+        workspace.update_in(cx, |workspace, window, cx| {
+            workspace.add_item_to_active_pane(Box::new(editor.clone()), None, true, window, cx);
+        });
+        cx.run_until_parked();
+        (editor, cx)
+    }
+
+    /// Records typing `-`, moving to the end of the line and typing `;`.
+    fn record_macro(cx: &mut VisualTestContext) {
+        cx.dispatch_action(RecordMacro);
+        cx.simulate_input("-");
+        cx.simulate_keystrokes("end");
+        cx.simulate_input(";");
+        cx.dispatch_action(StopRecordingMacro);
+    }
+
+    fn select_line_starts(editor: &Entity<Editor>, rows: &[u32], cx: &mut VisualTestContext) {
+        editor.update_in(cx, |editor, window, cx| {
+            editor.change_selections(None, window, cx, |s| {
+                s.select_ranges(
+                    rows.iter()
+                        .map(|row| Point::new(*row, 0)..Point::new(*row, 0)),
+                )
+            });
+        });
+    }
+
+    #[gpui::test]
+    async fn test_replay_macro_several_times(cx: &mut TestAppContext) {
+        let (editor, cx) = init_macro_test("one\ntwo\nthree\nfour", cx).await;
+        record_macro(cx);
+        assert_eq!(
+            editor.update(cx, |editor, cx| editor.text(cx)),
+            "-one;\ntwo\nthree\nfour"
+        );
+
+        select_line_starts(&editor, &[1], cx);
+        cx.dispatch_action(ReplayMacro {
+            times: Some(3),
+            ..Default::default()
+        });
+        assert_eq!(
+            editor.update(cx, |editor, cx| editor.text(cx)),
+            "-one;\n-two;-;-;\nthree\nfour"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_replay_macro_at_every_cursor(cx: &mut TestAppContext) {
+        let (editor, cx) = init_macro_test("one\ntwo\nthree\nfour", cx).await;
+        record_macro(cx);
+
+        select_line_starts(&editor, &[1, 3], cx);
+        cx.dispatch_action(ReplayMacroAtEveryCursor);
+        editor.update(cx, |editor, cx| {
+            assert_eq!(editor.text(cx), "-one;\n-two;\nthree\n-four;");
+            // The selections left behind by each run are selected afterwards.
+            assert_eq!(
+                editor.selections.ranges::<Point>(cx),
+                [
+                    Point::new(1, 5)..Point::new(1, 5),
+                    Point::new(3, 6)..Point::new(3, 6)
+                ]
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_replay_macro_too_many_times(cx: &mut TestAppContext) {
+        let (editor, cx) = init_macro_test("one\ntwo\nthree\nfour", cx).await;
+        record_macro(cx);
+
+        // The limit applies to the steps replayed at every location together.
+        select_line_starts(&editor, &[1, 2, 3], cx);
+        cx.dispatch_action(ReplayMacro {
+            times: Some(MAX_REPLAYED_STEPS / 3 / 3 + 1),
+            at: ReplayMacroAt::EveryCursor,
+            ..Default::default()
+        });
+        assert_eq!(
+            editor.update(cx, |editor, cx| editor.text(cx)),
+            "-one;\ntwo\nthree\nfour"
+        );
+        let workspace = editor.update(cx, |editor, _| editor.workspace().unwrap());
+        assert_eq!(
+            workspace.update(cx, |workspace, _| workspace.notification_ids().len()),
+            1
+        );
+    }
+
+    #[test]
+    fn test_saved_macro_format() {
+        let saved_macros: SavedMacros = settings::parse_json_with_comments(
+            r#"{
+                // Wraps the word under the cursor in quotes.
+                "quote word": [
+                    { "action": "editor::SelectNext", "args": { "replace_newest": true } },
+                    { "text": "\"" },
+                    { "keystroke": "ctrl-z" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            saved_macros["quote word"],
+            vec![
+                SavedMacroStep::Action {
+                    action: "editor::SelectNext".into(),
+                    args: Some(serde_json::json!({ "replace_newest": true })),
+                },
+                SavedMacroStep::Text { text: "\"".into() },
+                SavedMacroStep::Keystroke {
+                    keystroke: "ctrl-z".into()
+                },
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&saved_macros["quote word"][1..]).unwrap(),
+            serde_json::json!([{ "text": "\"" }, { "keystroke": "ctrl-z" }])
+        );
+    }
+}
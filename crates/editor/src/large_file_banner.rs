@@ -0,0 +1,127 @@
+use crate::Editor;
+use gpui::{Entity, EventEmitter, Subscription};
+use language::Buffer;
+use ui::prelude::*;
+use workspace::{ItemHandle, ToolbarItemEvent, ToolbarItemLocation, ToolbarItemView};
+
+/// Explains why an editor's file was opened without syntax highlighting,
+/// language servers or git diffs, and lets the user enable them anyway.
+pub struct LargeFileBanner {
+    editor: Option<Entity<Editor>>,
+    _observe_buffer: Option<Subscription>,
+}
+
+impl LargeFileBanner {
+    pub fn new() -> Self {
+        Self {
+            editor: None,
+            _observe_buffer: None,
+        }
+    }
+
+    fn large_file_buffer(editor: &Entity<Editor>, cx: &App) -> Option<Entity<Buffer>> {
+        editor
+            .read(cx)
+            .buffer()
+            .read(cx)
+            .as_singleton()
+            .filter(|buffer| buffer.read(cx).large_file_mode())
+    }
+
+    fn enable_full_features(&mut self, cx: &mut Context<Self>) {
+        let Some(editor) = self.editor.as_ref() else {
+            return;
+        };
+        let Some(buffer) = Self::large_file_buffer(editor, cx) else {
+            return;
+        };
+        if let Some(project) = editor.read(cx).project.clone() {
+            project
+                .update(cx, |project, cx| project.exit_large_file_mode(buffer, cx))
+                .detach_and_log_err(cx);
+        }
+    }
+}
+
+impl EventEmitter<ToolbarItemEvent> for LargeFileBanner {}
+
+impl ToolbarItemView for LargeFileBanner {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> ToolbarItemLocation {
+        self.editor = None;
+        self._observe_buffer = None;
+        let Some(editor) = active_pane_item.and_then(|item| item.act_as::<Editor>(cx)) else {
+            return ToolbarItemLocation::Hidden;
+        };
+        let Some(buffer) = Self::large_file_buffer(&editor, cx) else {
+            return ToolbarItemLocation::Hidden;
+        };
+
+        self._observe_buffer = Some(cx.observe(&buffer, |this, buffer, cx| {
+            if !buffer.read(cx).large_file_mode() {
+                this.editor = None;
+                this._observe_buffer = None;
+                cx.emit(ToolbarItemEvent::ChangeLocation(
+                    ToolbarItemLocation::Hidden,
+                ));
+            }
+            cx.notify();
+        }));
+        self.editor = Some(editor);
+        ToolbarItemLocation::Secondary
+    }
+}
+
+impl Render for LargeFileBanner {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let is_loading = self.editor.as_ref().is_some_and(|editor| {
+            let buffer = Self::large_file_buffer(editor, cx);
+            let project = editor.read(cx).project.as_ref();
+            project.zip(buffer).is_some_and(|(project, buffer)| {
+                let buffer_id = buffer.read(cx).remote_id();
+                project
+                    .read(cx)
+                    .buffer_store()
+                    .read(cx)
+                    .is_partially_loaded(buffer_id)
+            })
+        });
+        let message = if is_loading {
+            "This file is large, so it's read-only until it has finished loading, and \
+            syntax highlighting, language servers and git diffs have been turned off."
+        } else {
+            "This file is large, so syntax highlighting, language servers \
+            and git diffs have been turned off."
+        };
+
+        h_flex()
+            .py_1()
+            .pl_2()
+            .pr_1()
+            .flex_wrap()
+            .justify_between()
+            .bg(cx.theme().status().info_background.opacity(0.6))
+            .border_1()
+            .border_color(cx.theme().colors().border_variant)
+            .rounded_sm()
+            .overflow_hidden()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Icon::new(IconName::Info)
+                            .size(IconSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                    .child(Label::new(message)),
+            )
+            .child(
+                Button::new("enable-full-features", "Enable Full Features")
+                    .on_click(cx.listener(|this, _, _, cx| this.enable_full_features(cx))),
+            )
+    }
+}
@@ -18,6 +18,7 @@ pub use scroll_amount::ScrollAmount;
 use settings::Settings;
 use std::{
     cmp::Ordering,
+    ops::Range,
     time::{Duration, Instant},
 };
 use util::ResultExt;
@@ -132,6 +133,7 @@ pub struct ScrollManager {
     show_scrollbars: bool,
     hide_scrollbar_task: Option<Task<()>>,
     dragging_scrollbar: Option<Axis>,
+    minimap_thumb_drag_offset: Option<Pixels>,
     visible_line_count: Option<f32>,
     forbid_vertical_scroll: bool,
 }
@@ -146,6 +148,7 @@ impl ScrollManager {
             show_scrollbars: true,
             hide_scrollbar_task: None,
             dragging_scrollbar: None,
+            minimap_thumb_drag_offset: None,
             last_autoscroll: None,
             visible_line_count: None,
             forbid_vertical_scroll: false,
@@ -338,6 +341,26 @@ impl ScrollManager {
         }
     }
 
+    /// The distance between the top of the minimap thumb and the point where
+    /// it was grabbed, while the thumb is being dragged.
+    pub fn minimap_thumb_drag_offset(&self) -> Option<Pixels> {
+        self.minimap_thumb_drag_offset
+    }
+
+    pub fn set_minimap_thumb_drag_offset(&mut self, offset: Pixels, cx: &mut Context<Editor>) {
+        if self.minimap_thumb_drag_offset != Some(offset) {
+            self.minimap_thumb_drag_offset = Some(offset);
+            cx.notify();
+        }
+    }
+
+    pub fn reset_minimap_dragging_state(&mut self, cx: &mut Context<Editor>) {
+        if self.minimap_thumb_drag_offset.is_some() {
+            self.minimap_thumb_drag_offset = None;
+            cx.notify();
+        }
+    }
+
     pub fn clamp_scroll_left(&mut self, max: f32) -> bool {
         if max < self.anchor.offset.x {
             self.anchor.offset.x = max;
@@ -495,6 +518,19 @@ impl Editor {
         self.scroll_manager.anchor.scroll_position(&display_map)
     }
 
+    /// Returns the range of the multibuffer shown on the given number of lines,
+    /// starting at the top of the viewport.
+    pub fn buffer_range_from_scroll_top(&self, line_count: f32, cx: &mut App) -> Range<Point> {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let top = self.scroll_manager.anchor.scroll_position(&display_map).y;
+        let start = DisplayPoint::new(DisplayRow(top.floor() as u32), 0);
+        let end = display_map.clip_point(
+            DisplayPoint::new(DisplayRow((top + line_count).ceil() as u32), 0),
+            Bias::Left,
+        );
+        start.to_point(&display_map)..end.to_point(&display_map)
+    }
+
     pub fn set_scroll_anchor(
         &mut self,
         scroll_anchor: ScrollAnchor,
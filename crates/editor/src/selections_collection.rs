@@ -35,6 +35,68 @@ pub struct SelectionsCollection {
     pub disjoint: Arc<[Selection<Anchor>]>,
     /// A pending selection, such as when the mouse is being dragged
     pub pending: Option<PendingSelection>,
+    /// The rectangular selection that the disjoint selections were made from, if any.
+    columnar: Option<ColumnarSelection>,
+}
+
+/// A rectangular selection, whose columns can extend into the virtual space past
+/// the end of the lines it covers. It's made of one selection per row, clipped to
+/// the end of that row's line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnarSelection {
+    /// A position in the row where the selection started.
+    pub tail: Anchor,
+    /// A position in the row where the selection ends.
+    pub head: Anchor,
+    /// The number of cells on screen from the start of the row to where the
+    /// selection started.
+    pub tail_column: u32,
+    /// The number of cells on screen from the start of the row to where the
+    /// selection ends, which is where its cursors are.
+    pub head_column: u32,
+}
+
+impl ColumnarSelection {
+    pub fn rows(&self, map: &DisplaySnapshot) -> Range<DisplayRow> {
+        let tail = self.tail.to_display_point(map).row();
+        let head = self.head.to_display_point(map).row();
+        cmp::min(tail, head)..cmp::max(tail, head).next_row()
+    }
+
+    pub fn columns(&self) -> Range<u32> {
+        cmp::min(self.tail_column, self.head_column)..cmp::max(self.tail_column, self.head_column)
+    }
+
+    pub fn reversed(&self) -> bool {
+        self.head_column < self.tail_column
+    }
+
+    /// The rows covered by the selection that contain text, rather than blocks.
+    pub fn text_rows(&self, map: &DisplaySnapshot) -> Vec<DisplayRow> {
+        let rows = self.rows(map);
+        (rows.start.0..rows.end.0)
+            .map(DisplayRow)
+            .filter(|row| !map.is_block_line(*row))
+            .collect()
+    }
+
+    /// The range of text selected in each of the selection's rows, which is empty
+    /// for rows that end before the selection's columns start.
+    pub fn row_ranges(&self, map: &DisplaySnapshot) -> Vec<Range<Point>> {
+        let columns = self.columns();
+        self.text_rows(map)
+            .into_iter()
+            .map(|row| {
+                let (start, _) = map.point_for_screen_column(row, columns.start, Bias::Left);
+                let end = if columns.is_empty() {
+                    start
+                } else {
+                    map.point_for_screen_column(row, columns.end, Bias::Right).0
+                };
+                start.to_point(map)..end.to_point(map)
+            })
+            .collect()
+    }
 }
 
 impl SelectionsCollection {
@@ -55,6 +117,7 @@ impl SelectionsCollection {
                 },
                 mode: SelectMode::Character,
             }),
+            columnar: None,
         }
     }
 
@@ -71,6 +134,7 @@ impl SelectionsCollection {
         self.line_mode = other.line_mode;
         self.disjoint = other.disjoint.clone();
         self.pending.clone_from(&other.pending);
+        self.columnar.clone_from(&other.columnar);
     }
 
     pub fn count(&self) -> usize {
@@ -113,6 +177,12 @@ impl SelectionsCollection {
         self.pending.as_ref().map(|pending| pending.mode.clone())
     }
 
+    /// The rectangular selection that the current selections were made from, which
+    /// is cleared as soon as the selections are changed in any other way.
+    pub fn columnar(&self) -> Option<&ColumnarSelection> {
+        self.columnar.as_ref()
+    }
+
     pub fn all<'a, D>(&self, cx: &mut App) -> Vec<Selection<D>>
     where
         D: 'a + TextDimension + Ord + Sub<D, Output = D>,
@@ -413,6 +483,7 @@ impl<'a> MutableSelectionsCollection<'a> {
 
     pub fn clear_disjoint(&mut self) {
         self.collection.disjoint = Arc::default();
+        self.collection.columnar = None;
     }
 
     pub fn delete(&mut self, selection_id: usize) {
@@ -428,6 +499,9 @@ impl<'a> MutableSelectionsCollection<'a> {
             .cloned()
             .collect();
 
+        if changed {
+            self.collection.columnar = None;
+        }
         self.selections_changed |= changed;
     }
 
@@ -449,15 +523,18 @@ impl<'a> MutableSelectionsCollection<'a> {
             },
             mode,
         });
+        self.collection.columnar = None;
         self.selections_changed = true;
     }
 
     pub(crate) fn set_pending(&mut self, selection: Selection<Anchor>, mode: SelectMode) {
         self.collection.pending = Some(PendingSelection { selection, mode });
+        self.collection.columnar = None;
         self.selections_changed = true;
     }
 
     pub fn try_cancel(&mut self) -> bool {
+        let had_columnar = self.collection.columnar.take().is_some();
         if let Some(pending) = self.collection.pending.take() {
             if self.disjoint.is_empty() {
                 self.collection.disjoint = Arc::from([pending.selection]);
@@ -482,7 +559,8 @@ impl<'a> MutableSelectionsCollection<'a> {
             return true;
         }
 
-        false
+        self.selections_changed |= had_columnar;
+        had_columnar
     }
 
     pub fn insert_range<T>(&mut self, range: Range<T>)
@@ -546,9 +624,30 @@ impl<'a> MutableSelectionsCollection<'a> {
         }));
 
         self.collection.pending = None;
+        self.collection.columnar = None;
         self.selections_changed = true;
     }
 
+    /// Replaces the selections with one for each row of the given rectangular
+    /// selection.
+    pub fn select_columnar(&mut self, columnar: ColumnarSelection) {
+        let map = self.display_map();
+        let reversed = columnar.reversed();
+        let ranges = columnar
+            .row_ranges(&map)
+            .into_iter()
+            .map(|range| {
+                if reversed {
+                    range.end..range.start
+                } else {
+                    range
+                }
+            })
+            .collect::<Vec<_>>();
+        self.select_ranges(ranges);
+        self.collection.columnar = Some(columnar);
+    }
+
     pub fn select_anchors(&mut self, selections: Vec<Selection<Anchor>>) {
         let map = self.display_map();
         let resolved_selections =
@@ -795,6 +894,7 @@ impl<'a> MutableSelectionsCollection<'a> {
     /// the id of the new excerpt where the head of the selection has been moved.
     pub fn refresh(&mut self) -> HashMap<usize, ExcerptId> {
         let mut pending = self.collection.pending.take();
+        let columnar = self.collection.columnar.take();
         let mut selections_with_lost_position = HashMap::default();
 
         let anchors_with_status = {
@@ -856,6 +956,7 @@ impl<'a> MutableSelectionsCollection<'a> {
             pending.selection.end = end;
         }
         self.collection.pending = pending;
+        self.collection.columnar = columnar;
         self.selections_changed = true;
 
         selections_with_lost_position
@@ -0,0 +1,51 @@
+use std::ops::Range;
+
+use collections::HashMap;
+use multi_buffer::Anchor;
+
+use crate::Editor;
+use crate::display_map::{DisplaySnapshot, StickyScope};
+
+/// The outline items enclosing the rows at the top of the viewport, kept until
+/// the buffer or its syntax tree changes.
+#[derive(Default)]
+pub struct StickyScopesCache {
+    version: Option<(usize, usize)>,
+    /// The ranges of the outline items containing each offset, outermost first.
+    enclosing_items: HashMap<usize, Vec<Range<Anchor>>>,
+}
+
+impl Editor {
+    /// Returns the scopes pinned to the top of the viewport, only querying the
+    /// outline again when the viewport moved to other rows or the buffer changed.
+    pub(crate) fn sticky_scopes(
+        &mut self,
+        snapshot: &DisplaySnapshot,
+        scroll_top: f32,
+        max_depth: usize,
+    ) -> Vec<StickyScope> {
+        let buffer = &snapshot.buffer_snapshot;
+        let version = Some((buffer.edit_count(), buffer.non_text_state_update_count()));
+        let cache = &mut self.sticky_scopes_cache;
+        if cache.version != version {
+            cache.version = version;
+            cache.enclosing_items.clear();
+        }
+
+        // Only keep the rows looked up for this scroll position.
+        let mut enclosing_items = HashMap::default();
+        let scopes = snapshot.sticky_scopes(scroll_top, max_depth, |offset| {
+            enclosing_items
+                .entry(offset)
+                .or_insert_with(|| {
+                    cache
+                        .enclosing_items
+                        .remove(&offset)
+                        .unwrap_or_else(|| snapshot.enclosing_outline_items(offset))
+                })
+                .clone()
+        });
+        cache.enclosing_items = enclosing_items;
+        scopes
+    }
+}
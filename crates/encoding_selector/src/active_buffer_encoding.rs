@@ -0,0 +1,70 @@
+use editor::Editor;
+use gpui::{
+    Action, Context, Corner, Entity, IntoElement, ParentElement, Render, Subscription, Window, div,
+};
+use language::Encoding;
+use ui::{Button, ButtonCommon, ContextMenu, FluentBuilder, LabelSize, PopoverMenu, Tooltip};
+use workspace::{StatusItemView, item::ItemHandle};
+
+use crate::{ReopenWithEncoding, SaveWithEncoding};
+
+/// Shows the encoding of the active buffer's file in the status bar.
+#[derive(Default)]
+pub struct ActiveBufferEncoding {
+    active_encoding: Option<Encoding>,
+    _observe_active_editor: Option<Subscription>,
+}
+
+impl ActiveBufferEncoding {
+    fn update_encoding(&mut self, editor: Entity<Editor>, _: &mut Window, cx: &mut Context<Self>) {
+        self.active_encoding = editor
+            .read(cx)
+            .active_excerpt(cx)
+            .map(|(_, buffer, _)| buffer.read(cx))
+            .filter(|buffer| buffer.file().is_some())
+            .map(|buffer| buffer.encoding());
+        cx.notify();
+    }
+}
+
+impl Render for ActiveBufferEncoding {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        div().when_some(self.active_encoding, |el, active_encoding| {
+            el.child(
+                PopoverMenu::new("encoding-menu")
+                    .menu(|window, cx| {
+                        Some(ContextMenu::build(window, cx, |menu, _, _| {
+                            menu.action("Reopen with Encoding…", ReopenWithEncoding.boxed_clone())
+                                .action("Save with Encoding…", SaveWithEncoding.boxed_clone())
+                        }))
+                    })
+                    .anchor(Corner::BottomRight)
+                    .trigger_with_tooltip(
+                        Button::new("change-encoding", active_encoding.to_string())
+                            .label_size(LabelSize::Small),
+                        Tooltip::text("Select Encoding"),
+                    ),
+            )
+        })
+    }
+}
+
+impl StatusItemView for ActiveBufferEncoding {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(editor) = active_pane_item.and_then(|item| item.downcast::<Editor>()) {
+            self._observe_active_editor =
+                Some(cx.observe_in(&editor, window, Self::update_encoding));
+            self.update_encoding(editor, window, cx);
+        } else {
+            self.active_encoding = None;
+            self._observe_active_editor = None;
+        }
+
+        cx.notify();
+    }
+}
@@ -15,6 +15,7 @@ use ignore::gitignore::GitignoreBuilder;
 use rope::Rope;
 use smol::future::FutureExt as _;
 use std::{path::PathBuf, sync::Arc};
+use text::Encoding;
 
 #[derive(Clone)]
 pub struct FakeGitRepository {
@@ -81,7 +82,7 @@ impl FakeGitRepository {
 impl GitRepository for FakeGitRepository {
     fn reload_index(&self) {}
 
-    fn load_index_text(&self, path: RepoPath) -> BoxFuture<Option<String>> {
+    fn load_index_text(&self, path: RepoPath, _encoding: Encoding) -> BoxFuture<Option<String>> {
         async {
             self.with_state_async(false, move |state| {
                 state
@@ -96,7 +97,11 @@ impl GitRepository for FakeGitRepository {
         .boxed()
     }
 
-    fn load_committed_text(&self, path: RepoPath) -> BoxFuture<Option<String>> {
+    fn load_committed_text(
+        &self,
+        path: RepoPath,
+        _encoding: Encoding,
+    ) -> BoxFuture<Option<String>> {
         async {
             self.with_state_async(false, move |state| {
                 state
@@ -123,6 +128,7 @@ impl GitRepository for FakeGitRepository {
         &self,
         path: RepoPath,
         content: Option<String>,
+        _encoding: Encoding,
         _env: Arc<HashMap<String, String>>,
     ) -> BoxFuture<anyhow::Result<()>> {
         self.with_state_async(true, move |state| {
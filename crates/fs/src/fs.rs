@@ -27,14 +27,14 @@ use rope::Rope;
 use serde::{Deserialize, Serialize};
 use smol::io::AsyncWriteExt;
 use std::{
-    io::{self, Write},
+    io::{self, Read, Seek, Write},
     path::{Component, Path, PathBuf},
     pin::Pin,
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tempfile::{NamedTempFile, TempDir};
-use text::LineEnding;
+use text::{Encoding, LineEnding};
 
 #[cfg(any(test, feature = "test-support"))]
 mod fake_git_repo;
@@ -109,8 +109,24 @@ pub trait Fs: Send + Sync {
         Ok(String::from_utf8(self.load_bytes(path).await?)?)
     }
     async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>>;
+    /// Reads at most `len` bytes starting at `offset`, without loading the
+    /// rest of the file.
+    async fn read_at(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>>;
+    /// Overwrites the file's bytes starting at `offset`, leaving the rest of
+    /// it untouched.
+    async fn write_at(&self, path: &Path, offset: u64, bytes: Vec<u8>) -> Result<()>;
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
-    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
+    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
+        self.save_with_encoding(path, text, line_ending, Encoding::default())
+            .await
+    }
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
     async fn is_file(&self, path: &Path) -> bool;
     async fn is_dir(&self, path: &Path) -> bool;
@@ -524,6 +540,30 @@ impl Fs for RealFs {
         Ok(bytes)
     }
 
+    async fn read_at(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let path = path.to_path_buf();
+        smol::unblock(move || {
+            let mut file = std::fs::File::open(path)?;
+            file.seek(io::SeekFrom::Start(offset))?;
+            let mut bytes = Vec::with_capacity(len);
+            file.take(len as u64).read_to_end(&mut bytes)?;
+            Ok(bytes)
+        })
+        .await
+    }
+
+    async fn write_at(&self, path: &Path, offset: u64, bytes: Vec<u8>) -> Result<()> {
+        let path = path.to_path_buf();
+        smol::unblock(move || {
+            let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+            file.seek(io::SeekFrom::Start(offset))?;
+            file.write_all(&bytes)?;
+            file.flush()?;
+            Ok(())
+        })
+        .await
+    }
+
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         smol::unblock(move || {
             let mut tmp_file = if cfg!(any(target_os = "linux", target_os = "freebsd")) {
@@ -553,15 +593,32 @@ impl Fs for RealFs {
         Ok(())
     }
 
-    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()> {
+        // Encode up front, so that text that can't be encoded doesn't leave
+        // the file truncated.
+        let encoded = if encoding == Encoding::UTF_8 {
+            None
+        } else {
+            Some(encoding.encode(&chunks(text, line_ending).collect::<String>())?)
+        };
         let buffer_size = text.summary().len.min(10 * 1024);
         if let Some(path) = path.parent() {
             self.create_dir(path).await?;
         }
         let file = smol::fs::File::create(path).await?;
         let mut writer = smol::io::BufWriter::with_capacity(buffer_size, file);
-        for chunk in chunks(text, line_ending) {
-            writer.write_all(chunk.as_bytes()).await?;
+        if let Some(encoded) = encoded {
+            writer.write_all(&encoded).await?;
+        } else {
+            for chunk in chunks(text, line_ending) {
+                writer.write_all(chunk.as_bytes()).await?;
+            }
         }
         writer.flush().await?;
         Ok(())
@@ -2002,6 +2059,25 @@ impl Fs for FakeFs {
         self.load_internal(path).await
     }
 
+    async fn read_at(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let content = self.load_internal(path).await?;
+        let start = (offset as usize).min(content.len());
+        let end = start.saturating_add(len).min(content.len());
+        Ok(content[start..end].to_vec())
+    }
+
+    async fn write_at(&self, path: &Path, offset: u64, bytes: Vec<u8>) -> Result<()> {
+        let mut content = self.load_internal(path).await?;
+        let start = offset as usize;
+        let end = start + bytes.len();
+        if content.len() < end {
+            content.resize(end, 0);
+        }
+        content[start..end].copy_from_slice(&bytes);
+        self.write_file_internal(normalize_path(path), content, false)?;
+        Ok(())
+    }
+
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path.as_path());
@@ -2009,14 +2085,20 @@ impl Fs for FakeFs {
         Ok(())
     }
 
-    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path);
-        let content = chunks(text, line_ending).collect::<String>();
+        let content = encoding.encode(&chunks(text, line_ending).collect::<String>())?;
         if let Some(path) = path.parent() {
             self.create_dir(path).await?;
         }
-        self.write_file_internal(path, content.into_bytes(), false)?;
+        self.write_file_internal(path, content, false)?;
         Ok(())
     }
 
@@ -26,6 +26,7 @@ use std::{
     io::{BufRead, BufReader, BufWriter, Read},
 };
 use sum_tree::MapSeekTarget;
+use text::Encoding;
 use thiserror::Error;
 use util::ResultExt;
 use util::command::{new_smol_command, new_std_command};
@@ -172,20 +173,22 @@ pub enum ResetMode {
 pub trait GitRepository: Send + Sync {
     fn reload_index(&self);
 
-    /// Returns the contents of an entry in the repository's index, or None if there is no entry for the given path.
+    /// Returns the contents of an entry in the repository's index, decoded in the given encoding, or None if there is no entry for the given path.
     ///
     /// Also returns `None` for symlinks.
-    fn load_index_text(&self, path: RepoPath) -> BoxFuture<Option<String>>;
+    fn load_index_text(&self, path: RepoPath, encoding: Encoding) -> BoxFuture<Option<String>>;
 
-    /// Returns the contents of an entry in the repository's HEAD, or None if HEAD does not exist or has no entry for the given path.
+    /// Returns the contents of an entry in the repository's HEAD, decoded in the given encoding, or None if HEAD does not exist or has no entry for the given path.
     ///
     /// Also returns `None` for symlinks.
-    fn load_committed_text(&self, path: RepoPath) -> BoxFuture<Option<String>>;
+    fn load_committed_text(&self, path: RepoPath, encoding: Encoding) -> BoxFuture<Option<String>>;
 
+    /// Writes the contents of an entry in the repository's index, encoded in the given encoding, or removes the entry if they're `None`.
     fn set_index_text(
         &self,
         path: RepoPath,
         content: Option<String>,
+        encoding: Encoding,
         env: Arc<HashMap<String, String>>,
     ) -> BoxFuture<anyhow::Result<()>>;
 
@@ -585,14 +588,18 @@ impl GitRepository for RealGitRepository {
         .boxed()
     }
 
-    fn load_index_text(&self, path: RepoPath) -> BoxFuture<Option<String>> {
+    fn load_index_text(&self, path: RepoPath, encoding: Encoding) -> BoxFuture<Option<String>> {
         // https://git-scm.com/book/en/v2/Git-Internals-Git-Objects
         const GIT_MODE_SYMLINK: u32 = 0o120000;
 
         let repo = self.repository.clone();
         self.executor
             .spawn(async move {
-                fn logic(repo: &git2::Repository, path: &RepoPath) -> Result<Option<String>> {
+                fn logic(
+                    repo: &git2::Repository,
+                    path: &RepoPath,
+                    encoding: Encoding,
+                ) -> Result<Option<String>> {
                     // This check is required because index.get_path() unwraps internally :(
                     check_path_to_repo_path_errors(path)?;
 
@@ -605,11 +612,11 @@ impl GitRepository for RealGitRepository {
                         _ => return Ok(None),
                     };
 
-                    let content = repo.find_blob(oid)?.content().to_owned();
-                    Ok(String::from_utf8(content).ok())
+                    let content = repo.find_blob(oid)?;
+                    Ok(decode_blob(content.content(), encoding))
                 }
 
-                match logic(&repo.lock(), &path) {
+                match logic(&repo.lock(), &path, encoding) {
                     Ok(value) => return value,
                     Err(err) => log::error!("Error loading index text: {:?}", err),
                 }
@@ -618,7 +625,7 @@ impl GitRepository for RealGitRepository {
             .boxed()
     }
 
-    fn load_committed_text(&self, path: RepoPath) -> BoxFuture<Option<String>> {
+    fn load_committed_text(&self, path: RepoPath, encoding: Encoding) -> BoxFuture<Option<String>> {
         let repo = self.repository.clone();
         self.executor
             .spawn(async move {
@@ -628,8 +635,8 @@ impl GitRepository for RealGitRepository {
                 if entry.filemode() == i32::from(git2::FileMode::Link) {
                     return None;
                 }
-                let content = repo.find_blob(entry.id()).log_err()?.content().to_owned();
-                String::from_utf8(content).ok()
+                let content = repo.find_blob(entry.id()).log_err()?;
+                decode_blob(content.content(), encoding)
             })
             .boxed()
     }
@@ -638,6 +645,7 @@ impl GitRepository for RealGitRepository {
         &self,
         path: RepoPath,
         content: Option<String>,
+        encoding: Encoding,
         env: Arc<HashMap<String, String>>,
     ) -> BoxFuture<anyhow::Result<()>> {
         let working_directory = self.working_directory();
@@ -646,6 +654,7 @@ impl GitRepository for RealGitRepository {
             .spawn(async move {
                 let working_directory = working_directory?;
                 if let Some(content) = content {
+                    let content = encoding.encode(&content)?;
                     let mut child = new_smol_command(&git_binary_path)
                         .current_dir(&working_directory)
                         .envs(env.iter())
@@ -653,12 +662,7 @@ impl GitRepository for RealGitRepository {
                         .stdin(Stdio::piped())
                         .stdout(Stdio::piped())
                         .spawn()?;
-                    child
-                        .stdin
-                        .take()
-                        .unwrap()
-                        .write_all(content.as_bytes())
-                        .await?;
+                    child.stdin.take().unwrap().write_all(&content).await?;
                     let output = child.output().await?.stdout;
                     let sha = String::from_utf8(output)?;
 
@@ -1328,6 +1332,15 @@ impl GitRepository for RealGitRepository {
     }
 }
 
+/// Decodes a blob that is the base of a file's diff in the file's encoding.
+/// Blobs of UTF-8 files that aren't valid UTF-8 aren't treated as text.
+fn decode_blob(content: &[u8], encoding: Encoding) -> Option<String> {
+    if encoding == Encoding::UTF_8 {
+        return String::from_utf8(content.to_vec()).ok();
+    }
+    Some(encoding.decode(content).0)
+}
+
 fn git_status_args(path_prefixes: &[RepoPath]) -> Vec<OsString> {
     let mut args = vec![
         OsString::from("--no-optional-locks"),
@@ -1912,6 +1925,56 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_index_and_committed_text_encoding(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let repo =
+            RealGitRepository::new(&repo_dir.path().join(".git"), None, cx.executor()).unwrap();
+        let windows_1252 = Encoding::for_label("windows-1252").unwrap();
+        let path = RepoPath::from_str("file");
+
+        smol::fs::write(repo_dir.path().join("file"), b"caf\xe9\n")
+            .await
+            .unwrap();
+        repo.stage_paths(vec![path.clone()], Arc::new(HashMap::default()))
+            .await
+            .unwrap();
+        repo.commit(
+            "Initial commit".into(),
+            None,
+            Arc::new(checkpoint_author_envs()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            repo.load_committed_text(path.clone(), windows_1252).await,
+            Some("caf\u{e9}\n".to_string())
+        );
+        // The contents aren't valid UTF-8, so they aren't text in that encoding.
+        assert_eq!(
+            repo.load_committed_text(path.clone(), Encoding::UTF_8)
+                .await,
+            None
+        );
+
+        repo.set_index_text(
+            path.clone(),
+            Some("cr\u{e8}me\n".to_string()),
+            windows_1252,
+            Arc::new(HashMap::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            repo.load_index_text(path.clone(), windows_1252).await,
+            Some("cr\u{e8}me\n".to_string())
+        );
+    }
+
     #[test]
     fn test_branches_parsing() {
         // suppress "help: octal escapes are not supported, `\0` is always null"
@@ -1711,6 +1711,7 @@ impl GitPanel {
                     tools: Vec::new(),
                     stop: Vec::new(),
                     temperature: None,
+                    max_tokens: None,
                 };
 
                 let stream = model.stream_completion_text(request, &cx);
@@ -0,0 +1,682 @@
+mod hex_file;
+mod hex_prompt;
+
+use editor::{
+    EditorSettings,
+    actions::{
+        MoveDown, MoveLeft, MovePageDown, MovePageUp, MoveRight, MoveToBeginning,
+        MoveToBeginningOfLine, MoveToEnd, MoveToEndOfLine, MoveUp, Redo, Undo,
+    },
+    items::entry_git_aware_label_color,
+};
+use file_icons::FileIcons;
+use gpui::{
+    AnyElement, App, Context, Entity, EventEmitter, FocusHandle, Focusable, KeyDownEvent,
+    MouseButton, MouseDownEvent, ScrollStrategy, Subscription, Task, UniformListScrollHandle,
+    WeakEntity, Window, actions, uniform_list,
+};
+use project::{Project, ProjectPath};
+use settings::Settings;
+use std::ops::Range;
+use theme::Theme;
+use ui::prelude::*;
+use util::paths::PathExt;
+use workspace::{
+    ItemSettings, Pane, ToolbarItemLocation, Workspace, WorkspaceId,
+    item::{BreadcrumbText, Item, ItemEvent, ProjectItem, TabContentParams},
+};
+
+pub use hex_file::{HexFile, HexFileEvent};
+use hex_prompt::{HexPrompt, HexPromptKind};
+
+actions!(
+    hex_editor,
+    [GoToOffset, FindBytes, FindNextBytes, SwitchColumn]
+);
+
+/// How many bytes are shown on each row.
+const BYTES_PER_ROW: u64 = 16;
+
+pub fn init(cx: &mut App) {
+    workspace::register_binary_project_item::<HexEditor>(cx);
+}
+
+/// Which representation of the bytes typing goes to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Column {
+    Hex,
+    Text,
+}
+
+/// Shows a file's bytes as rows of offsets, hex digits and ASCII characters,
+/// and lets them be overwritten in place.
+pub struct HexEditor {
+    hex_file: Entity<HexFile>,
+    project: Entity<Project>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    cursor: u64,
+    column: Column,
+    /// Whether the next hex digit typed replaces the low half of the byte
+    /// under the cursor, rather than the high half.
+    low_nibble: bool,
+    visible_rows: Range<usize>,
+    last_search: Option<Vec<u8>>,
+    /// Shown in place of the cursor's position when a search fails.
+    search_failed: bool,
+    workspace: Option<WeakEntity<Workspace>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+pub enum HexEditorEvent {
+    TitleChanged,
+    Edited,
+}
+
+impl EventEmitter<HexEditorEvent> for HexEditor {}
+
+impl HexEditor {
+    pub fn new(
+        hex_file: Entity<HexFile>,
+        project: Entity<Project>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let subscriptions = vec![
+            cx.observe(&hex_file, |_, _, cx| cx.notify()),
+            cx.subscribe(&hex_file, Self::on_hex_file_event),
+        ];
+        Self {
+            hex_file,
+            project,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: UniformListScrollHandle::new(),
+            cursor: 0,
+            column: Column::Hex,
+            low_nibble: false,
+            visible_rows: 0..0,
+            last_search: None,
+            search_failed: false,
+            workspace: None,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    pub fn hex_file(&self) -> &Entity<HexFile> {
+        &self.hex_file
+    }
+
+    /// The offset of the byte under the cursor.
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    fn on_hex_file_event(
+        &mut self,
+        _: Entity<HexFile>,
+        event: &HexFileEvent,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            HexFileEvent::Edited => cx.emit(HexEditorEvent::Edited),
+            HexFileEvent::Saved => cx.emit(HexEditorEvent::TitleChanged),
+            HexFileEvent::Reloaded => {
+                let len = self.hex_file.read(cx).len();
+                self.cursor = self.cursor.min(len.saturating_sub(1));
+                cx.emit(HexEditorEvent::TitleChanged);
+            }
+        }
+    }
+
+    fn row_count(&self, cx: &App) -> usize {
+        self.hex_file.read(cx).len().div_ceil(BYTES_PER_ROW).max(1) as usize
+    }
+
+    /// Moves the cursor to `offset`, clamped to the file, centering it if it
+    /// isn't already in view.
+    pub fn move_cursor_to(&mut self, offset: u64, cx: &mut Context<Self>) {
+        self.set_cursor(offset, ScrollStrategy::Center, cx);
+    }
+
+    fn set_cursor(&mut self, offset: u64, scroll_strategy: ScrollStrategy, cx: &mut Context<Self>) {
+        let len = self.hex_file.read(cx).len();
+        self.cursor = offset.min(len.saturating_sub(1));
+        self.low_nibble = false;
+        self.search_failed = false;
+        self.scroll_handle
+            .scroll_to_item((self.cursor / BYTES_PER_ROW) as usize, scroll_strategy);
+        cx.notify();
+    }
+
+    fn move_cursor_by(&mut self, delta: i64, cx: &mut Context<Self>) {
+        // Moving before the start or past the end leaves the cursor in place,
+        // rather than moving it to a different column.
+        let Some(offset) = self.cursor.checked_add_signed(delta) else {
+            return;
+        };
+        if offset < self.hex_file.read(cx).len() {
+            self.set_cursor(offset, ScrollStrategy::Top, cx);
+        }
+    }
+
+    fn page_rows(&self) -> i64 {
+        self.visible_rows.len().saturating_sub(1).max(1) as i64
+    }
+
+    fn move_up(&mut self, _: &MoveUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_by(-(BYTES_PER_ROW as i64), cx);
+    }
+
+    fn move_down(&mut self, _: &MoveDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_by(BYTES_PER_ROW as i64, cx);
+    }
+
+    fn move_left(&mut self, _: &MoveLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_by(-1, cx);
+    }
+
+    fn move_right(&mut self, _: &MoveRight, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_by(1, cx);
+    }
+
+    fn move_page_up(&mut self, _: &MovePageUp, _: &mut Window, cx: &mut Context<Self>) {
+        let rows = self.page_rows().min((self.cursor / BYTES_PER_ROW) as i64);
+        self.move_cursor_by(-rows * BYTES_PER_ROW as i64, cx);
+    }
+
+    fn move_page_down(&mut self, _: &MovePageDown, _: &mut Window, cx: &mut Context<Self>) {
+        let last_row = self.row_count(cx) as i64 - 1;
+        let rows = self
+            .page_rows()
+            .min(last_row - (self.cursor / BYTES_PER_ROW) as i64);
+        let offset = self.cursor + (rows as u64) * BYTES_PER_ROW;
+        self.set_cursor(offset, ScrollStrategy::Top, cx);
+    }
+
+    fn move_to_beginning_of_line(
+        &mut self,
+        _: &MoveToBeginningOfLine,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_cursor(
+            self.cursor - self.cursor % BYTES_PER_ROW,
+            ScrollStrategy::Top,
+            cx,
+        );
+    }
+
+    fn move_to_end_of_line(&mut self, _: &MoveToEndOfLine, _: &mut Window, cx: &mut Context<Self>) {
+        let end_of_row = self.cursor - self.cursor % BYTES_PER_ROW + BYTES_PER_ROW - 1;
+        self.set_cursor(end_of_row, ScrollStrategy::Top, cx);
+    }
+
+    fn move_to_beginning(&mut self, _: &MoveToBeginning, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_to(0, cx);
+    }
+
+    fn move_to_end(&mut self, _: &MoveToEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_to(u64::MAX, cx);
+    }
+
+    fn switch_column(&mut self, _: &SwitchColumn, _: &mut Window, cx: &mut Context<Self>) {
+        self.column = match self.column {
+            Column::Hex => Column::Text,
+            Column::Text => Column::Hex,
+        };
+        self.low_nibble = false;
+        cx.notify();
+    }
+
+    fn undo(&mut self, _: &Undo, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(offset) = self.hex_file.update(cx, |hex_file, cx| hex_file.undo(cx)) {
+            self.move_cursor_to(offset, cx);
+        }
+    }
+
+    fn redo(&mut self, _: &Redo, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(offset) = self.hex_file.update(cx, |hex_file, cx| hex_file.redo(cx)) {
+            self.move_cursor_to(offset, cx);
+        }
+    }
+
+    fn go_to_offset(&mut self, _: &GoToOffset, window: &mut Window, cx: &mut Context<Self>) {
+        self.toggle_prompt(HexPromptKind::GoToOffset, window, cx);
+    }
+
+    fn find_bytes(&mut self, _: &FindBytes, window: &mut Window, cx: &mut Context<Self>) {
+        self.toggle_prompt(HexPromptKind::Find, window, cx);
+    }
+
+    fn toggle_prompt(&mut self, kind: HexPromptKind, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(workspace) = self.workspace.as_ref().and_then(WeakEntity::upgrade) else {
+            return;
+        };
+        let hex_editor = cx.entity().downgrade();
+        workspace.update(cx, |workspace, cx| {
+            workspace.toggle_modal(window, cx, |window, cx| {
+                HexPrompt::new(hex_editor, kind, window, cx)
+            });
+        });
+    }
+
+    fn find_next_bytes(&mut self, _: &FindNextBytes, window: &mut Window, cx: &mut Context<Self>) {
+        match self.last_search.clone() {
+            Some(pattern) => self.search(pattern, self.cursor + 1, window, cx),
+            None => self.toggle_prompt(HexPromptKind::Find, window, cx),
+        }
+    }
+
+    /// Moves the cursor to the next occurrence of `pattern` at or after
+    /// `start`.
+    pub(crate) fn search(
+        &mut self,
+        pattern: Vec<u8>,
+        start: u64,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.last_search = Some(pattern.clone());
+        let search = self.hex_file.read(cx).search(pattern, start, cx);
+        cx.spawn_in(window, async move |this, cx| {
+            let offset = search.await?;
+            this.update(cx, |this, cx| match offset {
+                Some(offset) => this.move_cursor_to(offset, cx),
+                None => {
+                    this.search_failed = true;
+                    cx.notify();
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn key_down(&mut self, event: &KeyDownEvent, _: &mut Window, cx: &mut Context<Self>) {
+        let modifiers = &event.keystroke.modifiers;
+        if modifiers.control || modifiers.platform || modifiers.function || modifiers.alt {
+            return;
+        }
+        let Some(typed) = event.keystroke.key_char.as_deref() else {
+            return;
+        };
+        let mut chars = typed.chars();
+        let (Some(typed), None) = (chars.next(), chars.next()) else {
+            return;
+        };
+        let Some(current) = self.hex_file.read(cx).byte(self.cursor) else {
+            return;
+        };
+
+        let (byte, advance) = match self.column {
+            Column::Hex => {
+                let Some(digit) = typed.to_digit(16) else {
+                    return;
+                };
+                let digit = digit as u8;
+                if self.low_nibble {
+                    ((current & 0xf0) | digit, true)
+                } else {
+                    ((current & 0x0f) | (digit << 4), false)
+                }
+            }
+            Column::Text => {
+                if !typed.is_ascii() || typed.is_ascii_control() {
+                    return;
+                }
+                (typed as u8, true)
+            }
+        };
+        cx.stop_propagation();
+
+        let cursor = self.cursor;
+        self.hex_file
+            .update(cx, |hex_file, cx| hex_file.overwrite(cursor, byte, cx));
+        if advance {
+            self.move_cursor_by(1, cx);
+        } else {
+            self.low_nibble = true;
+        }
+        cx.notify();
+    }
+
+    fn render_row(&self, row: usize, cx: &Context<Self>) -> impl IntoElement {
+        let hex_file = self.hex_file.read(cx);
+        let colors = cx.theme().colors();
+        let start = row as u64 * BYTES_PER_ROW;
+        let end = (start + BYTES_PER_ROW).min(hex_file.len());
+        let focused_cursor = colors.element_selected;
+        let unfocused_cursor = colors.element_hover;
+
+        let mut hex = h_flex().gap_1();
+        let mut text = h_flex();
+        for offset in start..start + BYTES_PER_ROW {
+            let (hex_text, text_char) = if offset >= end {
+                ("  ".to_string(), " ".to_string())
+            } else {
+                match hex_file.byte(offset) {
+                    Some(byte) => (format!("{byte:02X}"), printable(byte).to_string()),
+                    None => ("··".to_string(), "·".to_string()),
+                }
+            };
+            let color = if hex_file.is_edited(offset) {
+                Color::Modified
+            } else if offset >= end || hex_file.byte(offset) == Some(0) {
+                Color::Muted
+            } else {
+                Color::Default
+            };
+            let is_cursor = offset == self.cursor && offset < end;
+            let cursor_background = |column| {
+                if self.column == column {
+                    focused_cursor
+                } else {
+                    unfocused_cursor
+                }
+            };
+
+            hex = hex.child(
+                div()
+                    .when(offset % 8 == 0 && offset % BYTES_PER_ROW != 0, |cell| {
+                        cell.ml_2()
+                    })
+                    .when(is_cursor, |cell| cell.bg(cursor_background(Column::Hex)))
+                    .when(offset < end, |cell| {
+                        cell.on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _: &MouseDownEvent, window, cx| {
+                                this.column = Column::Hex;
+                                this.move_cursor_to(offset, cx);
+                                window.focus(&this.focus_handle);
+                            }),
+                        )
+                    })
+                    .child(Label::new(hex_text).buffer_font(cx).color(color)),
+            );
+            text = text.child(
+                div()
+                    .when(is_cursor, |cell| cell.bg(cursor_background(Column::Text)))
+                    .when(offset < end, |cell| {
+                        cell.on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _: &MouseDownEvent, window, cx| {
+                                this.column = Column::Text;
+                                this.move_cursor_to(offset, cx);
+                                window.focus(&this.focus_handle);
+                            }),
+                        )
+                    })
+                    .child(Label::new(text_char).buffer_font(cx).color(color)),
+            );
+        }
+
+        h_flex()
+            .id(row)
+            .gap_6()
+            .px_4()
+            .child(
+                Label::new(format!("{start:08X}"))
+                    .buffer_font(cx)
+                    .color(Color::Muted),
+            )
+            .child(hex)
+            .child(text)
+    }
+
+    fn render_status(&self, cx: &Context<Self>) -> impl IntoElement {
+        let hex_file = self.hex_file.read(cx);
+        let status = if self.search_failed {
+            "No matches found".to_string()
+        } else if hex_file.is_empty() {
+            "Empty file".to_string()
+        } else {
+            let cursor = self.cursor;
+            let value = match hex_file.byte(cursor) {
+                Some(byte) => format!(", value 0x{byte:02X} ({byte})"),
+                None => String::new(),
+            };
+            format!(
+                "Offset 0x{cursor:X} ({cursor}) of {} bytes{value}",
+                hex_file.len()
+            )
+        };
+        h_flex()
+            .px_4()
+            .py_1()
+            .border_t_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                Label::new(status)
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+    }
+}
+
+/// The character shown for a byte in the text column.
+fn printable(byte: u8) -> char {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+impl Render for HexEditor {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let row_count = self.row_count(cx);
+        v_flex()
+            .key_context("HexEditor")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .on_action(cx.listener(Self::move_up))
+            .on_action(cx.listener(Self::move_down))
+            .on_action(cx.listener(Self::move_left))
+            .on_action(cx.listener(Self::move_right))
+            .on_action(cx.listener(Self::move_page_up))
+            .on_action(cx.listener(Self::move_page_down))
+            .on_action(cx.listener(Self::move_to_beginning_of_line))
+            .on_action(cx.listener(Self::move_to_end_of_line))
+            .on_action(cx.listener(Self::move_to_beginning))
+            .on_action(cx.listener(Self::move_to_end))
+            .on_action(cx.listener(Self::switch_column))
+            .on_action(cx.listener(Self::undo))
+            .on_action(cx.listener(Self::redo))
+            .on_action(cx.listener(Self::go_to_offset))
+            .on_action(cx.listener(Self::find_bytes))
+            .on_action(cx.listener(Self::find_next_bytes))
+            .on_key_down(cx.listener(Self::key_down))
+            .child(
+                uniform_list(cx.entity(), "hex-rows", row_count, |this, range, _, cx| {
+                    this.visible_rows = range.clone();
+                    let bytes =
+                        range.start as u64 * BYTES_PER_ROW..range.end as u64 * BYTES_PER_ROW;
+                    this.hex_file
+                        .update(cx, |hex_file, cx| hex_file.load(bytes, cx));
+                    range.map(|row| this.render_row(row, cx)).collect()
+                })
+                .flex_grow()
+                .py_2()
+                .track_scroll(self.scroll_handle.clone()),
+            )
+            .child(self.render_status(cx))
+    }
+}
+
+impl Focusable for HexEditor {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for HexEditor {
+    type Event = HexEditorEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        match event {
+            HexEditorEvent::TitleChanged => {
+                f(ItemEvent::UpdateTab);
+                f(ItemEvent::UpdateBreadcrumbs);
+            }
+            HexEditorEvent::Edited => {
+                f(ItemEvent::Edit);
+                f(ItemEvent::UpdateTab);
+            }
+        }
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &App,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::ProjectItem),
+    ) {
+        f(self.hex_file.entity_id(), self.hex_file.read(cx))
+    }
+
+    fn is_singleton(&self, _: &App) -> bool {
+        true
+    }
+
+    fn tab_tooltip_text(&self, cx: &App) -> Option<SharedString> {
+        let abs_path = self.hex_file.read(cx).abs_path().compact();
+        Some(abs_path.to_string_lossy().to_string().into())
+    }
+
+    fn tab_content(&self, params: TabContentParams, _: &Window, cx: &App) -> AnyElement {
+        let project_path = self.hex_file.read(cx).project_path().clone();
+        let label_color = if ItemSettings::get_global(cx).git_status {
+            let git_status = self
+                .project
+                .read(cx)
+                .project_path_git_status(&project_path, cx)
+                .map(|status| status.summary())
+                .unwrap_or_default();
+
+            self.project
+                .read(cx)
+                .entry_for_path(&project_path, cx)
+                .map(|entry| {
+                    entry_git_aware_label_color(git_status, entry.is_ignored, params.selected)
+                })
+                .unwrap_or_else(|| params.text_color())
+        } else {
+            params.text_color()
+        };
+
+        Label::new(file_name(&project_path))
+            .single_line()
+            .color(label_color)
+            .when(params.preview, |this| this.italic())
+            .into_any_element()
+    }
+
+    fn tab_icon(&self, _: &Window, cx: &App) -> Option<Icon> {
+        let path = self.hex_file.read(cx).project_path().path.clone();
+        ItemSettings::get_global(cx)
+            .file_icons
+            .then(|| FileIcons::get_icon(&path, cx))
+            .flatten()
+            .map(Icon::from_path)
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("Hex Editor Opened")
+    }
+
+    fn added_to_workspace(
+        &mut self,
+        workspace: &mut Workspace,
+        _: &mut Window,
+        _: &mut Context<Self>,
+    ) {
+        self.workspace = Some(workspace.weak_handle());
+    }
+
+    fn breadcrumb_location(&self, cx: &App) -> ToolbarItemLocation {
+        if EditorSettings::get_global(cx).toolbar.breadcrumbs {
+            ToolbarItemLocation::PrimaryLeft
+        } else {
+            ToolbarItemLocation::Hidden
+        }
+    }
+
+    fn breadcrumbs(&self, _: &Theme, cx: &App) -> Option<Vec<BreadcrumbText>> {
+        let text = self
+            .hex_file
+            .read(cx)
+            .abs_path()
+            .compact()
+            .to_string_lossy()
+            .to_string();
+        Some(vec![BreadcrumbText {
+            text,
+            highlights: None,
+            font: None,
+        }])
+    }
+
+    fn clone_on_split(
+        &self,
+        _: Option<WorkspaceId>,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Entity<Self>> {
+        let cursor = self.cursor;
+        Some(cx.new(|cx| {
+            let mut hex_editor = Self::new(self.hex_file.clone(), self.project.clone(), cx);
+            hex_editor.cursor = cursor;
+            hex_editor.workspace = self.workspace.clone();
+            hex_editor
+        }))
+    }
+
+    fn is_dirty(&self, cx: &App) -> bool {
+        self.hex_file.read(cx).is_dirty()
+    }
+
+    fn can_save(&self, _: &App) -> bool {
+        true
+    }
+
+    fn save(
+        &mut self,
+        _format: bool,
+        _project: Entity<Project>,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        self.hex_file.update(cx, |hex_file, cx| hex_file.save(cx))
+    }
+
+    fn reload(
+        &mut self,
+        _project: Entity<Project>,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        self.hex_file.update(cx, |hex_file, cx| hex_file.reload(cx));
+        Task::ready(Ok(()))
+    }
+}
+
+fn file_name(project_path: &ProjectPath) -> String {
+    project_path
+        .path
+        .file_name()
+        .unwrap_or(project_path.path.as_os_str())
+        .to_string_lossy()
+        .to_string()
+}
+
+impl ProjectItem for HexEditor {
+    type Item = HexFile;
+
+    fn for_project_item(
+        project: Entity<Project>,
+        _: &Pane,
+        item: Entity<Self::Item>,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        Self::new(item, project, cx)
+    }
+}
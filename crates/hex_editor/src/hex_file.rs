@@ -0,0 +1,583 @@
+use anyhow::{Context as _, Result};
+use collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use fs::Fs;
+use gpui::{App, AppContext as _, Context, Entity, EventEmitter, Task};
+use project::{PathChange, Project, ProjectEntryId, ProjectPath};
+use std::{ops::Range, path::PathBuf, sync::Arc};
+use util::ResultExt;
+
+/// How many bytes are read from disk at a time while displaying a file.
+const PAGE_LEN: u64 = 64 * 1024;
+
+/// How many pages are kept in memory. Pages far from the ones being displayed
+/// are dropped once this is exceeded.
+const MAX_CACHED_PAGES: usize = 256;
+
+/// How many bytes are read from disk at a time while searching a file.
+const SEARCH_CHUNK_LEN: u64 = 1024 * 1024;
+
+/// A file opened for byte-level editing.
+///
+/// The file is never loaded in full. The pages that are displayed are read on
+/// demand, and overwritten bytes are kept in memory until they are written
+/// back to the file in place.
+pub struct HexFile {
+    fs: Arc<dyn Fs>,
+    abs_path: PathBuf,
+    project_path: ProjectPath,
+    entry_id: Option<ProjectEntryId>,
+    len: u64,
+    pages: HashMap<u64, Vec<u8>>,
+    loading_pages: HashSet<u64>,
+    /// Bytes that differ from the file on disk, by offset.
+    edits: BTreeMap<u64, u8>,
+    undo_stack: Vec<ByteEdit>,
+    redo_stack: Vec<ByteEdit>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ByteEdit {
+    offset: u64,
+    old: u8,
+    new: u8,
+    /// The byte on disk, which may no longer be in memory when the edit is
+    /// undone or redone.
+    saved: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexFileEvent {
+    Edited,
+    Saved,
+    Reloaded,
+}
+
+impl EventEmitter<HexFileEvent> for HexFile {}
+
+impl HexFile {
+    fn new(
+        fs: Arc<dyn Fs>,
+        abs_path: PathBuf,
+        project_path: ProjectPath,
+        entry_id: Option<ProjectEntryId>,
+        len: u64,
+    ) -> Self {
+        Self {
+            fs,
+            abs_path,
+            project_path,
+            entry_id,
+            len,
+            pages: HashMap::default(),
+            loading_pages: HashSet::default(),
+            edits: BTreeMap::default(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    pub fn abs_path(&self) -> &PathBuf {
+        &self.abs_path
+    }
+
+    pub fn project_path(&self) -> &ProjectPath {
+        &self.project_path
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.edits.is_empty()
+    }
+
+    /// Returns the byte at `offset`, or `None` if it hasn't been read from
+    /// disk yet.
+    pub fn byte(&self, offset: u64) -> Option<u8> {
+        if let Some(byte) = self.edits.get(&offset) {
+            return Some(*byte);
+        }
+        self.saved_byte(offset)
+    }
+
+    /// Whether the byte at `offset` differs from the file on disk.
+    pub fn is_edited(&self, offset: u64) -> bool {
+        self.edits.contains_key(&offset)
+    }
+
+    fn saved_byte(&self, offset: u64) -> Option<u8> {
+        let page = self.pages.get(&(offset / PAGE_LEN))?;
+        page.get((offset % PAGE_LEN) as usize).copied()
+    }
+
+    /// Starts reading the pages that contain `range`, unless they're already
+    /// in memory.
+    pub fn load(&mut self, range: Range<u64>, cx: &mut Context<Self>) {
+        let end = range.end.min(self.len);
+        if range.start >= end {
+            return;
+        }
+        let pages = range.start / PAGE_LEN..=(end - 1) / PAGE_LEN;
+        if self.pages.len() > MAX_CACHED_PAGES {
+            let keep = pages.start().saturating_sub(MAX_CACHED_PAGES as u64 / 2)
+                ..=pages.end() + MAX_CACHED_PAGES as u64 / 2;
+            self.pages.retain(|page, _| keep.contains(page));
+        }
+        for page in pages {
+            if !self.pages.contains_key(&page) {
+                self.load_page(page, cx);
+            }
+        }
+    }
+
+    fn load_page(&mut self, page: u64, cx: &mut Context<Self>) {
+        if !self.loading_pages.insert(page) {
+            return;
+        }
+        let fs = self.fs.clone();
+        let abs_path = self.abs_path.clone();
+        cx.spawn(async move |this, cx| {
+            let bytes = fs
+                .read_at(&abs_path, page * PAGE_LEN, PAGE_LEN as usize)
+                .await;
+            this.update(cx, |this, cx| {
+                this.loading_pages.remove(&page);
+                if let Some(bytes) = bytes.log_err() {
+                    this.pages.insert(page, bytes);
+                    cx.notify();
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Replaces the byte at `offset`, returning whether it changed. Bytes
+    /// that haven't been read from disk yet can't be replaced.
+    pub fn overwrite(&mut self, offset: u64, byte: u8, cx: &mut Context<Self>) -> bool {
+        let Some(old) = self.byte(offset) else {
+            return false;
+        };
+        if old == byte {
+            return false;
+        }
+        let saved = if self.is_edited(offset) {
+            self.undo_stack
+                .iter()
+                .chain(&self.redo_stack)
+                .find(|edit| edit.offset == offset)
+                .map_or(old, |edit| edit.saved)
+        } else {
+            old
+        };
+        self.set_byte(offset, byte, saved, cx);
+        self.undo_stack.push(ByteEdit {
+            offset,
+            old,
+            new: byte,
+            saved,
+        });
+        self.redo_stack.clear();
+        true
+    }
+
+    /// Reverts the last overwritten byte, returning its offset.
+    pub fn undo(&mut self, cx: &mut Context<Self>) -> Option<u64> {
+        let edit = self.undo_stack.pop()?;
+        self.set_byte(edit.offset, edit.old, edit.saved, cx);
+        self.redo_stack.push(edit);
+        Some(edit.offset)
+    }
+
+    /// Reapplies the last undone byte, returning its offset.
+    pub fn redo(&mut self, cx: &mut Context<Self>) -> Option<u64> {
+        let edit = self.redo_stack.pop()?;
+        self.set_byte(edit.offset, edit.new, edit.saved, cx);
+        self.undo_stack.push(edit);
+        Some(edit.offset)
+    }
+
+    fn set_byte(&mut self, offset: u64, byte: u8, saved: u8, cx: &mut Context<Self>) {
+        if byte == saved {
+            self.edits.remove(&offset);
+        } else {
+            self.edits.insert(offset, byte);
+        }
+        cx.emit(HexFileEvent::Edited);
+        cx.notify();
+    }
+
+    /// Writes the overwritten bytes back to the file, leaving the rest of it
+    /// untouched.
+    pub fn save(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let edits = self.edits.clone();
+        let fs = self.fs.clone();
+        let abs_path = self.abs_path.clone();
+        cx.spawn(async move |this, cx| {
+            for (offset, bytes) in contiguous_runs(&edits) {
+                fs.write_at(&abs_path, offset, bytes)
+                    .await
+                    .with_context(|| format!("writing to {abs_path:?}"))?;
+            }
+            this.update(cx, |this, cx| {
+                for (offset, byte) in edits {
+                    if let Some(page) = this.pages.get_mut(&(offset / PAGE_LEN)) {
+                        if let Some(saved) = page.get_mut((offset % PAGE_LEN) as usize) {
+                            *saved = byte;
+                        }
+                    }
+                    for edit in this.undo_stack.iter_mut().chain(&mut this.redo_stack) {
+                        if edit.offset == offset {
+                            edit.saved = byte;
+                        }
+                    }
+                    // Bytes overwritten again while saving are still unsaved.
+                    if this.edits.get(&offset) == Some(&byte) {
+                        this.edits.remove(&offset);
+                    }
+                }
+                cx.emit(HexFileEvent::Saved);
+                cx.notify();
+            })
+        })
+    }
+
+    /// Rereads the file after it changed on disk. Unsaved bytes are kept,
+    /// unless they're past the new end of the file or now match it.
+    pub fn reload(&mut self, cx: &mut Context<Self>) {
+        let fs = self.fs.clone();
+        let abs_path = self.abs_path.clone();
+        // The pages with edits are read before anything else, so that the
+        // edits can be compared with the bytes now on disk.
+        let edited_pages = self
+            .edits
+            .keys()
+            .copied()
+            .chain(
+                self.undo_stack
+                    .iter()
+                    .chain(&self.redo_stack)
+                    .map(|edit| edit.offset),
+            )
+            .map(|offset| offset / PAGE_LEN)
+            .collect::<BTreeSet<_>>();
+        cx.spawn(async move |this, cx| {
+            let Some(metadata) = fs.metadata(&abs_path).await? else {
+                return Ok(());
+            };
+            let len = metadata.len;
+            let mut saved_pages = HashMap::default();
+            for page in edited_pages {
+                if page * PAGE_LEN < len {
+                    let bytes = fs
+                        .read_at(&abs_path, page * PAGE_LEN, PAGE_LEN as usize)
+                        .await?;
+                    saved_pages.insert(page, bytes);
+                }
+            }
+            this.update(cx, |this, cx| {
+                let saved_byte = |offset: u64| {
+                    let page = saved_pages.get(&(offset / PAGE_LEN))?;
+                    page.get((offset % PAGE_LEN) as usize).copied()
+                };
+                this.len = len;
+                this.undo_stack.retain(|edit| edit.offset < len);
+                this.redo_stack.retain(|edit| edit.offset < len);
+                for edit in this.undo_stack.iter_mut().chain(&mut this.redo_stack) {
+                    if let Some(saved) = saved_byte(edit.offset) {
+                        edit.saved = saved;
+                    }
+                }
+                this.edits
+                    .retain(|offset, byte| *offset < len && saved_byte(*offset) != Some(*byte));
+
+                this.loading_pages.clear();
+                this.pages
+                    .retain(|page, _| *page * PAGE_LEN < len && !saved_pages.contains_key(page));
+                let pages = this.pages.keys().copied().collect::<Vec<_>>();
+                for page in pages {
+                    this.load_page(page, cx);
+                }
+                this.pages.extend(saved_pages);
+                cx.emit(HexFileEvent::Reloaded);
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Finds the next occurrence of `pattern` at or after `start`, wrapping
+    /// around to the start of the file. Unsaved bytes are searched too.
+    pub fn search(&self, pattern: Vec<u8>, start: u64, cx: &App) -> Task<Result<Option<u64>>> {
+        let fs = self.fs.clone();
+        let abs_path = self.abs_path.clone();
+        let edits = self.edits.clone();
+        let len = self.len;
+        let start = start.min(len);
+        cx.background_spawn(async move {
+            if pattern.is_empty() {
+                return Ok(None);
+            }
+            for range in [start..len, 0..start] {
+                let mut chunk_start = range.start;
+                while chunk_start < range.end {
+                    // Chunks overlap so that matches spanning two of them are found.
+                    let chunk_end = (chunk_start + SEARCH_CHUNK_LEN).min(len);
+                    let read_end = (chunk_end + pattern.len() as u64 - 1).min(len);
+                    let mut chunk = fs
+                        .read_at(&abs_path, chunk_start, (read_end - chunk_start) as usize)
+                        .await?;
+                    for (offset, byte) in edits.range(chunk_start..read_end) {
+                        if let Some(chunk_byte) = chunk.get_mut((offset - chunk_start) as usize) {
+                            *chunk_byte = *byte;
+                        }
+                    }
+                    if let Some(ix) = find_bytes(&chunk, &pattern) {
+                        let offset = chunk_start + ix as u64;
+                        if offset < range.end {
+                            return Ok(Some(offset));
+                        }
+                    }
+                    chunk_start = chunk_end;
+                }
+            }
+            Ok(None)
+        })
+    }
+
+    fn on_project_event(
+        &mut self,
+        _: Entity<Project>,
+        event: &project::Event,
+        cx: &mut Context<Self>,
+    ) {
+        if let project::Event::WorktreeUpdatedEntries(worktree_id, changes) = event {
+            if *worktree_id == self.project_path.worktree_id
+                && changes.iter().any(|(path, _, change)| {
+                    *path == self.project_path.path
+                        && matches!(change, PathChange::Updated | PathChange::AddedOrUpdated)
+                })
+            {
+                self.reload(cx);
+            }
+        }
+    }
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Groups overwritten bytes into runs of adjacent offsets, so that they can be
+/// written with as few writes as possible.
+fn contiguous_runs(edits: &BTreeMap<u64, u8>) -> Vec<(u64, Vec<u8>)> {
+    let mut runs: Vec<(u64, Vec<u8>)> = Vec::new();
+    for (offset, byte) in edits {
+        match runs.last_mut() {
+            Some((start, bytes)) if *start + bytes.len() as u64 == *offset => bytes.push(*byte),
+            _ => runs.push((*offset, vec![*byte])),
+        }
+    }
+    runs
+}
+
+impl project::ProjectItem for HexFile {
+    fn try_open(
+        project: &Entity<Project>,
+        path: &ProjectPath,
+        cx: &mut App,
+    ) -> Option<Task<Result<Entity<Self>>>> {
+        // Files are read and written piecemeal, which remote projects can't do.
+        if !project.read(cx).is_local() {
+            return None;
+        }
+        let abs_path = project.read(cx).absolute_path(path, cx)?;
+        let entry_id = project
+            .read(cx)
+            .entry_for_path(path, cx)
+            .map(|entry| entry.id);
+        let fs = project.read(cx).fs().clone();
+        let project = project.clone();
+        let path = path.clone();
+        Some(cx.spawn(async move |cx| {
+            let metadata = fs
+                .metadata(&abs_path)
+                .await?
+                .with_context(|| format!("{abs_path:?} does not exist"))?;
+            anyhow::ensure!(!metadata.is_dir, "{abs_path:?} is a directory");
+            cx.new(|cx| {
+                cx.subscribe(&project, Self::on_project_event).detach();
+                Self::new(fs, abs_path, path, entry_id, metadata.len)
+            })
+        }))
+    }
+
+    fn entry_id(&self, _: &App) -> Option<ProjectEntryId> {
+        self.entry_id
+    }
+
+    fn project_path(&self, _: &App) -> Option<ProjectPath> {
+        Some(self.project_path.clone())
+    }
+
+    fn is_dirty(&self) -> bool {
+        HexFile::is_dirty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use project::WorktreeId;
+    use std::path::Path;
+
+    async fn open_hex_file(
+        content: &[u8],
+        cx: &mut TestAppContext,
+    ) -> (Arc<FakeFs>, Entity<HexFile>) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_file("/dir/file.bin", content.to_vec()).await;
+        let hex_file = cx.new(|_| {
+            HexFile::new(
+                fs.clone(),
+                PathBuf::from("/dir/file.bin"),
+                ProjectPath {
+                    worktree_id: WorktreeId::from_usize(0),
+                    path: Arc::from(Path::new("file.bin")),
+                },
+                None,
+                content.len() as u64,
+            )
+        });
+        hex_file.update(cx, |hex_file, cx| {
+            hex_file.load(0..content.len() as u64, cx)
+        });
+        cx.run_until_parked();
+        (fs, hex_file)
+    }
+
+    #[gpui::test]
+    async fn test_overwrite_undo_and_save(cx: &mut TestAppContext) {
+        let (fs, hex_file) = open_hex_file(&[0, 1, 2, 3, 4, 5], cx).await;
+
+        hex_file.update(cx, |hex_file, cx| {
+            assert_eq!(hex_file.byte(2), Some(2));
+            assert!(hex_file.overwrite(2, 0xaa, cx));
+            assert!(hex_file.overwrite(3, 0xbb, cx));
+            assert!(hex_file.overwrite(5, 0xcc, cx));
+            assert!(!hex_file.overwrite(0, 0, cx));
+            assert_eq!(hex_file.undo(cx), Some(5));
+            assert_eq!(hex_file.byte(5), Some(5));
+            assert!(hex_file.is_dirty());
+        });
+        hex_file
+            .update(cx, |hex_file, cx| hex_file.save(cx))
+            .await
+            .unwrap();
+        assert_eq!(
+            fs.load_bytes(Path::new("/dir/file.bin")).await.unwrap(),
+            [0, 1, 0xaa, 0xbb, 4, 5]
+        );
+
+        hex_file.update(cx, |hex_file, cx| {
+            assert!(!hex_file.is_dirty());
+            // Undoing a saved edit makes the file dirty again, and redoing it
+            // makes it clean.
+            assert_eq!(hex_file.undo(cx), Some(3));
+            assert!(hex_file.is_dirty());
+            assert_eq!(hex_file.redo(cx), Some(3));
+            assert!(!hex_file.is_dirty());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_undo_after_page_is_evicted(cx: &mut TestAppContext) {
+        let len = (MAX_CACHED_PAGES as u64 + 2) * PAGE_LEN;
+        let (_, hex_file) = open_hex_file(&vec![0; len as usize], cx).await;
+
+        hex_file.update(cx, |hex_file, cx| {
+            assert!(hex_file.overwrite(0, 0xaa, cx));
+            hex_file.load(len - 1..len, cx);
+            assert_eq!(hex_file.saved_byte(0), None);
+            assert_eq!(hex_file.undo(cx), Some(0));
+            assert!(!hex_file.is_dirty());
+            assert_eq!(hex_file.redo(cx), Some(0));
+            assert!(hex_file.is_dirty());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_reload(cx: &mut TestAppContext) {
+        let (fs, hex_file) = open_hex_file(&[0, 1, 2, 3, 4, 5], cx).await;
+
+        hex_file.update(cx, |hex_file, cx| {
+            assert!(hex_file.overwrite(1, 0xaa, cx));
+            assert!(hex_file.overwrite(2, 0xbb, cx));
+            assert!(hex_file.overwrite(4, 0xcc, cx));
+        });
+
+        // The file is truncated, and the byte at offset 2 changes to match the edit.
+        fs.insert_file("/dir/file.bin", vec![0, 1, 0xbb, 3]).await;
+        hex_file.update(cx, |hex_file, cx| hex_file.reload(cx));
+        cx.run_until_parked();
+
+        hex_file.update(cx, |hex_file, cx| {
+            assert_eq!(hex_file.len(), 4);
+            assert!(hex_file.is_edited(1));
+            assert!(!hex_file.is_edited(2));
+            assert!(!hex_file.is_edited(4));
+            assert_eq!(hex_file.byte(2), Some(0xbb));
+            assert_eq!(hex_file.byte(3), Some(3));
+
+            // Edits past the end of the file can't be undone, and undoing
+            // the others restores the bytes now on disk.
+            assert_eq!(hex_file.undo(cx), Some(2));
+            assert!(hex_file.is_edited(2));
+            assert_eq!(hex_file.byte(2), Some(2));
+            assert_eq!(hex_file.undo(cx), Some(1));
+            assert_eq!(hex_file.undo(cx), None);
+            assert!(hex_file.is_edited(2));
+            assert_eq!(hex_file.redo(cx), Some(1));
+            assert_eq!(hex_file.redo(cx), Some(2));
+            assert!(!hex_file.is_edited(2));
+            assert_eq!(hex_file.redo(cx), None);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_search(cx: &mut TestAppContext) {
+        let (_, hex_file) = open_hex_file(b"\x7fELF..ELF..", cx).await;
+
+        let search = |pattern: &[u8], start: u64, cx: &mut TestAppContext| {
+            hex_file.read_with(cx, |hex_file, cx| {
+                hex_file.search(pattern.to_vec(), start, cx)
+            })
+        };
+        assert_eq!(search(b"ELF", 0, cx).await.unwrap(), Some(1));
+        assert_eq!(search(b"ELF", 2, cx).await.unwrap(), Some(6));
+        assert_eq!(search(b"ELF", 7, cx).await.unwrap(), Some(1));
+        assert_eq!(search(b"ELG", 0, cx).await.unwrap(), None);
+
+        // Unsaved bytes are searched instead of the ones on disk.
+        hex_file.update(cx, |hex_file, cx| hex_file.overwrite(8, b'G', cx));
+        assert_eq!(search(b"ELG", 0, cx).await.unwrap(), Some(6));
+        assert_eq!(search(b"ELF", 2, cx).await.unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_contiguous_runs() {
+        let edits = BTreeMap::from_iter([(1, 0xa), (2, 0xb), (4, 0xc), (5, 0xd), (9, 0xe)]);
+        assert_eq!(
+            contiguous_runs(&edits),
+            [(1, vec![0xa, 0xb]), (4, vec![0xc, 0xd]), (9, vec![0xe])]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use crate::HexEditor;
+use editor::Editor;
+use gpui::{
+    App, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Render, Subscription,
+    WeakEntity,
+};
+use ui::prelude::*;
+use workspace::ModalView;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HexPromptKind {
+    GoToOffset,
+    Find,
+}
+
+/// A modal for entering the offset to move to, or the bytes to search for,
+/// in a hex editor.
+pub(crate) struct HexPrompt {
+    query_editor: Entity<Editor>,
+    hex_editor: WeakEntity<HexEditor>,
+    kind: HexPromptKind,
+    _subscription: Subscription,
+}
+
+impl ModalView for HexPrompt {}
+
+impl EventEmitter<DismissEvent> for HexPrompt {}
+
+impl Focusable for HexPrompt {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.query_editor.focus_handle(cx)
+    }
+}
+
+impl HexPrompt {
+    pub(crate) fn new(
+        hex_editor: WeakEntity<HexEditor>,
+        kind: HexPromptKind,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let query_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            let placeholder = match kind {
+                HexPromptKind::GoToOffset => "Offset, e.g. 0x1F40 or 8000",
+                HexPromptKind::Find => "Bytes, e.g. 7F 45 4C 46, or \"text\"",
+            };
+            editor.set_placeholder_text(placeholder, cx);
+            editor
+        });
+        let subscription = cx.subscribe_in(&query_editor, window, Self::on_query_editor_event);
+        Self {
+            query_editor,
+            hex_editor,
+            kind,
+            _subscription: subscription,
+        }
+    }
+
+    fn on_query_editor_event(
+        &mut self,
+        _: &Entity<Editor>,
+        event: &editor::EditorEvent,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            editor::EditorEvent::Blurred => cx.emit(DismissEvent),
+            editor::EditorEvent::BufferEdited => cx.notify(),
+            _ => {}
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.query_editor.read(cx).text(cx);
+        let kind = self.kind;
+        self.hex_editor
+            .update(cx, |hex_editor, cx| {
+                match kind {
+                    HexPromptKind::GoToOffset => {
+                        let Some(offset) = parse_offset(&query) else {
+                            return;
+                        };
+                        hex_editor.move_cursor_to(offset, cx);
+                    }
+                    HexPromptKind::Find => {
+                        let Some(pattern) = parse_bytes(&query) else {
+                            return;
+                        };
+                        let start = hex_editor.cursor();
+                        hex_editor.search(pattern, start, window, cx);
+                    }
+                }
+                window.focus(&hex_editor.focus_handle(cx));
+            })
+            .ok();
+        cx.emit(DismissEvent);
+    }
+}
+
+/// Parses an offset written in hexadecimal with a `0x` prefix, or in decimal.
+fn parse_offset(query: &str) -> Option<u64> {
+    let query = query.trim().replace('_', "");
+    match query
+        .strip_prefix("0x")
+        .or_else(|| query.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => query.parse().ok(),
+    }
+}
+
+/// Parses a byte sequence written as pairs of hex digits, optionally separated
+/// by whitespace, or as text in double quotes.
+fn parse_bytes(query: &str) -> Option<Vec<u8>> {
+    let query = query.trim();
+    if let Some(text) = query
+        .strip_prefix('"')
+        .and_then(|query| query.strip_suffix('"'))
+    {
+        return Some(text.as_bytes().to_vec()).filter(|bytes| !bytes.is_empty());
+    }
+
+    let digits = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<_>>>()?;
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return None;
+    }
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect(),
+    )
+}
+
+impl Render for HexPrompt {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let query = self.query_editor.read(cx).text(cx);
+        let help_text = match self.kind {
+            HexPromptKind::GoToOffset => match parse_offset(&query) {
+                Some(offset) => format!("Go to offset 0x{offset:X} ({offset})"),
+                None => "Enter an offset".to_string(),
+            },
+            HexPromptKind::Find => match parse_bytes(&query) {
+                Some(bytes) if bytes.len() == 1 => "Find 1 byte".to_string(),
+                Some(bytes) => format!("Find {} bytes", bytes.len()),
+                None => "Enter hex digit pairs, or text in quotes".to_string(),
+            },
+        };
+
+        v_flex()
+            .w(rems(24.))
+            .elevation_2(cx)
+            .key_context("HexPrompt")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                div()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(self.query_editor.clone()),
+            )
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_1()
+                    .child(Label::new(help_text).color(Color::Muted)),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("0x1F40"), Some(0x1f40));
+        assert_eq!(parse_offset(" 8000 "), Some(8000));
+        assert_eq!(parse_offset("1_000"), Some(1000));
+        assert_eq!(parse_offset("1F40"), None);
+        assert_eq!(parse_offset(""), None);
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(
+            parse_bytes("7F 45 4c46"),
+            Some(vec![0x7f, 0x45, 0x4c, 0x46])
+        );
+        assert_eq!(parse_bytes("\"ELF\""), Some(b"ELF".to_vec()));
+        assert_eq!(parse_bytes("7F 4"), None);
+        assert_eq!(parse_bytes("zz"), None);
+        assert_eq!(parse_bytes("\"\""), None);
+    }
+}
@@ -59,9 +59,9 @@ use text::operation_queue::OperationQueue;
 use text::*;
 pub use text::{
     Anchor, Bias, Buffer as TextBuffer, BufferId, BufferSnapshot as TextBufferSnapshot, Edit,
-    OffsetRangeExt, OffsetUtf16, Patch, Point, PointUtf16, Rope, Selection, SelectionGoal,
-    Subscription, TextDimension, TextSummary, ToOffset, ToOffsetUtf16, ToPoint, ToPointUtf16,
-    Transaction, TransactionId, Unclipped,
+    Encoding, OffsetRangeExt, OffsetUtf16, Patch, Point, PointUtf16, Rope, Selection,
+    SelectionGoal, Subscription, TextDimension, TextSummary, ToOffset, ToOffsetUtf16, ToPoint,
+    ToPointUtf16, Transaction, TransactionId, Unclipped,
 };
 use theme::{ActiveTheme as _, SyntaxTheme};
 #[cfg(any(test, feature = "test-support"))]
@@ -101,6 +101,8 @@ pub struct Buffer {
     /// The version vector when this buffer was last loaded from
     /// or saved to disk.
     saved_version: clock::Global,
+    /// The character encoding of the file, used when reloading or saving it.
+    encoding: Encoding,
     preview_version: clock::Global,
     transaction_depth: usize,
     was_dirty_before_starting_transaction: Option<bool>,
@@ -121,6 +123,9 @@ pub struct Buffer {
     completion_triggers_timestamp: clock::Lamport,
     deferred_ops: OperationQueue<Operation>,
     capability: Capability,
+    /// Whether the buffer's file was too large to open with full features, in
+    /// which case the buffer is never parsed.
+    large_file_mode: bool,
     has_conflict: bool,
     /// Memoize calls to has_changes_since(saved_version).
     /// The contents of a cell are (self.version, has_changes) at the time of a last call.
@@ -299,6 +304,8 @@ pub enum BufferEvent {
     DiagnosticsUpdated,
     /// The buffer gained or lost editing capabilities.
     CapabilityChanged,
+    /// The buffer entered or left large file mode.
+    LargeFileModeChanged,
     /// The buffer was explicitly requested to close.
     Closed,
     /// The buffer was discarded when closing.
@@ -823,6 +830,8 @@ impl Buffer {
         ));
         this.saved_version = proto::deserialize_version(&message.saved_version);
         this.saved_mtime = message.saved_mtime.map(|time| time.into());
+        this.encoding = proto::deserialize_encoding(message.encoding);
+        this.large_file_mode = message.large_file_mode;
         Ok(this)
     }
 
@@ -835,6 +844,8 @@ impl Buffer {
             line_ending: proto::serialize_line_ending(self.line_ending()) as i32,
             saved_version: proto::serialize_version(&self.saved_version),
             saved_mtime: self.saved_mtime.map(|time| time.into()),
+            encoding: Some(proto::serialize_encoding(self.encoding)),
+            large_file_mode: self.large_file_mode,
         }
     }
 
@@ -912,6 +923,7 @@ impl Buffer {
         Self {
             saved_mtime,
             saved_version: buffer.version(),
+            encoding: Encoding::default(),
             preview_version: buffer.version(),
             reload_task: None,
             transaction_depth: 0,
@@ -921,6 +933,7 @@ impl Buffer {
             branch_state: None,
             file,
             capability,
+            large_file_mode: false,
             syntax_map,
             reparse: None,
             non_text_state_update_count: 0,
@@ -1045,6 +1058,7 @@ impl Buffer {
                     merged_operations: Default::default(),
                 }),
                 language: self.language.clone(),
+                large_file_mode: self.large_file_mode,
                 has_conflict: self.has_conflict,
                 has_unsaved_edits: Cell::new(self.has_unsaved_edits.get_mut().clone()),
                 _subscriptions: vec![cx.subscribe(&this, Self::on_base_buffer_event)],
@@ -1207,6 +1221,19 @@ impl Buffer {
         self.saved_mtime
     }
 
+    /// The character encoding of the buffer's file.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Sets the character encoding that the buffer's file is reloaded and saved in.
+    pub fn set_encoding(&mut self, encoding: Encoding, cx: &mut Context<Self>) {
+        if self.encoding != encoding {
+            self.encoding = encoding;
+            cx.notify();
+        }
+    }
+
     /// Assign a language to the buffer.
     pub fn set_language(&mut self, language: Option<Arc<Language>>, cx: &mut Context<Self>) {
         self.non_text_state_update_count += 1;
@@ -1235,6 +1262,28 @@ impl Buffer {
         cx.emit(BufferEvent::CapabilityChanged)
     }
 
+    /// Whether the buffer was opened in large file mode, without syntax
+    /// parsing, language servers or git diffs.
+    pub fn large_file_mode(&self) -> bool {
+        self.large_file_mode
+    }
+
+    /// Enters or leaves large file mode, parsing the buffer once it's left.
+    pub fn set_large_file_mode(&mut self, large_file_mode: bool, cx: &mut Context<Self>) {
+        if self.large_file_mode == large_file_mode {
+            return;
+        }
+        self.large_file_mode = large_file_mode;
+        if large_file_mode {
+            self.reparse = None;
+            self.syntax_map.lock().clear(&self.text);
+        } else {
+            self.reparse(cx);
+        }
+        cx.emit(BufferEvent::LargeFileModeChanged);
+        cx.notify();
+    }
+
     /// This method is called to signal that the buffer has been saved.
     pub fn did_save(
         &mut self,
@@ -1263,15 +1312,22 @@ impl Buffer {
         let (tx, rx) = futures::channel::oneshot::channel();
         let prev_version = self.text.version();
         self.reload_task = Some(cx.spawn(async move |this, cx| {
-            let Some((new_mtime, new_text)) = this.update(cx, |this, cx| {
+            let Some((new_mtime, encoding, new_bytes)) = this.update(cx, |this, cx| {
                 let file = this.file.as_ref()?.as_local()?;
-                Some((file.disk_state().mtime(), file.load(cx)))
+                Some((
+                    file.disk_state().mtime(),
+                    this.encoding,
+                    file.load_bytes(cx),
+                ))
             })?
             else {
                 return Ok(());
             };
 
-            let new_text = new_text.await?;
+            let new_bytes = new_bytes.await?;
+            let (new_text, encoding) = cx
+                .background_spawn(async move { encoding.decode(&new_bytes) })
+                .await;
             let diff = this
                 .update(cx, |this, cx| this.diff(new_text.clone(), cx))?
                 .await;
@@ -1281,7 +1337,7 @@ impl Buffer {
                     this.apply_diff(diff, cx);
                     tx.send(this.finalize_last_transaction().cloned()).ok();
                     this.has_conflict = false;
-                    this.did_reload(this.version(), this.line_ending(), new_mtime, cx);
+                    this.did_reload(this.version(), this.line_ending(), encoding, new_mtime, cx);
                 } else {
                     if !diff.edits.is_empty()
                         || this
@@ -1292,7 +1348,13 @@ impl Buffer {
                         this.has_conflict = true;
                     }
 
-                    this.did_reload(prev_version, this.line_ending(), this.saved_mtime, cx);
+                    this.did_reload(
+                        prev_version,
+                        this.line_ending(),
+                        this.encoding,
+                        this.saved_mtime,
+                        cx,
+                    );
                 }
 
                 this.reload_task.take();
@@ -1306,6 +1368,7 @@ impl Buffer {
         &mut self,
         version: clock::Global,
         line_ending: LineEnding,
+        encoding: Encoding,
         mtime: Option<MTime>,
         cx: &mut Context<Self>,
     ) {
@@ -1313,11 +1376,46 @@ impl Buffer {
         self.has_unsaved_edits
             .set((self.saved_version.clone(), false));
         self.text.set_line_ending(line_ending);
+        self.encoding = encoding;
         self.saved_mtime = mtime;
         cx.emit(BufferEvent::Reloaded);
         cx.notify();
     }
 
+    /// Appends text that was read from the buffer's file after the buffer was
+    /// opened. The text can't be undone, and doesn't make a buffer without
+    /// other edits dirty.
+    pub fn append_loaded_text(&mut self, text: &str, cx: &mut Context<Self>) {
+        let was_saved = !self.has_unsaved_edits();
+        let end = self.len();
+        self.finalize_last_transaction();
+        self.start_transaction();
+        self.edit([(end..end, text)], None, cx);
+        if let Some(transaction_id) = self.end_transaction(cx) {
+            self.forget_transaction(transaction_id);
+        }
+        if was_saved {
+            self.did_reload(
+                self.version(),
+                self.line_ending(),
+                self.encoding,
+                self.saved_mtime,
+                cx,
+            );
+        }
+    }
+
+    /// Marks the buffer as conflicting with its file. This is used when the
+    /// file was reloaded elsewhere while this replica had edits that had not
+    /// reached the other replicas yet.
+    pub fn set_conflict(&mut self, cx: &mut Context<Self>) {
+        if !self.has_conflict {
+            self.has_conflict = true;
+            cx.emit(BufferEvent::DirtyChanged);
+            cx.notify();
+        }
+    }
+
     /// Updates the [`File`] backing this buffer. This should be called when
     /// the file has changed or has been deleted.
     pub fn file_updated(&mut self, new_file: Arc<dyn File>, cx: &mut Context<Self>) {
@@ -1420,7 +1518,7 @@ impl Buffer {
     /// for the same buffer, we only initiate a new parse if we are not already
     /// parsing in the background.
     pub fn reparse(&mut self, cx: &mut Context<Self>) {
-        if self.reparse.is_some() {
+        if self.reparse.is_some() || self.large_file_mode {
             return;
         }
         let language = if let Some(language) = self.language.clone() {
@@ -3671,6 +3769,56 @@ impl BufferSnapshot {
             .filter(|pair| !pair.newline_only)
     }
 
+    /// Returns the ranges of the parentheses, square brackets and curly braces
+    /// overlapping the given range, in order, along with the number of bracket
+    /// pairs enclosing each of them.
+    ///
+    /// The pairs enclosing the whole range are matched by the same syntax query
+    /// as the ones inside of it, so depths don't depend on the rest of the buffer
+    /// being scanned.
+    pub fn bracket_depths<T: ToOffset>(&self, range: Range<T>) -> Vec<(Range<usize>, usize)> {
+        let range = range.start.to_offset(self)..range.end.to_offset(self);
+        let mut pairs = self
+            .bracket_ranges(range.clone())
+            .filter(|pair| {
+                pair.open_range.len() == 1
+                    && pair.close_range.len() == 1
+                    && matches!(
+                        (
+                            self.chars_at(pair.open_range.start).next(),
+                            self.chars_at(pair.close_range.start).next(),
+                        ),
+                        (Some('('), Some(')')) | (Some('['), Some(']')) | (Some('{'), Some('}'))
+                    )
+            })
+            .map(|pair| (pair.open_range, pair.close_range))
+            .collect::<Vec<_>>();
+        // Outer pairs come before the pairs they enclose.
+        pairs.sort_unstable_by_key(|(open, close)| (open.start, Reverse(close.end)));
+        pairs.dedup();
+
+        let mut enclosing_pair_ends = Vec::new();
+        let mut brackets = Vec::new();
+        for (open, close) in pairs {
+            while enclosing_pair_ends
+                .last()
+                .is_some_and(|end| *end <= open.start)
+            {
+                enclosing_pair_ends.pop();
+            }
+            let depth = enclosing_pair_ends.len();
+            enclosing_pair_ends.push(close.end);
+
+            for bracket in [open, close] {
+                if bracket.start < range.end && bracket.end > range.start {
+                    brackets.push((bracket, depth));
+                }
+            }
+        }
+        brackets.sort_unstable_by_key(|(bracket, _)| bracket.start);
+        brackets
+    }
+
     pub fn text_object_ranges<T: ToOffset>(
         &self,
         range: Range<T>,
@@ -1085,6 +1085,43 @@ fn test_enclosing_bracket_ranges(cx: &mut App) {
     );
 }
 
+#[gpui::test]
+fn test_bracket_depths(cx: &mut App) {
+    let text = indoc! {"
+        mod x {
+            mod y {
+                mod z {}
+            }
+        }
+    "};
+    let buffer = cx.new(|cx| Buffer::local(text, cx).with_language(Arc::new(rust_lang()), cx));
+    let snapshot = buffer.read(cx).snapshot();
+    let depths = |range: Range<Point>| {
+        snapshot
+            .bracket_depths(range)
+            .into_iter()
+            .map(|(range, depth)| (snapshot.offset_to_point(range.start), depth))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        depths(Point::zero()..snapshot.max_point()),
+        [
+            (Point::new(0, 6), 0),
+            (Point::new(1, 10), 1),
+            (Point::new(2, 14), 2),
+            (Point::new(2, 15), 2),
+            (Point::new(3, 4), 1),
+            (Point::new(4, 0), 0),
+        ]
+    );
+    // Depths account for the brackets enclosing the range.
+    assert_eq!(
+        depths(Point::new(2, 0)..Point::new(3, 0)),
+        [(Point::new(2, 14), 2), (Point::new(2, 15), 2)]
+    );
+}
+
 #[gpui::test]
 fn test_enclosing_bracket_ranges_where_brackets_are_not_outermost_children(cx: &mut App) {
     let mut assert = |selection_text, bracket_pair_texts| {
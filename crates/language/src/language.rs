@@ -1871,6 +1871,10 @@ impl Grammar {
         })
     }
 
+    pub fn highlights_query(&self) -> Option<&Query> {
+        self.highlights_query.as_ref()
+    }
+
     pub fn highlight_map(&self) -> HighlightMap {
         self.highlight_map.lock().clone()
     }
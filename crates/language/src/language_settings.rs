@@ -88,6 +88,8 @@ pub struct LanguageSettings {
     pub wrap_guides: Vec<usize>,
     /// Indent guide related settings.
     pub indent_guides: IndentGuideSettings,
+    /// Whether to color bracket pairs by how deeply they are nested.
+    pub colorize_brackets: bool,
     /// Whether or not to perform a buffer format before saving.
     pub format_on_save: FormatOnSave,
     /// Whether or not to remove any trailing whitespace from lines of a buffer
@@ -418,6 +420,12 @@ pub struct LanguageSettingsContent {
     /// Indent guide related settings.
     #[serde(default)]
     pub indent_guides: Option<IndentGuideSettings>,
+    /// Whether to color bracket pairs by how deeply they are nested, using
+    /// the theme's bracket colors.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub colorize_brackets: Option<bool>,
     /// Whether or not to perform a buffer format before saving.
     ///
     /// Default: on
@@ -950,6 +958,9 @@ pub enum IndentGuideColoring {
     Fixed,
     /// Use a different color for each indentation level.
     IndentAware,
+    /// Use the color of the bracket pair that opens the indented block, the
+    /// same as with `colorize_brackets`.
+    BracketAware,
 }
 
 /// Determines how indent guide backgrounds are colored.
@@ -961,6 +972,9 @@ pub enum IndentGuideBackgroundColoring {
     Disabled,
     /// Use a different color for each indentation level.
     IndentAware,
+    /// Use the color of the bracket pair that opens the indented block, the
+    /// same as with `colorize_brackets`.
+    BracketAware,
 }
 
 /// The settings for inlay hints.
@@ -1397,6 +1411,7 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
     merge(&mut settings.show_wrap_guides, src.show_wrap_guides);
     merge(&mut settings.wrap_guides, src.wrap_guides.clone());
     merge(&mut settings.indent_guides, src.indent_guides);
+    merge(&mut settings.colorize_brackets, src.colorize_brackets);
     merge(
         &mut settings.code_actions_on_format,
         src.code_actions_on_format.clone(),
@@ -27,6 +27,25 @@ pub fn serialize_line_ending(message: text::LineEnding) -> proto::LineEnding {
     }
 }
 
+/// Deserializes a [`text::Encoding`] from the RPC representation, falling back
+/// to UTF-8 for peers that don't send one.
+pub fn deserialize_encoding(message: Option<proto::Encoding>) -> text::Encoding {
+    message
+        .and_then(|message| {
+            text::Encoding::for_label(&message.name)
+                .map(|encoding| encoding.with_bom(message.has_bom))
+        })
+        .unwrap_or_default()
+}
+
+/// Serializes a [`text::Encoding`] to be sent over RPC.
+pub fn serialize_encoding(encoding: text::Encoding) -> proto::Encoding {
+    proto::Encoding {
+        name: encoding.name().to_string(),
+        has_bom: encoding.has_bom(),
+    }
+}
+
 /// Serializes a [`crate::Operation`] to be sent over RPC.
 pub fn serialize_operation(operation: &crate::Operation) -> proto::Operation {
     proto::Operation {
@@ -242,6 +242,9 @@ pub struct LanguageModelRequest {
     pub tools: Vec<LanguageModelRequestTool>,
     pub stop: Vec<String>,
     pub temperature: Option<f32>,
+    /// The maximum number of tokens to generate, when it should be lower than
+    /// the model's limit.
+    pub max_tokens: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
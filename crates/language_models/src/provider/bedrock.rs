@@ -602,6 +602,9 @@ pub fn into_bedrock(
     max_output_tokens: u32,
     mode: BedrockModelMode,
 ) -> Result<bedrock::Request> {
+    let max_output_tokens = request.max_tokens.map_or(max_output_tokens, |max_tokens| {
+        max_tokens.min(max_output_tokens)
+    });
     let mut new_messages: Vec<BedrockMessage> = Vec::new();
     let mut system_message = String::new();
 
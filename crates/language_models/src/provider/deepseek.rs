@@ -363,6 +363,11 @@ pub fn into_deepseek(
     model: String,
     max_output_tokens: Option<u32>,
 ) -> deepseek::Request {
+    let max_output_tokens = request
+        .max_tokens
+        .into_iter()
+        .chain(max_output_tokens)
+        .min();
     let is_reasoner = model == "deepseek-reasoner";
 
     let len = request.messages.len();
@@ -375,6 +375,11 @@ pub fn into_mistral(
     model: String,
     max_output_tokens: Option<u32>,
 ) -> mistral::Request {
+    let max_output_tokens = request
+        .max_tokens
+        .into_iter()
+        .chain(max_output_tokens)
+        .min();
     let len = request.messages.len();
     let merged_messages =
         request
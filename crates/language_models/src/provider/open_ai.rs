@@ -336,6 +336,11 @@ pub fn into_open_ai(
     model: &Model,
     max_output_tokens: Option<u32>,
 ) -> open_ai::Request {
+    let max_output_tokens = request
+        .max_tokens
+        .into_iter()
+        .chain(max_output_tokens)
+        .min();
     let stream = !model.id().starts_with("o1-");
 
     let mut messages = Vec::new();
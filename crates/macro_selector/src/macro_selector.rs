@@ -0,0 +1,271 @@
+use editor::{
+    actions::{ReplayMacro, ReplayNamedMacro, SaveMacro},
+    keyboard_macro,
+};
+use fuzzy::{StringMatch, StringMatchCandidate, match_strings};
+use gpui::{
+    Action, App, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
+    ParentElement, Render, Styled, WeakEntity, Window,
+};
+use picker::{Picker, PickerDelegate};
+use std::sync::Arc;
+use ui::{HighlightedLabel, ListItem, ListItemSpacing, prelude::*};
+use util::ResultExt;
+use workspace::{ModalView, Workspace, notifications::DetachAndPromptErr};
+
+pub fn init(cx: &mut App) {
+    cx.observe_new(MacroSelector::register).detach();
+}
+
+/// What happens once a macro name is picked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MacroAction {
+    /// Save the last recorded macro under the name.
+    Save,
+    /// Replay the saved macro with the name.
+    Replay,
+}
+
+pub struct MacroSelector {
+    picker: Entity<Picker<MacroSelectorDelegate>>,
+}
+
+impl MacroSelector {
+    fn register(
+        workspace: &mut Workspace,
+        _window: Option<&mut Window>,
+        _: &mut Context<Workspace>,
+    ) {
+        workspace.register_action(move |workspace, _: &SaveMacro, window, cx| {
+            Self::toggle(workspace, MacroAction::Save, window, cx);
+        });
+        workspace.register_action(move |workspace, _: &ReplayNamedMacro, window, cx| {
+            Self::toggle(workspace, MacroAction::Replay, window, cx);
+        });
+    }
+
+    fn toggle(
+        _: &mut Workspace,
+        action: MacroAction,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let names = keyboard_macro::saved_macro_names(cx);
+        cx.spawn_in(window, async move |workspace, cx| {
+            let names = names.await?;
+            workspace.update_in(cx, |workspace, window, cx| {
+                workspace.toggle_modal(window, cx, move |window, cx| {
+                    MacroSelector::new(names, action, window, cx)
+                });
+            })
+        })
+        .detach_and_prompt_err("Failed to load macros", window, cx, |_, _, _| None);
+    }
+
+    fn new(
+        names: Vec<String>,
+        action: MacroAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let delegate = MacroSelectorDelegate::new(cx.entity().downgrade(), names, action);
+        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+        Self { picker }
+    }
+}
+
+impl Render for MacroSelector {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl Focusable for MacroSelector {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for MacroSelector {}
+impl ModalView for MacroSelector {}
+
+pub struct MacroSelectorDelegate {
+    macro_selector: WeakEntity<MacroSelector>,
+    action: MacroAction,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    /// When saving, the typed query if it doesn't name an existing macro.
+    /// It is listed before the matches.
+    new_name: Option<String>,
+    selected_index: usize,
+}
+
+impl MacroSelectorDelegate {
+    fn new(
+        macro_selector: WeakEntity<MacroSelector>,
+        names: Vec<String>,
+        action: MacroAction,
+    ) -> Self {
+        let candidates = names
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, name)| StringMatchCandidate::new(candidate_id, name))
+            .collect();
+        Self {
+            macro_selector,
+            action,
+            candidates,
+            matches: Vec::new(),
+            new_name: None,
+            selected_index: 0,
+        }
+    }
+
+    fn name_at(&self, ix: usize) -> Option<String> {
+        match &self.new_name {
+            Some(new_name) if ix == 0 => Some(new_name.clone()),
+            Some(_) => self.matches.get(ix - 1).map(|mat| mat.string.clone()),
+            None => self.matches.get(ix).map(|mat| mat.string.clone()),
+        }
+    }
+}
+
+impl PickerDelegate for MacroSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        match self.action {
+            MacroAction::Save => "Save the last recorded macro as…".into(),
+            MacroAction::Replay => "Replay a saved macro…".into(),
+        }
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        Some("No saved macros".into())
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len() + usize::from(self.new_name.is_some())
+    }
+
+    fn confirm(&mut self, _: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        if let Some(name) = self.name_at(self.selected_index) {
+            match self.action {
+                MacroAction::Save => {
+                    keyboard_macro::save_last_macro(name, cx).detach_and_prompt_err(
+                        "Failed to save macro",
+                        window,
+                        cx,
+                        |_, _, _| None,
+                    );
+                }
+                MacroAction::Replay => {
+                    let action = ReplayMacro {
+                        name: Some(name),
+                        ..Default::default()
+                    };
+                    window.dispatch_action(action.boxed_clone(), cx);
+                }
+            }
+        }
+        self.dismissed(window, cx);
+    }
+
+    fn dismissed(&mut self, _: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.macro_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        let new_name = Some(query.trim().to_string()).filter(|name| {
+            self.action == MacroAction::Save
+                && !name.is_empty()
+                && !candidates.iter().any(|candidate| candidate.string == *name)
+        });
+        cx.spawn_in(window, async move |this, cx| {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.new_name = new_name;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.match_count().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut Window,
+        _: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let item = ListItem::new(ix)
+            .inset(true)
+            .spacing(ListItemSpacing::Sparse)
+            .toggle_state(selected);
+        let mat = match &self.new_name {
+            Some(new_name) if ix == 0 => {
+                return Some(item.child(Label::new(format!("Save as \"{new_name}\""))));
+            }
+            Some(_) => &self.matches[ix - 1],
+            None => &self.matches[ix],
+        };
+        Some(
+            item.child(HighlightedLabel::new(
+                mat.string.clone(),
+                mat.positions.clone(),
+            ))
+            .when(self.action == MacroAction::Save, |item| {
+                item.end_slot(Label::new("overwrite").color(Color::Muted))
+            }),
+        )
+    }
+}
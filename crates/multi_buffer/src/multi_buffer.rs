@@ -116,6 +116,7 @@ pub enum Event {
 
     LanguageChanged(BufferId),
     CapabilityChanged,
+    LargeFileModeChanged(BufferId),
     Reparsed(BufferId),
     Saved,
     FileHandleChanged,
@@ -141,6 +142,10 @@ pub struct MultiBufferDiffHunk {
     pub diff_base_byte_range: Range<usize>,
     /// Whether or not this hunk also appears in the 'secondary diff'.
     pub secondary_status: DiffHunkSecondaryStatus,
+    /// The ranges of the buffer containing words that were added or changed.
+    pub buffer_word_diffs: Vec<Range<text::Anchor>>,
+    /// The ranges within the buffer's diff base containing words that were deleted or changed.
+    pub base_word_diffs: Vec<Range<usize>>,
 }
 
 impl MultiBufferDiffHunk {
@@ -2383,6 +2388,9 @@ impl MultiBuffer {
                 self.capability = buffer.read(cx).capability();
                 Event::CapabilityChanged
             }
+            language::BufferEvent::LargeFileModeChanged => {
+                Event::LargeFileModeChanged(buffer.read(cx).remote_id())
+            }
             language::BufferEvent::Operation { .. } => return,
         });
     }
@@ -3752,6 +3760,8 @@ impl MultiBufferSnapshot {
                 buffer_range: hunk.buffer_range.clone(),
                 diff_base_byte_range: hunk.diff_base_byte_range.clone(),
                 secondary_status: hunk.secondary_status,
+                buffer_word_diffs: hunk.buffer_word_diffs,
+                base_word_diffs: hunk.base_word_diffs,
             })
         })
     }
@@ -5457,6 +5467,24 @@ impl MultiBufferSnapshot {
         .map(|(range, _, _)| range)
     }
 
+    /// Returns the single-character brackets within the given range, along
+    /// with how deeply each one is nested inside other bracket pairs.
+    pub fn bracket_depths<'a, T: ToOffset>(
+        &'a self,
+        range: Range<T>,
+        colorization_enabled: impl Fn(&BufferSnapshot) -> bool + 'a,
+    ) -> impl Iterator<Item = (Range<usize>, usize)> + 'a {
+        let range = range.start.to_offset(self)..range.end.to_offset(self);
+        self.lift_buffer_metadata(range, move |buffer, range| {
+            if colorization_enabled(buffer) {
+                Some(buffer.bracket_depths(range).into_iter())
+            } else {
+                None
+            }
+        })
+        .map(|(range, depth, _)| (range, depth))
+    }
+
     pub fn runnable_ranges(
         &self,
         range: Range<Anchor>,
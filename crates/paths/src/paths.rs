@@ -223,6 +223,20 @@ pub fn debug_tasks_file() -> &'static PathBuf {
     DEBUG_TASKS_FILE.get_or_init(|| config_dir().join("debug.json"))
 }
 
+/// Returns the path to the `macros.json` file.
+pub fn macros_file() -> &'static PathBuf {
+    static MACROS_FILE: OnceLock<PathBuf> = OnceLock::new();
+    MACROS_FILE.get_or_init(|| config_dir().join("macros.json"))
+}
+
+/// Returns the path to the shell integration directory.
+///
+/// This is where the scripts that the terminal injects into shells are written.
+pub fn shell_integration_dir() -> &'static PathBuf {
+    static SHELL_INTEGRATION_DIR: OnceLock<PathBuf> = OnceLock::new();
+    SHELL_INTEGRATION_DIR.get_or_init(|| data_dir().join("shell_integration"))
+}
+
 /// Returns the path to the extensions directory.
 ///
 /// This is where installed extensions are stored.
@@ -1,6 +1,7 @@
 use crate::{
     ProjectItem as _, ProjectPath,
     lsp_store::OpenLspBufferHandle,
+    project_settings::ProjectSettings,
     search::SearchQuery,
     worktree_store::{WorktreeStore, WorktreeStoreEvent},
 };
@@ -15,19 +16,22 @@ use gpui::{
 use language::{
     Buffer, BufferEvent, Capability, DiskState, File as _, Language, Operation,
     proto::{
-        deserialize_line_ending, deserialize_version, serialize_line_ending, serialize_version,
-        split_operations,
+        deserialize_encoding, deserialize_line_ending, deserialize_version, serialize_encoding,
+        serialize_line_ending, serialize_version, split_operations,
     },
 };
 use rpc::{
     AnyProtoClient, ErrorExt as _, TypedEnvelope,
     proto::{self, ToProto},
 };
+use settings::Settings as _;
 use smol::channel::Receiver;
 use std::{io, path::Path, pin::pin, sync::Arc, time::Instant};
-use text::BufferId;
+use text::{BufferId, Encoding};
 use util::{ResultExt as _, TryFutureExt, debug_panic, maybe};
-use worktree::{File, PathChange, ProjectEntryId, Worktree, WorktreeId};
+use worktree::{
+    BinaryFileError, File, FileRemainder, PathChange, ProjectEntryId, Worktree, WorktreeId,
+};
 
 /// A set of open buffers.
 pub struct BufferStore {
@@ -59,15 +63,27 @@ struct RemoteBufferStore {
     remote_buffer_listeners:
         HashMap<BufferId, Vec<oneshot::Sender<Result<Entity<Buffer>, anyhow::Error>>>>,
     worktree_store: Entity<WorktreeStore>,
+    offline: bool,
+    /// Buffers edited while offline, whose edits may not have reached the
+    /// host yet. Cleared once the buffer is saved.
+    edited_offline: HashSet<BufferId>,
 }
 
 struct LocalBufferStore {
     local_buffer_ids_by_path: HashMap<ProjectPath, BufferId>,
     local_buffer_ids_by_entry_id: HashMap<ProjectEntryId, BufferId>,
+    /// Large files whose content is still being appended to their buffers,
+    /// with the listeners waiting for it to have all been loaded. They can't be
+    /// saved until then.
+    partially_loaded_buffers: HashMap<BufferId, Vec<oneshot::Sender<()>>>,
     worktree_store: Entity<WorktreeStore>,
     _subscription: Subscription,
 }
 
+/// How many bytes of a large file are read at a time once its start has been
+/// opened.
+const LARGE_FILE_CHUNK_LEN: usize = 1024 * 1024;
+
 enum OpenBuffer {
     Complete { buffer: WeakEntity<Buffer> },
     Operations(Vec<Operation>),
@@ -123,6 +139,7 @@ impl RemoteBufferStore {
         let buffer = buffer_handle.read(cx);
         let buffer_id = buffer.remote_id().into();
         let version = buffer.version();
+        let encoding = buffer.encoding();
         let rpc = self.upstream_client.clone();
         let project_id = self.project_id;
         cx.spawn(async move |_, cx| {
@@ -132,12 +149,16 @@ impl RemoteBufferStore {
                     buffer_id,
                     new_path,
                     version: serialize_version(&version),
+                    encoding: Some(serialize_encoding(encoding)),
                 })
                 .await?;
             let version = deserialize_version(&response.version);
             let mtime = response.mtime.map(|mtime| mtime.into());
 
             buffer_handle.update(cx, |buffer, cx| {
+                if response.encoding.is_some() {
+                    buffer.set_encoding(deserialize_encoding(response.encoding), cx);
+                }
                 buffer.did_save(version.clone(), mtime, cx);
             })?;
 
@@ -329,6 +350,7 @@ impl RemoteBufferStore {
     fn reload_buffers(
         &self,
         buffers: HashSet<Entity<Buffer>>,
+        encoding: Option<Encoding>,
         push_to_history: bool,
         cx: &mut Context<BufferStore>,
     ) -> Task<Result<ProjectTransaction>> {
@@ -338,6 +360,7 @@ impl RemoteBufferStore {
                 .iter()
                 .map(|buffer| buffer.read(cx).remote_id().to_proto())
                 .collect(),
+            encoding: encoding.map(serialize_encoding),
         });
 
         cx.spawn(async move |this, cx| {
@@ -363,9 +386,18 @@ impl LocalBufferStore {
         cx: &mut Context<BufferStore>,
     ) -> Task<Result<()>> {
         let buffer = buffer_handle.read(cx);
+        if self
+            .partially_loaded_buffers
+            .contains_key(&buffer.remote_id())
+        {
+            return Task::ready(Err(anyhow!(
+                "{path:?} can't be saved until it has finished loading"
+            )));
+        }
 
         let text = buffer.as_rope().clone();
         let line_ending = buffer.line_ending();
+        let encoding = buffer.encoding();
         let version = buffer.version();
         let buffer_id = buffer.remote_id();
         if buffer
@@ -376,7 +408,7 @@ impl LocalBufferStore {
         }
 
         let save = worktree.update(cx, |worktree, cx| {
-            worktree.write_file(path.as_ref(), text, line_ending, cx)
+            worktree.write_file(path.as_ref(), text, line_ending, encoding, cx)
         });
 
         cx.spawn(async move |this, cx| {
@@ -399,6 +431,7 @@ impl LocalBufferStore {
                             buffer_id: buffer_id.to_proto(),
                             version: serialize_version(&version),
                             mtime: mtime.map(|time| time.into()),
+                            encoding: Some(serialize_encoding(encoding)),
                         })
                         .log_err();
                 }
@@ -637,40 +670,62 @@ impl LocalBufferStore {
         worktree: Entity<Worktree>,
         cx: &mut Context<BufferStore>,
     ) -> Task<Result<Entity<Buffer>>> {
+        let large_file = ProjectSettings::get_global(cx).large_file;
+        // Even with a threshold of zero, a whole chunk is loaded up front so
+        // that there's enough of the file to detect its encoding.
+        let max_len = large_file
+            .size_threshold_mb
+            .saturating_mul(1024 * 1024)
+            .max(LARGE_FILE_CHUNK_LEN as u64) as usize;
         let load_buffer = worktree.update(cx, |worktree, cx| {
-            let load_file = worktree.load_file(path.as_ref(), cx);
+            let load_file = worktree.load_file_start(path.as_ref(), max_len, cx);
             let reservation = cx.reserve_entity();
             let buffer_id = BufferId::from(reservation.entity_id().as_non_zero_u64());
             cx.spawn(async move |_, cx| {
                 let loaded = load_file.await?;
+                let large_file_mode = loaded.remainder.is_some()
+                    || large_file.is_large_file(loaded.text.len() as u64);
+                // Files that are still loading can't be edited, as edits
+                // would be mixed up with the content that's appended to them.
+                let capability = if loaded.remainder.is_some() {
+                    Capability::ReadOnly
+                } else {
+                    Capability::ReadWrite
+                };
                 let text_buffer = cx
                     .background_spawn(async move { text::Buffer::new(0, buffer_id, loaded.text) })
                     .await;
-                cx.insert_entity(reservation, |_| {
-                    Buffer::build(text_buffer, Some(loaded.file), Capability::ReadWrite)
-                })
+                let buffer = cx.insert_entity(reservation, |cx| {
+                    let mut buffer = Buffer::build(text_buffer, Some(loaded.file), capability);
+                    buffer.set_encoding(loaded.encoding, cx);
+                    buffer.set_large_file_mode(large_file_mode, cx);
+                    buffer
+                })?;
+                anyhow::Ok((buffer, loaded.remainder))
             })
         });
 
         cx.spawn(async move |this, cx| {
-            let buffer = match load_buffer.await {
-                Ok(buffer) => Ok(buffer),
-                Err(error) if is_not_found_error(&error) => cx.new(|cx| {
-                    let buffer_id = BufferId::from(cx.entity_id().as_non_zero_u64());
-                    let text_buffer = text::Buffer::new(0, buffer_id, "".into());
-                    Buffer::build(
-                        text_buffer,
-                        Some(Arc::new(File {
-                            worktree,
-                            path,
-                            disk_state: DiskState::New,
-                            entry_id: None,
-                            is_local: true,
-                            is_private: false,
-                        })),
-                        Capability::ReadWrite,
-                    )
-                }),
+            let (buffer, remainder) = match load_buffer.await {
+                Ok(loaded) => Ok(loaded),
+                Err(error) if is_not_found_error(&error) => cx
+                    .new(|cx| {
+                        let buffer_id = BufferId::from(cx.entity_id().as_non_zero_u64());
+                        let text_buffer = text::Buffer::new(0, buffer_id, "".into());
+                        Buffer::build(
+                            text_buffer,
+                            Some(Arc::new(File {
+                                worktree,
+                                path,
+                                disk_state: DiskState::New,
+                                entry_id: None,
+                                is_local: true,
+                                is_private: false,
+                            })),
+                            Capability::ReadWrite,
+                        )
+                    })
+                    .map(|buffer| (buffer, None)),
                 Err(e) => Err(e),
             }?;
             this.update(cx, |this, cx| {
@@ -691,6 +746,11 @@ impl LocalBufferStore {
                             .insert(entry_id, buffer_id);
                     }
                 }
+                if let Some(remainder) = remainder {
+                    this.as_local_mut()
+                        .unwrap()
+                        .load_remainder(buffer.clone(), remainder, cx);
+                }
 
                 anyhow::Ok(())
             })??;
@@ -699,6 +759,81 @@ impl LocalBufferStore {
         })
     }
 
+    /// Appends the rest of a large file to its buffer a chunk at a time,
+    /// keeping the buffer read-only until all of it has been loaded.
+    fn load_remainder(
+        &mut self,
+        buffer: Entity<Buffer>,
+        mut remainder: FileRemainder,
+        cx: &mut Context<BufferStore>,
+    ) {
+        let buffer_id = buffer.read(cx).remote_id();
+        let mtime = buffer.read(cx).saved_mtime();
+        let buffer = buffer.downgrade();
+        self.partially_loaded_buffers.insert(buffer_id, Vec::new());
+        cx.spawn(async move |this, cx| {
+            let result = async {
+                while let Some(chunk) = remainder.read_chunk(LARGE_FILE_CHUNK_LEN).await? {
+                    let appended = buffer.update(cx, |buffer, cx| {
+                        // Stop once the buffer has been reloaded from a newer
+                        // version of the file, which it now holds all of.
+                        if buffer.saved_mtime() != mtime {
+                            return false;
+                        }
+                        buffer.append_loaded_text(&chunk, cx);
+                        true
+                    });
+                    if !appended.unwrap_or(false) {
+                        break;
+                    }
+                }
+                anyhow::Ok(())
+            }
+            .await;
+
+            this.update(cx, |this, cx| {
+                if let Some(this) = this.as_local_mut() {
+                    for listener in this
+                        .partially_loaded_buffers
+                        .remove(&buffer_id)
+                        .into_iter()
+                        .flatten()
+                    {
+                        listener.send(()).ok();
+                    }
+                }
+                let read_only = ProjectSettings::get_global(cx).large_file.read_only;
+                buffer
+                    .update(cx, |buffer, cx| {
+                        if !(buffer.large_file_mode() && read_only) {
+                            buffer.set_capability(Capability::ReadWrite, cx);
+                        }
+                    })
+                    .ok();
+            })?;
+            result
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Waits for the rest of a large file to be appended to its buffer, if it
+    /// still is being.
+    fn wait_until_loaded(&mut self, buffer_id: BufferId) -> impl Future<Output = ()> + use<> {
+        let listener = self
+            .partially_loaded_buffers
+            .get_mut(&buffer_id)
+            .map(|listeners| {
+                let (tx, rx) = oneshot::channel();
+                listeners.push(tx);
+                rx
+            });
+        async move {
+            if let Some(listener) = listener {
+                listener.await.ok();
+            }
+        }
+    }
+
     fn create_buffer(&self, cx: &mut Context<BufferStore>) -> Task<Result<Entity<Buffer>>> {
         cx.spawn(async move |buffer_store, cx| {
             let buffer =
@@ -713,13 +848,21 @@ impl LocalBufferStore {
     fn reload_buffers(
         &self,
         buffers: HashSet<Entity<Buffer>>,
+        encoding: Option<Encoding>,
         push_to_history: bool,
         cx: &mut Context<BufferStore>,
     ) -> Task<Result<ProjectTransaction>> {
         cx.spawn(async move |_, cx| {
             let mut project_transaction = ProjectTransaction::default();
             for buffer in buffers {
-                let transaction = buffer.update(cx, |buffer, cx| buffer.reload(cx))?.await?;
+                let transaction = buffer
+                    .update(cx, |buffer, cx| {
+                        if let Some(encoding) = encoding {
+                            buffer.set_encoding(encoding, cx);
+                        }
+                        buffer.reload(cx)
+                    })?
+                    .await?;
                 buffer.update(cx, |buffer, cx| {
                     if let Some(transaction) = transaction {
                         if !push_to_history {
@@ -742,6 +885,7 @@ impl BufferStore {
         client.add_entity_message_handler(Self::handle_update_buffer_file);
         client.add_entity_request_handler(Self::handle_save_buffer);
         client.add_entity_request_handler(Self::handle_reload_buffers);
+        client.add_entity_request_handler(Self::handle_exit_large_file_mode);
     }
 
     /// Creates a buffer store, optionally retaining its buffers.
@@ -750,6 +894,7 @@ impl BufferStore {
             state: BufferStoreState::Local(LocalBufferStore {
                 local_buffer_ids_by_path: Default::default(),
                 local_buffer_ids_by_entry_id: Default::default(),
+                partially_loaded_buffers: Default::default(),
                 worktree_store: worktree_store.clone(),
                 _subscription: cx.subscribe(&worktree_store, |this, _, event, cx| {
                     if let WorktreeStoreEvent::WorktreeAdded(worktree) = event {
@@ -780,6 +925,8 @@ impl BufferStore {
                 project_id: remote_id,
                 upstream_client,
                 worktree_store: worktree_store.clone(),
+                offline: false,
+                edited_offline: Default::default(),
             }),
             downstream_client: None,
             opened_buffers: Default::default(),
@@ -870,7 +1017,16 @@ impl BufferStore {
             }
         };
 
-        cx.background_spawn(async move { task.await.map_err(|e| anyhow!("{e}")) })
+        cx.background_spawn(async move {
+            task.await.map_err(|e| {
+                // Keep binary files recognizable, so that they can be opened
+                // by something other than a text editor.
+                match e.downcast_ref::<BinaryFileError>() {
+                    Some(error) => anyhow!(error.clone()),
+                    None => anyhow!("{e}"),
+                }
+            })
+        })
     }
 
     pub fn create_buffer(&mut self, cx: &mut Context<Self>) -> Task<Result<Entity<Buffer>>> {
@@ -1041,6 +1197,21 @@ impl BufferStore {
         }
     }
 
+    /// Keeps buffers editable while the connection to the host is lost, and
+    /// remembers which ones get edited so that changes made to their files on
+    /// the host in the meantime are reported as conflicts.
+    pub fn went_offline(&mut self) {
+        if let Some(remote) = self.as_remote_mut() {
+            remote.offline = true;
+        }
+    }
+
+    pub fn reconnected(&mut self) {
+        if let Some(remote) = self.as_remote_mut() {
+            remote.offline = false;
+        }
+    }
+
     pub fn shared(&mut self, remote_id: u64, downstream_client: AnyProtoClient, _cx: &mut App) {
         self.downstream_client = Some((downstream_client, remote_id));
     }
@@ -1134,9 +1305,22 @@ impl BufferStore {
                         version: serialize_version(&buffer.version()),
                         mtime: buffer.saved_mtime().map(|t| t.into()),
                         line_ending: serialize_line_ending(buffer.line_ending()) as i32,
+                        encoding: Some(serialize_encoding(buffer.encoding())),
                     })
                     .log_err();
             }
+            BufferEvent::Edited => {
+                if let Some(remote) = self.as_remote_mut() {
+                    if remote.offline {
+                        remote.edited_offline.insert(buffer.read(cx).remote_id());
+                    }
+                }
+            }
+            BufferEvent::Saved => {
+                if let Some(remote) = self.as_remote_mut() {
+                    remote.edited_offline.remove(&buffer.read(cx).remote_id());
+                }
+            }
             BufferEvent::LanguageChanged => {}
             _ => {}
         }
@@ -1250,6 +1434,7 @@ impl BufferStore {
                         mtime: buffer.saved_mtime().map(|time| time.into()),
                         line_ending: language::proto::serialize_line_ending(buffer.line_ending())
                             as i32,
+                        encoding: Some(serialize_encoding(buffer.encoding())),
                     })
                     .log_err();
 
@@ -1363,17 +1548,29 @@ impl BufferStore {
                 buffer.wait_for_version(deserialize_version(&envelope.payload.version))
             })?
             .await?;
-        let buffer_id = buffer.update(&mut cx, |buffer, _| buffer.remote_id())?;
+        let (buffer_id, previous_encoding) = buffer.update(&mut cx, |buffer, cx| {
+            let previous_encoding = buffer.encoding();
+            if envelope.payload.encoding.is_some() {
+                buffer.set_encoding(deserialize_encoding(envelope.payload.encoding), cx);
+            }
+            (buffer.remote_id(), previous_encoding)
+        })?;
 
-        if let Some(new_path) = envelope.payload.new_path {
+        let save = if let Some(new_path) = envelope.payload.new_path {
             let new_path = ProjectPath::from_proto(new_path);
             this.update(&mut cx, |this, cx| {
                 this.save_buffer_as(buffer.clone(), new_path, cx)
             })?
-            .await?;
+            .await
         } else {
             this.update(&mut cx, |this, cx| this.save_buffer(buffer.clone(), cx))?
-                .await?;
+                .await
+        };
+        if let Err(error) = save {
+            buffer.update(&mut cx, |buffer, cx| {
+                buffer.set_encoding(previous_encoding, cx)
+            })?;
+            return Err(error);
         }
 
         buffer.update(&mut cx, |buffer, _| proto::BufferSaved {
@@ -1381,6 +1578,7 @@ impl BufferStore {
             buffer_id: buffer_id.into(),
             version: serialize_version(buffer.saved_version()),
             mtime: buffer.saved_mtime().map(|time| time.into()),
+            encoding: Some(serialize_encoding(buffer.encoding())),
         })
     }
 
@@ -1417,9 +1615,13 @@ impl BufferStore {
         let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
         let version = deserialize_version(&envelope.payload.version);
         let mtime = envelope.payload.mtime.clone().map(|time| time.into());
+        let encoding = envelope.payload.encoding.clone();
         this.update(&mut cx, move |this, cx| {
             if let Some(buffer) = this.get_possibly_incomplete(buffer_id) {
                 buffer.update(cx, |buffer, cx| {
+                    if encoding.is_some() {
+                        buffer.set_encoding(deserialize_encoding(encoding), cx);
+                    }
                     buffer.did_save(version, mtime, cx);
                 });
             }
@@ -1431,6 +1633,7 @@ impl BufferStore {
                         buffer_id: buffer_id.into(),
                         mtime: envelope.payload.mtime,
                         version: envelope.payload.version,
+                        encoding: envelope.payload.encoding,
                     })
                     .log_err();
             }
@@ -1449,10 +1652,20 @@ impl BufferStore {
             proto::LineEnding::from_i32(envelope.payload.line_ending)
                 .ok_or_else(|| anyhow!("missing line ending"))?,
         );
+        let encoding = deserialize_encoding(envelope.payload.encoding.clone());
         this.update(&mut cx, |this, cx| {
+            let edited_offline = this
+                .as_remote()
+                .is_some_and(|remote| remote.edited_offline.contains(&buffer_id));
             if let Some(buffer) = this.get_possibly_incomplete(buffer_id) {
                 buffer.update(cx, |buffer, cx| {
-                    buffer.did_reload(version, line_ending, mtime, cx);
+                    buffer.did_reload(version, line_ending, encoding, mtime, cx);
+                    // The file changed on the host before the edits made
+                    // offline arrived there, so they were merged into the new
+                    // contents instead of being saved over the old ones.
+                    if edited_offline && buffer.is_dirty() {
+                        buffer.set_conflict(cx);
+                    }
                 });
             }
 
@@ -1464,6 +1677,7 @@ impl BufferStore {
                         mtime: envelope.payload.mtime,
                         version: envelope.payload.version,
                         line_ending: envelope.payload.line_ending,
+                        encoding: envelope.payload.encoding,
                     })
                     .log_err();
             }
@@ -1475,13 +1689,82 @@ impl BufferStore {
         buffers: HashSet<Entity<Buffer>>,
         push_to_history: bool,
         cx: &mut Context<Self>,
+    ) -> Task<Result<ProjectTransaction>> {
+        self.reload_buffers_in_encoding(buffers, None, push_to_history, cx)
+    }
+
+    /// Reloads a buffer from disk, decoding its file in the given encoding.
+    pub fn reload_buffer_with_encoding(
+        &self,
+        buffer: Entity<Buffer>,
+        encoding: Encoding,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<ProjectTransaction>> {
+        self.reload_buffers_in_encoding(HashSet::from_iter([buffer]), Some(encoding), true, cx)
+    }
+
+    /// Whether the rest of a large file is still being appended to its buffer.
+    pub fn is_partially_loaded(&self, buffer_id: BufferId) -> bool {
+        self.as_local()
+            .is_some_and(|this| this.partially_loaded_buffers.contains_key(&buffer_id))
+    }
+
+    /// Enables syntax parsing, language servers and git diffs for a buffer that
+    /// was opened in large file mode. The returned task completes once all of
+    /// the file has been loaded, when a buffer that was read-only because of
+    /// its size becomes editable.
+    pub fn exit_large_file_mode(
+        &mut self,
+        buffer: Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        buffer.update(cx, |buffer, cx| buffer.set_large_file_mode(false, cx));
+        let buffer_id = buffer.read(cx).remote_id();
+        match &mut self.state {
+            BufferStoreState::Local(this) => {
+                let loaded = this.wait_until_loaded(buffer_id);
+                cx.spawn(async move |_, cx| {
+                    loaded.await;
+                    buffer.update(cx, |buffer, cx| {
+                        if buffer.read_only() {
+                            buffer.set_capability(Capability::ReadWrite, cx)
+                        }
+                    })
+                })
+            }
+            BufferStoreState::Remote(remote) => {
+                // The host decides which language servers the buffer is
+                // registered with, so it has to leave large file mode too. It
+                // responds once it has loaded all of the file.
+                let request = remote.upstream_client.request(proto::ExitLargeFileMode {
+                    project_id: remote.project_id,
+                    buffer_id: buffer_id.to_proto(),
+                });
+                cx.background_spawn(async move {
+                    request.await?;
+                    Ok(())
+                })
+            }
+        }
+    }
+
+    fn reload_buffers_in_encoding(
+        &self,
+        buffers: HashSet<Entity<Buffer>>,
+        encoding: Option<Encoding>,
+        push_to_history: bool,
+        cx: &mut Context<Self>,
     ) -> Task<Result<ProjectTransaction>> {
         if buffers.is_empty() {
             return Task::ready(Ok(ProjectTransaction::default()));
         }
         match &self.state {
-            BufferStoreState::Local(this) => this.reload_buffers(buffers, push_to_history, cx),
-            BufferStoreState::Remote(this) => this.reload_buffers(buffers, push_to_history, cx),
+            BufferStoreState::Local(this) => {
+                this.reload_buffers(buffers, encoding, push_to_history, cx)
+            }
+            BufferStoreState::Remote(this) => {
+                this.reload_buffers(buffers, encoding, push_to_history, cx)
+            }
         }
     }
 
@@ -1491,13 +1774,17 @@ impl BufferStore {
         mut cx: AsyncApp,
     ) -> Result<proto::ReloadBuffersResponse> {
         let sender_id = envelope.original_sender_id().unwrap_or_default();
+        let encoding = envelope
+            .payload
+            .encoding
+            .map(|encoding| deserialize_encoding(Some(encoding)));
         let reload = this.update(&mut cx, |this, cx| {
             let mut buffers = HashSet::default();
             for buffer_id in &envelope.payload.buffer_ids {
                 let buffer_id = BufferId::new(*buffer_id)?;
                 buffers.insert(this.get_existing(buffer_id)?);
             }
-            Ok::<_, anyhow::Error>(this.reload_buffers(buffers, false, cx))
+            Ok::<_, anyhow::Error>(this.reload_buffers_in_encoding(buffers, encoding, false, cx))
         })??;
 
         let project_transaction = reload.await?;
@@ -1509,6 +1796,20 @@ impl BufferStore {
         })
     }
 
+    async fn handle_exit_large_file_mode(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::ExitLargeFileMode>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        this.update(&mut cx, |this, cx| {
+            let buffer = this.get_existing(buffer_id)?;
+            anyhow::Ok(this.exit_large_file_mode(buffer, cx))
+        })??
+        .await?;
+        Ok(proto::Ack {})
+    }
+
     pub fn create_buffer_for_peer(
         &mut self,
         buffer: &Entity<Buffer>,
@@ -35,7 +35,7 @@ use gpui::{
 };
 use language::{
     Buffer, BufferEvent, Language, LanguageRegistry,
-    proto::{deserialize_version, serialize_version},
+    proto::{deserialize_encoding, deserialize_version, serialize_encoding, serialize_version},
 };
 use parking_lot::Mutex;
 use postage::stream::Stream as _;
@@ -58,7 +58,7 @@ use std::{
     time::Instant,
 };
 use sum_tree::{Edit, SumTree, TreeSet};
-use text::{Bias, BufferId};
+use text::{Bias, BufferId, Encoding};
 use util::{ResultExt, debug_panic, post_inc};
 use worktree::{
     File, PathKey, PathProgress, PathSummary, PathTarget, UpdatedGitRepositoriesSet, Worktree,
@@ -569,18 +569,22 @@ impl GitStore {
             }
         }
 
+        if buffer.read(cx).large_file_mode() {
+            return Task::ready(Err(anyhow!("git diffs are disabled in large file mode")));
+        }
         let Some((repo, repo_path)) =
             self.repository_and_path_for_buffer_id(buffer.read(cx).remote_id(), cx)
         else {
             return Task::ready(Err(anyhow!("failed to find git repository for buffer")));
         };
 
+        let encoding = buffer.read(cx).encoding();
         let task = self
             .loading_diffs
             .entry((buffer_id, DiffKind::Unstaged))
             .or_insert_with(|| {
                 let staged_text = repo.update(cx, |repo, cx| {
-                    repo.load_staged_text(buffer_id, repo_path, cx)
+                    repo.load_staged_text(buffer_id, repo_path, encoding, cx)
                 });
                 cx.spawn(async move |this, cx| {
                     Self::open_diff_internal(
@@ -626,18 +630,22 @@ impl GitStore {
             }
         }
 
+        if buffer.read(cx).large_file_mode() {
+            return Task::ready(Err(anyhow!("git diffs are disabled in large file mode")));
+        }
         let Some((repo, repo_path)) =
             self.repository_and_path_for_buffer_id(buffer.read(cx).remote_id(), cx)
         else {
             return Task::ready(Err(anyhow!("failed to find git repository for buffer")));
         };
 
+        let encoding = buffer.read(cx).encoding();
         let task = self
             .loading_diffs
             .entry((buffer_id, DiffKind::Uncommitted))
             .or_insert_with(|| {
                 let changes = repo.update(cx, |repo, cx| {
-                    repo.load_committed_text(buffer_id, repo_path, cx)
+                    repo.load_committed_text(buffer_id, repo_path, encoding, cx)
                 });
 
                 cx.spawn(async move |this, cx| {
@@ -1261,11 +1269,17 @@ impl GitStore {
                     diff_state.hunk_staging_operation_count
                 });
                 if let Some((repo, path)) = self.repository_and_path_for_buffer_id(buffer_id, cx) {
+                    let encoding = self
+                        .buffer_store
+                        .read(cx)
+                        .get(buffer_id)
+                        .map_or(Encoding::UTF_8, |buffer| buffer.read(cx).encoding());
                     let recv = repo.update(cx, |repo, cx| {
                         log::debug!("hunks changed for {}", path.display());
                         repo.spawn_set_index_text_job(
                             path,
                             new_index_text.as_ref().map(|rope| rope.to_string()),
+                            encoding,
                             Some(hunk_staging_operation_count),
                             cx,
                         )
@@ -1631,6 +1645,7 @@ impl GitStore {
                 repository_handle.spawn_set_index_text_job(
                     repo_path,
                     envelope.payload.text,
+                    deserialize_encoding(envelope.payload.encoding),
                     None,
                     cx,
                 )
@@ -2619,6 +2634,7 @@ impl Repository {
                             .filter_map(|(buffer_id, diff_state)| {
                                 let buffer_store = git_store.buffer_store.read(cx);
                                 let buffer = buffer_store.get(*buffer_id)?;
+                                let encoding = buffer.read(cx).encoding();
                                 let file = File::from_dyn(buffer.read(cx).file())?;
                                 let abs_path =
                                     file.worktree.read(cx).absolutize(&file.path).ok()?;
@@ -2640,6 +2656,7 @@ impl Repository {
                                     Some((
                                         buffer,
                                         repo_path,
+                                        encoding,
                                         has_unstaged_diff.then(|| diff_state.index_text.clone()),
                                         has_uncommitted_diff.then(|| diff_state.head_text.clone()),
                                     ))
@@ -2652,16 +2669,18 @@ impl Repository {
                 let buffer_diff_base_changes = cx
                     .background_spawn(async move {
                         let mut changes = Vec::new();
-                        for (buffer, repo_path, current_index_text, current_head_text) in
+                        for (buffer, repo_path, encoding, current_index_text, current_head_text) in
                             &repo_diff_state_updates
                         {
                             let index_text = if current_index_text.is_some() {
-                                backend.load_index_text(repo_path.clone()).await
+                                backend.load_index_text(repo_path.clone(), *encoding).await
                             } else {
                                 None
                             };
                             let head_text = if current_head_text.is_some() {
-                                backend.load_committed_text(repo_path.clone()).await
+                                backend
+                                    .load_committed_text(repo_path.clone(), *encoding)
+                                    .await
                             } else {
                                 None
                             };
@@ -3470,6 +3489,7 @@ impl Repository {
         &mut self,
         path: RepoPath,
         content: Option<String>,
+        encoding: Encoding,
         hunk_staging_operation_count: Option<usize>,
         cx: &mut Context<Self>,
     ) -> oneshot::Receiver<anyhow::Result<()>> {
@@ -3488,7 +3508,7 @@ impl Repository {
                         ..
                     } => {
                         backend
-                            .set_index_text(path.clone(), content, environment.clone())
+                            .set_index_text(path.clone(), content, encoding, environment.clone())
                             .await?;
                     }
                     RepositoryState::Remote { project_id, client } => {
@@ -3498,6 +3518,7 @@ impl Repository {
                                 repository_id: id.to_proto(),
                                 path: path.as_ref().to_proto(),
                                 text: content,
+                                encoding: Some(serialize_encoding(encoding)),
                             })
                             .await?;
                     }
@@ -3951,12 +3972,13 @@ impl Repository {
         &mut self,
         buffer_id: BufferId,
         repo_path: RepoPath,
+        encoding: Encoding,
         cx: &App,
     ) -> Task<Result<Option<String>>> {
         let rx = self.send_job(None, move |state, _| async move {
             match state {
                 RepositoryState::Local { backend, .. } => {
-                    anyhow::Ok(backend.load_index_text(repo_path).await)
+                    anyhow::Ok(backend.load_index_text(repo_path, encoding).await)
                 }
                 RepositoryState::Remote { project_id, client } => {
                     let response = client
@@ -3976,13 +3998,16 @@ impl Repository {
         &mut self,
         buffer_id: BufferId,
         repo_path: RepoPath,
+        encoding: Encoding,
         cx: &App,
     ) -> Task<Result<DiffBasesChange>> {
         let rx = self.send_job(None, move |state, _| async move {
             match state {
                 RepositoryState::Local { backend, .. } => {
-                    let committed_text = backend.load_committed_text(repo_path.clone()).await;
-                    let staged_text = backend.load_index_text(repo_path).await;
+                    let committed_text = backend
+                        .load_committed_text(repo_path.clone(), encoding)
+                        .await;
+                    let staged_text = backend.load_index_text(repo_path, encoding).await;
                     let diff_bases_change = if committed_text == staged_text {
                         DiffBasesChange::SetBoth(committed_text)
                     } else {
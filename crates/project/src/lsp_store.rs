@@ -2242,6 +2242,9 @@ impl LocalLspStore {
     ) {
         let buffer = buffer_handle.read(cx);
         let buffer_id = buffer.remote_id();
+        if buffer.large_file_mode() {
+            return;
+        }
 
         let Some(file) = File::from_dyn(buffer.file()) else {
             return;
@@ -3758,6 +3761,21 @@ impl LspStore {
                 self.on_buffer_saved(buffer, cx);
             }
 
+            language::BufferEvent::LargeFileModeChanged => {
+                if !buffer.read(cx).large_file_mode() {
+                    self.detect_language_for_buffer(&buffer, cx);
+                    if let Some(local) = self.as_local_mut() {
+                        local.initialize_buffer(&buffer, cx);
+                        if local
+                            .registered_buffers
+                            .contains_key(&buffer.read(cx).remote_id())
+                        {
+                            local.register_buffer_with_language_servers(&buffer, cx);
+                        }
+                    }
+                }
+            }
+
             _ => {}
         }
     }
@@ -4030,6 +4048,9 @@ impl LspStore {
     ) -> Option<language::AvailableLanguage> {
         // If the buffer has a language, set it and start the language server if we haven't already.
         let buffer = buffer_handle.read(cx);
+        if buffer.large_file_mode() {
+            return None;
+        }
         let file = buffer.file()?;
 
         let content = buffer.as_rope();
@@ -10,6 +10,7 @@ pub mod lsp_store;
 mod manifest_tree;
 pub mod prettier_store;
 pub mod project_settings;
+pub mod remote_ports;
 pub mod search;
 mod task_inventory;
 pub mod task_store;
@@ -79,6 +80,7 @@ use parking_lot::Mutex;
 pub use prettier_store::PrettierStore;
 use project_settings::{ProjectSettings, SettingsObserver, SettingsObserverEvent};
 use remote::{SshConnectionOptions, SshRemoteClient};
+use remote_ports::RemotePorts;
 use rpc::{
     AnyProtoClient, ErrorCode,
     proto::{FromProto, LanguageServerPromptResponse, SSH_PROJECT_ID, ToProto},
@@ -101,18 +103,19 @@ use std::{
 
 use task_store::TaskStore;
 use terminals::Terminals;
-use text::{Anchor, BufferId};
+use text::{Anchor, BufferId, Encoding};
 use toolchain_store::EmptyToolchainStore;
 use util::{
     ResultExt as _, maybe,
     paths::{SanitizedPath, compare_paths},
 };
-use worktree::{CreatedEntry, Snapshot, Traversal};
 pub use worktree::{
-    Entry, EntryKind, FS_WATCH_LATENCY, File, LocalWorktree, PathChange, ProjectEntryId,
-    UpdatedEntriesSet, UpdatedGitRepositoriesSet, Worktree, WorktreeId, WorktreeSettings,
+    BinaryFileError, Entry, EntryKind, FS_WATCH_LATENCY, File, LocalWorktree, PathChange,
+    ProjectEntryId, UpdatedEntriesSet, UpdatedGitRepositoriesSet, Worktree, WorktreeId,
+    WorktreeSettings,
 };
-use worktree_store::{WorktreeStore, WorktreeStoreEvent};
+use worktree::{CreatedEntry, Snapshot, Traversal};
+use worktree_store::{FileTransfer, WorktreeStore, WorktreeStoreEvent};
 
 pub use fs::*;
 pub use language::Location;
@@ -193,6 +196,7 @@ pub struct Project {
     environment: Entity<ProjectEnvironment>,
     settings_observer: Entity<SettingsObserver>,
     toolchain_store: Option<Entity<ToolchainStore>>,
+    remote_ports: Option<Entity<RemotePorts>>,
 }
 
 #[derive(Default)]
@@ -978,6 +982,7 @@ impl Project {
                 search_excluded_history: Self::new_search_history(),
 
                 toolchain_store: Some(toolchain_store),
+                remote_ports: None,
             }
         })
     }
@@ -1080,6 +1085,8 @@ impl Project {
 
             cx.subscribe(&ssh, Self::on_ssh_event).detach();
 
+            let remote_ports = cx.new(|cx| RemotePorts::new(ssh.clone(), cx));
+
             let this = Self {
                 buffer_ordered_messages_tx: tx,
                 collaborators: Default::default(),
@@ -1133,6 +1140,7 @@ impl Project {
                 search_excluded_history: Self::new_search_history(),
 
                 toolchain_store: Some(toolchain_store),
+                remote_ports: Some(remote_ports.clone()),
             };
 
             // ssh -> local machine handlers
@@ -1144,6 +1152,7 @@ impl Project {
             ssh.subscribe_to_entity(SSH_PROJECT_ID, &this.dap_store);
             ssh.subscribe_to_entity(SSH_PROJECT_ID, &this.settings_observer);
             ssh.subscribe_to_entity(SSH_PROJECT_ID, &this.git_store);
+            ssh.subscribe_to_entity(SSH_PROJECT_ID, &remote_ports);
 
             ssh_proto.add_entity_message_handler(Self::handle_create_buffer_for_peer);
             ssh_proto.add_entity_message_handler(Self::handle_update_worktree);
@@ -1159,6 +1168,7 @@ impl Project {
             ToolchainStore::init(&ssh_proto);
             DapStore::init(&ssh_proto);
             GitStore::init(&ssh_proto);
+            RemotePorts::init(&ssh_proto);
 
             this
         })
@@ -1367,6 +1377,7 @@ impl Project {
                 environment,
                 remotely_created_models: Arc::new(Mutex::new(RemotelyCreatedModels::default())),
                 toolchain_store: None,
+                remote_ports: None,
             };
             this.set_role(role, cx);
             for worktree in worktrees {
@@ -1637,6 +1648,11 @@ impl Project {
         self.ssh_client.clone()
     }
 
+    /// The ports listening on the remote machine, for SSH projects.
+    pub fn remote_ports(&self) -> Option<&Entity<RemotePorts>> {
+        self.remote_ports.as_ref()
+    }
+
     pub fn user_store(&self) -> Entity<UserStore> {
         self.user_store.clone()
     }
@@ -2016,6 +2032,38 @@ impl Project {
         }))
     }
 
+    /// Downloads entries of a worktree of an SSH project into a local directory,
+    /// replacing existing files only if `overwrite` is true.
+    pub fn download_entries(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        target_directory: PathBuf,
+        overwrite: bool,
+        cx: &mut Context<Self>,
+    ) -> (Entity<FileTransfer>, Task<Result<()>>) {
+        let fs = self.fs.clone();
+        self.worktree_store.update(cx, |worktree_store, cx| {
+            worktree_store.download_entries(worktree_id, paths, target_directory, overwrite, fs, cx)
+        })
+    }
+
+    /// Uploads local files and directories into a worktree of an SSH project,
+    /// replacing existing files only if `overwrite` is true.
+    pub fn upload_paths(
+        &self,
+        worktree_id: WorktreeId,
+        abs_paths: Vec<PathBuf>,
+        target_directory: Arc<Path>,
+        overwrite: bool,
+        cx: &mut Context<Self>,
+    ) -> (Entity<FileTransfer>, Task<Result<()>>) {
+        let fs = self.fs.clone();
+        self.worktree_store.update(cx, |worktree_store, cx| {
+            worktree_store.upload_paths(worktree_id, abs_paths, target_directory, overwrite, fs, cx)
+        })
+    }
+
     pub fn shared(&mut self, project_id: u64, cx: &mut Context<Self>) -> Result<()> {
         if !matches!(self.client_state, ProjectClientState::Local) {
             return Err(anyhow!("project was already shared"));
@@ -2483,6 +2531,13 @@ impl Project {
 
         self.request_buffer_diff_recalculation(buffer, cx);
 
+        if buffer.read(cx).large_file_mode() && ProjectSettings::get_global(cx).large_file.read_only
+        {
+            buffer.update(cx, |buffer, cx| {
+                buffer.set_capability(Capability::ReadOnly, cx)
+            });
+        }
+
         cx.subscribe(buffer, |this, buffer, event, cx| {
             this.on_buffer_event(buffer, event, cx);
         })
@@ -2772,6 +2827,14 @@ impl Project {
         cx: &mut Context<Self>,
     ) {
         match event {
+            remote::SshRemoteEvent::Offline => {
+                self.buffer_store
+                    .update(cx, |buffer_store, _| buffer_store.went_offline());
+            }
+            remote::SshRemoteEvent::Reconnected => {
+                self.buffer_store
+                    .update(cx, |buffer_store, _| buffer_store.reconnected());
+            }
             remote::SshRemoteEvent::Disconnected => {
                 // if self.is_via_ssh() {
                 // self.collaborators.clear();
@@ -3150,6 +3213,42 @@ impl Project {
         })
     }
 
+    pub fn reload_buffer_with_encoding(
+        &self,
+        buffer: Entity<Buffer>,
+        encoding: Encoding,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<ProjectTransaction>> {
+        self.buffer_store.update(cx, |buffer_store, cx| {
+            buffer_store.reload_buffer_with_encoding(buffer, encoding, cx)
+        })
+    }
+
+    /// Enables syntax parsing, language servers and git diffs for a buffer that
+    /// was opened in large file mode.
+    pub fn exit_large_file_mode(
+        &self,
+        buffer: Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let exit = self.buffer_store.update(cx, |buffer_store, cx| {
+            buffer_store.exit_large_file_mode(buffer.clone(), cx)
+        });
+        cx.spawn(async move |this, cx| {
+            exit.await?;
+            // Buffers that were read-only because of their size become editable
+            // once all of their content has been loaded, unless the project is
+            // read-only.
+            this.update(cx, |this, cx| {
+                if !this.is_read_only(cx) && buffer.read(cx).read_only() {
+                    buffer.update(cx, |buffer, cx| {
+                        buffer.set_capability(Capability::ReadWrite, cx)
+                    });
+                }
+            })
+        })
+    }
+
     pub fn reload_images(
         &self,
         images: HashSet<Entity<ImageItem>>,
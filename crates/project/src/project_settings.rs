@@ -69,6 +69,10 @@ pub struct ProjectSettings {
     /// Configuration for session-related features
     #[serde(default)]
     pub session: SessionSettings,
+
+    /// Configuration for how files too large to edit comfortably are opened
+    #[serde(default)]
+    pub large_file: LargeFileSettings,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
@@ -321,6 +325,42 @@ impl Default for SessionSettings {
     }
 }
 
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct LargeFileSettings {
+    /// The size in megabytes above which files are opened in large file
+    /// mode, without syntax highlighting, language servers or git diffs.
+    ///
+    /// Default: 20
+    #[serde(default = "default_large_file_size_threshold_mb")]
+    pub size_threshold_mb: u64,
+    /// Whether files opened in large file mode are read-only until full
+    /// features are enabled for them.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl LargeFileSettings {
+    /// Whether a file of the given size in bytes should be opened in large file mode.
+    pub fn is_large_file(&self, len: u64) -> bool {
+        len > self.size_threshold_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+impl Default for LargeFileSettings {
+    fn default() -> Self {
+        Self {
+            size_threshold_mb: default_large_file_size_threshold_mb(),
+            read_only: false,
+        }
+    }
+}
+
+fn default_large_file_size_threshold_mb() -> u64 {
+    20
+}
+
 impl Settings for ProjectSettings {
     const KEY: Option<&'static str> = None;
 
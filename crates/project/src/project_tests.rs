@@ -18,8 +18,8 @@ use git2::RepositoryInitOptions;
 use gpui::{App, BackgroundExecutor, SemanticVersion, UpdateGlobal};
 use http_client::Url;
 use language::{
-    Diagnostic, DiagnosticEntry, DiagnosticSet, DiskState, FakeLspAdapter, LanguageConfig,
-    LanguageMatcher, LanguageName, LineEnding, OffsetRangeExt, Point, ToPoint,
+    Diagnostic, DiagnosticEntry, DiagnosticSet, DiskState, Encoding, FakeLspAdapter,
+    LanguageConfig, LanguageMatcher, LanguageName, LineEnding, OffsetRangeExt, Point, ToPoint,
     language_settings::{AllLanguageSettings, LanguageSettingsContent, language_settings},
     tree_sitter_rust, tree_sitter_typescript,
 };
@@ -3487,6 +3487,235 @@ async fn test_save_file(cx: &mut gpui::TestAppContext) {
     assert_eq!(new_text, buffer.update(cx, |buffer, _| buffer.text()));
 }
 
+#[gpui::test]
+async fn test_save_file_in_detected_encoding(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/dir"), json!({})).await;
+    // "café" in Windows-1252.
+    fs.insert_file(path!("/dir/file1"), b"caf\xe9\n".to_vec())
+        .await;
+
+    let project = Project::test(fs.clone(), [path!("/dir").as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer(path!("/dir/file1"), cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "caf\u{e9}\n");
+        assert_eq!(buffer.encoding().name(), "windows-1252");
+        buffer.edit([(0..0, "cr\u{e8}me ")], None, cx);
+    });
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.load_bytes(Path::new(path!("/dir/file1"))).await.unwrap(),
+        b"cr\xe8me caf\xe9\n"
+    );
+
+    // Reopening the file as UTF-8 shows the bytes that aren't valid UTF-8 as
+    // replacement characters.
+    project
+        .update(cx, |project, cx| {
+            project.reload_buffer_with_encoding(buffer.clone(), Encoding::UTF_8, cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "cr\u{fffd}me caf\u{fffd}\n");
+        assert_eq!(buffer.encoding(), Encoding::UTF_8);
+        assert!(!buffer.is_dirty());
+    });
+}
+
+#[gpui::test]
+async fn test_open_large_file(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.large_file.size_threshold_mb = 0;
+                settings.large_file.read_only = true;
+            });
+        });
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({
+            "large.rs": "fn main() {}\n",
+            "empty.rs": "",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/dir").as_ref()], cx).await;
+    project.read_with(cx, |project, _| project.languages().add(rust_lang()));
+
+    let empty_buffer = project
+        .update(cx, |p, cx| p.open_local_buffer(path!("/dir/empty.rs"), cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    empty_buffer.update(cx, |buffer, _| {
+        assert!(!buffer.large_file_mode());
+        assert_eq!(buffer.language().map(|l| l.name()), Some("Rust".into()));
+    });
+
+    let large_buffer = project
+        .update(cx, |p, cx| p.open_local_buffer(path!("/dir/large.rs"), cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    large_buffer.update(cx, |buffer, _| {
+        assert!(buffer.large_file_mode());
+        assert!(buffer.read_only());
+        assert_eq!(buffer.language(), None);
+    });
+    project
+        .update(cx, |project, cx| {
+            project.exit_large_file_mode(large_buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    large_buffer.update(cx, |buffer, _| {
+        assert!(!buffer.large_file_mode());
+        assert!(!buffer.read_only());
+        assert_eq!(buffer.language().map(|l| l.name()), Some("Rust".into()));
+    });
+}
+
+#[gpui::test]
+async fn test_open_large_file_in_chunks(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.large_file.size_threshold_mb = 1;
+            });
+        });
+    });
+
+    // Every line is 17 bytes long, so the first megabyte ends between the
+    // carriage return and the newline of a line ending.
+    let content = (0..150_000)
+        .map(|ix| format!("{ix:015}\r\n"))
+        .collect::<String>();
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/dir"), json!({ "large.txt": content.clone() }))
+        .await;
+
+    let project = Project::test(fs.clone(), [path!("/dir").as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer(path!("/dir/large.txt"), cx))
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| assert!(buffer.large_file_mode()));
+
+    cx.executor().run_until_parked();
+    let buffer_id = buffer.read_with(cx, |buffer, _| buffer.remote_id());
+    project.read_with(cx, |project, cx| {
+        assert!(
+            !project
+                .buffer_store()
+                .read(cx)
+                .is_partially_loaded(buffer_id)
+        );
+    });
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), content.replace("\r\n", "\n"));
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+        assert!(!buffer.is_dirty());
+        assert!(!buffer.read_only());
+
+        // The loaded content isn't part of the undo history.
+        buffer.undo(cx);
+        assert_eq!(buffer.len(), content.len() - 150_000);
+    });
+
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.load(path!("/dir/large.txt").as_ref()).await.unwrap(),
+        content
+    );
+}
+
+#[gpui::test]
+async fn test_exit_large_file_mode_while_loading(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.large_file.size_threshold_mb = 0;
+                settings.large_file.read_only = true;
+            });
+        });
+    });
+
+    let content = (0..150_000)
+        .map(|ix| format!("{ix:015}\n"))
+        .collect::<String>();
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({ "one.txt": content.clone(), "two.txt": content.clone() }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), [path!("/dir").as_ref()], cx).await;
+
+    // Leaving large file mode while the file is still loading waits for all of
+    // it to have been loaded before making the buffer editable.
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer(path!("/dir/one.txt"), cx))
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert!(buffer.large_file_mode());
+        assert!(buffer.read_only());
+    });
+    project
+        .update(cx, |project, cx| {
+            project.exit_large_file_mode(buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert!(!buffer.large_file_mode());
+        assert!(!buffer.read_only());
+        assert_eq!(buffer.text(), content);
+    });
+
+    // A buffer that stayed read-only once it was loaded becomes editable too.
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer(path!("/dir/two.txt"), cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        assert!(buffer.large_file_mode());
+        assert!(buffer.read_only());
+        assert_eq!(buffer.text(), content);
+    });
+    project
+        .update(cx, |project, cx| {
+            project.exit_large_file_mode(buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert!(!buffer.large_file_mode());
+        assert!(!buffer.read_only());
+    });
+}
+
 #[gpui::test(iterations = 30)]
 async fn test_file_changes_multiple_times_on_disk(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -7069,7 +7298,9 @@ async fn test_staging_random_hunks(
 
     log::info!(
         "index text:\n{}",
-        repo.load_index_text("file.txt".into()).await.unwrap()
+        repo.load_index_text("file.txt".into(), Encoding::UTF_8)
+            .await
+            .unwrap()
     );
 
     uncommitted_diff.update(cx, |diff, cx| {
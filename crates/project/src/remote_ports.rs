@@ -0,0 +1,171 @@
+//! The TCP ports listening on the machine of an SSH project, and the ones that
+//! are forwarded to the local machine.
+
+use std::{collections::BTreeMap, net::TcpListener};
+
+use anyhow::Result;
+use client::{TypedEnvelope, proto};
+use gpui::{App, AppContext as _, AsyncApp, Context, Entity, EventEmitter, Task};
+use remote::SshRemoteClient;
+use rpc::AnyProtoClient;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemotePort {
+    pub port: u16,
+    /// The name of the process listening on the port, if it could be determined.
+    pub process_name: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemotePortsEvent {
+    PortsChanged,
+    /// A process started listening on a port that isn't forwarded yet.
+    PortOpened(RemotePort),
+}
+
+pub struct RemotePorts {
+    ssh_client: Entity<SshRemoteClient>,
+    ports: Vec<RemotePort>,
+    /// The local port that each forwarded remote port is reachable on.
+    forwards: BTreeMap<u16, u16>,
+    received_initial_ports: bool,
+}
+
+impl EventEmitter<RemotePortsEvent> for RemotePorts {}
+
+impl RemotePorts {
+    pub fn init(client: &AnyProtoClient) {
+        client.add_entity_message_handler(Self::handle_update_remote_ports);
+    }
+
+    pub fn new(ssh_client: Entity<SshRemoteClient>, cx: &mut Context<Self>) -> Self {
+        // Ports forwarded with `-L` when connecting show up as forwards too.
+        let forwards = ssh_client
+            .read(cx)
+            .connection_options()
+            .port_forwards
+            .into_iter()
+            .flatten()
+            .filter(|forward| {
+                forward
+                    .remote_host
+                    .as_deref()
+                    .map_or(true, |host| host == "localhost" || host == "127.0.0.1")
+            })
+            .map(|forward| (forward.remote_port, forward.local_port))
+            .collect();
+        Self {
+            ssh_client,
+            ports: Vec::new(),
+            forwards,
+            received_initial_ports: false,
+        }
+    }
+
+    /// The ports listening on the remote machine, sorted by port.
+    pub fn ports(&self) -> &[RemotePort] {
+        &self.ports
+    }
+
+    /// The forwarded remote ports, along with the local port they're reachable on.
+    pub fn forwards(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.forwards
+            .iter()
+            .map(|(remote_port, local_port)| (*remote_port, *local_port))
+    }
+
+    /// Whether ports can be forwarded, which requires connecting over ssh rather
+    /// than through a custom command.
+    pub fn supports_forwarding(&self, cx: &App) -> bool {
+        self.ssh_client
+            .read(cx)
+            .connection_options()
+            .command
+            .is_none()
+    }
+
+    pub fn local_port(&self, remote_port: u16) -> Option<u16> {
+        self.forwards.get(&remote_port).copied()
+    }
+
+    /// Forwards the given remote port to the same local port if it's free, or to
+    /// another free local port otherwise. Resolves to the local port.
+    pub fn forward(&mut self, remote_port: u16, cx: &mut Context<Self>) -> Task<Result<u16>> {
+        if let Some(local_port) = self.local_port(remote_port) {
+            return Task::ready(Ok(local_port));
+        }
+        let local_port = match free_local_port(remote_port) {
+            Ok(local_port) => local_port,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let forward = self
+            .ssh_client
+            .read(cx)
+            .forward_port(local_port, remote_port, cx);
+        cx.spawn(async move |this, cx| {
+            forward.await?;
+            this.update(cx, |this, cx| {
+                this.forwards.insert(remote_port, local_port);
+                cx.emit(RemotePortsEvent::PortsChanged);
+                cx.notify();
+            })?;
+            Ok(local_port)
+        })
+    }
+
+    pub fn stop_forwarding(
+        &mut self,
+        remote_port: u16,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let Some(local_port) = self.forwards.remove(&remote_port) else {
+            return Task::ready(Ok(()));
+        };
+        cx.emit(RemotePortsEvent::PortsChanged);
+        cx.notify();
+        self.ssh_client
+            .read(cx)
+            .cancel_port_forward(local_port, remote_port, cx)
+    }
+
+    async fn handle_update_remote_ports(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::UpdateRemotePorts>,
+        mut cx: AsyncApp,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, cx| {
+            let ports = envelope
+                .payload
+                .ports
+                .into_iter()
+                .filter_map(|port| {
+                    Some(RemotePort {
+                        port: u16::try_from(port.port).ok()?,
+                        process_name: port.process_name,
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // The ports that were already open when the project was opened aren't new.
+            if this.received_initial_ports && this.supports_forwarding(cx) {
+                for port in &ports {
+                    let is_new = !this.ports.iter().any(|old_port| old_port.port == port.port);
+                    if is_new && !this.forwards.contains_key(&port.port) {
+                        cx.emit(RemotePortsEvent::PortOpened(port.clone()));
+                    }
+                }
+            }
+            this.received_initial_ports = true;
+            this.ports = ports;
+            cx.emit(RemotePortsEvent::PortsChanged);
+            cx.notify();
+        })
+    }
+}
+
+fn free_local_port(preferred_port: u16) -> Result<u16> {
+    if TcpListener::bind(("127.0.0.1", preferred_port)).is_ok() {
+        return Ok(preferred_port);
+    }
+    Ok(TcpListener::bind(("127.0.0.1", 0))?.local_addr()?.port())
+}
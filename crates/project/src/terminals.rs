@@ -48,6 +48,7 @@ pub enum TerminalKind {
 /// SshCommand describes how to connect to a remote server
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SshCommand {
+    program: String,
     arguments: Vec<String>,
 }
 
@@ -75,10 +76,10 @@ impl Project {
     pub fn ssh_details(&self, cx: &App) -> Option<(String, SshCommand)> {
         if let Some(ssh_client) = &self.ssh_client {
             let ssh_client = ssh_client.read(cx);
-            if let Some(args) = ssh_client.ssh_args() {
+            if let Some((program, arguments)) = ssh_client.terminal_command() {
                 return Some((
                     ssh_client.connection_options().host.clone(),
-                    SshCommand { arguments: args },
+                    SshCommand { program, arguments },
                 ));
             }
         }
@@ -258,6 +259,8 @@ impl Project {
                         // so we set it to a default that does not break the highlighting via ssh.
                         env.entry("TERM".to_string())
                             .or_insert_with(|| "xterm-256color".to_string());
+                        // Lets the remote server detect the ports opened from the terminal.
+                        env.insert("ZED_TERM".to_string(), "true".to_string());
 
                         let (program, args) =
                             wrap_for_ssh(&ssh_command, None, path.as_deref(), env, None);
@@ -302,6 +305,7 @@ impl Project {
                         log::debug!("Connecting to a remote server: {ssh_command:?}");
                         env.entry("TERM".to_string())
                             .or_insert_with(|| "xterm-256color".to_string());
+                        env.insert("ZED_TERM".to_string(), "true".to_string());
                         let (program, args) = wrap_for_ssh(
                             &ssh_command,
                             Some((&spawn_task.command, &spawn_task.args)),
@@ -366,6 +370,7 @@ impl Project {
             settings.cursor_shape.unwrap_or_default(),
             settings.alternate_scroll,
             settings.max_scroll_history_lines,
+            settings.shell_integration,
             ssh_details.is_some(),
             window,
             completion_tx,
@@ -598,10 +603,8 @@ fn wrap_for_ssh(
     };
     let shell_invocation = format!("sh -c {}", shlex::try_quote(&commands).unwrap());
 
-    let program = "ssh".to_string();
+    let program = ssh_command.program.clone();
     let mut args = ssh_command.arguments.clone();
-
-    args.push("-t".to_string());
     args.push(shell_invocation);
     (program, args)
 }
@@ -1,16 +1,17 @@
 use std::{
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, Read as _},
     path::{Path, PathBuf},
-    pin::pin,
+    pin::{Pin, pin},
     sync::{Arc, atomic::AtomicUsize},
 };
 
 use anyhow::{Context as _, Result, anyhow};
 use collections::{HashMap, HashSet};
-use fs::Fs;
+use fs::{Fs, RemoveOptions, RenameOptions};
 use futures::{
-    FutureExt, SinkExt,
-    future::{BoxFuture, Shared},
+    FutureExt, SinkExt, TryStreamExt as _,
+    channel::mpsc,
+    future::{self, BoxFuture, Shared},
 };
 use gpui::{
     App, AppContext as _, AsyncApp, Context, Entity, EntityId, EventEmitter, Task, WeakEntity,
@@ -33,6 +34,24 @@ use worktree::{
 
 use crate::{ProjectPath, search::SearchQuery};
 
+/// Files are copied between the local machine and a remote host in pieces of
+/// at most this size.
+pub const FILE_TRANSFER_CHUNK_SIZE: usize = 256 * 1024;
+
+/// How many chunks of a file being transferred are buffered before they're
+/// written to disk.
+pub const FILE_TRANSFER_BUFFERED_CHUNKS: usize = 4;
+
+/// Where a file being transferred is written to, so that the file at `abs_path`
+/// is only replaced once the transfer is complete.
+pub fn file_transfer_temp_path(abs_path: &Path, transfer_id: u64) -> PathBuf {
+    let file_name = abs_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    abs_path.with_file_name(format!(".{file_name}.{transfer_id}.part"))
+}
+
 struct MatchingEntry {
     worktree_path: Arc<Path>,
     path: ProjectPath,
@@ -646,6 +665,178 @@ impl WorktreeStore {
         }
     }
 
+    /// Downloads entries of a remote worktree, including the contents of
+    /// directories, into a local directory. Existing files are only replaced
+    /// if `overwrite` is true.
+    pub fn download_entries(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        target_directory: PathBuf,
+        overwrite: bool,
+        fs: Arc<dyn Fs>,
+        cx: &mut Context<Self>,
+    ) -> (Entity<FileTransfer>, Task<Result<()>>) {
+        let (Some((client, project_id)), Some(worktree)) = (
+            self.upstream_client(),
+            self.worktree_for_id(worktree_id, cx),
+        ) else {
+            return (
+                cx.new(|_| FileTransfer::new(FileTransferDirection::Download, &paths)),
+                Task::ready(Err(anyhow!("only remote worktrees can be downloaded"))),
+            );
+        };
+
+        let snapshot = worktree.read(cx).snapshot();
+        let local_roots = paths
+            .iter()
+            .map(|path| match path.file_name() {
+                Some(name) => target_directory.join(name),
+                None => target_directory.join(snapshot.root_name()),
+            })
+            .collect::<Vec<_>>();
+        let transfer = cx.new(|_| FileTransfer::new(FileTransferDirection::Download, &local_roots));
+        let mut items = Vec::new();
+        for (path, local_root) in paths.iter().zip(local_roots) {
+            for entry in snapshot
+                .traverse_from_path(true, true, true, path)
+                .take_while(|entry| entry.path.starts_with(path))
+            {
+                let relative_path = entry.path.strip_prefix(path).unwrap_or(&entry.path);
+                let abs_path = if relative_path.as_os_str().is_empty() {
+                    local_root.clone()
+                } else {
+                    local_root.join(relative_path)
+                };
+                items.push(TransferItem {
+                    abs_path,
+                    path: entry.path.to_path_buf(),
+                    is_dir: entry.is_dir(),
+                    size: entry.size,
+                });
+            }
+        }
+        transfer.update(cx, |transfer, _| {
+            transfer.total_bytes = items.iter().map(|item| item.size).sum();
+        });
+
+        let task = cx.spawn({
+            let transfer = transfer.downgrade();
+            async move |_, cx| {
+                for item in items {
+                    if item.is_dir {
+                        fs.create_dir(&item.abs_path).await?;
+                    } else {
+                        download_file(
+                            &client,
+                            project_id,
+                            worktree_id,
+                            &item,
+                            overwrite,
+                            fs.as_ref(),
+                            &transfer,
+                            cx,
+                        )
+                        .await
+                        .with_context(|| format!("downloading {:?}", item.path))?;
+                    }
+                }
+                Ok(())
+            }
+        });
+        (transfer, task)
+    }
+
+    /// Uploads local files and directories into a directory of a remote
+    /// worktree. Existing files are only replaced if `overwrite` is true.
+    pub fn upload_paths(
+        &self,
+        worktree_id: WorktreeId,
+        abs_paths: Vec<PathBuf>,
+        target_directory: Arc<Path>,
+        overwrite: bool,
+        fs: Arc<dyn Fs>,
+        cx: &mut Context<Self>,
+    ) -> (Entity<FileTransfer>, Task<Result<()>>) {
+        let transfer = cx.new(|_| FileTransfer::new(FileTransferDirection::Upload, &abs_paths));
+        let (Some((client, project_id)), Some(worktree)) = (
+            self.upstream_client(),
+            self.worktree_for_id(worktree_id, cx),
+        ) else {
+            return (
+                transfer,
+                Task::ready(Err(anyhow!(
+                    "files can only be uploaded to remote worktrees"
+                ))),
+            );
+        };
+
+        let task = cx.spawn({
+            let transfer = transfer.downgrade();
+            async move |_, cx| {
+                let mut items = Vec::new();
+                let mut stack = abs_paths
+                    .into_iter()
+                    .filter_map(|abs_path| {
+                        let path = target_directory.join(abs_path.file_name()?);
+                        Some((abs_path, path))
+                    })
+                    .collect::<Vec<_>>();
+                while let Some((abs_path, path)) = stack.pop() {
+                    let metadata = fs
+                        .metadata(&abs_path)
+                        .await?
+                        .ok_or_else(|| anyhow!("{abs_path:?} does not exist"))?;
+                    if metadata.is_dir {
+                        let mut children = fs.read_dir(&abs_path).await?;
+                        while let Some(child) = children.next().await {
+                            let child = child?;
+                            if let Some(name) = child.file_name() {
+                                let child_path = path.join(name);
+                                stack.push((child, child_path));
+                            }
+                        }
+                    }
+                    items.push(TransferItem {
+                        abs_path,
+                        path,
+                        is_dir: metadata.is_dir,
+                        size: if metadata.is_dir { 0 } else { metadata.len },
+                    });
+                }
+                transfer.update(cx, |transfer, cx| {
+                    transfer.total_bytes = items.iter().map(|item| item.size).sum();
+                    cx.notify();
+                })?;
+
+                for item in items {
+                    if item.is_dir {
+                        worktree
+                            .update(cx, |worktree, cx| {
+                                worktree.create_entry(item.path.clone(), true, cx)
+                            })?
+                            .await?;
+                    } else {
+                        upload_file(
+                            &client,
+                            project_id,
+                            worktree_id,
+                            &item,
+                            overwrite,
+                            fs.as_ref(),
+                            &transfer,
+                            cx,
+                        )
+                        .await
+                        .with_context(|| format!("uploading {:?}", item.abs_path))?;
+                    }
+                }
+                Ok(())
+            }
+        });
+        (transfer, task)
+    }
+
     /// search over all worktrees and return buffers that *might* match the search.
     pub fn find_search_candidates(
         &self,
@@ -983,6 +1174,237 @@ impl WorktreeStore {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileTransferDirection {
+    Upload,
+    Download,
+}
+
+/// The progress of copying files between the local machine and a remote host.
+pub struct FileTransfer {
+    direction: FileTransferDirection,
+    description: String,
+    total_bytes: u64,
+    transferred_bytes: u64,
+    cancelled: bool,
+}
+
+impl FileTransfer {
+    fn new(direction: FileTransferDirection, paths: &[impl AsRef<Path>]) -> Self {
+        let description = match paths {
+            [path] => path
+                .as_ref()
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            paths => format!("{} items", paths.len()),
+        };
+        Self {
+            direction,
+            description,
+            total_bytes: 0,
+            transferred_bytes: 0,
+            cancelled: false,
+        }
+    }
+
+    pub fn direction(&self) -> FileTransferDirection {
+        self.direction
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    pub fn transferred_bytes(&self) -> u64 {
+        self.transferred_bytes
+    }
+
+    /// Stops the transfer after the chunk in flight, removing the partially
+    /// copied file. Files that were already copied are kept.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+struct TransferItem {
+    abs_path: PathBuf,
+    /// The path relative to the worktree root.
+    path: PathBuf,
+    is_dir: bool,
+    size: u64,
+}
+
+async fn download_file(
+    client: &AnyProtoClient,
+    project_id: u64,
+    worktree_id: WorktreeId,
+    item: &TransferItem,
+    overwrite: bool,
+    fs: &dyn Fs,
+    transfer: &WeakEntity<FileTransfer>,
+    cx: &mut AsyncApp,
+) -> Result<()> {
+    if !overwrite && fs.metadata(&item.abs_path).await?.is_some() {
+        return Err(anyhow!("{:?} already exists", item.abs_path));
+    }
+    let transfer_id = client
+        .request(proto::OpenFileTransfer {
+            project_id,
+            worktree_id: worktree_id.to_proto(),
+            path: item.path.to_proto(),
+            upload: false,
+            overwrite,
+        })
+        .await?
+        .transfer_id;
+
+    let temp_path = file_transfer_temp_path(&item.abs_path, transfer_id);
+    let (chunks_tx, chunks_rx) =
+        mpsc::channel::<io::Result<Vec<u8>>>(FILE_TRANSFER_BUFFERED_CHUNKS);
+    let receive_chunks = async {
+        let mut chunks_tx = chunks_tx;
+        loop {
+            if transfer.read_with(cx, |transfer, _| transfer.is_cancelled())? {
+                return Err(anyhow!("download cancelled"));
+            }
+            let data = client
+                .request(proto::ReadFileTransferChunk {
+                    project_id,
+                    transfer_id,
+                })
+                .await?
+                .data;
+            if data.is_empty() {
+                return anyhow::Ok(());
+            }
+            let len = data.len() as u64;
+            chunks_tx.send(Ok(data)).await?;
+            transfer.update(cx, |transfer, cx| {
+                transfer.transferred_bytes += len;
+                cx.notify();
+            })?;
+        }
+    };
+    let write_file = async {
+        let mut content = chunks_rx.into_async_read();
+        fs.create_file_with(&temp_path, Pin::new(&mut content))
+            .await
+    };
+
+    let mut result = future::try_join(receive_chunks, write_file)
+        .await
+        .map(|_| ());
+    if result.is_ok() {
+        result = fs
+            .rename(
+                &temp_path,
+                &item.abs_path,
+                RenameOptions {
+                    overwrite,
+                    ignore_if_exists: false,
+                },
+            )
+            .await;
+    }
+    client
+        .request(proto::CloseFileTransfer {
+            project_id,
+            transfer_id,
+            cancel: result.is_err(),
+        })
+        .await
+        .log_err();
+    if result.is_err() {
+        fs.remove_file(
+            &temp_path,
+            RemoveOptions {
+                ignore_if_not_exists: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .log_err();
+    }
+    result
+}
+
+async fn upload_file(
+    client: &AnyProtoClient,
+    project_id: u64,
+    worktree_id: WorktreeId,
+    item: &TransferItem,
+    overwrite: bool,
+    fs: &dyn Fs,
+    transfer: &WeakEntity<FileTransfer>,
+    cx: &mut AsyncApp,
+) -> Result<()> {
+    let mut file = fs.open_sync(&item.abs_path).await?;
+    let transfer_id = client
+        .request(proto::OpenFileTransfer {
+            project_id,
+            worktree_id: worktree_id.to_proto(),
+            path: item.path.to_proto(),
+            upload: true,
+            overwrite,
+        })
+        .await?
+        .transfer_id;
+
+    let send_chunks = async {
+        loop {
+            if transfer.read_with(cx, |transfer, _| transfer.is_cancelled())? {
+                return Err(anyhow!("upload cancelled"));
+            }
+            let (returned_file, data) = cx
+                .background_spawn(async move {
+                    let mut data = Vec::with_capacity(FILE_TRANSFER_CHUNK_SIZE);
+                    let result = file
+                        .by_ref()
+                        .take(FILE_TRANSFER_CHUNK_SIZE as u64)
+                        .read_to_end(&mut data);
+                    (file, result.map(|_| data))
+                })
+                .await;
+            file = returned_file;
+            let data = data?;
+            if data.is_empty() {
+                return anyhow::Ok(());
+            }
+            let len = data.len() as u64;
+            client
+                .request(proto::WriteFileTransferChunk {
+                    project_id,
+                    transfer_id,
+                    data,
+                })
+                .await?;
+            transfer.update(cx, |transfer, cx| {
+                transfer.transferred_bytes += len;
+                cx.notify();
+            })?;
+        }
+    };
+
+    let result = send_chunks.await;
+    client
+        .request(proto::CloseFileTransfer {
+            project_id,
+            transfer_id,
+            cancel: result.is_err(),
+        })
+        .await
+        .and(result)
+}
+
 #[derive(Clone, Debug)]
 enum WorktreeHandle {
     Strong(Entity<Worktree>),
@@ -20,9 +20,10 @@ use gpui::{
     Action, AnyElement, App, ArcCow, AsyncWindowContext, Bounds, ClipboardItem, Context,
     DismissEvent, Div, DragMoveEvent, Entity, EventEmitter, ExternalPaths, FocusHandle, Focusable,
     Hsla, InteractiveElement, KeyContext, ListHorizontalSizingBehavior, ListSizingBehavior,
-    MouseButton, MouseDownEvent, ParentElement, Pixels, Point, PromptLevel, Render, ScrollStrategy,
-    Stateful, Styled, Subscription, Task, UniformListScrollHandle, WeakEntity, Window, actions,
-    anchored, deferred, div, impl_actions, point, px, size, uniform_list,
+    MouseButton, MouseDownEvent, ParentElement, PathPromptOptions, Pixels, Point, PromptLevel,
+    Render, ScrollStrategy, Stateful, Styled, Subscription, Task, UniformListScrollHandle,
+    WeakEntity, Window, actions, anchored, deferred, div, impl_actions, point, px, size,
+    uniform_list,
 };
 use indexmap::IndexMap;
 use language::DiagnosticSeverity;
@@ -32,6 +33,7 @@ use project::{
     ProjectPath, Worktree, WorktreeId,
     git_store::{GitStoreEvent, git_traversal::ChildEntriesGitIter},
     relativize_path,
+    worktree_store::{FileTransfer, FileTransferDirection},
 };
 use project_panel_settings::{
     ProjectPanelDockPosition, ProjectPanelSettings, ShowDiagnostics, ShowIndentGuides,
@@ -45,7 +47,7 @@ use std::{
     cell::OnceCell,
     cmp,
     collections::HashSet,
-    ffi::OsStr,
+    ffi::{OsStr, OsString},
     ops::Range,
     path::{Path, PathBuf},
     sync::Arc,
@@ -62,7 +64,10 @@ use workspace::{
     DraggedSelection, OpenInTerminal, OpenOptions, OpenVisible, PreviewTabsSettings, SelectedEntry,
     Workspace,
     dock::{DockPosition, Panel, PanelEvent},
-    notifications::{DetachAndPromptErr, NotifyTaskExt},
+    notifications::{
+        DetachAndPromptErr, NotificationId, NotifyTaskExt,
+        simple_message_notification::MessageNotification,
+    },
 };
 use worktree::CreatedEntry;
 
@@ -201,6 +206,7 @@ actions!(
         RevealInFileManager,
         RemoveFromProject,
         OpenWithSystem,
+        OpenAsHex,
         Cut,
         Paste,
         Rename,
@@ -218,6 +224,8 @@ actions!(
         SelectPrevDiagnostic,
         SelectNextDirectory,
         SelectPrevDirectory,
+        Download,
+        Upload,
     ]
 );
 
@@ -727,6 +735,7 @@ impl ProjectPanel {
             let is_read_only = project.is_read_only(cx);
             let is_remote = project.is_via_collab();
             let is_local = project.is_local();
+            let is_via_ssh = project.is_via_ssh();
 
             let context_menu = ContextMenu::build(window, cx, |menu, _, _| {
                 menu.context(self.focus_handle.clone()).map(|menu| {
@@ -734,6 +743,9 @@ impl ProjectPanel {
                         menu.when(is_dir, |menu| {
                             menu.action("Search Inside", Box::new(NewSearchInDirectory))
                         })
+                        .when(is_via_ssh, |menu| {
+                            menu.separator().action("Download to…", Box::new(Download))
+                        })
                     } else {
                         menu.action("New File", Box::new(NewFile))
                             .action("New Folder", Box::new(NewDirectory))
@@ -747,6 +759,9 @@ impl ProjectPanel {
                             .when(is_local, |menu| {
                                 menu.action("Open in Default App", Box::new(OpenWithSystem))
                             })
+                            .when(is_local && !is_dir, |menu| {
+                                menu.action("Open as Hex", Box::new(OpenAsHex))
+                            })
                             .action("Open in Terminal", Box::new(OpenInTerminal))
                             .when(is_dir, |menu| {
                                 menu.separator()
@@ -758,6 +773,11 @@ impl ProjectPanel {
                             .when(is_foldable, |menu| {
                                 menu.action("Fold Directory", Box::new(FoldDirectory))
                             })
+                            .when(is_via_ssh, |menu| {
+                                menu.separator()
+                                    .action("Download to…", Box::new(Download))
+                                    .action("Upload Here…", Box::new(Upload))
+                            })
                             .separator()
                             .action("Cut", Box::new(Cut))
                             .action("Copy", Box::new(Copy))
@@ -2385,6 +2405,26 @@ impl ProjectPanel {
         }
     }
 
+    fn open_as_hex(&mut self, _: &OpenAsHex, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((worktree, entry)) = self.selected_entry(cx) else {
+            return;
+        };
+        if entry.is_dir() {
+            return;
+        }
+        let project_path = ProjectPath {
+            worktree_id: worktree.id(),
+            path: entry.path.clone(),
+        };
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace
+                    .open_binary_path(project_path, None, true, window, cx)
+                    .detach_and_prompt_err("Failed to open file", window, cx, |_, _, _| None);
+            })
+            .ok();
+    }
+
     fn open_in_terminal(
         &mut self,
         _: &OpenInTerminal,
@@ -2411,6 +2451,214 @@ impl ProjectPanel {
         }
     }
 
+    fn download(&mut self, _: &Download, window: &mut Window, cx: &mut Context<Self>) {
+        let mut entries = self.disjoint_entries(cx);
+        if entries.is_empty() {
+            // Worktree roots are left out of the disjoint entries.
+            entries = self.effective_entries();
+        }
+        let project = self.project.read(cx);
+        let fs = project.fs().clone();
+        let mut paths_by_worktree = HashMap::<WorktreeId, Vec<Arc<Path>>>::default();
+        let mut local_names = HashMap::<(WorktreeId, Arc<Path>), OsString>::default();
+        for entry in entries {
+            let Some(worktree) = project.worktree_for_id(entry.worktree_id, cx) else {
+                continue;
+            };
+            if let Some(path) = project.path_for_entry(entry.entry_id, cx) {
+                // Worktree roots are downloaded into a directory named after them.
+                let local_name = match path.path.file_name() {
+                    Some(name) => name.to_os_string(),
+                    None => OsString::from(worktree.read(cx).root_name()),
+                };
+                local_names.insert((entry.worktree_id, path.path.clone()), local_name);
+                paths_by_worktree
+                    .entry(entry.worktree_id)
+                    .or_default()
+                    .push(path.path);
+            }
+        }
+        if paths_by_worktree.is_empty() {
+            return;
+        }
+
+        let target_directory = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+        });
+        cx.spawn_in(window, async move |this, cx| {
+            let Some(target_directory) = target_directory
+                .await??
+                .and_then(|paths| paths.into_iter().next())
+            else {
+                return Ok(());
+            };
+            let mut downloads = Vec::new();
+            for (worktree_id, paths) in paths_by_worktree {
+                let mut paths_to_download = Vec::new();
+                for path in paths {
+                    let Some(local_name) = local_names.get(&(worktree_id, path.clone())) else {
+                        continue;
+                    };
+                    let local_path = target_directory.join(local_name);
+                    if fs.metadata(&local_path).await?.is_some()
+                        && !confirm_replace(&local_name.to_string_lossy(), cx).await?
+                    {
+                        continue;
+                    }
+                    paths_to_download.push(path);
+                }
+                if !paths_to_download.is_empty() {
+                    downloads.push((worktree_id, paths_to_download));
+                }
+            }
+            this.update_in(cx, |this, window, cx| {
+                for (worktree_id, paths) in downloads {
+                    let (transfer, task) = this.project.update(cx, |project, cx| {
+                        project.download_entries(
+                            worktree_id,
+                            paths,
+                            target_directory.clone(),
+                            true,
+                            cx,
+                        )
+                    });
+                    this.track_file_transfer(transfer, task, window, cx);
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn upload(&mut self, _: &Upload, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((worktree, entry)) = self.selected_sub_entry(cx) else {
+            return;
+        };
+        let worktree_id = worktree.read(cx).id();
+        let target_directory = if entry.is_dir() {
+            entry.path.clone()
+        } else {
+            Arc::from(entry.path.parent().unwrap_or(Path::new("")))
+        };
+
+        let abs_paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: true,
+            multiple: true,
+        });
+        cx.spawn_in(window, async move |this, cx| {
+            let Some(abs_paths) = abs_paths.await?? else {
+                return Ok(());
+            };
+            this.update_in(cx, |this, window, cx| {
+                this.upload_paths(worktree_id, abs_paths, target_directory, window, cx)
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn upload_paths(
+        &mut self,
+        worktree_id: WorktreeId,
+        abs_paths: Vec<PathBuf>,
+        target_directory: Arc<Path>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(worktree) = self.project.read(cx).worktree_for_id(worktree_id, cx) else {
+            return;
+        };
+        let worktree = worktree.read(cx);
+        let existing_names = abs_paths
+            .iter()
+            .filter_map(|abs_path| {
+                let name = abs_path.file_name()?;
+                worktree
+                    .entry_for_path(target_directory.join(name))
+                    .is_some()
+                    .then(|| name.to_os_string())
+            })
+            .collect::<HashSet<_>>();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let mut paths_to_upload = Vec::new();
+            for abs_path in abs_paths {
+                if let Some(name) = abs_path.file_name() {
+                    if existing_names.contains(name)
+                        && !confirm_replace(&name.to_string_lossy(), cx).await?
+                    {
+                        continue;
+                    }
+                }
+                paths_to_upload.push(abs_path);
+            }
+            if paths_to_upload.is_empty() {
+                return Ok(());
+            }
+            this.update_in(cx, |this, window, cx| {
+                let (transfer, task) = this.project.update(cx, |project, cx| {
+                    project.upload_paths(worktree_id, paths_to_upload, target_directory, true, cx)
+                });
+                this.track_file_transfer(transfer, task, window, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Shows the progress of a file transfer in a notification that lets the
+    /// user cancel it, until the transfer is done.
+    fn track_file_transfer(
+        &mut self,
+        transfer: Entity<FileTransfer>,
+        task: Task<Result<()>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        struct FileTransferNotification;
+
+        let notification_id = NotificationId::composite::<FileTransferNotification>(
+            transfer.entity_id().as_u64() as usize,
+        );
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_notification(notification_id.clone(), cx, |cx| {
+                    cx.new(|cx| {
+                        cx.observe(&transfer, |_, _, cx| cx.notify()).detach();
+                        let progress_transfer = transfer.clone();
+                        let cancel_transfer = transfer.clone();
+                        MessageNotification::new_from_builder(cx, move |_, cx| {
+                            Label::new(file_transfer_message(progress_transfer.read(cx)))
+                                .into_any_element()
+                        })
+                        .primary_message("Cancel")
+                        .primary_icon(IconName::Close)
+                        .primary_on_click(move |_, cx| {
+                            cancel_transfer.update(cx, |transfer, _| transfer.cancel());
+                        })
+                    })
+                });
+            })
+            .ok();
+
+        let error_message = match transfer.read(cx).direction() {
+            FileTransferDirection::Upload => "Failed to upload files",
+            FileTransferDirection::Download => "Failed to download files",
+        };
+        let workspace = self.workspace.clone();
+        cx.spawn(async move |_, cx| {
+            let result = task.await;
+            workspace.update(cx, |workspace, cx| {
+                workspace.dismiss_notification(&notification_id, cx);
+            })?;
+            if transfer.read_with(cx, |transfer, _| transfer.is_cancelled())? {
+                return Ok(());
+            }
+            result
+        })
+        .detach_and_prompt_err(error_message, window, cx, |_, _, _| None);
+    }
+
     pub fn new_search_in_directory(
         &mut self,
         _: &NewSearchInDirectory,
@@ -2979,6 +3227,24 @@ impl ProjectPanel {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if self.project.read(cx).is_via_ssh() {
+            let Some((worktree_id, target_directory)) = maybe!({
+                let worktree = self.project.read(cx).worktree_for_entry(entry_id, cx)?;
+                let worktree = worktree.read(cx);
+                let entry = worktree.entry_for_id(entry_id)?;
+                let target_directory = if entry.is_dir() {
+                    entry.path.clone()
+                } else {
+                    Arc::from(entry.path.parent()?)
+                };
+                Some((worktree.id(), target_directory))
+            }) else {
+                return;
+            };
+            self.upload_paths(worktree_id, paths.to_vec(), target_directory, window, cx);
+            return;
+        }
+
         let mut paths: Vec<Arc<Path>> = paths.iter().map(|path| Arc::from(path.clone())).collect();
 
         let open_file_after_drop = paths.len() == 1 && paths[0].is_file();
@@ -4513,6 +4779,40 @@ impl ProjectPanel {
     }
 }
 
+/// Asks whether a file or folder in the destination folder of a transfer
+/// should be replaced.
+async fn confirm_replace(name: &str, cx: &mut AsyncWindowContext) -> Result<bool> {
+    let answer = cx
+        .update(|window, cx| {
+            window.prompt(
+                PromptLevel::Info,
+                &format!(
+                    "A file or folder with name {name} already exists in the destination folder. Do you want to replace it?"
+                ),
+                None,
+                &["Replace", "Cancel"],
+                cx,
+            )
+        })?
+        .await?;
+    Ok(answer == 0)
+}
+
+fn file_transfer_message(transfer: &FileTransfer) -> String {
+    let action = match transfer.direction() {
+        FileTransferDirection::Upload => "Uploading",
+        FileTransferDirection::Download => "Downloading",
+    };
+    let description = transfer.description();
+    match transfer.total_bytes() {
+        0 => format!("{action} {description}…"),
+        total_bytes => format!(
+            "{action} {description}… {}%",
+            transfer.transferred_bytes() * 100 / total_bytes
+        ),
+    }
+}
+
 fn item_width_estimate(depth: usize, item_text_chars: usize, is_symlink: bool) -> usize {
     const ICON_SIZE_FACTOR: usize = 2;
     let mut item_width = depth * ICON_SIZE_FACTOR + item_text_chars;
@@ -4677,10 +4977,15 @@ impl Render for ProjectPanel {
                 .when(project.is_local(), |el| {
                     el.on_action(cx.listener(Self::reveal_in_finder))
                         .on_action(cx.listener(Self::open_system))
+                        .on_action(cx.listener(Self::open_as_hex))
                         .on_action(cx.listener(Self::open_in_terminal))
                 })
                 .when(project.is_via_ssh(), |el| {
                     el.on_action(cx.listener(Self::open_in_terminal))
+                        .on_action(cx.listener(Self::download))
+                })
+                .when(project.is_via_ssh() && !project.is_read_only(cx), |el| {
+                    el.on_action(cx.listener(Self::upload))
                 })
                 .on_mouse_down(
                     MouseButton::Right,
@@ -275,6 +275,7 @@ messages!(
     (UpdateParticipantLocation, Foreground),
     (UpdateProject, Foreground),
     (UpdateProjectCollaborator, Foreground),
+    (UpdateRemotePorts, Background),
     (UpdateUserChannels, Foreground),
     (UpdateUserPlan, Foreground),
     (UpdateWorktree, Foreground),
@@ -302,6 +303,13 @@ messages!(
     (GitDiff, Background),
     (GitDiffResponse, Background),
     (GitInit, Background),
+    (OpenFileTransfer, Background),
+    (OpenFileTransferResponse, Background),
+    (ReadFileTransferChunk, Background),
+    (ReadFileTransferChunkResponse, Background),
+    (WriteFileTransferChunk, Background),
+    (CloseFileTransfer, Background),
+    (ExitLargeFileMode, Foreground),
 );
 
 request_messages!(
@@ -460,6 +468,11 @@ request_messages!(
     (GitDiff, GitDiffResponse),
     (GitInit, Ack),
     (ToggleBreakpoint, Ack),
+    (OpenFileTransfer, OpenFileTransferResponse),
+    (ReadFileTransferChunk, ReadFileTransferChunkResponse),
+    (WriteFileTransferChunk, Ack),
+    (CloseFileTransfer, Ack),
+    (ExitLargeFileMode, Ack),
 );
 
 entity_messages!(
@@ -579,6 +592,12 @@ entity_messages!(
     GitInit,
     BreakpointsForFile,
     ToggleBreakpoint,
+    UpdateRemotePorts,
+    OpenFileTransfer,
+    ReadFileTransferChunk,
+    WriteFileTransferChunk,
+    CloseFileTransfer,
+    ExitLargeFileMode,
 );
 
 entity_messages!(
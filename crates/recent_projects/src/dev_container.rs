@@ -0,0 +1,749 @@
+//! Reopening a local project inside its dev container, as described by its
+//! `devcontainer.json`: https://containers.dev/implementors/json_reference/
+//!
+//! The container is built and started with the local docker CLI, and Zed then
+//! connects to the remote server inside it through `docker exec`. Connections
+//! to dev containers are saved with the folder and its `devcontainer.json`, so
+//! the container is started, or created again, every time they're reopened.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result, anyhow};
+use fs::Fs;
+use futures::AsyncWriteExt as _;
+use gpui::{AsyncApp, Entity, WindowHandle, actions};
+use project::{Project, WorktreeId};
+use remote::SshConnectionOptions;
+use serde::Deserialize;
+use settings::update_settings_file;
+use sha2::{Digest as _, Sha256};
+use smol::process::Stdio;
+use ui::prelude::*;
+use util::ResultExt as _;
+use workspace::{
+    OpenOptions, Toast, Workspace,
+    notifications::{
+        DetachAndPromptErr, NotificationId, simple_message_notification::MessageNotification,
+    },
+};
+
+use crate::{
+    open_ssh_project,
+    ssh_connections::{DevContainerConnection, SshConnection, SshProject, SshSettings},
+};
+
+actions!(dev_container, [ReopenInContainer]);
+
+const CONFIG_PATHS: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+/// Identifies the container of a local folder, so that it's reused.
+const LOCAL_FOLDER_LABEL: &str = "dev.zed.local_folder";
+/// Keeps the container running without depending on the image's entrypoint.
+const KEEP_ALIVE_COMMAND: &[&str] = &["sh", "-c", "while sleep 1000; do :; done"];
+/// Created in the container once postCreateCommand has succeeded, which is run
+/// again every time the container is started until then.
+const POST_CREATE_MARKER: &str = "\"$HOME/.config/zed/.post_create_command_done\"";
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct DevContainerConfig {
+    name: Option<String>,
+    image: Option<String>,
+    build: Option<BuildConfig>,
+    /// The pre-`build` way of specifying a Dockerfile.
+    docker_file: Option<String>,
+    #[serde(default)]
+    forward_ports: Vec<ForwardPort>,
+    post_create_command: Option<LifecycleCommand>,
+    remote_user: Option<String>,
+    container_user: Option<String>,
+    workspace_folder: Option<String>,
+    #[serde(default)]
+    container_env: BTreeMap<String, String>,
+    #[serde(default)]
+    run_args: Vec<String>,
+    customizations: Option<Customizations>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct BuildConfig {
+    dockerfile: Option<String>,
+    context: Option<String>,
+    #[serde(default)]
+    args: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum ForwardPort {
+    Port(u16),
+    /// A `host:port` pair, for ports of other services.
+    HostPort(String),
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum LifecycleCommand {
+    /// Run through a shell.
+    Shell(String),
+    /// Run without a shell.
+    Exec(Vec<String>),
+    /// Named commands, which are run one after another.
+    Named(BTreeMap<String, LifecycleCommand>),
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+struct Customizations {
+    zed: Option<ZedCustomizations>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+struct ZedCustomizations {
+    /// Settings for the remote server inside the container.
+    settings: Option<serde_json::Value>,
+}
+
+impl DevContainerConfig {
+    fn name(&self, local_folder: &Path) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| folder_name(local_folder))
+    }
+
+    fn workspace_folder(&self, local_folder: &Path) -> String {
+        self.workspace_folder
+            .clone()
+            .unwrap_or_else(|| format!("/workspaces/{}", folder_name(local_folder)))
+    }
+
+    /// The arguments to `docker build`, if the image is built from a Dockerfile.
+    fn build_args(&self, config_dir: &Path, tag: &str) -> Option<Vec<String>> {
+        let dockerfile = self
+            .build
+            .as_ref()
+            .and_then(|build| build.dockerfile.as_ref())
+            .or(self.docker_file.as_ref())?;
+        let context = self
+            .build
+            .as_ref()
+            .and_then(|build| build.context.as_deref())
+            .unwrap_or(".");
+
+        let mut args = vec![
+            "build".to_string(),
+            "--file".to_string(),
+            config_dir.join(dockerfile).to_string_lossy().into_owned(),
+            "--tag".to_string(),
+            tag.to_string(),
+        ];
+        for (name, value) in self.build.iter().flat_map(|build| &build.args) {
+            args.push("--build-arg".to_string());
+            args.push(format!("{name}={value}"));
+        }
+        args.push(config_dir.join(context).to_string_lossy().into_owned());
+        Some(args)
+    }
+
+    /// The arguments to `docker run` that create the container for the given folder.
+    fn run_args(&self, local_folder: &Path, image: &str) -> Vec<String> {
+        let local_folder = local_folder.to_string_lossy();
+        let mut args = vec![
+            "run".to_string(),
+            "--detach".to_string(),
+            "--label".to_string(),
+            format!("{LOCAL_FOLDER_LABEL}={local_folder}"),
+            "--mount".to_string(),
+            format!(
+                "type=bind,source={local_folder},target={}",
+                self.workspace_folder(Path::new(local_folder.as_ref()))
+            ),
+        ];
+        if let Some(user) = &self.container_user {
+            args.push("--user".to_string());
+            args.push(user.clone());
+        }
+        for (name, value) in &self.container_env {
+            args.push("--env".to_string());
+            args.push(format!("{name}={value}"));
+        }
+        for port in &self.forward_ports {
+            match port {
+                ForwardPort::Port(port) => {
+                    args.push("--publish".to_string());
+                    args.push(format!("127.0.0.1:{port}:{port}"));
+                }
+                ForwardPort::HostPort(port) => {
+                    log::warn!(
+                        "not forwarding {port}: only ports of the container can be forwarded"
+                    )
+                }
+            }
+        }
+        args.extend(self.run_args.iter().cloned());
+        args.push(image.to_string());
+        args.extend(KEEP_ALIVE_COMMAND.iter().map(|arg| arg.to_string()));
+        args
+    }
+}
+
+fn folder_name(folder: &Path) -> String {
+    folder
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "workspace".to_string())
+}
+
+/// Identifies a local folder in the names of its image and connection. It
+/// includes a hash of the folder's path, so that folders with the same name
+/// don't share them.
+fn folder_key(folder: &Path) -> String {
+    let name = folder_name(folder)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>();
+    let digest = Sha256::digest(folder.to_string_lossy().as_bytes());
+    let hash = digest[..4]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("{name}-{hash}")
+}
+
+/// The name of the connection to a folder's dev container.
+fn connection_host(local_folder: &Path) -> String {
+    format!("dev-container-{}", folder_key(local_folder))
+}
+
+/// A running dev container.
+struct DevContainer {
+    id: String,
+    name: String,
+    host: String,
+    workspace_folder: String,
+    remote_user: Option<String>,
+}
+
+impl DevContainer {
+    /// The `docker exec` command that runs a program inside the container.
+    fn exec_command(&self, flags: &[&str]) -> Vec<String> {
+        let mut command = vec!["docker".to_string(), "exec".to_string()];
+        command.extend(flags.iter().map(|flag| flag.to_string()));
+        if let Some(user) = &self.remote_user {
+            command.push("--user".to_string());
+            command.push(user.clone());
+        }
+        command.push(self.id.clone());
+        command
+    }
+
+    fn connection_options(&self) -> SshConnectionOptions {
+        SshConnectionOptions {
+            host: self.host.clone(),
+            nickname: Some(self.name.clone()),
+            command: Some(self.exec_command(&["--interactive"])),
+            terminal_command: Some(self.exec_command(&["--interactive", "--tty"])),
+            ..Default::default()
+        }
+    }
+
+    /// Runs a shell script in the container, returning its output.
+    async fn run_script(&self, script: &str) -> Result<String> {
+        let mut args = self.exec_command(&[]);
+        args.extend(["sh".to_string(), "-c".to_string(), script.to_string()]);
+        docker(&args[1..]).await
+    }
+
+    async fn run_lifecycle_command(&self, command: &LifecycleCommand) -> Result<()> {
+        let mut args = self.exec_command(&["--workdir", &self.workspace_folder]);
+        match command {
+            LifecycleCommand::Shell(script) => {
+                args.extend(["sh".to_string(), "-c".to_string(), script.clone()]);
+                docker(&args[1..]).await?;
+            }
+            LifecycleCommand::Exec(command) => {
+                args.extend(command.iter().cloned());
+                docker(&args[1..]).await?;
+            }
+            LifecycleCommand::Named(commands) => {
+                for command in commands.values() {
+                    Box::pin(self.run_lifecycle_command(command)).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the settings of the remote server inside the container.
+    async fn write_server_settings(&self, settings: &serde_json::Value) -> Result<()> {
+        let mut args = self.exec_command(&["--interactive"]);
+        args.extend(
+            [
+                "sh",
+                "-c",
+                "mkdir -p \"$HOME/.config/zed\" && cat > \"$HOME/.config/zed/settings.json\"",
+            ]
+            .map(String::from),
+        );
+        let mut child = util::command::new_smol_command("docker")
+            .args(&args[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let mut stdin = child.stdin.take().context("failed to get stdin")?;
+        stdin
+            .write_all(serde_json::to_string_pretty(settings)?.as_bytes())
+            .await?;
+        drop(stdin);
+
+        let output = child.output().await?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "failed to write settings: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Runs the docker CLI, returning its trimmed output.
+async fn docker(args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> Result<String> {
+    let mut command = util::command::new_smol_command("docker");
+    command.args(args);
+    log::debug!("running {command:?}");
+    let output = command
+        .output()
+        .await
+        .context("failed to run docker, is it installed?")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "docker failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Builds and starts the container for the given folder, or starts the one that
+/// was created for it before.
+async fn start_dev_container(
+    config: &DevContainerConfig,
+    config_path: &Path,
+    local_folder: &Path,
+    mut set_status: impl FnMut(&str),
+) -> Result<DevContainer> {
+    let config_dir = config_path.parent().unwrap_or(local_folder);
+    let existing_id = docker([
+        "ps",
+        "--all",
+        "--filter",
+        &format!(
+            "label={LOCAL_FOLDER_LABEL}={}",
+            local_folder.to_string_lossy()
+        ),
+        "--format",
+        "{{.ID}}",
+    ])
+    .await?
+    .lines()
+    .next()
+    .map(str::to_string);
+
+    let id = if let Some(id) = existing_id {
+        set_status("Starting dev container");
+        docker(["start", &id]).await?;
+        id
+    } else {
+        let tag = format!("zed-dev-container-{}", folder_key(local_folder));
+        let image = if let Some(build_args) = config.build_args(config_dir, &tag) {
+            set_status("Building dev container image");
+            docker(build_args).await?;
+            tag
+        } else {
+            config
+                .image
+                .clone()
+                .context("devcontainer.json specifies neither an image nor a Dockerfile")?
+        };
+        set_status("Creating dev container");
+        docker(config.run_args(local_folder, &image)).await?
+    };
+
+    let container = DevContainer {
+        id,
+        name: config.name(local_folder),
+        host: connection_host(local_folder),
+        workspace_folder: config.workspace_folder(local_folder),
+        remote_user: config.remote_user.clone().or(config.container_user.clone()),
+    };
+
+    if let Some(settings) = config
+        .customizations
+        .as_ref()
+        .and_then(|customizations| customizations.zed.as_ref())
+        .and_then(|zed| zed.settings.as_ref())
+    {
+        container.write_server_settings(settings).await?;
+    }
+    if let Some(command) = &config.post_create_command {
+        let has_run = container
+            .run_script(&format!("test -e {POST_CREATE_MARKER}"))
+            .await
+            .is_ok();
+        if !has_run {
+            set_status("Running postCreateCommand");
+            container
+                .run_lifecycle_command(command)
+                .await
+                .context("postCreateCommand failed")?;
+            container
+                .run_script(&format!(
+                    "mkdir -p \"$(dirname {POST_CREATE_MARKER})\" && touch {POST_CREATE_MARKER}"
+                ))
+                .await
+                .context("failed to record that postCreateCommand has run")?;
+        }
+    }
+
+    Ok(container)
+}
+
+/// Reads a folder's `devcontainer.json` and starts its container, showing the
+/// progress in the given window.
+async fn start_dev_container_in_window(
+    config_path: &Path,
+    local_folder: &Path,
+    fs: &dyn Fs,
+    window: WindowHandle<Workspace>,
+    cx: &mut AsyncApp,
+) -> Result<DevContainer> {
+    struct DevContainerStatus;
+
+    let config: DevContainerConfig =
+        settings::parse_json_with_comments(&fs.load(config_path).await?)
+            .with_context(|| format!("Failed to parse {}", config_path.display()))?;
+
+    let status_id = NotificationId::unique::<DevContainerStatus>();
+    let set_status = |status: &str| {
+        let toast = Toast::new(status_id.clone(), format!("{status}…"));
+        window
+            .update(cx, |workspace, _, cx| workspace.show_toast(toast, cx))
+            .ok();
+    };
+    let container = start_dev_container(&config, config_path, local_folder, set_status).await;
+    window
+        .update(cx, |workspace, _, cx| {
+            workspace.dismiss_toast(&status_id, cx)
+        })
+        .ok();
+    container
+}
+
+/// Starts the container that a saved dev container connection was made to,
+/// returning the options to connect to it with.
+pub(crate) async fn start_saved_dev_container(
+    dev_container: &DevContainerConnection,
+    fs: &dyn Fs,
+    window: WindowHandle<Workspace>,
+    cx: &mut AsyncApp,
+) -> Result<SshConnectionOptions> {
+    let container = start_dev_container_in_window(
+        &dev_container.config_path,
+        &dev_container.local_folder,
+        fs,
+        window,
+        cx,
+    )
+    .await?;
+    Ok(container.connection_options())
+}
+
+async fn find_config(fs: &dyn Fs, folder: &Path) -> Option<PathBuf> {
+    for config_path in CONFIG_PATHS {
+        let path = folder.join(config_path);
+        if fs.is_file(&path).await {
+            return Some(path);
+        }
+    }
+    None
+}
+
+pub fn register(
+    workspace: &mut Workspace,
+    window: Option<&mut Window>,
+    cx: &mut Context<Workspace>,
+) {
+    let Some(window) = window else {
+        return;
+    };
+
+    workspace.register_action(|workspace, _: &ReopenInContainer, window, cx| {
+        reopen_in_container(workspace, window, cx);
+    });
+
+    cx.subscribe_in(
+        workspace.project(),
+        window,
+        |workspace, project, event, _, cx| {
+            if let project::Event::WorktreeAdded(worktree_id) = event {
+                suggest_reopening_in_container(workspace, project, *worktree_id, cx);
+            }
+        },
+    )
+    .detach();
+}
+
+/// Offers to reopen the project in its dev container, if the added worktree has one.
+fn suggest_reopening_in_container(
+    workspace: &mut Workspace,
+    project: &Entity<Project>,
+    worktree_id: WorktreeId,
+    cx: &mut Context<Workspace>,
+) {
+    struct DevContainerNotification;
+
+    let project = project.read(cx);
+    if !project.is_local() {
+        return;
+    }
+    let Some(worktree) = project.worktree_for_id(worktree_id, cx) else {
+        return;
+    };
+    let worktree = worktree.read(cx);
+    if !worktree.is_visible() {
+        return;
+    }
+    let folder = worktree.abs_path().to_path_buf();
+    let fs = workspace.app_state().fs.clone();
+
+    cx.spawn(async move |workspace, cx| {
+        if find_config(fs.as_ref(), &folder).await.is_none() {
+            return;
+        }
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_notification(
+                    NotificationId::composite::<DevContainerNotification>(worktree_id.to_usize()),
+                    cx,
+                    |cx| {
+                        cx.new(|cx| {
+                            MessageNotification::new(
+                                format!(
+                                    "{} has a dev container configuration.",
+                                    folder_name(&folder)
+                                ),
+                                cx,
+                            )
+                            .primary_message("Reopen in Container")
+                            .primary_icon(IconName::Server)
+                            .primary_on_click(|window, cx| {
+                                window.dispatch_action(Box::new(ReopenInContainer), cx)
+                            })
+                        })
+                    },
+                );
+            })
+            .log_err();
+    })
+    .detach();
+}
+
+fn reopen_in_container(
+    workspace: &mut Workspace,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    let project = workspace.project().read(cx);
+    if !project.is_local() {
+        return;
+    }
+    let folders = project
+        .visible_worktrees(cx)
+        .map(|worktree| worktree.read(cx).abs_path().to_path_buf())
+        .collect::<Vec<_>>();
+    let app_state = workspace.app_state().clone();
+    let Some(window_handle) = window.window_handle().downcast::<Workspace>() else {
+        return;
+    };
+
+    cx.spawn_in(window, async move |_, cx| {
+        let mut found = None;
+        for folder in folders {
+            if let Some(config_path) = find_config(app_state.fs.as_ref(), &folder).await {
+                found = Some((folder, config_path));
+                break;
+            }
+        }
+        let (local_folder, config_path) =
+            found.context("No devcontainer.json found in this project")?;
+        let container = start_dev_container_in_window(
+            &config_path,
+            &local_folder,
+            app_state.fs.as_ref(),
+            window_handle,
+            cx,
+        )
+        .await?;
+
+        cx.update(|_, cx| {
+            save_connection(
+                app_state.fs.clone(),
+                &container,
+                &local_folder,
+                &config_path,
+                cx,
+            )
+        })?;
+        open_ssh_project(
+            container.connection_options(),
+            vec![PathBuf::from(&container.workspace_folder)],
+            app_state,
+            OpenOptions {
+                replace_window: Some(window_handle),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await
+    })
+    .detach_and_prompt_err("Failed to open dev container", window, cx, |_, _, _| None);
+}
+
+/// Adds the container to the remote connections, so that the project can be
+/// reopened from the recent projects. The connection is saved with the folder's
+/// `devcontainer.json` rather than the container, which may be removed.
+fn save_connection(
+    fs: Arc<dyn Fs>,
+    container: &DevContainer,
+    local_folder: &Path,
+    config_path: &Path,
+    cx: &mut App,
+) {
+    let host = container.host.clone();
+    let nickname = container.name.clone();
+    let dev_container = DevContainerConnection {
+        local_folder: local_folder.to_path_buf(),
+        config_path: config_path.to_path_buf(),
+    };
+    let project = SshProject {
+        paths: vec![container.workspace_folder.clone()],
+    };
+    update_settings_file::<SshSettings>(fs, cx, move |settings, _| {
+        let connections = settings.ssh_connections.get_or_insert_default();
+        let connection = match connections.iter_mut().position(|connection| {
+            connection
+                .dev_container
+                .as_ref()
+                .is_some_and(|existing| existing.local_folder == dev_container.local_folder)
+        }) {
+            Some(ix) => &mut connections[ix],
+            None => {
+                connections.push(SshConnection::default());
+                connections.last_mut().unwrap()
+            }
+        };
+        connection.host = host.into();
+        connection.nickname = Some(nickname);
+        connection.dev_container = Some(dev_container);
+        connection.projects.insert(project);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folder_key() {
+        let key = folder_key(Path::new("/home/user/work/My App"));
+        assert!(key.starts_with("my-app-"), "{key}");
+        assert_eq!(key, folder_key(Path::new("/home/user/work/My App")));
+        assert_ne!(key, folder_key(Path::new("/home/user/other/My App")));
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config: DevContainerConfig = settings::parse_json_with_comments(
+            r#"{
+                // A comment
+                "name": "Rust",
+                "build": { "dockerfile": "Dockerfile", "context": "..", "args": { "VARIANT": "1" } },
+                "forwardPorts": [3000, "db:5432"],
+                "postCreateCommand": ["cargo", "fetch"],
+                "remoteUser": "vscode",
+                "customizations": { "zed": { "settings": { "tab_size": 2 } } },
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.name.as_deref(), Some("Rust"));
+        assert_eq!(
+            config.forward_ports,
+            [
+                ForwardPort::Port(3000),
+                ForwardPort::HostPort("db:5432".into())
+            ]
+        );
+        assert_eq!(
+            config.post_create_command,
+            Some(LifecycleCommand::Exec(vec!["cargo".into(), "fetch".into()]))
+        );
+        assert_eq!(
+            config.customizations.unwrap().zed.unwrap().settings,
+            Some(serde_json::json!({ "tab_size": 2 }))
+        );
+
+        assert_eq!(
+            config
+                .build_args(Path::new("/code/app/.devcontainer"), "tag")
+                .unwrap(),
+            [
+                "build",
+                "--file",
+                "/code/app/.devcontainer/Dockerfile",
+                "--tag",
+                "tag",
+                "--build-arg",
+                "VARIANT=1",
+                "/code/app/.devcontainer/..",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_args() {
+        let config = DevContainerConfig {
+            forward_ports: vec![ForwardPort::Port(8080)],
+            container_env: BTreeMap::from_iter([("RUST_LOG".into(), "info".into())]),
+            run_args: vec!["--init".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.run_args(Path::new("/code/app"), "rust:latest"),
+            [
+                "run",
+                "--detach",
+                "--label",
+                "dev.zed.local_folder=/code/app",
+                "--mount",
+                "type=bind,source=/code/app,target=/workspaces/app",
+                "--env",
+                "RUST_LOG=info",
+                "--publish",
+                "127.0.0.1:8080:8080",
+                "--init",
+                "rust:latest",
+                "sh",
+                "-c",
+                "while sleep 1000; do :; done",
+            ]
+        );
+    }
+}
@@ -1,4 +1,6 @@
+mod dev_container;
 pub mod disconnected_overlay;
+mod remote_ports;
 mod remote_servers;
 mod ssh_connections;
 pub use ssh_connections::{is_connecting_over_ssh, open_ssh_project};
@@ -14,6 +16,7 @@ use picker::{
     Picker, PickerDelegate,
     highlighted_match_with_paths::{HighlightedMatch, HighlightedMatchWithPaths},
 };
+use remote_ports::RemotePortsModal;
 pub use remote_servers::RemoteServerProjects;
 use settings::Settings;
 pub use ssh_connections::SshSettings;
@@ -34,6 +37,8 @@ pub fn init(cx: &mut App) {
     cx.observe_new(RecentProjects::register).detach();
     cx.observe_new(RemoteServerProjects::register).detach();
     cx.observe_new(DisconnectedOverlay::register).detach();
+    cx.observe_new(RemotePortsModal::register).detach();
+    cx.observe_new(dev_container::register).detach();
 }
 
 pub struct RecentProjects {
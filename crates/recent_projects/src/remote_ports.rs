@@ -0,0 +1,261 @@
+use gpui::{
+    Action, AppContext as _, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Render,
+    Subscription, actions,
+};
+use project::remote_ports::{RemotePort, RemotePorts, RemotePortsEvent};
+use ui::{Tooltip, prelude::*};
+use workspace::{
+    ModalView, Workspace,
+    notifications::{
+        DetachAndPromptErr, NotificationId, simple_message_notification::MessageNotification,
+    },
+};
+
+actions!(remote_ports, [ShowPorts]);
+
+/// Lists the ports listening on the remote machine of an SSH project, and lets
+/// the user forward them to the local machine.
+pub struct RemotePortsModal {
+    remote_ports: Entity<RemotePorts>,
+    focus_handle: FocusHandle,
+    _subscription: Subscription,
+}
+
+impl EventEmitter<DismissEvent> for RemotePortsModal {}
+impl Focusable for RemotePortsModal {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+impl ModalView for RemotePortsModal {}
+
+impl RemotePortsModal {
+    pub fn register(
+        workspace: &mut Workspace,
+        window: Option<&mut Window>,
+        cx: &mut Context<Workspace>,
+    ) {
+        let Some(window) = window else {
+            return;
+        };
+        let Some(remote_ports) = workspace.project().read(cx).remote_ports().cloned() else {
+            return;
+        };
+
+        workspace.register_action(|workspace, _: &ShowPorts, window, cx| {
+            let Some(remote_ports) = workspace.project().read(cx).remote_ports().cloned() else {
+                return;
+            };
+            workspace.toggle_modal(window, cx, |_, cx| Self::new(remote_ports, cx));
+        });
+
+        cx.subscribe_in(
+            &remote_ports,
+            window,
+            |workspace, remote_ports, event, _, cx| {
+                if let RemotePortsEvent::PortOpened(port) = event {
+                    show_port_opened_notification(workspace, remote_ports.clone(), port, cx);
+                }
+            },
+        )
+        .detach();
+    }
+
+    fn new(remote_ports: Entity<RemotePorts>, cx: &mut Context<Self>) -> Self {
+        Self {
+            _subscription: cx.observe(&remote_ports, |_, _, cx| cx.notify()),
+            remote_ports,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent)
+    }
+
+    fn render_port(
+        &self,
+        remote_port: u16,
+        process_name: Option<String>,
+        is_listening: bool,
+        local_port: Option<u16>,
+        supports_forwarding: bool,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let row_ix = remote_port as usize;
+        h_flex()
+            .id(("remote-port", row_ix))
+            .w_full()
+            .justify_between()
+            .gap_2()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(remote_port.to_string()))
+                    .when_some(process_name, |this, process_name| {
+                        this.child(Label::new(process_name).color(Color::Muted))
+                    })
+                    .when(!is_listening, |this| {
+                        this.child(
+                            Label::new("Not listening")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    }),
+            )
+            .child(h_flex().gap_1().map(|this| {
+                match local_port {
+                    Some(local_port) => this
+                        .child(
+                            Button::new(("open-port", row_ix), format!("localhost:{local_port}"))
+                                .icon(IconName::ArrowUpRight)
+                                .icon_position(IconPosition::End)
+                                .icon_size(IconSize::XSmall)
+                                .tooltip(Tooltip::text("Open in Browser"))
+                                .on_click(move |_, _, cx| {
+                                    cx.open_url(&format!("http://localhost:{local_port}"))
+                                }),
+                        )
+                        .child(
+                            IconButton::new(("stop-forwarding", row_ix), IconName::Close)
+                                .icon_size(IconSize::Small)
+                                .tooltip(Tooltip::text("Stop Forwarding"))
+                                .on_click(cx.listener(move |this, _, window, cx| {
+                                    this.remote_ports
+                                        .update(cx, |remote_ports, cx| {
+                                            remote_ports.stop_forwarding(remote_port, cx)
+                                        })
+                                        .detach_and_prompt_err(
+                                            "Failed to stop forwarding port",
+                                            window,
+                                            cx,
+                                            |_, _, _| None,
+                                        );
+                                })),
+                        ),
+                    None if !supports_forwarding => this,
+                    None => this.child(Button::new(("forward-port", row_ix), "Forward").on_click(
+                        cx.listener(move |this, _, window, cx| {
+                            this.remote_ports
+                                .update(cx, |remote_ports, cx| {
+                                    remote_ports.forward(remote_port, cx)
+                                })
+                                .detach_and_prompt_err(
+                                    "Failed to forward port",
+                                    window,
+                                    cx,
+                                    |_, _, _| None,
+                                );
+                        }),
+                    )),
+                }
+            }))
+    }
+}
+
+impl Render for RemotePortsModal {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let remote_ports = self.remote_ports.read(cx);
+        let supports_forwarding = remote_ports.supports_forwarding(cx);
+        let mut rows = remote_ports
+            .ports()
+            .iter()
+            .map(|port| {
+                (
+                    port.port,
+                    port.process_name.clone(),
+                    true,
+                    remote_ports.local_port(port.port),
+                )
+            })
+            .collect::<Vec<_>>();
+        // Forwards outlive the processes that listen on their ports.
+        for (remote_port, local_port) in remote_ports.forwards() {
+            if !rows.iter().any(|row| row.0 == remote_port) {
+                rows.push((remote_port, None, false, Some(local_port)));
+            }
+        }
+        rows.sort_by_key(|row| row.0);
+
+        let content = if rows.is_empty() {
+            Label::new("No ports are listening on the remote machine.")
+                .color(Color::Muted)
+                .into_any_element()
+        } else {
+            v_flex()
+                .gap_1()
+                .children(rows.into_iter().map(
+                    |(remote_port, process_name, is_listening, local_port)| {
+                        self.render_port(
+                            remote_port,
+                            process_name,
+                            is_listening,
+                            local_port,
+                            supports_forwarding,
+                            cx,
+                        )
+                    },
+                ))
+                .into_any_element()
+        };
+
+        div()
+            .track_focus(&self.focus_handle(cx))
+            .elevation_3(cx)
+            .on_action(cx.listener(Self::cancel))
+            .occlude()
+            .w(rems(34.))
+            .max_h(rems(40.))
+            .child(
+                Modal::new("remote-ports", None)
+                    .header(
+                        ModalHeader::new()
+                            .show_dismiss_button(true)
+                            .child(Headline::new("Ports").size(HeadlineSize::Small)),
+                    )
+                    .section(Section::new().child(content)),
+            )
+    }
+}
+
+fn show_port_opened_notification(
+    workspace: &mut Workspace,
+    remote_ports: Entity<RemotePorts>,
+    port: &RemotePort,
+    cx: &mut Context<Workspace>,
+) {
+    struct PortOpenedNotification;
+
+    let remote_port = port.port;
+    let message = match &port.process_name {
+        Some(process_name) => {
+            format!("{process_name} is listening on port {remote_port} of the remote machine.")
+        }
+        None => format!("A process is listening on port {remote_port} of the remote machine."),
+    };
+    workspace.show_notification(
+        NotificationId::composite::<PortOpenedNotification>(remote_port as usize),
+        cx,
+        |cx| {
+            cx.new(|cx| {
+                MessageNotification::new(message, cx)
+                    .primary_message("Forward Port")
+                    .primary_icon(IconName::ArrowUpRight)
+                    .primary_on_click(move |window, cx| {
+                        remote_ports
+                            .update(cx, |remote_ports, cx| remote_ports.forward(remote_port, cx))
+                            .detach_and_prompt_err(
+                                "Failed to forward port",
+                                window,
+                                cx,
+                                |_, _, _| None,
+                            );
+                    })
+                    .secondary_message("Show Ports")
+                    .secondary_on_click(|window, cx| {
+                        window.dispatch_action(ShowPorts.boxed_clone(), cx);
+                    })
+            })
+        },
+    );
+}
@@ -873,6 +873,9 @@ impl RemoteServerProjects {
                     args: connection_options.args.unwrap_or_default(),
                     upload_binary_over_ssh: None,
                     port_forwards: connection_options.port_forwards,
+                    command: connection_options.command,
+                    terminal_command: connection_options.terminal_command,
+                    dev_container: None,
                 })
         });
     }
@@ -1,6 +1,8 @@
 use std::collections::BTreeSet;
 use std::{path::PathBuf, sync::Arc, time::Duration};
 
+use crate::dev_container;
+
 use anyhow::{Result, anyhow};
 use auto_update::AutoUpdater;
 use editor::Editor;
@@ -54,6 +56,8 @@ impl SshSettings {
                     username,
                     port_forwards: conn.port_forwards,
                     password: None,
+                    command: conn.command,
+                    terminal_command: conn.terminal_command,
                 };
             }
         }
@@ -64,6 +68,13 @@ impl SshSettings {
             ..Default::default()
         }
     }
+
+    /// The dev container that the connection to the given host was made to, if any.
+    pub fn dev_container_for(&self, host: &str) -> Option<DevContainerConnection> {
+        self.ssh_connections()
+            .find(|conn| conn.host == host)
+            .and_then(|conn| conn.dev_container)
+    }
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
@@ -90,6 +101,30 @@ pub struct SshConnection {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub port_forwards: Option<Vec<SshPortForwardOption>>,
+
+    /// A command to connect with instead of ssh, such as
+    /// `["docker", "exec", "-i", "my-container"]`. The command must run the
+    /// program given as its trailing arguments on the remote machine, without
+    /// allocating a tty. The `host` is then only used to identify the connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    /// The variant of `command` that allocates a tty, such as
+    /// `["docker", "exec", "-it", "my-container"]`, used for terminals.
+    /// Defaults to `command`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal_command: Option<Vec<String>>,
+    /// The dev container this connection was made to. Its container is started,
+    /// or created again, before connecting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dev_container: Option<DevContainerConnection>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct DevContainerConnection {
+    /// The local folder that is mounted into the container.
+    pub local_folder: PathBuf,
+    /// The path of the folder's `devcontainer.json`.
+    pub config_path: PathBuf,
 }
 
 impl From<SshConnection> for SshConnectionOptions {
@@ -103,6 +138,8 @@ impl From<SshConnection> for SshConnectionOptions {
             nickname: val.nickname,
             upload_binary_over_ssh: val.upload_binary_over_ssh.unwrap_or_default(),
             port_forwards: val.port_forwards,
+            command: val.command,
+            terminal_command: val.terminal_command,
         }
     }
 }
@@ -579,6 +616,21 @@ pub async fn open_ssh_project(
         })?
     };
 
+    let mut connection_options = connection_options;
+    if connection_options.command.is_none() {
+        let dev_container = cx
+            .update(|cx| SshSettings::get_global(cx).dev_container_for(&connection_options.host))?;
+        if let Some(dev_container) = dev_container {
+            connection_options = dev_container::start_saved_dev_container(
+                &dev_container,
+                app_state.fs.as_ref(),
+                window,
+                cx,
+            )
+            .await?;
+        }
+    }
+
     loop {
         let (cancel_tx, cancel_rx) = oneshot::channel();
         let delegate = window.update(cx, {
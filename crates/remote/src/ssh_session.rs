@@ -44,7 +44,7 @@ use std::{
     path::{Path, PathBuf},
     sync::{
         Arc, Weak,
-        atomic::{AtomicU32, AtomicU64, Ordering::SeqCst},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering::SeqCst},
     },
     time::{Duration, Instant},
 };
@@ -83,6 +83,13 @@ pub struct SshConnectionOptions {
 
     pub nickname: Option<String>,
     pub upload_binary_over_ssh: bool,
+
+    /// A command to connect with instead of ssh, such as `docker exec -i <container>`.
+    /// It must run the program given as its trailing arguments on the remote machine,
+    /// with its stdio connected to ours and without allocating a tty.
+    pub command: Option<Vec<String>>,
+    /// The variant of `command` that allocates a tty, used for terminals.
+    pub terminal_command: Option<Vec<String>>,
 }
 
 #[macro_export]
@@ -234,6 +241,8 @@ impl SshConnectionOptions {
             password: None,
             nickname: None,
             upload_binary_over_ssh: false,
+            command: None,
+            terminal_command: None,
         })
     }
 
@@ -367,18 +376,6 @@ impl SshSocket {
         command
     }
 
-    async fn run_command(&self, program: &str, args: &[&str]) -> Result<String> {
-        let output = self.ssh_command(program, args).output().await?;
-        if output.status.success() {
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
-        } else {
-            Err(anyhow!(
-                "failed to run command: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ))
-        }
-    }
-
     fn ssh_options<'a>(&self, command: &'a mut process::Command) -> &'a mut process::Command {
         command
             .stdin(Stdio::piped())
@@ -397,6 +394,82 @@ impl SshSocket {
             self.connection_options.ssh_url(),
         ]
     }
+
+    /// Asks the master connection to start (`forward`) or stop (`cancel`)
+    /// forwarding a local port to a port on the remote machine.
+    async fn control_port_forward(
+        &self,
+        control_command: &str,
+        local_port: u16,
+        remote_port: u16,
+    ) -> Result<()> {
+        let mut command = util::command::new_smol_command("ssh");
+        let output = self
+            .ssh_options(&mut command)
+            .args(["-O", control_command, "-L"])
+            .arg(format!("localhost:{local_port}:localhost:{remote_port}"))
+            .arg(self.connection_options.ssh_url())
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "failed to {control_command} forwarding of port {remote_port}: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl RemoteShell for SshSocket {
+    fn upload_binary_over_ssh(&self) -> bool {
+        self.connection_options.upload_binary_over_ssh
+    }
+
+    async fn run_command(&self, program: &str, args: &[&str]) -> Result<String> {
+        let output = self.ssh_command(program, args).output().await?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(anyhow!(
+                "failed to run command: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+
+    async fn upload_file(&self, src_path: &Path, dest_path: &Path) -> Result<()> {
+        log::debug!("uploading file {:?} to {:?}", src_path, dest_path);
+        let mut command = util::command::new_smol_command("scp");
+        let output = self
+            .ssh_options(&mut command)
+            .args(
+                self.connection_options
+                    .port
+                    .map(|port| vec!["-P".to_string(), port.to_string()])
+                    .unwrap_or_default(),
+            )
+            .arg(src_path)
+            .arg(format!(
+                "{}:{}",
+                self.connection_options.scp_url(),
+                dest_path.display()
+            ))
+            .output()
+            .await?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "failed to upload file {} -> {}: {}",
+                src_path.display(),
+                dest_path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
 }
 
 const MAX_MISSED_HEARTBEATS: usize = 5;
@@ -404,6 +477,9 @@ const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(5);
 
 const MAX_RECONNECT_ATTEMPTS: usize = 3;
+/// How often to try reconnecting once the reconnect attempts are exhausted and
+/// the client is working offline.
+pub const OFFLINE_RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
 
 enum State {
     Connecting,
@@ -431,7 +507,10 @@ enum State {
         error: anyhow::Error,
         attempts: usize,
     },
-    ReconnectExhausted,
+    ReconnectExhausted {
+        ssh_connection: Arc<dyn RemoteConnection>,
+        delegate: Arc<dyn SshClientDelegate>,
+    },
     ServerNotRunning,
 }
 
@@ -442,7 +521,7 @@ impl fmt::Display for State {
             Self::Connected { .. } => write!(f, "connected"),
             Self::Reconnecting => write!(f, "reconnecting"),
             Self::ReconnectFailed { .. } => write!(f, "reconnect failed"),
-            Self::ReconnectExhausted => write!(f, "reconnect exhausted"),
+            Self::ReconnectExhausted { .. } => write!(f, "reconnect exhausted"),
             Self::HeartbeatMissed { .. } => write!(f, "heartbeat missed"),
             Self::ServerNotRunning { .. } => write!(f, "server not running"),
         }
@@ -463,14 +542,16 @@ impl State {
         match self {
             Self::Connected { .. }
             | Self::HeartbeatMissed { .. }
-            | Self::ReconnectFailed { .. } => true,
-            State::Connecting
-            | State::Reconnecting
-            | State::ReconnectExhausted
-            | State::ServerNotRunning => false,
+            | Self::ReconnectFailed { .. }
+            | Self::ReconnectExhausted { .. } => true,
+            State::Connecting | State::Reconnecting | State::ServerNotRunning => false,
         }
     }
 
+    fn is_connected(&self) -> bool {
+        matches!(self, Self::Connected { .. })
+    }
+
     fn is_reconnect_failed(&self) -> bool {
         matches!(self, Self::ReconnectFailed { .. })
     }
@@ -544,6 +625,10 @@ pub enum ConnectionState {
     Connected,
     HeartbeatMissed,
     Reconnecting,
+    /// The connection was lost and could not be re-established yet. Open
+    /// buffers stay editable, and their edits are sent to the server once the
+    /// connection is back.
+    Offline,
     Disconnected,
 }
 
@@ -554,7 +639,7 @@ impl From<&State> for ConnectionState {
             State::Connected { .. } => Self::Connected,
             State::Reconnecting | State::ReconnectFailed { .. } => Self::Reconnecting,
             State::HeartbeatMissed { .. } => Self::HeartbeatMissed,
-            State::ReconnectExhausted => Self::Disconnected,
+            State::ReconnectExhausted { .. } => Self::Offline,
             State::ServerNotRunning => Self::Disconnected,
         }
     }
@@ -569,6 +654,10 @@ pub struct SshRemoteClient {
 
 #[derive(Debug)]
 pub enum SshRemoteEvent {
+    /// All reconnect attempts failed. The client keeps retrying in the background.
+    Offline,
+    /// The connection was re-established after having been lost.
+    Reconnected,
     Disconnected,
 }
 
@@ -760,10 +849,11 @@ impl SshRemoteClient {
                 delegate,
                 ..
             } => (attempts, ssh_connection, delegate),
-            State::Connecting
-            | State::Reconnecting
-            | State::ReconnectExhausted
-            | State::ServerNotRunning => unreachable!(),
+            State::ReconnectExhausted {
+                ssh_connection,
+                delegate,
+            } => (0, ssh_connection, delegate),
+            State::Connecting | State::Reconnecting | State::ServerNotRunning => unreachable!(),
         };
 
         let attempts = attempts + 1;
@@ -773,7 +863,26 @@ impl SshRemoteClient {
                 MAX_RECONNECT_ATTEMPTS
             );
             drop(lock);
-            self.set_state(State::ReconnectExhausted, cx);
+            self.set_state(
+                State::ReconnectExhausted {
+                    ssh_connection,
+                    delegate,
+                },
+                cx,
+            );
+            cx.spawn(async move |this, cx| {
+                cx.background_executor()
+                    .timer(OFFLINE_RECONNECT_INTERVAL)
+                    .await;
+                this.update(cx, |this, cx| {
+                    if this.state_is(State::is_reconnect_exhausted) {
+                        this.reconnect(cx)
+                    } else {
+                        Ok(())
+                    }
+                })?
+            })
+            .detach_and_log_err(cx);
             return Ok(());
         }
         drop(lock);
@@ -874,7 +983,7 @@ impl SshRemoteClient {
                                     error
                                 );
                             }
-                            State::ReconnectExhausted => {
+                            State::ReconnectExhausted { .. } => {
                                 log::error!("Reconnect attempt failed and all attempts exhausted");
                             }
                         }
@@ -888,6 +997,9 @@ impl SshRemoteClient {
                     this.reconnect(cx)
                 } else if this.state_is(State::is_reconnect_exhausted) {
                     Ok(())
+                } else if this.state_is(State::is_connected) {
+                    cx.emit(SshRemoteEvent::Reconnected);
+                    Ok(())
                 } else {
                     log::debug!("State has transition from Reconnecting into new state while attempting reconnect.");
                     Ok(())
@@ -1058,7 +1170,9 @@ impl SshRemoteClient {
         let is_server_not_running = state.is_server_not_running();
         self.state.lock().replace(state);
 
-        if is_reconnect_exhausted || is_server_not_running {
+        if is_reconnect_exhausted {
+            cx.emit(SshRemoteEvent::Offline);
+        } else if is_server_not_running {
             cx.emit(SshRemoteEvent::Disconnected);
         }
         cx.notify();
@@ -1068,12 +1182,14 @@ impl SshRemoteClient {
         self.client.subscribe_to_entity(remote_id, entity);
     }
 
-    pub fn ssh_args(&self) -> Option<Vec<String>> {
+    /// The program and leading arguments that run a shell command, given as the
+    /// final argument, on the remote machine with a tty.
+    pub fn terminal_command(&self) -> Option<(String, Vec<String>)> {
         self.state
             .lock()
             .as_ref()
             .and_then(|state| state.ssh_connection())
-            .map(|ssh_connection| ssh_connection.ssh_args())
+            .map(|ssh_connection| ssh_connection.terminal_command())
     }
 
     pub fn upload_directory(
@@ -1089,6 +1205,29 @@ impl SshRemoteClient {
         connection.upload_directory(src_path, dest_path, cx)
     }
 
+    /// Forwards the given local port to a port on the remote machine, through
+    /// the existing connection.
+    pub fn forward_port(&self, local_port: u16, remote_port: u16, cx: &App) -> Task<Result<()>> {
+        let state = self.state.lock();
+        let Some(connection) = state.as_ref().and_then(|state| state.ssh_connection()) else {
+            return Task::ready(Err(anyhow!("no ssh connection")));
+        };
+        connection.forward_port(local_port, remote_port, cx)
+    }
+
+    pub fn cancel_port_forward(
+        &self,
+        local_port: u16,
+        remote_port: u16,
+        cx: &App,
+    ) -> Task<Result<()>> {
+        let state = self.state.lock();
+        let Some(connection) = state.as_ref().and_then(|state| state.ssh_connection()) else {
+            return Task::ready(Err(anyhow!("no ssh connection")));
+        };
+        connection.cancel_port_forward(local_port, remote_port, cx)
+    }
+
     pub fn proto_client(&self) -> AnyProtoClient {
         self.client.clone().into()
     }
@@ -1133,6 +1272,34 @@ impl SshRemoteClient {
         })
     }
 
+    /// Makes the host unreachable, so that the connection is lost and every
+    /// attempt to reconnect fails, or makes it reachable again.
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn simulate_unreachable_host(&self, unreachable: bool, client_cx: &mut App) -> Task<()> {
+        let opts = self.connection_options();
+        client_cx.spawn(async move |cx| {
+            let entry = cx
+                .update_global(|c: &mut ConnectionPool, _| c.connections.remove(&opts))
+                .unwrap();
+            let connection = match entry {
+                Some(ConnectionPoolEntry::Connecting(c)) => c.await.unwrap(),
+                Some(ConnectionPoolEntry::Unreachable(c)) => c,
+                _ => panic!("missing test connection"),
+            };
+
+            let entry = if unreachable {
+                ConnectionPoolEntry::Unreachable(connection.clone())
+            } else {
+                ConnectionPoolEntry::Connecting(Task::ready(Ok(connection.clone())).shared())
+            };
+            cx.update_global(|c: &mut ConnectionPool, _| c.connections.insert(opts, entry))
+                .unwrap();
+            if unreachable {
+                connection.simulate_disconnect(&cx);
+            }
+        })
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn fake_server(
         client_cx: &mut gpui::TestAppContext,
@@ -1198,6 +1365,9 @@ impl SshRemoteClient {
 enum ConnectionPoolEntry {
     Connecting(Shared<Task<Result<Arc<dyn RemoteConnection>, Arc<anyhow::Error>>>>),
     Connected(Weak<dyn RemoteConnection>),
+    /// A fake connection whose host can't be reached, so connecting to it fails.
+    #[cfg(any(test, feature = "test-support"))]
+    Unreachable(Arc<dyn RemoteConnection>),
 }
 
 #[derive(Default)]
@@ -1232,6 +1402,10 @@ impl ConnectionPool {
                 }
                 self.connections.remove(&opts);
             }
+            #[cfg(any(test, feature = "test-support"))]
+            Some(ConnectionPoolEntry::Unreachable(_)) => {
+                return Task::ready(Err(Arc::new(anyhow!("host is unreachable")))).shared();
+            }
             None => {}
         }
 
@@ -1240,9 +1414,15 @@ impl ConnectionPool {
                 let opts = opts.clone();
                 let delegate = delegate.clone();
                 async move |cx| {
-                    let connection = SshRemoteConnection::new(opts.clone(), delegate, cx)
-                        .await
-                        .map(|connection| Arc::new(connection) as Arc<dyn RemoteConnection>);
+                    let connection = if opts.command.is_some() {
+                        CommandRemoteConnection::new(opts.clone(), delegate, cx)
+                            .await
+                            .map(|connection| Arc::new(connection) as Arc<dyn RemoteConnection>)
+                    } else {
+                        SshRemoteConnection::new(opts.clone(), delegate, cx)
+                            .await
+                            .map(|connection| Arc::new(connection) as Arc<dyn RemoteConnection>)
+                    };
 
                     cx.update_global(|pool: &mut Self, _| {
                         debug_assert!(matches!(
@@ -1293,9 +1473,11 @@ trait RemoteConnection: Send + Sync {
     ) -> Task<Result<i32>>;
     fn upload_directory(&self, src_path: PathBuf, dest_path: PathBuf, cx: &App)
     -> Task<Result<()>>;
+    fn forward_port(&self, local_port: u16, remote_port: u16, cx: &App) -> Task<Result<()>>;
+    fn cancel_port_forward(&self, local_port: u16, remote_port: u16, cx: &App) -> Task<Result<()>>;
     async fn kill(&self) -> Result<()>;
     fn has_been_killed(&self) -> bool;
-    fn ssh_args(&self) -> Vec<String>;
+    fn terminal_command(&self) -> (String, Vec<String>);
     fn connection_options(&self) -> SshConnectionOptions;
 
     #[cfg(any(test, feature = "test-support"))]
@@ -1324,14 +1506,34 @@ impl RemoteConnection for SshRemoteConnection {
         self.master_process.lock().is_none()
     }
 
-    fn ssh_args(&self) -> Vec<String> {
-        self.socket.ssh_args()
+    fn terminal_command(&self) -> (String, Vec<String>) {
+        let mut args = self.socket.ssh_args();
+        args.push("-t".to_string());
+        ("ssh".to_string(), args)
     }
 
     fn connection_options(&self) -> SshConnectionOptions {
         self.socket.connection_options.clone()
     }
 
+    fn forward_port(&self, local_port: u16, remote_port: u16, cx: &App) -> Task<Result<()>> {
+        let socket = self.socket.clone();
+        cx.background_spawn(async move {
+            socket
+                .control_port_forward("forward", local_port, remote_port)
+                .await
+        })
+    }
+
+    fn cancel_port_forward(&self, local_port: u16, remote_port: u16, cx: &App) -> Task<Result<()>> {
+        let socket = self.socket.clone();
+        cx.background_spawn(async move {
+            socket
+                .control_port_forward("cancel", local_port, remote_port)
+                .await
+        })
+    }
+
     fn upload_directory(
         &self,
         src_path: PathBuf,
@@ -1391,30 +1593,8 @@ impl RemoteConnection for SshRemoteConnection {
             return Task::ready(Err(anyhow!("Remote binary path not set")));
         };
 
-        let mut start_proxy_command = shell_script!(
-            "exec {binary_path} proxy --identifier {identifier}",
-            binary_path = &remote_binary_path.to_string_lossy(),
-            identifier = &unique_identifier,
-        );
-
-        if let Some(rust_log) = std::env::var("RUST_LOG").ok() {
-            start_proxy_command = format!(
-                "RUST_LOG={} {}",
-                shlex::try_quote(&rust_log).unwrap(),
-                start_proxy_command
-            )
-        }
-        if let Some(rust_backtrace) = std::env::var("RUST_BACKTRACE").ok() {
-            start_proxy_command = format!(
-                "RUST_BACKTRACE={} {}",
-                shlex::try_quote(&rust_backtrace).unwrap(),
-                start_proxy_command
-            )
-        }
-        if reconnect {
-            start_proxy_command.push_str(" --reconnect");
-        }
-
+        let start_proxy_command =
+            start_proxy_command(&remote_binary_path, &unique_identifier, reconnect);
         let ssh_proxy_process = match self
             .socket
             .ssh_command("sh", &["-c", &start_proxy_command])
@@ -1438,6 +1618,39 @@ impl RemoteConnection for SshRemoteConnection {
     }
 }
 
+/// The shell command that starts the remote server's proxy, which relays messages
+/// between its stdio and the server.
+fn start_proxy_command(
+    remote_binary_path: &Path,
+    unique_identifier: &str,
+    reconnect: bool,
+) -> String {
+    let mut start_proxy_command = shell_script!(
+        "exec {binary_path} proxy --identifier {identifier}",
+        binary_path = &remote_binary_path.to_string_lossy(),
+        identifier = unique_identifier,
+    );
+
+    if let Some(rust_log) = std::env::var("RUST_LOG").ok() {
+        start_proxy_command = format!(
+            "RUST_LOG={} {}",
+            shlex::try_quote(&rust_log).unwrap(),
+            start_proxy_command
+        )
+    }
+    if let Some(rust_backtrace) = std::env::var("RUST_BACKTRACE").ok() {
+        start_proxy_command = format!(
+            "RUST_BACKTRACE={} {}",
+            shlex::try_quote(&rust_backtrace).unwrap(),
+            start_proxy_command
+        )
+    }
+    if reconnect {
+        start_proxy_command.push_str(" --reconnect");
+    }
+    start_proxy_command
+}
+
 impl SshRemoteConnection {
     #[cfg(not(unix))]
     async fn new(
@@ -1555,44 +1768,14 @@ impl SshRemoteConnection {
             )
         })?;
         this.remote_binary_path = Some(
-            this.ensure_server_binary(&delegate, release_channel, version, commit, cx)
+            this.socket
+                .ensure_server_binary(&delegate, release_channel, version, commit, cx)
                 .await?,
         );
 
         Ok(this)
     }
 
-    async fn platform(&self) -> Result<SshPlatform> {
-        let uname = self.socket.run_command("sh", &["-c", "uname -sm"]).await?;
-        let Some((os, arch)) = uname.split_once(" ") else {
-            Err(anyhow!("unknown uname: {uname:?}"))?
-        };
-
-        let os = match os.trim() {
-            "Darwin" => "macos",
-            "Linux" => "linux",
-            _ => Err(anyhow!(
-                "Prebuilt remote servers are not yet available for {os:?}. See https://zed.dev/docs/remote-development"
-            ))?,
-        };
-        // exclude armv5,6,7 as they are 32-bit.
-        let arch = if arch.starts_with("armv8")
-            || arch.starts_with("armv9")
-            || arch.starts_with("arm64")
-            || arch.starts_with("aarch64")
-        {
-            "aarch64"
-        } else if arch.starts_with("x86") {
-            "x86_64"
-        } else {
-            Err(anyhow!(
-                "Prebuilt remote servers are not yet available for {arch:?}. See https://zed.dev/docs/remote-development"
-            ))?
-        };
-
-        Ok(SshPlatform { os, arch })
-    }
-
     fn multiplex(
         mut ssh_proxy_process: Child,
         incoming_tx: UnboundedSender<Envelope>,
@@ -1694,6 +1877,308 @@ impl SshRemoteConnection {
             }
         })
     }
+}
+
+/// A connection through a user-provided command, such as `docker exec -i <container>`,
+/// which runs the program given as its trailing arguments on the remote machine.
+/// Every command, including the proxy to the remote server, gets its own process.
+struct CommandRemoteConnection {
+    connection_options: SshConnectionOptions,
+    remote_binary_path: Option<PathBuf>,
+    killed: AtomicBool,
+}
+
+impl CommandRemoteConnection {
+    async fn new(
+        connection_options: SshConnectionOptions,
+        delegate: Arc<dyn SshClientDelegate>,
+        cx: &mut AsyncApp,
+    ) -> Result<Self> {
+        delegate.set_status(Some("Connecting"), cx);
+
+        if connection_options
+            .command
+            .as_ref()
+            .map_or(true, |command| command.is_empty())
+        {
+            anyhow::bail!("no command to connect with");
+        }
+
+        let mut this = Self {
+            connection_options,
+            remote_binary_path: None,
+            killed: AtomicBool::new(false),
+        };
+        this.run_command("true", &[])
+            .await
+            .context("Failed to connect to host")?;
+
+        let (release_channel, version, commit) = cx.update(|cx| {
+            (
+                ReleaseChannel::global(cx),
+                AppVersion::global(cx),
+                AppCommitSha::try_global(cx),
+            )
+        })?;
+        this.remote_binary_path = Some(
+            this.ensure_server_binary(&delegate, release_channel, version, commit, cx)
+                .await?,
+        );
+
+        Ok(this)
+    }
+
+    fn command(&self, program: &str, args: &[&str]) -> process::Command {
+        let (transport, transport_args) = self
+            .connection_options
+            .command
+            .as_deref()
+            .and_then(|command| command.split_first())
+            .expect("command connections have a command");
+        let mut command = util::command::new_smol_command(transport);
+        log::debug!("{transport} {transport_args:?} {program} {args:?}");
+        // Like ssh, start in the home directory, which the remote server's paths
+        // are relative to.
+        command
+            .args(transport_args)
+            .args(["sh", "-c", "cd; exec \"$@\"", "sh", program])
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+
+    /// Runs a command on the remote machine with the given stream as its stdin.
+    async fn run_with_input(
+        mut command: process::Command,
+        input: impl futures::AsyncRead,
+    ) -> Result<()> {
+        let mut child = command.kill_on_drop(true).spawn()?;
+        let mut stdin = child.stdin.take().context("failed to get stdin")?;
+        futures::io::copy(input, &mut stdin).await?;
+        drop(stdin);
+
+        let output = child.output().await?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "failed to run command: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl RemoteShell for CommandRemoteConnection {
+    fn upload_binary_over_ssh(&self) -> bool {
+        self.connection_options.upload_binary_over_ssh
+    }
+
+    async fn run_command(&self, program: &str, args: &[&str]) -> Result<String> {
+        let output = self.command(program, args).output().await?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(anyhow!(
+                "failed to run command: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+
+    async fn upload_file(&self, src_path: &Path, dest_path: &Path) -> Result<()> {
+        log::debug!("uploading file {:?} to {:?}", src_path, dest_path);
+        let file = fs::File::open(src_path).await?;
+        let command = self.command(
+            "sh",
+            &["-c", "cat > \"$1\"", "sh", &dest_path.to_string_lossy()],
+        );
+        Self::run_with_input(command, file).await.with_context(|| {
+            format!(
+                "failed to upload file {} -> {}",
+                src_path.display(),
+                dest_path.display()
+            )
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl RemoteConnection for CommandRemoteConnection {
+    async fn kill(&self) -> Result<()> {
+        // There's no connection to close besides the proxy process, which is
+        // killed along with the task that multiplexes it.
+        self.killed.store(true, SeqCst);
+        Ok(())
+    }
+
+    fn has_been_killed(&self) -> bool {
+        self.killed.load(SeqCst)
+    }
+
+    fn terminal_command(&self) -> (String, Vec<String>) {
+        let command = self
+            .connection_options
+            .terminal_command
+            .as_ref()
+            .or(self.connection_options.command.as_ref())
+            .cloned()
+            .unwrap_or_default();
+        let mut command = command.into_iter();
+        let program = command.next().unwrap_or_default();
+        let args = command
+            .chain(["sh".to_string(), "-c".to_string()])
+            .collect();
+        (program, args)
+    }
+
+    fn connection_options(&self) -> SshConnectionOptions {
+        self.connection_options.clone()
+    }
+
+    fn forward_port(&self, _local_port: u16, remote_port: u16, _cx: &App) -> Task<Result<()>> {
+        Task::ready(Err(anyhow!(
+            "cannot forward port {remote_port}: {} is not connected over ssh",
+            self.connection_options.host
+        )))
+    }
+
+    fn cancel_port_forward(
+        &self,
+        _local_port: u16,
+        _remote_port: u16,
+        _cx: &App,
+    ) -> Task<Result<()>> {
+        Task::ready(Ok(()))
+    }
+
+    fn upload_directory(
+        &self,
+        src_path: PathBuf,
+        dest_path: PathBuf,
+        cx: &App,
+    ) -> Task<Result<()>> {
+        let archive = util::command::new_smol_command("tar")
+            .arg("-C")
+            .arg(&src_path)
+            .args(["-cf", "-", "."])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn();
+        let extract = self.command(
+            "sh",
+            &[
+                "-c",
+                "mkdir -p \"$1\" && tar -C \"$1\" -xf -",
+                "sh",
+                &dest_path.to_string_lossy(),
+            ],
+        );
+
+        cx.background_spawn(async move {
+            let mut archive = archive?;
+            let archive_stdout = archive.stdout.take().context("failed to get stdout")?;
+            let result = Self::run_with_input(extract, archive_stdout).await;
+
+            let output = archive.output().await?;
+            if !output.status.success() {
+                anyhow::bail!(
+                    "failed to archive directory {}: {}",
+                    src_path.display(),
+                    String::from_utf8_lossy(&output.stderr)
+                );
+            }
+            result.with_context(|| {
+                format!(
+                    "failed to upload directory {} -> {}",
+                    src_path.display(),
+                    dest_path.display()
+                )
+            })
+        })
+    }
+
+    fn start_proxy(
+        &self,
+        unique_identifier: String,
+        reconnect: bool,
+        incoming_tx: UnboundedSender<Envelope>,
+        outgoing_rx: UnboundedReceiver<Envelope>,
+        connection_activity_tx: Sender<()>,
+        delegate: Arc<dyn SshClientDelegate>,
+        cx: &mut AsyncApp,
+    ) -> Task<Result<i32>> {
+        delegate.set_status(Some("Starting proxy"), cx);
+
+        let Some(remote_binary_path) = self.remote_binary_path.clone() else {
+            return Task::ready(Err(anyhow!("Remote binary path not set")));
+        };
+
+        let start_proxy_command =
+            start_proxy_command(&remote_binary_path, &unique_identifier, reconnect);
+        let proxy_process = match self
+            .command("sh", &["-c", &start_proxy_command])
+            // IMPORTANT: we kill this process when we drop the task that uses it.
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(process) => process,
+            Err(error) => {
+                return Task::ready(Err(anyhow!("failed to spawn remote server: {}", error)));
+            }
+        };
+
+        SshRemoteConnection::multiplex(
+            proxy_process,
+            incoming_tx,
+            outgoing_rx,
+            connection_activity_tx,
+            &cx,
+        )
+    }
+}
+
+/// Runs commands on the remote machine while connecting to it, before the remote
+/// server is running. Used to install the remote server.
+#[async_trait(?Send)]
+trait RemoteShell {
+    fn upload_binary_over_ssh(&self) -> bool;
+    async fn run_command(&self, program: &str, args: &[&str]) -> Result<String>;
+    async fn upload_file(&self, src_path: &Path, dest_path: &Path) -> Result<()>;
+
+    async fn platform(&self) -> Result<SshPlatform> {
+        let uname = self.run_command("sh", &["-c", "uname -sm"]).await?;
+        let Some((os, arch)) = uname.split_once(" ") else {
+            Err(anyhow!("unknown uname: {uname:?}"))?
+        };
+
+        let os = match os.trim() {
+            "Darwin" => "macos",
+            "Linux" => "linux",
+            _ => Err(anyhow!(
+                "Prebuilt remote servers are not yet available for {os:?}. See https://zed.dev/docs/remote-development"
+            ))?,
+        };
+        // exclude armv5,6,7 as they are 32-bit.
+        let arch = if arch.starts_with("armv8")
+            || arch.starts_with("armv9")
+            || arch.starts_with("arm64")
+            || arch.starts_with("aarch64")
+        {
+            "aarch64"
+        } else if arch.starts_with("x86") {
+            "x86_64"
+        } else {
+            Err(anyhow!(
+                "Prebuilt remote servers are not yet available for {arch:?}. See https://zed.dev/docs/remote-development"
+            ))?
+        };
+
+        Ok(SshPlatform { os, arch })
+    }
 
     #[allow(unused)]
     async fn ensure_server_binary(
@@ -1738,7 +2223,6 @@ impl SshRemoteConnection {
         }
 
         if self
-            .socket
             .run_command(&dst_path.to_string_lossy(), &["version"])
             .await
             .is_ok()
@@ -1759,7 +2243,7 @@ impl SshRemoteConnection {
 
         let platform = self.platform().await?;
 
-        if !self.socket.connection_options.upload_binary_over_ssh {
+        if !self.upload_binary_over_ssh() {
             if let Some((url, body)) = delegate
                 .get_download_params(platform, release_channel, wanted_version, cx)
                 .await?
@@ -1802,15 +2286,13 @@ impl SshRemoteConnection {
         cx: &mut AsyncApp,
     ) -> Result<()> {
         if let Some(parent) = tmp_path_gz.parent() {
-            self.socket
-                .run_command("mkdir", &["-p", &parent.to_string_lossy()])
+            self.run_command("mkdir", &["-p", &parent.to_string_lossy()])
                 .await?;
         }
 
         delegate.set_status(Some("Downloading remote development server on host"), cx);
 
         match self
-            .socket
             .run_command(
                 "curl",
                 &[
@@ -1831,12 +2313,11 @@ impl SshRemoteConnection {
         {
             Ok(_) => {}
             Err(e) => {
-                if self.socket.run_command("which", &["curl"]).await.is_ok() {
+                if self.run_command("which", &["curl"]).await.is_ok() {
                     return Err(e);
                 }
 
                 match self
-                    .socket
                     .run_command(
                         "wget",
                         &[
@@ -1853,7 +2334,7 @@ impl SshRemoteConnection {
                 {
                     Ok(_) => {}
                     Err(e) => {
-                        if self.socket.run_command("which", &["wget"]).await.is_ok() {
+                        if self.run_command("which", &["wget"]).await.is_ok() {
                             return Err(e);
                         } else {
                             anyhow::bail!("Neither curl nor wget is available");
@@ -1874,8 +2355,7 @@ impl SshRemoteConnection {
         cx: &mut AsyncApp,
     ) -> Result<()> {
         if let Some(parent) = tmp_path_gz.parent() {
-            self.socket
-                .run_command("mkdir", &["-p", &parent.to_string_lossy()])
+            self.run_command("mkdir", &["-p", &parent.to_string_lossy()])
                 .await?;
         }
 
@@ -1913,44 +2393,10 @@ impl SshRemoteConnection {
             server_mode = &format!("{:o}", server_mode),
             dst_path = &dst_path.to_string_lossy()
         );
-        self.socket.run_command("sh", &["-c", &script]).await?;
+        self.run_command("sh", &["-c", &script]).await?;
         Ok(())
     }
 
-    async fn upload_file(&self, src_path: &Path, dest_path: &Path) -> Result<()> {
-        log::debug!("uploading file {:?} to {:?}", src_path, dest_path);
-        let mut command = util::command::new_smol_command("scp");
-        let output = self
-            .socket
-            .ssh_options(&mut command)
-            .args(
-                self.socket
-                    .connection_options
-                    .port
-                    .map(|port| vec!["-P".to_string(), port.to_string()])
-                    .unwrap_or_default(),
-            )
-            .arg(src_path)
-            .arg(format!(
-                "{}:{}",
-                self.socket.connection_options.scp_url(),
-                dest_path.display()
-            ))
-            .output()
-            .await?;
-
-        if output.status.success() {
-            Ok(())
-        } else {
-            Err(anyhow!(
-                "failed to upload file {} -> {}: {}",
-                src_path.display(),
-                dest_path.display(),
-                String::from_utf8_lossy(&output.stderr)
-            ))
-        }
-    }
-
     #[cfg(debug_assertions)]
     async fn build_local(
         &self,
@@ -2420,8 +2866,8 @@ mod fake {
             false
         }
 
-        fn ssh_args(&self) -> Vec<String> {
-            Vec::new()
+        fn terminal_command(&self) -> (String, Vec<String>) {
+            ("ssh".to_string(), Vec::new())
         }
         fn upload_directory(
             &self,
@@ -2436,6 +2882,19 @@ mod fake {
             self.connection_options.clone()
         }
 
+        fn forward_port(&self, _local_port: u16, _remote_port: u16, _cx: &App) -> Task<Result<()>> {
+            Task::ready(Ok(()))
+        }
+
+        fn cancel_port_forward(
+            &self,
+            _local_port: u16,
+            _remote_port: u16,
+            _cx: &App,
+        ) -> Task<Result<()>> {
+            Task::ready(Ok(()))
+        }
+
         fn simulate_disconnect(&self, cx: &AsyncApp) {
             let (outgoing_tx, _) = mpsc::unbounded::<Envelope>();
             let (_, incoming_rx) = mpsc::unbounded::<Envelope>();
@@ -2515,3 +2974,45 @@ mod fake {
         fn set_status(&self, _: Option<&str>, _: &mut AsyncApp) {}
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_remote_connection() {
+        // A wrapper that runs the program locally stands in for a container.
+        let wrapper = ["sh", "-c", "exec \"$@\"", "sh"].map(String::from).to_vec();
+        let connection = CommandRemoteConnection {
+            connection_options: SshConnectionOptions {
+                host: "local".to_string(),
+                command: Some(wrapper.clone()),
+                ..Default::default()
+            },
+            remote_binary_path: None,
+            killed: AtomicBool::new(false),
+        };
+        let dir = tempfile::tempdir().unwrap();
+
+        smol::block_on(async {
+            assert_eq!(
+                connection
+                    .run_command("echo", &["hello world"])
+                    .await
+                    .unwrap(),
+                "hello world\n"
+            );
+            assert!(connection.run_command("false", &[]).await.is_err());
+
+            let src_path = dir.path().join("src");
+            let dest_path = dir.path().join("dest");
+            std::fs::write(&src_path, "contents").unwrap();
+            connection.upload_file(&src_path, &dest_path).await.unwrap();
+            assert_eq!(std::fs::read_to_string(&dest_path).unwrap(), "contents");
+        });
+
+        let (program, args) = connection.terminal_command();
+        assert_eq!(program, "sh");
+        assert_eq!(args, ["-c", "exec \"$@\"", "sh", "sh", "-c"]);
+    }
+}
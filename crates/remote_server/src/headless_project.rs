@@ -1,10 +1,13 @@
+use crate::port_watcher::{self, PortSource};
 use ::proto::{FromProto, ToProto};
 use anyhow::{Result, anyhow};
+use collections::HashMap;
 use dap::DapRegistry;
 use extension::ExtensionHostProxy;
 use extension_host::headless_host::HeadlessExtensionStore;
-use fs::Fs;
-use gpui::{App, AppContext as _, AsyncApp, Context, Entity, PromptLevel};
+use fs::{Fs, RemoveOptions, RenameOptions};
+use futures::{SinkExt as _, TryStreamExt as _, channel::mpsc};
+use gpui::{App, AppContext as _, AsyncApp, Context, Entity, PromptLevel, Task};
 use http_client::HttpClient;
 use language::{Buffer, BufferEvent, LanguageRegistry, proto::serialize_operation};
 use node_runtime::NodeRuntime;
@@ -17,7 +20,10 @@ use project::{
     project_settings::SettingsObserver,
     search::SearchQuery,
     task_store::TaskStore,
-    worktree_store::WorktreeStore,
+    worktree_store::{
+        FILE_TRANSFER_BUFFERED_CHUNKS, FILE_TRANSFER_CHUNK_SIZE, WorktreeStore,
+        file_transfer_temp_path,
+    },
 };
 use remote::ssh_session::ChannelClient;
 use rpc::{
@@ -28,7 +34,9 @@ use rpc::{
 use settings::initial_server_settings_content;
 use smol::stream::StreamExt;
 use std::{
-    path::{Path, PathBuf},
+    io::{self, Read as _},
+    path::{Component, Path, PathBuf},
+    pin::Pin,
     sync::{Arc, atomic::AtomicUsize},
 };
 use util::ResultExt;
@@ -46,6 +54,26 @@ pub struct HeadlessProject {
     pub languages: Arc<LanguageRegistry>,
     pub extensions: Entity<HeadlessExtensionStore>,
     pub git_store: Entity<GitStore>,
+    file_transfers: HashMap<u64, FileTransfer>,
+    next_file_transfer_id: u64,
+    _port_watcher: Option<Task<()>>,
+}
+
+/// A file being copied between this machine and the client, one chunk per request.
+enum FileTransfer {
+    Download {
+        /// Taken while a chunk is being read.
+        file: Option<Box<dyn io::Read + Send + Sync>>,
+    },
+    /// Written to a temporary file that replaces the one at `abs_path` once
+    /// the upload is complete.
+    Upload {
+        abs_path: PathBuf,
+        temp_path: PathBuf,
+        overwrite: bool,
+        chunks_tx: mpsc::Sender<io::Result<Vec<u8>>>,
+        write_task: Task<Result<()>>,
+    },
 }
 
 pub struct HeadlessAppState {
@@ -56,6 +84,9 @@ pub struct HeadlessAppState {
     pub languages: Arc<LanguageRegistry>,
     pub debug_adapters: Arc<DapRegistry>,
     pub extension_host_proxy: Arc<ExtensionHostProxy>,
+    /// Where the listening ports that the client can forward are read from.
+    /// They aren't watched if this is `None`.
+    pub port_source: Option<Arc<dyn PortSource>>,
 }
 
 impl HeadlessProject {
@@ -74,6 +105,7 @@ impl HeadlessProject {
             languages,
             debug_adapters,
             extension_host_proxy: proxy,
+            port_source,
         }: HeadlessAppState,
         cx: &mut Context<Self>,
     ) -> Self {
@@ -228,6 +260,10 @@ impl HeadlessProject {
         client.add_entity_request_handler(Self::handle_open_new_buffer);
         client.add_entity_request_handler(Self::handle_find_search_candidates);
         client.add_entity_request_handler(Self::handle_open_server_settings);
+        client.add_entity_request_handler(Self::handle_open_file_transfer);
+        client.add_entity_request_handler(Self::handle_read_file_transfer_chunk);
+        client.add_entity_request_handler(Self::handle_write_file_transfer_chunk);
+        client.add_entity_request_handler(Self::handle_close_file_transfer);
 
         client.add_entity_request_handler(BufferStore::handle_update_buffer);
         client.add_entity_message_handler(BufferStore::handle_close_buffer);
@@ -252,6 +288,9 @@ impl HeadlessProject {
         // BreakpointStore::init(&client);
         GitStore::init(&client);
 
+        let port_watcher =
+            port_source.map(|source| port_watcher::watch_ports(client.clone(), source, cx));
+
         HeadlessProject {
             session: client,
             settings_observer,
@@ -264,6 +303,9 @@ impl HeadlessProject {
             languages,
             extensions,
             git_store,
+            file_transfers: HashMap::default(),
+            next_file_transfer_id: 0,
+            _port_watcher: port_watcher,
         }
     }
 
@@ -637,6 +679,214 @@ impl HeadlessProject {
         })
     }
 
+    pub async fn handle_open_file_transfer(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::OpenFileTransfer>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::OpenFileTransferResponse> {
+        let worktree_id = WorktreeId::from_proto(envelope.payload.worktree_id);
+        let path = PathBuf::from_proto(envelope.payload.path);
+        if !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(anyhow!("invalid path {path:?}"));
+        }
+        let (fs, abs_path) = this.update(&mut cx, |this, cx| {
+            let worktree = this
+                .worktree_store
+                .read(cx)
+                .worktree_for_id(worktree_id, cx)
+                .ok_or_else(|| anyhow!("worktree not found"))?;
+            anyhow::Ok((this.fs.clone(), worktree.read(cx).abs_path().join(&path)))
+        })??;
+
+        let transfer_id = this.update(&mut cx, |this, _| {
+            let transfer_id = this.next_file_transfer_id;
+            this.next_file_transfer_id += 1;
+            transfer_id
+        })?;
+
+        let (transfer, size) = if envelope.payload.upload {
+            let overwrite = envelope.payload.overwrite;
+            if !overwrite && fs.metadata(&abs_path).await?.is_some() {
+                return Err(anyhow!("{path:?} already exists"));
+            }
+            if let Some(parent) = abs_path.parent() {
+                fs.create_dir(parent).await?;
+            }
+            let temp_path = file_transfer_temp_path(&abs_path, transfer_id);
+            let (chunks_tx, chunks_rx) = mpsc::channel(FILE_TRANSFER_BUFFERED_CHUNKS);
+            let write_task = cx.background_spawn({
+                let fs = fs.clone();
+                let temp_path = temp_path.clone();
+                async move {
+                    let mut content = chunks_rx.into_async_read();
+                    fs.create_file_with(&temp_path, Pin::new(&mut content))
+                        .await
+                }
+            });
+            let transfer = FileTransfer::Upload {
+                abs_path,
+                temp_path,
+                overwrite,
+                chunks_tx,
+                write_task,
+            };
+            (transfer, 0)
+        } else {
+            let metadata = fs
+                .metadata(&abs_path)
+                .await?
+                .ok_or_else(|| anyhow!("{path:?} does not exist"))?;
+            let file = fs.open_sync(&abs_path).await?;
+            (FileTransfer::Download { file: Some(file) }, metadata.len)
+        };
+
+        this.update(&mut cx, |this, _| {
+            this.file_transfers.insert(transfer_id, transfer);
+            proto::OpenFileTransferResponse { transfer_id, size }
+        })
+    }
+
+    pub async fn handle_read_file_transfer_chunk(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::ReadFileTransferChunk>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::ReadFileTransferChunkResponse> {
+        let transfer_id = envelope.payload.transfer_id;
+        let mut file = this.update(&mut cx, |this, _| {
+            match this.file_transfers.get_mut(&transfer_id) {
+                Some(FileTransfer::Download { file }) => file
+                    .take()
+                    .ok_or_else(|| anyhow!("file transfer {transfer_id} is busy")),
+                _ => Err(anyhow!("no download with id {transfer_id}")),
+            }
+        })??;
+
+        let (file, data) = cx
+            .background_spawn(async move {
+                let mut data = Vec::with_capacity(FILE_TRANSFER_CHUNK_SIZE);
+                let result = file
+                    .by_ref()
+                    .take(FILE_TRANSFER_CHUNK_SIZE as u64)
+                    .read_to_end(&mut data);
+                (file, result.map(|_| data))
+            })
+            .await;
+
+        this.update(&mut cx, |this, _| {
+            if let Some(FileTransfer::Download { file: slot }) =
+                this.file_transfers.get_mut(&transfer_id)
+            {
+                *slot = Some(file);
+            }
+        })?;
+        Ok(proto::ReadFileTransferChunkResponse { data: data? })
+    }
+
+    pub async fn handle_write_file_transfer_chunk(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::WriteFileTransferChunk>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let transfer_id = envelope.payload.transfer_id;
+        let mut chunks_tx = this.update(&mut cx, |this, _| {
+            match this.file_transfers.get(&transfer_id) {
+                Some(FileTransfer::Upload { chunks_tx, .. }) => Ok(chunks_tx.clone()),
+                _ => Err(anyhow!("no upload with id {transfer_id}")),
+            }
+        })??;
+        // Waits for the file to catch up when chunks arrive faster than they're written.
+        chunks_tx
+            .send(Ok(envelope.payload.data))
+            .await
+            .map_err(|_| anyhow!("failed to write file"))?;
+        Ok(proto::Ack {})
+    }
+
+    pub async fn handle_close_file_transfer(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::CloseFileTransfer>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let transfer_id = envelope.payload.transfer_id;
+        let (fs, transfer) = this.update(&mut cx, |this, _| {
+            (this.fs.clone(), this.file_transfers.remove(&transfer_id))
+        })?;
+        let transfer = transfer.ok_or_else(|| anyhow!("no file transfer with id {transfer_id}"))?;
+
+        if let FileTransfer::Upload {
+            abs_path,
+            temp_path,
+            overwrite,
+            chunks_tx,
+            write_task,
+        } = transfer
+        {
+            drop(chunks_tx);
+            let result = if envelope.payload.cancel {
+                drop(write_task);
+                Ok(())
+            } else {
+                match write_task.await {
+                    Ok(()) => {
+                        fs.rename(
+                            &temp_path,
+                            &abs_path,
+                            RenameOptions {
+                                overwrite,
+                                ignore_if_exists: false,
+                            },
+                        )
+                        .await
+                    }
+                    Err(error) => Err(error),
+                }
+            };
+            if envelope.payload.cancel || result.is_err() {
+                fs.remove_file(
+                    &temp_path,
+                    RemoveOptions {
+                        ignore_if_not_exists: true,
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            }
+            result?;
+        }
+        Ok(proto::Ack {})
+    }
+
+    /// Drops the file transfers of a client that went away, removing the
+    /// temporary files of its unfinished uploads.
+    pub fn client_disconnected(&mut self, cx: &mut Context<Self>) {
+        let temp_paths = self
+            .file_transfers
+            .drain()
+            .filter_map(|(_, transfer)| match transfer {
+                FileTransfer::Upload { temp_path, .. } => Some(temp_path),
+                FileTransfer::Download { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        let fs = self.fs.clone();
+        cx.background_spawn(async move {
+            for temp_path in temp_paths {
+                fs.remove_file(
+                    &temp_path,
+                    RemoveOptions {
+                        ignore_if_not_exists: true,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .log_err();
+            }
+        })
+        .detach();
+    }
+
     pub async fn handle_shutdown_remote_server(
         _this: Entity<Self>,
         _envelope: TypedEnvelope<proto::ShutdownRemoteServer>,
@@ -0,0 +1,84 @@
+//! Detects the TCP ports that processes started from terminals and tasks listen
+//! on, so that the client can offer to forward them. The remote server's own
+//! processes, such as language servers, are ignored.
+
+#[cfg(target_os = "linux")]
+mod proc_ports;
+
+use std::{sync::Arc, time::Duration};
+
+use gpui::{App, Task};
+use rpc::{
+    AnyProtoClient,
+    proto::{self, SSH_PROJECT_ID},
+};
+use util::ResultExt;
+
+#[cfg(target_os = "linux")]
+pub use proc_ports::ProcPortSource;
+
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ListeningPort {
+    pub port: u16,
+    pub process_name: Option<String>,
+}
+
+/// Where the ports that are listening on the server's machine are read from.
+pub trait PortSource: Send + Sync {
+    /// The listening ports, sorted by port, with each port listed once.
+    fn listening_ports(&self) -> Vec<ListeningPort>;
+}
+
+/// A port source whose ports are set by tests.
+#[cfg(any(test, feature = "test-support"))]
+#[derive(Default)]
+pub struct FakePortSource {
+    ports: std::sync::Mutex<Vec<ListeningPort>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl FakePortSource {
+    pub fn set_ports(&self, ports: Vec<ListeningPort>) {
+        *self.ports.lock().unwrap() = ports;
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl PortSource for FakePortSource {
+    fn listening_ports(&self) -> Vec<ListeningPort> {
+        self.ports.lock().unwrap().clone()
+    }
+}
+
+/// Sends the listening ports to the client whenever they change.
+pub fn watch_ports(session: AnyProtoClient, source: Arc<dyn PortSource>, cx: &mut App) -> Task<()> {
+    cx.spawn(async move |cx| {
+        let mut last_ports = Vec::new();
+        loop {
+            let ports = cx
+                .background_spawn({
+                    let source = source.clone();
+                    async move { source.listening_ports() }
+                })
+                .await;
+            if ports != last_ports {
+                session
+                    .send(proto::UpdateRemotePorts {
+                        project_id: SSH_PROJECT_ID,
+                        ports: ports
+                            .iter()
+                            .map(|port| proto::RemotePort {
+                                port: port.port as u32,
+                                process_name: port.process_name.clone(),
+                            })
+                            .collect(),
+                    })
+                    .log_err();
+                last_ports = ports;
+            }
+            cx.background_executor().timer(POLL_INTERVAL).await;
+        }
+    })
+}
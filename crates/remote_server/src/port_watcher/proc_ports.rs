@@ -0,0 +1,230 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::{ListeningPort, PortSource};
+
+/// Reads the listening ports from `/proc`.
+///
+/// Only the TCP sockets of processes started from terminals and tasks are
+/// reported. Those run over their own ssh sessions rather than under the
+/// server, and are recognized by the `ZED_TERM` variable that the client sets
+/// in their environment, which is looked up in the socket owner's ancestors as
+/// well, in case it was cleared. The server's own descendants, like language
+/// servers, are never reported.
+pub struct ProcPortSource;
+
+const TERMINAL_ENV_VAR: &[u8] = b"ZED_TERM=true";
+
+impl PortSource for ProcPortSource {
+    fn listening_ports(&self) -> Vec<ListeningPort> {
+        listening_ports()
+    }
+}
+
+fn listening_ports() -> Vec<ListeningPort> {
+    let uid = unsafe { libc::getuid() };
+    let mut sockets = HashMap::new();
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        if let Ok(contents) = std::fs::read_to_string(table) {
+            sockets.extend(parse_listening_sockets(&contents, uid));
+        }
+    }
+
+    let processes = processes();
+    let server_pid = std::process::id();
+    let mut ports = BTreeSet::new();
+    for (inode, port) in sockets {
+        let Some(owner) = processes
+            .iter()
+            .find(|process| process.socket_inodes.contains(&inode))
+        else {
+            continue;
+        };
+        if is_started_from_terminal(owner.pid, server_pid, &processes) {
+            ports.insert(ListeningPort {
+                port,
+                process_name: Some(owner.name.clone()),
+            });
+        }
+    }
+
+    // A port that listens on both IPv4 and IPv6 is only reported once.
+    let mut ports = ports.into_iter().collect::<Vec<_>>();
+    ports.dedup_by_key(|port| port.port);
+    ports
+}
+
+struct Process {
+    pid: u32,
+    parent_pid: u32,
+    name: String,
+    is_terminal: bool,
+    socket_inodes: Vec<u64>,
+}
+
+/// Lists the processes whose sockets can be inspected, which are the ones owned
+/// by the current user.
+fn processes() -> Vec<Process> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+            let (name, parent_pid) = parse_stat(&stat)?;
+            let is_terminal = std::fs::read(entry.path().join("environ"))
+                .is_ok_and(|environ| is_terminal_environ(&environ));
+            let socket_inodes = std::fs::read_dir(entry.path().join("fd"))
+                .ok()?
+                .filter_map(|fd| {
+                    let target = std::fs::read_link(fd.ok()?.path()).ok()?;
+                    target
+                        .to_str()?
+                        .strip_prefix("socket:[")?
+                        .strip_suffix(']')?
+                        .parse()
+                        .ok()
+                })
+                .collect();
+            Some(Process {
+                pid,
+                parent_pid,
+                name,
+                is_terminal,
+                socket_inodes,
+            })
+        })
+        .collect()
+}
+
+/// Whether the process or one of its ancestors is a terminal or task, stopping
+/// at the server's own processes.
+fn is_started_from_terminal(mut pid: u32, server_pid: u32, processes: &[Process]) -> bool {
+    let mut is_terminal = false;
+    // Bounded, in case the process table changed while it was being read.
+    for _ in 0..processes.len() {
+        if pid == server_pid {
+            return false;
+        }
+        match processes.iter().find(|process| process.pid == pid) {
+            Some(process) => {
+                is_terminal |= process.is_terminal;
+                if process.parent_pid == pid {
+                    break;
+                }
+                pid = process.parent_pid;
+            }
+            None => break,
+        }
+    }
+    is_terminal
+}
+
+/// Whether the contents of `/proc/<pid>/environ` mark a terminal or task.
+fn is_terminal_environ(environ: &[u8]) -> bool {
+    environ
+        .split(|byte| *byte == 0)
+        .any(|variable| variable == TERMINAL_ENV_VAR)
+}
+
+/// Parses the name and parent pid out of the contents of `/proc/<pid>/stat`.
+fn parse_stat(stat: &str) -> Option<(String, u32)> {
+    // The name is in parentheses and may contain spaces or parentheses itself.
+    let name_start = stat.find('(')?;
+    let name_end = stat.rfind(')')?;
+    let name = stat.get(name_start + 1..name_end)?.to_string();
+    let mut fields = stat.get(name_end + 1..)?.split_whitespace();
+    let _state = fields.next()?;
+    let parent_pid = fields.next()?.parse().ok()?;
+    Some((name, parent_pid))
+}
+
+/// Parses the listening sockets owned by the given user out of the contents of
+/// `/proc/net/tcp` or `/proc/net/tcp6`, returning their inodes and ports.
+fn parse_listening_sockets(contents: &str, uid: u32) -> Vec<(u64, u16)> {
+    const TCP_LISTEN: &str = "0A";
+
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [_, local_address, _, state, _, _, _, owner, _, inode, ..] = fields[..] else {
+                return None;
+            };
+            if state != TCP_LISTEN || owner.parse::<u32>().ok()? != uid {
+                return None;
+            }
+            let (_, port) = local_address.rsplit_once(':')?;
+            let port = u16::from_str_radix(port, 16).ok()?;
+            let inode = inode.parse().ok()?;
+            Some((inode, port))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listening_sockets() {
+        let contents = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 17861 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 52011 1 0000000000000000 100 0 0 10 0
+   2: 0100007F:0BB8 0100007F:D2A4 01 00000000:00000000 00:00000000 00000000  1000        0 52101 1 0000000000000000 20 4 30 10 -1
+";
+        assert_eq!(parse_listening_sockets(contents, 1000), vec![(52011, 3000)]);
+
+        let contents = "\
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 60231 1 0000000000000000 100 0 0 10 0
+";
+        assert_eq!(parse_listening_sockets(contents, 1000), vec![(60231, 8080)]);
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        assert_eq!(
+            parse_stat("4242 (node (dev)) S 4100 4242 4100 34816 4242 4194304"),
+            Some(("node (dev)".to_string(), 4100))
+        );
+        assert_eq!(parse_stat("4242 node"), None);
+    }
+
+    #[test]
+    fn test_is_started_from_terminal() {
+        let process = |pid, parent_pid, is_terminal| Process {
+            pid,
+            parent_pid,
+            name: String::new(),
+            is_terminal,
+            socket_inodes: Vec::new(),
+        };
+        let server_pid = 20;
+        let processes = [
+            process(1, 0, false),
+            // A terminal's shell, which started a dev server that cleared its
+            // environment.
+            process(10, 1, true),
+            process(11, 10, false),
+            // The server and a language server it started, while itself
+            // running in a terminal.
+            process(20, 10, true),
+            process(21, 20, true),
+            // A process started outside of Zed.
+            process(30, 1, false),
+        ];
+
+        assert!(is_started_from_terminal(10, server_pid, &processes));
+        assert!(is_started_from_terminal(11, server_pid, &processes));
+        assert!(!is_started_from_terminal(21, server_pid, &processes));
+        assert!(!is_started_from_terminal(30, server_pid, &processes));
+        assert!(!is_started_from_terminal(40, server_pid, &processes));
+
+        assert!(is_terminal_environ(b"HOME=/home/me\0ZED_TERM=true\0"));
+        assert!(!is_terminal_environ(b"HOME=/home/me\0ZED_TERM=false\0"));
+    }
+}
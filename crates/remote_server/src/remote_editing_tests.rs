@@ -1,7 +1,10 @@
 /// todo(windows)
 /// The tests in this file assume that server_cx is running on Windows too.
 /// We neead to find a way to test Windows-Non-Windows interactions.
-use crate::headless_project::HeadlessProject;
+use crate::{
+    headless_project::HeadlessProject,
+    port_watcher::{FakePortSource, ListeningPort, POLL_INTERVAL, PortSource},
+};
 use client::{Client, UserStore};
 use clock::FakeSystemClock;
 use dap::DapRegistry;
@@ -17,15 +20,20 @@ use lsp::{CompletionContext, CompletionResponse, CompletionTriggerKind, Language
 use node_runtime::NodeRuntime;
 use project::{
     Project, ProjectPath,
+    project_settings::ProjectSettings,
+    remote_ports::RemotePort,
     search::{SearchQuery, SearchResult},
 };
-use remote::SshRemoteClient;
+use remote::{ConnectionState, SshRemoteClient, ssh_session::OFFLINE_RECONNECT_INTERVAL};
+use rpc::proto::{self, SSH_PROJECT_ID};
 use serde_json::json;
 use settings::{Settings, SettingsLocation, SettingsStore, initial_server_settings_content};
 use smol::stream::StreamExt;
 use std::{
+    cell::RefCell,
     collections::HashSet,
     path::{Path, PathBuf},
+    rc::Rc,
     sync::Arc,
 };
 #[cfg(not(windows))]
@@ -787,6 +795,70 @@ async fn test_remote_reload(cx: &mut TestAppContext, server_cx: &mut TestAppCont
     });
 }
 
+#[gpui::test]
+async fn test_remote_exit_large_file_mode(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
+    let content = (0..150_000)
+        .map(|ix| format!("{ix:015}\n"))
+        .collect::<String>();
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(
+        path!("/code"),
+        json!({ "project1": { "large.txt": content.clone() } }),
+    )
+    .await;
+
+    let (project, headless) = init_test(&fs, cx, server_cx).await;
+    for cx in [&mut *cx, &mut *server_cx] {
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<ProjectSettings>(cx, |settings| {
+                    settings.large_file.size_threshold_mb = 0;
+                    settings.large_file.read_only = true;
+                });
+            });
+        });
+    }
+    let (worktree, _) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_worktree(path!("/code/project1"), true, cx)
+        })
+        .await
+        .unwrap();
+    let worktree_id = cx.update(|cx| worktree.read(cx).id());
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer((worktree_id, Path::new("large.txt")), cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert!(buffer.large_file_mode());
+        assert!(buffer.read_only());
+    });
+
+    // Leaving large file mode makes the buffer editable on both sides once the
+    // host has loaded all of the file.
+    project
+        .update(cx, |project, cx| {
+            project.exit_large_file_mode(buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    cx.run_until_parked();
+    server_cx.run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        assert!(!buffer.large_file_mode());
+        assert!(!buffer.read_only());
+        assert_eq!(buffer.text(), content);
+    });
+    headless.read_with(server_cx, |headless, cx| {
+        let buffer = headless.buffer_store.read(cx).buffers().next().unwrap();
+        assert!(!buffer.read(cx).large_file_mode());
+        assert!(!buffer.read(cx).read_only());
+    });
+}
+
 #[gpui::test]
 async fn test_remote_resolve_path_in_buffer(
     cx: &mut TestAppContext,
@@ -1124,6 +1196,441 @@ async fn test_reconnect(cx: &mut TestAppContext, server_cx: &mut TestAppContext)
     );
 }
 
+#[gpui::test]
+async fn test_offline_edits_are_replayed_on_reconnect(
+    cx: &mut TestAppContext,
+    server_cx: &mut TestAppContext,
+) {
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(
+        path!("/code"),
+        json!({
+            "project1": {
+                "src": {
+                    "lib.rs": "fn one() -> usize { 1 }"
+                }
+            },
+        }),
+    )
+    .await;
+
+    let (project, headless) = init_test(&fs, cx, server_cx).await;
+    let (worktree, _) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_worktree(path!("/code/project1"), true, cx)
+        })
+        .await
+        .unwrap();
+    let worktree_id = worktree.read_with(cx, |worktree, _| worktree.id());
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer((worktree_id, Path::new("src/lib.rs")), cx)
+        })
+        .await
+        .unwrap();
+
+    let client = cx.read(|cx| project.read(cx).ssh_client().unwrap());
+    client
+        .update(cx, |client, cx| client.simulate_unreachable_host(true, cx))
+        .await;
+    cx.run_until_parked();
+    client.read_with(cx, |client, _| {
+        assert_eq!(client.connection_state(), ConnectionState::Offline)
+    });
+
+    // The buffer stays editable while the host can't be reached.
+    buffer.update(cx, |buffer, cx| {
+        assert!(!buffer.read_only());
+        let ix = buffer.text().find('1').unwrap();
+        buffer.edit([(ix..ix + 1, "100")], None, cx);
+    });
+    cx.run_until_parked();
+    server_cx.run_until_parked();
+    headless.read_with(server_cx, |headless, cx| {
+        let buffer = headless.buffer_store.read(cx).buffers().next().unwrap();
+        assert_eq!(buffer.read(cx).text(), "fn one() -> usize { 1 }");
+    });
+
+    client
+        .update(cx, |client, cx| client.simulate_unreachable_host(false, cx))
+        .await;
+    cx.executor().advance_clock(OFFLINE_RECONNECT_INTERVAL);
+    cx.run_until_parked();
+    server_cx.run_until_parked();
+    client.read_with(cx, |client, _| {
+        assert_eq!(client.connection_state(), ConnectionState::Connected)
+    });
+    headless.read_with(server_cx, |headless, cx| {
+        let buffer = headless.buffer_store.read(cx).buffers().next().unwrap();
+        assert_eq!(buffer.read(cx).text(), "fn one() -> usize { 100 }");
+    });
+    buffer.read_with(cx, |buffer, _| assert!(!buffer.has_conflict()));
+
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.load(path!("/code/project1/src/lib.rs").as_ref())
+            .await
+            .unwrap(),
+        "fn one() -> usize { 100 }"
+    );
+}
+
+#[gpui::test]
+async fn test_offline_edits_conflict_with_changes_on_host(
+    cx: &mut TestAppContext,
+    server_cx: &mut TestAppContext,
+) {
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(
+        path!("/code"),
+        json!({
+            "project1": {
+                "src": {
+                    "lib.rs": "fn one() -> usize { 1 }\n"
+                }
+            },
+        }),
+    )
+    .await;
+
+    let (project, headless) = init_test(&fs, cx, server_cx).await;
+    let (worktree, _) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_worktree(path!("/code/project1"), true, cx)
+        })
+        .await
+        .unwrap();
+    let worktree_id = worktree.read_with(cx, |worktree, _| worktree.id());
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer((worktree_id, Path::new("src/lib.rs")), cx)
+        })
+        .await
+        .unwrap();
+
+    let client = cx.read(|cx| project.read(cx).ssh_client().unwrap());
+    client
+        .update(cx, |client, cx| client.simulate_unreachable_host(true, cx))
+        .await;
+    cx.run_until_parked();
+    buffer.update(cx, |buffer, cx| {
+        let end = buffer.len();
+        buffer.edit([(end..end, "fn two() -> usize { 2 }\n")], None, cx);
+    });
+
+    // The file changes on the host before the offline edits reach it.
+    fs.save(
+        path!("/code/project1/src/lib.rs").as_ref(),
+        &"fn one() -> usize { 100 }\n".into(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    server_cx.run_until_parked();
+
+    client
+        .update(cx, |client, cx| client.simulate_unreachable_host(false, cx))
+        .await;
+    cx.executor().advance_clock(OFFLINE_RECONNECT_INTERVAL);
+    cx.run_until_parked();
+    server_cx.run_until_parked();
+    cx.run_until_parked();
+
+    // Both edits are merged, but the buffer no longer matches the file that
+    // the offline edits were made against.
+    let expected_text = "fn one() -> usize { 100 }\nfn two() -> usize { 2 }\n";
+    headless.read_with(server_cx, |headless, cx| {
+        let buffer = headless.buffer_store.read(cx).buffers().next().unwrap();
+        assert_eq!(buffer.read(cx).text(), expected_text);
+    });
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.text(), expected_text);
+        assert!(buffer.is_dirty());
+        assert!(buffer.has_conflict());
+    });
+}
+
+#[gpui::test]
+async fn test_reconnecting_after_attempts_are_exhausted(
+    cx: &mut TestAppContext,
+    server_cx: &mut TestAppContext,
+) {
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(path!("/code"), json!({ "project1": {} }))
+        .await;
+
+    let (project, _headless) = init_test(&fs, cx, server_cx).await;
+    let client = cx.read(|cx| project.read(cx).ssh_client().unwrap());
+    let events = Rc::new(RefCell::new(Vec::new()));
+    cx.update(|cx| {
+        let events = events.clone();
+        cx.subscribe(&client, move |_, event, _| {
+            events.borrow_mut().push(format!("{event:?}"));
+        })
+        .detach();
+    });
+
+    client
+        .update(cx, |client, cx| client.simulate_unreachable_host(true, cx))
+        .await;
+    cx.run_until_parked();
+    assert_eq!(
+        events.borrow_mut().drain(..).collect::<Vec<_>>(),
+        ["Offline"]
+    );
+
+    // Once every attempt has failed, another round of attempts is made after
+    // a while, which fails as well while the host is still unreachable.
+    cx.executor().advance_clock(OFFLINE_RECONNECT_INTERVAL);
+    cx.run_until_parked();
+    assert_eq!(
+        events.borrow_mut().drain(..).collect::<Vec<_>>(),
+        ["Offline"]
+    );
+    client.read_with(cx, |client, _| {
+        assert_eq!(client.connection_state(), ConnectionState::Offline)
+    });
+
+    client
+        .update(cx, |client, cx| client.simulate_unreachable_host(false, cx))
+        .await;
+    cx.run_until_parked();
+    assert!(events.borrow().is_empty());
+    cx.executor().advance_clock(OFFLINE_RECONNECT_INTERVAL);
+    cx.run_until_parked();
+    assert_eq!(
+        events.borrow_mut().drain(..).collect::<Vec<_>>(),
+        ["Reconnected"]
+    );
+    client.read_with(cx, |client, _| {
+        assert_eq!(client.connection_state(), ConnectionState::Connected)
+    });
+}
+
+#[gpui::test]
+async fn test_remote_ports(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(path!("/code"), json!({ "project1": {} }))
+        .await;
+
+    let port_source = Arc::new(FakePortSource::default());
+    let (project, _headless) =
+        init_test_with_port_source(&fs, Some(port_source.clone()), cx, server_cx).await;
+    let remote_ports = project.read_with(cx, |project, _| project.remote_ports().cloned().unwrap());
+    cx.run_until_parked();
+    remote_ports.read_with(cx, |remote_ports, _| {
+        assert!(remote_ports.ports().is_empty())
+    });
+
+    port_source.set_ports(vec![ListeningPort {
+        port: 3000,
+        process_name: Some("node".to_string()),
+    }]);
+    server_cx.executor().advance_clock(POLL_INTERVAL);
+    cx.run_until_parked();
+    remote_ports.read_with(cx, |remote_ports, _| {
+        assert_eq!(
+            remote_ports.ports(),
+            [RemotePort {
+                port: 3000,
+                process_name: Some("node".to_string()),
+            }]
+        )
+    });
+
+    port_source.set_ports(Vec::new());
+    server_cx.executor().advance_clock(POLL_INTERVAL);
+    cx.run_until_parked();
+    remote_ports.read_with(cx, |remote_ports, _| {
+        assert!(remote_ports.ports().is_empty())
+    });
+}
+
+#[gpui::test]
+async fn test_remote_file_transfer(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(
+        path!("/code"),
+        json!({
+            "project1": {
+                "src": {
+                    "lib.rs": "fn one() -> usize { 1 }",
+                    "main.rs": "fn main() {}",
+                },
+            },
+        }),
+    )
+    .await;
+
+    let (project, headless) = init_test(&fs, cx, server_cx).await;
+    let (worktree, _) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_worktree(path!("/code/project1"), true, cx)
+        })
+        .await
+        .unwrap();
+    let worktree_id = worktree.read_with(cx, |worktree, _| worktree.id());
+    cx.run_until_parked();
+
+    let local_fs = project.read_with(cx, |project, _| project.fs().as_fake());
+    local_fs
+        .insert_tree(path!("/local"), json!({ "notes.txt": "remember" }))
+        .await;
+
+    let download = |overwrite: bool, cx: &mut TestAppContext| {
+        project.update(cx, |project, cx| {
+            project.download_entries(
+                worktree_id,
+                vec![Path::new("src").into()],
+                PathBuf::from(path!("/local")),
+                overwrite,
+                cx,
+            )
+        })
+    };
+    let (_transfer, task) = download(false, cx);
+    task.await.unwrap();
+    assert_eq!(
+        local_fs
+            .load(path!("/local/src/lib.rs").as_ref())
+            .await
+            .unwrap(),
+        "fn one() -> usize { 1 }"
+    );
+    assert_eq!(
+        local_fs
+            .load(path!("/local/src/main.rs").as_ref())
+            .await
+            .unwrap(),
+        "fn main() {}"
+    );
+
+    // Existing files are only replaced when asked to.
+    local_fs
+        .save(
+            path!("/local/src/lib.rs").as_ref(),
+            &"local edit".into(),
+            LineEnding::Unix,
+        )
+        .await
+        .unwrap();
+    let (_transfer, task) = download(false, cx);
+    task.await.unwrap_err();
+    assert_eq!(
+        local_fs
+            .load(path!("/local/src/lib.rs").as_ref())
+            .await
+            .unwrap(),
+        "local edit"
+    );
+    let (_transfer, task) = download(true, cx);
+    task.await.unwrap();
+    assert_eq!(
+        local_fs
+            .load(path!("/local/src/lib.rs").as_ref())
+            .await
+            .unwrap(),
+        "fn one() -> usize { 1 }"
+    );
+
+    let upload = |overwrite: bool, cx: &mut TestAppContext| {
+        project.update(cx, |project, cx| {
+            project.upload_paths(
+                worktree_id,
+                vec![PathBuf::from(path!("/local/notes.txt"))],
+                Path::new("src").into(),
+                overwrite,
+                cx,
+            )
+        })
+    };
+    let (transfer, task) = upload(false, cx);
+    task.await.unwrap();
+    assert_eq!(
+        fs.load(path!("/code/project1/src/notes.txt").as_ref())
+            .await
+            .unwrap(),
+        "remember"
+    );
+    transfer.read_with(cx, |transfer, _| {
+        assert_eq!(transfer.total_bytes(), 8);
+        assert_eq!(transfer.transferred_bytes(), 8);
+    });
+
+    local_fs
+        .save(
+            path!("/local/notes.txt").as_ref(),
+            &"forget".into(),
+            LineEnding::Unix,
+        )
+        .await
+        .unwrap();
+    let (_transfer, task) = upload(false, cx);
+    task.await.unwrap_err();
+    assert_eq!(
+        fs.load(path!("/code/project1/src/notes.txt").as_ref())
+            .await
+            .unwrap(),
+        "remember"
+    );
+    let (_transfer, task) = upload(true, cx);
+    task.await.unwrap();
+    assert_eq!(
+        fs.load(path!("/code/project1/src/notes.txt").as_ref())
+            .await
+            .unwrap(),
+        "forget"
+    );
+
+    // An upload that's left unfinished by a client that went away doesn't touch
+    // the file it would have replaced.
+    let client = project.read_with(cx, |project, cx| {
+        project.ssh_client().unwrap().read(cx).proto_client()
+    });
+    let transfer_id = client
+        .request(proto::OpenFileTransfer {
+            project_id: SSH_PROJECT_ID,
+            worktree_id: worktree_id.to_proto(),
+            path: "src/notes.txt".into(),
+            upload: true,
+            overwrite: true,
+        })
+        .await
+        .unwrap()
+        .transfer_id;
+    client
+        .request(proto::WriteFileTransferChunk {
+            project_id: SSH_PROJECT_ID,
+            transfer_id,
+            data: b"partial".to_vec(),
+        })
+        .await
+        .unwrap();
+    headless.update(server_cx, |headless, cx| headless.client_disconnected(cx));
+    server_cx.run_until_parked();
+    client
+        .request(proto::WriteFileTransferChunk {
+            project_id: SSH_PROJECT_ID,
+            transfer_id,
+            data: b"more".to_vec(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(
+        fs.load(path!("/code/project1/src/notes.txt").as_ref())
+            .await
+            .unwrap(),
+        "forget"
+    );
+
+    let is_temp_file = |path: &PathBuf| path.extension().is_some_and(|ext| ext == "part");
+    assert!(!fs.files().iter().any(is_temp_file));
+    assert!(!local_fs.files().iter().any(is_temp_file));
+}
+
 #[gpui::test]
 async fn test_remote_root_rename(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
     let fs = FakeFs::new(server_cx.executor());
@@ -1441,6 +1948,15 @@ pub async fn init_test(
     server_fs: &Arc<FakeFs>,
     cx: &mut TestAppContext,
     server_cx: &mut TestAppContext,
+) -> (Entity<Project>, Entity<HeadlessProject>) {
+    init_test_with_port_source(server_fs, None, cx, server_cx).await
+}
+
+async fn init_test_with_port_source(
+    server_fs: &Arc<FakeFs>,
+    port_source: Option<Arc<dyn PortSource>>,
+    cx: &mut TestAppContext,
+    server_cx: &mut TestAppContext,
 ) -> (Entity<Project>, Entity<HeadlessProject>) {
     let server_fs = server_fs.clone();
     cx.update(|cx| {
@@ -1470,6 +1986,7 @@ pub async fn init_test(
                 languages,
                 debug_adapters,
                 extension_host_proxy: proxy,
+                port_source,
             },
             cx,
         )
@@ -1,4 +1,5 @@
 mod headless_project;
+pub mod port_watcher;
 
 #[cfg(not(windows))]
 pub mod unix;
@@ -1,5 +1,7 @@
 use crate::HeadlessProject;
 use crate::headless_project::HeadlessAppState;
+#[cfg(target_os = "linux")]
+use crate::port_watcher::{PortSource, ProcPortSource};
 use anyhow::{Context as _, Result, anyhow};
 use chrono::Utc;
 use client::{ProxySettings, telemetry};
@@ -255,6 +257,7 @@ impl ServerListeners {
 fn start_server(
     listeners: ServerListeners,
     log_rx: Receiver<Vec<u8>>,
+    disconnected_tx: mpsc::UnboundedSender<()>,
     cx: &mut App,
 ) -> Arc<ChannelClient> {
     // This is the server idle timeout. If no connection comes in in this timeout, the server will shut down.
@@ -370,6 +373,7 @@ fn start_server(
                     }
                 }
             }
+            disconnected_tx.unbounded_send(()).ok();
         }
         anyhow::Ok(())
     })
@@ -435,7 +439,8 @@ pub fn execute_run(
         HeadlessProject::init(cx);
 
         log::info!("gpui app started, initializing server");
-        let session = start_server(listeners, log_rx, cx);
+        let (disconnected_tx, mut disconnected_rx) = mpsc::unbounded();
+        let session = start_server(listeners, log_rx, disconnected_tx, cx);
 
         client::init_settings(cx);
 
@@ -473,6 +478,10 @@ pub fn execute_run(
             languages.set_language_server_download_dir(paths::languages_dir().clone());
             let languages = Arc::new(languages);
             let debug_adapters = DapRegistry::default().into();
+            #[cfg(target_os = "linux")]
+            let port_source = Some(Arc::new(ProcPortSource) as Arc<dyn PortSource>);
+            #[cfg(not(target_os = "linux"))]
+            let port_source = None;
 
             HeadlessProject::new(
                 HeadlessAppState {
@@ -483,6 +492,7 @@ pub fn execute_run(
                     languages,
                     debug_adapters,
                     extension_host_proxy,
+                    port_source,
                 },
                 cx,
             )
@@ -490,6 +500,17 @@ pub fn execute_run(
 
         handle_panic_requests(&project, &session);
 
+        cx.spawn({
+            let project = project.downgrade();
+            async move |cx| {
+                while let Some(()) = disconnected_rx.next().await {
+                    project.update(cx, |project, cx| project.client_disconnected(cx))?;
+                }
+                anyhow::Ok(())
+            }
+        })
+        .detach_and_log_err(cx);
+
         cx.background_spawn(async move { cleanup_old_binaries() })
             .detach();
 
@@ -0,0 +1,515 @@
+//! A reader for Hunspell dictionaries.
+//!
+//! Only the parts of the affix file format that common dictionaries rely on are
+//! supported: prefixes and suffixes (combined when both allow it), forbidden
+//! words, `NEEDAFFIX`, `NOSUGGEST`, and the `TRY` and `REP` tables used for
+//! suggestions. Compound words are not supported.
+
+use collections::{HashMap, HashSet};
+
+type Flag = u32;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+enum FlagFormat {
+    /// Every character is a flag.
+    #[default]
+    Char,
+    /// Every two characters are a flag.
+    Long,
+    /// Flags are comma-separated numbers.
+    Numeric,
+}
+
+impl FlagFormat {
+    fn parse(self, flags: &str) -> Vec<Flag> {
+        match self {
+            FlagFormat::Char => flags.chars().map(|flag| flag as Flag).collect(),
+            FlagFormat::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|pair| pair.iter().fold(0, |flag, c| (flag << 16) | *c as Flag))
+                .collect(),
+            FlagFormat::Numeric => flags
+                .split(',')
+                .filter_map(|flag| flag.trim().parse().ok())
+                .collect(),
+        }
+    }
+
+    fn parse_one(self, flag: Option<&str>) -> Option<Flag> {
+        self.parse(flag?).first().copied()
+    }
+}
+
+#[derive(Debug)]
+enum ConditionPart {
+    Any,
+    Char(char),
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl ConditionPart {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionPart::Any => true,
+            ConditionPart::Char(expected) => c == *expected,
+            ConditionPart::Set { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+/// The characters a word must start (for prefixes) or end (for suffixes) with
+/// for an affix to apply to it.
+#[derive(Debug)]
+struct Condition(Vec<ConditionPart>);
+
+impl Condition {
+    fn parse(source: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut chars = source.chars();
+        while let Some(c) = chars.next() {
+            parts.push(match c {
+                '.' => ConditionPart::Any,
+                '[' => {
+                    let mut set = Vec::new();
+                    let mut negated = false;
+                    for c in chars.by_ref() {
+                        match c {
+                            ']' => break,
+                            '^' if set.is_empty() && !negated => negated = true,
+                            c => set.push(c),
+                        }
+                    }
+                    ConditionPart::Set {
+                        chars: set,
+                        negated,
+                    }
+                }
+                c => ConditionPart::Char(c),
+            });
+        }
+        Self(parts)
+    }
+
+    fn matches_start(&self, word: &str) -> bool {
+        let mut chars = word.chars();
+        self.0
+            .iter()
+            .all(|part| chars.next().is_some_and(|c| part.matches(c)))
+    }
+
+    fn matches_end(&self, word: &str) -> bool {
+        let mut chars = word.chars().rev();
+        self.0
+            .iter()
+            .rev()
+            .all(|part| chars.next().is_some_and(|c| part.matches(c)))
+    }
+}
+
+#[derive(Debug)]
+struct Affix {
+    flag: Flag,
+    cross_product: bool,
+    strip: String,
+    condition: Condition,
+}
+
+/// A dictionary loaded from a pair of Hunspell `.aff` and `.dic` files.
+pub struct Dictionary {
+    words: HashMap<String, Vec<Vec<Flag>>>,
+    /// Prefix rules, keyed by the text they add to the start of a word.
+    prefixes: HashMap<String, Vec<Affix>>,
+    /// Suffix rules, keyed by the text they add to the end of a word.
+    suffixes: HashMap<String, Vec<Affix>>,
+    forbidden_flag: Option<Flag>,
+    need_affix_flag: Option<Flag>,
+    no_suggest_flag: Option<Flag>,
+    try_chars: Vec<char>,
+    replacements: Vec<(String, String)>,
+}
+
+impl Dictionary {
+    /// Parses a dictionary from the raw contents of its affix and word files,
+    /// decoding them with the encoding named by the affix file's `SET` line.
+    pub fn from_bytes(aff: &[u8], dic: &[u8]) -> Self {
+        let is_latin1 = aff.split(|byte| *byte == b'\n').any(|line| {
+            let line = String::from_utf8_lossy(line);
+            let mut fields = line.split_whitespace();
+            fields.next() == Some("SET")
+                && fields
+                    .next()
+                    .is_some_and(|encoding| encoding.starts_with("ISO8859-1"))
+        });
+        let decode = |bytes: &[u8]| -> String {
+            if is_latin1 {
+                bytes.iter().map(|byte| char::from(*byte)).collect()
+            } else {
+                String::from_utf8_lossy(bytes).into_owned()
+            }
+        };
+        Self::parse(&decode(aff), &decode(dic))
+    }
+
+    pub fn parse(aff: &str, dic: &str) -> Self {
+        let mut flag_format = FlagFormat::default();
+        let mut forbidden_flag = None;
+        let mut need_affix_flag = None;
+        let mut no_suggest_flag = None;
+        let mut try_chars = Vec::new();
+        let mut replacements = Vec::new();
+        let mut prefixes = HashMap::<String, Vec<Affix>>::default();
+        let mut suffixes = HashMap::<String, Vec<Affix>>::default();
+        let mut cross_products = HashMap::<(bool, Flag), bool>::default();
+
+        for line in aff.lines() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("FLAG") => {
+                    flag_format = match fields.next() {
+                        Some("long") => FlagFormat::Long,
+                        Some("num") => FlagFormat::Numeric,
+                        _ => FlagFormat::Char,
+                    }
+                }
+                Some("FORBIDDENWORD") => forbidden_flag = flag_format.parse_one(fields.next()),
+                Some("NEEDAFFIX" | "PSEUDOROOT") => {
+                    need_affix_flag = flag_format.parse_one(fields.next())
+                }
+                Some("NOSUGGEST") => no_suggest_flag = flag_format.parse_one(fields.next()),
+                Some("TRY") => try_chars = fields.next().unwrap_or_default().chars().collect(),
+                Some("REP") => {
+                    // The first `REP` line only holds the number of replacements.
+                    if let (Some(from), Some(to)) = (fields.next(), fields.next()) {
+                        replacements.push((from.replace('_', " "), to.replace('_', " ")));
+                    }
+                }
+                Some(kind @ ("PFX" | "SFX")) => {
+                    let is_prefix = kind == "PFX";
+                    let Some(flag) = flag_format.parse_one(fields.next()) else {
+                        continue;
+                    };
+                    let (Some(strip), Some(append)) = (fields.next(), fields.next()) else {
+                        continue;
+                    };
+                    // Each affix class starts with a header stating whether it
+                    // combines with affixes of the other kind.
+                    let Some(cross_product) = cross_products.get(&(is_prefix, flag)).copied()
+                    else {
+                        cross_products.insert((is_prefix, flag), strip == "Y");
+                        continue;
+                    };
+                    // Flags following the appended text allow further affixes
+                    // on top of this one, which isn't supported.
+                    let append = append.split('/').next().unwrap_or_default();
+                    let affix = Affix {
+                        flag,
+                        cross_product,
+                        strip: if strip == "0" { "" } else { strip }.to_string(),
+                        condition: Condition::parse(fields.next().unwrap_or(".")),
+                    };
+                    let append = if append == "0" { "" } else { append }.to_string();
+                    let affixes = if is_prefix {
+                        &mut prefixes
+                    } else {
+                        &mut suffixes
+                    };
+                    affixes.entry(append).or_default().push(affix);
+                }
+                _ => {}
+            }
+        }
+
+        let mut words = HashMap::<String, Vec<Vec<Flag>>>::default();
+        // The first line holds the approximate number of words.
+        for line in dic.lines().skip(1) {
+            let Some(entry) = line.split_whitespace().next() else {
+                continue;
+            };
+            let (word, flags) = match entry.split_once('/') {
+                Some((word, flags)) if !word.is_empty() => (word, flag_format.parse(flags)),
+                _ => (entry, Vec::new()),
+            };
+            words.entry(word.to_string()).or_default().push(flags);
+        }
+
+        if try_chars.is_empty() {
+            try_chars = ('a'..='z').collect();
+        }
+
+        Self {
+            words,
+            prefixes,
+            suffixes,
+            forbidden_flag,
+            need_affix_flag,
+            no_suggest_flag,
+            try_chars,
+            replacements,
+        }
+    }
+
+    /// Returns whether the word is spelled correctly, allowing words from the
+    /// dictionary to be capitalized or written in all caps.
+    pub fn check(&self, word: &str) -> bool {
+        if self.check_exact(word) {
+            return true;
+        }
+        let mut chars = word.chars();
+        let Some(first) = chars.next() else {
+            return false;
+        };
+        let rest = chars.as_str();
+        if first.is_uppercase() && !rest.chars().any(char::is_uppercase) {
+            return self.check_exact(&word.to_lowercase());
+        }
+        if !word.chars().any(char::is_lowercase) {
+            let lowercase = rest.to_lowercase();
+            return self.check_exact(&format!("{first}{lowercase}"))
+                || self.check_exact(&word.to_lowercase());
+        }
+        false
+    }
+
+    /// Returns up to `limit` correctly spelled words that the given word may
+    /// have been intended as, most likely first.
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let chars = word.chars().collect::<Vec<_>>();
+        let mut candidates = Vec::new();
+
+        for (from, to) in &self.replacements {
+            for (ix, _) in word.match_indices(from.as_str()) {
+                candidates.push(format!("{}{to}{}", &word[..ix], &word[ix + from.len()..]));
+            }
+        }
+        for ix in 1..chars.len() {
+            let mut swapped = chars.clone();
+            swapped.swap(ix - 1, ix);
+            candidates.push(swapped.into_iter().collect());
+        }
+        for ix in 0..chars.len() {
+            let mut removed = chars.clone();
+            removed.remove(ix);
+            candidates.push(removed.into_iter().collect());
+        }
+        for ix in 0..chars.len() {
+            for c in &self.try_chars {
+                if *c != chars[ix] {
+                    let mut replaced = chars.clone();
+                    replaced[ix] = *c;
+                    candidates.push(replaced.into_iter().collect());
+                }
+            }
+        }
+        for ix in 0..=chars.len() {
+            for c in &self.try_chars {
+                let mut inserted = chars.clone();
+                inserted.insert(ix, *c);
+                candidates.push(inserted.into_iter().collect());
+            }
+        }
+
+        let mut seen = HashSet::default();
+        let mut suggestions = candidates
+            .into_iter()
+            .filter(|candidate| candidate != word && seen.insert(candidate.clone()))
+            .filter(|candidate| self.is_suggestible(candidate))
+            .take(limit)
+            .collect::<Vec<_>>();
+
+        if suggestions.len() < limit {
+            for ix in 1..chars.len() {
+                let first = chars[..ix].iter().collect::<String>();
+                let second = chars[ix..].iter().collect::<String>();
+                let split = format!("{first} {second}");
+                if self.is_suggestible(&split) && !suggestions.contains(&split) {
+                    suggestions.push(split);
+                    if suggestions.len() == limit {
+                        break;
+                    }
+                }
+            }
+        }
+
+        suggestions
+    }
+
+    fn is_suggestible(&self, word: &str) -> bool {
+        if word.contains(' ') {
+            return word.split(' ').all(|word| self.is_suggestible(word));
+        }
+        let no_suggest = self.no_suggest_flag.is_some_and(|no_suggest| {
+            self.words
+                .get(word)
+                .is_some_and(|homonyms| homonyms.iter().all(|flags| flags.contains(&no_suggest)))
+        });
+        !no_suggest && self.check(word)
+    }
+
+    fn check_exact(&self, word: &str) -> bool {
+        if let Some(homonyms) = self.words.get(word) {
+            if homonyms
+                .iter()
+                .any(|flags| self.has_flag(flags, self.forbidden_flag))
+            {
+                return false;
+            }
+            if homonyms
+                .iter()
+                .any(|flags| !self.has_flag(flags, self.need_affix_flag))
+            {
+                return true;
+            }
+        }
+        self.check_with_suffix(word, None) || self.check_with_prefix(word)
+    }
+
+    fn has_flag(&self, flags: &[Flag], flag: Option<Flag>) -> bool {
+        flag.is_some_and(|flag| flags.contains(&flag))
+    }
+
+    /// Returns whether `root` is in the dictionary with the affix's flag, and
+    /// with the flag of the prefix that was already removed, if any.
+    fn root_allows(&self, root: &str, affix: &Affix, prefix: Option<&Affix>) -> bool {
+        self.words.get(root).is_some_and(|homonyms| {
+            homonyms.iter().any(|flags| {
+                flags.contains(&affix.flag)
+                    && prefix.is_none_or(|prefix| flags.contains(&prefix.flag))
+                    && !self.has_flag(flags, self.forbidden_flag)
+            })
+        })
+    }
+
+    fn check_with_suffix(&self, word: &str, prefix: Option<&Affix>) -> bool {
+        (1..=word.len())
+            .filter(|ix| word.is_char_boundary(*ix))
+            .any(|ix| {
+                let (stem, append) = word.split_at(ix);
+                self.suffixes.get(append).is_some_and(|suffixes| {
+                    suffixes.iter().any(|suffix| {
+                        if prefix.is_some() && !suffix.cross_product {
+                            return false;
+                        }
+                        let root = format!("{stem}{}", suffix.strip);
+                        suffix.condition.matches_end(&root)
+                            && self.root_allows(&root, suffix, prefix)
+                    })
+                })
+            })
+    }
+
+    fn check_with_prefix(&self, word: &str) -> bool {
+        (0..word.len())
+            .filter(|ix| word.is_char_boundary(*ix))
+            .any(|ix| {
+                let (append, stem) = word.split_at(ix);
+                self.prefixes.get(append).is_some_and(|prefixes| {
+                    prefixes.iter().any(|prefix| {
+                        let root = format!("{}{stem}", prefix.strip);
+                        if !prefix.condition.matches_start(&root) {
+                            return false;
+                        }
+                        self.root_allows(&root, prefix, None)
+                            || (prefix.cross_product && self.check_with_suffix(&root, Some(prefix)))
+                    })
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "
+SET UTF-8
+TRY esianrtolcdugmphbyfvkwzESIANRTOLCDUGMPHBYFVKWZ'
+NOSUGGEST !
+FORBIDDENWORD ?
+
+REP 2
+REP teh the
+REP alot a_lot
+
+PFX U Y 1
+PFX U   0     un         .
+
+SFX S Y 3
+SFX S   y     ies        [^aeiou]y
+SFX S   0     s          [aeiou]y
+SFX S   0     s          [^y]
+
+SFX D Y 2
+SFX D   0     ed         [^ey]
+SFX D   0     d          e
+";
+
+    const DIC: &str = "\
+8
+a
+lot
+the
+fly/S
+key/S
+do/U
+lock/UDS
+darn/!
+irregardless/?
+";
+
+    #[test]
+    fn test_check() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+
+        for word in [
+            "the", "The", "THE", "fly", "flies", "keys", "lock", "locks", "locked", "unlock",
+            "unlocked", "undo",
+        ] {
+            assert!(dictionary.check(word), "{word:?} should be correct");
+        }
+        for word in [
+            "teh",
+            "flys",
+            "keies",
+            "dos",
+            "undos",
+            "unfly",
+            "unflies",
+            "tHe",
+            "irregardless",
+            "",
+        ] {
+            assert!(!dictionary.check(word), "{word:?} should be misspelled");
+        }
+    }
+
+    #[test]
+    fn test_suggest() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+
+        assert_eq!(dictionary.suggest("teh", 3), ["the"]);
+        assert_eq!(dictionary.suggest("alot", 3), ["a lot", "lot"]);
+        assert_eq!(dictionary.suggest("lokc", 3), ["lock"]);
+        assert_eq!(dictionary.suggest("unlokced", 3), ["unlocked"]);
+        assert_eq!(dictionary.suggest("flyes", 3), ["flies"]);
+        // Words marked as `NOSUGGEST` are accepted but never suggested.
+        assert!(dictionary.check("darn"));
+        assert_eq!(dictionary.suggest("dran", 3), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_flag_formats() {
+        let aff = "FLAG long\nSFX Aa Y 1\nSFX Aa 0 s .\n";
+        let dictionary = Dictionary::parse(aff, "1\ncat/AaBb\n");
+        assert!(dictionary.check("cats"));
+
+        let aff = "FLAG num\nSFX 101 Y 1\nSFX 101 0 s .\n";
+        let dictionary = Dictionary::parse(aff, "1\ncat/7,101\n");
+        assert!(dictionary.check("cats"));
+
+        let aff = "SET ISO8859-1\nSFX S Y 1\nSFX S 0 s .\n";
+        let dictionary = Dictionary::from_bytes(aff.as_bytes(), b"1\ncaf\xe9/S\n");
+        assert!(dictionary.check("caf\u{e9}s"));
+    }
+}
@@ -0,0 +1,209 @@
+//! Finding the words to spell check in a buffer.
+
+use language::{BufferSnapshot, Grammar, Language};
+use std::{ops::Range, sync::Arc};
+
+/// Languages whose text is checked in its entirety, rather than only in
+/// comments and strings.
+const PROSE_LANGUAGES: &[&str] = &["Markdown", "Markdown-Inline", "Git Commit", "Plain Text"];
+
+pub(crate) struct Word {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+fn is_prose_language(language: Option<&Arc<Language>>) -> bool {
+    language.is_none_or(|language| PROSE_LANGUAGES.contains(&language.name().as_ref()))
+}
+
+fn is_checked_capture(name: &str) -> bool {
+    name == "comment" || name.starts_with("comment.") || name == "string" || name == "string.doc"
+}
+
+fn is_skipped_capture(name: &str) -> bool {
+    name.starts_with("string.escape")
+        || name.starts_with("string.regex")
+        || name.starts_with("string.special")
+        || name.starts_with("text.literal")
+        || name.starts_with("link_uri")
+}
+
+/// Returns the parts of `range` that contain prose: comments and strings in
+/// code, and everything but code in prose languages.
+pub(crate) fn prose_ranges(buffer: &BufferSnapshot, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut prose = Vec::new();
+    let mut code_blocks = Vec::new();
+    if is_prose_language(buffer.language()) {
+        prose.push(range.clone());
+        // Code embedded in prose is only checked in its comments and strings.
+        for layer in buffer.syntax_layers() {
+            if !is_prose_language(Some(layer.language)) {
+                code_blocks.push(layer.node().byte_range());
+            }
+        }
+    }
+
+    let mut code = Vec::new();
+    let mut skipped = Vec::new();
+    let mut matches = buffer.matches(range.clone(), Grammar::highlights_query);
+    let grammars = matches.grammars().to_vec();
+    while let Some(mat) = matches.peek() {
+        if let Some(query) = grammars[mat.grammar_index].highlights_query() {
+            for capture in mat.captures {
+                let name = query.capture_names()[capture.index as usize];
+                if is_skipped_capture(name) {
+                    skipped.push(capture.node.byte_range());
+                } else if is_checked_capture(name) && !is_prose_language(Some(&mat.language)) {
+                    code.push(capture.node.byte_range());
+                }
+            }
+        }
+        matches.advance();
+    }
+
+    let skipped = merge_ranges(skipped);
+    code_blocks.extend(skipped.iter().cloned());
+    let mut ranges = subtract_ranges(merge_ranges(prose), &merge_ranges(code_blocks));
+    ranges.extend(subtract_ranges(merge_ranges(code), &skipped));
+    merge_ranges(ranges)
+        .into_iter()
+        .map(|prose| prose.start.max(range.start)..prose.end.min(range.end))
+        .filter(|prose| !prose.is_empty())
+        .collect()
+}
+
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_unstable_by_key(|range| (range.start, range.end));
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Removes the sorted, disjoint `removed` ranges from the sorted, disjoint `ranges`.
+fn subtract_ranges(ranges: Vec<Range<usize>>, removed: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut result = Vec::new();
+    for mut range in ranges {
+        for removed in removed {
+            if removed.end <= range.start {
+                continue;
+            }
+            if removed.start >= range.end {
+                break;
+            }
+            if removed.start > range.start {
+                result.push(range.start..removed.start);
+            }
+            range.start = removed.end.min(range.end);
+        }
+        if !range.is_empty() {
+            result.push(range);
+        }
+    }
+    result
+}
+
+/// Returns the words in the prose within `range` that look like they're meant
+/// to be spelled correctly, skipping identifiers, acronyms, URLs and paths.
+pub(crate) fn words(buffer: &BufferSnapshot, range: Range<usize>) -> Vec<Word> {
+    let mut words = Vec::new();
+    for prose in prose_ranges(buffer, range) {
+        let text = buffer.text_for_range(prose.clone()).collect::<String>();
+        let mut offset = prose.start;
+        for segment in text.split_inclusive(char::is_whitespace) {
+            if !is_link_or_path(segment) {
+                words.extend(words_in_segment(segment, offset));
+            }
+            offset += segment.len();
+        }
+    }
+    words
+}
+
+fn is_link_or_path(segment: &str) -> bool {
+    segment.contains("://")
+        || segment.contains(['/', '\\', '@'])
+        || segment.as_bytes().windows(3).any(|window| {
+            window[0].is_ascii_alphanumeric()
+                && window[1] == b'.'
+                && window[2].is_ascii_alphanumeric()
+        })
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+fn words_in_segment(segment: &str, offset: usize) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut chars = segment.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if !c.is_alphanumeric() && c != '_' {
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        while let Some(&(ix, c)) = chars.peek() {
+            let continues_word = c.is_alphanumeric()
+                || c == '_'
+                || (is_apostrophe(c)
+                    && segment[ix + c.len_utf8()..]
+                        .chars()
+                        .next()
+                        .is_some_and(char::is_alphabetic));
+            if !continues_word {
+                break;
+            }
+            end = ix + c.len_utf8();
+            chars.next();
+        }
+
+        let word = &segment[start..end];
+        let is_identifier = word.contains(|c: char| c.is_numeric() || c == '_');
+        let has_inner_capital = word.chars().skip(1).any(char::is_uppercase);
+        if word.chars().count() > 1 && !is_identifier && !has_inner_capital {
+            words.push(Word {
+                range: offset + start..offset + end,
+                text: word.replace('\u{2019}', "'"),
+            });
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word_texts(text: &str) -> Vec<String> {
+        text.split_inclusive(char::is_whitespace)
+            .filter(|segment| !is_link_or_path(segment))
+            .flat_map(|segment| words_in_segment(segment, 0))
+            .map(|word| word.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_words_in_text() {
+        assert_eq!(
+            word_texts("Don\u{2019}t check fooBar, foo_bar, HTTP or x2 (but 'quoted' words)."),
+            ["Don't", "check", "or", "but", "quoted", "words"]
+        );
+        assert_eq!(
+            word_texts("See https://zed.dev, src/main.rs, a@b.com and std::fs for details"),
+            ["See", "and", "std", "fs", "for", "details"]
+        );
+    }
+
+    #[test]
+    fn test_subtract_ranges() {
+        assert_eq!(
+            subtract_ranges(vec![0..10, 20..30], &[2..4, 8..22, 25..26]),
+            vec![0..2, 4..8, 22..25, 26..30]
+        );
+        assert_eq!(merge_ranges(vec![5..7, 0..2, 1..4]), vec![0..4, 5..7]);
+    }
+}
@@ -0,0 +1,1060 @@
+mod dictionary;
+mod prose;
+
+use anyhow::{Context as _, Result, anyhow};
+use collections::{HashMap, HashSet};
+pub use dictionary::Dictionary;
+use editor::{Addon, CodeActionProvider, Editor, EditorEvent, EditorMode, ExcerptId};
+use fs::Fs;
+use gpui::{
+    App, AppContext as _, Context, Entity, Global, HighlightStyle, Subscription, Task,
+    UnderlineStyle, WeakEntity, Window, px,
+};
+use language::{Bias, Buffer, BufferId, BufferSnapshot, Point, ToOffset as _};
+use project::{CodeAction, LspAction, Project, ProjectTransaction};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources, SettingsStore};
+use std::{
+    mem,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+use theme::ActiveTheme as _;
+use util::ResultExt as _;
+
+const CHECK_DEBOUNCE: Duration = Duration::from_millis(250);
+const MAX_SUGGESTIONS: usize = 5;
+/// How many lines are checked below the top of an editor that hasn't been laid out yet.
+const UNLAID_OUT_LINE_COUNT: f32 = 100.;
+const SPELL_CHECK_CODE_ACTION_PROVIDER_ID: &str = "spell_check";
+
+/// The project word list, relative to a worktree's root.
+fn project_words_path() -> PathBuf {
+    paths::local_settings_folder_relative_path().join("words.txt")
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SpellCheckSettings {
+    /// Whether to underline misspelled words in comments, strings and prose files.
+    ///
+    /// Default: false
+    pub enabled: bool,
+    /// The name of the Hunspell dictionary to use, without the `.aff` or `.dic` extension.
+    ///
+    /// Default: en_US
+    pub dictionary: String,
+    /// Additional directories to look for Hunspell dictionaries in.
+    ///
+    /// Default: []
+    pub dictionary_paths: Vec<PathBuf>,
+    /// Words that are never reported as misspelled.
+    ///
+    /// Default: []
+    pub words: Vec<String>,
+}
+
+impl Default for SpellCheckSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dictionary: "en_US".into(),
+            dictionary_paths: Vec::new(),
+            words: Vec::new(),
+        }
+    }
+}
+
+impl Settings for SpellCheckSettings {
+    const KEY: Option<&'static str> = Some("spell_check");
+
+    type FileContent = Self;
+
+    fn load(sources: SettingsSources<Self::FileContent>, _: &mut App) -> Result<Self> {
+        sources.json_merge()
+    }
+}
+
+pub fn init(cx: &mut App) {
+    SpellCheckSettings::register(cx);
+
+    let spell_checker = cx.new(SpellChecker::new);
+    cx.set_global(GlobalSpellChecker(spell_checker.clone()));
+
+    cx.observe_new(move |editor: &mut Editor, window, cx| {
+        let Some(window) = window else {
+            return;
+        };
+        if matches!(editor.mode(), EditorMode::SingleLine { .. }) {
+            return;
+        }
+        let project = editor.project.clone();
+        let editor_handle = cx.entity();
+        let editor_spell_check = cx.new(|cx| {
+            EditorSpellCheck::new(editor_handle, project.clone(), spell_checker.clone(), cx)
+        });
+        editor.register_addon(SpellCheckAddon {
+            _editor_spell_check: editor_spell_check,
+        });
+        editor.add_code_action_provider(
+            Rc::new(SpellCheckCodeActionProvider {
+                project: project.map(|project| project.downgrade()),
+            }),
+            window,
+            cx,
+        );
+    })
+    .detach();
+}
+
+/// Returns the directories to look for dictionaries in, in order of preference.
+fn dictionary_directories(settings: &SpellCheckSettings) -> Vec<PathBuf> {
+    let mut directories = settings.dictionary_paths.clone();
+    directories.push(paths::config_dir().join("dictionaries"));
+    if let Some(dicpath) = std::env::var_os("DICPATH") {
+        directories.extend(std::env::split_paths(&dicpath));
+    }
+    if cfg!(target_os = "macos") {
+        directories.push(paths::home_dir().join("Library/Spelling"));
+        directories.push(PathBuf::from("/Library/Spelling"));
+    } else if cfg!(any(target_os = "linux", target_os = "freebsd")) {
+        directories.extend(
+            [
+                "/usr/share/hunspell",
+                "/usr/share/myspell",
+                "/usr/share/myspell/dicts",
+                "/usr/local/share/hunspell",
+            ]
+            .map(PathBuf::from),
+        );
+    }
+    directories
+}
+
+async fn load_dictionary(
+    fs: Arc<dyn Fs>,
+    name: String,
+    directories: Vec<PathBuf>,
+    cx: &gpui::AsyncApp,
+) -> Result<Dictionary> {
+    for directory in &directories {
+        let aff_path = directory.join(format!("{name}.aff"));
+        let dic_path = directory.join(format!("{name}.dic"));
+        if !fs.is_file(&aff_path).await || !fs.is_file(&dic_path).await {
+            continue;
+        }
+        let aff = fs.load_bytes(&aff_path).await?;
+        let dic = fs.load_bytes(&dic_path).await?;
+        log::info!("loading spell check dictionary from {dic_path:?}");
+        return Ok(cx
+            .background_spawn(async move { Dictionary::from_bytes(&aff, &dic) })
+            .await);
+    }
+    Err(anyhow!(
+        "no Hunspell dictionary named {name:?} found in {directories:?}"
+    ))
+}
+
+fn parse_word_list(content: &str) -> HashSet<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|word| !word.is_empty() && !word.starts_with('#'))
+        .map(ToString::to_string)
+        .collect()
+}
+
+struct GlobalSpellChecker(Entity<SpellChecker>);
+
+impl Global for GlobalSpellChecker {}
+
+/// Holds the dictionary and word lists shared by every editor.
+pub struct SpellChecker {
+    dictionary: Option<Arc<Dictionary>>,
+    /// The dictionary name and search directories that the dictionary was loaded for.
+    dictionary_source: Option<(String, Vec<PathBuf>)>,
+    load_dictionary: Option<Task<()>>,
+    enabled: bool,
+    settings_words: Arc<HashSet<String>>,
+    /// The project word lists, keyed by the path of their worktree's root.
+    project_words: HashMap<Arc<Path>, ProjectWords>,
+    _settings_subscription: Subscription,
+}
+
+enum ProjectWords {
+    Loading { _task: Task<()> },
+    Loaded(Arc<HashSet<String>>),
+}
+
+impl SpellChecker {
+    pub fn global(cx: &App) -> Option<Entity<Self>> {
+        cx.try_global::<GlobalSpellChecker>()
+            .map(|spell_checker| spell_checker.0.clone())
+    }
+
+    fn new(cx: &mut Context<Self>) -> Self {
+        let mut this = Self {
+            dictionary: None,
+            dictionary_source: None,
+            load_dictionary: None,
+            enabled: false,
+            settings_words: Arc::default(),
+            project_words: HashMap::default(),
+            _settings_subscription: cx
+                .observe_global::<SettingsStore>(|this, cx| this.settings_changed(cx)),
+        };
+        this.settings_changed(cx);
+        this
+    }
+
+    fn settings_changed(&mut self, cx: &mut Context<Self>) {
+        let settings = SpellCheckSettings::get_global(cx).clone();
+        let settings_words = settings.words.iter().cloned().collect::<HashSet<_>>();
+        if *self.settings_words != settings_words || self.enabled != settings.enabled {
+            self.settings_words = Arc::new(settings_words);
+            self.enabled = settings.enabled;
+            cx.notify();
+        }
+
+        // Dictionaries are large, so they're only loaded once spell checking is enabled.
+        if !settings.enabled {
+            return;
+        }
+        let directories = dictionary_directories(&settings);
+        let source = (settings.dictionary, directories);
+        if self.dictionary_source.as_ref() == Some(&source) {
+            return;
+        }
+        self.dictionary_source = Some(source.clone());
+        let fs = <dyn Fs>::global(cx);
+        self.load_dictionary = Some(cx.spawn(async move |this, cx| {
+            let (name, directories) = source;
+            let dictionary = load_dictionary(fs, name, directories, cx).await.log_err();
+            this.update(cx, |this, cx| {
+                this.dictionary = dictionary.map(Arc::new);
+                cx.notify();
+            })
+            .ok();
+        }));
+    }
+
+    /// Returns what counts as a correctly spelled word in the given buffer, or
+    /// `None` if spell checking is disabled or no dictionary is loaded yet.
+    fn known_words(
+        &mut self,
+        buffer: &Entity<Buffer>,
+        project: Option<&Entity<Project>>,
+        cx: &mut Context<Self>,
+    ) -> Option<KnownWords> {
+        let buffer = buffer.read(cx);
+        if !self.enabled || buffer.large_file_mode() {
+            return None;
+        }
+        let dictionary = self.dictionary.clone()?;
+        let project_words = local_worktree_root(buffer, project, cx)
+            .and_then(|worktree_root| self.project_words(worktree_root, cx));
+        Some(KnownWords {
+            dictionary,
+            settings_words: self.settings_words.clone(),
+            project_words,
+        })
+    }
+
+    /// Returns the project word list of the worktree with the given root,
+    /// starting to load it if needed.
+    fn project_words(
+        &mut self,
+        worktree_root: Arc<Path>,
+        cx: &mut Context<Self>,
+    ) -> Option<Arc<HashSet<String>>> {
+        match self.project_words.get(&worktree_root) {
+            Some(ProjectWords::Loaded(words)) => return Some(words.clone()),
+            Some(ProjectWords::Loading { .. }) => return None,
+            None => {}
+        }
+
+        let fs = <dyn Fs>::global(cx);
+        let path = worktree_root.join(project_words_path());
+        let load = cx.spawn({
+            let worktree_root = worktree_root.clone();
+            async move |this, cx| {
+                let words = if fs.is_file(&path).await {
+                    fs.load(&path)
+                        .await
+                        .log_err()
+                        .map(|content| parse_word_list(&content))
+                        .unwrap_or_default()
+                } else {
+                    HashSet::default()
+                };
+                this.update(cx, |this, cx| {
+                    this.project_words
+                        .insert(worktree_root, ProjectWords::Loaded(Arc::new(words)));
+                    cx.notify();
+                })
+                .ok();
+            }
+        });
+        self.project_words
+            .insert(worktree_root, ProjectWords::Loading { _task: load });
+        None
+    }
+
+    fn invalidate_project_words(&mut self, worktree_root: &Path, cx: &mut Context<Self>) {
+        if self.project_words.remove(worktree_root).is_some() {
+            cx.notify();
+        }
+    }
+
+    /// Appends a word to the project word list of the worktree with the given root.
+    fn add_project_word(
+        &mut self,
+        worktree_root: Arc<Path>,
+        word: String,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let fs = <dyn Fs>::global(cx);
+        let path = worktree_root.join(project_words_path());
+        cx.spawn(async move |this, cx| {
+            let mut content = if fs.is_file(&path).await {
+                fs.load(&path).await?
+            } else {
+                String::new()
+            };
+            if !content.is_empty() && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(&word);
+            content.push('\n');
+            if let Some(parent) = path.parent() {
+                fs.create_dir(parent).await?;
+            }
+            fs.atomic_write(path, content).await?;
+            this.update(cx, |this, cx| {
+                this.invalidate_project_words(&worktree_root, cx)
+            })
+        })
+    }
+}
+
+/// Returns the root of the worktree containing the buffer's file, if the
+/// project is local and project word lists can be used.
+fn local_worktree_root(
+    buffer: &Buffer,
+    project: Option<&Entity<Project>>,
+    cx: &App,
+) -> Option<Arc<Path>> {
+    let project = project?.read(cx);
+    if !project.is_local() {
+        return None;
+    }
+    let worktree = project.worktree_for_id(buffer.file()?.worktree_id(cx), cx)?;
+    Some(worktree.read(cx).abs_path())
+}
+
+#[derive(Clone)]
+struct KnownWords {
+    dictionary: Arc<Dictionary>,
+    settings_words: Arc<HashSet<String>>,
+    project_words: Option<Arc<HashSet<String>>>,
+}
+
+impl KnownWords {
+    fn contains(&self, word: &str) -> bool {
+        let listed = |word: &str| {
+            self.settings_words.contains(word)
+                || self
+                    .project_words
+                    .as_ref()
+                    .is_some_and(|words| words.contains(word))
+        };
+        listed(word) || listed(&word.to_lowercase()) || self.dictionary.check(word)
+    }
+}
+
+fn misspelling_style(cx: &App) -> HighlightStyle {
+    HighlightStyle {
+        underline: Some(UnderlineStyle {
+            thickness: px(1.),
+            color: Some(cx.theme().status().info),
+            wavy: true,
+        }),
+        ..Default::default()
+    }
+}
+
+/// Returns the misspelled words in the given range of the buffer.
+fn find_misspellings(
+    buffer: &BufferSnapshot,
+    range: Range<usize>,
+    known_words: &KnownWords,
+) -> Vec<Range<usize>> {
+    prose::words(buffer, range)
+        .into_iter()
+        .filter(|word| !known_words.contains(&word.text))
+        .map(|word| word.range)
+        .collect()
+}
+
+/// Extends the range to the start and end of the lines it touches, so that it
+/// doesn't split any words.
+fn line_range(buffer: &BufferSnapshot, range: Range<usize>) -> Range<usize> {
+    let start = buffer.offset_to_point(range.start);
+    let end = buffer.offset_to_point(range.end);
+    Point::new(start.row, 0).to_offset(buffer)
+        ..Point::new(end.row, buffer.line_len(end.row)).to_offset(buffer)
+}
+
+/// Sorts the ranges and merges the ones that overlap or touch.
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_unstable_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Returns the parts of `ranges` that aren't covered by `removed`, which must
+/// be sorted and disjoint.
+fn subtract_ranges(ranges: &[Range<usize>], removed: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut result = Vec::new();
+    for range in ranges {
+        let mut start = range.start;
+        for removed in removed {
+            if removed.end <= start || removed.start >= range.end {
+                continue;
+            }
+            if removed.start > start {
+                result.push(start..removed.start);
+            }
+            start = start.max(removed.end);
+        }
+        if start < range.end {
+            result.push(start..range.end);
+        }
+    }
+    result
+}
+
+/// The misspellings found in a buffer, along with the parts of it that were
+/// checked. Results are kept for text that isn't edited.
+struct BufferMisspellings {
+    version: clock::Global,
+    checked: Vec<Range<text::Anchor>>,
+    misspellings: Vec<Range<text::Anchor>>,
+}
+
+impl BufferMisspellings {
+    fn new(buffer: &BufferSnapshot) -> Self {
+        Self {
+            version: buffer.version().clone(),
+            checked: Vec::new(),
+            misspellings: Vec::new(),
+        }
+    }
+
+    fn checked_ranges(&self, buffer: &BufferSnapshot) -> Vec<Range<usize>> {
+        merge_ranges(
+            self.checked
+                .iter()
+                .map(|range| range.start.to_offset(buffer)..range.end.to_offset(buffer))
+                .collect(),
+        )
+    }
+
+    fn set_checked_ranges(&mut self, ranges: Vec<Range<usize>>, buffer: &BufferSnapshot) {
+        self.checked = ranges
+            .into_iter()
+            .map(|range| buffer.anchor_before(range.start)..buffer.anchor_after(range.end))
+            .collect();
+    }
+
+    /// Forgets the results for the lines edited since the last check, and
+    /// returns those lines.
+    fn invalidate_edits(&mut self, buffer: &BufferSnapshot) -> Vec<Range<usize>> {
+        let edited_lines = merge_ranges(
+            buffer
+                .edits_since::<usize>(&self.version)
+                .map(|edit| line_range(buffer, edit.new))
+                .collect(),
+        );
+        self.version = buffer.version().clone();
+        self.invalidate(&edited_lines, buffer);
+        edited_lines
+    }
+
+    /// Forgets the results for the given ranges, which must be sorted and disjoint.
+    fn invalidate(&mut self, ranges: &[Range<usize>], buffer: &BufferSnapshot) {
+        if ranges.is_empty() {
+            return;
+        }
+        let checked = subtract_ranges(&self.checked_ranges(buffer), ranges);
+        self.set_checked_ranges(checked, buffer);
+        self.misspellings.retain(|misspelling| {
+            let start = misspelling.start.to_offset(buffer);
+            let end = misspelling.end.to_offset(buffer);
+            !ranges
+                .iter()
+                .any(|range| start <= range.end && range.start <= end)
+        });
+    }
+
+    /// Returns the parts of the range that haven't been checked yet.
+    fn unchecked(&self, range: Range<usize>, buffer: &BufferSnapshot) -> Vec<Range<usize>> {
+        subtract_ranges(&[range], &self.checked_ranges(buffer))
+    }
+
+    /// Records the misspellings found by checking the given ranges.
+    fn insert(
+        &mut self,
+        checked: &[Range<usize>],
+        misspellings: Vec<Range<usize>>,
+        buffer: &BufferSnapshot,
+    ) {
+        let mut checked_ranges = self.checked_ranges(buffer);
+        checked_ranges.extend(checked.iter().cloned());
+        self.set_checked_ranges(merge_ranges(checked_ranges), buffer);
+        self.misspellings.extend(
+            misspellings
+                .into_iter()
+                .map(|range| buffer.anchor_after(range.start)..buffer.anchor_before(range.end)),
+        );
+        self.misspellings
+            .sort_by(|a, b| a.start.cmp(&b.start, buffer));
+    }
+}
+
+/// The parts of a buffer to check for misspellings.
+struct BufferCheck {
+    buffer_id: BufferId,
+    snapshot: BufferSnapshot,
+    ranges: Vec<Range<usize>>,
+    known_words: KnownWords,
+}
+
+enum MisspellingHighlight {}
+
+/// Keeps an editor's misspelling highlights up to date.
+///
+/// Only the lines that were edited and the ones around the viewport are
+/// checked, and the results for other lines are kept until they're edited.
+struct EditorSpellCheck {
+    editor: WeakEntity<Editor>,
+    project: Option<Entity<Project>>,
+    spell_checker: Entity<SpellChecker>,
+    buffers: HashMap<BufferId, BufferMisspellings>,
+    /// Buffers whose syntax changed, so their visible lines are checked again.
+    reparsed_buffers: HashSet<BufferId>,
+    check_task: Task<Result<()>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl EditorSpellCheck {
+    fn new(
+        editor: Entity<Editor>,
+        project: Option<Entity<Project>>,
+        spell_checker: Entity<SpellChecker>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let mut subscriptions = vec![
+            cx.subscribe(&editor, |this, _, event: &EditorEvent, cx| match event {
+                EditorEvent::Reparsed(buffer_id) => {
+                    this.reparsed_buffers.insert(*buffer_id);
+                    this.schedule_check(true, cx);
+                }
+                EditorEvent::BufferEdited
+                | EditorEvent::ScrollPositionChanged { .. }
+                | EditorEvent::ExcerptsAdded { .. }
+                | EditorEvent::ExcerptsEdited { .. }
+                | EditorEvent::ExcerptsExpanded { .. }
+                | EditorEvent::ExcerptsRemoved { .. } => this.schedule_check(true, cx),
+                _ => {}
+            }),
+            cx.observe(&spell_checker, |this, _, cx| {
+                // The known words changed, so every result is stale.
+                this.buffers.clear();
+                this.schedule_check(false, cx);
+            }),
+        ];
+        if let Some(project) = project.as_ref() {
+            subscriptions.push(cx.subscribe(
+                project,
+                |this, project, event: &project::Event, cx| {
+                    let project::Event::WorktreeUpdatedEntries(worktree_id, changes) = event else {
+                        return;
+                    };
+                    let words_path = project_words_path();
+                    if !changes.iter().any(|(path, _, _)| **path == *words_path) {
+                        return;
+                    }
+                    if let Some(worktree) = project.read(cx).worktree_for_id(*worktree_id, cx) {
+                        let worktree_root = worktree.read(cx).abs_path();
+                        this.spell_checker.update(cx, |spell_checker, cx| {
+                            spell_checker.invalidate_project_words(&worktree_root, cx)
+                        });
+                    }
+                },
+            ));
+        }
+
+        let mut this = Self {
+            editor: editor.downgrade(),
+            project,
+            spell_checker,
+            buffers: HashMap::default(),
+            reparsed_buffers: HashSet::default(),
+            check_task: Task::ready(Ok(())),
+            _subscriptions: subscriptions,
+        };
+        this.schedule_check(false, cx);
+        this
+    }
+
+    fn schedule_check(&mut self, debounce: bool, cx: &mut Context<Self>) {
+        self.check_task = cx.spawn(async move |this, cx| {
+            if debounce {
+                cx.background_executor().timer(CHECK_DEBOUNCE).await;
+            }
+            let checks = this.update(cx, |this, cx| this.ranges_to_check(cx))??;
+            let results = cx
+                .background_spawn(async move {
+                    checks
+                        .into_iter()
+                        .map(|check| {
+                            let misspellings = check
+                                .ranges
+                                .iter()
+                                .flat_map(|range| {
+                                    find_misspellings(
+                                        &check.snapshot,
+                                        range.clone(),
+                                        &check.known_words,
+                                    )
+                                })
+                                .collect::<Vec<_>>();
+                            (check, misspellings)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await;
+            this.update(cx, |this, cx| {
+                for (check, misspellings) in results {
+                    if let Some(buffer) = this.buffers.get_mut(&check.buffer_id) {
+                        buffer.insert(&check.ranges, misspellings, &check.snapshot);
+                    }
+                }
+                this.update_highlights(cx)
+            })?
+        });
+    }
+
+    /// Forgets the results for edited lines, and returns the lines that need to
+    /// be checked: the edited ones, and the unchecked ones around the viewport.
+    fn ranges_to_check(&mut self, cx: &mut Context<Self>) -> Result<Vec<BufferCheck>> {
+        let editor = self.editor.upgrade().context("editor was released")?;
+        let line_count = editor
+            .read(cx)
+            .visible_line_count()
+            .unwrap_or(UNLAID_OUT_LINE_COUNT);
+        let visible_range = editor.update(cx, |editor, cx| {
+            editor.buffer_range_from_scroll_top(line_count, cx)
+        });
+        let multibuffer = editor.read(cx).buffer().clone();
+        let snapshot = multibuffer.read(cx).snapshot(cx);
+
+        // Check the lines around the viewport too, so that scrolling a little
+        // doesn't require another check.
+        let margin = line_count.ceil() as u32;
+        let checked_range = Point::new(visible_range.start.row.saturating_sub(margin), 0)
+            ..snapshot.clip_point(Point::new(visible_range.end.row + margin, 0), Bias::Left);
+        let mut visible_ranges = HashMap::<BufferId, Vec<Range<usize>>>::default();
+        for (buffer, range, _) in snapshot.range_to_buffer_ranges(checked_range) {
+            visible_ranges
+                .entry(buffer.remote_id())
+                .or_default()
+                .push(line_range(buffer, range));
+        }
+
+        let reparsed_buffers = mem::take(&mut self.reparsed_buffers);
+        let buffers = multibuffer.read(cx).all_buffers();
+        let buffer_ids = buffers
+            .iter()
+            .map(|buffer| buffer.read(cx).remote_id())
+            .collect::<HashSet<_>>();
+        self.buffers
+            .retain(|buffer_id, _| buffer_ids.contains(buffer_id));
+
+        let mut checks = Vec::new();
+        for buffer in buffers {
+            let buffer_snapshot = buffer.read(cx).snapshot();
+            let buffer_id = buffer_snapshot.remote_id();
+            let known_words = self.spell_checker.update(cx, |spell_checker, cx| {
+                spell_checker.known_words(&buffer, self.project.as_ref(), cx)
+            });
+            let Some(known_words) = known_words else {
+                self.buffers.remove(&buffer_id);
+                continue;
+            };
+
+            let misspellings = self
+                .buffers
+                .entry(buffer_id)
+                .or_insert_with(|| BufferMisspellings::new(&buffer_snapshot));
+            let mut ranges = misspellings.invalidate_edits(&buffer_snapshot);
+            let visible_ranges =
+                merge_ranges(visible_ranges.remove(&buffer_id).unwrap_or_default());
+            if reparsed_buffers.contains(&buffer_id) {
+                misspellings.invalidate(&visible_ranges, &buffer_snapshot);
+            }
+            for range in visible_ranges {
+                ranges.extend(misspellings.unchecked(range, &buffer_snapshot));
+            }
+            let ranges = merge_ranges(ranges);
+            if !ranges.is_empty() {
+                checks.push(BufferCheck {
+                    buffer_id,
+                    snapshot: buffer_snapshot,
+                    ranges,
+                    known_words,
+                });
+            }
+        }
+        Ok(checks)
+    }
+
+    fn update_highlights(&self, cx: &mut Context<Self>) -> Result<()> {
+        let editor = self.editor.upgrade().context("editor was released")?;
+        let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+        let mut highlights = Vec::new();
+        for (excerpt_id, buffer, excerpt_range) in snapshot.excerpts() {
+            let Some(misspellings) = self.buffers.get(&buffer.remote_id()) else {
+                continue;
+            };
+            let context = &excerpt_range.context;
+            for misspelling in &misspellings.misspellings {
+                if misspelling.start.cmp(&context.start, buffer).is_lt()
+                    || misspelling.end.cmp(&context.end, buffer).is_gt()
+                {
+                    continue;
+                }
+                if let Some((start, end)) = snapshot
+                    .anchor_in_excerpt(excerpt_id, misspelling.start)
+                    .zip(snapshot.anchor_in_excerpt(excerpt_id, misspelling.end))
+                {
+                    highlights.push(start..end);
+                }
+            }
+        }
+
+        editor.update(cx, |editor, cx| {
+            if highlights.is_empty() {
+                editor.clear_highlights::<MisspellingHighlight>(cx);
+            } else {
+                let style = misspelling_style(cx);
+                editor.highlight_text::<MisspellingHighlight>(highlights, style, cx);
+            }
+        });
+        Ok(())
+    }
+}
+
+struct SpellCheckAddon {
+    _editor_spell_check: Entity<EditorSpellCheck>,
+}
+
+impl Addon for SpellCheckAddon {
+    fn to_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// What a spell check code action does, stored in the action's data.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum SpellCheckAction {
+    Replace { replacement: String },
+    AddToProjectWords { word: String },
+}
+
+struct SpellCheckCodeActionProvider {
+    project: Option<WeakEntity<Project>>,
+}
+
+impl CodeActionProvider for SpellCheckCodeActionProvider {
+    fn id(&self) -> Arc<str> {
+        SPELL_CHECK_CODE_ACTION_PROVIDER_ID.into()
+    }
+
+    fn code_actions(
+        &self,
+        buffer: &Entity<Buffer>,
+        range: Range<text::Anchor>,
+        _: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Vec<CodeAction>>> {
+        let project = self.project.as_ref().and_then(WeakEntity::upgrade);
+        let Some(spell_checker) = SpellChecker::global(cx) else {
+            return Task::ready(Ok(Vec::new()));
+        };
+        let Some(known_words) = spell_checker.update(cx, |spell_checker, cx| {
+            spell_checker.known_words(buffer, project.as_ref(), cx)
+        }) else {
+            return Task::ready(Ok(Vec::new()));
+        };
+        let buffer = buffer.read(cx);
+        let can_add_words = local_worktree_root(buffer, project.as_ref(), cx).is_some();
+        let snapshot = buffer.snapshot();
+
+        cx.background_spawn(async move {
+            let range = range.start.to_offset(&snapshot)..range.end.to_offset(&snapshot);
+            let start_row = snapshot.offset_to_point(range.start).row;
+            let end_row = snapshot.offset_to_point(range.end).row;
+            let lines = Point::new(start_row, 0).to_offset(&snapshot)
+                ..Point::new(end_row, snapshot.line_len(end_row)).to_offset(&snapshot);
+            let Some(word) = prose::words(&snapshot, lines).into_iter().find(|word| {
+                word.range.start <= range.end
+                    && range.start <= word.range.end
+                    && !known_words.contains(&word.text)
+            }) else {
+                return Ok(Vec::new());
+            };
+
+            let word_range =
+                snapshot.anchor_before(word.range.start)..snapshot.anchor_after(word.range.end);
+            let code_action = |title: String, action: SpellCheckAction| -> Result<CodeAction> {
+                Ok(CodeAction {
+                    server_id: language::LanguageServerId(0),
+                    range: word_range.clone(),
+                    lsp_action: LspAction::Action(Box::new(lsp::CodeAction {
+                        title,
+                        data: Some(serde_json::to_value(action)?),
+                        ..Default::default()
+                    })),
+                    resolved: true,
+                })
+            };
+            let mut actions = known_words
+                .dictionary
+                .suggest(&word.text, MAX_SUGGESTIONS)
+                .into_iter()
+                .map(|replacement| {
+                    code_action(
+                        format!("Change to \"{replacement}\""),
+                        SpellCheckAction::Replace { replacement },
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if can_add_words {
+                actions.push(code_action(
+                    format!("Add \"{}\" to project words", word.text),
+                    SpellCheckAction::AddToProjectWords { word: word.text },
+                )?);
+            }
+            Ok(actions)
+        })
+    }
+
+    fn apply_code_action(
+        &self,
+        buffer: Entity<Buffer>,
+        action: CodeAction,
+        _excerpt_id: ExcerptId,
+        push_to_history: bool,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<ProjectTransaction>> {
+        let LspAction::Action(lsp_action) = &action.lsp_action else {
+            return Task::ready(Ok(ProjectTransaction::default()));
+        };
+        let spell_check_action = match lsp_action
+            .data
+            .clone()
+            .context("missing code action data")
+            .and_then(|data| Ok(serde_json::from_value::<SpellCheckAction>(data)?))
+        {
+            Ok(spell_check_action) => spell_check_action,
+            Err(error) => return Task::ready(Err(error)),
+        };
+
+        match spell_check_action {
+            SpellCheckAction::Replace { replacement } => {
+                let mut project_transaction = ProjectTransaction::default();
+                buffer.update(cx, |buffer, cx| {
+                    buffer.finalize_last_transaction();
+                    buffer.start_transaction();
+                    buffer.edit([(action.range.clone(), replacement)], None, cx);
+                    if buffer.end_transaction(cx).is_some() {
+                        let transaction = buffer.finalize_last_transaction().unwrap().clone();
+                        if !push_to_history {
+                            buffer.forget_transaction(transaction.id);
+                        }
+                        project_transaction.0.insert(cx.entity(), transaction);
+                    }
+                });
+                Task::ready(Ok(project_transaction))
+            }
+            SpellCheckAction::AddToProjectWords { word } => {
+                let project = self.project.as_ref().and_then(WeakEntity::upgrade);
+                let Some(worktree_root) =
+                    local_worktree_root(buffer.read(cx), project.as_ref(), cx)
+                else {
+                    return Task::ready(Err(anyhow!("buffer is not in a local worktree")));
+                };
+                let Some(spell_checker) = SpellChecker::global(cx) else {
+                    return Task::ready(Ok(ProjectTransaction::default()));
+                };
+                let add_word = spell_checker.update(cx, |spell_checker, cx| {
+                    spell_checker.add_project_word(worktree_root, word, cx)
+                });
+                cx.background_spawn(async move {
+                    add_word.await?;
+                    Ok(ProjectTransaction::default())
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use language::{Language, LanguageConfig, LanguageMatcher};
+
+    fn known_words() -> KnownWords {
+        KnownWords {
+            dictionary: Arc::new(Dictionary::parse(
+                "SFX S Y 1\nSFX S 0 s .\n",
+                "6\nthe/S\nanswer/S\nis\nhere\nwrong\nfile\n",
+            )),
+            settings_words: Arc::new(HashSet::from_iter(["zed".to_string()])),
+            project_words: None,
+        }
+    }
+
+    fn rust_lang() -> Arc<Language> {
+        Arc::new(
+            Language::new(
+                LanguageConfig {
+                    name: "Rust".into(),
+                    matcher: LanguageMatcher {
+                        path_suffixes: vec!["rs".to_string()],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                Some(tree_sitter_rust::LANGUAGE.into()),
+            )
+            .with_highlights_query(
+                r#"
+                (line_comment) @comment
+                (string_literal) @string
+                (escape_sequence) @string.escape
+                "#,
+            )
+            .unwrap(),
+        )
+    }
+
+    fn misspellings(buffer: &Entity<Buffer>, cx: &mut TestAppContext) -> Vec<String> {
+        buffer.read_with(cx, |buffer, _| {
+            let snapshot = buffer.snapshot();
+            find_misspellings(&snapshot, 0..snapshot.len(), &known_words())
+                .into_iter()
+                .map(|range| snapshot.text_for_range(range).collect())
+                .collect()
+        })
+    }
+
+    fn texts(ranges: &[Range<text::Anchor>], buffer: &BufferSnapshot) -> Vec<String> {
+        ranges
+            .iter()
+            .map(|range| buffer.text_for_range(range.clone()).collect())
+            .collect()
+    }
+
+    #[gpui::test]
+    async fn test_misspellings_in_code(cx: &mut TestAppContext) {
+        let text = "// teh answr is here\nfn wrnog() { \"wrong fiel\\nzed Zed\" }\n";
+        let buffer = cx.new(|cx| Buffer::local(text, cx).with_language(rust_lang(), cx));
+        cx.run_until_parked();
+
+        // Identifiers aren't checked, and escape sequences don't run into the following word.
+        assert_eq!(misspellings(&buffer, cx), ["teh", "answr", "fiel"]);
+    }
+
+    #[gpui::test]
+    async fn test_misspellings_in_prose(cx: &mut TestAppContext) {
+        let buffer = cx.new(|cx| Buffer::local("Teh answers is wrnog. Zed zed", cx));
+
+        assert_eq!(misspellings(&buffer, cx), ["Teh", "wrnog"]);
+    }
+
+    #[gpui::test]
+    async fn test_keeping_results_for_unedited_lines(cx: &mut TestAppContext) {
+        let buffer = cx.new(|cx| Buffer::local("teh answer\nis wrnog\nhere fiel\n", cx));
+        let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+        let known_words = known_words();
+
+        // Only the unchecked part of a range is checked.
+        let mut misspellings = BufferMisspellings::new(&snapshot);
+        let first_line = line_range(&snapshot, 0..0);
+        misspellings.insert(
+            &[first_line.clone()],
+            find_misspellings(&snapshot, first_line.clone(), &known_words),
+            &snapshot,
+        );
+        assert_eq!(texts(&misspellings.misspellings, &snapshot), ["teh"]);
+        let unchecked = misspellings.unchecked(0..snapshot.len(), &snapshot);
+        assert_eq!(unchecked, [first_line.end..snapshot.len()]);
+        for range in &unchecked {
+            let found = find_misspellings(&snapshot, range.clone(), &known_words);
+            misspellings.insert(&[range.clone()], found, &snapshot);
+        }
+        assert_eq!(
+            texts(&misspellings.misspellings, &snapshot),
+            ["teh", "wrnog", "fiel"]
+        );
+        assert!(
+            misspellings
+                .unchecked(0..snapshot.len(), &snapshot)
+                .is_empty()
+        );
+
+        // Editing a line only invalidates the results for that line.
+        buffer.update(cx, |buffer, cx| buffer.edit([(14..19, "wrong")], None, cx));
+        let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+        let edited_lines = misspellings.invalidate_edits(&snapshot);
+        assert_eq!(edited_lines, [11..19]);
+        assert_eq!(
+            texts(&misspellings.misspellings, &snapshot),
+            ["teh", "fiel"]
+        );
+        assert_eq!(
+            misspellings.unchecked(0..snapshot.len(), &snapshot),
+            [11..19]
+        );
+
+        let found = find_misspellings(&snapshot, 11..19, &known_words);
+        assert!(found.is_empty());
+        misspellings.insert(&edited_lines, found, &snapshot);
+        assert_eq!(
+            texts(&misspellings.misspellings, &snapshot),
+            ["teh", "fiel"]
+        );
+        assert!(
+            misspellings
+                .unchecked(0..snapshot.len(), &snapshot)
+                .is_empty()
+        );
+        assert!(misspellings.invalidate_edits(&snapshot).is_empty());
+    }
+}
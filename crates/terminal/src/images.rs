@@ -0,0 +1,1014 @@
+//! Inline images, sent by programs with the kitty graphics protocol, iTerm2's
+//! `OSC 1337 ; File=` sequence or sixel.
+//!
+//! Alacritty doesn't know about any of these, so they're filtered out of the
+//! PTY output before it's parsed, and replaced with rows of placeholder cells
+//! that reserve the image's space on the grid. Like kitty's Unicode
+//! placeholders, each placeholder cell encodes the image id in its foreground
+//! color and its row within the image in its underline color, which lets the
+//! image scroll with the text and be evicted along with the scrollback.
+
+use std::{
+    fmt::Write as _,
+    sync::{Arc, Mutex},
+};
+
+use alacritty_terminal::{
+    term::cell::Cell,
+    vte::ansi::{Color, Rgb},
+};
+use base64::{
+    Engine as _, alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+use collections::HashMap;
+use gpui::RenderImage;
+use image::{DynamicImage, Frame, RgbImage, RgbaImage};
+
+/// The character printed in the cells covered by an image.
+pub const IMAGE_PLACEHOLDER: char = '\u{10EEEE}';
+
+/// The largest escape sequence we're willing to buffer for an image.
+const MAX_SEQUENCE_LEN: usize = 64 * 1024 * 1024;
+/// The largest width or height of a decoded image, in pixels.
+const MAX_IMAGE_DIMENSION: u32 = 10_000;
+/// The largest number of images transmitted with the kitty protocol that are
+/// kept around to be displayed later.
+const MAX_KITTY_IMAGES: usize = 64;
+/// The largest number of rows an image can cover.
+const MAX_IMAGE_ROWS: usize = 1000;
+
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// An image shown in the terminal, covering a rectangle of cells.
+#[derive(Clone, Debug)]
+pub struct TerminalImage {
+    pub id: u32,
+    pub image: Arc<RenderImage>,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+/// Returns the id of the image covering the given cell, and the cell's row
+/// within the image.
+pub fn image_placeholder(cell: &Cell) -> Option<(u32, usize)> {
+    if cell.c != IMAGE_PLACEHOLDER {
+        return None;
+    }
+    let Color::Spec(id) = cell.fg else {
+        return None;
+    };
+    let Some(Color::Spec(row)) = cell.underline_color() else {
+        return None;
+    };
+    Some((rgb_to_u32(id), rgb_to_u32(row) as usize))
+}
+
+fn rgb_to_u32(rgb: Rgb) -> u32 {
+    u32::from_be_bytes([0, rgb.r, rgb.g, rgb.b])
+}
+
+/// The size of the terminal's cells and grid, used to decide how many cells an
+/// image covers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ImageGeometry {
+    pub cell_width: f32,
+    pub cell_height: f32,
+    pub columns: usize,
+    pub lines: usize,
+}
+
+impl Default for ImageGeometry {
+    fn default() -> Self {
+        Self {
+            cell_width: 8.,
+            cell_height: 16.,
+            columns: 80,
+            lines: 24,
+        }
+    }
+}
+
+/// Images decoded by the PTY reader, waiting to be picked up by the terminal.
+#[derive(Debug, Default)]
+pub(crate) struct PendingImages {
+    images: Mutex<Vec<TerminalImage>>,
+    geometry: Mutex<ImageGeometry>,
+}
+
+impl PendingImages {
+    pub(crate) fn push(&self, image: TerminalImage) {
+        self.images.lock().unwrap().push(image);
+    }
+
+    pub(crate) fn take(&self) -> Vec<TerminalImage> {
+        std::mem::take(&mut *self.images.lock().unwrap())
+    }
+
+    pub(crate) fn geometry(&self) -> ImageGeometry {
+        *self.geometry.lock().unwrap()
+    }
+
+    pub(crate) fn set_geometry(&self, geometry: ImageGeometry) {
+        *self.geometry.lock().unwrap() = geometry;
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum ImageEvent {
+    Image(TerminalImage),
+    /// A reply to a kitty graphics command, to be written back to the PTY.
+    Response(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FilterState {
+    Ground,
+    Escape,
+    OscPrefix,
+    DcsParams,
+    Sequence(SequenceKind),
+    Discard,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SequenceKind {
+    Iterm,
+    Kitty,
+    Sixel,
+}
+
+const ITERM_PREFIX: &[u8] = b"1337;File=";
+
+/// Removes image escape sequences from PTY output, replacing them with
+/// placeholder cells.
+pub(crate) struct ImageFilter {
+    state: FilterState,
+    /// Whether the previous byte of a sequence was an escape, which may start
+    /// the string terminator.
+    pending_escape: bool,
+    params: Vec<u8>,
+    buffer: Vec<u8>,
+    next_image_id: u32,
+    kitty_transfer: Option<(KittyCommand, Vec<u8>)>,
+    kitty_images: HashMap<u32, RgbaImage>,
+}
+
+impl Default for ImageFilter {
+    fn default() -> Self {
+        Self {
+            state: FilterState::Ground,
+            pending_escape: false,
+            params: Vec::new(),
+            buffer: Vec::new(),
+            next_image_id: 1,
+            kitty_transfer: None,
+            kitty_images: HashMap::default(),
+        }
+    }
+}
+
+impl ImageFilter {
+    /// Filters the given PTY output into `output`, reporting decoded images and
+    /// replies to the program in `events`.
+    pub(crate) fn process(
+        &mut self,
+        input: &[u8],
+        geometry: ImageGeometry,
+        output: &mut Vec<u8>,
+        events: &mut Vec<ImageEvent>,
+    ) {
+        let mut ix = 0;
+        while ix < input.len() {
+            let byte = input[ix];
+            match self.state {
+                FilterState::Ground => {
+                    let rest = &input[ix..];
+                    match rest.iter().position(|&byte| byte == 0x1b) {
+                        Some(escape_ix) => {
+                            output.extend_from_slice(&rest[..escape_ix]);
+                            self.state = FilterState::Escape;
+                            ix += escape_ix;
+                        }
+                        None => {
+                            output.extend_from_slice(rest);
+                            ix = input.len();
+                            continue;
+                        }
+                    }
+                }
+                FilterState::Escape => match byte {
+                    b'_' => self.start_sequence(FilterState::Sequence(SequenceKind::Kitty)),
+                    b'P' => self.start_sequence(FilterState::DcsParams),
+                    b']' => self.start_sequence(FilterState::OscPrefix),
+                    0x1b => output.push(0x1b),
+                    _ => {
+                        output.extend_from_slice(&[0x1b, byte]);
+                        self.state = FilterState::Ground;
+                    }
+                },
+                FilterState::OscPrefix => {
+                    self.buffer.push(byte);
+                    if self.buffer == ITERM_PREFIX {
+                        self.buffer.clear();
+                        self.state = FilterState::Sequence(SequenceKind::Iterm);
+                    } else if !ITERM_PREFIX.starts_with(&self.buffer) {
+                        output.extend_from_slice(b"\x1b]");
+                        output.append(&mut self.buffer);
+                        self.state = FilterState::Ground;
+                    }
+                }
+                FilterState::DcsParams => match byte {
+                    b'0'..=b'9' | b';' => self.params.push(byte),
+                    b'q' => self.state = FilterState::Sequence(SequenceKind::Sixel),
+                    _ => {
+                        output.extend_from_slice(b"\x1bP");
+                        output.append(&mut self.params);
+                        output.push(byte);
+                        self.state = FilterState::Ground;
+                    }
+                },
+                FilterState::Sequence(_) | FilterState::Discard => {
+                    if self.pending_escape {
+                        self.pending_escape = false;
+                        if byte == b'\\' {
+                            self.finish_sequence(geometry, output, events);
+                        } else {
+                            // The escape aborted the sequence and starts a new one.
+                            self.state = FilterState::Escape;
+                            continue;
+                        }
+                    } else if byte == 0x1b {
+                        self.pending_escape = true;
+                    } else if byte == 0x07
+                        && self.state == FilterState::Sequence(SequenceKind::Iterm)
+                    {
+                        self.finish_sequence(geometry, output, events);
+                    } else if self.state != FilterState::Discard {
+                        if self.buffer.len() < MAX_SEQUENCE_LEN {
+                            self.buffer.push(byte);
+                        } else {
+                            self.buffer = Vec::new();
+                            self.state = FilterState::Discard;
+                        }
+                    }
+                }
+            }
+            ix += 1;
+        }
+    }
+
+    fn start_sequence(&mut self, state: FilterState) {
+        self.buffer.clear();
+        self.params.clear();
+        self.pending_escape = false;
+        self.state = state;
+    }
+
+    fn finish_sequence(
+        &mut self,
+        geometry: ImageGeometry,
+        output: &mut Vec<u8>,
+        events: &mut Vec<ImageEvent>,
+    ) {
+        let state = std::mem::replace(&mut self.state, FilterState::Ground);
+        let buffer = std::mem::take(&mut self.buffer);
+        match state {
+            FilterState::Sequence(SequenceKind::Iterm) => {
+                if let Some((image, columns, rows)) = decode_iterm(&buffer, geometry) {
+                    self.display(
+                        image,
+                        columns,
+                        rows,
+                        geometry,
+                        Placement::NextLine,
+                        output,
+                        events,
+                    );
+                }
+            }
+            FilterState::Sequence(SequenceKind::Kitty) => {
+                if let Some(command) = buffer.strip_prefix(b"G") {
+                    self.handle_kitty(command, geometry, output, events);
+                }
+            }
+            FilterState::Sequence(SequenceKind::Sixel) => {
+                if let Some(image) = decode_sixel(&buffer) {
+                    self.display(
+                        image,
+                        None,
+                        None,
+                        geometry,
+                        Placement::NextLine,
+                        output,
+                        events,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_kitty(
+        &mut self,
+        command: &[u8],
+        geometry: ImageGeometry,
+        output: &mut Vec<u8>,
+        events: &mut Vec<ImageEvent>,
+    ) {
+        let (control, payload) = match command.iter().position(|&byte| byte == b';') {
+            Some(ix) => (&command[..ix], &command[ix + 1..]),
+            None => (command, &[][..]),
+        };
+        let chunk = KittyCommand::parse(control);
+
+        // Continuation chunks only carry `m` and `q`, the rest of the command is
+        // taken from the first chunk.
+        let (command, payload) = match self.kitty_transfer.take() {
+            Some((command, mut data)) => {
+                data.extend_from_slice(payload);
+                (command, data)
+            }
+            None => (chunk.clone(), payload.to_vec()),
+        };
+        if chunk.more {
+            if payload.len() < MAX_SEQUENCE_LEN {
+                self.kitty_transfer = Some((command, payload));
+            }
+            return;
+        }
+
+        let result = match command.action {
+            // Deleting isn't supported, images stay in the scrollback until they're evicted.
+            b'd' => return,
+            b'p' => match command.id.and_then(|id| self.kitty_images.get(&id)) {
+                Some(image) => {
+                    let image = image.clone();
+                    self.display(
+                        image,
+                        command.columns,
+                        command.rows,
+                        geometry,
+                        command.placement(),
+                        output,
+                        events,
+                    );
+                    Ok(())
+                }
+                None => Err("ENOENT:image not found"),
+            },
+            b't' | b'T' | b'q' => command.decode(&payload).map(|image| {
+                if command.action == b'T' {
+                    self.display(
+                        image.clone(),
+                        command.columns,
+                        command.rows,
+                        geometry,
+                        command.placement(),
+                        output,
+                        events,
+                    );
+                }
+                if command.action != b'q' {
+                    if let Some(id) = command.id {
+                        if self.kitty_images.len() >= MAX_KITTY_IMAGES {
+                            if let Some(&oldest) = self.kitty_images.keys().min() {
+                                self.kitty_images.remove(&oldest);
+                            }
+                        }
+                        self.kitty_images.insert(id, image);
+                    }
+                }
+            }),
+            _ => Err("EINVAL:unsupported action"),
+        };
+
+        if let Some(response) = command.response(result) {
+            events.push(ImageEvent::Response(response));
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn display(
+        &mut self,
+        mut image: RgbaImage,
+        columns: Option<usize>,
+        rows: Option<usize>,
+        geometry: ImageGeometry,
+        placement: Placement,
+        output: &mut Vec<u8>,
+        events: &mut Vec<ImageEvent>,
+    ) {
+        let (columns, rows) = cell_size(image.width(), image.height(), columns, rows, geometry);
+        if columns == 0 || rows == 0 {
+            return;
+        }
+
+        let id = self.next_image_id;
+        // Ids have to fit in the 24 bits of a color.
+        self.next_image_id = self.next_image_id % 0xff_ffff + 1;
+
+        // gpui expects BGRA.
+        for pixel in image.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        events.push(ImageEvent::Image(TerminalImage {
+            id,
+            image: Arc::new(RenderImage::new(vec![Frame::new(image)])),
+            columns,
+            rows,
+        }));
+        output.extend_from_slice(placeholder_text(id, columns, rows, placement).as_bytes());
+    }
+}
+
+/// Where the cursor goes once an image is displayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Placement {
+    /// The cursor stays where the image started.
+    KeepCursor,
+    /// The cursor moves to the right of the image's last row.
+    AfterImage,
+    /// The cursor moves to the start of the line below the image.
+    NextLine,
+}
+
+fn placeholder_text(id: u32, columns: usize, rows: usize, placement: Placement) -> String {
+    let mut text = String::new();
+    if placement == Placement::KeepCursor {
+        text.push_str("\x1b7");
+    }
+    let [_, r, g, b] = id.to_be_bytes();
+    for row in 0..rows {
+        if row > 0 {
+            write!(text, "\n\x1b[{columns}D").unwrap();
+        }
+        let [_, row_r, row_g, row_b] = (row as u32).to_be_bytes();
+        write!(text, "\x1b[38;2;{r};{g};{b};58;2;{row_r};{row_g};{row_b}m").unwrap();
+        text.extend(std::iter::repeat_n(IMAGE_PLACEHOLDER, columns));
+        text.push_str("\x1b[39;59m");
+    }
+    match placement {
+        Placement::KeepCursor => text.push_str("\x1b8"),
+        Placement::AfterImage => {}
+        Placement::NextLine => text.push_str("\r\n"),
+    }
+    text
+}
+
+/// Returns how many columns and rows an image covers. Sizes that weren't
+/// requested are derived from the image's size in pixels, and the image is
+/// scaled down to fit the terminal's width.
+fn cell_size(
+    width: u32,
+    height: u32,
+    columns: Option<usize>,
+    rows: Option<usize>,
+    geometry: ImageGeometry,
+) -> (usize, usize) {
+    let cell_width = geometry.cell_width.max(1.);
+    let cell_height = geometry.cell_height.max(1.);
+    let width = width.max(1) as f32;
+    let height = height.max(1) as f32;
+
+    let (columns, rows) = match (columns, rows) {
+        (Some(columns), Some(rows)) => (columns as f32, rows as f32),
+        (Some(columns), None) => {
+            let columns = columns as f32;
+            (columns, columns * cell_width * height / width / cell_height)
+        }
+        (None, Some(rows)) => {
+            let rows = rows as f32;
+            (rows * cell_height * width / height / cell_width, rows)
+        }
+        (None, None) => (width / cell_width, height / cell_height),
+    };
+
+    // Leave the last column free, so that the cursor doesn't wrap while the
+    // placeholders are printed.
+    let max_columns = geometry.columns.saturating_sub(1).max(1) as f32;
+    let (columns, rows) = if columns > max_columns {
+        (max_columns, rows * max_columns / columns)
+    } else {
+        (columns, rows)
+    };
+    (
+        columns.ceil() as usize,
+        (rows.ceil() as usize).min(MAX_IMAGE_ROWS),
+    )
+}
+
+fn check_dimensions(image: RgbaImage) -> Option<RgbaImage> {
+    (image.width() > 0
+        && image.height() > 0
+        && image.width() <= MAX_IMAGE_DIMENSION
+        && image.height() <= MAX_IMAGE_DIMENSION)
+        .then_some(image)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Dimension {
+    Auto,
+    Cells(usize),
+    Pixels(f32),
+    Percent(f32),
+}
+
+impl Dimension {
+    fn parse(value: &str) -> Option<Self> {
+        if value == "auto" {
+            Some(Self::Auto)
+        } else if let Some(pixels) = value.strip_suffix("px") {
+            pixels.parse().ok().map(Self::Pixels)
+        } else if let Some(percent) = value.strip_suffix('%') {
+            percent.parse().ok().map(Self::Percent)
+        } else {
+            value.parse().ok().map(Self::Cells)
+        }
+    }
+
+    fn to_cells(self, cell_size: f32, total_cells: usize) -> Option<usize> {
+        match self {
+            Self::Auto => None,
+            Self::Cells(cells) => Some(cells),
+            Self::Pixels(pixels) => Some((pixels / cell_size.max(1.)).ceil() as usize),
+            Self::Percent(percent) => Some((percent / 100. * total_cells as f32).ceil() as usize),
+        }
+    }
+}
+
+/// Decodes the arguments and contents of an `OSC 1337 ; File=` sequence,
+/// returning the image and the number of columns and rows it asked for.
+fn decode_iterm(
+    sequence: &[u8],
+    geometry: ImageGeometry,
+) -> Option<(RgbaImage, Option<usize>, Option<usize>)> {
+    let colon_ix = sequence.iter().position(|&byte| byte == b':')?;
+    let args = std::str::from_utf8(&sequence[..colon_ix]).ok()?;
+    let mut inline = false;
+    let mut width = Dimension::Auto;
+    let mut height = Dimension::Auto;
+    for arg in args.split(';') {
+        let Some((key, value)) = arg.split_once('=') else {
+            continue;
+        };
+        match key {
+            "inline" => inline = value == "1",
+            "width" => width = Dimension::parse(value)?,
+            "height" => height = Dimension::parse(value)?,
+            _ => {}
+        }
+    }
+    // Files that aren't inline are downloads, which aren't supported.
+    if !inline {
+        return None;
+    }
+
+    let data = decode_base64(&sequence[colon_ix + 1..])?;
+    let image = check_dimensions(image::load_from_memory(&data).ok()?.into_rgba8())?;
+    Some((
+        image,
+        width.to_cells(geometry.cell_width, geometry.columns),
+        height.to_cells(geometry.cell_height, geometry.lines),
+    ))
+}
+
+fn decode_base64(data: &[u8]) -> Option<Vec<u8>> {
+    let data = data
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    BASE64.decode(data).ok()
+}
+
+/// The control data of a kitty graphics command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct KittyCommand {
+    action: u8,
+    format: u32,
+    transmission: u8,
+    compression: Option<u8>,
+    width: u32,
+    height: u32,
+    id: Option<u32>,
+    placement_id: Option<u32>,
+    columns: Option<usize>,
+    rows: Option<usize>,
+    more: bool,
+    quiet: u32,
+    keep_cursor: bool,
+}
+
+impl KittyCommand {
+    fn parse(control: &[u8]) -> Self {
+        let mut command = Self {
+            action: b't',
+            format: 32,
+            transmission: b'd',
+            compression: None,
+            width: 0,
+            height: 0,
+            id: None,
+            placement_id: None,
+            columns: None,
+            rows: None,
+            more: false,
+            quiet: 0,
+            keep_cursor: false,
+        };
+        for pair in control.split(|&byte| byte == b',') {
+            let [key, b'=', value @ ..] = pair else {
+                continue;
+            };
+            let number = std::str::from_utf8(value)
+                .ok()
+                .and_then(|value| value.parse::<u32>().ok());
+            match key {
+                b'a' => command.action = value.first().copied().unwrap_or(b't'),
+                b't' => command.transmission = value.first().copied().unwrap_or(b'd'),
+                b'o' => command.compression = value.first().copied(),
+                b'f' => command.format = number.unwrap_or(32),
+                b's' => command.width = number.unwrap_or(0),
+                b'v' => command.height = number.unwrap_or(0),
+                b'i' => command.id = number.filter(|id| *id > 0),
+                b'p' => command.placement_id = number,
+                b'c' => command.columns = number.map(|columns| columns as usize),
+                b'r' => command.rows = number.map(|rows| rows as usize),
+                b'm' => command.more = number == Some(1),
+                b'q' => command.quiet = number.unwrap_or(0),
+                b'C' => command.keep_cursor = number == Some(1),
+                _ => {}
+            }
+        }
+        command
+    }
+
+    fn placement(&self) -> Placement {
+        if self.keep_cursor {
+            Placement::KeepCursor
+        } else {
+            Placement::AfterImage
+        }
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<RgbaImage, &'static str> {
+        if self.compression.is_some() {
+            return Err("EINVAL:compression is not supported");
+        }
+        let payload = decode_base64(payload).ok_or("EINVAL:invalid base64 payload")?;
+        let data = match self.transmission {
+            b'd' => payload,
+            b'f' | b't' => {
+                let path = String::from_utf8(payload).map_err(|_| "EINVAL:invalid file path")?;
+                let data = std::fs::read(&path).map_err(|_| "EBADF:failed to read file")?;
+                // Temporary files are deleted once read, as long as they look
+                // like they were created for this purpose.
+                if self.transmission == b't' && path.contains("tty-graphics-protocol") {
+                    std::fs::remove_file(&path).ok();
+                }
+                data
+            }
+            _ => return Err("EINVAL:unsupported transmission medium"),
+        };
+
+        let image = match self.format {
+            100 => image::load_from_memory(&data)
+                .map_err(|_| "EBADPNG:failed to decode image")?
+                .into_rgba8(),
+            32 => RgbaImage::from_raw(self.width, self.height, data)
+                .ok_or("ENODATA:not enough image data")?,
+            24 => RgbImage::from_raw(self.width, self.height, data)
+                .map(|image| DynamicImage::ImageRgb8(image).into_rgba8())
+                .ok_or("ENODATA:not enough image data")?,
+            _ => return Err("EINVAL:unsupported format"),
+        };
+        check_dimensions(image).ok_or("EINVAL:invalid image dimensions")
+    }
+
+    fn response(&self, result: Result<(), &str>) -> Option<Vec<u8>> {
+        let id = self.id?;
+        let message = match result {
+            Ok(()) if self.quiet == 0 => "OK",
+            Err(error) if self.quiet < 2 => error,
+            _ => return None,
+        };
+        let mut response = format!("\x1b_Gi={id}");
+        if let Some(placement_id) = self.placement_id {
+            write!(response, ",p={placement_id}").unwrap();
+        }
+        write!(response, ";{message}\x1b\\").unwrap();
+        Some(response.into_bytes())
+    }
+}
+
+/// The default sixel palette of the VT340, in percentages.
+const SIXEL_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+fn percent_to_u8(percent: u32) -> u8 {
+    (percent.min(100) * 255 / 100) as u8
+}
+
+/// Converts a sixel HLS color, where a hue of 0 is blue, to RGB.
+fn sixel_hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> [u8; 3] {
+    let hue = ((hue + 240) % 360) as f32;
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+    let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+    let x = chroma * (1. - ((hue / 60.) % 2. - 1.).abs());
+    let m = lightness - chroma / 2.;
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+    [r, g, b].map(|channel| ((channel + m) * 255.).round() as u8)
+}
+
+/// Decodes the data of a sixel `DCS` sequence into an image. Pixels that no
+/// sixel was drawn on are transparent.
+fn decode_sixel(data: &[u8]) -> Option<RgbaImage> {
+    const MAX_SIXEL_DIMENSION: usize = 4096;
+
+    let mut palette = [[0u8; 3]; 256];
+    for (color, percentages) in palette.iter_mut().zip(SIXEL_PALETTE) {
+        *color = percentages.map(|percent| percent_to_u8(percent as u32));
+    }
+    let mut color = 0usize;
+    let mut rows: Vec<Vec<[u8; 4]>> = Vec::new();
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut width = 0;
+
+    let mut ix = 0;
+    let number = |ix: &mut usize| -> Option<u32> {
+        let start = *ix;
+        while *ix < data.len() && data[*ix].is_ascii_digit() {
+            *ix += 1;
+        }
+        std::str::from_utf8(&data[start..*ix]).ok()?.parse().ok()
+    };
+    let numbers = |ix: &mut usize| -> Vec<u32> {
+        let mut numbers = vec![number(ix).unwrap_or(0)];
+        while *ix < data.len() && data[*ix] == b';' {
+            *ix += 1;
+            numbers.push(number(ix).unwrap_or(0));
+        }
+        numbers
+    };
+
+    while ix < data.len() {
+        let byte = data[ix];
+        ix += 1;
+        let mut repeat = 1;
+        let sixel = match byte {
+            b'"' => {
+                // Raster attributes: aspect ratio and the image's size.
+                let attributes = numbers(&mut ix);
+                if let [_, _, width_attr, height_attr] = attributes[..] {
+                    width = width.max((width_attr as usize).min(MAX_SIXEL_DIMENSION));
+                    let height = (height_attr as usize).min(MAX_SIXEL_DIMENSION);
+                    if rows.len() < height {
+                        rows.resize(height, Vec::new());
+                    }
+                }
+                continue;
+            }
+            b'#' => {
+                let args = numbers(&mut ix);
+                color = args[0] as usize % palette.len();
+                if let [_, space, a, b, c] = args[..] {
+                    palette[color] = match space {
+                        1 => sixel_hls_to_rgb(a, b, c),
+                        _ => [a, b, c].map(percent_to_u8),
+                    };
+                }
+                continue;
+            }
+            b'!' => {
+                repeat = number(&mut ix).unwrap_or(1).max(1) as usize;
+                let Some(&byte) = data.get(ix) else {
+                    break;
+                };
+                ix += 1;
+                byte
+            }
+            b'$' => {
+                x = 0;
+                continue;
+            }
+            b'-' => {
+                x = 0;
+                y += 6;
+                continue;
+            }
+            _ => byte,
+        };
+        if !(0x3f..=0x7e).contains(&sixel) {
+            continue;
+        }
+
+        let bits = sixel - 0x3f;
+        let end = (x + repeat).min(MAX_SIXEL_DIMENSION);
+        let [r, g, b] = palette[color];
+        for bit in 0..6 {
+            let pixel_y = y + bit;
+            if bits & (1 << bit) == 0 || pixel_y >= MAX_SIXEL_DIMENSION {
+                continue;
+            }
+            if rows.len() <= pixel_y {
+                rows.resize(pixel_y + 1, Vec::new());
+            }
+            let row = &mut rows[pixel_y];
+            if row.len() < end {
+                row.resize(end, [0; 4]);
+            }
+            for pixel in &mut row[x.min(end)..end] {
+                *pixel = [r, g, b, 255];
+            }
+        }
+        x = end;
+        width = width.max(end);
+    }
+
+    let height = rows.len();
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, pixel) in row.iter().enumerate() {
+            image.put_pixel(x as u32, y as u32, image::Rgba(*pixel));
+        }
+    }
+    Some(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(chunks: &[&[u8]]) -> (Vec<u8>, Vec<ImageEvent>) {
+        let mut filter = ImageFilter::default();
+        let mut output = Vec::new();
+        let mut events = Vec::new();
+        let geometry = ImageGeometry {
+            cell_width: 10.,
+            cell_height: 20.,
+            columns: 80,
+            lines: 24,
+        };
+        for chunk in chunks {
+            filter.process(chunk, geometry, &mut output, &mut events);
+        }
+        (output, events)
+    }
+
+    fn images(events: &[ImageEvent]) -> Vec<(u32, usize, usize)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ImageEvent::Image(image) => Some((image.id, image.columns, image.rows)),
+                ImageEvent::Response(_) => None,
+            })
+            .collect()
+    }
+
+    fn responses(events: &[ImageEvent]) -> Vec<String> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ImageEvent::Response(response) => {
+                    Some(String::from_utf8(response.clone()).unwrap())
+                }
+                ImageEvent::Image(_) => None,
+            })
+            .collect()
+    }
+
+    fn png(width: u32, height: u32) -> String {
+        let image = RgbaImage::from_pixel(width, height, image::Rgba([255, 0, 0, 255]));
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        BASE64.encode(bytes)
+    }
+
+    #[test]
+    fn test_other_sequences_pass_through() {
+        let input: &[u8] = b"a\x1b[31mb\x1b]0;title\x07\x1b]133;A\x1b\\\x1bP$qm\x1b\\\x1b\x1b7c\n";
+        for split in 0..input.len() {
+            let (head, tail) = input.split_at(split);
+            let (output, events) = filter(&[head, tail]);
+            assert_eq!(output, input, "split at {split}");
+            assert!(events.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_iterm_image() {
+        let sequence = format!("x\x1b]1337;File=inline=1;width=3:{}\x07y", png(30, 20));
+        let (output, events) = filter(&[sequence.as_bytes()]);
+        assert_eq!(images(&events), vec![(1, 3, 1)]);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "x\x1b[38;2;0;0;1;58;2;0;0;0m{}\x1b[39;59m\r\ny",
+                IMAGE_PLACEHOLDER.to_string().repeat(3)
+            )
+        );
+
+        // Files that aren't inline are ignored.
+        let sequence = format!("\x1b]1337;File=name=eA==:{}\x07", png(1, 1));
+        let (output, events) = filter(&[sequence.as_bytes()]);
+        assert!(output.is_empty());
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_kitty_image() {
+        let data = png(25, 50);
+        let (first, second) = data.split_at(8);
+        let (output, events) = filter(&[
+            b"\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\",
+            format!("\x1b_Ga=T,f=100,i=7,m=1;{first}\x1b\\").as_bytes(),
+            format!("\x1b_Gm=0;{second}\x1b\\").as_bytes(),
+            b"\x1b_Ga=p,i=7,c=1,r=1,C=1\x1b\\",
+            b"\x1b_Ga=p,i=8\x1b\\",
+        ]);
+        assert_eq!(images(&events), vec![(1, 3, 3), (2, 1, 1)]);
+        assert_eq!(
+            responses(&events),
+            vec![
+                "\x1b_Gi=31;OK\x1b\\",
+                "\x1b_Gi=7;OK\x1b\\",
+                "\x1b_Gi=7;OK\x1b\\",
+                "\x1b_Gi=8;ENOENT:image not found\x1b\\",
+            ]
+        );
+        let placeholders = String::from_utf8(output)
+            .unwrap()
+            .chars()
+            .filter(|c| *c == IMAGE_PLACEHOLDER)
+            .count();
+        assert_eq!(placeholders, 3 * 3 + 1);
+    }
+
+    #[test]
+    fn test_sixel_image() {
+        // Two red columns, 6 pixels high, followed by a blue one that is 7 pixels high.
+        let (output, events) = filter(&[b"\x1bPq#1;2;100;0;0!2~#2;2;0;0;100~-#2@\x1b\\"]);
+        let [ImageEvent::Image(image)] = &events[..] else {
+            panic!("expected an image, got {events:?}");
+        };
+        assert_eq!((image.columns, image.rows), (1, 1));
+        assert!(output.ends_with(b"\x1b[39;59m\r\n"));
+
+        let image = decode_sixel(b"#1;2;100;0;0!2~#2;2;0;0;100~-#2@").unwrap();
+        assert_eq!((image.width(), image.height()), (3, 7));
+        assert_eq!(image.get_pixel(1, 5).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(2, 0).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(0, 6).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(1, 6).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_cell_size() {
+        let geometry = ImageGeometry {
+            cell_width: 10.,
+            cell_height: 20.,
+            columns: 11,
+            lines: 24,
+        };
+        assert_eq!(cell_size(95, 41, None, None, geometry), (10, 3));
+        assert_eq!(cell_size(100, 100, Some(5), None, geometry), (5, 3));
+        assert_eq!(cell_size(100, 100, None, Some(2), geometry), (4, 2));
+        assert_eq!(cell_size(400, 200, None, None, geometry), (10, 3));
+    }
+}
@@ -0,0 +1,167 @@
+//! A PTY whose output is filtered on its way to Alacritty's parser, to handle
+//! the sequences Alacritty doesn't know about: inline images are replaced with
+//! placeholder cells, and shell marks are recorded.
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    sync::Arc,
+};
+
+use alacritty_terminal::{
+    event::{OnResize, WindowSize},
+    tty::{ChildEvent, EventedPty, EventedReadWrite, Pty},
+};
+use polling::{Event, PollMode, Poller};
+
+use crate::{
+    images::{ImageEvent, ImageFilter, PendingImages},
+    shell_integration::{MarkScanner, PendingMarks},
+};
+
+const READ_BUFFER_SIZE: usize = 0x10_0000;
+
+pub(crate) struct FilteredPty {
+    pty: Pty,
+    reader: FilteredReader,
+}
+
+impl FilteredPty {
+    pub(crate) fn new(
+        pty: Pty,
+        images: Arc<PendingImages>,
+        marks: Option<Arc<PendingMarks>>,
+    ) -> io::Result<Self> {
+        let file = pty.file().try_clone()?;
+        Ok(Self {
+            pty,
+            reader: FilteredReader {
+                file,
+                buffer: vec![0; READ_BUFFER_SIZE],
+                output: Vec::new(),
+                output_ix: 0,
+                image_filter: ImageFilter::default(),
+                images,
+                marks: marks.map(|marks| (MarkScanner::default(), marks)),
+            },
+        })
+    }
+}
+
+pub(crate) struct FilteredReader {
+    file: File,
+    buffer: Vec<u8>,
+    /// Filtered output that didn't fit in the parser's buffer yet.
+    output: Vec<u8>,
+    output_ix: usize,
+    image_filter: ImageFilter,
+    images: Arc<PendingImages>,
+    marks: Option<(MarkScanner, Arc<PendingMarks>)>,
+}
+
+impl FilteredReader {
+    fn write_output(&mut self, buf: &mut [u8]) -> usize {
+        let pending = &self.output[self.output_ix..];
+        let len = pending.len().min(buf.len());
+        buf[..len].copy_from_slice(&pending[..len]);
+        self.output_ix += len;
+        if self.output_ix == self.output.len() {
+            self.output.clear();
+            self.output_ix = 0;
+        }
+        len
+    }
+}
+
+impl Read for FilteredReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.output_ix < self.output.len() {
+            return Ok(self.write_output(buf));
+        }
+
+        // Reading a whole image sequence yields no output, so keep reading until
+        // there's something to hand to the parser.
+        loop {
+            let len = self.file.read(&mut self.buffer)?;
+            if len == 0 {
+                return Ok(0);
+            }
+
+            let mut events = Vec::new();
+            self.image_filter.process(
+                &self.buffer[..len],
+                self.images.geometry(),
+                &mut self.output,
+                &mut events,
+            );
+            for event in events {
+                match event {
+                    ImageEvent::Image(image) => self.images.push(image),
+                    ImageEvent::Response(response) => {
+                        if let Err(error) = self.file.write_all(&response) {
+                            log::error!("failed to reply to a graphics command: {error}");
+                        }
+                    }
+                }
+            }
+
+            if let Some((scanner, marks)) = &mut self.marks {
+                scanner.advance(&self.output, |mark, newline_ix| {
+                    marks.push(mark, newline_ix)
+                });
+                marks.set_newlines(scanner.newlines());
+            }
+
+            if !self.output.is_empty() {
+                return Ok(self.write_output(buf));
+            }
+        }
+    }
+}
+
+impl EventedReadWrite for FilteredPty {
+    type Reader = FilteredReader;
+    type Writer = <Pty as EventedReadWrite>::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        unsafe { self.pty.register(poll, interest, mode) }
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poll, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poll)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        &mut self.reader
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self.pty.writer()
+    }
+}
+
+impl EventedPty for FilteredPty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl OnResize for FilteredPty {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.pty.on_resize(window_size)
+    }
+}
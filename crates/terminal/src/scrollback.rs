@@ -0,0 +1,67 @@
+//! Saving the text of a terminal's scrollback, and restoring it into a new
+//! terminal when the workspace is reopened.
+
+use alacritty_terminal::{
+    Term,
+    event::EventListener,
+    grid::Dimensions,
+    index::{Column, Line, Point as AlacPoint},
+    term::TermMode,
+    vte::ansi::{ClearMode, Handler, Processor, StdSyncHandler},
+};
+
+/// Returns the last `max_lines` lines of the scrollback and of the screen up to
+/// the cursor, so that they can be restored with [`restore_scrollback`].
+///
+/// Returns `None` while a full screen application is running, since the
+/// scrollback isn't accessible then.
+pub fn scrollback_text<T: EventListener>(term: &Term<T>, max_lines: usize) -> Option<String> {
+    if term.mode().contains(TermMode::ALT_SCREEN) {
+        return None;
+    }
+    let start = AlacPoint::new(term.topmost_line(), Column(0));
+    let end = AlacPoint::new(term.grid().cursor.point.line, term.last_column());
+    let text = term.bounds_to_string(start, end);
+    let lines = text.trim_end().lines().collect::<Vec<_>>();
+    Some(lines[lines.len().saturating_sub(max_lines)..].join("\n"))
+}
+
+/// Puts the scrollback of a previous session above what the shell printed so
+/// far, followed by a note saying where the restored output ends.
+pub fn restore_scrollback<T: EventListener>(
+    term: &mut Term<T>,
+    scrollback: &str,
+    running_command: Option<&str>,
+) {
+    if term.mode().contains(TermMode::ALT_SCREEN) {
+        return;
+    }
+
+    let cursor = term.grid().cursor.point;
+    let printed = term.bounds_to_string(AlacPoint::new(term.topmost_line(), Column(0)), cursor);
+
+    term.clear_screen(ClearMode::Saved);
+    term.grid_mut().reset_region(..);
+    term.grid_mut().cursor.point = AlacPoint::new(Line(0), Column(0));
+
+    let note = match running_command {
+        Some(command) => {
+            format!("Restored from the previous session, where `{command}` was running")
+        }
+        None => "Restored from the previous session".to_string(),
+    };
+    let mut text = String::new();
+    for line in scrollback.lines() {
+        text.extend(line.chars().filter(|c| !c.is_control() || *c == '\t'));
+        text.push_str("\r\n");
+    }
+    text.push_str(&format!(
+        "\x1b[2m[{}]\x1b[0m\r\n",
+        note.replace(|c: char| c.is_control(), "")
+    ));
+    text.push_str(&printed.replace('\n', "\r\n"));
+
+    let mut processor = Processor::<StdSyncHandler>::new();
+    processor.advance(term, text.as_bytes());
+    term.grid_mut().cursor.point.column = cursor.column;
+}
@@ -0,0 +1,637 @@
+//! Shell integration, based on the OSC 133 semantic prompt sequences (and the
+//! OSC 633 extensions to them), which let the shell tell the terminal where
+//! prompts, commands and their output start, and how each command exited.
+//!
+//! Alacritty ignores these sequences, so the bytes read from the PTY are scanned
+//! for them before they're handed to its parser.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use alacritty_terminal::{
+    grid::{Dimensions as _, Grid},
+    index::Line,
+    term::cell::{Cell, Flags},
+};
+use anyhow::Result;
+use collections::HashMap;
+use task::Shell;
+
+/// The longest OSC payload we're willing to buffer while looking for marks.
+const MAX_PAYLOAD_LEN: usize = 4096;
+
+const BASH_SCRIPT: &str = include_str!("shell_integration/zed.bash");
+const ZSH_SCRIPT: &str = include_str!("shell_integration/zed.zsh");
+const ZSH_ENV: &str = include_str!("shell_integration/zsh/.zshenv");
+const FISH_SCRIPT: &str = include_str!("shell_integration/zed.fish");
+
+/// A mark emitted by the shell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShellMark {
+    /// `OSC 133 ; A`: the prompt is about to be printed.
+    PromptStart,
+    /// `OSC 133 ; B`: the prompt ended and the user is typing a command.
+    CommandStart,
+    /// `OSC 133 ; C`: the command was submitted and its output follows.
+    CommandExecuted,
+    /// `OSC 133 ; D [; exit code]`: the command finished.
+    CommandFinished { exit_code: Option<i32> },
+    /// `OSC 633 ; E ; command line`: the command line that is about to run.
+    CommandLine(String),
+    /// `OSC 633 ; P ; Cwd=path`: the shell's working directory.
+    WorkingDirectory(PathBuf),
+}
+
+impl ShellMark {
+    fn parse(payload: &[u8]) -> Option<Self> {
+        let payload = std::str::from_utf8(payload).ok()?;
+        let params = payload
+            .strip_prefix("133;")
+            .or_else(|| payload.strip_prefix("633;"))?;
+        let mut params = params.split(';');
+        let mark = match params.next()? {
+            "A" => Self::PromptStart,
+            "B" => Self::CommandStart,
+            "C" => Self::CommandExecuted,
+            "D" => Self::CommandFinished {
+                exit_code: params.next().and_then(|code| code.parse().ok()),
+            },
+            "E" => Self::CommandLine(unescape(params.next()?)),
+            "P" => {
+                let cwd = params.next()?.strip_prefix("Cwd=")?;
+                Self::WorkingDirectory(PathBuf::from(unescape(cwd)))
+            }
+            _ => return None,
+        };
+        Some(mark)
+    }
+}
+
+/// Reverses the escaping of OSC 633 values, where `\\` is a backslash and
+/// `\xAB` is an arbitrary byte.
+fn unescape(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match rest {
+            [b'\\', tail @ ..] => {
+                bytes.push(b'\\');
+                rest = tail;
+            }
+            [b'x', hi, lo, tail @ ..] => {
+                let hex = [*hi, *lo];
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(decoded) => {
+                        bytes.push(decoded);
+                        rest = tail;
+                    }
+                    None => bytes.push(b'\\'),
+                }
+            }
+            _ => bytes.push(b'\\'),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScanState {
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Finds shell marks in a stream of PTY output, which may split them across
+/// reads.
+#[derive(Debug)]
+pub struct MarkScanner {
+    state: ScanState,
+    payload: Vec<u8>,
+    newlines: usize,
+}
+
+impl Default for MarkScanner {
+    fn default() -> Self {
+        Self {
+            state: ScanState::Ground,
+            payload: Vec::new(),
+            newlines: 0,
+        }
+    }
+}
+
+impl MarkScanner {
+    /// The number of line feeds seen so far.
+    pub fn newlines(&self) -> usize {
+        self.newlines
+    }
+
+    /// Scans the given bytes, reporting each mark along with the number of
+    /// line feeds that preceded it in the stream.
+    pub fn advance(&mut self, bytes: &[u8], mut on_mark: impl FnMut(ShellMark, usize)) {
+        for &byte in bytes {
+            match self.state {
+                ScanState::Ground => match byte {
+                    b'\n' => self.newlines += 1,
+                    0x1b => self.state = ScanState::Escape,
+                    _ => {}
+                },
+                ScanState::Escape => match byte {
+                    b']' => {
+                        self.payload.clear();
+                        self.state = ScanState::Osc;
+                    }
+                    0x1b => {}
+                    b'\n' => {
+                        self.newlines += 1;
+                        self.state = ScanState::Ground;
+                    }
+                    _ => self.state = ScanState::Ground,
+                },
+                ScanState::Osc => match byte {
+                    0x07 => self.finish_osc(&mut on_mark),
+                    0x1b => self.state = ScanState::OscEscape,
+                    // Control characters other than the terminators abort the sequence.
+                    0x18 | 0x1a => self.state = ScanState::Ground,
+                    b'\n' => {
+                        self.newlines += 1;
+                        self.state = ScanState::Ground;
+                    }
+                    _ => {
+                        if self.payload.len() < MAX_PAYLOAD_LEN {
+                            self.payload.push(byte);
+                        }
+                    }
+                },
+                ScanState::OscEscape => {
+                    if byte == b'\\' {
+                        self.finish_osc(&mut on_mark);
+                    } else if byte == b']' {
+                        // The escape started a new OSC sequence.
+                        self.payload.clear();
+                        self.state = ScanState::Osc;
+                    } else {
+                        self.state = ScanState::Ground;
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish_osc(&mut self, on_mark: &mut impl FnMut(ShellMark, usize)) {
+        self.state = ScanState::Ground;
+        if self.payload.len() < MAX_PAYLOAD_LEN {
+            if let Some(mark) = ShellMark::parse(&self.payload) {
+                on_mark(mark, self.newlines);
+            }
+        }
+        self.payload.clear();
+    }
+}
+
+/// Marks found by the PTY reader, waiting to be placed on the terminal grid.
+#[derive(Debug, Default)]
+pub(crate) struct PendingMarks {
+    marks: Mutex<Vec<(ShellMark, usize)>>,
+    newlines: AtomicUsize,
+}
+
+impl PendingMarks {
+    pub(crate) fn push(&self, mark: ShellMark, newline_ix: usize) {
+        self.marks.lock().unwrap().push((mark, newline_ix));
+    }
+
+    /// Records the number of line feeds read so far.
+    pub(crate) fn set_newlines(&self, newlines: usize) {
+        self.newlines.store(newlines, Ordering::Release);
+    }
+
+    /// The number of line feeds read so far.
+    pub(crate) fn newlines(&self) -> usize {
+        self.newlines.load(Ordering::Acquire)
+    }
+
+    /// Takes the pending marks, along with the number of line feeds that were
+    /// read before each of them.
+    pub(crate) fn take(&self) -> Vec<(ShellMark, usize)> {
+        std::mem::take(&mut *self.marks.lock().unwrap())
+    }
+}
+
+/// A command the shell ran.
+///
+/// Each of its parts is located by its logical line, which is the number of
+/// line feeds the shell printed before it. Logical lines are resolved to rows
+/// of the grid whenever the grid changes, since wrapped output, reflowing and
+/// lines rotating out of the scrollback all move them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandBlock {
+    /// The absolute lines (counting from the top of the scrollback) where each
+    /// part starts, as of the last time the grid was synced.
+    pub prompt_line: usize,
+    pub input_line: Option<usize>,
+    pub output_line: Option<usize>,
+    /// The line where the next prompt started, once the command finished.
+    pub end_line: Option<usize>,
+    pub command: Option<String>,
+    pub exit_code: Option<i32>,
+    pub working_directory: Option<PathBuf>,
+    pub(crate) marks: BlockMarks,
+}
+
+/// The logical lines where each part of a [`CommandBlock`] starts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct BlockMarks {
+    pub prompt: usize,
+    pub input: Option<usize>,
+    pub output: Option<usize>,
+    pub end: Option<usize>,
+}
+
+impl CommandBlock {
+    pub(crate) fn new(prompt_line_ix: usize, working_directory: Option<PathBuf>) -> Self {
+        Self {
+            prompt_line: 0,
+            input_line: None,
+            output_line: None,
+            end_line: None,
+            command: None,
+            exit_code: None,
+            working_directory,
+            marks: BlockMarks {
+                prompt: prompt_line_ix,
+                ..BlockMarks::default()
+            },
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.marks.end.is_some()
+    }
+
+    /// Whether the command was submitted and printed its output.
+    pub fn has_output(&self) -> bool {
+        self.marks.output.is_some()
+    }
+
+    /// Whether the command finished successfully, if it reported an exit code.
+    pub fn succeeded(&self) -> Option<bool> {
+        self.exit_code.map(|code| code == 0)
+    }
+}
+
+/// Resolves the logical lines of the command blocks to the rows of the grid,
+/// given the logical line the cursor is on, and drops the blocks whose prompt
+/// rotated out of the scrollback.
+pub(crate) fn update_block_lines(
+    blocks: &mut Vec<CommandBlock>,
+    grid: &Grid<Cell>,
+    cursor_line_ix: usize,
+) {
+    let Some(oldest_ix) = blocks.first().map(|block| block.marks.prompt) else {
+        return;
+    };
+    let starts = LineStarts::new(grid, cursor_line_ix, oldest_ix);
+    blocks.retain_mut(|block| {
+        let Some(prompt_line) = starts.get(block.marks.prompt) else {
+            return false;
+        };
+        block.prompt_line = prompt_line;
+        block.input_line = block.marks.input.and_then(|ix| starts.get(ix));
+        block.output_line = block.marks.output.and_then(|ix| starts.get(ix));
+        block.end_line = block.marks.end.and_then(|ix| starts.get(ix));
+        true
+    });
+}
+
+/// The absolute lines (counting from the top of the scrollback) where recent
+/// logical lines start. A logical line spans several rows when it wraps.
+struct LineStarts {
+    first_ix: usize,
+    lines: Vec<usize>,
+}
+
+impl LineStarts {
+    /// Walks up the grid from the cursor until the start of the logical line
+    /// with index `oldest_ix`, or until the top of the scrollback.
+    fn new(grid: &Grid<Cell>, cursor_ix: usize, oldest_ix: usize) -> Self {
+        let history_size = grid.history_size() as i32;
+        let top = Line(-history_size);
+        let last_column = grid.last_column();
+        let continues_above =
+            |line: Line| line > top && grid[line - 1][last_column].flags.contains(Flags::WRAPLINE);
+
+        let mut lines = Vec::new();
+        let mut line = grid.cursor.point.line;
+        let mut ix = cursor_ix;
+        loop {
+            while continues_above(line) {
+                line -= 1;
+            }
+            lines.push((line.0 + history_size) as usize);
+            if ix <= oldest_ix || line <= top {
+                break;
+            }
+            ix -= 1;
+            line -= 1;
+        }
+        lines.reverse();
+        Self {
+            first_ix: ix,
+            lines,
+        }
+    }
+
+    /// Returns where the given logical line starts, or `None` if it rotated out
+    /// of the scrollback. Lines that weren't parsed yet are placed on the line
+    /// of the cursor.
+    fn get(&self, ix: usize) -> Option<usize> {
+        let last_ix = self.first_ix + self.lines.len() - 1;
+        self.lines
+            .get(ix.checked_sub(self.first_ix)?.min(last_ix - self.first_ix))
+            .copied()
+    }
+}
+
+/// Changes the shell invocation and environment so that the shell loads Zed's
+/// integration script. Returns `None` for shells that aren't supported.
+pub(crate) fn inject(shell: &Shell, env: &mut HashMap<String, String>) -> Option<Shell> {
+    let (program, args, login) = match shell {
+        Shell::System => {
+            let program = env
+                .get("SHELL")
+                .cloned()
+                .or_else(|| std::env::var("SHELL").ok())?;
+            // The system shell is started as a login shell on macOS.
+            (program, Vec::new(), cfg!(target_os = "macos"))
+        }
+        Shell::Program(program) => (program.clone(), Vec::new(), false),
+        Shell::WithArguments { program, args, .. } => (program.clone(), args.clone(), false),
+    };
+    let shell_name = Path::new(&program)
+        .file_name()?
+        .to_str()?
+        .trim_start_matches('-')
+        .to_string();
+    let dir = install_scripts()?;
+
+    let original_env = |env: &HashMap<String, String>, name: &str| {
+        env.get(name).cloned().or_else(|| std::env::var(name).ok())
+    };
+
+    match shell_name.as_str() {
+        "bash" => {
+            // `--init-file` replaces `~/.bashrc`, so we can't honor other arguments.
+            if !args.is_empty() {
+                return None;
+            }
+            if login {
+                env.insert("ZED_SHELL_LOGIN".to_string(), "1".to_string());
+            }
+            Some(Shell::WithArguments {
+                program,
+                args: vec![
+                    "--init-file".to_string(),
+                    dir.join("zed.bash").to_string_lossy().into_owned(),
+                ],
+                title_override: None,
+            })
+        }
+        "zsh" => {
+            if let Some(zdotdir) = original_env(env, "ZDOTDIR") {
+                env.insert("ZED_ORIGINAL_ZDOTDIR".to_string(), zdotdir);
+            }
+            env.insert(
+                "ZDOTDIR".to_string(),
+                dir.join("zsh").to_string_lossy().into_owned(),
+            );
+            env.insert(
+                "ZED_SHELL_INTEGRATION_DIR".to_string(),
+                dir.to_string_lossy().into_owned(),
+            );
+            Some(shell.clone())
+        }
+        "fish" => {
+            let data_dirs = match original_env(env, "XDG_DATA_DIRS") {
+                Some(data_dirs) => {
+                    env.insert("ZED_ORIGINAL_XDG_DATA_DIRS".to_string(), data_dirs.clone());
+                    data_dirs
+                }
+                None => "/usr/local/share:/usr/share".to_string(),
+            };
+            env.insert(
+                "XDG_DATA_DIRS".to_string(),
+                format!("{}:{data_dirs}", dir.join("fish_data").to_string_lossy()),
+            );
+            Some(shell.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Writes the integration scripts to disk, once per process.
+fn install_scripts() -> Option<&'static Path> {
+    static SCRIPTS_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+    SCRIPTS_DIR
+        .get_or_init(|| {
+            let dir = paths::shell_integration_dir();
+            write_scripts(dir)
+                .inspect_err(|error| {
+                    log::error!("failed to install terminal shell integration: {error:#}")
+                })
+                .ok()?;
+            Some(dir.clone())
+        })
+        .as_deref()
+}
+
+fn write_scripts(dir: &Path) -> Result<()> {
+    for (path, contents) in [
+        ("zed.bash", BASH_SCRIPT),
+        ("zed.zsh", ZSH_SCRIPT),
+        ("zsh/.zshenv", ZSH_ENV),
+        ("fish_data/fish/vendor_conf.d/zed.fish", FISH_SCRIPT),
+    ] {
+        let path = dir.join(path);
+        if std::fs::read_to_string(&path).is_ok_and(|existing| existing == contents) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener,
+        term::{Config, Term, test::TermSize},
+        vte::ansi::{Processor, StdSyncHandler},
+    };
+
+    use super::*;
+
+    /// Feeds output to a terminal, recording the marks in it as command blocks.
+    struct TestTerminal {
+        term: Term<VoidListener>,
+        processor: Processor<StdSyncHandler>,
+        scanner: MarkScanner,
+        blocks: Vec<CommandBlock>,
+    }
+
+    impl TestTerminal {
+        fn new(columns: usize, lines: usize, scrolling_history: usize) -> Self {
+            let config = Config {
+                scrolling_history,
+                ..Config::default()
+            };
+            Self {
+                term: Term::new(config, &TermSize::new(columns, lines), VoidListener),
+                processor: Processor::new(),
+                scanner: MarkScanner::default(),
+                blocks: Vec::new(),
+            }
+        }
+
+        fn print(&mut self, output: &str) {
+            let blocks = &mut self.blocks;
+            self.scanner
+                .advance(output.as_bytes(), |mark, line_ix| match mark {
+                    ShellMark::PromptStart => blocks.push(CommandBlock::new(line_ix, None)),
+                    ShellMark::CommandExecuted => {
+                        blocks.last_mut().unwrap().marks.output = Some(line_ix)
+                    }
+                    ShellMark::CommandFinished { .. } => {
+                        blocks.last_mut().unwrap().marks.end = Some(line_ix)
+                    }
+                    _ => {}
+                });
+            self.processor.advance(&mut self.term, output.as_bytes());
+            update_block_lines(&mut self.blocks, self.term.grid(), self.scanner.newlines());
+        }
+
+        fn block_lines(&self) -> Vec<(usize, Option<usize>, Option<usize>)> {
+            self.blocks
+                .iter()
+                .map(|block| (block.prompt_line, block.output_line, block.end_line))
+                .collect()
+        }
+    }
+
+    const PROMPT: &str = "\x1b]133;A\x07$ ";
+    const EXECUTED: &str = "\r\n\x1b]133;C\x07";
+    const FINISHED: &str = "\x1b]133;D;0\x07";
+
+    fn scan(chunks: &[&[u8]]) -> Vec<(ShellMark, usize)> {
+        let mut scanner = MarkScanner::default();
+        let mut marks = Vec::new();
+        for chunk in chunks {
+            scanner.advance(chunk, |mark, newline_ix| marks.push((mark, newline_ix)));
+        }
+        marks
+    }
+
+    #[test]
+    fn test_scan_marks() {
+        let marks = scan(&[
+            b"\x1b]633;P;Cwd=/home/user/a\\x3bb\x07\x1b]133;A\x07$ ",
+            b"\x1b]133;B\x1b\\ls\r\n\x1b]633;E;ls -la\x07\x1b]133;C\x07",
+            b"one\r\ntwo\r\n\x1b]133;D;1\x07\x1b]0;title\x07",
+        ]);
+        assert_eq!(
+            marks,
+            vec![
+                (
+                    ShellMark::WorkingDirectory(PathBuf::from("/home/user/a;b")),
+                    0
+                ),
+                (ShellMark::PromptStart, 0),
+                (ShellMark::CommandStart, 0),
+                (ShellMark::CommandLine("ls -la".to_string()), 1),
+                (ShellMark::CommandExecuted, 1),
+                (ShellMark::CommandFinished { exit_code: Some(1) }, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_marks_split_across_reads() {
+        let bytes = b"out\n\x1b]133;D;0\x1b\\\x1b]133;A\x07";
+        for split in 0..bytes.len() {
+            let (head, tail) = bytes.split_at(split);
+            assert_eq!(
+                scan(&[head, tail]),
+                vec![
+                    (ShellMark::CommandFinished { exit_code: Some(0) }, 1),
+                    (ShellMark::PromptStart, 1),
+                ],
+                "split at {split}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_scan_ignores_unrelated_sequences() {
+        assert_eq!(
+            scan(&[b"\x1b[31mred\x1b[0m\x1b]8;;http://a\x07link\x1b]8;;\x07\x1b]133;Z\x07"]),
+            Vec::new()
+        );
+        assert_eq!(
+            scan(&[b"\x1b]133;D\x07"]),
+            vec![(ShellMark::CommandFinished { exit_code: None }, 0)]
+        );
+    }
+
+    #[test]
+    fn test_block_lines_with_wrapped_output() {
+        let mut terminal = TestTerminal::new(10, 10, 100);
+        terminal.print(&format!("{PROMPT}ls{EXECUTED}"));
+        terminal.print(&format!("{}\r\nshort\r\n", "a".repeat(25)));
+        terminal.print(&format!("{FINISHED}{PROMPT}"));
+        // The long line of output wraps onto three rows.
+        assert_eq!(
+            terminal.block_lines(),
+            vec![(0, Some(1), Some(5)), (5, None, None)]
+        );
+    }
+
+    #[test]
+    fn test_block_lines_with_saturated_scrollback() {
+        let mut terminal = TestTerminal::new(10, 3, 2);
+        terminal.print(&format!("{PROMPT}one{EXECUTED}1\r\n2\r\n{FINISHED}"));
+        terminal.print(&format!("{PROMPT}two{EXECUTED}3\r\n{FINISHED}{PROMPT}"));
+        // The first prompt rotated out of the scrollback, which stays at two lines.
+        assert_eq!(terminal.term.grid().history_size(), 2);
+        assert_eq!(
+            terminal.block_lines(),
+            vec![(2, Some(3), Some(4)), (4, None, None)]
+        );
+
+        terminal.print("\r\n\r\n");
+        assert_eq!(terminal.term.grid().history_size(), 2);
+        assert_eq!(
+            terminal.block_lines(),
+            vec![(0, Some(1), Some(2)), (2, None, None)]
+        );
+
+        terminal.print("\r\n\r\n\r\n");
+        assert_eq!(terminal.block_lines(), vec![]);
+    }
+}
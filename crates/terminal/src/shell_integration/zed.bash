@@ -0,0 +1,57 @@
+# Zed shell integration for bash.
+#
+# Zed starts bash with `--init-file` pointing at this file, so the startup
+# files bash would otherwise have read have to be sourced from here.
+
+if [ -n "$ZED_SHELL_LOGIN" ]; then
+    unset ZED_SHELL_LOGIN
+    if [ -f /etc/profile ]; then
+        . /etc/profile
+    fi
+    if [ -f ~/.bash_profile ]; then
+        . ~/.bash_profile
+    elif [ -f ~/.bash_login ]; then
+        . ~/.bash_login
+    elif [ -f ~/.profile ]; then
+        . ~/.profile
+    fi
+elif [ -f ~/.bashrc ]; then
+    . ~/.bashrc
+fi
+
+if [ -n "$__zed_shell_integration_loaded" ]; then
+    return
+fi
+__zed_shell_integration_loaded=1
+
+__zed_escape() {
+    local value="${1//\\/\\\\}"
+    value="${value//;/\\x3b}"
+    printf '%s' "${value//[[:cntrl:]]/}"
+}
+
+__zed_prompt_command() {
+    local status=$?
+    if [ -n "$__zed_prompt_shown" ]; then
+        printf '\e]133;D;%s\a' "$status"
+    fi
+    __zed_prompt_shown=1
+    printf '\e]633;P;Cwd=%s\a' "$(__zed_escape "$PWD")"
+    printf '\e]133;A\a'
+    case "$PS1" in
+        *'133;B'*) ;;
+        *) PS1="${PS1}\[\e]133;B\a\]" ;;
+    esac
+    return $status
+}
+
+case "$PS0" in
+    *'133;C'*) ;;
+    *) PS0="${PS0}\e]133;C\a" ;;
+esac
+
+if [[ "$(declare -p PROMPT_COMMAND 2>/dev/null)" == "declare -a"* ]]; then
+    PROMPT_COMMAND=(__zed_prompt_command "${PROMPT_COMMAND[@]}")
+else
+    PROMPT_COMMAND="__zed_prompt_command${PROMPT_COMMAND:+; $PROMPT_COMMAND}"
+fi
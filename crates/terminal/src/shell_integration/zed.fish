@@ -0,0 +1,47 @@
+# Zed shell integration for fish.
+#
+# Zed prepends its shell integration directory to XDG_DATA_DIRS so that fish
+# loads this file from `vendor_conf.d`. Remove it again so it doesn't leak into
+# child processes.
+
+if set -q ZED_ORIGINAL_XDG_DATA_DIRS
+    set -gx XDG_DATA_DIRS $ZED_ORIGINAL_XDG_DATA_DIRS
+    set -e ZED_ORIGINAL_XDG_DATA_DIRS
+else
+    set -e XDG_DATA_DIRS
+end
+
+status is-interactive; or exit
+
+if set -q __zed_shell_integration_loaded
+    exit
+end
+set -g __zed_shell_integration_loaded 1
+
+function __zed_escape
+    string replace -a -- '\\' '\\\\' $argv | string replace -a -- ';' '\\x3b' | string replace -ra -- '[[:cntrl:]]' ''
+end
+
+function __zed_preexec --on-event fish_preexec
+    printf '\e]633;E;%s\a' (__zed_escape "$argv")
+    printf '\e]133;C\a'
+end
+
+function __zed_postexec --on-event fish_postexec
+    printf '\e]133;D;%s\a' $status
+end
+
+function __zed_prompt --on-event fish_prompt
+    printf '\e]633;P;Cwd=%s\a' (__zed_escape "$PWD")
+    printf '\e]133;A\a'
+
+    # The user's config is loaded after this file and may define its own
+    # prompt, so wrap it the first time it's shown.
+    if not functions -q __zed_original_fish_prompt
+        functions -c fish_prompt __zed_original_fish_prompt
+        function fish_prompt
+            __zed_original_fish_prompt
+            printf '\e]133;B\a'
+        end
+    end
+end
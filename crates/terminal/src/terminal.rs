@@ -2,13 +2,18 @@ pub mod mappings;
 
 pub use alacritty_terminal;
 
+mod images;
+#[cfg(unix)]
+mod pty_filter;
 mod pty_info;
+pub mod scrollback;
+mod shell_integration;
 pub mod terminal_settings;
 
 use alacritty_terminal::{
     Term,
-    event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
-    event_loop::{EventLoop, Msg, Notifier},
+    event::{Event as AlacTermEvent, EventListener, Notify, OnResize, WindowSize},
+    event_loop::{EventLoop, EventLoopSender, Msg, Notifier},
     grid::{Dimensions, Grid, Row, Scroll as AlacScroll},
     index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint},
     selection::{Selection, SelectionRange, SelectionType},
@@ -38,10 +43,12 @@ use mappings::mouse::{
 
 use collections::{HashMap, VecDeque};
 use futures::StreamExt;
+use images::{ImageGeometry, PendingImages};
 use pty_info::PtyProcessInfo;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use shell_integration::PendingMarks;
 use smol::channel::{Receiver, Sender};
 use task::{HideStrategy, Shell, TaskId};
 use terminal_settings::{AlternateScroll, CursorShape, TerminalSettings};
@@ -66,6 +73,9 @@ use gpui::{
 
 use crate::mappings::{colors::to_alac_rgb, keys::to_esc_str};
 
+pub use images::{IMAGE_PLACEHOLDER, TerminalImage, image_placeholder};
+pub use shell_integration::{CommandBlock, ShellMark};
+
 actions!(
     terminal,
     [
@@ -80,6 +90,10 @@ actions!(
         ScrollPageDown,
         ScrollToTop,
         ScrollToBottom,
+        ScrollToPreviousPrompt,
+        ScrollToNextPrompt,
+        SelectCommandOutput,
+        CopyLastCommandOutput,
         ToggleViMode,
     ]
 );
@@ -96,6 +110,9 @@ const DEBUG_TERMINAL_WIDTH: Pixels = px(500.);
 const DEBUG_TERMINAL_HEIGHT: Pixels = px(30.);
 const DEBUG_CELL_WIDTH: Pixels = px(5.);
 const DEBUG_LINE_HEIGHT: Pixels = px(5.);
+/// Older inline images are dropped, leaving blank space in their place.
+const MAX_IMAGES: usize = 256;
+const MAX_COMMAND_BLOCKS: usize = 1000;
 
 ///Upward flowing events, for changing the title and such
 #[derive(Clone, Debug)]
@@ -349,6 +366,7 @@ impl TerminalBuilder {
         cursor_shape: CursorShape,
         alternate_scroll: AlternateScroll,
         max_scroll_history_lines: Option<usize>,
+        shell_integration: bool,
         is_ssh_terminal: bool,
         window: AnyWindowHandle,
         completion_tx: Sender<()>,
@@ -374,8 +392,24 @@ impl TerminalBuilder {
 
         let mut terminal_title_override = None;
 
+        // Shell integration scripts only exist on this machine, and tasks don't run an
+        // interactive shell.
+        let shell_integration_shell = if shell_integration
+            && cfg!(unix)
+            && task.is_none()
+            && !is_ssh_terminal
+            && !debug_terminal
+        {
+            shell_integration::inject(&shell, &mut env)
+        } else {
+            None
+        };
+        let pending_shell_marks = shell_integration_shell
+            .is_some()
+            .then(|| Arc::new(PendingMarks::default()));
+
         let pty_options = {
-            let alac_shell = match shell.clone() {
+            let alac_shell = match shell_integration_shell.unwrap_or_else(|| shell.clone()) {
                 Shell::System => {
                     #[cfg(target_os = "windows")]
                     {
@@ -468,17 +502,25 @@ impl TerminalBuilder {
         let pty_info = PtyProcessInfo::new(&pty);
 
         //And connect them together
-        let event_loop = EventLoop::new(
-            term.clone(),
-            ZedListener(events_tx.clone()),
-            pty,
-            pty_options.drain_on_exit,
-            false,
-        )?;
-
-        //Kick things off
-        let pty_tx = event_loop.channel();
-        let _io_thread = event_loop.spawn(); // DANGER
+        let listener = ZedListener(events_tx.clone());
+        #[cfg(unix)]
+        let (pty_tx, pending_images) = {
+            let pending_images = Arc::new(PendingImages::default());
+            let pty = pty_filter::FilteredPty::new(
+                pty,
+                pending_images.clone(),
+                pending_shell_marks.clone(),
+            )?;
+            (
+                spawn_event_loop(term.clone(), listener, pty, pty_options.drain_on_exit)?,
+                Some(pending_images),
+            )
+        };
+        #[cfg(not(unix))]
+        let (pty_tx, pending_images) = (
+            spawn_event_loop(term.clone(), listener, pty, pty_options.drain_on_exit)?,
+            None,
+        );
 
         let terminal = Terminal {
             task,
@@ -505,6 +547,13 @@ impl TerminalBuilder {
             debug_terminal,
             is_ssh_terminal,
             python_venv_directory,
+            pending_shell_marks,
+            command_blocks: Vec::new(),
+            shell_working_directory: None,
+            pending_images,
+            images: HashMap::default(),
+            image_order: VecDeque::new(),
+            evicted_images: Vec::new(),
         };
 
         Ok(TerminalBuilder {
@@ -576,6 +625,23 @@ impl TerminalBuilder {
     }
 }
 
+fn spawn_event_loop<T>(
+    term: Arc<FairMutex<Term<ZedListener>>>,
+    listener: ZedListener,
+    pty: T,
+    drain_on_exit: bool,
+) -> Result<EventLoopSender>
+where
+    T: tty::EventedPty + OnResize + Send + 'static,
+{
+    let event_loop = EventLoop::new(term, listener, pty, drain_on_exit, false)?;
+
+    //Kick things off
+    let pty_tx = event_loop.channel();
+    let _io_thread = event_loop.spawn(); // DANGER
+    Ok(pty_tx)
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct IndexedCell {
     pub point: AlacPoint,
@@ -597,6 +663,7 @@ pub struct TerminalContent {
     pub cells: Vec<IndexedCell>,
     pub mode: TermMode,
     pub display_offset: usize,
+    pub history_size: usize,
     pub selection_text: Option<String>,
     pub selection: Option<SelectionRange>,
     pub cursor: RenderableCursor,
@@ -618,6 +685,7 @@ impl Default for TerminalContent {
             cells: Default::default(),
             mode: Default::default(),
             display_offset: Default::default(),
+            history_size: Default::default(),
             selection_text: Default::default(),
             selection: Default::default(),
             cursor: RenderableCursor {
@@ -662,6 +730,15 @@ pub struct Terminal {
     vi_mode_enabled: bool,
     debug_terminal: bool,
     is_ssh_terminal: bool,
+    pending_shell_marks: Option<Arc<PendingMarks>>,
+    command_blocks: Vec<CommandBlock>,
+    shell_working_directory: Option<PathBuf>,
+    pending_images: Option<Arc<PendingImages>>,
+    images: HashMap<u32, TerminalImage>,
+    /// Image ids, oldest first.
+    image_order: VecDeque<u32>,
+    /// Images that are no longer shown, whose textures can be released.
+    evicted_images: Vec<Arc<gpui::RenderImage>>,
 }
 
 pub struct TaskState {
@@ -743,6 +820,8 @@ impl Terminal {
                 //NOOP, Handled in render
             }
             AlacTermEvent::Wakeup => {
+                self.process_shell_marks();
+                self.process_images();
                 cx.emit(Event::Wakeup);
 
                 if self.pty_info.has_changed() {
@@ -769,6 +848,141 @@ impl Terminal {
         }
     }
 
+    /// Adds the marks the shell printed since the last wakeup to the command
+    /// blocks. They're placed on the grid the next time it's synced.
+    fn process_shell_marks(&mut self) {
+        let Some(pending_marks) = &self.pending_shell_marks else {
+            return;
+        };
+        let marks = pending_marks.take();
+        if marks.is_empty() || self.term.lock().mode().contains(TermMode::ALT_SCREEN) {
+            return;
+        }
+
+        for (mark, line_ix) in marks {
+            self.apply_shell_mark(mark, line_ix);
+        }
+    }
+
+    /// Applies a mark found on the logical line with the given index.
+    fn apply_shell_mark(&mut self, mark: ShellMark, line_ix: usize) {
+        if let ShellMark::PromptStart = mark {
+            if let Some(last_block) = self.command_blocks.last_mut() {
+                if !last_block.has_output() {
+                    // No command ran from the previous prompt.
+                    self.command_blocks.pop();
+                } else if !last_block.is_finished() {
+                    last_block.marks.end = Some(line_ix);
+                }
+            }
+            if self.command_blocks.len() >= MAX_COMMAND_BLOCKS {
+                self.command_blocks.remove(0);
+            }
+            self.command_blocks.push(CommandBlock::new(
+                line_ix,
+                self.shell_working_directory.clone(),
+            ));
+            return;
+        }
+        if let ShellMark::WorkingDirectory(path) = mark {
+            self.shell_working_directory = Some(path);
+            return;
+        }
+
+        let Some(block) = self
+            .command_blocks
+            .last_mut()
+            .filter(|block| !block.is_finished())
+        else {
+            return;
+        };
+        match mark {
+            ShellMark::CommandStart => block.marks.input = Some(line_ix),
+            ShellMark::CommandLine(command) => block.command = Some(command),
+            ShellMark::CommandExecuted => block.marks.output = Some(line_ix),
+            ShellMark::CommandFinished { exit_code } => {
+                if block.has_output() {
+                    block.marks.end = Some(line_ix);
+                    block.exit_code = exit_code;
+                }
+            }
+            ShellMark::PromptStart | ShellMark::WorkingDirectory(_) => {}
+        }
+    }
+
+    /// Returns the last `max_lines` lines of the scrollback and of the screen
+    /// up to the cursor, or `None` while a full screen application is running.
+    pub fn scrollback_text(&self, max_lines: usize) -> Option<String> {
+        scrollback::scrollback_text(&self.term.lock(), max_lines)
+    }
+
+    /// Returns the command running in the foreground of the shell, if any.
+    pub fn running_command(&self) -> Option<String> {
+        let shell_integration_command = self
+            .command_blocks
+            .last()
+            .filter(|block| block.has_output() && !block.is_finished())
+            .and_then(|block| block.command.clone());
+        if shell_integration_command.is_some() {
+            return shell_integration_command;
+        }
+
+        let foreground_pid = self.pty_info.pid()?;
+        if foreground_pid.as_u32() == self.pty_info.pid_getter().fallback_pid() {
+            return None;
+        }
+        let process = self.pty_info.current.as_ref()?;
+        if process.argv.is_empty() {
+            Some(process.name.clone())
+        } else {
+            Some(process.argv.join(" "))
+        }
+    }
+
+    /// Puts the scrollback of a previous session above what the shell printed
+    /// so far, followed by a note saying where the restored output ends.
+    pub fn restore_scrollback(&mut self, scrollback: &str, running_command: Option<&str>) {
+        scrollback::restore_scrollback(&mut self.term.lock(), scrollback, running_command);
+    }
+
+    /// The commands the shell reported through shell integration, oldest first.
+    pub fn command_blocks(&self) -> &[CommandBlock] {
+        &self.command_blocks
+    }
+
+    /// Returns the inline image with the given id, if it's still in memory.
+    pub fn image(&self, id: u32) -> Option<&TerminalImage> {
+        self.images.get(&id)
+    }
+
+    /// Takes the images that were evicted since the last call, so that their
+    /// textures can be dropped.
+    pub fn take_evicted_images(&mut self) -> Vec<Arc<gpui::RenderImage>> {
+        std::mem::take(&mut self.evicted_images)
+    }
+
+    /// Stores the images the PTY reader decoded since the last wakeup, evicting
+    /// the oldest ones once there are too many.
+    fn process_images(&mut self) {
+        let Some(pending_images) = &self.pending_images else {
+            return;
+        };
+        for image in pending_images.take() {
+            if let Some(replaced) = self.images.insert(image.id, image.clone()) {
+                self.evicted_images.push(replaced.image);
+                self.image_order.retain(|id| *id != image.id);
+            }
+            self.image_order.push_back(image.id);
+        }
+        while self.image_order.len() > MAX_IMAGES {
+            if let Some(id) = self.image_order.pop_front() {
+                if let Some(image) = self.images.remove(&id) {
+                    self.evicted_images.push(image.image);
+                }
+            }
+        }
+    }
+
     pub fn selection_started(&self) -> bool {
         self.selection_phase == SelectionPhase::Selecting
     }
@@ -789,6 +1003,14 @@ impl Terminal {
                 self.last_content.terminal_bounds = new_bounds;
 
                 self.pty_tx.0.send(Msg::Resize(new_bounds.into())).ok();
+                if let Some(pending_images) = &self.pending_images {
+                    pending_images.set_geometry(ImageGeometry {
+                        cell_width: new_bounds.cell_width.into(),
+                        cell_height: new_bounds.line_height.into(),
+                        columns: new_bounds.num_columns(),
+                        lines: new_bounds.num_lines(),
+                    });
+                }
 
                 term.resize(new_bounds);
             }
@@ -822,6 +1044,12 @@ impl Terminal {
                     term.grid_mut().reset_region((new_cursor.line + 1)..);
                 }
 
+                // The scrollback the commands and images were located in is gone.
+                self.command_blocks.clear();
+                self.evicted_images
+                    .extend(self.images.drain().map(|(_, image)| image.image));
+                self.image_order.clear();
+
                 cx.emit(Event::Wakeup);
             }
             InternalEvent::Scroll(scroll) => {
@@ -1211,6 +1439,92 @@ impl Terminal {
             .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
     }
 
+    pub fn scroll_to_previous_prompt(&mut self) {
+        let viewport_top = self.viewport_top_line();
+        if let Some(block) = self
+            .command_blocks
+            .iter()
+            .rev()
+            .find(|block| block.prompt_line < viewport_top)
+        {
+            self.scroll_to_line(block.prompt_line);
+        }
+    }
+
+    pub fn scroll_to_next_prompt(&mut self) {
+        let viewport_top = self.viewport_top_line();
+        match self
+            .command_blocks
+            .iter()
+            .find(|block| block.prompt_line > viewport_top)
+        {
+            Some(block) => self.scroll_to_line(block.prompt_line),
+            None => self.scroll_to_bottom(),
+        }
+    }
+
+    /// Selects the output of the last finished command, or of the command whose
+    /// prompt is at the top of the viewport when scrolled up.
+    pub fn select_command_output(&mut self) {
+        let viewport_top = self.viewport_top_line();
+        let scrolled_up = self.last_content.display_offset > 0;
+        let Some(range) = self
+            .command_blocks
+            .iter()
+            .rev()
+            .filter(|block| !scrolled_up || block.prompt_line <= viewport_top)
+            .find_map(|block| self.command_output_range(block))
+        else {
+            return;
+        };
+        let end = *range.end();
+        self.set_selection(Some((make_selection(&range), end)));
+    }
+
+    /// Returns the output of the last finished command.
+    pub fn last_command_output(&self) -> Option<String> {
+        let range = self
+            .command_blocks
+            .iter()
+            .rev()
+            .find_map(|block| self.command_output_range(block))?;
+        let output = self
+            .term
+            .lock()
+            .bounds_to_string(*range.start(), *range.end());
+        Some(output.trim_end().to_string())
+    }
+
+    fn command_output_range(&self, block: &CommandBlock) -> Option<RangeInclusive<AlacPoint>> {
+        let output_line = block.output_line?;
+        let end_line = block.end_line?.checked_sub(1)?;
+        if end_line < output_line {
+            return None;
+        }
+
+        // The lines were resolved against the grid when it was last synced.
+        let term = self.term.lock();
+        let history_size = self.last_content.history_size as i32;
+        let start = AlacPoint::new(Line(output_line as i32 - history_size), Column(0));
+        let end = AlacPoint::new(Line(end_line as i32 - history_size), term.last_column());
+        (start.line >= term.topmost_line() && end.line <= term.bottommost_line())
+            .then_some(start..=end)
+    }
+
+    /// The absolute line at the top of the viewport.
+    fn viewport_top_line(&self) -> usize {
+        self.last_content
+            .history_size
+            .saturating_sub(self.last_content.display_offset)
+    }
+
+    fn scroll_to_line(&mut self, line: usize) {
+        let display_offset = self.last_content.history_size.saturating_sub(line);
+        let delta = display_offset as i32 - self.last_content.display_offset as i32;
+        self.events
+            .push_back(InternalEvent::Scroll(AlacScroll::Delta(delta)));
+    }
+
     ///Resize the terminal and the PTY.
     pub fn set_size(&mut self, new_bounds: TerminalBounds) {
         if self.last_content.terminal_bounds != new_bounds {
@@ -1392,6 +1706,15 @@ impl Terminal {
             self.process_terminal_event(&e, &mut terminal, window, cx)
         }
 
+        if let Some(pending_marks) = &self.pending_shell_marks {
+            if !terminal.mode().contains(TermMode::ALT_SCREEN) {
+                shell_integration::update_block_lines(
+                    &mut self.command_blocks,
+                    terminal.grid(),
+                    pending_marks.newlines(),
+                );
+            }
+        }
         self.last_content = Self::make_content(&terminal, &self.last_content);
     }
 
@@ -1414,6 +1737,7 @@ impl Terminal {
                 .collect::<Vec<IndexedCell>>(),
             mode: content.mode,
             display_offset: content.display_offset,
+            history_size: term.grid().history_size(),
             selection_text: term.selection_to_string(),
             selection: content.selection,
             cursor: content.cursor,
@@ -46,6 +46,7 @@ pub struct TerminalSettings {
     pub default_height: Pixels,
     pub detect_venv: VenvSettings,
     pub max_scroll_history_lines: Option<usize>,
+    pub shell_integration: bool,
     pub toolbar: Toolbar,
     pub scrollbar: ScrollbarSettings,
 }
@@ -219,12 +220,28 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: 10_000
     pub max_scroll_history_lines: Option<usize>,
+    /// Whether to load Zed's integration script into bash, zsh and fish, which
+    /// marks each prompt and command in the scrollback so that you can jump
+    /// between prompts and select a command's output.
+    /// Existing terminals will not pick up this change until they are recreated.
+    ///
+    /// Default: true
+    pub shell_integration: Option<bool>,
     /// Toolbar related settings
     pub toolbar: Option<ToolbarContent>,
     /// Scrollbar-related settings
     pub scrollbar: Option<ScrollbarSettingsContent>,
 }
 
+impl TerminalSettings {
+    /// The number of lines of scrollback history that terminals keep.
+    pub fn scroll_history_lines(&self) -> usize {
+        self.max_scroll_history_lines
+            .unwrap_or(crate::DEFAULT_SCROLL_HISTORY_LINES)
+            .min(crate::MAX_SCROLL_HISTORY_LINES)
+    }
+}
+
 impl settings::Settings for TerminalSettings {
     const KEY: Option<&'static str> = Some("terminal");
 
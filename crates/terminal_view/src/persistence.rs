@@ -407,6 +407,10 @@ define_connection! {
             ALTER TABLE terminals ADD COLUMN working_directory_path TEXT;
             UPDATE terminals SET working_directory_path = CAST(working_directory AS TEXT);
         ),
+        sql! (
+            ALTER TABLE terminals ADD COLUMN scrollback TEXT;
+            ALTER TABLE terminals ADD COLUMN running_command TEXT;
+        ),
     ];
 }
 
@@ -449,6 +453,37 @@ impl TerminalDb {
         .await
     }
 
+    pub async fn save_scrollback(
+        &self,
+        item_id: ItemId,
+        workspace_id: WorkspaceId,
+        scrollback: String,
+        running_command: Option<String>,
+    ) -> Result<()> {
+        let query = "INSERT INTO terminals(item_id, workspace_id, scrollback, running_command)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT DO UPDATE SET
+                scrollback = ?3,
+                running_command = ?4";
+        self.write(move |conn| {
+            let mut statement = Statement::prepare(conn, query)?;
+            let mut next_index = statement.bind(&item_id, 1)?;
+            next_index = statement.bind(&workspace_id, next_index)?;
+            next_index = statement.bind(&scrollback, next_index)?;
+            statement.bind(&running_command, next_index)?;
+            statement.exec()
+        })
+        .await
+    }
+
+    query! {
+        pub fn get_scrollback(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<(Option<String>, Option<String>)>> {
+            SELECT scrollback, running_command
+            FROM terminals
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+
     query! {
         pub fn get_working_directory(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<PathBuf>> {
             SELECT working_directory
@@ -483,3 +518,74 @@ impl TerminalDb {
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use terminal::{
+        alacritty_terminal::{
+            Term,
+            event::VoidListener,
+            term::{Config, test::TermSize},
+            vte::ansi::{Processor, StdSyncHandler},
+        },
+        scrollback::{restore_scrollback, scrollback_text},
+    };
+
+    fn term_with_output(output: &str) -> Term<VoidListener> {
+        let mut term = Term::new(Config::default(), &TermSize::new(40, 5), VoidListener);
+        Processor::<StdSyncHandler>::new().advance(&mut term, output.as_bytes());
+        term
+    }
+
+    #[gpui::test]
+    async fn test_save_and_restore_scrollback() {
+        let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+
+        let output = (1..=8)
+            .map(|ix| format!("line {ix}\r\n"))
+            .collect::<String>()
+            + "$ sleep 100";
+        let term = term_with_output(&output);
+        // Only the last lines of the scrollback are saved.
+        let scrollback = scrollback_text(&term, 3).unwrap();
+        assert_eq!(scrollback, "line 7\nline 8\n$ sleep 100");
+
+        TERMINAL_DB
+            .save_scrollback(
+                1234,
+                workspace_id,
+                scrollback,
+                Some("sleep 100".to_string()),
+            )
+            .await
+            .unwrap();
+        let (scrollback, running_command) = TERMINAL_DB
+            .get_scrollback(1234, workspace_id)
+            .unwrap()
+            .unwrap();
+
+        let mut term = term_with_output("$ ");
+        restore_scrollback(&mut term, &scrollback.unwrap(), running_command.as_deref());
+        assert_eq!(
+            scrollback_text(&term, usize::MAX).unwrap(),
+            "line 7\nline 8\n$ sleep 100\n\
+             [Restored from the previous session, where `sleep 100` was running]\n$"
+        );
+        assert_eq!(term.grid().cursor.point.column.0, 2);
+
+        let mut term = term_with_output("$ ");
+        restore_scrollback(&mut term, "line 1", None);
+        assert_eq!(
+            scrollback_text(&term, usize::MAX).unwrap(),
+            "line 1\n[Restored from the previous session]\n$"
+        );
+
+        // Nothing is saved or restored while a full screen application is running.
+        let mut term = term_with_output("\x1b[?1049h");
+        assert_eq!(scrollback_text(&term, 3), None);
+        restore_scrollback(&mut term, "line 1", None);
+        Processor::<StdSyncHandler>::new().advance(&mut term, b"\x1b[?1049l");
+        assert_eq!(scrollback_text(&term, 3).unwrap(), "");
+    }
+}
@@ -3,7 +3,7 @@ use gpui::{
     AnyElement, App, AvailableSpace, Bounds, ContentMask, Context, DispatchPhase, Element,
     ElementId, Entity, FocusHandle, Font, FontStyle, FontWeight, GlobalElementId, HighlightStyle,
     Hitbox, Hsla, InputHandler, InteractiveElement, Interactivity, IntoElement, LayoutId,
-    ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels, Point, ShapedLine,
+    ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels, Point, RenderImage, ShapedLine,
     StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun, TextStyle, UTF16Selection,
     UnderlineStyle, WeakEntity, WhiteSpace, Window, WindowTextSystem, div, fill, point, px,
     relative, size,
@@ -12,7 +12,7 @@ use itertools::Itertools;
 use language::CursorShape;
 use settings::Settings;
 use terminal::{
-    IndexedCell, Terminal, TerminalBounds, TerminalContent,
+    IMAGE_PLACEHOLDER, IndexedCell, Terminal, TerminalBounds, TerminalContent,
     alacritty_terminal::{
         grid::Dimensions,
         index::Point as AlacPoint,
@@ -22,14 +22,21 @@ use terminal::{
             CursorShape as AlacCursorShape, NamedColor,
         },
     },
+    image_placeholder,
     terminal_settings::TerminalSettings,
 };
 use theme::{ActiveTheme, Theme, ThemeSettings};
 use ui::{ParentElement, Tooltip};
+use util::ResultExt;
 use workspace::Workspace;
 
 use std::mem;
-use std::{fmt::Debug, ops::RangeInclusive, rc::Rc};
+use std::{
+    fmt::Debug,
+    ops::{Range, RangeInclusive},
+    rc::Rc,
+    sync::Arc,
+};
 
 use crate::{BlockContext, BlockProperties, TerminalView};
 
@@ -47,6 +54,45 @@ pub struct LayoutState {
     hyperlink_tooltip: Option<AnyElement>,
     gutter: Pixels,
     block_below_cursor_element: Option<AnyElement>,
+    /// Visible rows of finished commands, colored by whether they succeeded.
+    command_markers: Vec<(Range<i32>, Hsla)>,
+    images: Vec<ImagePlacement>,
+}
+
+/// An inline image, positioned in cells relative to the top left of the viewport.
+struct ImagePlacement {
+    image: Arc<RenderImage>,
+    row: i32,
+    column: i32,
+    rows: usize,
+    columns: usize,
+}
+
+impl ImagePlacement {
+    /// Paints the image as large as it fits in its cells, keeping its aspect ratio.
+    fn paint(&self, origin: Point<Pixels>, dimensions: &TerminalBounds, window: &mut Window) {
+        let image_size = self.image.size(0);
+        if image_size.width.0 <= 0 || image_size.height.0 <= 0 {
+            return;
+        }
+        let available_width = dimensions.cell_width * self.columns as f32;
+        let available_height = dimensions.line_height * self.rows as f32;
+        let scale = (f32::from(available_width) / image_size.width.0 as f32)
+            .min(f32::from(available_height) / image_size.height.0 as f32);
+        let bounds = Bounds::new(
+            point(
+                origin.x + dimensions.cell_width * self.column as f32,
+                origin.y + dimensions.line_height * self.row as f32,
+            ),
+            size(
+                px(image_size.width.0 as f32 * scale),
+                px(image_size.height.0 as f32 * scale),
+            ),
+        );
+        window
+            .paint_image(bounds, Default::default(), self.image.clone(), 0, false)
+            .log_err();
+    }
 }
 
 /// Helper struct for converting data between Alacritty's cursor points, and displayed cursor points.
@@ -278,7 +324,7 @@ impl TerminalElement {
 
                 //Layout current cell text
                 {
-                    if !is_blank(&cell) {
+                    if !is_blank(&cell) && cell.c != IMAGE_PLACEHOLDER {
                         let cell_text = cell.c.to_string();
                         let cell_style =
                             TerminalElement::cell_style(&cell, fg, theme, text_style, hyperlink);
@@ -712,6 +758,9 @@ impl Element for TerminalElement {
                 let (last_hovered_word, hover_target) = self.terminal.update(cx, |terminal, cx| {
                     terminal.set_size(dimensions);
                     terminal.sync(window, cx);
+                    for image in terminal.take_evicted_images() {
+                        window.drop_image(image).log_err();
+                    }
 
                     if window.modifiers().secondary()
                         && bounds.contains(&window.mouse_position())
@@ -748,6 +797,12 @@ impl Element for TerminalElement {
                 } = &self.terminal.read(cx).last_content;
                 let mode = *mode;
                 let display_offset = *display_offset;
+                let command_markers = command_markers(
+                    self.terminal.read(cx),
+                    dimensions.num_lines() as i32,
+                    &theme,
+                );
+                let images = image_placements(self.terminal.read(cx), cells, display_offset);
 
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
@@ -868,6 +923,8 @@ impl Element for TerminalElement {
                     hyperlink_tooltip,
                     gutter,
                     block_below_cursor_element,
+                    command_markers,
+                    images,
                 }
             },
         )
@@ -891,6 +948,7 @@ impl Element for TerminalElement {
 
             let terminal_input_handler = TerminalInputHandler {
                 terminal: self.terminal.clone(),
+                terminal_view: self.terminal_view.clone(),
                 cursor_bounds: layout
                     .cursor
                     .as_ref()
@@ -937,6 +995,19 @@ impl Element for TerminalElement {
                         rect.paint(origin, &layout.dimensions, window);
                     }
 
+                    let marker_width = (layout.gutter * 0.25).max(px(2.));
+                    for (rows, color) in &layout.command_markers {
+                        let line_height = layout.dimensions.line_height;
+                        let marker_bounds = Bounds::new(
+                            point(
+                                origin.x - layout.gutter + (layout.gutter - marker_width) / 2.,
+                                origin.y + rows.start as f32 * line_height,
+                            ),
+                            size(marker_width, rows.len() as f32 * line_height),
+                        );
+                        window.paint_quad(fill(marker_bounds, *color));
+                    }
+
                     for (relative_highlighted_range, color) in
                         layout.relative_highlighted_ranges.iter()
                     {
@@ -954,6 +1025,10 @@ impl Element for TerminalElement {
                         }
                     }
 
+                    for image in &layout.images {
+                        image.paint(origin, &layout.dimensions, window);
+                    }
+
                     for cell in &layout.cells {
                         cell.paint(origin, &layout.dimensions, bounds, window, cx);
                     }
@@ -987,6 +1062,7 @@ impl IntoElement for TerminalElement {
 
 struct TerminalInputHandler {
     terminal: Entity<Terminal>,
+    terminal_view: Entity<TerminalView>,
     workspace: WeakEntity<Workspace>,
     cursor_bounds: Option<Bounds<Pixels>>,
 }
@@ -1038,6 +1114,11 @@ impl InputHandler for TerminalInputHandler {
         self.terminal.update(cx, |terminal, _| {
             terminal.input(text.into());
         });
+        for terminal in self.terminal_view.read(cx).broadcast_targets(cx) {
+            terminal.update(cx, |terminal, _| {
+                terminal.input(text.into());
+            });
+        }
 
         self.workspace
             .update(cx, |this, cx| {
@@ -1107,6 +1188,66 @@ pub fn is_blank(cell: &IndexedCell) -> bool {
     true
 }
 
+/// Returns the visible rows of each finished command, along with the color of
+/// its gutter marker.
+fn command_markers(
+    terminal: &Terminal,
+    visible_lines: i32,
+    theme: &Theme,
+) -> Vec<(Range<i32>, Hsla)> {
+    let content = &terminal.last_content;
+    let first_visible_line = content.history_size as i64 - content.display_offset as i64;
+    let to_row =
+        |line: usize| (line as i64 - first_visible_line).clamp(0, visible_lines as i64) as i32;
+
+    terminal
+        .command_blocks()
+        .iter()
+        .filter_map(|block| {
+            let color = match block.succeeded()? {
+                true => theme.status().success,
+                false => theme.status().error,
+            };
+            let rows = to_row(block.prompt_line)..to_row(block.end_line?);
+            (!rows.is_empty()).then_some((rows, color))
+        })
+        .collect()
+}
+
+/// Locates the images covering the visible cells, including the ones that are
+/// only partially visible.
+fn image_placements(
+    terminal: &Terminal,
+    cells: &[IndexedCell],
+    display_offset: usize,
+) -> Vec<ImagePlacement> {
+    let mut placements: Vec<ImagePlacement> = Vec::new();
+    for cell in cells {
+        let Some((id, image_row)) = image_placeholder(cell) else {
+            continue;
+        };
+        let Some(image) = terminal.image(id) else {
+            continue;
+        };
+        let column = cell.point.column.0 as i32;
+        if let Some(placement) = placements
+            .iter_mut()
+            .find(|placement| Arc::ptr_eq(&placement.image, &image.image))
+        {
+            placement.column = placement.column.min(column);
+            continue;
+        }
+        placements.push(ImagePlacement {
+            image: image.image.clone(),
+            row: cell.point.line.0 + display_offset as i32 - image_row as i32,
+            column,
+            rows: image.rows,
+            columns: image.columns,
+        });
+    }
+    placements
+}
+
 fn to_highlighted_range_lines(
     range: &RangeInclusive<AlacPoint>,
     layout: &LayoutState,
@@ -1,4 +1,4 @@
-use std::{cmp, ops::ControlFlow, path::PathBuf, sync::Arc, time::Duration};
+use std::{cmp, mem, ops::ControlFlow, path::PathBuf, sync::Arc, time::Duration};
 
 use crate::{
     TerminalView, default_working_directory,
@@ -11,9 +11,9 @@ use collections::HashMap;
 use db::kvp::KEY_VALUE_STORE;
 use futures::future::join_all;
 use gpui::{
-    Action, AnyView, App, AsyncApp, AsyncWindowContext, Context, Corner, Entity, EventEmitter,
-    ExternalPaths, FocusHandle, Focusable, IntoElement, ParentElement, Pixels, Render, Styled,
-    Task, WeakEntity, Window, actions,
+    Action, AnyView, App, AsyncApp, AsyncWindowContext, Context, Corner, Entity, EntityId,
+    EventEmitter, ExternalPaths, FocusHandle, Focusable, IntoElement, ParentElement, Pixels,
+    Render, Styled, Subscription, Task, WeakEntity, Window, actions,
 };
 use itertools::Itertools;
 use project::{Fs, Project, ProjectEntryId, terminals::TerminalKind};
@@ -36,7 +36,7 @@ use workspace::{
     SplitRight, SplitUp, SwapPaneDown, SwapPaneLeft, SwapPaneRight, SwapPaneUp, ToggleZoom,
     Workspace,
     dock::{DockPosition, Panel, PanelEvent, PanelHandle},
-    item::SerializableItem,
+    item::{ItemEvent, SerializableItem},
     move_active_item, move_item, pane,
     ui::IconName,
 };
@@ -63,6 +63,10 @@ pub fn init(cx: &mut App) {
     .detach();
 }
 
+/// A terminal view in the broadcast group, along with its terminal, which can be
+/// accessed while the view is being updated.
+pub(crate) type BroadcastMember = (WeakEntity<TerminalView>, WeakEntity<Terminal>);
+
 pub struct TerminalPanel {
     pub(crate) active_pane: Entity<Pane>,
     pub(crate) center: PaneGroup,
@@ -76,6 +80,9 @@ pub struct TerminalPanel {
     assistant_enabled: bool,
     assistant_tab_bar_button: Option<AnyView>,
     active: bool,
+    /// Terminals whose input is mirrored to each other.
+    broadcast_group: Vec<BroadcastMember>,
+    _broadcast_group_subscriptions: Vec<Subscription>,
 }
 
 impl TerminalPanel {
@@ -96,6 +103,8 @@ impl TerminalPanel {
             assistant_enabled: false,
             assistant_tab_bar_button: None,
             active: false,
+            broadcast_group: Vec::new(),
+            _broadcast_group_subscriptions: Vec::new(),
         };
         terminal_panel.apply_tab_bar_buttons(&terminal_panel.active_pane, cx);
         terminal_panel
@@ -921,6 +930,84 @@ impl TerminalPanel {
         self.assistant_enabled
     }
 
+    /// Whether input typed into the given terminal is mirrored to other terminals.
+    pub fn is_broadcasting(&self, terminal: EntityId) -> bool {
+        self.broadcast_group
+            .iter()
+            .any(|(_, member)| member.entity_id() == terminal)
+    }
+
+    /// Returns the terminals that input typed into the given terminal is mirrored to.
+    pub(crate) fn broadcast_targets(&self, terminal: EntityId) -> Vec<Entity<Terminal>> {
+        if !self.is_broadcasting(terminal) {
+            return Vec::new();
+        }
+        self.broadcast_group
+            .iter()
+            .filter(|(_, member)| member.entity_id() != terminal)
+            .filter_map(|(_, member)| member.upgrade())
+            .collect()
+    }
+
+    /// Adds the given terminal to the broadcast group, or removes it if it's
+    /// already part of it.
+    pub(crate) fn toggle_broadcast(&mut self, member: BroadcastMember, cx: &mut Context<Self>) {
+        let mut group = self.broadcast_group.clone();
+        if let Some(ix) = group
+            .iter()
+            .position(|(_, terminal)| terminal.entity_id() == member.1.entity_id())
+        {
+            group.remove(ix);
+        } else {
+            group.push(member);
+        }
+        self.set_broadcast_group(group, cx);
+    }
+
+    pub(crate) fn set_broadcast_group(
+        &mut self,
+        group: Vec<BroadcastMember>,
+        cx: &mut Context<Self>,
+    ) {
+        let group = group
+            .into_iter()
+            .filter(|(_, terminal)| terminal.is_upgradable())
+            .collect::<Vec<_>>();
+        let previous_group = mem::replace(&mut self.broadcast_group, group);
+
+        // Closed terminals leave the group.
+        self._broadcast_group_subscriptions = self
+            .broadcast_group
+            .iter()
+            .filter_map(|(terminal_view, _)| {
+                let terminal_view = terminal_view.upgrade()?;
+                let terminal_view_id = terminal_view.entity_id();
+                Some(
+                    cx.observe_release(&terminal_view, move |terminal_panel, _, cx| {
+                        let mut group = terminal_panel.broadcast_group.clone();
+                        group.retain(|(member, _)| member.entity_id() != terminal_view_id);
+                        terminal_panel.set_broadcast_group(group, cx);
+                    }),
+                )
+            })
+            .collect();
+
+        // Deferred, as the terminal that changed the group is usually being updated.
+        let changed_views = previous_group
+            .into_iter()
+            .chain(self.broadcast_group.iter().cloned())
+            .map(|(terminal_view, _)| terminal_view)
+            .collect::<Vec<_>>();
+        cx.defer(move |cx| {
+            for terminal_view in changed_views {
+                terminal_view
+                    .update(cx, |_, cx| cx.emit(ItemEvent::UpdateTab))
+                    .ok();
+            }
+        });
+        cx.notify();
+    }
+
     fn is_enabled(&self, cx: &App) -> bool {
         self.workspace.upgrade().map_or(false, |workspace| {
             is_enabled_in_workspace(workspace.read(cx), cx)
@@ -1498,3 +1585,179 @@ impl Render for InlineAssistTabBarButton {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use workspace::{AppState, SaveIntent};
+
+    #[gpui::test]
+    async fn test_join_and_leave_broadcast_group(cx: &mut TestAppContext) {
+        let (terminal_panel, cx) = init_test(cx).await;
+        let [a, b, c] = add_terminals(&terminal_panel, cx).await;
+
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel.toggle_broadcast(member(&a, cx), cx);
+            terminal_panel.toggle_broadcast(member(&b, cx), cx);
+        });
+        cx.read(|cx| {
+            assert!(a.read(cx).is_broadcasting(cx));
+            assert!(b.read(cx).is_broadcasting(cx));
+            assert!(!c.read(cx).is_broadcasting(cx));
+            assert_eq!(targets(&a, cx), vec![terminal_id(&b, cx)]);
+            assert_eq!(targets(&b, cx), vec![terminal_id(&a, cx)]);
+            assert_eq!(targets(&c, cx), Vec::new());
+        });
+
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel.toggle_broadcast(member(&a, cx), cx);
+        });
+        cx.read(|cx| {
+            assert!(!a.read(cx).is_broadcasting(cx));
+            assert!(b.read(cx).is_broadcasting(cx));
+            assert_eq!(targets(&a, cx), Vec::new());
+            assert_eq!(targets(&b, cx), Vec::new());
+        });
+
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel.set_broadcast_group(Vec::new(), cx);
+        });
+        cx.read(|cx| {
+            assert!(!b.read(cx).is_broadcasting(cx));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_broadcast_input_to_group(cx: &mut TestAppContext) {
+        let (terminal_panel, cx) = init_test(cx).await;
+        let [a, b, c] = add_terminals(&terminal_panel, cx).await;
+
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            let group = vec![member(&a, cx), member(&b, cx), member(&c, cx)];
+            terminal_panel.set_broadcast_group(group, cx);
+        });
+        cx.read(|cx| {
+            assert_eq!(
+                targets(&a, cx),
+                vec![terminal_id(&b, cx), terminal_id(&c, cx)]
+            );
+            assert_eq!(
+                targets(&b, cx),
+                vec![terminal_id(&a, cx), terminal_id(&c, cx)]
+            );
+            assert_eq!(
+                targets(&c, cx),
+                vec![terminal_id(&a, cx), terminal_id(&b, cx)]
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_closed_terminals_leave_broadcast_group(cx: &mut TestAppContext) {
+        let (terminal_panel, cx) = init_test(cx).await;
+        let [a, b, c] = add_terminals(&terminal_panel, cx).await;
+
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            let group = vec![member(&a, cx), member(&b, cx), member(&c, cx)];
+            terminal_panel.set_broadcast_group(group, cx);
+        });
+
+        let pane =
+            terminal_panel.read_with(cx, |terminal_panel, _| terminal_panel.active_pane.clone());
+        let b_id = b.entity_id();
+        drop(b);
+        pane.update_in(cx, |pane, window, cx| {
+            pane.close_item_by_id(b_id, SaveIntent::Skip, window, cx)
+        })
+        .await
+        .unwrap();
+        cx.run_until_parked();
+
+        terminal_panel.read_with(cx, |terminal_panel, _| {
+            assert_eq!(
+                terminal_panel
+                    .broadcast_group
+                    .iter()
+                    .map(|(terminal_view, _)| terminal_view.entity_id())
+                    .collect::<Vec<_>>(),
+                vec![a.entity_id(), c.entity_id()]
+            );
+        });
+        cx.read(|cx| {
+            assert_eq!(targets(&a, cx), vec![terminal_id(&c, cx)]);
+            assert_eq!(targets(&c, cx), vec![terminal_id(&a, cx)]);
+        });
+    }
+
+    async fn init_test(cx: &mut TestAppContext) -> (Entity<TerminalPanel>, &mut VisualTestContext) {
+        // Terminals are backed by real shell processes.
+        cx.executor().allow_parking();
+
+        let params = cx.update(AppState::test);
+        cx.update(|cx| {
+            terminal::init(cx);
+            Project::init_settings(cx);
+            language::init(cx);
+        });
+
+        let project = Project::test(params.fs.clone(), [], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project, window, cx));
+        let terminal_panel = workspace.update_in(cx, |workspace, window, cx| {
+            let terminal_panel = cx.new(|cx| TerminalPanel::new(workspace, window, cx));
+            workspace.add_panel(terminal_panel.clone(), window, cx);
+            terminal_panel
+        });
+        (terminal_panel, cx)
+    }
+
+    async fn add_terminals(
+        terminal_panel: &Entity<TerminalPanel>,
+        cx: &mut VisualTestContext,
+    ) -> [Entity<TerminalView>; 3] {
+        for _ in 0..3 {
+            terminal_panel
+                .update_in(cx, |terminal_panel, window, cx| {
+                    terminal_panel.add_terminal(
+                        TerminalKind::Shell(None),
+                        RevealStrategy::Never,
+                        window,
+                        cx,
+                    )
+                })
+                .await
+                .unwrap();
+        }
+        terminal_panel.read_with(cx, |terminal_panel, cx| {
+            terminal_panel
+                .active_pane
+                .read(cx)
+                .items()
+                .filter_map(|item| item.downcast::<TerminalView>())
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap()
+        })
+    }
+
+    fn member(terminal_view: &Entity<TerminalView>, cx: &App) -> BroadcastMember {
+        (
+            terminal_view.downgrade(),
+            terminal_view.read(cx).terminal().downgrade(),
+        )
+    }
+
+    fn terminal_id(terminal_view: &Entity<TerminalView>, cx: &App) -> EntityId {
+        terminal_view.read(cx).terminal().entity_id()
+    }
+
+    fn targets(terminal_view: &Entity<TerminalView>, cx: &App) -> Vec<EntityId> {
+        terminal_view
+            .read(cx)
+            .broadcast_targets(cx)
+            .iter()
+            .map(|terminal| terminal.entity_id())
+            .collect()
+    }
+}
@@ -6,18 +6,20 @@ pub mod terminal_tab_tooltip;
 
 use editor::{Editor, EditorSettings, actions::SelectAll, scroll::ScrollbarAutoHide};
 use gpui::{
-    AnyElement, App, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, KeyContext,
-    KeyDownEvent, Keystroke, MouseButton, MouseDownEvent, Pixels, Render, ScrollWheelEvent,
-    Stateful, Styled, Subscription, Task, WeakEntity, anchored, deferred, div, impl_actions,
+    AnyElement, App, ClipboardItem, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
+    KeyContext, KeyDownEvent, Keystroke, MouseButton, MouseDownEvent, Pixels, Render,
+    ScrollWheelEvent, Stateful, Styled, Subscription, Task, WeakEntity, actions, anchored,
+    deferred, div, impl_actions,
 };
 use itertools::Itertools;
 use persistence::TERMINAL_DB;
 use project::{Entry, Metadata, Project, search::SearchQuery, terminals::TerminalKind};
 use schemars::JsonSchema;
 use terminal::{
-    Clear, Copy, Event, MaybeNavigationTarget, Paste, ScrollLineDown, ScrollLineUp, ScrollPageDown,
-    ScrollPageUp, ScrollToBottom, ScrollToTop, ShowCharacterPalette, TaskState, TaskStatus,
-    Terminal, TerminalBounds, ToggleViMode,
+    Clear, Copy, CopyLastCommandOutput, Event, MaybeNavigationTarget, Paste, ScrollLineDown,
+    ScrollLineUp, ScrollPageDown, ScrollPageUp, ScrollToBottom, ScrollToNextPrompt,
+    ScrollToPreviousPrompt, ScrollToTop, SelectCommandOutput, ShowCharacterPalette, TaskState,
+    TaskStatus, Terminal, TerminalBounds, ToggleViMode,
     alacritty_terminal::{
         index::Point,
         term::{TermMode, search::RegexSearch},
@@ -77,6 +79,18 @@ pub struct SendKeystroke(String);
 
 impl_actions!(terminal, [SendText, SendKeystroke]);
 
+actions!(
+    terminal,
+    [
+        /// Adds the terminal to the group of terminals that input is mirrored to,
+        /// or removes it from the group.
+        ToggleBroadcastInput,
+        /// Mirrors input to all terminals in the pane.
+        BroadcastInputToPane,
+        StopBroadcastingInput,
+    ]
+);
+
 pub fn init(cx: &mut App) {
     terminal_panel::init(cx);
     terminal::init(cx);
@@ -209,11 +223,50 @@ impl TerminalView {
                 focus_in,
                 focus_out,
                 cx.observe_global::<SettingsStore>(Self::settings_changed),
+                cx.on_app_quit(|this, cx| {
+                    let save_scrollback = this.save_scrollback(cx.entity_id().as_u64(), cx);
+                    async move {
+                        if let Some(save_scrollback) = save_scrollback {
+                            save_scrollback.await.log_err();
+                        }
+                    }
+                }),
+                cx.on_release({
+                    let item_id = cx.entity_id().as_u64();
+                    move |this, cx| {
+                        if let Some(save_scrollback) = this.save_scrollback(item_id, cx) {
+                            save_scrollback.detach_and_log_err(cx);
+                        }
+                    }
+                }),
             ],
             _terminal_subscriptions: terminal_subscriptions,
         }
     }
 
+    /// Saves the scrollback, so that it's restored along with the terminal the
+    /// next time the workspace is opened. Happens whenever the terminal is
+    /// serialized, and when it's closed or the app quits.
+    fn save_scrollback(
+        &self,
+        item_id: workspace::ItemId,
+        cx: &App,
+    ) -> Option<Task<anyhow::Result<()>>> {
+        let terminal = self.terminal.read(cx);
+        if terminal.task().is_some() || terminal.debug_terminal() {
+            return None;
+        }
+        let max_lines = TerminalSettings::get_global(cx).scroll_history_lines();
+        let (workspace_id, scrollback) =
+            self.workspace_id.zip(terminal.scrollback_text(max_lines))?;
+        let running_command = terminal.running_command();
+        Some(cx.background_spawn(async move {
+            TERMINAL_DB
+                .save_scrollback(item_id, workspace_id, scrollback, running_command)
+                .await
+        }))
+    }
+
     pub fn entity(&self) -> &Entity<Terminal> {
         &self.terminal
     }
@@ -240,6 +293,7 @@ impl TerminalView {
             .map_or(false, |terminal_panel| {
                 terminal_panel.read(cx).assistant_enabled()
             });
+        let broadcasting = self.is_broadcasting(cx);
         let context_menu = ContextMenu::build(window, cx, |menu, _, _| {
             menu.context(self.focus_handle.clone())
                 .action("New Terminal", Box::new(NewTerminal))
@@ -253,6 +307,22 @@ impl TerminalView {
                         .action("Inline Assist", Box::new(InlineAssist::default()))
                 })
                 .separator()
+                .action(
+                    if broadcasting {
+                        "Stop Broadcasting to This Terminal"
+                    } else {
+                        "Broadcast Input to This Terminal"
+                    },
+                    Box::new(ToggleBroadcastInput),
+                )
+                .action(
+                    "Broadcast Input to All in Pane",
+                    Box::new(BroadcastInputToPane),
+                )
+                .when(broadcasting, |menu| {
+                    menu.action("Stop Broadcasting Input", Box::new(StopBroadcastingInput))
+                })
+                .separator()
                 .action(
                     "Close Terminal Tab",
                     Box::new(CloseActiveItem {
@@ -458,6 +528,50 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn scroll_to_previous_prompt(
+        &mut self,
+        _: &ScrollToPreviousPrompt,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_prompt());
+        cx.notify();
+    }
+
+    fn scroll_to_next_prompt(
+        &mut self,
+        _: &ScrollToNextPrompt,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_prompt());
+        cx.notify();
+    }
+
+    fn select_command_output(
+        &mut self,
+        _: &SelectCommandOutput,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.select_command_output());
+        cx.notify();
+    }
+
+    fn copy_last_command_output(
+        &mut self,
+        _: &CopyLastCommandOutput,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(output) = self.terminal.read(cx).last_command_output() {
+            cx.write_to_clipboard(ClipboardItem::new_string(output));
+        }
+    }
+
     fn toggle_vi_mode(&mut self, _: &ToggleViMode, _: &mut Window, cx: &mut Context<Self>) {
         self.terminal.update(cx, |term, _| term.toggle_vi_mode());
         cx.notify();
@@ -565,23 +679,133 @@ impl TerminalView {
     ///Attempt to paste the clipboard into the terminal
     fn paste(&mut self, _: &Paste, _: &mut Window, cx: &mut Context<Self>) {
         if let Some(clipboard_string) = cx.read_from_clipboard().and_then(|item| item.text()) {
-            self.terminal
-                .update(cx, |terminal, _cx| terminal.paste(&clipboard_string));
+            for terminal in self.input_terminals(cx) {
+                terminal.update(cx, |terminal, _cx| terminal.paste(&clipboard_string));
+            }
         }
     }
 
     fn send_text(&mut self, text: &SendText, _: &mut Window, cx: &mut Context<Self>) {
         self.clear_bell(cx);
-        self.terminal.update(cx, |term, _| {
-            term.input(text.0.to_string());
-        });
+        for terminal in self.input_terminals(cx) {
+            terminal.update(cx, |term, _| {
+                term.input(text.0.to_string());
+            });
+        }
     }
 
     fn send_keystroke(&mut self, text: &SendKeystroke, _: &mut Window, cx: &mut Context<Self>) {
         if let Some(keystroke) = Keystroke::parse(&text.0).log_err() {
             self.clear_bell(cx);
-            self.terminal.update(cx, |term, cx| {
-                term.try_keystroke(&keystroke, TerminalSettings::get_global(cx).option_as_meta);
+            for terminal in self.input_terminals(cx) {
+                terminal.update(cx, |term, cx| {
+                    term.try_keystroke(&keystroke, TerminalSettings::get_global(cx).option_as_meta);
+                });
+            }
+        }
+    }
+
+    fn terminal_panel(&self, cx: &App) -> Option<Entity<TerminalPanel>> {
+        self.workspace
+            .upgrade()
+            .and_then(|workspace| workspace.read(cx).panel::<TerminalPanel>(cx))
+    }
+
+    /// Whether input typed into this terminal is mirrored to other terminals.
+    pub fn is_broadcasting(&self, cx: &App) -> bool {
+        self.terminal_panel(cx).map_or(false, |terminal_panel| {
+            terminal_panel
+                .read(cx)
+                .is_broadcasting(self.terminal.entity_id())
+        })
+    }
+
+    /// Returns the other terminals that input typed into this one is mirrored to.
+    pub(crate) fn broadcast_targets(&self, cx: &App) -> Vec<Entity<Terminal>> {
+        self.terminal_panel(cx)
+            .map(|terminal_panel| {
+                terminal_panel
+                    .read(cx)
+                    .broadcast_targets(self.terminal.entity_id())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns this terminal, followed by the terminals its input is mirrored to.
+    fn input_terminals(&self, cx: &App) -> Vec<Entity<Terminal>> {
+        let mut terminals = vec![self.terminal.clone()];
+        terminals.extend(self.broadcast_targets(cx));
+        terminals
+    }
+
+    fn toggle_broadcast_input(
+        &mut self,
+        _: &ToggleBroadcastInput,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(terminal_panel) = self.terminal_panel(cx) else {
+            return;
+        };
+        let member = (cx.entity().downgrade(), self.terminal.downgrade());
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel.toggle_broadcast(member, cx)
+        });
+    }
+
+    fn broadcast_input_to_pane(
+        &mut self,
+        _: &BroadcastInputToPane,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let Some(terminal_panel) = self.terminal_panel(cx) else {
+            return;
+        };
+        let this_id = cx.entity_id();
+        let pane = terminal_panel
+            .read(cx)
+            .center
+            .panes()
+            .into_iter()
+            .chain(workspace.read(cx).panes())
+            .find(|pane| pane.read(cx).items().any(|item| item.item_id() == this_id))
+            .cloned();
+        let Some(pane) = pane else {
+            return;
+        };
+
+        let group = pane
+            .read(cx)
+            .items()
+            .filter_map(|item| item.downcast::<TerminalView>())
+            .map(|terminal_view| {
+                // This view is being updated, so its terminal can't be read through it.
+                let terminal = if terminal_view.entity_id() == this_id {
+                    self.terminal.downgrade()
+                } else {
+                    terminal_view.read(cx).terminal.downgrade()
+                };
+                (terminal_view.downgrade(), terminal)
+            })
+            .collect();
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel.set_broadcast_group(group, cx)
+        });
+    }
+
+    fn stop_broadcasting_input(
+        &mut self,
+        _: &StopBroadcastingInput,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(terminal_panel) = self.terminal_panel(cx) {
+            terminal_panel.update(cx, |terminal_panel, cx| {
+                terminal_panel.set_broadcast_group(Vec::new(), cx)
             });
         }
     }
@@ -1215,15 +1439,18 @@ impl TerminalView {
         self.clear_bell(cx);
         self.pause_cursor_blinking(window, cx);
 
-        self.terminal.update(cx, |term, cx| {
-            let handled = term.try_keystroke(
-                &event.keystroke,
-                TerminalSettings::get_global(cx).option_as_meta,
-            );
-            if handled {
-                cx.stop_propagation();
-            }
+        let option_as_meta = TerminalSettings::get_global(cx).option_as_meta;
+        let handled = self.terminal.update(cx, |term, _| {
+            term.try_keystroke(&event.keystroke, option_as_meta)
         });
+        if handled {
+            for terminal in self.broadcast_targets(cx) {
+                terminal.update(cx, |term, _| {
+                    term.try_keystroke(&event.keystroke, option_as_meta)
+                });
+            }
+            cx.stop_propagation();
+        }
     }
 
     fn focus_in(&mut self, window: &mut Window, cx: &mut Context<Self>) {
@@ -1270,7 +1497,14 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::scroll_page_down))
             .on_action(cx.listener(TerminalView::scroll_to_top))
             .on_action(cx.listener(TerminalView::scroll_to_bottom))
+            .on_action(cx.listener(TerminalView::scroll_to_previous_prompt))
+            .on_action(cx.listener(TerminalView::scroll_to_next_prompt))
+            .on_action(cx.listener(TerminalView::select_command_output))
+            .on_action(cx.listener(TerminalView::copy_last_command_output))
             .on_action(cx.listener(TerminalView::toggle_vi_mode))
+            .on_action(cx.listener(TerminalView::toggle_broadcast_input))
+            .on_action(cx.listener(TerminalView::broadcast_input_to_pane))
+            .on_action(cx.listener(TerminalView::stop_broadcasting_input))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
             .on_key_down(cx.listener(Self::key_down))
@@ -1389,6 +1623,13 @@ impl Item for TerminalView {
                     }),
             )
             .child(Label::new(title).color(params.text_color()))
+            .when(self.is_broadcasting(cx), |this| {
+                this.child(
+                    Icon::new(IconName::Keyboard)
+                        .size(IconSize::XSmall)
+                        .color(Color::Accent),
+                )
+            })
             .into_any()
     }
 
@@ -1527,15 +1768,17 @@ impl SerializableItem for TerminalView {
             return None;
         }
 
-        if let Some((cwd, workspace_id)) = terminal.working_directory().zip(self.workspace_id) {
-            Some(cx.background_spawn(async move {
-                TERMINAL_DB
-                    .save_working_directory(item_id, workspace_id, cwd)
-                    .await
-            }))
-        } else {
-            None
-        }
+        let (cwd, workspace_id) = terminal.working_directory().zip(self.workspace_id)?;
+        let save_scrollback = self.save_scrollback(item_id, cx);
+        Some(cx.background_spawn(async move {
+            TERMINAL_DB
+                .save_working_directory(item_id, workspace_id, cwd)
+                .await?;
+            if let Some(save_scrollback) = save_scrollback {
+                save_scrollback.await?;
+            }
+            Ok(())
+        }))
     }
 
     fn should_serialize(&self, event: &Self::Event) -> bool {
@@ -1577,6 +1820,17 @@ impl SerializableItem for TerminalView {
                     project.create_terminal(TerminalKind::Shell(cwd), window_handle, cx)
                 })?
                 .await?;
+            if let Some((Some(scrollback), running_command)) = TERMINAL_DB
+                .get_scrollback(item_id, workspace_id)
+                .log_err()
+                .flatten()
+            {
+                if !scrollback.is_empty() {
+                    terminal.update(cx, |terminal, _| {
+                        terminal.restore_scrollback(&scrollback, running_command.as_deref())
+                    })?;
+                }
+            }
             cx.update(|window, cx| {
                 cx.new(|cx| {
                     TerminalView::new(
@@ -0,0 +1,348 @@
+use anyhow::{Result, anyhow};
+use std::{fmt, str};
+
+/// The encodings offered when choosing how to read or write a file, in the
+/// order they're presented.
+static ENCODINGS: [&encoding_rs::Encoding; 20] = [
+    &encoding_rs::UTF_8_INIT,
+    &encoding_rs::UTF_16LE_INIT,
+    &encoding_rs::UTF_16BE_INIT,
+    &encoding_rs::WINDOWS_1252_INIT,
+    &encoding_rs::ISO_8859_2_INIT,
+    &encoding_rs::ISO_8859_5_INIT,
+    &encoding_rs::ISO_8859_7_INIT,
+    &encoding_rs::ISO_8859_15_INIT,
+    &encoding_rs::WINDOWS_1250_INIT,
+    &encoding_rs::WINDOWS_1251_INIT,
+    &encoding_rs::WINDOWS_1253_INIT,
+    &encoding_rs::WINDOWS_1254_INIT,
+    &encoding_rs::KOI8_R_INIT,
+    &encoding_rs::SHIFT_JIS_INIT,
+    &encoding_rs::EUC_JP_INIT,
+    &encoding_rs::ISO_2022_JP_INIT,
+    &encoding_rs::EUC_KR_INIT,
+    &encoding_rs::GBK_INIT,
+    &encoding_rs::GB18030_INIT,
+    &encoding_rs::BIG5_INIT,
+];
+
+/// How many bytes are inspected when guessing the legacy encoding of a file.
+const DETECTION_SAMPLE_LEN: usize = 64 * 1024;
+
+/// The character encoding of a file on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Encoding {
+    encoding: &'static encoding_rs::Encoding,
+    bom: bool,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Self::UTF_8
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.bom {
+            write!(f, "{} with BOM", self.name())
+        } else {
+            f.write_str(self.name())
+        }
+    }
+}
+
+impl Encoding {
+    pub const UTF_8: Self = Self {
+        encoding: &encoding_rs::UTF_8_INIT,
+        bom: false,
+    };
+
+    /// Returns the encoding with the given name, or with any of the labels
+    /// that the WHATWG Encoding Standard defines for it.
+    pub fn for_label(label: &str) -> Option<Self> {
+        encoding_rs::Encoding::for_label(label.as_bytes()).map(|encoding| Self {
+            encoding,
+            bom: false,
+        })
+    }
+
+    /// Returns every encoding that files can be read and written in.
+    pub fn all() -> impl Iterator<Item = Self> {
+        ENCODINGS.into_iter().flat_map(|encoding| {
+            let encoding = Self {
+                encoding,
+                bom: false,
+            };
+            let with_bom = encoding.is_unicode().then(|| encoding.with_bom(true));
+            [encoding].into_iter().chain(with_bom)
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+
+    /// Whether files in this encoding start with a byte order mark.
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
+
+    /// Returns this encoding with or without a byte order mark. Only the
+    /// Unicode encodings can have one.
+    pub fn with_bom(self, bom: bool) -> Self {
+        Self {
+            encoding: self.encoding,
+            bom: bom && self.is_unicode(),
+        }
+    }
+
+    fn is_unicode(&self) -> bool {
+        self.encoding == encoding_rs::UTF_8
+            || self.encoding == encoding_rs::UTF_16LE
+            || self.encoding == encoding_rs::UTF_16BE
+    }
+
+    /// Guesses the encoding of a file's contents, returning `None` if they
+    /// don't look like text.
+    ///
+    /// A byte order mark always decides the encoding. Otherwise valid UTF-8 is
+    /// taken as UTF-8, other contents with NUL bytes as binary, then the legacy
+    /// CJK encodings are tried, and anything else is assumed to be Windows-1252.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(bytes) {
+            return Some(Self {
+                encoding,
+                bom: true,
+            });
+        }
+        if str::from_utf8(bytes).is_ok() {
+            return Some(Self::UTF_8);
+        }
+        // NUL bytes only mean a file isn't text if it isn't valid UTF-8.
+        if bytes.contains(&0) {
+            return None;
+        }
+
+        // Only inspect the start of large files, ending the sample on a line
+        // break so that it doesn't cut a multi-byte character in half.
+        let sample = if bytes.len() > DETECTION_SAMPLE_LEN {
+            let sample = &bytes[..DETECTION_SAMPLE_LEN];
+            match sample.iter().rposition(|byte| *byte == b'\n') {
+                Some(ix) => &sample[..ix],
+                None => sample,
+            }
+        } else {
+            bytes
+        };
+        let encoding = [
+            encoding_rs::SHIFT_JIS,
+            encoding_rs::EUC_JP,
+            encoding_rs::EUC_KR,
+            encoding_rs::GBK,
+            encoding_rs::BIG5,
+        ]
+        .into_iter()
+        .find(|encoding| is_plausible_cjk(encoding, sample))
+        .unwrap_or(encoding_rs::WINDOWS_1252);
+        Some(Self {
+            encoding,
+            bom: false,
+        })
+    }
+
+    /// Decodes a file's contents, replacing malformed sequences with U+FFFD.
+    ///
+    /// A byte order mark at the start of the contents takes precedence over
+    /// this encoding, so the encoding that was actually used is returned along
+    /// with the text.
+    pub fn decode(&self, bytes: &[u8]) -> (String, Self) {
+        let (encoding, bom_len) = match encoding_rs::Encoding::for_bom(bytes) {
+            Some((encoding, bom_len)) => (encoding, bom_len),
+            None => (self.encoding, 0),
+        };
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        let encoding = Self {
+            encoding,
+            bom: bom_len > 0,
+        };
+        (text.into_owned(), encoding)
+    }
+
+    /// Returns a decoder for reading a file in this encoding a chunk at a
+    /// time. Like [`Encoding::decode`], it skips a byte order mark at the
+    /// start of the file.
+    pub fn decoder(&self) -> TextDecoder {
+        TextDecoder {
+            decoder: self.encoding.new_decoder_with_bom_removal(),
+        }
+    }
+
+    /// Encodes text to be written to a file, failing if it contains characters
+    /// that can't be represented in this encoding.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        if self.encoding == encoding_rs::UTF_16LE || self.encoding == encoding_rs::UTF_16BE {
+            // encoding_rs only decodes UTF-16, so it's encoded by hand.
+            let little_endian = self.encoding == encoding_rs::UTF_16LE;
+            if self.bom {
+                bytes.extend_from_slice(&encode_utf16_unit(0xfeff, little_endian));
+            }
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&encode_utf16_unit(unit, little_endian));
+            }
+        } else {
+            if self.bom {
+                bytes.extend_from_slice(b"\xef\xbb\xbf");
+            }
+            let (encoded, _, has_unmappable_characters) = self.encoding.encode(text);
+            if has_unmappable_characters {
+                return Err(anyhow!(
+                    "the text contains characters that can't be encoded in {}",
+                    self.name()
+                ));
+            }
+            bytes.extend_from_slice(&encoded);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Decodes a file incrementally, carrying characters that are split between
+/// chunks over to the next one.
+pub struct TextDecoder {
+    decoder: encoding_rs::Decoder,
+}
+
+impl TextDecoder {
+    /// Decodes the next chunk of the file, replacing malformed sequences with
+    /// U+FFFD. `last` must be set for the final chunk, so that an incomplete
+    /// trailing sequence isn't silently dropped.
+    pub fn decode(&mut self, bytes: &[u8], last: bool) -> String {
+        let capacity = self
+            .decoder
+            .max_utf8_buffer_length(bytes.len())
+            .unwrap_or(bytes.len());
+        let mut text = String::with_capacity(capacity);
+        self.decoder.decode_to_string(bytes, &mut text, last);
+        text
+    }
+}
+
+fn encode_utf16_unit(unit: u16, little_endian: bool) -> [u8; 2] {
+    if little_endian {
+        unit.to_le_bytes()
+    } else {
+        unit.to_be_bytes()
+    }
+}
+
+/// Whether the given bytes decode cleanly in a CJK encoding and most of the
+/// resulting non-ASCII characters belong to the scripts it's used for.
+fn is_plausible_cjk(encoding: &'static encoding_rs::Encoding, bytes: &[u8]) -> bool {
+    let Some(text) = encoding.decode_without_bom_handling_and_without_replacement(bytes) else {
+        return false;
+    };
+
+    let is_japanese = encoding == encoding_rs::SHIFT_JIS || encoding == encoding_rs::EUC_JP;
+    let is_korean = encoding == encoding_rs::EUC_KR;
+    let mut non_ascii_count = 0;
+    let mut expected_count = 0;
+    let mut kana_count = 0;
+    for c in text.chars().filter(|c| !c.is_ascii()) {
+        non_ascii_count += 1;
+        let is_kana = matches!(c, '\u{3040}'..='\u{30ff}');
+        let is_expected = match c {
+            // CJK punctuation and full-width forms.
+            '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff60}' => true,
+            '\u{ac00}'..='\u{d7af}' => is_korean,
+            '\u{4e00}'..='\u{9fff}' => !is_korean,
+            _ => is_kana && is_japanese,
+        };
+        if is_kana {
+            kana_count += 1;
+        }
+        if is_expected {
+            expected_count += 1;
+        }
+    }
+
+    // Chinese text often decodes cleanly as Japanese kanji, but Japanese text
+    // is rarely written without any kana.
+    if is_japanese && kana_count == 0 {
+        return false;
+    }
+    expected_count * 10 >= non_ascii_count * 9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Encoding::detect(b"plain text"), Some(Encoding::UTF_8));
+        assert_eq!(
+            Encoding::detect("caf\u{e9}".as_bytes()),
+            Some(Encoding::UTF_8)
+        );
+        assert_eq!(
+            Encoding::detect(b"\xef\xbb\xbftext"),
+            Some(Encoding::UTF_8.with_bom(true))
+        );
+        assert_eq!(
+            Encoding::detect(b"\xff\xfet\x00"),
+            Encoding::for_label("utf-16le").map(|encoding| encoding.with_bom(true))
+        );
+        assert_eq!(Encoding::detect(b"\x00\x01\xff"), None);
+        assert_eq!(Encoding::detect(b"a\x00b"), Some(Encoding::UTF_8));
+
+        let (shift_jis, _, _) = encoding_rs::SHIFT_JIS.encode("これは日本語です。\n");
+        assert_eq!(
+            Encoding::detect(&shift_jis),
+            Encoding::for_label("shift_jis")
+        );
+        let (gbk, _, _) = encoding_rs::GBK.encode("这是中文。\n");
+        assert_eq!(Encoding::detect(&gbk), Encoding::for_label("gbk"));
+        let (euc_kr, _, _) = encoding_rs::EUC_KR.encode("한국어입니다.\n");
+        assert_eq!(Encoding::detect(&euc_kr), Encoding::for_label("euc-kr"));
+        assert_eq!(
+            Encoding::detect(b"caf\xe9 cr\xe8me\n"),
+            Encoding::for_label("windows-1252")
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "h\u{e9}llo, w\u{f6}rld\n";
+        for encoding in Encoding::all().filter(|encoding| encoding.encode(text).is_ok()) {
+            let bytes = encoding.encode(text).unwrap();
+            assert_eq!(
+                encoding.decode(&bytes),
+                (text.to_string(), encoding),
+                "round trip through {encoding}"
+            );
+        }
+
+        let text = "h\u{e9}llo\nw\u{f6}rld\n";
+        for encoding in Encoding::all().filter(|encoding| encoding.encode(text).is_ok()) {
+            let bytes = encoding.encode(text).unwrap();
+            let mut decoder = encoding.decoder();
+            let mut decoded = String::new();
+            for chunk in bytes.chunks(3) {
+                decoded.push_str(&decoder.decode(chunk, false));
+            }
+            decoded.push_str(&decoder.decode(&[], true));
+            assert_eq!(decoded, text, "chunked decoding of {encoding}");
+        }
+
+        let utf16 = Encoding::for_label("utf-16be").unwrap().with_bom(true);
+        assert_eq!(utf16.encode("a").unwrap(), b"\xfe\xff\x00a");
+        assert!(
+            Encoding::for_label("shift_jis")
+                .unwrap()
+                .encode("\u{1f600}")
+                .is_err()
+        );
+    }
+}
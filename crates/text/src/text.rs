@@ -1,4 +1,5 @@
 mod anchor;
+mod encoding;
 pub mod locator;
 #[cfg(any(test, feature = "test-support"))]
 pub mod network;
@@ -15,6 +16,7 @@ use anyhow::{Context as _, Result, anyhow};
 use clock::LOCAL_BRANCH_REPLICA_ID;
 pub use clock::ReplicaId;
 use collections::{HashMap, HashSet};
+pub use encoding::{Encoding, TextDecoder};
 use locator::Locator;
 use operation_queue::OperationQueue;
 pub use patch::Patch;
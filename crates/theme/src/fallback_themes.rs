@@ -82,6 +82,7 @@ pub(crate) fn zed_default_dark() -> Theme {
             window_background_appearance: WindowBackgroundAppearance::Opaque,
             system: SystemColors::default(),
             accents: AccentColors(vec![blue, orange, purple, teal, red, green, yellow]),
+            bracket_colors: AccentColors(vec![blue, orange, purple, teal, red, green, yellow]),
             colors: ThemeColors {
                 border: hsla(225. / 360., 13. / 100., 12. / 100., 1.),
                 border_variant: hsla(228. / 360., 8. / 100., 25. / 100., 1.),
@@ -80,6 +80,11 @@ pub struct ThemeStyleContent {
     #[serde(default)]
     pub accents: Vec<AccentContent>,
 
+    /// The colors of bracket pairs, cycled through by how deeply they are nested.
+    /// Falls back to the accent colors when empty.
+    #[serde(default)]
+    pub bracket_colors: Vec<AccentContent>,
+
     #[serde(flatten, default)]
     pub colors: ThemeColorsContent,
 
@@ -515,6 +515,8 @@ pub struct ThemeStyles {
     ///
     /// Example: Player colors, rainbow brackets and indent guides, etc.
     pub accents: AccentColors,
+    /// The colors of bracket pairs, cycled through by how deeply they are nested.
+    pub bracket_colors: AccentColors,
 
     #[refineable]
     pub colors: ThemeColors,
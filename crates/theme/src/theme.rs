@@ -191,6 +191,9 @@ impl ThemeFamily {
         };
         refined_accent_colors.merge(&theme.style.accents);
 
+        let mut refined_bracket_colors = refined_accent_colors.clone();
+        refined_bracket_colors.merge(&theme.style.bracket_colors);
+
         let syntax_highlights = theme
             .style
             .syntax
@@ -230,6 +233,7 @@ impl ThemeFamily {
                 system: SystemColors::default(),
                 window_background_appearance,
                 accents: refined_accent_colors,
+                bracket_colors: refined_bracket_colors,
                 colors: refined_theme_colors,
                 status: refined_status_colors,
                 player: refined_player_colors,
@@ -290,6 +294,12 @@ impl Theme {
         &self.styles.accents
     }
 
+    /// Returns the [`AccentColors`] used for bracket pairs.
+    #[inline(always)]
+    pub fn bracket_colors(&self) -> &AccentColors {
+        &self.styles.bracket_colors
+    }
+
     /// Returns the [`PlayerColors`] for the theme.
     #[inline(always)]
     pub fn players(&self) -> &PlayerColors {
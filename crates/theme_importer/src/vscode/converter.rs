@@ -58,6 +58,7 @@ impl VsCodeThemeConverter {
             style: ThemeStyleContent {
                 window_background_appearance: Some(theme::WindowBackgroundContent::Opaque),
                 accents: Vec::new(), //TODO can we read this from the theme?
+                bracket_colors: Vec::new(),
                 colors: theme_colors,
                 status: status_colors,
                 players: Vec::new(),
@@ -370,6 +370,10 @@ impl TitleBar {
                 Color::Warning,
                 format!("Lost connection to {host}. Reconnecting..."),
             ),
+            remote::ConnectionState::Offline => (
+                Color::Warning,
+                format!("Offline. Edits will be sent to {host} once reconnected."),
+            ),
             remote::ConnectionState::Disconnected => {
                 (Color::Error, format!("Disconnected from {host}"))
             }
@@ -380,6 +384,7 @@ impl TitleBar {
             remote::ConnectionState::Connected => Color::Default,
             remote::ConnectionState::HeartbeatMissed => Color::Warning,
             remote::ConnectionState::Reconnecting => Color::Warning,
+            remote::ConnectionState::Offline => Color::Warning,
             remote::ConnectionState::Disconnected => Color::Error,
         };
 
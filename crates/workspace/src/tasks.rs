@@ -19,7 +19,8 @@ pub fn schedule_task(
             ConnectionState::Connecting
             | ConnectionState::Disconnected
             | ConnectionState::HeartbeatMissed
-            | ConnectionState::Reconnecting,
+            | ConnectionState::Reconnecting
+            | ConnectionState::Offline,
         ) => {
             log::warn!("Cannot schedule tasks when disconnected from a remote host");
             return;
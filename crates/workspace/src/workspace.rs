@@ -66,8 +66,8 @@ pub use persistence::{
 };
 use postage::stream::Stream;
 use project::{
-    DirectoryLister, Project, ProjectEntryId, ProjectPath, ResolvedPath, Worktree, WorktreeId,
-    debugger::breakpoint_store::BreakpointStoreEvent,
+    BinaryFileError, DirectoryLister, Project, ProjectEntryId, ProjectPath, ResolvedPath, Worktree,
+    WorktreeId, debugger::breakpoint_store::BreakpointStoreEvent,
 };
 use remote::{SshClientDelegate, SshConnectionOptions, ssh_session::ConnectionIdentifier};
 use schemars::JsonSchema;
@@ -447,23 +447,43 @@ impl Global for ProjectItemOpeners {}
 /// was added last.
 pub fn register_project_item<I: ProjectItem>(cx: &mut App) {
     let builders = cx.default_global::<ProjectItemOpeners>();
-    builders.push(|project, project_path, window, cx| {
-        let project_item = <I::Item as project::ProjectItem>::try_open(project, project_path, cx)?;
-        let project = project.clone();
-        Some(window.spawn(cx, async move |cx| {
-            let project_item = project_item.await?;
-            let project_entry_id: Option<ProjectEntryId> =
-                project_item.read_with(cx, project::ProjectItem::entry_id)?;
-            let build_workspace_item = Box::new(
-                |pane: &mut Pane, window: &mut Window, cx: &mut Context<Pane>| {
-                    Box::new(
-                        cx.new(|cx| I::for_project_item(project, pane, project_item, window, cx)),
-                    ) as Box<dyn ItemHandle>
-                },
-            ) as Box<_>;
-            Ok((project_entry_id, build_workspace_item))
-        }))
-    });
+    builders.push(open_project_item::<I>);
+}
+
+#[derive(Clone, Default, Deref, DerefMut)]
+struct BinaryProjectItemOpeners(Vec<ProjectItemOpener>);
+
+impl Global for BinaryProjectItemOpeners {}
+
+/// Registers a [ProjectItem] for opening files that contain binary data. It
+/// gets a chance to open a file when the items registered with
+/// [register_project_item] fail to because the file isn't text, and when a
+/// file is explicitly opened with [Workspace::open_binary_path].
+pub fn register_binary_project_item<I: ProjectItem>(cx: &mut App) {
+    let builders = cx.default_global::<BinaryProjectItemOpeners>();
+    builders.push(open_project_item::<I>);
+}
+
+fn open_project_item<I: ProjectItem>(
+    project: &Entity<Project>,
+    project_path: &ProjectPath,
+    window: &mut Window,
+    cx: &mut App,
+) -> Option<Task<Result<(Option<ProjectEntryId>, WorkspaceItemBuilder)>>> {
+    let project_item = <I::Item as project::ProjectItem>::try_open(project, project_path, cx)?;
+    let project = project.clone();
+    Some(window.spawn(cx, async move |cx| {
+        let project_item = project_item.await?;
+        let project_entry_id: Option<ProjectEntryId> =
+            project_item.read_with(cx, project::ProjectItem::entry_id)?;
+        let build_workspace_item = Box::new(
+            |pane: &mut Pane, window: &mut Window, cx: &mut Context<Pane>| {
+                Box::new(cx.new(|cx| I::for_project_item(project, pane, project_item, window, cx)))
+                    as Box<dyn ItemHandle>
+            },
+        ) as Box<_>;
+        Ok((project_entry_id, build_workspace_item))
+    }))
 }
 
 #[derive(Default)]
@@ -2990,6 +3010,45 @@ impl Workspace {
         })
     }
 
+    /// Opens the file at the given path with the item registered for binary
+    /// files, even if it contains text.
+    pub fn open_binary_path(
+        &mut self,
+        path: impl Into<ProjectPath>,
+        pane: Option<WeakEntity<Pane>>,
+        focus_item: bool,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Box<dyn ItemHandle>, anyhow::Error>> {
+        let pane = pane.unwrap_or_else(|| {
+            self.last_active_center_pane.clone().unwrap_or_else(|| {
+                self.panes
+                    .first()
+                    .expect("There must be an active pane")
+                    .downgrade()
+            })
+        });
+
+        let path = path.into();
+        let project = self.project().clone();
+        let binary_item_builders = cx.default_global::<BinaryProjectItemOpeners>().clone();
+        let Some(task) = binary_item_builders
+            .iter()
+            .rev()
+            .find_map(|open_project_item| open_project_item(&project, &path, window, cx))
+        else {
+            return Task::ready(Err(anyhow!("cannot open file {:?} as binary", path.path)));
+        };
+        window.spawn(cx, async move |cx| {
+            let (_, build_item) = task.await?;
+            // The file may already be open as text, so the item isn't looked
+            // up by its entry.
+            pane.update_in(cx, |pane, window, cx| {
+                pane.open_item(None, focus_item, false, true, None, window, cx, build_item)
+            })
+        })
+    }
+
     fn load_path(
         &mut self,
         path: ProjectPath,
@@ -3005,7 +3064,26 @@ impl Workspace {
         else {
             return Task::ready(Err(anyhow!("cannot open file {:?}", path.path)));
         };
-        open_project_item
+
+        let binary_item_builders = cx.default_global::<BinaryProjectItemOpeners>().clone();
+        if binary_item_builders.is_empty() {
+            return open_project_item;
+        }
+        window.spawn(cx, async move |cx| match open_project_item.await {
+            Err(error) if error.downcast_ref::<BinaryFileError>().is_some() => {
+                cx.update(|window, cx| {
+                    binary_item_builders
+                        .iter()
+                        .rev()
+                        .find_map(|open_project_item| {
+                            open_project_item(&project, &path, window, cx)
+                        })
+                })?
+                .ok_or(error)?
+                .await
+            }
+            result => result,
+        })
     }
 
     pub fn find_project_item<T>(
@@ -63,7 +63,7 @@ use std::{
     time::{Duration, Instant},
 };
 use sum_tree::{Bias, Edit, KeyedItem, SeekTarget, SumTree, Summary, TreeMap, TreeSet, Unit};
-use text::{LineEnding, Rope};
+use text::{Encoding, LineEnding, Rope, TextDecoder};
 use util::{
     ResultExt,
     paths::{PathMatcher, SanitizedPath, home_dir},
@@ -100,6 +100,50 @@ pub enum CreatedEntry {
 pub struct LoadedFile {
     pub file: Arc<File>,
     pub text: String,
+    pub encoding: Encoding,
+    /// The part of the file that wasn't loaded, when only its start was
+    /// requested.
+    pub remainder: Option<FileRemainder>,
+}
+
+/// The rest of a file that was too large to load at once, which is read and
+/// decoded a chunk at a time.
+pub struct FileRemainder {
+    fs: Arc<dyn Fs>,
+    abs_path: PathBuf,
+    offset: u64,
+    decoder: TextDecoder,
+    pending_carriage_return: bool,
+    finished: bool,
+}
+
+impl FileRemainder {
+    /// Reads and decodes up to `len` more bytes of the file, returning `None`
+    /// once all of it has been read.
+    pub async fn read_chunk(&mut self, len: usize) -> Result<Option<String>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let bytes = self.fs.read_at(&self.abs_path, self.offset, len).await?;
+        Ok(Some(self.decode_chunk(&bytes, len)))
+    }
+
+    fn decode_chunk(&mut self, bytes: &[u8], requested_len: usize) -> String {
+        self.offset += bytes.len() as u64;
+        self.finished = bytes.len() < requested_len;
+
+        let mut text = self.decoder.decode(&bytes, self.finished);
+        if mem::take(&mut self.pending_carriage_return) {
+            text.insert(0, '\r');
+        }
+        // Hold back a trailing carriage return, so that a CRLF line ending
+        // that's split between chunks isn't normalized into two line breaks.
+        if !self.finished && text.ends_with('\r') {
+            text.pop();
+            self.pending_carriage_return = true;
+        }
+        text
+    }
 }
 
 pub struct LoadedBinaryFile {
@@ -107,6 +151,21 @@ pub struct LoadedBinaryFile {
     pub content: Vec<u8>,
 }
 
+/// The error returned when loading a file as text fails because it contains
+/// binary data.
+#[derive(Clone, Debug)]
+pub struct BinaryFileError {
+    pub abs_path: PathBuf,
+}
+
+impl fmt::Display for BinaryFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a text file", self.abs_path)
+    }
+}
+
+impl std::error::Error for BinaryFileError {}
+
 pub struct LocalWorktree {
     snapshot: LocalSnapshot,
     scan_requests_tx: channel::Sender<ScanRequest>,
@@ -797,7 +856,23 @@ impl Worktree {
 
     pub fn load_file(&self, path: &Path, cx: &Context<Worktree>) -> Task<Result<LoadedFile>> {
         match self {
-            Worktree::Local(this) => this.load_file(path, cx),
+            Worktree::Local(this) => this.load_file(path, None, cx),
+            Worktree::Remote(_) => {
+                Task::ready(Err(anyhow!("remote worktrees can't yet load files")))
+            }
+        }
+    }
+
+    /// Loads at most `max_len` bytes from the start of a file, leaving the
+    /// rest of it to be read from the returned [`FileRemainder`].
+    pub fn load_file_start(
+        &self,
+        path: &Path,
+        max_len: usize,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<LoadedFile>> {
+        match self {
+            Worktree::Local(this) => this.load_file(path, Some(max_len), cx),
             Worktree::Remote(_) => {
                 Task::ready(Err(anyhow!("remote worktrees can't yet load files")))
             }
@@ -822,10 +897,11 @@ impl Worktree {
         path: &Path,
         text: Rope,
         line_ending: LineEnding,
+        encoding: Encoding,
         cx: &Context<Worktree>,
     ) -> Task<Result<Arc<File>>> {
         match self {
-            Worktree::Local(this) => this.write_file(path, text, line_ending, cx),
+            Worktree::Local(this) => this.write_file(path, text, line_ending, encoding, cx),
             Worktree::Remote(_) => {
                 Task::ready(Err(anyhow!("remote worktree can't yet write files")))
             }
@@ -1488,7 +1564,12 @@ impl LocalWorktree {
         })
     }
 
-    fn load_file(&self, path: &Path, cx: &Context<Worktree>) -> Task<Result<LoadedFile>> {
+    fn load_file(
+        &self,
+        path: &Path,
+        max_len: Option<usize>,
+        cx: &Context<Worktree>,
+    ) -> Task<Result<LoadedFile>> {
         let path = Arc::from(path);
         let abs_path = self.absolutize(&path);
         let fs = self.fs.clone();
@@ -1503,15 +1584,43 @@ impl LocalWorktree {
             //       if it is too large
             //       5GB seems to be more reasonable, peaking at ~16GB, while 6GB jumps up to >24GB which seems like a
             //       reasonable limit
-            {
-                const FILE_SIZE_MAX: u64 = 6 * 1024 * 1024 * 1024; // 6GB
-                if let Ok(Some(metadata)) = fs.metadata(&abs_path).await {
-                    if metadata.len >= FILE_SIZE_MAX {
-                        anyhow::bail!("File is too large to load");
-                    }
-                }
+            const FILE_SIZE_MAX: u64 = 6 * 1024 * 1024 * 1024; // 6GB
+            let len = fs.metadata(&abs_path).await.ok().flatten().map(|m| m.len);
+            if len.is_some_and(|len| len >= FILE_SIZE_MAX) {
+                anyhow::bail!("File is too large to load");
             }
-            let text = fs.load(&abs_path).await?;
+
+            let max_len = max_len.filter(|max_len| len.is_some_and(|len| len > *max_len as u64));
+            let (text, encoding, remainder) = if let Some(max_len) = max_len {
+                let bytes = fs.read_at(&abs_path, 0, max_len).await?;
+                // Detect the encoding on whole lines, so that a multi-byte
+                // character cut off at the end isn't mistaken for invalid UTF-8.
+                let sample = match bytes.iter().rposition(|byte| *byte == b'\n') {
+                    Some(ix) => &bytes[..ix],
+                    None => &bytes[..],
+                };
+                let encoding = Encoding::detect(sample).ok_or_else(|| BinaryFileError {
+                    abs_path: abs_path.clone(),
+                })?;
+                let mut remainder = FileRemainder {
+                    fs: fs.clone(),
+                    abs_path: abs_path.clone(),
+                    offset: 0,
+                    decoder: encoding.decoder(),
+                    pending_carriage_return: false,
+                    finished: false,
+                };
+                let text = remainder.decode_chunk(&bytes, max_len);
+                (text, encoding, Some(remainder))
+            } else {
+                let bytes = fs.load_bytes(&abs_path).await?;
+                let (text, encoding) = Encoding::detect(&bytes)
+                    .ok_or_else(|| BinaryFileError {
+                        abs_path: abs_path.clone(),
+                    })?
+                    .decode(&bytes);
+                (text, encoding, None)
+            };
 
             let worktree = this
                 .upgrade()
@@ -1541,7 +1650,12 @@ impl LocalWorktree {
                 }
             };
 
-            Ok(LoadedFile { file, text })
+            Ok(LoadedFile {
+                file,
+                text,
+                encoding,
+                remainder,
+            })
         })
     }
 
@@ -1627,6 +1741,7 @@ impl LocalWorktree {
         path: impl Into<Arc<Path>>,
         text: Rope,
         line_ending: LineEnding,
+        encoding: Encoding,
         cx: &Context<Worktree>,
     ) -> Task<Result<Arc<File>>> {
         let path = path.into();
@@ -1639,7 +1754,10 @@ impl LocalWorktree {
         let write = cx.background_spawn({
             let fs = fs.clone();
             let abs_path = abs_path.clone();
-            async move { fs.save(&abs_path, &text, line_ending).await }
+            async move {
+                fs.save_with_encoding(&abs_path, &text, line_ending, encoding)
+                    .await
+            }
         });
 
         cx.spawn(async move |this, cx| {